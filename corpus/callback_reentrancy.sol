@@ -0,0 +1,34 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+interface IERC721 {
+    function safeTransferFrom(address from, address to, uint256 tokenId) external;
+}
+
+// `buy` hands the token over before marking it sold. The buyer's `onERC721Received` hook runs
+// inside `safeTransferFrom` and can call `buy` again for the same token. `buyGuarded` is the same
+// code behind a reentrancy lock.
+contract Marketplace {
+    IERC721 public nft;
+    mapping(uint256 => bool) public sold;
+    bool private locked;
+
+    modifier nonReentrant() {
+        require(!locked, "reentrant call");
+        locked = true;
+        _;
+        locked = false;
+    }
+
+    function buy(uint256 id) external payable {
+        require(!sold[id], "sold");
+        nft.safeTransferFrom(address(this), msg.sender, id);
+        sold[id] = true;
+    }
+
+    function buyGuarded(uint256 id) external payable nonReentrant {
+        require(!sold[id], "sold");
+        nft.safeTransferFrom(address(this), msg.sender, id);
+        sold[id] = true;
+    }
+}
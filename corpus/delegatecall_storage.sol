@@ -0,0 +1,24 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+// The proxy keeps its implementation address in slot 0, which the implementation uses for
+// `owner`. `setOwner` run through the proxy overwrites the implementation address.
+contract Proxy {
+    address public implementation;
+
+    constructor(address implementation_) {
+        implementation = implementation_;
+    }
+
+    fallback() external payable {
+        implementation.delegatecall(msg.data);
+    }
+}
+
+contract Implementation {
+    address public owner;
+
+    function setOwner(address owner_) external {
+        owner = owner_;
+    }
+}
@@ -0,0 +1,30 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+// `FeeStoreUpgradeable` declares state but reserves no `__gap`. Adding a variable to it in a later
+// version shifts every slot `Vault` declares after it. `RolesUpgradeable` reserves its gap.
+contract FeeStoreUpgradeable {
+    uint256 public fee;
+
+    function __FeeStore_init(uint256 fee_) internal {
+        fee = fee_;
+    }
+}
+
+contract RolesUpgradeable {
+    address public admin;
+    uint256[49] private __gap;
+
+    function __Roles_init(address admin_) internal {
+        admin = admin_;
+    }
+}
+
+contract Vault is FeeStoreUpgradeable, RolesUpgradeable {
+    uint256 public totalShares;
+
+    function initialize(uint256 fee_) external {
+        __FeeStore_init(fee_);
+        __Roles_init(msg.sender);
+    }
+}
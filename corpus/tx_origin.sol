@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+// Authenticates with `tx.origin`. Any contract the owner calls can call `withdraw` and pass the
+// check.
+contract Wallet {
+    address public owner;
+
+    constructor() {
+        owner = msg.sender;
+    }
+
+    function withdraw(address payable to, uint256 amount) external {
+        require(tx.origin == owner, "not owner");
+        to.transfer(amount);
+    }
+}
@@ -0,0 +1,13 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+// The low-level call's success flag is dropped, so a failed payout still clears the balance.
+contract Payouts {
+    mapping(address => uint256) public owed;
+
+    function claim() external {
+        uint256 amount = owed[msg.sender];
+        owed[msg.sender] = 0;
+        msg.sender.call{value: amount}("");
+    }
+}
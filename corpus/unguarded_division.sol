@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+// `assetsPerShare` divides by its argument, so a zero reverts the call with a panic instead of a
+// reason. `assetsPerShareChecked` rules zero out first.
+contract Vault {
+    uint256 public totalAssets;
+
+    function assetsPerShare(uint256 shares) external view returns (uint256) {
+        return totalAssets / shares;
+    }
+
+    function assetsPerShareChecked(uint256 shares) external view returns (uint256) {
+        require(shares > 0, "no shares");
+        return totalAssets / shares;
+    }
+}
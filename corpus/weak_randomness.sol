@@ -0,0 +1,18 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+// The winner is the block timestamp modulo the number of players. The block producer chooses the
+// timestamp, within limits, and so chooses the winner.
+contract Lottery {
+    address[] public players;
+
+    function enter() external payable {
+        require(msg.value > 0, "no stake");
+        players.push(msg.sender);
+    }
+
+    function pickWinner() external view returns (address) {
+        uint256 index = block.timestamp % players.length;
+        return players[index];
+    }
+}
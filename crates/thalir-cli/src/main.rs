@@ -2,12 +2,23 @@ use anyhow::Result;
 use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
+#[cfg(feature = "memory-profile")]
+#[global_allocator]
+static ALLOCATOR: thalir_core::profiling::TrackingAllocator =
+    thalir_core::profiling::TrackingAllocator;
+
 #[derive(Parser)]
 #[command(name = "thalir")]
 #[command(about = "ThalIR - Privacy-preserving IR for smart contract security analysis")]
 #[command(version = "0.1.0")]
 #[command(author = "Gianluca Brigandi <gbrigand@gmail.com>")]
 struct Cli {
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    log_format: LogFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -15,7 +26,17 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     Compile {
-        input: PathBuf,
+        #[arg(required_unless_present = "address")]
+        input: Option<PathBuf>,
+
+        #[arg(long, conflicts_with = "input")]
+        address: Option<String>,
+
+        #[arg(long, default_value_t = 1, requires = "address")]
+        chain: u64,
+
+        #[arg(long, requires = "address")]
+        api_key: Option<String>,
 
         #[arg(short, long)]
         output: Option<PathBuf>,
@@ -26,12 +47,77 @@ enum Commands {
         #[arg(long, requires = "annotated")]
         ascii: bool,
 
-        #[arg(long, value_enum, default_value = "none")]
-        obfuscate: ObfuscationLevel,
+        #[arg(long, requires = "annotated")]
+        intrinsics: bool,
+
+        #[arg(long, conflicts_with = "annotated")]
+        jsonl: bool,
+
+        #[arg(long, conflicts_with_all = ["annotated", "jsonl", "storage_names", "summaries"])]
+        pseudo: bool,
+
+        #[arg(
+            long,
+            value_enum,
+            requires = "annotated",
+            help = "How much the annotated output shows besides the IR"
+        )]
+        verbosity: Option<Verbosity>,
+
+        #[arg(long)]
+        lenient: bool,
+
+        #[arg(long)]
+        strict: bool,
+
+        #[arg(long)]
+        evm_version: Option<thalir_core::EvmVersion>,
+
+        #[arg(long, value_enum, conflicts_with_all = ["annotated", "jsonl"])]
+        storage_names: Option<StorageNames>,
+
+        #[arg(long, conflicts_with_all = ["annotated", "jsonl"])]
+        summaries: bool,
+
+        #[arg(long, value_enum, conflicts_with_all = ["jsonl", "pseudo"])]
+        function_order: Option<FunctionOrder>,
+
+        #[arg(long, conflicts_with_all = ["jsonl", "pseudo"])]
+        group_functions: bool,
+
+        #[arg(
+            long,
+            help = "Inline small internal and private functions into their callers"
+        )]
+        inline: bool,
+
+        #[arg(
+            long,
+            help = "Factor duplicated instruction regions into internal functions"
+        )]
+        outline: bool,
+
+        #[arg(long, requires = "annotated")]
+        review: Option<PathBuf>,
+
+        #[arg(long, value_enum)]
+        obfuscate: Option<ObfuscationLevel>,
 
         #[arg(long, requires = "obfuscate")]
         save_mapping: Option<PathBuf>,
 
+        #[arg(long, requires = "save_mapping")]
+        mapping_key: Option<PathBuf>,
+
+        #[arg(long, requires = "obfuscate")]
+        salt_file: Option<PathBuf>,
+
+        #[arg(long = "retain", value_enum, requires = "obfuscate")]
+        retain: Vec<RetainedMetadata>,
+
+        #[arg(long = "alias", value_enum, requires = "obfuscate")]
+        aliases: Vec<AliasClass>,
+
         #[arg(short, long)]
         verbose: bool,
     },
@@ -40,11 +126,49 @@ enum Commands {
         #[arg(short, long)]
         mapping: PathBuf,
 
+        #[arg(long)]
+        key: Option<PathBuf>,
+
         #[arg(short, long)]
         report: Option<PathBuf>,
 
         #[arg(short, long)]
         output: Option<PathBuf>,
+
+        #[arg(long, value_enum, default_value = "auto")]
+        format: ReportFormatArg,
+    },
+
+    VerifyObfuscation {
+        original: PathBuf,
+
+        obfuscated: PathBuf,
+
+        mapping: PathBuf,
+
+        #[arg(long)]
+        key: PathBuf,
+
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        #[arg(long)]
+        json: bool,
+    },
+
+    DiffRounds {
+        previous: PathBuf,
+
+        current: PathBuf,
+
+        #[arg(long, requires = "current_mapping")]
+        previous_mapping: Option<PathBuf>,
+
+        #[arg(long, requires = "previous_mapping")]
+        current_mapping: Option<PathBuf>,
+
+        #[arg(long)]
+        json: bool,
     },
 
     Validate {
@@ -60,334 +184,2709 @@ enum Commands {
         #[arg(short, long)]
         verbose: bool,
     },
-}
 
-#[derive(Debug, Clone, Copy, ValueEnum)]
-enum ObfuscationLevel {
-    None,
-    Minimal,
-    Standard,
-}
+    Similar {
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
 
-impl From<ObfuscationLevel> for thalir_core::ObfuscationLevel {
-    fn from(level: ObfuscationLevel) -> Self {
-        match level {
-            ObfuscationLevel::None => thalir_core::ObfuscationLevel::None,
-            ObfuscationLevel::Minimal => thalir_core::ObfuscationLevel::Minimal,
-            ObfuscationLevel::Standard => thalir_core::ObfuscationLevel::Standard,
-        }
-    }
-}
+        #[arg(long)]
+        corpus: Option<PathBuf>,
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+        #[arg(long, default_value_t = 0.8)]
+        threshold: f64,
 
-    match cli.command {
-        Commands::Compile {
-            input,
-            output,
-            annotated,
-            ascii,
-            obfuscate,
-            save_mapping,
-            verbose,
-        } => cmd_compile(
-            input,
-            output,
-            annotated,
-            ascii,
-            obfuscate,
-            save_mapping,
-            verbose,
-        ),
-        Commands::Deobfuscate {
-            mapping,
-            report,
-            output,
-        } => cmd_deobfuscate(mapping, report, output),
-        Commands::Validate { input, verbose } => cmd_validate(input, verbose),
-        Commands::Debug { input, verbose } => cmd_debug(input, verbose),
-    }
-}
+        #[arg(long)]
+        json: bool,
+    },
 
-fn cmd_compile(
-    input: PathBuf,
-    output: Option<PathBuf>,
-    annotated: bool,
-    ascii: bool,
-    obfuscate: ObfuscationLevel,
-    save_mapping: Option<PathBuf>,
-    verbose: bool,
-) -> Result<()> {
-    use colored::*;
-    use std::fs;
-    use std::time::Instant;
-    use thalir_core::ObfuscationConfig;
-    use thalir_emit::{AnnotatedIREmitter, ThalIREmitter};
-    use thalir_transform::transform_solidity_to_ir_with_filename;
+    Signatures {
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
 
-    if verbose {
-        println!("{}", " ThalIR Compiler".bright_blue().bold());
-        println!("{}", "=".repeat(50).bright_blue());
-        println!(" Input: {}", input.display());
-        if let Some(ref out) = output {
-            println!(" Output: {}", out.display());
-        }
-        if annotated {
-            println!(
-                " Mode: Annotated ThalIR{}",
-                if ascii { " (ASCII)" } else { "" }
-            );
-        }
-        if !matches!(obfuscate, ObfuscationLevel::None) {
-            println!(" Obfuscation: {:?}", obfuscate);
-        }
-        println!();
-    }
+        #[arg(long)]
+        db: PathBuf,
 
-    let start = Instant::now();
+        #[arg(long)]
+        json: bool,
+    },
 
-    if verbose {
-        println!(" Loading Solidity source...");
-    }
-    let solidity_content = fs::read_to_string(&input)?;
-    let filename = input.to_str();
+    Xref {
+        name: String,
 
-    if verbose {
-        println!(" Transforming to ThalIR...");
-    }
-    let contracts = transform_solidity_to_ir_with_filename(&solidity_content, filename)?;
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
 
-    if contracts.is_empty() {
-        println!("{}", "  No contracts found in input".yellow());
-        return Ok(());
-    }
+        #[arg(long)]
+        json: bool,
+    },
 
-    if verbose {
-        println!(" Generating IR output...");
-    }
-
-    let ir_output = match (annotated, matches!(obfuscate, ObfuscationLevel::None)) {
-        (true, true) => {
-            use thalir_emit::annotated_ir_emitter::AnnotationConfig;
-            let config = AnnotationConfig {
-                emit_position_markers: true,
-                emit_visual_cues: true,
-                use_ascii_cues: ascii,
-                emit_ordering_analysis: true,
-                emit_function_headers: true,
-            };
-            let emitter = AnnotatedIREmitter::new(contracts).with_annotation_config(config);
-            (emitter.emit_to_string(false), None)
-        }
-        (true, false) => {
-            use thalir_emit::annotated_ir_emitter::AnnotationConfig;
-            let obf_config = ObfuscationConfig {
-                level: obfuscate.into(),
-                retain_mapping: save_mapping.is_some(),
-                hash_salt: None,
-                strip_string_constants: true,
-                strip_error_messages: true,
-                strip_metadata: true,
-            };
-            let ann_config = AnnotationConfig {
-                emit_position_markers: true,
-                emit_visual_cues: true,
-                use_ascii_cues: ascii,
-                emit_ordering_analysis: true,
-                emit_function_headers: true,
-            };
-            let (emitter, mapping) =
-                AnnotatedIREmitter::with_obfuscation(contracts, obf_config, ann_config)?;
-            (emitter.emit_to_string(false), mapping)
-        }
-        (false, true) => {
-            let emitter = ThalIREmitter::new(contracts);
-            (emitter.emit_to_string(false), None)
-        }
-        (false, false) => {
-            let obf_config = ObfuscationConfig {
-                level: obfuscate.into(),
-                retain_mapping: save_mapping.is_some(),
-                hash_salt: None,
-                strip_string_constants: true,
-                strip_error_messages: true,
-                strip_metadata: true,
-            };
-            let (emitter, mapping) = ThalIREmitter::with_obfuscation(contracts, obf_config)?;
-            (emitter.emit_to_string(false), mapping)
-        }
-    };
+    Query {
+        script: PathBuf,
 
-    if let (Some(mapping_path), Some(mapping)) = (save_mapping, ir_output.1) {
-        if verbose {
-            println!(" Saving obfuscation mapping...");
-        }
-        let mapping_json = serde_json::to_string_pretty(&mapping)?;
-        fs::write(&mapping_path, mapping_json)?;
-        if verbose {
-            println!("   Saved to: {}", mapping_path.display());
-        }
-    }
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+    },
 
-    if let Some(output_path) = output {
-        fs::write(&output_path, &ir_output.0)?;
-        if verbose {
-            let elapsed = start.elapsed();
-            println!(
-                "\n {} Compilation successful!",
-                "SUCCESS:".bright_green().bold()
-            );
-            println!("   Time: {:.3}s", elapsed.as_secs_f64());
-            println!("   Output: {}", output_path.display());
-        }
-    } else {
-        println!("{}", ir_output.0);
-    }
+    Analyze {
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
 
-    Ok(())
-}
+        #[arg(long)]
+        baseline: Option<PathBuf>,
 
-fn cmd_deobfuscate(
-    mapping: PathBuf,
-    report: Option<PathBuf>,
-    output: Option<PathBuf>,
-) -> Result<()> {
-    use colored::*;
-    use std::fs;
-    use thalir_core::{ObfuscationMapping, VulnerabilityMapper};
+        #[arg(long, requires = "baseline")]
+        update_baseline: bool,
 
-    let mapping_json = fs::read_to_string(&mapping)?;
-    let obf_mapping: ObfuscationMapping = serde_json::from_str(&mapping_json)?;
-    let mapper = VulnerabilityMapper::from_mapping(obf_mapping);
+        #[arg(long)]
+        html: Option<PathBuf>,
 
-    let report_content = if let Some(report_path) = report {
-        fs::read_to_string(&report_path)?
-    } else {
-        use std::io::Read;
-        let mut buffer = String::new();
-        std::io::stdin().read_to_string(&mut buffer)?;
-        buffer
-    };
+        #[arg(long)]
+        json: bool,
 
-    let deobfuscated = mapper.deobfuscate_report(&report_content);
+        #[arg(long)]
+        no_prune: bool,
+    },
 
-    if let Some(output_path) = output {
-        fs::write(&output_path, &deobfuscated)?;
-        println!(
-            " {} De-obfuscated report saved to: {}",
-            "SUCCESS:".bright_green().bold(),
-            output_path.display()
-        );
-    } else {
-        println!("{}", deobfuscated);
-    }
+    #[cfg(feature = "unstable")]
+    Test {
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+    },
 
-    Ok(())
-}
+    Coverage {
+        input: PathBuf,
 
-fn cmd_validate(input: PathBuf, verbose: bool) -> Result<()> {
-    use colored::*;
-    use std::fs;
+        #[arg(long)]
+        json: bool,
+    },
 
-    if verbose {
-        println!("{}", " Validating ThalIR".bright_cyan().bold());
-        println!("{}", "".repeat(50).bright_cyan());
-        println!(" Input: {}", input.display());
-        println!();
-    }
+    Inspect {
+        input: PathBuf,
 
-    let ir_content = fs::read_to_string(&input)?;
+        #[arg(long)]
+        contract: Option<String>,
 
-    if verbose {
-        println!(" Parsing with Pest parser...");
-    }
+        #[arg(long)]
+        function: Option<String>,
 
-    match thalir_parser::parse(&ir_content) {
-        Ok(pairs) => {
-            let count = pairs.count();
-            println!("{}", " VALID".bright_green().bold());
-            if verbose {
-                println!("   Parsed {} top-level elements", count);
-            }
-            Ok(())
-        }
-        Err(e) => {
-            println!("{}", " INVALID".bright_red().bold());
-            println!("\n{}", "Parse Error:".bright_red());
-            println!("{}", e);
-            Err(anyhow::anyhow!("Validation failed"))
-        }
-    }
-}
+        #[arg(long, value_enum, default_value = "summary")]
+        show: InspectView,
+    },
 
-fn cmd_debug(input: PathBuf, verbose: bool) -> Result<()> {
-    use colored::*;
-    use std::fs;
-    use thalir_transform::transform_solidity_to_ir;
+    Trace {
+        input: PathBuf,
 
-    if verbose {
-        println!("{}", " Debug IR Dump".bright_cyan().bold());
-        println!("{}", "=".repeat(50).bright_cyan());
-        println!(" Input: {}", input.display());
-        println!();
-    }
+        function: String,
 
-    let solidity_content = fs::read_to_string(&input)?;
-    let contracts = transform_solidity_to_ir(&solidity_content)?;
+        #[arg(long, value_delimiter = ',', required = true)]
+        path: Vec<u32>,
 
-    if contracts.is_empty() {
-        println!("  No contracts found");
-        return Ok(());
-    }
+        #[arg(long = "arg")]
+        args: Vec<String>,
 
-    println!(" Found {} contract(s)\n", contracts.len());
+        #[arg(long, conflicts_with = "args")]
+        calldata: Option<String>,
 
-    for (idx, contract) in contracts.iter().enumerate() {
-        println!(
-            "{}",
-            format!(" Contract {}: {}", idx, contract.name)
-                .bright_green()
-                .bold()
-        );
-        println!("{}", "".repeat(60).bright_green());
-        println!("Functions: {}", contract.functions.len());
-        println!("Storage slots: {}", contract.storage_layout.slots.len());
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 
-        if verbose {
-            if !contract.storage_layout.slots.is_empty() {
-                println!("\n  Storage Layout:");
-                for slot in &contract.storage_layout.slots {
+    Codegen {
+        input: PathBuf,
+
+        #[arg(long)]
+        contract: Option<String>,
+
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    Interface {
+        input: PathBuf,
+
+        #[arg(long)]
+        contract: Option<String>,
+
+        #[arg(long)]
+        abi: bool,
+
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        #[arg(long, conflicts_with = "output")]
+        check: Option<PathBuf>,
+    },
+
+    Lint {
+        input: PathBuf,
+
+        #[arg(long)]
+        deny_warnings: bool,
+
+        #[arg(long)]
+        json: bool,
+    },
+
+    Review {
+        #[arg(required = true)]
+        notes: Vec<PathBuf>,
+
+        #[arg(long = "against")]
+        inputs: Vec<PathBuf>,
+
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    Addresses {
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+
+        #[arg(long)]
+        labels: Option<PathBuf>,
+
+        #[arg(long)]
+        json: bool,
+    },
+
+    #[cfg(feature = "bench")]
+    Bench {
+        #[arg(long = "fixture")]
+        fixtures: Vec<String>,
+
+        #[arg(long = "stage", value_enum)]
+        stages: Vec<BenchStage>,
+
+        #[arg(short, long, default_value_t = 20)]
+        iterations: u32,
+
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+
+        #[arg(long, default_value_t = 10.0, requires = "baseline")]
+        max_regression: f64,
+
+        #[arg(long)]
+        save_baseline: Option<PathBuf>,
+
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ObfuscationLevel {
+    None,
+    Minimal,
+    Standard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ReportFormatArg {
+    Auto,
+    Text,
+    Json,
+    Sarif,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum RetainedMetadata {
+    Lines,
+    Files,
+    Source,
+    Natspec,
+    Selectors,
+    EventHashes,
+}
+
+fn retained_metadata_policy(
+    retain: &[RetainedMetadata],
+) -> Option<thalir_core::obfuscation::MetadataPolicy> {
+    use thalir_core::obfuscation::{EventSignatures, MetadataPolicy};
+
+    if retain.is_empty() {
+        return None;
+    }
+    let mut policy = MetadataPolicy::strip_all();
+    for class in retain {
+        match class {
+            RetainedMetadata::Lines => policy.source_lines = true,
+            RetainedMetadata::Files => policy.source_files = true,
+            RetainedMetadata::Source => policy.source_code = true,
+            RetainedMetadata::Natspec => policy.natspec = true,
+            RetainedMetadata::Selectors => policy.function_selectors = true,
+            RetainedMetadata::EventHashes => policy.event_signatures = EventSignatures::Hash,
+        }
+    }
+    Some(policy)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum AliasClass {
+    Contracts,
+    Functions,
+    Storage,
+    Events,
+    All,
+}
+
+fn alias_classes(aliases: &[AliasClass]) -> thalir_core::obfuscation::AliasClasses {
+    use thalir_core::obfuscation::AliasClasses;
+
+    let mut classes = AliasClasses::default();
+    for class in aliases {
+        match class {
+            AliasClass::Contracts => classes.contracts = true,
+            AliasClass::Functions => classes.functions = true,
+            AliasClass::Storage => classes.storage = true,
+            AliasClass::Events => classes.events = true,
+            AliasClass::All => classes = AliasClasses::all(),
+        }
+    }
+    classes
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum StorageNames {
+    Slots,
+    Comments,
+    Symbolic,
+}
+
+impl From<StorageNames> for thalir_emit::StorageNaming {
+    fn from(names: StorageNames) -> Self {
+        match names {
+            StorageNames::Slots => thalir_emit::StorageNaming::Slots,
+            StorageNames::Comments => thalir_emit::StorageNaming::Comments,
+            StorageNames::Symbolic => thalir_emit::StorageNaming::Symbolic,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum FunctionOrder {
+    Source,
+    Alphabetical,
+    Visibility,
+    Selector,
+}
+
+impl From<FunctionOrder> for thalir_emit::FunctionOrder {
+    fn from(order: FunctionOrder) -> Self {
+        match order {
+            FunctionOrder::Source => thalir_emit::FunctionOrder::Source,
+            FunctionOrder::Alphabetical => thalir_emit::FunctionOrder::Alphabetical,
+            FunctionOrder::Visibility => thalir_emit::FunctionOrder::Visibility,
+            FunctionOrder::Selector => thalir_emit::FunctionOrder::Selector,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum InspectView {
+    Cfg,
+    Defuse,
+    Summary,
+    Storage,
+    Pause,
+}
+
+#[cfg(feature = "bench")]
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum BenchStage {
+    Transform,
+    Emit,
+    Parse,
+    Analyze,
+}
+
+#[cfg(feature = "bench")]
+impl From<BenchStage> for thalir::bench::Stage {
+    fn from(stage: BenchStage) -> Self {
+        match stage {
+            BenchStage::Transform => thalir::bench::Stage::Transform,
+            BenchStage::Emit => thalir::bench::Stage::Emit,
+            BenchStage::Parse => thalir::bench::Stage::Parse,
+            BenchStage::Analyze => thalir::bench::Stage::Analyze,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+/* Progress goes to stderr so it never mixes with IR on stdout. Text logs stay quiet unless the
+ * command asked for verbose output; JSON logs exist to be consumed by tooling, so they always carry
+ * progress. `THALIR_LOG` takes an `EnvFilter` directive and overrides both. */
+fn init_logging(format: LogFormat, verbose: bool) {
+    use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter};
+
+    let default = if verbose || format == LogFormat::Json {
+        "info"
+    } else {
+        "warn"
+    };
+    let filter = EnvFilter::try_from_env("THALIR_LOG").unwrap_or_else(|_| EnvFilter::new(default));
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_span_events(FmtSpan::CLOSE);
+
+    match format {
+        LogFormat::Text => subscriber.without_time().with_target(false).init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
+}
+
+/* One event per profiled phase, so batch runs can be charted from the JSON log alone. */
+fn log_phase_timings(profiler: &thalir_core::profiling::MemoryProfiler) {
+    for phase in &profiler.phases {
+        tracing::info!(
+            phase = %phase.phase,
+            elapsed_ms = phase.elapsed.as_secs_f64() * 1000.0,
+            allocated = phase.allocated,
+            "phase timing"
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Verbosity {
+    Minimal,
+    Audit,
+    Debug,
+}
+
+impl From<Verbosity> for thalir_emit::EmitterProfile {
+    fn from(verbosity: Verbosity) -> Self {
+        match verbosity {
+            Verbosity::Minimal => thalir_emit::EmitterProfile::Minimal,
+            Verbosity::Audit => thalir_emit::EmitterProfile::Audit,
+            Verbosity::Debug => thalir_emit::EmitterProfile::Debug,
+        }
+    }
+}
+
+impl From<ObfuscationLevel> for thalir_core::ObfuscationLevel {
+    fn from(level: ObfuscationLevel) -> Self {
+        match level {
+            ObfuscationLevel::None => thalir_core::ObfuscationLevel::None,
+            ObfuscationLevel::Minimal => thalir_core::ObfuscationLevel::Minimal,
+            ObfuscationLevel::Standard => thalir_core::ObfuscationLevel::Standard,
+        }
+    }
+}
+
+impl From<thalir_emit::EmitterProfile> for Verbosity {
+    fn from(profile: thalir_emit::EmitterProfile) -> Self {
+        match profile {
+            thalir_emit::EmitterProfile::Minimal => Verbosity::Minimal,
+            thalir_emit::EmitterProfile::Audit => Verbosity::Audit,
+            thalir_emit::EmitterProfile::Debug => Verbosity::Debug,
+        }
+    }
+}
+
+impl From<thalir_emit::StorageNaming> for StorageNames {
+    fn from(naming: thalir_emit::StorageNaming) -> Self {
+        match naming {
+            thalir_emit::StorageNaming::Slots => StorageNames::Slots,
+            thalir_emit::StorageNaming::Comments => StorageNames::Comments,
+            thalir_emit::StorageNaming::Symbolic => StorageNames::Symbolic,
+        }
+    }
+}
+
+impl From<thalir_emit::FunctionOrder> for FunctionOrder {
+    fn from(order: thalir_emit::FunctionOrder) -> Self {
+        match order {
+            thalir_emit::FunctionOrder::Source => FunctionOrder::Source,
+            thalir_emit::FunctionOrder::Alphabetical => FunctionOrder::Alphabetical,
+            thalir_emit::FunctionOrder::Visibility => FunctionOrder::Visibility,
+            thalir_emit::FunctionOrder::Selector => FunctionOrder::Selector,
+        }
+    }
+}
+
+impl From<thalir_core::ObfuscationLevel> for ObfuscationLevel {
+    fn from(level: thalir_core::ObfuscationLevel) -> Self {
+        match level {
+            thalir_core::ObfuscationLevel::None => ObfuscationLevel::None,
+            thalir_core::ObfuscationLevel::Minimal => ObfuscationLevel::Minimal,
+            thalir_core::ObfuscationLevel::Standard => ObfuscationLevel::Standard,
+        }
+    }
+}
+
+/* An explicit --config must exist; otherwise the nearest thalir.toml from the working directory up
+ * is used, and without one every command runs on built-in defaults. */
+fn load_config(path: Option<PathBuf>) -> Result<thalir::Config> {
+    match path {
+        Some(path) => thalir::Config::load(&path),
+        None => Ok(thalir::Config::discover(&std::env::current_dir()?)?
+            .map(|(_, config)| config)
+            .unwrap_or_default()),
+    }
+}
+
+/* Config values use the same spellings as the flags, so they go through clap's own parser. */
+fn config_values<T: ValueEnum>(names: &[String], key: &str) -> Result<Vec<T>> {
+    names
+        .iter()
+        .map(|name| {
+            T::from_str(name, true)
+                .map_err(|_| anyhow::anyhow!("Unknown value `{}` for {} in thalir.toml", name, key))
+        })
+        .collect()
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let verbose = match &cli.command {
+        Commands::Compile { verbose, .. }
+        | Commands::Validate { verbose, .. }
+        | Commands::Debug { verbose, .. } => *verbose,
+        _ => false,
+    };
+    init_logging(cli.log_format, verbose);
+    let config = load_config(cli.config)?;
+
+    match cli.command {
+        Commands::Compile {
+            input,
+            address,
+            chain,
+            api_key,
+            output,
+            annotated,
+            ascii,
+            intrinsics,
+            jsonl,
+            pseudo,
+            verbosity,
+            lenient,
+            strict,
+            evm_version,
+            storage_names,
+            summaries,
+            function_order,
+            group_functions,
+            inline,
+            outline,
+            review,
+            obfuscate,
+            save_mapping,
+            mapping_key,
+            salt_file,
+            retain,
+            aliases,
+            verbose,
+        } => {
+            use thalir::config::OutputFormat;
+
+            let format = config.output.format;
+            let explicit_format = annotated || jsonl || pseudo;
+            let retain = if retain.is_empty() {
+                config_values(&config.obfuscation.retain, "obfuscation.retain")?
+            } else {
+                retain
+            };
+            let aliases = if aliases.is_empty() {
+                config_values(&config.obfuscation.aliases, "obfuscation.aliases")?
+            } else {
+                aliases
+            };
+            let options = CompileOptions {
+                input,
+                address: address.map(|address| (address, chain, api_key)),
+                output,
+                annotated: annotated || (!explicit_format && format == OutputFormat::Annotated),
+                ascii: ascii || config.output.ascii,
+                intrinsics,
+                jsonl: jsonl || (!explicit_format && format == OutputFormat::Jsonl),
+                pseudo: pseudo || (!explicit_format && format == OutputFormat::Pseudo),
+                verbosity: verbosity
+                    .or(config.output.verbosity.map(Verbosity::from))
+                    .unwrap_or(Verbosity::Audit),
+                lenient,
+                strict,
+                evm_version: evm_version.or(config.evm_version),
+                storage_names: storage_names
+                    .or(config.output.storage_names.map(StorageNames::from))
+                    .unwrap_or(StorageNames::Slots),
+                summaries: summaries || config.output.summaries,
+                function_order: function_order
+                    .or(config.output.function_order.map(FunctionOrder::from))
+                    .unwrap_or(FunctionOrder::Source),
+                group_functions: group_functions || config.output.group_functions,
+                inline,
+                outline,
+                review,
+                obfuscate: obfuscate.unwrap_or_else(|| config.obfuscation.level.into()),
+                save_mapping,
+                mapping_key,
+                salt_file,
+                retain,
+                aliases,
+                verbose,
+            };
+            cmd_compile(options, &config)
+        }
+        Commands::Deobfuscate {
+            mapping,
+            key,
+            report,
+            output,
+            format,
+        } => cmd_deobfuscate(mapping, key, report, output, format),
+        Commands::VerifyObfuscation {
+            original,
+            obfuscated,
+            mapping,
+            key,
+            output,
+            json,
+        } => cmd_verify_obfuscation(original, obfuscated, mapping, key, output, json),
+        Commands::DiffRounds {
+            previous,
+            current,
+            previous_mapping,
+            current_mapping,
+            json,
+        } => cmd_diff_rounds(
+            previous,
+            current,
+            previous_mapping.zip(current_mapping),
+            json,
+        ),
+        Commands::Validate { input, .. } => cmd_validate(input),
+        Commands::Debug { input, verbose } => cmd_debug(input, verbose),
+        Commands::Similar {
+            inputs,
+            corpus,
+            threshold,
+            json,
+        } => cmd_similar(inputs, corpus, threshold, json, &config),
+        Commands::Signatures { inputs, db, json } => cmd_signatures(inputs, db, json),
+        Commands::Xref { name, inputs, json } => cmd_xref(name, inputs, json),
+        Commands::Query { script, inputs } => cmd_query(script, inputs),
+        Commands::Analyze {
+            inputs,
+            baseline,
+            update_baseline,
+            html,
+            json,
+            no_prune,
+        } => cmd_analyze(
+            inputs,
+            baseline,
+            update_baseline,
+            html,
+            json,
+            no_prune,
+            &config,
+        ),
+        #[cfg(feature = "unstable")]
+        Commands::Test { inputs } => cmd_test(inputs),
+        Commands::Coverage { input, json } => cmd_coverage(input, json),
+        Commands::Inspect {
+            input,
+            contract,
+            function,
+            show,
+        } => cmd_inspect(input, contract, function, show, &config),
+        Commands::Trace {
+            input,
+            function,
+            path,
+            args,
+            calldata,
+            output,
+        } => cmd_trace(input, function, path, args, calldata, output),
+        Commands::Codegen {
+            input,
+            contract,
+            output,
+        } => cmd_codegen(input, contract, output, config.codegen.word_abi),
+        Commands::Interface {
+            input,
+            contract,
+            abi,
+            output,
+            check,
+        } => cmd_interface(input, contract, abi, output, check),
+        Commands::Lint {
+            input,
+            deny_warnings,
+            json,
+        } => cmd_lint(input, config.lint, deny_warnings, json),
+        Commands::Review {
+            notes,
+            inputs,
+            output,
+        } => cmd_review(notes, inputs, output),
+        Commands::Addresses {
+            inputs,
+            labels,
+            json,
+        } => cmd_addresses(inputs, labels, json, &config),
+        #[cfg(feature = "bench")]
+        Commands::Bench {
+            fixtures,
+            stages,
+            iterations,
+            baseline,
+            max_regression,
+            save_baseline,
+            json,
+        } => cmd_bench(
+            fixtures,
+            stages,
+            iterations,
+            baseline,
+            max_regression,
+            save_baseline,
+            json,
+        ),
+    }
+}
+
+enum CompileSource {
+    File(String),
+    Sources(
+        Vec<thalir::standard_json::SourceFile>,
+        Option<thalir_core::EvmVersion>,
+    ),
+}
+
+/* A solc standard-json input is lowered source by source in its own order; paths given only as
+ * `urls` are resolved next to the JSON file, using the project's remappings after its own. */
+fn load_standard_json(
+    input: &std::path::Path,
+    text: &str,
+    config: &thalir::Config,
+) -> Result<CompileSource> {
+    use thalir::standard_json::StandardJsonInput;
+
+    let mut standard = StandardJsonInput::parse(text)?;
+    standard
+        .settings
+        .remappings
+        .extend(config.remappings.iter().cloned());
+    let base_dir = input.parent().unwrap_or_else(|| std::path::Path::new("."));
+    Ok(CompileSource::Sources(
+        standard.load_sources(base_dir)?,
+        standard.evm_version(),
+    ))
+}
+
+#[cfg(feature = "fetch")]
+fn fetch_verified_source(
+    address: &str,
+    chain: u64,
+    api_key: Option<String>,
+    config: &thalir::Config,
+) -> Result<CompileSource> {
+    use colored::*;
+    use thalir::fetch::SourceFetcher;
+
+    let mut fetcher = SourceFetcher::new();
+    if let Some(key) = api_key {
+        fetcher = fetcher.with_api_key(key);
+    }
+    if std::env::var_os("THALIR_CACHE_DIR").is_none() {
+        if let Some(dir) = &config.cache_dir {
+            fetcher = fetcher.with_cache_dir(Some(dir.clone()));
+        }
+    }
+    let verified = fetcher.fetch(address, chain)?;
+
+    tracing::info!(
+        contract = %verified.contract_name,
+        files = verified.files.len(),
+        compiler = %verified.compiler_version,
+        "fetched verified source"
+    );
+    if let Some(implementation) = &verified.implementation {
+        eprintln!(
+            "{} {} is a proxy; its logic lives at {}",
+            "NOTE:".bright_black(),
+            verified.address,
+            implementation
+        );
+    }
+    let evm_version = verified.evm_version.as_deref().and_then(|v| v.parse().ok());
+    Ok(CompileSource::Sources(verified.files, evm_version))
+}
+
+#[cfg(not(feature = "fetch"))]
+fn fetch_verified_source(
+    _address: &str,
+    _chain: u64,
+    _api_key: Option<String>,
+    _config: &thalir::Config,
+) -> Result<CompileSource> {
+    Err(anyhow::anyhow!(
+        "--address needs a build with --features fetch"
+    ))
+}
+
+/* The `compile` flags once the config file has filled in what the command line left out. */
+struct CompileOptions {
+    input: Option<PathBuf>,
+    address: Option<(String, u64, Option<String>)>,
+    output: Option<PathBuf>,
+    annotated: bool,
+    ascii: bool,
+    intrinsics: bool,
+    jsonl: bool,
+    pseudo: bool,
+    verbosity: Verbosity,
+    lenient: bool,
+    strict: bool,
+    evm_version: Option<thalir_core::EvmVersion>,
+    storage_names: StorageNames,
+    summaries: bool,
+    function_order: FunctionOrder,
+    group_functions: bool,
+    inline: bool,
+    outline: bool,
+    review: Option<PathBuf>,
+    obfuscate: ObfuscationLevel,
+    save_mapping: Option<PathBuf>,
+    mapping_key: Option<PathBuf>,
+    salt_file: Option<PathBuf>,
+    retain: Vec<RetainedMetadata>,
+    aliases: Vec<AliasClass>,
+    verbose: bool,
+}
+
+fn cmd_compile(options: CompileOptions, config: &thalir::Config) -> Result<()> {
+    use colored::*;
+    use std::fs;
+    use std::time::Instant;
+    use thalir_core::{profiling::MemoryProfiler, review::ReviewFile, ObfuscationConfig};
+    use thalir_emit::{
+        AnnotatedIREmitter, EmitterConfig, JsonlEmitter, PseudoSolidityEmitter, ThalIREmitter,
+    };
+    use thalir_transform::{DiagnosticKind, DiagnosticSeverity, TransformationPipeline};
+
+    let CompileOptions {
+        input,
+        address,
+        output,
+        annotated,
+        ascii,
+        intrinsics,
+        jsonl,
+        pseudo,
+        verbosity,
+        lenient,
+        strict,
+        evm_version,
+        storage_names,
+        summaries,
+        function_order,
+        group_functions,
+        inline,
+        outline,
+        review,
+        obfuscate,
+        save_mapping,
+        mapping_key,
+        salt_file,
+        retain,
+        aliases,
+        verbose,
+    } = options;
+
+    type Render = Box<dyn FnOnce() -> Result<String>>;
+    fn render(f: impl FnOnce() -> Result<String> + 'static) -> Render {
+        Box::new(f)
+    }
+
+    let source_label = match (&input, &address) {
+        (Some(input), _) => input.display().to_string(),
+        (None, Some((address, chain, _))) => format!("{} (chain {})", address, chain),
+        (None, None) => String::new(),
+    };
+    let mode = match (annotated, jsonl) {
+        _ if pseudo => "pseudo",
+        (true, _) if ascii => "annotated-ascii",
+        (true, _) => "annotated",
+        (false, true) => "jsonl",
+        (false, false) => "thalir",
+    };
+    let _span = tracing::info_span!("compile", input = %source_label).entered();
+    tracing::info!(
+        mode,
+        verbosity = ?verbosity,
+        obfuscation = ?obfuscate,
+        evm_version = evm_version.map(|v| v.to_string()),
+        output = output.as_ref().map(|out| out.display().to_string()),
+        "compile started"
+    );
+
+    let start = Instant::now();
+
+    tracing::info!("loading Solidity source");
+    let limits = config.limits.input_limits();
+    let source = match (&input, address) {
+        (Some(input), _) => {
+            /* Checked before reading so an oversized file is never loaded. */
+            limits.check_size(fs::metadata(input)?.len() as usize)?;
+            if input.extension().is_some_and(|ext| ext == "json") {
+                let text = fs::read_to_string(input)?;
+                load_standard_json(input, &text, config)?
+            } else {
+                CompileSource::File(fs::read_to_string(input)?)
+            }
+        }
+        (None, Some((address, chain, api_key))) => {
+            fetch_verified_source(&address, chain, api_key, config)?
+        }
+        (None, None) => {
+            return Err(anyhow::anyhow!(
+                "Either an input file or --address is required"
+            ))
+        }
+    };
+
+    tracing::info!("transforming to ThalIR");
+    let mut profiler = MemoryProfiler::new();
+    let transformed = profiler.phase("transform", || match &source {
+        CompileSource::File(content) => {
+            let pipeline = match input.as_deref().and_then(|path| path.to_str()) {
+                Some(path) => TransformationPipeline::with_filename(content, path.to_string()),
+                None => TransformationPipeline::default(content),
+            };
+            pipeline
+                .lenient(lenient)
+                .evm_version(evm_version)
+                .limits(limits)
+                .transform_with_diagnostics()
+        }
+        CompileSource::Sources(files, declared) => thalir::standard_json::transform_sources(
+            files,
+            lenient,
+            evm_version.or(*declared),
+            limits,
+        ),
+    })?;
+
+    for diagnostic in transformed.diagnostics.iter() {
+        if diagnostic.kind == DiagnosticKind::SkippedSyntaxError {
+            eprintln!("{} {}", "SKIPPED:".yellow().bold(), diagnostic);
+        } else if verbose || (strict && diagnostic.severity == DiagnosticSeverity::Error) {
+            let label = match diagnostic.severity {
+                DiagnosticSeverity::Error => "LOSS:".red().bold(),
+                DiagnosticSeverity::Warning => "APPROX:".yellow().bold(),
+                DiagnosticSeverity::Info => "NOTE:".bright_black(),
+            };
+            eprintln!("{} {}", label, diagnostic);
+        }
+    }
+
+    if strict && transformed.diagnostics.has_errors() {
+        let losses = transformed
+            .diagnostics
+            .at_least(DiagnosticSeverity::Error)
+            .count();
+        return Err(anyhow::anyhow!(
+            "Strict mode: {} high-severity fidelity loss{} during transformation",
+            losses,
+            if losses == 1 { "" } else { "es" }
+        ));
+    }
+
+    let mut contracts = transformed.contracts;
+
+    if contracts.is_empty() {
+        println!("{}", "  No contracts found in input".yellow());
+        return Ok(());
+    }
+
+    if inline {
+        let inlined = thalir_core::transform::Inliner::new().inline_workspace(&mut contracts);
+        tracing::info!(inlined, "inlined call sites");
+    }
+
+    if outline {
+        let outliner = thalir_core::transform::Outliner::new();
+        let outlined: usize = contracts
+            .iter_mut()
+            .map(|contract| outliner.outline_contract(contract).len())
+            .sum();
+        tracing::info!(outlined, "outlined duplicated regions");
+    }
+
+    tracing::info!(contracts = contracts.len(), "generating IR output");
+
+    let metadata = retained_metadata_policy(&retain);
+    let hash_salt = match &salt_file {
+        Some(path) => Some(fs::read_to_string(path)?.trim().to_string()),
+        None => None,
+    };
+    let aliases = alias_classes(&aliases);
+    let review = match &review {
+        Some(path) => ReviewFile::load(path)?,
+        None => ReviewFile::default(),
+    };
+
+    /* Building the emitter is where obfuscation runs, so it is timed apart from rendering. */
+    let (renderer, mapping) = profiler.phase("build", || -> Result<(Render, _)> {
+        Ok(if pseudo {
+            if matches!(obfuscate, ObfuscationLevel::None) {
+                let emitter = PseudoSolidityEmitter::new(contracts);
+                (render(move || Ok(emitter.emit_to_string())), None)
+            } else {
+                let obf_config = ObfuscationConfig {
+                    level: obfuscate.into(),
+                    retain_mapping: save_mapping.is_some(),
+                    hash_salt,
+                    strip_string_constants: true,
+                    strip_error_messages: true,
+                    strip_metadata: true,
+                    metadata,
+                    aliases,
+                };
+                let (emitter, mapping) =
+                    PseudoSolidityEmitter::with_obfuscation(contracts, obf_config)?;
+                (render(move || Ok(emitter.emit_to_string())), mapping)
+            }
+        } else if jsonl {
+            if matches!(obfuscate, ObfuscationLevel::None) {
+                let emitter = JsonlEmitter::new(contracts);
+                (render(move || emitter.emit_to_string()), None)
+            } else {
+                let obf_config = ObfuscationConfig {
+                    level: obfuscate.into(),
+                    retain_mapping: save_mapping.is_some(),
+                    hash_salt,
+                    strip_string_constants: true,
+                    strip_error_messages: true,
+                    strip_metadata: true,
+                    metadata,
+                    aliases,
+                };
+                let (emitter, mapping) = JsonlEmitter::with_obfuscation(contracts, obf_config)?;
+                (render(move || emitter.emit_to_string()), mapping)
+            }
+        } else {
+            let emitter_config = EmitterConfig {
+                function_order: function_order.into(),
+                group_functions,
+                ..EmitterConfig::for_profile(verbosity.into())
+            };
+            match (annotated, matches!(obfuscate, ObfuscationLevel::None)) {
+                (true, true) => {
+                    use thalir_emit::annotated_ir_emitter::AnnotationConfig;
+                    let config = AnnotationConfig {
+                        emit_position_markers: true,
+                        emit_visual_cues: true,
+                        use_ascii_cues: ascii,
+                        emit_ordering_analysis: true,
+                        emit_function_headers: true,
+                        condense_intrinsics: intrinsics,
+                    };
+                    let emitter = AnnotatedIREmitter::new(contracts)
+                        .with_annotation_config(config)
+                        .with_emitter_config(emitter_config)
+                        .with_review(review);
+                    (render(move || Ok(emitter.emit_to_string(false))), None)
+                }
+                (true, false) => {
+                    use thalir_emit::annotated_ir_emitter::AnnotationConfig;
+                    let obf_config = ObfuscationConfig {
+                        level: obfuscate.into(),
+                        retain_mapping: save_mapping.is_some(),
+                        hash_salt,
+                        strip_string_constants: true,
+                        strip_error_messages: true,
+                        strip_metadata: true,
+                        metadata,
+                        aliases,
+                    };
+                    let ann_config = AnnotationConfig {
+                        emit_position_markers: true,
+                        emit_visual_cues: true,
+                        use_ascii_cues: ascii,
+                        emit_ordering_analysis: true,
+                        emit_function_headers: true,
+                        condense_intrinsics: intrinsics,
+                    };
+                    let (emitter, mapping) =
+                        AnnotatedIREmitter::with_obfuscation(contracts, obf_config, ann_config)?;
+                    let emitter = emitter
+                        .with_emitter_config(emitter_config)
+                        .with_review(review);
+                    (render(move || Ok(emitter.emit_to_string(false))), mapping)
+                }
+                (false, true) => {
+                    let emitter = ThalIREmitter::new(contracts)
+                        .with_storage_naming(storage_names.into())
+                        .with_emitter_config(EmitterConfig {
+                            include_summaries: summaries,
+                            ..emitter_config
+                        });
+                    (render(move || Ok(emitter.emit_to_string(false))), None)
+                }
+                (false, false) => {
+                    let obf_config = ObfuscationConfig {
+                        level: obfuscate.into(),
+                        retain_mapping: save_mapping.is_some(),
+                        hash_salt,
+                        strip_string_constants: true,
+                        strip_error_messages: true,
+                        strip_metadata: true,
+                        metadata,
+                        aliases,
+                    };
+                    let (emitter, mapping) =
+                        ThalIREmitter::with_obfuscation(contracts, obf_config)?;
+                    let emitter = emitter
+                        .with_storage_naming(storage_names.into())
+                        .with_emitter_config(EmitterConfig {
+                            include_summaries: summaries,
+                            ..emitter_config
+                        });
+                    (render(move || Ok(emitter.emit_to_string(false))), mapping)
+                }
+            }
+        })
+    })?;
+
+    let ir_output = (profiler.phase("emit", renderer)?, mapping);
+
+    if let (Some(mapping_path), Some(mut mapping)) = (save_mapping, ir_output.1) {
+        let key = match &mapping_key {
+            Some(key_path) => fs::read(key_path)?,
+            None => thalir_core::obfuscation::mapping_store::key_from_env().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "--save-mapping needs a signing key: pass --mapping-key or set {}",
+                    thalir_core::obfuscation::MAPPING_KEY_ENV
+                )
+            })?,
+        };
+        mapping.sign(&key);
+        let mapping_json = serde_json::to_string_pretty(&mapping)?;
+        fs::write(&mapping_path, mapping_json)?;
+        tracing::info!(path = %mapping_path.display(), "saved obfuscation mapping");
+    }
+
+    if let Some(output_path) = output {
+        fs::write(&output_path, &ir_output.0)?;
+        tracing::info!(
+            elapsed_s = start.elapsed().as_secs_f64(),
+            output = %output_path.display(),
+            "compile finished"
+        );
+    } else {
+        println!("{}", ir_output.0);
+    }
+
+    log_phase_timings(&profiler);
+    if verbose {
+        print_memory_report(&profiler);
+    }
+
+    Ok(())
+}
+
+fn print_memory_report(profiler: &thalir_core::profiling::MemoryProfiler) {
+    use colored::*;
+    use thalir_core::profiling::{format_bytes, peak_rss, tracking_enabled};
+
+    eprintln!("\n{}", " Memory".bright_cyan().bold());
+    if tracking_enabled() {
+        for phase in &profiler.phases {
+            eprintln!(
+                "   {:<10} peak +{:<10} allocated {:<10} ({} allocations, {:.3}s)",
+                phase.phase,
+                format_bytes(phase.peak_above_start as u64),
+                format_bytes(phase.allocated as u64),
+                phase.allocations,
+                phase.elapsed.as_secs_f64()
+            );
+        }
+    } else {
+        eprintln!(
+            "   {}",
+            "Per-phase allocations need a build with --features memory-profile".bright_black()
+        );
+    }
+    match peak_rss() {
+        Some(rss) => eprintln!("   Peak RSS:  {}", format_bytes(rss)),
+        None => eprintln!("   Peak RSS:  unavailable on this platform"),
+    }
+}
+
+fn cmd_deobfuscate(
+    mapping: PathBuf,
+    key: Option<PathBuf>,
+    report: Option<PathBuf>,
+    output: Option<PathBuf>,
+    format: ReportFormatArg,
+) -> Result<()> {
+    use colored::*;
+    use std::fs;
+    use thalir_core::obfuscation::ReportFormat;
+    use thalir_core::{ObfuscationMapping, VulnerabilityMapper};
+
+    let mapping_json = fs::read_to_string(&mapping)?;
+    let obf_mapping: ObfuscationMapping = serde_json::from_str(&mapping_json)?;
+    let mapper = match key {
+        Some(key_path) => {
+            VulnerabilityMapper::from_mapping_with_key(obf_mapping, &fs::read(key_path)?)?
+        }
+        None => VulnerabilityMapper::from_mapping(obf_mapping)?,
+    };
+
+    let report_content = if let Some(report_path) = report {
+        fs::read_to_string(&report_path)?
+    } else {
+        use std::io::Read;
+        let mut buffer = String::new();
+        std::io::stdin().read_to_string(&mut buffer)?;
+        buffer
+    };
+
+    let format = match format {
+        ReportFormatArg::Auto => ReportFormat::detect(&report_content),
+        ReportFormatArg::Text => ReportFormat::Text,
+        ReportFormatArg::Json => ReportFormat::Json,
+        ReportFormatArg::Sarif => ReportFormat::Sarif,
+    };
+    let deobfuscated = mapper
+        .deobfuscate_formatted(&report_content, format)
+        .map_err(|e| anyhow::anyhow!("Report is not valid {:?}: {}", format, e))?;
+
+    if let Some(output_path) = output {
+        fs::write(&output_path, &deobfuscated)?;
+        println!(
+            " {} De-obfuscated report saved to: {}",
+            "SUCCESS:".bright_green().bold(),
+            output_path.display()
+        );
+    } else {
+        println!("{}", deobfuscated);
+    }
+
+    Ok(())
+}
+
+fn cmd_verify_obfuscation(
+    original: PathBuf,
+    obfuscated: PathBuf,
+    mapping: PathBuf,
+    key: PathBuf,
+    output: Option<PathBuf>,
+    json: bool,
+) -> Result<()> {
+    use colored::*;
+    use std::fs;
+    use thalir_core::obfuscation::{verify_obfuscation, ObfuscationAttestation};
+    use thalir_core::ObfuscationMapping;
+
+    let original_ir = fs::read_to_string(&original)?;
+    let obfuscated_ir = fs::read_to_string(&obfuscated)?;
+    let mapping_json = fs::read_to_string(&mapping)?;
+    let obf_mapping: ObfuscationMapping = serde_json::from_str(&mapping_json)?;
+    let key = fs::read(&key)?;
+
+    let report = verify_obfuscation(&original_ir, &obfuscated_ir, &obf_mapping);
+    let attestation = ObfuscationAttestation::sign(
+        &report,
+        original_ir.as_bytes(),
+        obfuscated_ir.as_bytes(),
+        mapping_json.as_bytes(),
+        &key,
+    );
+    let attestation_json = serde_json::to_string_pretty(&attestation)?;
+
+    if let Some(output_path) = &output {
+        fs::write(output_path, &attestation_json)?;
+    }
+
+    if json {
+        println!("{}", attestation_json);
+    } else {
+        let verdict = if report.is_verified() {
+            " VERIFIED".bright_green().bold()
+        } else {
+            " MISMATCH".bright_red().bold()
+        };
+        println!("{}", verdict);
+        println!("   Instructions:     {}", report.instructions);
+        println!("   Renamed tokens:   {}", report.renamed_tokens);
+        println!("   Redacted strings: {}", report.redacted_strings);
+        if report.unused_mappings > 0 {
+            println!("   Unused mappings:  {}", report.unused_mappings);
+        }
+        for discrepancy in &report.discrepancies {
+            println!(
+                "   {} line {}: {}",
+                format!("{:?}", discrepancy.kind).bright_yellow(),
+                discrepancy.line,
+                discrepancy.detail
+            );
+        }
+        if let Some(output_path) = &output {
+            println!(" Attestation saved to: {}", output_path.display());
+        }
+    }
+
+    if report.is_verified() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "obfuscated IR differs from the original in {} place(s)",
+            report.discrepancies.len()
+        ))
+    }
+}
+
+fn cmd_diff_rounds(
+    previous: PathBuf,
+    current: PathBuf,
+    mappings: Option<(PathBuf, PathBuf)>,
+    json: bool,
+) -> Result<()> {
+    use colored::*;
+    use std::fs;
+    use thalir_core::obfuscation::{ChangeKind, RoundDiff};
+    use thalir_core::ObfuscationMapping;
+
+    if let Some((previous_mapping, current_mapping)) = &mappings {
+        RoundDiff::check_mappings(
+            &ObfuscationMapping::load_from_file(previous_mapping)?,
+            &ObfuscationMapping::load_from_file(current_mapping)?,
+        )?;
+    }
+
+    let mut rounds = Vec::new();
+    for path in [&previous, &current] {
+        let reconstruction = thalir_parser::reconstruct(&fs::read_to_string(path)?)?;
+        if !reconstruction.unrecognized.is_empty() {
+            eprintln!(
+                "{} {} instruction(s) in {} could not be reconstructed and are left out",
+                "APPROX:".yellow().bold(),
+                reconstruction.unrecognized.len(),
+                path.display()
+            );
+        }
+        rounds.push(reconstruction.contracts);
+    }
+    let diff = RoundDiff::new(&rounds[0], &rounds[1]);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        return Ok(());
+    }
+    if mappings.is_none() {
+        eprintln!(
+            "{} names are only comparable if both rounds were obfuscated with the same --salt-file",
+            "NOTE:".bright_black()
+        );
+    }
+
+    for change in &diff.changes {
+        let kind = match change.kind {
+            ChangeKind::Added => change.kind.to_string().bright_green(),
+            ChangeKind::Removed => change.kind.to_string().bright_red(),
+            ChangeKind::Modified => change.kind.to_string().bright_yellow(),
+        };
+        println!(" {:<8} {}::{}", kind, change.contract, change.function);
+    }
+    println!(
+        " {} changed, {} unchanged",
+        diff.changes.len(),
+        diff.unchanged
+    );
+    Ok(())
+}
+
+fn cmd_validate(input: PathBuf) -> Result<()> {
+    use colored::*;
+    use std::fs;
+
+    let _span = tracing::info_span!("validate", input = %input.display()).entered();
+    let ir_content = fs::read_to_string(&input)?;
+
+    tracing::info!("parsing ThalIR");
+    match thalir_parser::parse(&ir_content) {
+        Ok(pairs) => {
+            tracing::info!(elements = pairs.count(), "parsed top-level elements");
+            println!("{}", " VALID".bright_green().bold());
+            Ok(())
+        }
+        Err(e) => {
+            println!("{}", " INVALID".bright_red().bold());
+            println!("\n{}", "Parse Error:".bright_red());
+            println!("{}", e);
+            Err(anyhow::anyhow!("Validation failed"))
+        }
+    }
+}
+
+fn cmd_debug(input: PathBuf, verbose: bool) -> Result<()> {
+    use colored::*;
+    use std::fs;
+    use thalir_transform::transform_solidity_to_ir;
+
+    let _span = tracing::info_span!("debug", input = %input.display()).entered();
+    let solidity_content = fs::read_to_string(&input)?;
+    tracing::info!("transforming to ThalIR");
+    let contracts = transform_solidity_to_ir(&solidity_content)?;
+
+    if contracts.is_empty() {
+        println!("  No contracts found");
+        return Ok(());
+    }
+
+    println!(" Found {} contract(s)\n", contracts.len());
+
+    for (idx, contract) in contracts.iter().enumerate() {
+        println!(
+            "{}",
+            format!(" Contract {}: {}", idx, contract.name)
+                .bright_green()
+                .bold()
+        );
+        println!("{}", "".repeat(60).bright_green());
+        println!("Functions: {}", contract.functions.len());
+        println!("Storage slots: {}", contract.storage_layout.slots.len());
+
+        if verbose {
+            if !contract.storage_layout.slots.is_empty() {
+                println!("\n  Storage Layout:");
+                for slot in &contract.storage_layout.slots {
+                    println!(
+                        "    slot {} = {}: {:?}",
+                        slot.slot, slot.name, slot.var_type
+                    );
+                }
+            }
+
+            for (func_name, function) in &contract.functions {
+                println!(
+                    "\n  {}",
+                    format!(" Function: {}", func_name).bright_yellow()
+                );
+                println!("     Visibility: {:?}", function.visibility);
+                println!("     Mutability: {:?}", function.mutability);
+                println!("     Parameters: {}", function.signature.params.len());
+                println!("     Returns: {}", function.signature.returns.len());
+                println!("     Blocks: {}", function.body.blocks.len());
+
+                for (block_id, block) in &function.body.blocks {
+                    println!(
+                        "       Block {:?}: {} instructions",
+                        block_id,
+                        block.instructions.len()
+                    );
+                }
+            }
+        }
+
+        println!();
+    }
+
+    Ok(())
+}
+
+fn collect_solidity_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    collect_files(paths, "sol")
+}
+
+fn collect_files(paths: &[PathBuf], extension: &str) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending: Vec<PathBuf> = paths.to_vec();
+
+    while let Some(path) = pending.pop() {
+        if path.is_dir() {
+            for entry in std::fs::read_dir(&path)? {
+                pending.push(entry?.path());
+            }
+        } else if path.extension().is_some_and(|ext| ext == extension) {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/* With a cache, fingerprints of contracts seen on an earlier run are read back instead of
+ * recomputed. */
+fn build_similarity_index(
+    paths: &[PathBuf],
+    mut cache: Option<&mut thalir_core::analysis::DiskCache>,
+) -> Result<thalir_core::analysis::SimilarityIndex> {
+    use std::fs;
+    use thalir_core::analysis::SimilarityIndex;
+    use thalir_transform::transform_solidity_to_ir_with_filename;
+
+    let mut index = SimilarityIndex::new();
+    let files = collect_solidity_files(paths)?;
+    for (position, file) in files.iter().enumerate() {
+        let _span = tracing::info_span!("file", path = %file.display()).entered();
+        tracing::info!(position = position + 1, total = files.len(), "processing");
+        let source = fs::read_to_string(file)?;
+        let contracts = transform_solidity_to_ir_with_filename(&source, file.to_str())?;
+        for contract in &contracts {
+            match cache.as_deref_mut() {
+                Some(cache) => {
+                    for fingerprint in cache.analyses(contract).fingerprints {
+                        index.add_fingerprint(fingerprint);
+                    }
+                }
+                None => index.add_contract(contract),
+            }
+        }
+    }
+    Ok(index)
+}
+
+/* Analyses are only kept between runs when the project sets `cache-dir`. */
+fn analysis_cache(config: &thalir::Config) -> Option<thalir_core::analysis::DiskCache> {
+    config
+        .cache_dir
+        .as_ref()
+        .map(|dir| thalir_core::analysis::DiskCache::new(dir.join("analyses")))
+}
+
+fn cmd_similar(
+    inputs: Vec<PathBuf>,
+    corpus: Option<PathBuf>,
+    threshold: f64,
+    json: bool,
+    config: &thalir::Config,
+) -> Result<()> {
+    use colored::*;
+
+    let mut cache = analysis_cache(config);
+    let index = build_similarity_index(&inputs, cache.as_mut())?;
+
+    let matches = match &corpus {
+        Some(corpus_path) => {
+            let corpus_index =
+                build_similarity_index(std::slice::from_ref(corpus_path), cache.as_mut())?;
+            index.match_against(&corpus_index, threshold)
+        }
+        None => index.near_duplicates(threshold),
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&matches)?);
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!(" Indexed {} function(s)", index.len())
+            .bright_cyan()
+            .bold()
+    );
+
+    if matches.is_empty() {
+        println!("  No functions above similarity {:.2}", threshold);
+        return Ok(());
+    }
+
+    for m in &matches {
+        let tag = if m.exact {
+            "EXACT".bright_red().bold()
+        } else {
+            "SIMILAR".yellow().bold()
+        };
+        println!("  {} {:.2}  {} <-> {}", tag, m.score, m.left, m.right);
+    }
+
+    Ok(())
+}
+
+fn cmd_signatures(inputs: Vec<PathBuf>, db: PathBuf, json: bool) -> Result<()> {
+    use colored::*;
+    use std::fs;
+    use thalir_core::analysis::SignatureDatabase;
+    use thalir_transform::transform_solidity_to_ir_with_filename;
+
+    let database = SignatureDatabase::load_dir(&db)?;
+
+    let mut matches = Vec::new();
+    let files = collect_solidity_files(&inputs)?;
+    for (position, file) in files.iter().enumerate() {
+        let _span = tracing::info_span!("file", path = %file.display()).entered();
+        tracing::info!(position = position + 1, total = files.len(), "processing");
+        let source = fs::read_to_string(file)?;
+        let contracts = transform_solidity_to_ir_with_filename(&source, file.to_str())?;
+        for contract in &contracts {
+            matches.extend(database.match_contract(contract));
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&matches)?);
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!(
+            " Loaded {} signature(s) from {} pack(s)",
+            database.signature_count(),
+            database.packs().len()
+        )
+        .bright_cyan()
+        .bold()
+    );
+
+    if matches.is_empty() {
+        println!("  No known signatures matched");
+        return Ok(());
+    }
+
+    for m in &matches {
+        println!(
+            "  {} {}::{} - {} [{}]",
+            format!("[{}]", m.severity).bright_red().bold(),
+            m.contract,
+            m.function,
+            m.title,
+            m.provenance()
+        );
+    }
+
+    Ok(())
+}
+
+/* `name` may be qualified as `Contract.name` to pick one of several symbols sharing a name. */
+fn cmd_xref(name: String, inputs: Vec<PathBuf>, json: bool) -> Result<()> {
+    use colored::*;
+    use std::fs;
+    use thalir_core::analysis::SymbolIndex;
+    use thalir_transform::transform_solidity_to_ir_with_filename;
+
+    let mut contracts = Vec::new();
+    for file in collect_solidity_files(&inputs)? {
+        let source = fs::read_to_string(&file)?;
+        contracts.extend(transform_solidity_to_ir_with_filename(
+            &source,
+            file.to_str(),
+        )?);
+    }
+    let index = SymbolIndex::new(&contracts);
+    let symbols = index.lookup(&name);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&symbols)?);
+        return Ok(());
+    }
+    if symbols.is_empty() {
+        return Err(anyhow::anyhow!("No symbol named `{}`", name));
+    }
+
+    for symbol in symbols {
+        let declared = match &symbol.location {
+            Some(location) => format!(" at {}:{}", location.file, location.line),
+            None => String::new(),
+        };
+        println!(
+            " {} {}{}",
+            symbol.kind.to_string().bright_cyan().bold(),
+            symbol.qualified_name().bold(),
+            declared
+        );
+        for reference in &symbol.references {
+            println!(
+                "    {:<6} {}::{} ({}:{})",
+                reference.kind,
+                reference.contract,
+                reference.function,
+                reference.location.block,
+                reference.location.index
+            );
+        }
+        if symbol.references.is_empty() {
+            println!("    no references");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "query")]
+fn cmd_query(script: PathBuf, inputs: Vec<PathBuf>) -> Result<()> {
+    use std::fs;
+    use thalir_transform::transform_solidity_to_ir_with_filename;
+
+    let script = fs::read_to_string(&script)
+        .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", script.display(), e))?;
+    let mut contracts = Vec::new();
+    for file in collect_solidity_files(&inputs)? {
+        let source = fs::read_to_string(&file)?;
+        contracts.extend(transform_solidity_to_ir_with_filename(
+            &source,
+            file.to_str(),
+        )?);
+    }
+
+    for line in thalir::query::run_query(&script, contracts)? {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "query"))]
+fn cmd_query(_script: PathBuf, _inputs: Vec<PathBuf>) -> Result<()> {
+    Err(anyhow::anyhow!("query needs a build with --features query"))
+}
+
+/* Without a baseline every finding is reported. With one, the first run records the current
+ * findings and later runs report, and fail on, only those it has not seen. The HTML report
+ * covers the same findings as the console. */
+fn cmd_analyze(
+    inputs: Vec<PathBuf>,
+    baseline: Option<PathBuf>,
+    update_baseline: bool,
+    html: Option<PathBuf>,
+    json: bool,
+    no_prune: bool,
+    config: &thalir::Config,
+) -> Result<()> {
+    use colored::*;
+    use std::fs;
+    use thalir_core::analysis::{
+        baseline::dedup, rank, run_detectors, AddressBook, Baseline, CallbackAnalysis,
+        ContractPruning, SelectorCollisionAnalysis, SeverityScorer, Suppressions, TrustZone,
+    };
+    use thalir_core::{profiling::MemoryProfiler, stable_id};
+    use thalir_emit::HtmlReportEmitter;
+    use thalir_transform::transform_solidity_to_ir_with_filename;
+
+    let files = collect_solidity_files(&inputs)?;
+    let mut profiler = MemoryProfiler::new();
+    let sources = profiler.phase("transform", || -> Result<Vec<_>> {
+        let mut sources = Vec::new();
+        for (position, file) in files.iter().enumerate() {
+            let _span = tracing::info_span!("file", path = %file.display()).entered();
+            tracing::info!(position = position + 1, total = files.len(), "processing");
+            let source = fs::read_to_string(file)?;
+            let contracts = transform_solidity_to_ir_with_filename(&source, file.to_str())?;
+            sources.push((source, contracts));
+        }
+        Ok(sources)
+    })?;
+
+    /* Reachability needs every file transformed first: a target may use a contract from a file
+     * read after its own. With no roots at all, say when only vendored code was given, nothing is
+     * pruned. */
+    let everything: Vec<_> = sources
+        .iter()
+        .flat_map(|(_, contracts)| contracts.iter().cloned())
+        .collect();
+    let roots = config.project.roots(&everything);
+    let pruning = (!no_prune && !roots.is_empty())
+        .then(|| ContractPruning::prune(&everything, &roots))
+        .filter(|report| !report.pruned.is_empty());
+    if let Some(report) = &pruning {
+        tracing::info!(
+            kept = report.kept.len(),
+            pruned = report.pruned.len(),
+            unused_files = report.unused_files.len(),
+            "pruned contracts the targets never reach"
+        );
+    }
+    let zones = config.project.trust_zones(&everything);
+
+    let (findings, analyzed) = profiler.phase("analyze", || {
+        let callbacks = config.callbacks.model();
+        let mut findings = Vec::new();
+        let mut analyzed = Vec::new();
+        for (source, mut contracts) in sources {
+            if let Some(report) = &pruning {
+                contracts.retain(|contract| report.keeps(&contract.name));
+                if contracts.is_empty() {
+                    continue;
+                }
+            }
+            let mut reported = run_detectors(&contracts, |name| {
+                name != CallbackAnalysis::DETECTOR && config.passes.is_enabled(name)
+            });
+            if config.passes.is_enabled(CallbackAnalysis::DETECTOR) {
+                for contract in &contracts {
+                    reported.extend(CallbackAnalysis::findings_with(contract, &callbacks));
+                }
+            }
+            let reported = Suppressions::parse(&source).filter(reported, &contracts);
+            findings.extend(
+                SeverityScorer::new(&contracts)
+                    .with_zones(&zones)
+                    .score_all(reported),
+            );
+            analyzed.extend(contracts);
+        }
+        /* A proxy and its implementation usually live in different files, so selector collisions
+         * are checked again across everything that was read; dedup drops the ones a file found
+         * alone. */
+        if config.passes.is_enabled(SelectorCollisionAnalysis::DETECTOR) {
+            let collisions = SelectorCollisionAnalysis::findings(&analyzed);
+            findings.extend(
+                SeverityScorer::new(&analyzed)
+                    .with_zones(&zones)
+                    .score_all(collisions),
+            );
+        }
+        (findings, analyzed)
+    });
+    log_phase_timings(&profiler);
+    let mut findings = stable_id::anchor(&analyzed, zones.attribute(dedup(findings)));
+    if config.project.suppress_dependency_findings {
+        findings = zones.project_findings(findings);
+    }
+    rank(&mut findings);
+
+    let total = findings.len();
+    let findings = match &baseline {
+        Some(path) if update_baseline || !path.exists() => {
+            Baseline::from_findings(&findings).save(path)?;
+            if json {
+                println!("[]");
+            } else {
+                println!(
+                    "{}",
+                    format!(" Recorded {} finding(s) in {}", total, path.display())
+                        .bright_cyan()
+                        .bold()
+                );
+            }
+            return Ok(());
+        }
+        Some(path) => Baseline::load(path)?.new_findings(findings),
+        None => findings,
+    };
+
+    if let Some(path) = &html {
+        let mut book = AddressBook::collect(&analyzed);
+        book.label(&config.addresses.address_labels()?);
+        let report = HtmlReportEmitter::new(analyzed, findings.clone())
+            .with_title(format!("ThalIR analysis of {} file(s)", files.len()))
+            .with_address_book(book)
+            .emit_to_string();
+        fs::write(path, report)?;
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&findings)?);
+    } else {
+        if let Some(report) = &pruning {
+            println!(
+                "{}",
+                format!(
+                    " Skipped {} contract(s) the targets never reach: {}",
+                    report.pruned.len(),
+                    report.pruned.join(", ")
+                )
+                .bright_black()
+            );
+            for file in &report.unused_files {
+                println!("{}", format!("   unused file {}", file).bright_black());
+            }
+        }
+        for finding in &findings {
+            println!("  {}", finding);
+            if let Some(score) = &finding.score {
+                println!("      score {}", score);
+            }
+            if finding.zone == Some(TrustZone::Dependency) {
+                println!("      in dependency code");
+            }
+        }
+        let accepted = match &baseline {
+            Some(_) => format!(", {} accepted in the baseline", total - findings.len()),
+            None => String::new(),
+        };
+        println!(
+            " {} finding(s) across {} file(s){}",
+            findings.len(),
+            files.len(),
+            accepted
+        );
+    }
+
+    if baseline.is_some() && !findings.is_empty() {
+        return Err(anyhow::anyhow!(
+            "{} finding{} not in the baseline",
+            findings.len(),
+            if findings.len() == 1 { "" } else { "s" }
+        ));
+    }
+    Ok(())
+}
+
+/* Runs every `.thalir` file under `inputs` that has a `test` line; the others are plain IR. */
+#[cfg(feature = "unstable")]
+fn cmd_test(inputs: Vec<PathBuf>) -> Result<()> {
+    use colored::*;
+    use thalir::filetest::run_file;
+
+    let mut passed = 0;
+    let mut failed = 0;
+    for file in collect_files(&inputs, "thalir")? {
+        let Some(report) = run_file(&file)? else {
+            continue;
+        };
+        for outcome in &report.outcomes {
+            if outcome.passed() {
+                passed += 1;
+                println!("  {} {}: {}", "ok".green(), file.display(), outcome.command);
+            } else {
+                failed += 1;
+                println!(
+                    "  {} {}: {}",
+                    "FAILED".red().bold(),
+                    file.display(),
+                    outcome.command
+                );
+                for failure in &outcome.failures {
+                    println!("      {}", failure);
+                }
+            }
+        }
+    }
+
+    println!(" {} passed, {} failed", passed, failed);
+    if failed > 0 {
+        return Err(anyhow::anyhow!(
+            "{} filetest{} failed",
+            failed,
+            if failed == 1 { "" } else { "s" }
+        ));
+    }
+    Ok(())
+}
+
+fn cmd_coverage(input: PathBuf, json: bool) -> Result<()> {
+    use colored::*;
+    use std::fs;
+    use thalir_transform::{CoverageReport, LoweringStatus};
+
+    let source = fs::read_to_string(&input)?;
+    let report = CoverageReport::analyze(&source, &input.to_string_lossy())?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!(" Lowering coverage for {}", report.file)
+            .bright_cyan()
+            .bold()
+    );
+    println!("  Nodes:         {}", report.total());
+    println!(
+        "  Fully lowered: {} ({:.1}%)",
+        report.count(LoweringStatus::Full),
+        report.percent(LoweringStatus::Full)
+    );
+    println!(
+        "  Partial:       {} ({:.1}%)",
+        report.count(LoweringStatus::Partial),
+        report.percent(LoweringStatus::Partial)
+    );
+    println!(
+        "  Ignored:       {} ({:.1}%)",
+        report.count(LoweringStatus::Ignored),
+        report.percent(LoweringStatus::Ignored)
+    );
+
+    println!();
+    println!(
+        "  {:<36} {:>6} {:>8} {:>8}",
+        "Node kind", "Full", "Partial", "Ignored"
+    );
+    for kind in &report.kinds {
+        let line = format!(
+            "  {:<36} {:>6} {:>8} {:>8}",
+            kind.kind, kind.full, kind.partial, kind.ignored
+        );
+        if kind.ignored > 0 {
+            println!("{}", line.red());
+        } else if kind.partial > 0 {
+            println!("{}", line.yellow());
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    if !report.diagnostics.is_empty() {
+        println!();
+        println!("{}", " Details".bright_cyan().bold());
+        for diagnostic in report.diagnostics.iter() {
+            println!("  [{:?}] {}", diagnostic.kind, diagnostic);
+        }
+    }
+
+    Ok(())
+}
+
+fn cmd_inspect(
+    input: PathBuf,
+    contract: Option<String>,
+    function: Option<String>,
+    show: InspectView,
+    config: &thalir::Config,
+) -> Result<()> {
+    use colored::*;
+    use std::fs;
+    use thalir_core::analysis::{
+        CallbackAnalysis, ContextUsageAnalysis, ControlFlowGraph, DefUseChains, PauseAnalysis,
+        StorageAccess, StorageUsageAnalysis,
+    };
+    use thalir_core::values::ValueId;
+
+    let text = fs::read_to_string(&input)?;
+    let reconstruction = thalir_parser::reconstruct(&text)?;
+    if !reconstruction.unrecognized.is_empty() {
+        eprintln!(
+            "{} {} instruction(s) could not be reconstructed and are left out",
+            "APPROX:".yellow().bold(),
+            reconstruction.unrecognized.len()
+        );
+    }
+
+    let contracts: Vec<_> = reconstruction
+        .contracts
+        .iter()
+        .filter(|c| contract.iter().all(|name| &c.name == name))
+        .collect();
+    if contracts.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No contract named `{}` in {}",
+            contract.unwrap_or_default(),
+            input.display()
+        ));
+    }
+
+    let functions: Vec<_> = contracts
+        .iter()
+        .flat_map(|c| c.functions.values().map(move |f| (*c, f)))
+        .filter(|(_, f)| function.iter().all(|name| f.name() == name))
+        .collect();
+    if functions.is_empty() {
+        if let Some(name) = &function {
+            return Err(anyhow::anyhow!("No function named `{}`", name));
+        }
+    }
+
+    let value_name = |id: &ValueId| match id {
+        ValueId::Temp(t) => format!("v{}", t.0),
+        ValueId::Param(p) => format!("arg{}", p.0),
+        other => format!("{:?}", other),
+    };
+
+    match show {
+        InspectView::Summary => {
+            for contract in &contracts {
+                let entries = contract.entry_points();
+                println!("{}", format!(" {}", contract.name).bright_cyan().bold());
+                println!("  Storage slots: {}", contract.storage_layout.slots.len());
+                println!("  Functions:     {}", contract.functions.len());
+                for (_, f) in functions.iter().filter(|(c, _)| c.name == contract.name) {
+                    let instructions: usize =
+                        f.body.blocks.values().map(|b| b.instructions.len()).sum();
+                    println!();
+                    println!("  {}", f.name().bright_yellow());
                     println!(
-                        "    slot {} = {}: {:?}",
-                        slot.slot, slot.name, slot.var_type
+                        "    {:?} {:?}, {} param(s), {} return(s)",
+                        f.visibility,
+                        f.mutability,
+                        f.signature.params.len(),
+                        f.signature.returns.len()
+                    );
+                    println!(
+                        "    {} block(s), {} instruction(s)",
+                        f.body.blocks.len(),
+                        instructions
+                    );
+                    if let Some(entry) = entries.iter().find(|e| e.name == f.name()) {
+                        match entry.selector {
+                            Some(selector) => {
+                                println!("    Entry point, selector 0x{:08x}", selector)
+                            }
+                            None => println!("    Entry point ({:?})", entry.kind),
+                        }
+                    }
+                    println!(
+                        "    Calls external: {}, modifies state: {}",
+                        f.metadata.calls_external, f.metadata.modifies_state
                     );
                 }
-            }
 
-            for (func_name, function) in &contract.functions {
+                let usage = ContextUsageAnalysis::analyze_contract(contract);
+                if !usage.is_empty() {
+                    println!();
+                    println!("  Context reads:");
+                    for line in usage.to_string().lines() {
+                        println!("    {}", line);
+                    }
+                }
+
+                let edges = CallbackAnalysis::edges(contract, &config.callbacks.model());
+                if !edges.is_empty() {
+                    println!();
+                    println!("  Re-entry edges:");
+                    for edge in &edges {
+                        println!("    {}", edge);
+                    }
+                }
+            }
+        }
+        InspectView::Cfg => {
+            for (contract, f) in &functions {
+                let cfg = ControlFlowGraph::build(f);
                 println!(
-                    "\n  {}",
-                    format!(" Function: {}", func_name).bright_yellow()
+                    "{}",
+                    format!(" {}::{}", contract.name, f.name())
+                        .bright_cyan()
+                        .bold()
                 );
-                println!("     Visibility: {:?}", function.visibility);
-                println!("     Mutability: {:?}", function.mutability);
-                println!("     Parameters: {}", function.signature.params.len());
-                println!("     Returns: {}", function.signature.returns.len());
-                println!("     Blocks: {}", function.body.blocks.len());
-
-                for (block_id, block) in &function.body.blocks {
+                for (id, block) in &f.body.blocks {
+                    let succs: Vec<String> = cfg
+                        .successors(*id)
+                        .iter()
+                        .map(|s| {
+                            if cfg.is_back_edge(*id, *s) {
+                                format!("{} (back edge)", s)
+                            } else {
+                                s.to_string()
+                            }
+                        })
+                        .collect();
+                    let preds: Vec<String> = cfg
+                        .predecessors(*id)
+                        .iter()
+                        .map(|p| p.to_string())
+                        .collect();
+                    let mut tags = Vec::new();
+                    if *id == cfg.entry() {
+                        tags.push("entry");
+                    }
+                    if cfg.exits().contains(id) {
+                        tags.push("exit");
+                    }
+                    if cfg.is_loop_header(*id) {
+                        tags.push("loop header");
+                    }
                     println!(
-                        "       Block {:?}: {} instructions",
-                        block_id,
-                        block.instructions.len()
+                        "  {}: {} instruction(s){}",
+                        id,
+                        block.instructions.len(),
+                        if tags.is_empty() {
+                            String::new()
+                        } else {
+                            format!(" [{}]", tags.join(", "))
+                        }
+                    );
+                    println!(
+                        "    preds: {}",
+                        if preds.is_empty() {
+                            "-".to_string()
+                        } else {
+                            preds.join(", ")
+                        }
+                    );
+                    println!(
+                        "    succs: {}",
+                        if succs.is_empty() {
+                            "-".to_string()
+                        } else {
+                            succs.join(", ")
+                        }
+                    );
+                }
+                println!();
+            }
+        }
+        InspectView::Defuse => {
+            for (contract, f) in &functions {
+                let chains = DefUseChains::build(f);
+                println!(
+                    "{}",
+                    format!(" {}::{}", contract.name, f.name())
+                        .bright_cyan()
+                        .bold()
+                );
+                for (id, block) in &f.body.blocks {
+                    for index in 0..block.instructions.len() {
+                        for def in chains.get_inst_defs(*id, index) {
+                            let uses: Vec<String> = chains
+                                .get_uses(*def)
+                                .iter()
+                                .map(|u| format!("{}[{}] {:?}", u.block, u.instruction, u.kind))
+                                .collect();
+                            let uses = if uses.is_empty() {
+                                "unused".red().to_string()
+                            } else {
+                                uses.join(", ")
+                            };
+                            println!(
+                                "  {} defined at {}[{}] -> {}",
+                                value_name(def),
+                                id,
+                                index,
+                                uses
+                            );
+                        }
+                    }
+                }
+                println!();
+            }
+        }
+        InspectView::Storage => {
+            let usage = StorageUsageAnalysis::analyze_workspace(&contracts);
+            for contract in &contracts {
+                println!("{}", format!(" {}", contract.name).bright_cyan().bold());
+                let accesses: Vec<_> = functions
+                    .iter()
+                    .filter(|(c, _)| c.name == contract.name)
+                    .map(|(_, f)| (f.name(), StorageAccess::of_function(f)))
+                    .collect();
+                for var in &contract.storage_layout.slots {
+                    let readers: Vec<&str> = accesses
+                        .iter()
+                        .filter(|(_, a)| a.reads.contains(&var.slot))
+                        .map(|(name, _)| *name)
+                        .collect();
+                    let writers: Vec<&str> = accesses
+                        .iter()
+                        .filter(|(_, a)| a.writes.contains(&var.slot))
+                        .map(|(name, _)| *name)
+                        .collect();
+                    println!("  slot {} = {}: {:?}", var.slot, var.name, var.var_type);
+                    println!(
+                        "    read by:    {}",
+                        if readers.is_empty() {
+                            "-".to_string()
+                        } else {
+                            readers.join(", ")
+                        }
                     );
+                    println!(
+                        "    written by: {}",
+                        if writers.is_empty() {
+                            "-".to_string()
+                        } else {
+                            writers.join(", ")
+                        }
+                    );
+                    if let Some((_, hygiene)) = usage.iter().find(|(name, h)| {
+                        *name == contract.name && h.slot == var.slot && h.offset == var.offset
+                    }) {
+                        println!("    {}", hygiene.usage.to_string().yellow());
+                    }
+                }
+                let unresolved: Vec<&str> = accesses
+                    .iter()
+                    .filter(|(_, a)| a.unresolved_reads || a.unresolved_writes)
+                    .map(|(name, _)| *name)
+                    .collect();
+                if !unresolved.is_empty() {
+                    println!("  Unresolved slot accesses in: {}", unresolved.join(", "));
+                }
+                println!();
+            }
+        }
+        InspectView::Pause => {
+            for contract in &contracts {
+                println!("{}", format!(" {}", contract.name).bright_cyan().bold());
+                let matrix = PauseAnalysis::analyze_contract(contract);
+                if !matrix.is_pausable() {
+                    println!("  No pause flag or pause guard found");
+                    println!();
+                    continue;
                 }
+                println!("  Pause flags: {}", matrix.flags.join(", "));
+                for line in matrix.to_string().lines() {
+                    println!("  {}", line);
+                }
+                println!();
             }
         }
+    }
 
-        println!();
+    Ok(())
+}
+
+fn cmd_trace(
+    input: PathBuf,
+    function: String,
+    path: Vec<u32>,
+    args: Vec<String>,
+    calldata: Option<String>,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    use colored::*;
+    use std::fs;
+    use thalir_core::block::BlockId;
+    use thalir_emit::TraceEmitter;
+    use thalir_transform::transform_solidity_to_ir;
+
+    let (contract, function) = function
+        .split_once("::")
+        .ok_or_else(|| anyhow::anyhow!("Expected Contract::function, got `{}`", function))?;
+
+    let source = fs::read_to_string(&input)?;
+    let contracts = transform_solidity_to_ir(&source)?;
+
+    let mut emitter = TraceEmitter::new(contracts);
+    for (index, arg) in args.iter().enumerate() {
+        emitter = emitter.with_input(index as u32, TraceEmitter::parse_input(arg)?);
+    }
+    if let Some(calldata) = calldata {
+        emitter = emitter.with_calldata(TraceEmitter::parse_calldata(&calldata)?);
+    }
+    let path: Vec<BlockId> = path.into_iter().map(BlockId).collect();
+    let trace = emitter.trace(contract, function, &path)?;
+
+    if let Some(output_path) = output {
+        fs::write(&output_path, &trace)?;
+        println!(
+            " {} Trace saved to: {}",
+            "SUCCESS:".bright_green().bold(),
+            output_path.display()
+        );
+    } else {
+        print!("{}", trace);
+    }
+
+    Ok(())
+}
+
+/* Compiles one contract to an object file. Without `--contract` the source must define exactly one,
+ * and without `--output` the object is written next to it as `<Contract>.o`. */
+fn cmd_codegen(
+    input: PathBuf,
+    contract: Option<String>,
+    output: Option<PathBuf>,
+    word_abi: thalir_core::codegen::WordAbi,
+) -> Result<()> {
+    use colored::*;
+    use std::fs;
+    use thalir_transform::transform_solidity_to_ir_with_filename;
+
+    let source = fs::read_to_string(&input)?;
+    let contracts = transform_solidity_to_ir_with_filename(&source, input.to_str())?;
+    let names: Vec<&str> = contracts.iter().map(|c| c.name.as_str()).collect();
+    let selected = match &contract {
+        Some(name) => contracts.iter().find(|c| &c.name == name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "No contract `{}` in {} ({})",
+                name,
+                input.display(),
+                names.join(", ")
+            )
+        })?,
+        None if contracts.len() == 1 => &contracts[0],
+        None => {
+            return Err(anyhow::anyhow!(
+                "{} defines {} contracts; pick one with --contract ({})",
+                input.display(),
+                contracts.len(),
+                names.join(", ")
+            ))
+        }
+    };
+
+    let compiled = thalir_core::codegen::ModuleBuilder::new()?
+        .with_word_abi(word_abi)
+        .compile_contract(selected)?;
+    let output_path =
+        output.unwrap_or_else(|| input.with_file_name(format!("{}.o", selected.name)));
+    fs::write(&output_path, &compiled.object)?;
+
+    println!(
+        " {} {} compiled to: {} ({} bytes)",
+        "SUCCESS:".bright_green().bold(),
+        selected.name,
+        output_path.display(),
+        compiled.object.len()
+    );
+    println!("  Defines: {}", compiled.functions.join(", "));
+    if !compiled.imports.is_empty() {
+        println!("  Imports: {}", compiled.imports.join(", "));
+    }
+    Ok(())
+}
+
+fn cmd_interface(
+    input: PathBuf,
+    contract: Option<String>,
+    abi: bool,
+    output: Option<PathBuf>,
+    check: Option<PathBuf>,
+) -> Result<()> {
+    use colored::*;
+    use std::collections::HashSet;
+    use std::fs;
+    use thalir_emit::InterfaceEmitter;
+    use thalir_transform::transform_solidity_to_ir_with_filename;
+
+    let source = fs::read_to_string(&input)?;
+    let mut contracts = if input.extension().is_some_and(|ext| ext == "thalir") {
+        thalir_parser::reconstruct(&source)?.contracts
+    } else {
+        transform_solidity_to_ir_with_filename(&source, input.to_str())?
+    };
+    if let Some(name) = &contract {
+        let names: Vec<String> = contracts.iter().map(|c| c.name.clone()).collect();
+        contracts.retain(|c| &c.name == name);
+        if contracts.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No contract `{}` in {} ({})",
+                name,
+                input.display(),
+                names.join(", ")
+            ));
+        }
+    }
+
+    let emitter = InterfaceEmitter::new(contracts);
+    let text = if abi {
+        format!("{}\n", serde_json::to_string_pretty(&emitter.abi())?)
+    } else {
+        emitter.emit_to_string()
+    };
+
+    /* A line diff is enough here: the interface lists one entry per line, selector included. */
+    if let Some(path) = check {
+        let expected = fs::read_to_string(&path)?;
+        if expected == text {
+            println!(
+                " {} {} matches {}",
+                "SUCCESS:".bright_green().bold(),
+                input.display(),
+                path.display()
+            );
+            return Ok(());
+        }
+        let before: HashSet<&str> = expected.lines().collect();
+        let after: HashSet<&str> = text.lines().collect();
+        for line in expected.lines().filter(|line| !after.contains(line)) {
+            println!("{}", format!("- {}", line).red());
+        }
+        for line in text.lines().filter(|line| !before.contains(line)) {
+            println!("{}", format!("+ {}", line).green());
+        }
+        return Err(anyhow::anyhow!(
+            "The external surface of {} differs from {}",
+            input.display(),
+            path.display()
+        ));
+    }
+
+    match output {
+        Some(path) => fs::write(path, text)?,
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+fn cmd_lint(
+    input: PathBuf,
+    config: thalir_core::analysis::LintConfig,
+    deny_warnings: bool,
+    json: bool,
+) -> Result<()> {
+    use colored::*;
+    use std::fs;
+    use thalir_core::analysis::{LintLevel, Linter};
+    use thalir_transform::transform_solidity_to_ir_with_filename;
+
+    let source = fs::read_to_string(&input)?;
+    let contracts = transform_solidity_to_ir_with_filename(&source, input.to_str())?;
+    let linter = Linter::new(config);
+    let lints: Vec<_> = contracts
+        .iter()
+        .flat_map(|contract| linter.check_contract(contract))
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&lints)?);
+    } else {
+        for lint in &lints {
+            let label = match lint.level {
+                LintLevel::Deny => "DENY:".red().bold(),
+                LintLevel::Warn => "WARN:".yellow().bold(),
+                LintLevel::Allow => continue,
+            };
+            println!("{} {}", label, lint);
+        }
+    }
+
+    let denied = lints.iter().filter(|l| l.level == LintLevel::Deny).count();
+    let warned = lints.iter().filter(|l| l.level == LintLevel::Warn).count();
+    if !json {
+        println!(
+            " {} deny, {} warn across {} contract{}",
+            denied,
+            warned,
+            contracts.len(),
+            if contracts.len() == 1 { "" } else { "s" }
+        );
+    }
+
+    let failing = denied + if deny_warnings { warned } else { 0 };
+    if failing > 0 {
+        return Err(anyhow::anyhow!(
+            "{} lint{} at a failing level",
+            failing,
+            if failing == 1 { "" } else { "s" }
+        ));
+    }
+    Ok(())
+}
+
+/* Merges review sidecars in the order given and, with `--against`, checks every note still
+ * resolves in the current IR of those sources. */
+fn cmd_review(notes: Vec<PathBuf>, inputs: Vec<PathBuf>, output: Option<PathBuf>) -> Result<()> {
+    use colored::*;
+    use std::fs;
+    use thalir_core::review::{self, ReviewFile, ReviewProblem};
+    use thalir_transform::transform_solidity_to_ir_with_filename;
+
+    let mut merged = ReviewFile::default();
+    for path in &notes {
+        let added = merged.merge(ReviewFile::load(path)?);
+        tracing::info!(path = %path.display(), added, "merged review notes");
+    }
+
+    let mut contracts = Vec::new();
+    for file in collect_solidity_files(&inputs)? {
+        let source = fs::read_to_string(&file)?;
+        contracts.extend(transform_solidity_to_ir_with_filename(
+            &source,
+            file.to_str(),
+        )?);
+    }
+    for file in collect_files(&inputs, "thalir")? {
+        contracts.extend(thalir_parser::reconstruct(&fs::read_to_string(&file)?)?.contracts);
+    }
+
+    let targets = review::targets(&contracts);
+    for note in &merged.notes {
+        let place = targets
+            .get(&note.target)
+            .or(note.label.as_ref())
+            .map_or_else(String::new, |label| format!(" {}", label));
+        println!("  {}{} {}", note.target.bright_black(), place, note);
+    }
+
+    let problems = if inputs.is_empty() {
+        Vec::new()
+    } else {
+        merged.validate(&contracts)
+    };
+    for problem in &problems {
+        let label = match problem {
+            ReviewProblem::Unresolved { .. } => "STALE:".red().bold(),
+            ReviewProblem::Conflict { .. } => "CONFLICT:".yellow().bold(),
+        };
+        println!("{} {}", label, problem);
+    }
+
+    if let Some(path) = &output {
+        merged.save(path)?;
+        println!(
+            "{}",
+            format!(
+                " Merged {} note(s) from {} file(s) into {}",
+                merged.notes.len(),
+                notes.len(),
+                path.display()
+            )
+            .bright_cyan()
+            .bold()
+        );
+    }
+
+    if !problems.is_empty() {
+        return Err(anyhow::anyhow!(
+            "{} review problem{}",
+            problems.len(),
+            if problems.len() == 1 { "" } else { "s" }
+        ));
+    }
+    Ok(())
+}
+
+/* Every hardcoded address in the sources with where it is used. Labels from `--labels` win over
+ * the project's registry and inline labels. */
+fn cmd_addresses(
+    inputs: Vec<PathBuf>,
+    labels: Option<PathBuf>,
+    json: bool,
+    config: &thalir::Config,
+) -> Result<()> {
+    use colored::*;
+    use std::fs;
+    use thalir_core::analysis::{AddressBook, AddressLabels};
+    use thalir_transform::transform_solidity_to_ir_with_filename;
+
+    let files = collect_solidity_files(&inputs)?;
+    let mut contracts = Vec::new();
+    for file in &files {
+        let source = fs::read_to_string(file)?;
+        contracts.extend(transform_solidity_to_ir_with_filename(
+            &source,
+            file.to_str(),
+        )?);
+    }
+
+    let mut registry = config.addresses.address_labels()?;
+    if let Some(path) = &labels {
+        registry.extend(AddressLabels::load(path)?);
     }
+    let mut book = AddressBook::collect(&contracts);
+    book.label(&registry);
 
+    if json {
+        println!("{}", serde_json::to_string_pretty(&book)?);
+        return Ok(());
+    }
+    for entry in &book.entries {
+        let label = entry
+            .label
+            .as_ref()
+            .map_or_else(String::new, |label| format!(" {}", label.bright_cyan()));
+        println!("  {}{}", entry.address, label);
+        for constant in &entry.constants {
+            println!("{}", format!("      constant {}", constant).bright_black());
+        }
+        for used in &entry.uses {
+            let via = used
+                .constant
+                .as_ref()
+                .map_or_else(String::new, |constant| format!(" via {}", constant));
+            println!(
+                "      {}::{} {}[{}]{}",
+                used.contract, used.function, used.location.block, used.location.index, via
+            );
+        }
+    }
+    println!(
+        " {} hardcoded address(es) across {} file(s)",
+        book.len(),
+        files.len()
+    );
     Ok(())
 }
+
+#[cfg(feature = "bench")]
+fn cmd_bench(
+    fixtures: Vec<String>,
+    stages: Vec<BenchStage>,
+    iterations: u32,
+    baseline: Option<PathBuf>,
+    max_regression: f64,
+    save_baseline: Option<PathBuf>,
+    json: bool,
+) -> Result<()> {
+    use colored::*;
+    use std::fs;
+    use thalir::bench::{self, Measurement, Stage, Workload};
+
+    let selected: Vec<&'static bench::Fixture> = if fixtures.is_empty() {
+        bench::FIXTURES.iter().collect()
+    } else {
+        fixtures
+            .iter()
+            .map(|name| {
+                bench::fixture(name).ok_or_else(|| {
+                    let known: Vec<_> = bench::FIXTURES.iter().map(|f| f.name).collect();
+                    anyhow::anyhow!(
+                        "Unknown fixture '{}' (expected one of: {})",
+                        name,
+                        known.join(", ")
+                    )
+                })
+            })
+            .collect::<Result<_>>()?
+    };
+    let stages: Vec<Stage> = if stages.is_empty() {
+        Stage::ALL.to_vec()
+    } else {
+        stages.into_iter().map(Stage::from).collect()
+    };
+
+    let mut measurements = Vec::new();
+    for fixture in selected {
+        let workload = Workload::prepare(fixture)?;
+        for &stage in &stages {
+            measurements.push(bench::measure(&workload, stage, iterations)?);
+        }
+    }
+
+    if let Some(path) = &save_baseline {
+        fs::write(path, serde_json::to_string_pretty(&measurements)?)?;
+    }
+
+    let regressions = match &baseline {
+        Some(path) => {
+            let saved: Vec<Measurement> = serde_json::from_str(&fs::read_to_string(path)?)?;
+            bench::regressions(&saved, &measurements, max_regression)
+        }
+        None => Vec::new(),
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&measurements)?);
+    } else {
+        println!(
+            "{}",
+            format!(
+                " Benchmarked {} stage(s) x {} iteration(s)",
+                measurements.len(),
+                iterations
+            )
+            .bright_cyan()
+            .bold()
+        );
+        for m in &measurements {
+            println!(
+                "  {:<20} {:<10} mean {:>12?}  min {:>12?}",
+                m.fixture,
+                m.stage.name(),
+                m.mean(),
+                std::time::Duration::from_nanos(m.min_ns as u64)
+            );
+        }
+        if let Some(path) = &save_baseline {
+            println!("  Baseline saved to {}", path.display());
+        }
+    }
+
+    if regressions.is_empty() {
+        return Ok(());
+    }
+
+    for r in &regressions {
+        eprintln!(
+            "{} {} {} is {:.1}% slower than baseline",
+            "REGRESSION:".red().bold(),
+            r.fixture,
+            r.stage.name(),
+            r.slowdown_percent()
+        );
+    }
+    Err(anyhow::anyhow!(
+        "{} benchmark(s) regressed by more than {:.1}%",
+        regressions.len(),
+        max_regression
+    ))
+}
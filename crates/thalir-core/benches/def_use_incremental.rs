@@ -0,0 +1,88 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use thalir_core::{
+    analysis::DefUseChains,
+    builder::IRBuilder,
+    cursor::FuncCursor,
+    function::Function,
+    instructions::Instruction,
+    types::Type,
+    values::{TempId, Value},
+    IRCursor, IRCursorMut,
+};
+
+const EDITS: usize = 200;
+
+fn build_function(size: usize) -> (Function, Value) {
+    let mut builder = IRBuilder::new();
+    let mut contract = builder.contract("Bench");
+    let mut func = contract.function("chain");
+    func.param("seed", Type::Uint(256));
+    let seed = func.get_param(0);
+    let mut entry = func.entry_block();
+    let mut acc = seed.clone();
+    for _ in 0..size {
+        acc = entry.add(acc, seed.clone(), Type::Uint(256));
+    }
+    entry.return_value(acc).unwrap();
+    (func.build().unwrap(), seed)
+}
+
+fn edit(index: usize, seed: &Value) -> Instruction {
+    Instruction::Mul {
+        result: Value::Temp(TempId(1_000_000 + index as u32)),
+        left: seed.clone(),
+        right: seed.clone(),
+        ty: Type::Uint(256),
+    }
+}
+
+fn rebuild_per_edit(mut function: Function, seed: &Value) {
+    let entry = function.body.entry_block;
+    for i in 0..EDITS {
+        {
+            let mut cursor = FuncCursor::new(&mut function);
+            cursor.goto_inst(entry, i * 3);
+            cursor.insert_inst(edit(i, seed)).unwrap();
+        }
+        black_box(DefUseChains::build(&function));
+    }
+}
+
+fn incremental_updates(mut function: Function, seed: &Value) {
+    let entry = function.body.entry_block;
+    let mut chains = DefUseChains::build(&function);
+    {
+        let mut cursor = FuncCursor::new(&mut function).with_listener(&mut chains);
+        for i in 0..EDITS {
+            cursor.goto_inst(entry, i * 3);
+            cursor.insert_inst(edit(i, seed)).unwrap();
+        }
+    }
+    black_box(&chains);
+}
+
+fn def_use_edits(c: &mut Criterion) {
+    let mut group = c.benchmark_group("def_use_edits");
+    group.sample_size(10);
+    for size in [1_000, 5_000, 20_000] {
+        let (function, seed) = build_function(size);
+        group.bench_with_input(BenchmarkId::new("rebuild", size), &size, |b, _| {
+            b.iter_batched(
+                || function.clone(),
+                |function| rebuild_per_edit(function, &seed),
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("incremental", size), &size, |b, _| {
+            b.iter_batched(
+                || function.clone(),
+                |function| incremental_updates(function, &seed),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, def_use_edits);
+criterion_main!(benches);
@@ -62,6 +62,8 @@ fn create_novel_amm_contract() -> Contract {
                     var_type: Type::Uint(256),
                     name: "liquidityPoolReserves".to_string(),
                     packed_with: Vec::new(),
+                    location: None,
+                    is_private: false,
                 },
                 StorageSlot {
                     slot: BigUint::from(1u32),
@@ -69,6 +71,8 @@ fn create_novel_amm_contract() -> Contract {
                     var_type: Type::Address,
                     name: "protocolOwner".to_string(),
                     packed_with: Vec::new(),
+                    location: None,
+                    is_private: false,
                 },
                 StorageSlot {
                     slot: BigUint::from(2u32),
@@ -76,11 +80,14 @@ fn create_novel_amm_contract() -> Contract {
                     var_type: Type::Uint(256),
                     name: "customBondingParameter".to_string(),
                     packed_with: Vec::new(),
+                    location: None,
+                    is_private: false,
                 },
             ],
             mappings: Vec::new(),
             arrays: Vec::new(),
             structs: Vec::new(),
+            inherited: Vec::new(),
         },
         events: Vec::new(),
         modifiers: Vec::new(),
@@ -248,7 +255,8 @@ fn demonstrate_full_workflow(mut contract: Contract) -> anyhow::Result<()> {
     let pass = manager
         .get_pass::<ObfuscationPass>()
         .expect("ObfuscationPass should be registered");
-    let mapping = pass.export_mapping();
+    let mut mapping = pass.export_mapping();
+    mapping.sign(b"example-signing-key");
 
     println!("  Mapping contains {} entries", mapping.mapping.len());
     println!("  Sample mappings:");
@@ -270,7 +278,8 @@ fn demonstrate_full_workflow(mut contract: Contract) -> anyhow::Result<()> {
     }
 
     println!("\nStep 5: De-obfuscating vulnerability report...");
-    let mapper = VulnerabilityMapper::from_mapping(mapping.clone());
+    let mapper =
+        VulnerabilityMapper::from_mapping_with_key(mapping.clone(), b"example-signing-key")?;
     let original_report = mapper.deobfuscate_report(&obfuscated_report);
 
     println!("  Original Report (with your actual names):");
@@ -0,0 +1,497 @@
+use crate::hash;
+use crate::types::{Type, TypeRegistry};
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::{One, Zero};
+use std::fmt;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum AbiError {
+    #[error("invalid ABI type `{0}`")]
+    InvalidType(String),
+    #[error("value does not match ABI type `{0}`")]
+    TypeMismatch(AbiType),
+    #[error("ABI data too short: {needed} bytes needed at offset {offset}")]
+    OutOfBounds { offset: usize, needed: usize },
+    #[error("malformed `{0}` in ABI data")]
+    Malformed(AbiType),
+}
+
+/* The ABI's own view of a type. Kept apart from `Type` because the IR folds `bytes` into
+ * `bytes32` and names structs by id, while the encoding needs dynamic bytes and field lists. */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AbiType {
+    Uint(u16),
+    Int(u16),
+    Address,
+    Bool,
+    FixedBytes(u8),
+    Bytes,
+    String,
+    Array(Box<AbiType>),
+    FixedArray(Box<AbiType>, usize),
+    Tuple(Vec<AbiType>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiValue {
+    Uint(BigUint),
+    Int(BigInt),
+    Address([u8; 20]),
+    Bool(bool),
+    FixedBytes(Vec<u8>),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<AbiValue>),
+    Tuple(Vec<AbiValue>),
+}
+
+impl AbiType {
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            AbiType::Bytes | AbiType::String | AbiType::Array(_) => true,
+            AbiType::FixedArray(elem, _) => elem.is_dynamic(),
+            AbiType::Tuple(fields) => fields.iter().any(AbiType::is_dynamic),
+            _ => false,
+        }
+    }
+
+    /* Bytes this type occupies in the head of an enclosing tuple. */
+    pub fn head_size(&self) -> usize {
+        if self.is_dynamic() {
+            return 32;
+        }
+        match self {
+            AbiType::FixedArray(elem, len) => elem.head_size() * len,
+            AbiType::Tuple(fields) => fields.iter().map(AbiType::head_size).sum(),
+            _ => 32,
+        }
+    }
+
+    /* Structs are expanded through the registry; mappings, function types and storage pointers
+     * have no ABI representation. */
+    pub fn from_ir(ty: &Type, registry: &TypeRegistry) -> Option<Self> {
+        Some(match ty {
+            Type::Bool => AbiType::Bool,
+            Type::Uint(bits) => AbiType::Uint(*bits),
+            Type::Int(bits) => AbiType::Int(*bits),
+            Type::Address | Type::Contract(_) => AbiType::Address,
+            Type::Bytes(len) => AbiType::FixedBytes(*len),
+            Type::Bytes4 => AbiType::FixedBytes(4),
+            Type::Bytes20 => AbiType::FixedBytes(20),
+            Type::Bytes32 => AbiType::FixedBytes(32),
+            Type::String => AbiType::String,
+            Type::Enum(_) => AbiType::Uint(8),
+            Type::Array(elem, None) => AbiType::Array(Box::new(Self::from_ir(elem, registry)?)),
+            Type::Array(elem, Some(len)) => {
+                AbiType::FixedArray(Box::new(Self::from_ir(elem, registry)?), *len)
+            }
+            Type::Struct(id) => AbiType::Tuple(
+                registry
+                    .structs
+                    .get(id)?
+                    .fields
+                    .iter()
+                    .map(|field| Self::from_ir(&field.field_type, registry))
+                    .collect::<Option<_>>()?,
+            ),
+            Type::MemoryPointer(inner) | Type::CalldataPointer(inner) => {
+                Self::from_ir(inner, registry)?
+            }
+            _ => return None,
+        })
+    }
+
+    /* Parses canonical type strings as they appear in signatures, e.g. `(address,uint8[])[2]`.
+     * `uint`, `int` and `byte` are accepted as the usual aliases. */
+    pub fn parse(text: &str) -> Result<Self, AbiError> {
+        let text = text.trim();
+        let invalid = || AbiError::InvalidType(text.to_string());
+
+        if let Some(prefix) = text.strip_suffix(']') {
+            let open = prefix.rfind('[').ok_or_else(invalid)?;
+            let elem = Box::new(Self::parse(&prefix[..open])?);
+            let len = &prefix[open + 1..];
+            return if len.is_empty() {
+                Ok(AbiType::Array(elem))
+            } else {
+                let len = len.parse().map_err(|_| invalid())?;
+                Ok(AbiType::FixedArray(elem, len))
+            };
+        }
+
+        if let Some(inner) = text.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+            let mut fields = Vec::new();
+            let mut depth = 0usize;
+            let mut start = 0;
+            for (i, c) in inner.char_indices() {
+                match c {
+                    '(' | '[' => depth += 1,
+                    ')' | ']' => depth = depth.checked_sub(1).ok_or_else(invalid)?,
+                    ',' if depth == 0 => {
+                        fields.push(Self::parse(&inner[start..i])?);
+                        start = i + 1;
+                    }
+                    _ => {}
+                }
+            }
+            if !inner.trim().is_empty() {
+                fields.push(Self::parse(&inner[start..])?);
+            }
+            return Ok(AbiType::Tuple(fields));
+        }
+
+        let sized = |digits: &str, max: u16, step: u16| -> Result<u16, AbiError> {
+            match digits.parse::<u16>() {
+                Ok(n) if n > 0 && n <= max && n % step == 0 => Ok(n),
+                _ => Err(invalid()),
+            }
+        };
+
+        match text {
+            "address" => Ok(AbiType::Address),
+            "bool" => Ok(AbiType::Bool),
+            "bytes" => Ok(AbiType::Bytes),
+            "string" => Ok(AbiType::String),
+            "uint" => Ok(AbiType::Uint(256)),
+            "int" => Ok(AbiType::Int(256)),
+            "byte" => Ok(AbiType::FixedBytes(1)),
+            _ => {
+                if let Some(bits) = text.strip_prefix("uint") {
+                    Ok(AbiType::Uint(sized(bits, 256, 8)?))
+                } else if let Some(bits) = text.strip_prefix("int") {
+                    Ok(AbiType::Int(sized(bits, 256, 8)?))
+                } else if let Some(len) = text.strip_prefix("bytes") {
+                    Ok(AbiType::FixedBytes(sized(len, 32, 1)? as u8))
+                } else {
+                    Err(invalid())
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for AbiType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbiType::Uint(bits) => write!(f, "uint{}", bits),
+            AbiType::Int(bits) => write!(f, "int{}", bits),
+            AbiType::Address => write!(f, "address"),
+            AbiType::Bool => write!(f, "bool"),
+            AbiType::FixedBytes(len) => write!(f, "bytes{}", len),
+            AbiType::Bytes => write!(f, "bytes"),
+            AbiType::String => write!(f, "string"),
+            AbiType::Array(elem) => write!(f, "{}[]", elem),
+            AbiType::FixedArray(elem, len) => write!(f, "{}[{}]", elem, len),
+            AbiType::Tuple(fields) => {
+                write!(f, "(")?;
+                for (i, field) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", field)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+pub fn signature(name: &str, params: &[AbiType]) -> String {
+    format!("{}{}", name, AbiType::Tuple(params.to_vec()))
+}
+
+/* Calldata for `name(params)`: the four-byte selector followed by the encoded arguments. */
+pub fn encode_call(name: &str, params: &[AbiType], args: &[AbiValue]) -> Result<Vec<u8>, AbiError> {
+    let mut data = hash::selector(&signature(name, params)).to_vec();
+    data.extend(encode(params, args)?);
+    Ok(data)
+}
+
+/* Encodes `values` as the tuple `types`, the layout used for call arguments, return data and
+ * `abi.encode`. */
+pub fn encode(types: &[AbiType], values: &[AbiValue]) -> Result<Vec<u8>, AbiError> {
+    if types.len() != values.len() {
+        return Err(AbiError::TypeMismatch(AbiType::Tuple(types.to_vec())));
+    }
+    let head_size: usize = types.iter().map(AbiType::head_size).sum();
+    let mut head = Vec::with_capacity(head_size);
+    let mut tail = Vec::new();
+    for (ty, value) in types.iter().zip(values) {
+        if ty.is_dynamic() {
+            head.extend(word_from_usize(head_size + tail.len()));
+            tail.extend(encode_value(ty, value)?);
+        } else {
+            head.extend(encode_value(ty, value)?);
+        }
+    }
+    head.extend(tail);
+    Ok(head)
+}
+
+fn encode_value(ty: &AbiType, value: &AbiValue) -> Result<Vec<u8>, AbiError> {
+    let mismatch = || AbiError::TypeMismatch(ty.clone());
+    match (ty, value) {
+        (AbiType::Uint(bits), AbiValue::Uint(n)) if n.bits() <= u64::from(*bits) => {
+            Ok(word_from_biguint(n))
+        }
+        (AbiType::Int(bits), AbiValue::Int(n)) if fits_signed(n, *bits) => {
+            let unsigned = if n.sign() == Sign::Minus {
+                ((BigInt::one() << 256) + n).magnitude().clone()
+            } else {
+                n.magnitude().clone()
+            };
+            Ok(word_from_biguint(&unsigned))
+        }
+        (AbiType::Address, AbiValue::Address(addr)) => {
+            let mut word = vec![0u8; 12];
+            word.extend_from_slice(addr);
+            Ok(word)
+        }
+        (AbiType::Bool, AbiValue::Bool(b)) => Ok(word_from_usize(*b as usize)),
+        (AbiType::FixedBytes(len), AbiValue::FixedBytes(bytes)) if bytes.len() == *len as usize => {
+            Ok(padded(bytes))
+        }
+        (AbiType::Bytes, AbiValue::Bytes(bytes)) => {
+            let mut out = word_from_usize(bytes.len());
+            out.extend(padded(bytes));
+            Ok(out)
+        }
+        (AbiType::String, AbiValue::String(s)) => {
+            let mut out = word_from_usize(s.len());
+            out.extend(padded(s.as_bytes()));
+            Ok(out)
+        }
+        (AbiType::Array(elem), AbiValue::Array(items)) => {
+            let mut out = word_from_usize(items.len());
+            out.extend(encode(&vec![(**elem).clone(); items.len()], items)?);
+            Ok(out)
+        }
+        (AbiType::FixedArray(elem, len), AbiValue::Array(items)) if items.len() == *len => {
+            encode(&vec![(**elem).clone(); *len], items)
+        }
+        (AbiType::Tuple(fields), AbiValue::Tuple(items)) => encode(fields, items),
+        _ => Err(mismatch()),
+    }
+}
+
+/* Decodes `data` as the tuple `types`. Offsets, lengths and padding are checked the way the
+ * Solidity decoder checks them, so malformed return data fails here rather than decoding to
+ * something the EVM would have reverted on. */
+pub fn decode(types: &[AbiType], data: &[u8]) -> Result<Vec<AbiValue>, AbiError> {
+    decode_tuple(types, data, 0)
+}
+
+fn decode_tuple(types: &[AbiType], data: &[u8], base: usize) -> Result<Vec<AbiValue>, AbiError> {
+    let mut values = Vec::with_capacity(types.len());
+    let mut offset = base;
+    for ty in types {
+        let value = if ty.is_dynamic() {
+            let pointer = read_usize(ty, data, offset)?;
+            let at = base
+                .checked_add(pointer)
+                .ok_or_else(|| AbiError::Malformed(ty.clone()))?;
+            decode_value(ty, data, at)?
+        } else {
+            decode_value(ty, data, offset)?
+        };
+        values.push(value);
+        offset += ty.head_size();
+    }
+    Ok(values)
+}
+
+fn decode_value(ty: &AbiType, data: &[u8], at: usize) -> Result<AbiValue, AbiError> {
+    let malformed = || AbiError::Malformed(ty.clone());
+    match ty {
+        AbiType::Uint(bits) => {
+            let n = BigUint::from_bytes_be(read(data, at, 32)?);
+            if n.bits() > u64::from(*bits) {
+                return Err(malformed());
+            }
+            Ok(AbiValue::Uint(n))
+        }
+        AbiType::Int(bits) => {
+            let raw = BigUint::from_bytes_be(read(data, at, 32)?);
+            let n = if raw.bit(255) {
+                BigInt::from(raw) - (BigInt::one() << 256)
+            } else {
+                BigInt::from(raw)
+            };
+            if !fits_signed(&n, *bits) {
+                return Err(malformed());
+            }
+            Ok(AbiValue::Int(n))
+        }
+        AbiType::Address => {
+            let word = read(data, at, 32)?;
+            if word[..12].iter().any(|b| *b != 0) {
+                return Err(malformed());
+            }
+            let mut addr = [0u8; 20];
+            addr.copy_from_slice(&word[12..]);
+            Ok(AbiValue::Address(addr))
+        }
+        AbiType::Bool => match BigUint::from_bytes_be(read(data, at, 32)?) {
+            n if n.is_zero() => Ok(AbiValue::Bool(false)),
+            n if n.is_one() => Ok(AbiValue::Bool(true)),
+            _ => Err(malformed()),
+        },
+        AbiType::FixedBytes(len) => {
+            let word = read(data, at, 32)?;
+            let len = *len as usize;
+            if word[len..].iter().any(|b| *b != 0) {
+                return Err(malformed());
+            }
+            Ok(AbiValue::FixedBytes(word[..len].to_vec()))
+        }
+        AbiType::Bytes | AbiType::String => {
+            let len = read_usize(ty, data, at)?;
+            let start = at.checked_add(32).ok_or_else(malformed)?;
+            let bytes = read(data, start, len)?.to_vec();
+            if *ty == AbiType::Bytes {
+                Ok(AbiValue::Bytes(bytes))
+            } else {
+                String::from_utf8(bytes)
+                    .map(AbiValue::String)
+                    .map_err(|_| malformed())
+            }
+        }
+        AbiType::Array(elem) => {
+            let len = read_usize(ty, data, at)?;
+            /* Every element takes at least one head word; rejecting early keeps a hostile length
+             * from turning into a huge allocation. */
+            let needed = len.checked_mul(32).ok_or_else(malformed)?;
+            let start = at.checked_add(32).ok_or_else(malformed)?;
+            read(data, start, needed)?;
+            Ok(AbiValue::Array(decode_tuple(
+                &vec![(**elem).clone(); len],
+                data,
+                start,
+            )?))
+        }
+        AbiType::FixedArray(elem, len) => Ok(AbiValue::Array(decode_tuple(
+            &vec![(**elem).clone(); *len],
+            data,
+            at,
+        )?)),
+        AbiType::Tuple(fields) => Ok(AbiValue::Tuple(decode_tuple(fields, data, at)?)),
+    }
+}
+
+fn read(data: &[u8], offset: usize, needed: usize) -> Result<&[u8], AbiError> {
+    offset
+        .checked_add(needed)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(AbiError::OutOfBounds { offset, needed })
+}
+
+fn read_usize(ty: &AbiType, data: &[u8], offset: usize) -> Result<usize, AbiError> {
+    let word = read(data, offset, 32)?;
+    if word[..24].iter().any(|b| *b != 0) {
+        return Err(AbiError::Malformed(ty.clone()));
+    }
+    let mut low = [0u8; 8];
+    low.copy_from_slice(&word[24..]);
+    usize::try_from(u64::from_be_bytes(low)).map_err(|_| AbiError::Malformed(ty.clone()))
+}
+
+fn fits_signed(n: &BigInt, bits: u16) -> bool {
+    let limit = BigInt::one() << (bits - 1);
+    *n >= -limit.clone() && *n < limit
+}
+
+fn word_from_biguint(n: &BigUint) -> Vec<u8> {
+    let bytes = n.to_bytes_be();
+    let mut word = vec![0u8; 32 - bytes.len()];
+    word.extend(bytes);
+    word
+}
+
+fn word_from_usize(n: usize) -> Vec<u8> {
+    word_from_biguint(&BigUint::from(n))
+}
+
+fn padded(bytes: &[u8]) -> Vec<u8> {
+    let mut out = bytes.to_vec();
+    out.resize(bytes.len().div_ceil(32) * 32, 0);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(hex_words: &[&str]) -> Vec<u8> {
+        hex_words
+            .iter()
+            .flat_map(|w| {
+                let w = format!("{:0>64}", w);
+                hex::decode(w).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_solidity_reference_encoding() {
+        let params = [
+            AbiType::Bytes,
+            AbiType::Bool,
+            AbiType::parse("uint[]").unwrap(),
+        ];
+        let args = [
+            AbiValue::Bytes(b"dave".to_vec()),
+            AbiValue::Bool(true),
+            AbiValue::Array((1u32..=3).map(|n| AbiValue::Uint(n.into())).collect()),
+        ];
+        let calldata = encode_call("sam", &params, &args).unwrap();
+
+        assert_eq!(signature("sam", &params), "sam(bytes,bool,uint256[])");
+        assert_eq!(calldata[..4], [0xa5, 0x64, 0x3b, 0xf2]);
+        let mut expected = words(&["60", "1", "a0", "4"]);
+        expected.extend(padded(b"dave"));
+        expected.extend(words(&["3", "1", "2", "3"]));
+        assert_eq!(calldata[4..], expected[..]);
+        assert_eq!(decode(&params, &calldata[4..]).unwrap(), args);
+    }
+
+    #[test]
+    fn test_round_trips_nested_dynamic_structs() {
+        let ty = AbiType::parse("(address,int16,string,uint8[2])[]").unwrap();
+        assert_eq!(ty.to_string(), "(address,int16,string,uint8[2])[]");
+
+        let entry = |tag: u8, name: &str| {
+            AbiValue::Tuple(vec![
+                AbiValue::Address([tag; 20]),
+                AbiValue::Int(BigInt::from(-(tag as i32))),
+                AbiValue::String(name.to_string()),
+                AbiValue::Array(vec![AbiValue::Uint(tag.into()), AbiValue::Uint(0u8.into())]),
+            ])
+        };
+        let value = AbiValue::Array(vec![entry(1, "alice"), entry(2, "")]);
+        let data = encode(std::slice::from_ref(&ty), std::slice::from_ref(&value)).unwrap();
+        assert_eq!(
+            decode(std::slice::from_ref(&ty), &data).unwrap(),
+            vec![value]
+        );
+
+        assert!(matches!(
+            decode(std::slice::from_ref(&ty), &data[..data.len() - 32]),
+            Err(AbiError::OutOfBounds { .. })
+        ));
+        assert!(encode(&[AbiType::Uint(8)], &[AbiValue::Uint(256u32.into())]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_pointers_past_the_end_of_the_data() {
+        for ty in [
+            AbiType::Bytes,
+            AbiType::String,
+            AbiType::parse("uint8[]").unwrap(),
+        ] {
+            let mut data = vec![0u8; 32];
+            data[24..].copy_from_slice(&(usize::MAX as u64).to_be_bytes());
+            assert!(decode(std::slice::from_ref(&ty), &data).is_err());
+        }
+    }
+}
@@ -0,0 +1,269 @@
+/*! Every hardcoded address in a workspace, with where each one is used.
+ *
+ * Addresses show up as literals in function bodies and as the values of constants. The transform
+ * inlines a constant's value where it is read, so a literal equal to one of the contract's
+ * constants is attributed to that constant; IR that still reads a constant from its slot counts
+ * the load as a use. Labels for well-known addresses come from a registry the user supplies;
+ * nothing is looked up on-chain.
+ */
+
+use crate::{
+    contract::Contract,
+    hash::hex,
+    instructions::{Instruction, StorageKey},
+    metadata::InstructionLocation,
+    values::{Constant, Value},
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressUse {
+    pub contract: String,
+    pub function: String,
+    pub location: InstructionLocation,
+    /* The constant the address was read through, when it was not written inline. */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constant: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressEntry {
+    /* Lowercase `0x`-prefixed hex. */
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /* Constants holding the address, as `Contract.NAME`. */
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constants: Vec<String>,
+    pub uses: Vec<AddressUse>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressBook {
+    pub entries: Vec<AddressEntry>,
+}
+
+impl AddressBook {
+    /* Entries sorted by address. The zero address is left out: it marks an unset value rather than
+     * a counterparty. */
+    pub fn collect(contracts: &[Contract]) -> Self {
+        let mut book: BTreeMap<String, AddressEntry> = BTreeMap::new();
+        for contract in contracts {
+            let constants: HashMap<&str, [u8; 20]> = contract
+                .constants
+                .iter()
+                .filter_map(|constant| match constant.value {
+                    Constant::Address(address) if !is_zero(&address) => {
+                        Some((constant.name.as_str(), address))
+                    }
+                    _ => None,
+                })
+                .collect();
+            for constant in &contract.constants {
+                if let Some(address) = constants.get(constant.name.as_str()) {
+                    book_entry(&mut book, address)
+                        .constants
+                        .push(format!("{}.{}", contract.name, constant.name));
+                }
+            }
+            let holder = |address: &[u8; 20]| {
+                contract
+                    .constants
+                    .iter()
+                    .find(|constant| constant.value == Constant::Address(*address))
+                    .map(|constant| constant.name.clone())
+            };
+
+            for (function_name, function) in &contract.functions {
+                for (&block, body) in &function.body.blocks {
+                    let mut found: Vec<(usize, [u8; 20], Option<String>)> = Vec::new();
+                    for (index, inst) in body.instructions.iter().enumerate() {
+                        for value in inst.operands() {
+                            if let Value::Constant(Constant::Address(address)) = value {
+                                found.push((index, *address, holder(address)));
+                            }
+                        }
+                        if let Instruction::StorageLoad {
+                            key: StorageKey::Slot(slot),
+                            ..
+                        } = inst
+                        {
+                            let constant = contract
+                                .storage_layout
+                                .slots
+                                .iter()
+                                .find(|var| &var.slot == slot)
+                                .and_then(|var| constants.get_key_value(var.name.as_str()));
+                            if let Some((name, address)) = constant {
+                                found.push((index, *address, Some(name.to_string())));
+                            }
+                        }
+                    }
+                    for value in body.terminator.values() {
+                        if let Value::Constant(Constant::Address(address)) = value {
+                            found.push((body.instructions.len(), *address, holder(address)));
+                        }
+                    }
+                    for (index, address, constant) in found {
+                        if is_zero(&address) {
+                            continue;
+                        }
+                        book_entry(&mut book, &address).uses.push(AddressUse {
+                            contract: contract.name.clone(),
+                            function: function_name.clone(),
+                            location: InstructionLocation { block, index },
+                            constant,
+                        });
+                    }
+                }
+            }
+        }
+        Self {
+            entries: book.into_values().collect(),
+        }
+    }
+
+    pub fn get(&self, address: &str) -> Option<&AddressEntry> {
+        let address = address.to_lowercase();
+        self.entries.iter().find(|entry| entry.address == address)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /* Names each entry the registry knows. */
+    pub fn label(&mut self, labels: &AddressLabels) {
+        for entry in &mut self.entries {
+            entry.label = labels.get(&entry.address).map(str::to_string);
+        }
+    }
+}
+
+fn book_entry<'a>(
+    book: &'a mut BTreeMap<String, AddressEntry>,
+    address: &[u8; 20],
+) -> &'a mut AddressEntry {
+    let address = format_address(address);
+    book.entry(address.clone()).or_insert_with(|| AddressEntry {
+        address,
+        label: None,
+        constants: Vec::new(),
+        uses: Vec::new(),
+    })
+}
+
+fn format_address(address: &[u8; 20]) -> String {
+    format!("0x{}", hex(address))
+}
+
+fn is_zero(address: &[u8; 20]) -> bool {
+    address.iter().all(|&byte| byte == 0)
+}
+
+/* Known addresses and what they are, such as a router or an oracle. The registry file is a JSON
+ * object from address to label; addresses match case-insensitively, so checksummed keys work. */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AddressLabels {
+    labels: BTreeMap<String, String>,
+}
+
+impl AddressLabels {
+    pub fn new(labels: impl IntoIterator<Item = (String, String)>) -> Self {
+        let mut registry = Self::default();
+        registry.extend(labels);
+        registry
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("reading address labels {}", path.display()))?;
+        let labels: BTreeMap<String, String> = serde_json::from_str(&content)
+            .with_context(|| format!("parsing address labels {}", path.display()))?;
+        Ok(Self::new(labels))
+    }
+
+    /* Later labels win over earlier ones for the same address. */
+    pub fn extend(&mut self, labels: impl IntoIterator<Item = (String, String)>) {
+        self.labels.extend(
+            labels
+                .into_iter()
+                .map(|(address, label)| (address.to_lowercase(), label)),
+        );
+    }
+
+    pub fn get(&self, address: &str) -> Option<&str> {
+        self.labels.get(&address.to_lowercase()).map(String::as_str)
+    }
+}
+
+impl IntoIterator for AddressLabels {
+    type Item = (String, String);
+    type IntoIter = std::collections::btree_map::IntoIter<String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.labels.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_book_collects_literals_and_constants_with_their_uses() {
+        let router = [0x7a; 20];
+        let oracle = [0x5f; 20];
+
+        let mut builder = IRBuilder::new();
+        let mut contract = builder.contract("Vault");
+        contract
+            .state_variable("ROUTER", Type::Address, 0)
+            .constant("ROUTER", Type::Address, Constant::Address(router));
+        let mut function = contract.function("swap");
+        let mut entry = function.entry_block();
+        let target = entry.storage_load(BigUint::from(0u32));
+        let zero = entry.constant_uint(0, 256);
+        entry.call_external(target, zero.clone(), Vec::new(), None);
+        let inlined = entry.constant_address(router);
+        entry.call_external(inlined, zero.clone(), Vec::new(), None);
+        let feed = entry.constant_address(oracle);
+        entry.call_external(feed, zero, Vec::new(), None);
+        let unset = entry.constant_address([0; 20]);
+        entry.return_value(unset).unwrap();
+        function.build().unwrap();
+        let contracts = vec![contract.build().unwrap()];
+
+        let mut book = AddressBook::collect(&contracts);
+        assert_eq!(book.len(), 2);
+        book.label(&AddressLabels::new([(
+            format!("0x{}", "7A".repeat(20)),
+            "Uniswap V2 Router".to_string(),
+        )]));
+
+        let entry = book.get(&format_address(&router)).unwrap();
+        assert_eq!(entry.label.as_deref(), Some("Uniswap V2 Router"));
+        assert_eq!(entry.constants, ["Vault.ROUTER"]);
+        assert_eq!(entry.uses.len(), 2);
+        assert!(entry
+            .uses
+            .iter()
+            .all(|used| used.constant.as_deref() == Some("ROUTER")));
+
+        let entry = book.get(&format_address(&oracle)).unwrap();
+        assert_eq!(entry.label, None);
+        assert_eq!(entry.uses[0].function, "swap");
+        assert_eq!(entry.uses[0].constant, None);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     block::BlockId,
     function::Function,
-    instructions::Instruction,
+    instructions::{Instruction, StorageKey},
     values::{Value, ValueId},
 };
 use std::collections::{HashMap, HashSet};
@@ -117,6 +117,40 @@ impl AliasAnalysis {
             .map(|set| set.may_alias_unknown)
             .unwrap_or(true)
     }
+
+    /* Storage keys alias by how their slot is derived, not by pointer. Declared slots, mapping
+     * entries and array data areas are all keccak-separated, so keys rooted at different slots, or
+     * of different shapes, never meet; an array's length at `p` is disjoint from its elements. */
+    pub fn query_storage(a: &StorageKey, b: &StorageKey) -> AliasResult {
+        if let (Some(x), Some(y)) = (a.resolved_slot(), b.resolved_slot()) {
+            return if x == y {
+                AliasResult::MustAlias
+            } else {
+                AliasResult::NoAlias
+            };
+        }
+        match (a, b) {
+            (StorageKey::Dynamic(_) | StorageKey::Computed(_), _)
+            | (_, StorageKey::Dynamic(_) | StorageKey::Computed(_)) => AliasResult::MayAlias,
+            (
+                StorageKey::MappingKey { base: p, key: i },
+                StorageKey::MappingKey { base: q, key: j },
+            )
+            | (
+                StorageKey::ArrayElement { base: p, index: i },
+                StorageKey::ArrayElement { base: q, index: j },
+            ) if p == q => {
+                if i == j {
+                    AliasResult::MustAlias
+                } else if i.is_constant() && j.is_constant() {
+                    AliasResult::NoAlias
+                } else {
+                    AliasResult::MayAlias
+                }
+            }
+            _ => AliasResult::NoAlias,
+        }
+    }
 }
 
 struct AliasAnalyzer {
@@ -236,7 +270,9 @@ impl AliasAnalyzer {
                             self.mark_escaped(id);
                         }
                     }
-                    Instruction::Store { value, .. } | Instruction::StorageStore { value, .. } => {
+                    Instruction::Store { value, .. }
+                    | Instruction::StorageStore { value, .. }
+                    | Instruction::TransientStore { value, .. } => {
                         if let Some(id) = value.as_register() {
                             self.mark_escaped(id);
                         }
@@ -347,4 +383,46 @@ mod tests {
             let result = alias.query(id1, id2);
         }
     }
+
+    #[test]
+    fn test_storage_keys_alias_by_slot_derivation() {
+        use crate::values::{Constant, TempId};
+        use num_bigint::BigUint;
+
+        let slot = |n: u32| BigUint::from(n);
+        let index = |n: u32| Value::Constant(Constant::Uint(BigUint::from(n), 256));
+        let i = Value::Temp(TempId(7));
+        let element = |base: u32, index: Value| StorageKey::ArrayElement {
+            base: slot(base),
+            index,
+        };
+
+        let length = StorageKey::Slot(slot(2));
+        assert_eq!(
+            AliasAnalysis::query_storage(&length, &element(2, i.clone())),
+            AliasResult::NoAlias
+        );
+        assert_eq!(
+            AliasAnalysis::query_storage(&element(2, i.clone()), &element(2, i.clone())),
+            AliasResult::MustAlias
+        );
+        assert_eq!(
+            AliasAnalysis::query_storage(&element(2, i.clone()), &element(2, index(0))),
+            AliasResult::MayAlias
+        );
+        assert_eq!(
+            AliasAnalysis::query_storage(&element(2, index(0)), &element(3, i)),
+            AliasResult::NoAlias
+        );
+
+        let data = StorageKey::array_data_slot(&slot(2));
+        assert_eq!(
+            data.to_str_radix(16),
+            "405787fa12a823e0f2b7631cc41b3ba8828b3321ca811111fa75cd3aa3bb5ace"
+        );
+        assert_eq!(
+            AliasAnalysis::query_storage(&element(2, index(1)), &StorageKey::Slot(data + 1u32)),
+            AliasResult::MustAlias
+        );
+    }
 }
@@ -0,0 +1,314 @@
+use super::pass::AnalysisID;
+use crate::instructions::Instruction;
+use std::any::Any;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/* A value a pass can hang off a block or instruction. `render` is what emitters print next to the
+ * annotated site; results with no useful text form keep the default and stay out of the output. */
+pub trait Annotation: Clone + Send + Sync + 'static {
+    fn render(&self) -> Option<String> {
+        None
+    }
+}
+
+impl Annotation for bool {
+    fn render(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl Annotation for u64 {
+    fn render(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl Annotation for usize {
+    fn render(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl Annotation for String {
+    fn render(&self) -> Option<String> {
+        Some(self.clone())
+    }
+}
+
+/* One pass's results for one block: an optional block-level value plus values keyed by
+ * instruction index, the same indexing `instruction_locations` uses. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalysisAnnotations<T> {
+    pub block: Option<T>,
+    pub instructions: BTreeMap<usize, T>,
+}
+
+impl<T> Default for AnalysisAnnotations<T> {
+    fn default() -> Self {
+        Self {
+            block: None,
+            instructions: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Annotation> AnalysisAnnotations<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_block(&mut self, value: T) {
+        self.block = Some(value);
+    }
+
+    pub fn set_instruction(&mut self, index: usize, value: T) {
+        self.instructions.insert(index, value);
+    }
+
+    pub fn instruction(&self, index: usize) -> Option<&T> {
+        self.instructions.get(&index)
+    }
+}
+
+trait ErasedAnnotations: Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn clone_box(&self) -> Box<dyn ErasedAnnotations>;
+    fn render_block(&self) -> Option<String>;
+    fn render_instruction(&self, index: usize) -> Option<String>;
+}
+
+impl<T: Annotation> ErasedAnnotations for AnalysisAnnotations<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn ErasedAnnotations> {
+        Box::new(self.clone())
+    }
+
+    fn render_block(&self) -> Option<String> {
+        self.block.as_ref().and_then(Annotation::render)
+    }
+
+    fn render_instruction(&self, index: usize) -> Option<String> {
+        self.instruction(index).and_then(Annotation::render)
+    }
+}
+
+/* Identifies the instructions a table was filled against. Entries are keyed by index, so any edit
+ * to the block, even one that leaves its length alone, changes what an index points at. */
+fn fingerprint(instructions: &[Instruction]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    format!("{:?}", instructions).hash(&mut hasher);
+    hasher.finish()
+}
+
+struct Table {
+    fingerprint: u64,
+    annotations: Box<dyn ErasedAnnotations>,
+}
+
+/* Per-block side table of analysis results keyed by the pass that produced them. Each pass picks
+ * its own result type; asking for a table with a different type than was stored yields `None`.
+ * Lookups take the block's current instructions, and a table filled against different ones reads
+ * as absent, so a pass that edits the block without going through the pass manager cannot leave
+ * results attached to the wrong instructions. */
+#[derive(Default)]
+pub struct AnnotationStore {
+    tables: HashMap<AnalysisID, Table>,
+}
+
+impl AnnotationStore {
+    pub fn get<T: Annotation>(
+        &self,
+        pass: AnalysisID,
+        instructions: &[Instruction],
+    ) -> Option<&AnalysisAnnotations<T>> {
+        self.tables
+            .get(&pass)
+            .filter(|table| table.fingerprint == fingerprint(instructions))
+            .and_then(|table| table.annotations.as_any().downcast_ref())
+    }
+
+    /* Creates the table on first use; a stale table, or one of another type under the same pass,
+     * is replaced. */
+    pub fn get_mut<T: Annotation>(
+        &mut self,
+        pass: AnalysisID,
+        instructions: &[Instruction],
+    ) -> &mut AnalysisAnnotations<T> {
+        let current = fingerprint(instructions);
+        let table = self.tables.entry(pass).or_insert_with(|| Table {
+            fingerprint: current,
+            annotations: Box::new(AnalysisAnnotations::<T>::new()),
+        });
+        if table.fingerprint != current
+            || !table.annotations.as_any().is::<AnalysisAnnotations<T>>()
+        {
+            table.fingerprint = current;
+            table.annotations = Box::new(AnalysisAnnotations::<T>::new());
+        }
+        table
+            .annotations
+            .as_any_mut()
+            .downcast_mut()
+            .expect("annotation table type checked above")
+    }
+
+    pub fn remove(&mut self, pass: AnalysisID) {
+        self.tables.remove(&pass);
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(AnalysisID) -> bool) {
+        self.tables.retain(|pass, _| keep(*pass));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    pub fn passes(&self) -> Vec<AnalysisID> {
+        let mut passes: Vec<AnalysisID> = self.tables.keys().copied().collect();
+        passes.sort_by_key(|pass| pass.to_string());
+        passes
+    }
+
+    fn current(&self, instructions: &[Instruction]) -> Vec<(AnalysisID, &dyn ErasedAnnotations)> {
+        let current = fingerprint(instructions);
+        self.passes()
+            .into_iter()
+            .filter(|pass| self.tables[pass].fingerprint == current)
+            .map(|pass| (pass, self.tables[&pass].annotations.as_ref()))
+            .collect()
+    }
+
+    pub fn render_block(&self, instructions: &[Instruction]) -> Vec<(AnalysisID, String)> {
+        self.current(instructions)
+            .into_iter()
+            .filter_map(|(pass, table)| Some((pass, table.render_block()?)))
+            .collect()
+    }
+
+    /* One list per instruction, in block order. */
+    pub fn render_instructions(
+        &self,
+        instructions: &[Instruction],
+    ) -> Vec<Vec<(AnalysisID, String)>> {
+        let tables = self.current(instructions);
+        (0..instructions.len())
+            .map(|index| {
+                tables
+                    .iter()
+                    .filter_map(|(pass, table)| Some((*pass, table.render_instruction(index)?)))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl Clone for AnnotationStore {
+    fn clone(&self) -> Self {
+        Self {
+            tables: self
+                .tables
+                .iter()
+                .map(|(pass, table)| {
+                    let table = Table {
+                        fingerprint: table.fingerprint,
+                        annotations: table.annotations.clone_box(),
+                    };
+                    (*pass, table)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Debug for AnnotationStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.passes()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::values::{Constant, TempId, Value};
+
+    #[derive(Clone)]
+    struct Opaque;
+
+    impl Annotation for Opaque {}
+
+    fn block() -> Vec<Instruction> {
+        (0..3)
+            .map(|i| Instruction::Assign {
+                result: Value::Temp(TempId(i)),
+                value: Value::Constant(Constant::Bool(true)),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_typed_tables_render_per_pass() {
+        let instructions = block();
+        let mut store = AnnotationStore::default();
+        store
+            .get_mut::<u64>(AnalysisID::Custom("gas"), &instructions)
+            .set_instruction(2, 21_000);
+        store
+            .get_mut::<bool>(AnalysisID::Custom("reachable"), &instructions)
+            .set_block(true);
+        store
+            .get_mut::<Opaque>(AnalysisID::TaintAnalysis, &instructions)
+            .set_instruction(2, Opaque);
+
+        let gas = store
+            .get::<u64>(AnalysisID::Custom("gas"), &instructions)
+            .unwrap();
+        assert_eq!(gas.instruction(2), Some(&21_000));
+        assert!(store
+            .get::<bool>(AnalysisID::Custom("gas"), &instructions)
+            .is_none());
+
+        assert_eq!(
+            store.render_instructions(&instructions)[2],
+            vec![(AnalysisID::Custom("gas"), "21000".to_string())]
+        );
+        assert_eq!(store.render_block(&instructions).len(), 1);
+
+        let copy = store.clone();
+        store.retain(|pass| pass == AnalysisID::TaintAnalysis);
+        assert_eq!(store.passes(), vec![AnalysisID::TaintAnalysis]);
+        assert_eq!(copy.passes().len(), 3);
+    }
+
+    #[test]
+    fn test_tables_go_stale_when_the_block_changes() {
+        let mut instructions = block();
+        let mut store = AnnotationStore::default();
+        store
+            .get_mut::<u64>(AnalysisID::Custom("gas"), &instructions)
+            .set_instruction(1, 3);
+
+        instructions.remove(0);
+        assert!(store
+            .get::<u64>(AnalysisID::Custom("gas"), &instructions)
+            .is_none());
+        assert!(store
+            .render_instructions(&instructions)
+            .iter()
+            .all(Vec::is_empty));
+
+        let table = store.get_mut::<u64>(AnalysisID::Custom("gas"), &instructions);
+        assert_eq!(table.instruction(1), None);
+    }
+}
@@ -0,0 +1,294 @@
+use super::findings::Finding;
+use crate::contract::Contract;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+
+const BASELINE_VERSION: u32 = 1;
+const IGNORE: &str = "thalir-ignore";
+const IGNORE_FILE: &str = "thalir-ignore-file";
+
+/* Fingerprints for `findings` in order. A finding reported more than once with the same
+ * fingerprint gets an occurrence suffix, so a second copy of an accepted issue still shows up as
+ * new. */
+pub fn fingerprints(findings: &[Finding]) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    findings
+        .iter()
+        .map(|finding| {
+            let fingerprint = finding.fingerprint();
+            let count = seen.entry(fingerprint.clone()).or_insert(0);
+            *count += 1;
+            if *count == 1 {
+                fingerprint
+            } else {
+                format!("{}#{}", fingerprint, count)
+            }
+        })
+        .collect()
+}
+
+/* Drops findings that are exact repeats of an earlier one, as happens when overlapping
+ * detectors or inherited functions report the same instruction twice. */
+pub fn dedup(findings: Vec<Finding>) -> Vec<Finding> {
+    let mut seen = HashSet::new();
+    findings
+        .into_iter()
+        .filter(|finding| {
+            seen.insert((
+                finding.fingerprint(),
+                finding.location.as_ref().map(|loc| (loc.block, loc.index)),
+            ))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub fingerprints: BTreeSet<String>,
+}
+
+impl Baseline {
+    pub fn from_findings(findings: &[Finding]) -> Self {
+        Self {
+            version: BASELINE_VERSION,
+            fingerprints: fingerprints(findings).into_iter().collect(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("reading baseline {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("parsing baseline {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("writing baseline {}", path.display()))
+    }
+
+    pub fn contains(&self, fingerprint: &str) -> bool {
+        self.fingerprints.contains(fingerprint)
+    }
+
+    /* The findings whose fingerprints the baseline has not recorded. */
+    pub fn new_findings(&self, findings: Vec<Finding>) -> Vec<Finding> {
+        let fingerprints = fingerprints(&findings);
+        findings
+            .into_iter()
+            .zip(fingerprints)
+            .filter(|(_, fingerprint)| !self.contains(fingerprint))
+            .map(|(finding, _)| finding)
+            .collect()
+    }
+}
+
+/* Inline `thalir-ignore` comments from Solidity or ThalIR text, in any comment style. A bare marker
+ * silences every detector and `thalir-ignore: name, other` only the listed ones. The marker
+ * covers its own line and the next, so it can trail the offending statement or sit above it;
+ * `thalir-ignore-file` covers the whole file. */
+#[derive(Debug, Clone, Default)]
+pub struct Suppressions {
+    file: Option<Scope>,
+    lines: HashMap<u32, Scope>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Scope {
+    All,
+    Only(Vec<String>),
+}
+
+impl Scope {
+    fn covers(&self, detector: &str) -> bool {
+        match self {
+            Scope::All => true,
+            Scope::Only(detectors) => detectors.iter().any(|d| d == detector),
+        }
+    }
+
+    fn merge(&mut self, other: Scope) {
+        match (self, other) {
+            (Scope::Only(detectors), Scope::Only(more)) => detectors.extend(more),
+            (this, Scope::All) => *this = Scope::All,
+            (Scope::All, Scope::Only(_)) => {}
+        }
+    }
+}
+
+impl Suppressions {
+    pub fn parse(source: &str) -> Self {
+        let mut suppressions = Self::default();
+        for (index, line) in source.lines().enumerate() {
+            let Some(start) = comment_start(line) else {
+                continue;
+            };
+            let comment = &line[start..];
+            let Some(pos) = comment.find(IGNORE) else {
+                continue;
+            };
+            let rest = &comment[pos..];
+            if let Some(list) = rest.strip_prefix(IGNORE_FILE) {
+                let scope = detector_scope(list);
+                match &mut suppressions.file {
+                    Some(file) => file.merge(scope),
+                    None => suppressions.file = Some(scope),
+                }
+                continue;
+            }
+            let scope = detector_scope(&rest[IGNORE.len()..]);
+            let line = index as u32 + 1;
+            for covered in [line, line + 1] {
+                match suppressions.lines.get_mut(&covered) {
+                    Some(existing) => existing.merge(scope.clone()),
+                    None => {
+                        suppressions.lines.insert(covered, scope.clone());
+                    }
+                }
+            }
+        }
+        suppressions
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.file.is_none() && self.lines.is_empty()
+    }
+
+    pub fn suppresses(&self, finding: &Finding, line: Option<u32>) -> bool {
+        let covers = |scope: &Scope| scope.covers(&finding.detector);
+        self.file.as_ref().is_some_and(covers)
+            || line
+                .and_then(|line| self.lines.get(&line))
+                .is_some_and(covers)
+    }
+
+    /* Keeps the findings no comment in the source silences. Lines come from the instruction
+     * locations the transformer recorded, so a finding without a location can only be silenced
+     * file-wide. */
+    pub fn filter(&self, findings: Vec<Finding>, contracts: &[Contract]) -> Vec<Finding> {
+        if self.is_empty() {
+            return findings;
+        }
+        findings
+            .into_iter()
+            .filter(|finding| !self.suppresses(finding, source_line(finding, contracts)))
+            .collect()
+    }
+}
+
+/* The source line of the instruction a finding points at, when the transformer kept one. */
+pub fn source_line(finding: &Finding, contracts: &[Contract]) -> Option<u32> {
+    let location = finding.location.as_ref()?;
+    let contract = contracts.iter().find(|c| c.name == finding.contract)?;
+    let function = contract.functions.get(&finding.function).or_else(|| {
+        contract
+            .functions
+            .values()
+            .find(|f| f.name() == finding.function)
+    })?;
+    let block = function.body.blocks.get(&location.block)?;
+    block
+        .metadata
+        .get_location(location.index)
+        .map(|source| source.line)
+}
+
+fn detector_scope(text: &str) -> Scope {
+    let Some(list) = text.trim_start().strip_prefix(':') else {
+        return Scope::All;
+    };
+    /* Anything after the list, such as a reason or the end of a block comment, ends it. */
+    let mut detectors = Vec::new();
+    for item in list.split(',') {
+        let mut words = item.split_whitespace();
+        match words.next() {
+            Some(name) if name != "*/" => detectors.push(name.to_string()),
+            _ => break,
+        }
+        if words.next().is_some() {
+            break;
+        }
+    }
+    if detectors.is_empty() {
+        Scope::All
+    } else {
+        Scope::Only(detectors)
+    }
+}
+
+fn comment_start(line: &str) -> Option<usize> {
+    let bytes = line.as_bytes();
+    let mut in_string = false;
+    for (index, byte) in bytes.iter().enumerate() {
+        match byte {
+            b'"' => in_string = !in_string,
+            b';' if !in_string => return Some(index),
+            b'/' if !in_string && matches!(bytes.get(index + 1), Some(b'/' | b'*')) => {
+                return Some(index)
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::findings::Severity;
+    use crate::block::BlockId;
+    use crate::metadata::InstructionLocation;
+
+    #[test]
+    fn test_baseline_and_inline_suppressions() {
+        let finding = |detector: &str, function: &str, index: usize| {
+            Finding::new(
+                detector,
+                Severity::Medium,
+                "Vault",
+                function,
+                format!("issue in {}", function),
+            )
+            .at(InstructionLocation {
+                block: BlockId(0),
+                index,
+            })
+        };
+
+        let accepted = vec![finding("weak-randomness", "draw", 3)];
+        let baseline = Baseline::from_findings(&accepted);
+
+        /* The same issue moved to another instruction still matches, a second copy does not. */
+        let current = dedup(vec![
+            finding("weak-randomness", "draw", 5),
+            finding("weak-randomness", "draw", 5),
+            finding("weak-randomness", "draw", 9),
+            finding("time-dependence", "accrue", 1),
+        ]);
+        assert_eq!(current.len(), 3);
+        let fresh = baseline.new_findings(current);
+        assert_eq!(fresh.len(), 2);
+        assert_eq!(fresh[0].location.as_ref().unwrap().index, 9);
+        assert_eq!(fresh[1].detector, "time-dependence");
+
+        let source = "uint x = now % 10; // thalir-ignore: weak-randomness -- accepted\n\
+                      // thalir-ignore\n\
+                      y = 1;\n\
+                      string s = \"thalir-ignore\";\n";
+        let suppressions = Suppressions::parse(source);
+        let randomness = finding("weak-randomness", "draw", 0);
+        let timing = finding("time-dependence", "draw", 0);
+        assert!(suppressions.suppresses(&randomness, Some(1)));
+        assert!(!suppressions.suppresses(&timing, Some(1)));
+        assert!(suppressions.suppresses(&timing, Some(3)));
+        assert!(!suppressions.suppresses(&timing, Some(5)));
+        assert!(!suppressions.suppresses(&timing, None));
+
+        let file = Suppressions::parse("; thalir-ignore-file: time-dependence\n");
+        assert!(file.suppresses(&timing, None));
+        assert!(!file.suppresses(&randomness, None));
+    }
+}
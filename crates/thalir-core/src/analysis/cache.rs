@@ -1,5 +1,17 @@
+use super::{FunctionFingerprint, FunctionSummary};
+use crate::{
+    contract::Contract,
+    instructions::{CallTarget, Instruction},
+    transform::Canonicalizer,
+};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::any::{Any, TypeId};
 use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 
@@ -272,6 +284,123 @@ impl Default for SharedAnalysisCache {
     }
 }
 
+/* The analyses worth keeping between runs, all of one contract. */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContractAnalyses {
+    /* Each function's internal callees, in order of first call. */
+    pub call_graph: IndexMap<String, Vec<String>>,
+    pub summaries: IndexMap<String, FunctionSummary>,
+    pub fingerprints: Vec<FunctionFingerprint>,
+}
+
+impl ContractAnalyses {
+    pub fn compute(contract: &Contract) -> Self {
+        let mut analyses = Self::default();
+        for (name, function) in &contract.functions {
+            let mut callees: Vec<String> = Vec::new();
+            for inst in function.body.blocks.values().flat_map(|b| &b.instructions) {
+                if let Instruction::Call {
+                    target: CallTarget::Internal(callee),
+                    ..
+                } = inst
+                {
+                    if !callees.contains(callee) {
+                        callees.push(callee.clone());
+                    }
+                }
+            }
+            analyses.call_graph.insert(name.clone(), callees);
+            analyses.summaries.insert(
+                name.clone(),
+                FunctionSummary::of_function(contract, function),
+            );
+            analyses
+                .fingerprints
+                .push(FunctionFingerprint::compute(&contract.name, function));
+        }
+        analyses
+    }
+}
+
+/* Keeps `ContractAnalyses` on disk, one JSON file per contract named after its canonical hash, so
+ * a later run over an unchanged contract reads them back instead of recomputing. Entries are never
+ * revalidated; anything unreadable counts as a miss and is overwritten. */
+pub struct DiskCache {
+    dir: PathBuf,
+    stats: CacheStatistics,
+}
+
+impl DiskCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            stats: CacheStatistics::default(),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /* The canonical hash ignores value numbering and block order but not names. Modifier names
+     * are added because the canonical form drops them and summaries report them, and the crate
+     * version because the cached types may change between releases. */
+    pub fn key(contract: &Contract) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(env!("CARGO_PKG_VERSION"));
+        hasher.update(Canonicalizer::new().contract_fingerprint(contract));
+        for function in contract.functions.values() {
+            hasher.update(format!("{:?}", contract.guards(function).modifiers));
+        }
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    pub fn analyses(&mut self, contract: &Contract) -> ContractAnalyses {
+        let key = Self::key(contract);
+        if let Some(cached) = self.load(&key) {
+            self.stats.hits += 1;
+            return cached;
+        }
+
+        self.stats.misses += 1;
+        let start = Instant::now();
+        let analyses = ContractAnalyses::compute(contract);
+        self.stats.total_compute_time += start.elapsed();
+
+        if let Err(error) = self.store(&key, &analyses) {
+            tracing::warn!(%error, dir = %self.dir.display(), "cannot write analysis cache");
+        }
+        analyses
+    }
+
+    pub fn statistics(&self) -> &CacheStatistics {
+        &self.stats
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    fn load(&self, key: &str) -> Option<ContractAnalyses> {
+        let text = fs::read_to_string(self.path(key)).ok()?;
+        serde_json::from_str(&text).ok()
+    }
+
+    /* Writes through a temporary file so a concurrent run never reads half an entry. */
+    fn store(&self, key: &str, analyses: &ContractAnalyses) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let json = serde_json::to_string(analyses)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let partial = self.dir.join(format!("{}.{}.tmp", key, std::process::id()));
+        fs::write(&partial, json)?;
+        fs::rename(&partial, self.path(key))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -324,4 +453,45 @@ mod tests {
         assert!(cache.get::<String>(&key2).is_some());
         assert_eq!(cache.statistics().invalidations, 1);
     }
+
+    #[test]
+    fn test_disk_cache_survives_a_new_instance_and_misses_on_change() {
+        use crate::builder::IRBuilder;
+        use crate::types::Type;
+
+        let build = |slot: u32| {
+            let mut builder = IRBuilder::new();
+            let mut contract = builder.contract("Vault");
+            contract.state_variable("total", Type::Uint(256), 0);
+            let mut function = contract.function("sweep");
+            let mut entry = function.entry_block();
+            let total = entry.storage_load(slot.into());
+            entry.call_internal("_settle", vec![total]);
+            entry.return_void().unwrap();
+            function.build().unwrap();
+            contract.build().unwrap()
+        };
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut first = DiskCache::new(dir.path());
+        let computed = first.analyses(&build(0));
+        assert_eq!(first.statistics().misses, 1);
+        assert_eq!(computed.call_graph["sweep"], vec!["_settle".to_string()]);
+
+        let mut second = DiskCache::new(dir.path());
+        let cached = second.analyses(&build(0));
+        assert_eq!(second.statistics().hits, 1);
+        assert_eq!(cached.summaries, computed.summaries);
+        assert_eq!(
+            cached.fingerprints[0].structural_hash,
+            computed.fingerprints[0].structural_hash
+        );
+
+        second.analyses(&build(1));
+        assert_eq!(second.statistics().misses, 1);
+
+        fs::write(second.path(&DiskCache::key(&build(0))), "{").unwrap();
+        second.analyses(&build(0));
+        assert_eq!(second.statistics().misses, 2);
+    }
 }
@@ -0,0 +1,484 @@
+use super::control_flow::ControlFlowGraph;
+use super::findings::{Finding, Severity};
+use super::reentrancy_guard::ReentrancyGuardAnalysis;
+use crate::{
+    contract::Contract,
+    function::{Function, Mutability},
+    hash::selector_u32,
+    instructions::{CallTarget, Instruction},
+    metadata::InstructionLocation,
+    values::{Constant, Value},
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookKind {
+    /* The token calls into the sender or recipient, a third party that may call back into any
+     * entry point of the caller. */
+    Recipient,
+    /* The pool calls back the contract that asked for the loan or swap, in that contract's own
+     * hook function. */
+    Initiator,
+}
+
+/* An interface whose calls hand control to code the caller does not control. Triggers are full
+ * signatures, since external calls in the IR carry only a selector; hooks are matched by name,
+ * as `bytes` parameters do not survive into the IR's types. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct CallbackInterface {
+    pub name: String,
+    pub kind: HookKind,
+    pub triggers: Vec<String>,
+    pub hooks: Vec<String>,
+}
+
+impl CallbackInterface {
+    fn new(name: &str, kind: HookKind, triggers: &[&str], hooks: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            triggers: triggers.iter().map(|s| s.to_string()).collect(),
+            hooks: hooks.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    fn trigger_for(&self, called: u32) -> Option<&str> {
+        self.triggers
+            .iter()
+            .find(|trigger| selector_u32(trigger) == called)
+            .map(String::as_str)
+    }
+}
+
+/* The `[callbacks]` table of a `thalir.toml`: which built-in interfaces to model, all of them if
+ * `interfaces` is unset, and any project-specific ones. */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct CallbackConfig {
+    pub interfaces: Option<Vec<String>>,
+    pub custom: Vec<CallbackInterface>,
+}
+
+impl CallbackConfig {
+    pub fn unknown_interfaces(&self) -> Vec<&str> {
+        let standard = CallbackModel::standard();
+        self.interfaces
+            .iter()
+            .flatten()
+            .map(String::as_str)
+            .filter(|name| !standard.interfaces.iter().any(|i| i.name == *name))
+            .collect()
+    }
+
+    pub fn model(&self) -> CallbackModel {
+        let mut model = CallbackModel::standard();
+        if let Some(names) = &self.interfaces {
+            model.interfaces.retain(|i| names.contains(&i.name));
+        }
+        model.interfaces.extend(self.custom.iter().cloned());
+        model
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallbackModel {
+    pub interfaces: Vec<CallbackInterface>,
+}
+
+impl CallbackModel {
+    /* ERC-777's `transfer` and `transferFrom` are the ERC-20 ones, so with `erc777` modelled any
+     * token transfer to an unknown token counts as a hook; that is the imBTC and Lendf.Me bug, but
+     * projects that only ever hold vetted tokens can leave it out. */
+    pub fn standard() -> Self {
+        use HookKind::*;
+        Self {
+            interfaces: vec![
+                CallbackInterface::new(
+                    "erc777",
+                    Recipient,
+                    &[
+                        "send(address,uint256,bytes)",
+                        "transfer(address,uint256)",
+                        "transferFrom(address,address,uint256)",
+                        "operatorSend(address,address,uint256,bytes,bytes)",
+                    ],
+                    &["tokensReceived", "tokensToSend"],
+                ),
+                CallbackInterface::new(
+                    "erc721",
+                    Recipient,
+                    &[
+                        "safeTransferFrom(address,address,uint256)",
+                        "safeTransferFrom(address,address,uint256,bytes)",
+                    ],
+                    &["onERC721Received"],
+                ),
+                CallbackInterface::new(
+                    "erc1155",
+                    Recipient,
+                    &[
+                        "safeTransferFrom(address,address,uint256,uint256,bytes)",
+                        "safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)",
+                    ],
+                    &["onERC1155Received", "onERC1155BatchReceived"],
+                ),
+                CallbackInterface::new(
+                    "uniswap-v2",
+                    Initiator,
+                    &["swap(uint256,uint256,address,bytes)"],
+                    &["uniswapV2Call"],
+                ),
+                CallbackInterface::new(
+                    "uniswap-v3",
+                    Initiator,
+                    &[
+                        "swap(address,bool,int256,uint160,bytes)",
+                        "flash(address,uint256,uint256,bytes)",
+                        "mint(address,int24,int24,uint128,bytes)",
+                    ],
+                    &[
+                        "uniswapV3SwapCallback",
+                        "uniswapV3FlashCallback",
+                        "uniswapV3MintCallback",
+                    ],
+                ),
+                CallbackInterface::new(
+                    "aave",
+                    Initiator,
+                    &[
+                        "flashLoan(address,address[],uint256[],uint256[],address,bytes,uint16)",
+                        "flashLoanSimple(address,address,uint256,bytes,uint16)",
+                    ],
+                    &["executeOperation"],
+                ),
+                CallbackInterface::new(
+                    "erc3156",
+                    Initiator,
+                    &["flashLoan(address,address,uint256,bytes)"],
+                    &["onFlashLoan"],
+                ),
+                CallbackInterface::new(
+                    "balancer",
+                    Initiator,
+                    &["flashLoan(address,address[],uint256[],bytes)"],
+                    &["receiveFlashLoan"],
+                ),
+            ],
+        }
+    }
+
+    /* The one trigger named `name` taking `arity` arguments, for front ends that know the member
+     * called but not the exact parameter types. */
+    pub fn trigger_named(&self, name: &str, arity: usize) -> Option<&str> {
+        let mut matches = self
+            .interfaces
+            .iter()
+            .flat_map(|i| &i.triggers)
+            .filter(|trigger| {
+                trigger.split_once('(').is_some_and(|(n, params)| {
+                    let params = params.trim_end_matches(')');
+                    let count = if params.is_empty() {
+                        0
+                    } else {
+                        params.split(',').count()
+                    };
+                    n == name && count == arity
+                })
+            });
+        let first = matches.next()?;
+        matches
+            .all(|other| other == first)
+            .then_some(first.as_str())
+    }
+
+    /* The interface and trigger an instruction calls, when it is an external call whose selector
+     * is a constant naming one. */
+    pub fn hook_at(&self, inst: &Instruction) -> Option<(&CallbackInterface, &str)> {
+        let Instruction::Call {
+            target: CallTarget::External(_),
+            args,
+            ..
+        } = inst
+        else {
+            return None;
+        };
+        let called = match args.first() {
+            Some(Value::Constant(Constant::Uint(value, _))) => u32::try_from(value).ok()?,
+            _ => return None,
+        };
+        self.interfaces
+            .iter()
+            .find_map(|i| i.trigger_for(called).map(|trigger| (i, trigger)))
+    }
+}
+
+impl Default for CallbackModel {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+/* A call after which control may come back into the contract before the call returns, and where
+ * it may come back to. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReentryEdge {
+    pub function: String,
+    pub location: InstructionLocation,
+    pub interface: String,
+    pub trigger: String,
+    pub targets: Vec<String>,
+}
+
+impl fmt::Display for ReentryEdge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} ({}) -> {}",
+            self.function,
+            self.trigger,
+            self.interface,
+            self.targets.join(", ")
+        )
+    }
+}
+
+/* A re-entry edge out of a function that still writes state after the call, at `write`, with no
+ * reentrancy lock held over the call. */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallbackReentrancy {
+    pub edge: ReentryEdge,
+    pub write: InstructionLocation,
+}
+
+impl CallbackReentrancy {
+    pub fn severity(&self) -> Severity {
+        Severity::Medium
+    }
+
+    pub fn to_finding(&self, contract: &str) -> Finding {
+        Finding::new(
+            CallbackAnalysis::DETECTOR,
+            self.severity(),
+            contract,
+            &self.edge.function,
+            format!(
+                "`{}` hands control to a {} hook, which can re-enter {} before `{}` has finished writing state",
+                self.edge.trigger,
+                self.edge.interface,
+                self.edge
+                    .targets
+                    .iter()
+                    .map(|t| format!("`{}`", t))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                self.edge.function
+            ),
+        )
+        .at(self.edge.location.clone())
+    }
+}
+
+pub struct CallbackAnalysis;
+
+impl CallbackAnalysis {
+    pub const DETECTOR: &'static str = "callback-reentrancy";
+
+    /* A recipient hook gives a third party the floor, so every state-changing entry point is a
+     * target. An initiator hook only calls this contract's own implementation of it; an edge
+     * whose hook the contract does not implement is dropped, as the call would just revert. */
+    pub fn edges(contract: &Contract, model: &CallbackModel) -> Vec<ReentryEdge> {
+        let mut edges = Vec::new();
+        for (name, function) in &contract.functions {
+            for (block_id, block) in &function.body.blocks {
+                for (index, inst) in block.instructions.iter().enumerate() {
+                    let Some((interface, trigger)) = model.hook_at(inst) else {
+                        continue;
+                    };
+                    let targets: Vec<String> = contract
+                        .functions
+                        .iter()
+                        .filter(|(_, f)| match interface.kind {
+                            HookKind::Recipient => {
+                                f.is_entry_point()
+                                    && !matches!(f.mutability, Mutability::View | Mutability::Pure)
+                            }
+                            HookKind::Initiator => interface.hooks.iter().any(|h| h == f.name()),
+                        })
+                        .map(|(target, _)| target.clone())
+                        .collect();
+                    if targets.is_empty() {
+                        continue;
+                    }
+                    edges.push(ReentryEdge {
+                        function: name.clone(),
+                        location: InstructionLocation {
+                            block: *block_id,
+                            index,
+                        },
+                        interface: interface.name.clone(),
+                        trigger: trigger.to_string(),
+                        targets,
+                    });
+                }
+            }
+        }
+        edges
+    }
+
+    pub fn analyze_contract(contract: &Contract, model: &CallbackModel) -> Vec<CallbackReentrancy> {
+        Self::edges(contract, model)
+            .into_iter()
+            .filter_map(|edge| {
+                let function = contract.functions.get(&edge.function)?;
+                if ReentrancyGuardAnalysis::protects(contract, function, &edge.location) {
+                    return None;
+                }
+                let write = write_after(function, &edge.location)?;
+                Some(CallbackReentrancy { edge, write })
+            })
+            .collect()
+    }
+
+    pub fn findings_with(contract: &Contract, model: &CallbackModel) -> Vec<Finding> {
+        Self::analyze_contract(contract, model)
+            .iter()
+            .map(|issue| issue.to_finding(&contract.name))
+            .collect()
+    }
+
+    pub fn findings(contract: &Contract) -> Vec<Finding> {
+        Self::findings_with(contract, &CallbackModel::standard())
+    }
+}
+
+/* The first storage write that can run after the call: later in its block, or in any block the
+ * call's block reaches, its own included when it sits in a loop. */
+fn write_after(function: &Function, call: &InstructionLocation) -> Option<InstructionLocation> {
+    let writes = |inst: &Instruction| {
+        matches!(
+            inst,
+            Instruction::StorageStore { .. }
+                | Instruction::StorageDelete { .. }
+                | Instruction::MappingStore { .. }
+                | Instruction::ArrayStore { .. }
+                | Instruction::ArrayPush { .. }
+                | Instruction::ArrayPop { .. }
+        )
+    };
+    let block = function.body.blocks.get(&call.block)?;
+    if let Some(index) = block.instructions[call.index + 1..]
+        .iter()
+        .position(|inst| writes(inst))
+    {
+        return Some(InstructionLocation {
+            block: call.block,
+            index: call.index + 1 + index,
+        });
+    }
+
+    let cfg = ControlFlowGraph::build(function);
+    function.body.blocks.iter().find_map(|(id, other)| {
+        let reached = cfg
+            .successors(call.block)
+            .iter()
+            .any(|succ| cfg.has_path(*succ, *id));
+        if !reached {
+            return None;
+        }
+        other
+            .instructions
+            .iter()
+            .position(|inst| writes(inst))
+            .map(|index| InstructionLocation { block: *id, index })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{IRBuilder, InstBuilderExt};
+    use crate::function::Visibility;
+    use crate::types::Type;
+
+    fn trigger(signature: &str) -> Value {
+        Value::Constant(Constant::Uint(selector_u32(signature).into(), 32))
+    }
+
+    #[test]
+    fn test_hooks_add_reentry_edges() {
+        let mut builder = IRBuilder::new();
+        let mut contract = builder.contract("Marketplace");
+
+        let mut buy = contract.function("buy");
+        buy.visibility(Visibility::External);
+        let mut entry = buy.entry_block();
+        let nft = entry.storage_load(0u32.into());
+        let buyer = entry.msg_sender();
+        let this = entry.this_address();
+        let id = entry.constant_uint(7, 256);
+        entry.call_external(
+            nft,
+            trigger("safeTransferFrom(address,address,uint256)"),
+            vec![this, buyer, id],
+            None,
+        );
+        let sold = entry.constant_uint(1, 256);
+        entry.storage_store(1u32.into(), sold);
+        entry.return_void().unwrap();
+        buy.build().unwrap();
+
+        let mut borrow = contract.function("borrow");
+        borrow.visibility(Visibility::External);
+        let mut entry = borrow.entry_block();
+        let pool = entry.storage_load(2u32.into());
+        let amount = entry.constant_uint(1000, 256);
+        entry.call_external(
+            pool,
+            trigger("flashLoan(address,address,uint256,bytes)"),
+            vec![amount],
+            None,
+        );
+        entry.return_void().unwrap();
+        borrow.build().unwrap();
+
+        let mut hook = contract.function("onFlashLoan");
+        hook.visibility(Visibility::External);
+        hook.param("amount", Type::Uint(256));
+        let amount = hook.get_param(0);
+        let mut entry = hook.entry_block();
+        entry.storage_store(3u32.into(), amount);
+        entry.return_void().unwrap();
+        hook.build().unwrap();
+
+        let contract = contract.build().unwrap();
+        let model = CallbackModel::standard();
+
+        let edges = CallbackAnalysis::edges(&contract, &model);
+        assert_eq!(edges.len(), 2, "{:#?}", edges);
+        let transfer = edges.iter().find(|e| e.function == "buy").unwrap();
+        assert_eq!(transfer.interface, "erc721");
+        assert_eq!(transfer.targets.len(), 3);
+        let flash = edges.iter().find(|e| e.function == "borrow").unwrap();
+        assert_eq!(flash.targets, vec!["onFlashLoan".to_string()]);
+
+        let issues = CallbackAnalysis::analyze_contract(&contract, &model);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].edge.function, "buy");
+
+        let config = CallbackConfig {
+            interfaces: Some(vec!["erc3156".to_string()]),
+            ..Default::default()
+        };
+        assert!(CallbackAnalysis::edges(&contract, &config.model())
+            .iter()
+            .all(|e| e.interface == "erc3156"));
+        assert_eq!(
+            model.trigger_named("safeTransferFrom", 4),
+            Some("safeTransferFrom(address,address,uint256,bytes)")
+        );
+        assert_eq!(model.trigger_named("flashLoan", 4), None);
+    }
+}
@@ -0,0 +1,81 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum Cancelled {
+    #[error("analysis was cancelled")]
+    Requested,
+    #[error("analysis exceeded its deadline")]
+    DeadlineExceeded,
+}
+
+/* Shared between the embedder and a running analysis. Clones observe the same `cancel()`; a
+ * deadline belongs to the token it was set on and to clones made after that. Analyses poll
+ * `check()` at safe points (between passes, between functions) and unwind with `Cancelled`, which
+ * callers can recover with `downcast_ref` on the returned error. */
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    flag: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.check().is_err()
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.flag.load(Ordering::Relaxed) {
+            return Err(Cancelled::Requested);
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(Cancelled::DeadlineExceeded),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_share_cancel_and_deadlines_expire() {
+        let token = CancellationToken::new();
+        let handle = token.clone();
+        assert_eq!(token.check(), Ok(()));
+        handle.cancel();
+        assert_eq!(token.check(), Err(Cancelled::Requested));
+
+        let expired = CancellationToken::new().with_deadline(Instant::now());
+        assert_eq!(expired.check(), Err(Cancelled::DeadlineExceeded));
+        assert_eq!(expired.remaining(), Some(Duration::ZERO));
+
+        let generous = CancellationToken::new().with_timeout(Duration::from_secs(3600));
+        assert!(!generous.is_cancelled());
+    }
+}
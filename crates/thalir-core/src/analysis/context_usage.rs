@@ -0,0 +1,314 @@
+use crate::{
+    chain::{ChainCapabilities, ContextSupport},
+    contract::Contract,
+    function::Function,
+    instructions::{BuiltinFunction, CallTarget, ContextVariable, Instruction},
+    version::EvmVersion,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+/* A read of the execution environment: a context variable, or one of the builtins that look up
+ * chain state rather than compute. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContextRead {
+    Variable(ContextVariable),
+    Builtin(BuiltinFunction),
+}
+
+impl ContextRead {
+    /* `gasleft()` lowers to a context read but parsed IR may carry it as a builtin call; both
+     * become the variable so the matrix has one column for it. */
+    fn of(inst: &Instruction) -> Option<Self> {
+        match inst {
+            Instruction::GetContext { var, .. } => Some(ContextRead::Variable(*var)),
+            Instruction::Call {
+                target: CallTarget::Builtin(builtin),
+                ..
+            } => match builtin {
+                BuiltinFunction::GasLeft => Some(ContextRead::Variable(ContextVariable::GasLeft)),
+                BuiltinFunction::BlockHash | BuiltinFunction::BlobHash => {
+                    Some(ContextRead::Builtin(*builtin))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn support(&self, chain: &ChainCapabilities, evm: EvmVersion) -> ContextSupport {
+        match self {
+            ContextRead::Variable(var) => chain.context_support(*var, evm),
+            ContextRead::Builtin(builtin) => chain.builtin_support(*builtin, evm),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ContextRead::Variable(var) => match var {
+                ContextVariable::MsgSender => "msg.sender",
+                ContextVariable::MsgValue => "msg.value",
+                ContextVariable::MsgData => "msg.data",
+                ContextVariable::MsgSig => "msg.sig",
+                ContextVariable::BlockNumber => "block.number",
+                ContextVariable::BlockTimestamp => "block.timestamp",
+                ContextVariable::BlockDifficulty => "block.difficulty",
+                ContextVariable::PrevRandao => "block.prevrandao",
+                ContextVariable::BlockGasLimit => "block.gaslimit",
+                ContextVariable::BlockCoinbase => "block.coinbase",
+                ContextVariable::ChainId => "block.chainid",
+                ContextVariable::BlockBaseFee => "block.basefee",
+                ContextVariable::BlobBaseFee => "block.blobbasefee",
+                ContextVariable::TxOrigin => "tx.origin",
+                ContextVariable::TxGasPrice => "tx.gasprice",
+                ContextVariable::GasLeft => "gasleft()",
+                ContextVariable::ThisAddress => "address(this)",
+                ContextVariable::ThisBalance => "address(this).balance",
+            },
+            ContextRead::Builtin(BuiltinFunction::BlockHash) => "blockhash()",
+            ContextRead::Builtin(BuiltinFunction::BlobHash) => "blobhash()",
+            ContextRead::Builtin(_) => "builtin",
+        }
+    }
+}
+
+impl fmt::Display for ContextRead {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionContextUsage {
+    pub function: String,
+    pub reads: Vec<ContextRead>,
+}
+
+/* A read the target chain answers differently from Ethereum mainnet. */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilityIssue {
+    pub function: String,
+    pub read: ContextRead,
+    pub support: ContextSupport,
+}
+
+impl fmt::Display for CapabilityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.support {
+            ContextSupport::Fixed(value) => write!(
+                f,
+                "{} reads {}, which is always {} on this chain",
+                self.function, self.read, value
+            ),
+            _ => write!(
+                f,
+                "{} reads {}, which this chain does not support",
+                self.function, self.read
+            ),
+        }
+    }
+}
+
+/* Functions that read the environment, against the reads that occur anywhere in the contract,
+ * ordered by name so msg.*, block.* and tx.* sit together. */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContextUsageMatrix {
+    pub columns: Vec<ContextRead>,
+    pub rows: Vec<FunctionContextUsage>,
+}
+
+impl ContextUsageMatrix {
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn readers(&self, read: ContextRead) -> impl Iterator<Item = &str> {
+        self.rows
+            .iter()
+            .filter(move |row| row.reads.contains(&read))
+            .map(|row| row.function.as_str())
+    }
+
+    pub fn capability_issues(
+        &self,
+        chain: &ChainCapabilities,
+        evm: EvmVersion,
+    ) -> Vec<CapabilityIssue> {
+        self.rows
+            .iter()
+            .flat_map(|row| {
+                row.reads.iter().filter_map(|read| {
+                    let support = read.support(chain, evm);
+                    (!support.varies()).then(|| CapabilityIssue {
+                        function: row.function.clone(),
+                        read: *read,
+                        support,
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for ContextUsageMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .rows
+            .iter()
+            .map(|row| row.function.len())
+            .chain(Some("function".len()))
+            .max()
+            .unwrap_or(0);
+        write!(f, "{:<width$}", "function")?;
+        for column in &self.columns {
+            write!(f, "  {}", column)?;
+        }
+        writeln!(f)?;
+        for row in &self.rows {
+            let mut line = format!("{:<width$}", row.function);
+            for column in &self.columns {
+                let mark = if row.reads.contains(column) { "x" } else { "-" };
+                line.push_str(&format!("  {:<w$}", mark, w = column.label().len()));
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+pub struct ContextUsageAnalysis;
+
+impl ContextUsageAnalysis {
+    /* Reads made by the function itself or by any internal function it reaches, since a guard
+     * such as `_checkOrigin()` puts its `tx.origin` check on every caller. */
+    pub fn analyze_function(contract: &Contract, function: &Function) -> Vec<ContextRead> {
+        let mut reads = Vec::new();
+        collect_reads(contract, function, &mut reads, &mut HashSet::new());
+        reads.sort_by_key(|read| read.label());
+        reads
+    }
+
+    pub fn analyze_contract(contract: &Contract) -> ContextUsageMatrix {
+        let rows: Vec<FunctionContextUsage> = contract
+            .functions
+            .values()
+            .map(|function| FunctionContextUsage {
+                function: function.name().to_string(),
+                reads: Self::analyze_function(contract, function),
+            })
+            .filter(|row| !row.reads.is_empty())
+            .collect();
+
+        let mut columns: Vec<ContextRead> = Vec::new();
+        for read in rows.iter().flat_map(|row| &row.reads) {
+            if !columns.contains(read) {
+                columns.push(*read);
+            }
+        }
+        columns.sort_by_key(|read| read.label());
+
+        ContextUsageMatrix { columns, rows }
+    }
+}
+
+fn collect_reads<'a>(
+    contract: &'a Contract,
+    function: &'a Function,
+    reads: &mut Vec<ContextRead>,
+    visited: &mut HashSet<&'a str>,
+) {
+    if !visited.insert(function.name()) {
+        return;
+    }
+    for inst in function.body.blocks.values().flat_map(|b| &b.instructions) {
+        if let Some(read) = ContextRead::of(inst) {
+            if !reads.contains(&read) {
+                reads.push(read);
+            }
+        }
+        if let Instruction::Call {
+            target: CallTarget::Internal(callee),
+            ..
+        } = inst
+        {
+            if let Some(callee) = contract.functions.values().find(|f| f.name() == callee) {
+                collect_reads(contract, callee, reads, visited);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{IRBuilder, InstBuilderExt};
+    use crate::types::Type;
+
+    #[test]
+    fn test_reads_through_internal_calls_and_chain_support() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Lottery");
+
+        let mut check = contract_builder.function("_checkOrigin");
+        let mut entry = check.entry_block();
+        let origin = entry.tx_origin();
+        let sender = entry.msg_sender();
+        let same = entry.eq(origin, sender);
+        entry.require(same, "no contracts");
+        entry.return_void().unwrap();
+        check.build().unwrap();
+
+        let mut draw = contract_builder.function("draw");
+        let mut entry = draw.entry_block();
+        entry.call_internal("_checkOrigin", vec![]);
+        let seed = entry.block_prevrandao();
+        let now = entry.block_timestamp();
+        let mixed = entry.add(seed, now, Type::Uint(256));
+        entry.storage_store(0u32.into(), mixed);
+        entry.return_void().unwrap();
+        draw.build().unwrap();
+
+        let mut idle = contract_builder.function("idle");
+        idle.entry_block().return_void().unwrap();
+        idle.build().unwrap();
+
+        let contract = contract_builder.build().unwrap();
+        let matrix = ContextUsageAnalysis::analyze_contract(&contract);
+
+        let labels: Vec<&str> = matrix.columns.iter().map(|c| c.label()).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "block.prevrandao",
+                "block.timestamp",
+                "msg.sender",
+                "tx.origin"
+            ]
+        );
+        assert_eq!(matrix.rows.len(), 2, "{}", matrix);
+        let origin_readers: Vec<&str> = matrix
+            .readers(ContextRead::Variable(ContextVariable::TxOrigin))
+            .collect();
+        assert_eq!(origin_readers, vec!["_checkOrigin", "draw"]);
+
+        let rendered = matrix.to_string();
+        let header = rendered.lines().next().unwrap();
+        assert!(
+            header.starts_with("function      block.prevrandao"),
+            "{}",
+            rendered
+        );
+        assert!(rendered
+            .lines()
+            .any(|line| line.starts_with("_checkOrigin  -")));
+
+        assert!(matrix
+            .capability_issues(&ChainCapabilities::ethereum(), EvmVersion::Cancun)
+            .is_empty());
+        let issues = matrix.capability_issues(&ChainCapabilities::arbitrum(), EvmVersion::Cancun);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].function, "draw");
+        assert_eq!(issues[0].support, ContextSupport::Fixed(1));
+    }
+}
@@ -0,0 +1,534 @@
+use super::control_flow::ControlFlowGraph;
+use super::pass::{AnalysisID, AnalysisPass, Pass, PassManager};
+use crate::{
+    abi::AbiType,
+    block::{BlockId, Terminator},
+    contract::Contract,
+    function::Function,
+    instructions::{CallTarget, Instruction},
+    types::TypeRegistry,
+    values::{Constant, Value},
+    version::EvmVersion,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/* Prices an instruction for one execution target. Costs are static upper-bound estimates: storage
+ * and account accesses are priced cold, memory expansion is ignored, and dynamic lengths count as
+ * one word. */
+pub trait CostModel: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn instruction_cost(&self, inst: &Instruction) -> u64;
+
+    fn terminator_cost(&self, terminator: &Terminator) -> u64;
+
+    fn calldata_cost(&self, data: &[u8]) -> u64;
+}
+
+/* The price list the shipped models are built from. Field names follow the yellow-paper tiers
+ * where one exists. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct GasSchedule {
+    pub base: u64,
+    pub very_low: u64,
+    pub low: u64,
+    pub exp: u64,
+    pub exp_byte: u64,
+    pub keccak: u64,
+    pub keccak_word: u64,
+    pub memory_word: u64,
+    pub storage_read: u64,
+    pub storage_write: u64,
+    pub storage_clear: u64,
+    pub transient: u64,
+    pub account_access: u64,
+    pub call_value: u64,
+    pub create: u64,
+    pub selfdestruct: u64,
+    pub log: u64,
+    pub log_topic: u64,
+    pub log_word: u64,
+    pub precompile: u64,
+    pub jump: u64,
+    pub branch: u64,
+    pub calldata_zero_byte: u64,
+    pub calldata_nonzero_byte: u64,
+}
+
+impl Default for GasSchedule {
+    fn default() -> Self {
+        Self {
+            base: 2,
+            very_low: 3,
+            low: 5,
+            exp: 10,
+            exp_byte: 50,
+            keccak: 30,
+            keccak_word: 6,
+            memory_word: 3,
+            storage_read: 2_100,
+            storage_write: 22_100,
+            storage_clear: 5_000,
+            transient: 100,
+            account_access: 2_600,
+            call_value: 9_000,
+            create: 32_000,
+            selfdestruct: 5_000,
+            log: 375,
+            log_topic: 375,
+            log_word: 256,
+            precompile: 3_000,
+            jump: 8,
+            branch: 10,
+            calldata_zero_byte: 4,
+            calldata_nonzero_byte: 16,
+        }
+    }
+}
+
+impl GasSchedule {
+    fn instruction_cost(&self, inst: &Instruction) -> u64 {
+        match inst {
+            Instruction::Add { .. }
+            | Instruction::Sub { .. }
+            | Instruction::CheckedAdd { .. }
+            | Instruction::CheckedSub { .. }
+            | Instruction::And { .. }
+            | Instruction::Or { .. }
+            | Instruction::Xor { .. }
+            | Instruction::Not { .. }
+            | Instruction::Shl { .. }
+            | Instruction::Shr { .. }
+            | Instruction::Sar { .. }
+            | Instruction::Eq { .. }
+            | Instruction::Ne { .. }
+            | Instruction::Lt { .. }
+            | Instruction::Gt { .. }
+            | Instruction::Le { .. }
+            | Instruction::Ge { .. }
+            | Instruction::Slt { .. }
+            | Instruction::Sgt { .. }
+            | Instruction::Sle { .. }
+            | Instruction::Sge { .. }
+            | Instruction::Load { .. }
+            | Instruction::Store { .. } => self.very_low,
+            Instruction::Mul { .. }
+            | Instruction::Div { .. }
+            | Instruction::Mod { .. }
+            | Instruction::Sdiv { .. }
+            | Instruction::Smod { .. }
+            | Instruction::CheckedMul { .. }
+            | Instruction::CheckedDiv { .. } => self.low,
+            Instruction::Pow { exp, .. } => self.exp + self.exp_byte * exponent_bytes(exp),
+            Instruction::StorageLoad { .. } => self.storage_read,
+            Instruction::StorageStore { .. } => self.storage_write,
+            Instruction::StorageDelete { .. } => self.storage_clear,
+            Instruction::TransientLoad { .. } | Instruction::TransientStore { .. } => {
+                self.transient
+            }
+            Instruction::MappingLoad { .. } | Instruction::ArrayLoad { .. } => {
+                self.storage_read + self.keccak + 2 * self.keccak_word
+            }
+            Instruction::MappingStore { .. } | Instruction::ArrayStore { .. } => {
+                self.storage_write + self.keccak + 2 * self.keccak_word
+            }
+            Instruction::ArrayLength { .. } => self.storage_read,
+            Instruction::ArrayPush { .. } => 2 * self.storage_write + self.keccak,
+            Instruction::ArrayPop { .. } => {
+                self.storage_read + self.storage_clear + self.storage_write
+            }
+            Instruction::Call { target, value, .. } => match target {
+                CallTarget::Internal(_) => 2 * self.jump + self.very_low,
+                CallTarget::Builtin(_) => self.low,
+                CallTarget::External(_) | CallTarget::Library(_) => {
+                    self.account_access + value.as_ref().map_or(0, |_| self.call_value)
+                }
+            },
+            Instruction::DelegateCall { .. } | Instruction::StaticCall { .. } => {
+                self.account_access
+            }
+            Instruction::Create { .. } | Instruction::Create2 { .. } => self.create,
+            Instruction::Selfdestruct { .. } => self.selfdestruct + self.account_access,
+            Instruction::GetBalance { .. }
+            | Instruction::GetCode { .. }
+            | Instruction::GetCodeSize { .. }
+            | Instruction::GetCodeHash { .. } => self.account_access,
+            Instruction::GetContext { .. } => self.base,
+            Instruction::Keccak256 { .. } => self.keccak + self.keccak_word,
+            Instruction::Sha256 { .. }
+            | Instruction::Ripemd160 { .. }
+            | Instruction::EcRecover { .. } => self.precompile,
+            Instruction::EmitEvent { topics, data, .. } => {
+                self.log
+                    + self.log_topic * (topics.len() as u64 + 1)
+                    + self.log_word * data.len() as u64
+            }
+            Instruction::MemoryAlloc { .. } | Instruction::Allocate { .. } => self.memory_word,
+            Instruction::MemoryCopy { .. } => self.very_low + self.memory_word,
+            Instruction::Assert { .. } | Instruction::Require { .. } => self.branch,
+            Instruction::Assign { .. }
+            | Instruction::Phi { .. }
+            | Instruction::Cast { .. }
+            | Instruction::ZeroExtend { .. }
+            | Instruction::SignExtend { .. }
+            | Instruction::Truncate { .. } => 0,
+            _ => self.base,
+        }
+    }
+
+    fn terminator_cost(&self, terminator: &Terminator) -> u64 {
+        match terminator {
+            Terminator::Jump(..) => self.jump,
+            Terminator::Branch { .. } => self.branch,
+            Terminator::Switch { cases, .. } => self.branch * (cases.len() as u64).max(1),
+            Terminator::Return(_)
+            | Terminator::Revert(_)
+            | Terminator::Panic(_)
+            | Terminator::Invalid => 0,
+        }
+    }
+
+    fn calldata_cost(&self, data: &[u8]) -> u64 {
+        data.iter()
+            .map(|&byte| match byte {
+                0 => self.calldata_zero_byte,
+                _ => self.calldata_nonzero_byte,
+            })
+            .sum()
+    }
+}
+
+fn exponent_bytes(exp: &Value) -> u64 {
+    match exp {
+        Value::Constant(Constant::Uint(value, _)) => value.bits().div_ceil(8),
+        _ => 32,
+    }
+}
+
+/* Ethereum mainnet pricing for a given hard fork: account and storage access went cold/warm in
+ * Berlin, and calldata got cheaper in Istanbul. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvmCostModel {
+    evm_version: EvmVersion,
+    schedule: GasSchedule,
+}
+
+impl EvmCostModel {
+    pub fn new(evm_version: EvmVersion) -> Self {
+        let mut schedule = GasSchedule::default();
+        if evm_version < EvmVersion::Berlin {
+            schedule.storage_read = if evm_version >= EvmVersion::Istanbul {
+                800
+            } else {
+                200
+            };
+            schedule.storage_write = 20_000;
+            schedule.account_access = 700;
+        }
+        if evm_version < EvmVersion::Istanbul {
+            schedule.calldata_nonzero_byte = 68;
+        }
+        Self {
+            evm_version,
+            schedule,
+        }
+    }
+
+    pub fn evm_version(&self) -> EvmVersion {
+        self.evm_version
+    }
+
+    pub fn schedule(&self) -> &GasSchedule {
+        &self.schedule
+    }
+}
+
+impl Default for EvmCostModel {
+    fn default() -> Self {
+        Self::new(EvmVersion::Prague)
+    }
+}
+
+impl CostModel for EvmCostModel {
+    fn name(&self) -> &str {
+        "evm"
+    }
+
+    fn instruction_cost(&self, inst: &Instruction) -> u64 {
+        self.schedule.instruction_cost(inst)
+    }
+
+    fn terminator_cost(&self, terminator: &Terminator) -> u64 {
+        self.schedule.terminator_cost(terminator)
+    }
+
+    fn calldata_cost(&self, data: &[u8]) -> u64 {
+        self.schedule.calldata_cost(data)
+    }
+}
+
+/* A named schedule for targets that price differently from mainnet, typically rollups where
+ * calldata dominates and storage is discounted. Deserializes from a table of overrides; anything
+ * left out keeps the mainnet price. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomCostModel {
+    pub name: String,
+    #[serde(flatten)]
+    pub schedule: GasSchedule,
+}
+
+impl CustomCostModel {
+    pub fn new(name: impl Into<String>, schedule: GasSchedule) -> Self {
+        Self {
+            name: name.into(),
+            schedule,
+        }
+    }
+}
+
+impl CostModel for CustomCostModel {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn instruction_cost(&self, inst: &Instruction) -> u64 {
+        self.schedule.instruction_cost(inst)
+    }
+
+    fn terminator_cost(&self, terminator: &Terminator) -> u64 {
+        self.schedule.terminator_cost(terminator)
+    }
+
+    fn calldata_cost(&self, data: &[u8]) -> u64 {
+        self.schedule.calldata_cost(data)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GasEstimate {
+    pub model: String,
+    pub blocks: HashMap<BlockId, u64>,
+    /* Costliest path from the entry with every loop body counted once. */
+    pub worst_path: u64,
+    /* Selector plus one nonzero word per parameter. */
+    pub calldata: u64,
+}
+
+impl GasEstimate {
+    pub fn total(&self) -> u64 {
+        self.worst_path + self.calldata
+    }
+}
+
+/* Fills `estimated_gas` on every function and leaves per-instruction and per-block costs in the
+ * annotation table under `GasEstimationPass::ANALYSIS`. */
+pub struct GasEstimationPass {
+    model: Box<dyn CostModel>,
+}
+
+impl GasEstimationPass {
+    pub const ANALYSIS: AnalysisID = AnalysisID::Custom("gas");
+
+    pub fn new(model: impl CostModel + 'static) -> Self {
+        Self {
+            model: Box::new(model),
+        }
+    }
+
+    pub fn model(&self) -> &dyn CostModel {
+        self.model.as_ref()
+    }
+
+    pub fn estimate(&self, function: &Function) -> GasEstimate {
+        let blocks: HashMap<BlockId, u64> = function
+            .body
+            .blocks
+            .iter()
+            .map(|(&id, block)| {
+                let body: u64 = block
+                    .instructions
+                    .iter()
+                    .map(|inst| self.model.instruction_cost(inst))
+                    .sum();
+                (id, body + self.model.terminator_cost(&block.terminator))
+            })
+            .collect();
+
+        let cfg = ControlFlowGraph::build(function);
+        let mut memo = HashMap::new();
+        let worst_path = costliest_path(function.entry_block(), &cfg, &blocks, &mut memo);
+
+        /* Dynamic arguments are priced at their offset and length words; their contents are
+         * unknown until the call. */
+        let registry = TypeRegistry::default();
+        let args: usize = function
+            .signature
+            .params
+            .iter()
+            .map(|param| AbiType::from_ir(&param.param_type, &registry))
+            .map(|ty| match ty {
+                Some(ty) if ty.is_dynamic() => ty.head_size() + 32,
+                Some(ty) => ty.head_size(),
+                None => 32,
+            })
+            .sum();
+        let calldata = vec![0xffu8; 4 + args];
+
+        GasEstimate {
+            model: self.model.name().to_string(),
+            blocks,
+            worst_path,
+            calldata: self.model.calldata_cost(&calldata),
+        }
+    }
+}
+
+impl Default for GasEstimationPass {
+    fn default() -> Self {
+        Self::new(EvmCostModel::default())
+    }
+}
+
+fn costliest_path(
+    block: BlockId,
+    cfg: &ControlFlowGraph,
+    costs: &HashMap<BlockId, u64>,
+    memo: &mut HashMap<BlockId, u64>,
+) -> u64 {
+    if let Some(&cost) = memo.get(&block) {
+        return cost;
+    }
+    let tail = cfg
+        .successors(block)
+        .iter()
+        .filter(|&&succ| !cfg.is_back_edge(block, succ))
+        .map(|&succ| costliest_path(succ, cfg, costs, memo))
+        .max()
+        .unwrap_or(0);
+    let cost = costs.get(&block).copied().unwrap_or(0) + tail;
+    memo.insert(block, cost);
+    cost
+}
+
+impl Pass for GasEstimationPass {
+    fn name(&self) -> &'static str {
+        "gas-estimation"
+    }
+
+    fn description(&self) -> &'static str {
+        "Static gas estimate per function under a pluggable cost model"
+    }
+
+    fn run_on_contract(
+        &mut self,
+        contract: &mut Contract,
+        _manager: &mut PassManager,
+    ) -> Result<()> {
+        for function in contract.functions.values_mut() {
+            let estimate = self.estimate(function);
+            function.metadata.estimated_gas = Some(estimate.total());
+            for (id, block) in function.body.blocks.iter_mut() {
+                let costs: Vec<u64> = block
+                    .instructions
+                    .iter()
+                    .map(|inst| self.model.instruction_cost(inst))
+                    .collect();
+                let table = block
+                    .metadata
+                    .annotations
+                    .get_mut::<u64>(Self::ANALYSIS, &block.instructions);
+                for (index, cost) in costs.into_iter().enumerate() {
+                    table.set_instruction(index, cost);
+                }
+                table.set_block(estimate.blocks[id]);
+            }
+        }
+        Ok(())
+    }
+
+    fn preserved_analyses(&self) -> Vec<AnalysisID> {
+        vec![Self::ANALYSIS]
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl AnalysisPass for GasEstimationPass {
+    type Result = GasEstimate;
+
+    fn analyze(&mut self, function: &Function) -> Result<Self::Result> {
+        Ok(self.estimate(function))
+    }
+
+    fn analysis_id(&self) -> AnalysisID {
+        Self::ANALYSIS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_models_price_storage_by_target() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Counter");
+        contract_builder.state_variable("count", Type::Uint(256), 0);
+
+        let mut bump = contract_builder.function("bump");
+        let mut entry = bump.entry_block();
+        let count = entry.storage_load(BigUint::from(0u32));
+        let one = entry.constant_uint(1, 256);
+        let next = entry.add(count, one, Type::Uint(256));
+        entry.storage_store(BigUint::from(0u32), next);
+        entry.return_void().unwrap();
+        bump.build().unwrap();
+        let mut contract = contract_builder.build().unwrap();
+
+        let function = &contract.functions["bump"];
+        let mainnet = GasEstimationPass::default().estimate(function);
+        assert_eq!(mainnet.worst_path, 2_100 + 3 + 22_100);
+        assert_eq!(mainnet.calldata, 4 * 16);
+
+        let legacy = GasEstimationPass::new(EvmCostModel::new(EvmVersion::Byzantium));
+        assert_eq!(legacy.estimate(function).worst_path, 200 + 3 + 20_000);
+
+        let rollup = CustomCostModel::new(
+            "rollup",
+            GasSchedule {
+                storage_read: 100,
+                storage_write: 1_000,
+                calldata_nonzero_byte: 64,
+                ..GasSchedule::default()
+            },
+        );
+        let mut pass = GasEstimationPass::new(rollup);
+        let estimate = pass.estimate(function);
+        assert_eq!(estimate.model, "rollup");
+        assert_eq!(estimate.total(), 100 + 3 + 1_000 + 4 * 64);
+
+        pass.run_on_contract(&mut contract, &mut PassManager::new())
+            .unwrap();
+        let function = &contract.functions["bump"];
+        assert_eq!(function.metadata.estimated_gas, Some(estimate.total()));
+        let entry = &function.body.blocks[&function.entry_block()];
+        let table = entry
+            .metadata
+            .annotations
+            .get::<u64>(GasEstimationPass::ANALYSIS)
+            .unwrap();
+        assert_eq!(table.instruction(0), Some(&100));
+    }
+}
@@ -1,3 +1,4 @@
+pub use crate::cursor::{CursorPosition, IRCursor};
 use crate::{
     block::{BasicBlock, BlockId},
     function::Function,
@@ -5,43 +6,10 @@ use crate::{
 };
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum CursorPosition {
-    Nowhere,
-    At(BlockId, usize),
-    Before(BlockId),
-    After(BlockId),
-}
-
-pub trait IRCursor {
-    fn position(&self) -> CursorPosition;
-
-    fn set_position(&mut self, pos: CursorPosition);
-
-    fn current_block(&self) -> Option<BlockId>;
-
-    fn current_inst(&self) -> Option<&Instruction>;
-
-    fn goto_block(&mut self, block: BlockId);
-
-    fn goto_first_inst(&mut self, block: BlockId);
-
-    fn goto_last_inst(&mut self, block: BlockId);
-
-    fn next_inst(&mut self) -> Option<&Instruction>;
-
-    fn prev_inst(&mut self) -> Option<&Instruction>;
-
-    fn next_block(&mut self) -> Option<BlockId>;
-
-    fn prev_block(&mut self) -> Option<BlockId>;
-}
-
 pub struct ScannerCursor<'a> {
     function: &'a Function,
     position: CursorPosition,
     block_order: Vec<BlockId>,
-    block_idx: Option<usize>,
     cache: HashMap<String, Box<dyn std::any::Any>>,
 }
 
@@ -53,37 +21,22 @@ impl<'a> ScannerCursor<'a> {
             function,
             position: CursorPosition::Nowhere,
             block_order,
-            block_idx: None,
             cache: HashMap::new(),
         }
     }
 
     pub fn at_entry(function: &'a Function) -> Self {
         let mut cursor = Self::new(function);
-        cursor.goto_block(function.body.entry_block);
+        cursor.goto_top(function.body.entry_block);
         cursor
     }
 
-    pub fn current_block_ref(&self) -> Option<&BasicBlock> {
-        match self.position {
-            CursorPosition::At(block_id, _)
-            | CursorPosition::Before(block_id)
-            | CursorPosition::After(block_id) => self.function.body.blocks.get(&block_id),
-            CursorPosition::Nowhere => None,
-        }
-    }
-
     pub fn analyze_at<T>(&self, f: impl FnOnce(&Instruction) -> T) -> Option<T> {
         self.current_inst().map(f)
     }
 
     pub fn is_at_terminator(&self) -> bool {
-        if let Some(block) = self.current_block_ref() {
-            if let CursorPosition::At(_, idx) = self.position {
-                return idx == block.instructions.len();
-            }
-        }
-        false
+        matches!(self.position, CursorPosition::After(_))
     }
 
     pub fn traverse_dom_order(&mut self) -> DomTreeIterator<'a, '_> {
@@ -110,131 +63,14 @@ impl<'a> IRCursor for ScannerCursor<'a> {
 
     fn set_position(&mut self, pos: CursorPosition) {
         self.position = pos;
-
-        match pos {
-            CursorPosition::At(block_id, _)
-            | CursorPosition::Before(block_id)
-            | CursorPosition::After(block_id) => {
-                self.block_idx = self.block_order.iter().position(|&b| b == block_id);
-            }
-            CursorPosition::Nowhere => {
-                self.block_idx = None;
-            }
-        }
-    }
-
-    fn current_block(&self) -> Option<BlockId> {
-        match self.position {
-            CursorPosition::At(block, _)
-            | CursorPosition::Before(block)
-            | CursorPosition::After(block) => Some(block),
-            CursorPosition::Nowhere => None,
-        }
-    }
-
-    fn current_inst(&self) -> Option<&Instruction> {
-        match self.position {
-            CursorPosition::At(block_id, idx) => self
-                .function
-                .body
-                .blocks
-                .get(&block_id)
-                .and_then(|block| block.instructions.get(idx)),
-            _ => None,
-        }
     }
 
-    fn goto_block(&mut self, block: BlockId) {
-        self.set_position(CursorPosition::Before(block));
+    fn block(&self, id: BlockId) -> Option<&BasicBlock> {
+        self.function.body.blocks.get(&id)
     }
 
-    fn goto_first_inst(&mut self, block: BlockId) {
-        if let Some(block_ref) = self.function.body.blocks.get(&block) {
-            if !block_ref.instructions.is_empty() {
-                self.set_position(CursorPosition::At(block, 0));
-            } else {
-                self.set_position(CursorPosition::After(block));
-            }
-        }
-    }
-
-    fn goto_last_inst(&mut self, block: BlockId) {
-        if let Some(block_ref) = self.function.body.blocks.get(&block) {
-            let len = block_ref.instructions.len();
-            if len > 0 {
-                self.set_position(CursorPosition::At(block, len - 1));
-            } else {
-                self.set_position(CursorPosition::After(block));
-            }
-        }
-    }
-
-    fn next_inst(&mut self) -> Option<&Instruction> {
-        match self.position {
-            CursorPosition::Before(block) => {
-                self.goto_first_inst(block);
-                self.current_inst()
-            }
-            CursorPosition::At(block_id, idx) => {
-                if let Some(block) = self.function.body.blocks.get(&block_id) {
-                    let next_idx = idx + 1;
-                    if next_idx < block.instructions.len() {
-                        self.set_position(CursorPosition::At(block_id, next_idx));
-                        return Some(&block.instructions[next_idx]);
-                    } else {
-                        self.set_position(CursorPosition::After(block_id));
-                    }
-                }
-                None
-            }
-            _ => None,
-        }
-    }
-
-    fn prev_inst(&mut self) -> Option<&Instruction> {
-        match self.position {
-            CursorPosition::After(block) => {
-                self.goto_last_inst(block);
-                self.current_inst()
-            }
-            CursorPosition::At(block_id, idx) => {
-                if idx > 0 {
-                    let prev_idx = idx - 1;
-                    self.set_position(CursorPosition::At(block_id, prev_idx));
-                    if let Some(block) = self.function.body.blocks.get(&block_id) {
-                        return Some(&block.instructions[prev_idx]);
-                    }
-                } else {
-                    self.set_position(CursorPosition::Before(block_id));
-                }
-                None
-            }
-            _ => None,
-        }
-    }
-
-    fn next_block(&mut self) -> Option<BlockId> {
-        if let Some(idx) = self.block_idx {
-            let next_idx = idx + 1;
-            if next_idx < self.block_order.len() {
-                let next_block = self.block_order[next_idx];
-                self.goto_block(next_block);
-                return Some(next_block);
-            }
-        }
-        None
-    }
-
-    fn prev_block(&mut self) -> Option<BlockId> {
-        if let Some(idx) = self.block_idx {
-            if idx > 0 {
-                let prev_idx = idx - 1;
-                let prev_block = self.block_order[prev_idx];
-                self.goto_block(prev_block);
-                return Some(prev_block);
-            }
-        }
-        None
+    fn layout(&self) -> Vec<BlockId> {
+        self.block_order.clone()
     }
 }
 
@@ -280,7 +116,7 @@ impl<'a, 'c> Iterator for DomTreeIterator<'a, 'c> {
                     }
                 }
 
-                self.cursor.goto_block(block);
+                self.cursor.goto_top(block);
                 return Some(block);
             }
         }
@@ -291,17 +127,47 @@ impl<'a, 'c> Iterator for DomTreeIterator<'a, 'c> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
 
     #[test]
-    /*
-    Cursor navigation tests should verify:
-    - Positioning at block start/end
-    - Instruction-level navigation
-    - Insertion point management
-    - State consistency across moves
-    */
-    #[ignore]
-    fn test_cursor_navigation() {}
+    fn test_cursor_navigation() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Scan");
+        let mut func = contract_builder.function("f");
+        func.param("x", Type::Uint(256));
+        let x = func.get_param(0);
+        let mut entry = func.entry_block();
+        let doubled = entry.add(x.clone(), x.clone(), Type::Uint(256));
+        let check = entry.gt(doubled.clone(), x);
+        entry.require(check, "overflow");
+        entry.return_value(doubled).unwrap();
+        func.build().unwrap();
+        let contract = contract_builder.build().unwrap();
+        let function = &contract.functions["f"];
+
+        let mut cursor = ScannerCursor::at_entry(function);
+        assert_eq!(
+            cursor.position(),
+            CursorPosition::Before(function.body.entry_block)
+        );
+        assert!(matches!(cursor.next_inst(), Some(Instruction::Add { .. })));
+
+        let anchored = cursor.find_next(|inst| matches!(inst, Instruction::Require { .. }));
+        assert!(anchored.is_some());
+        assert!(matches!(cursor.window(), [Instruction::Require { .. }]));
+        assert!(cursor.next_inst().is_none());
+        assert!(cursor.is_at_terminator());
+        assert!(cursor.is_terminated());
+
+        assert!(cursor
+            .find_prev(|inst| matches!(inst, Instruction::Add { .. }))
+            .is_some());
+        assert_eq!(
+            cursor.analyze_at(|inst| inst.result().is_some()),
+            Some(true)
+        );
+    }
 
     /*
     Cache operation tests should verify:
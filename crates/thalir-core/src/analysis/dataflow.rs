@@ -1,4 +1,4 @@
-use crate::{block::BlockId, function::FunctionBody, instructions::Instruction, values::Value};
+use crate::{block::BlockId, function::FunctionBody, values::Value};
 use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone)]
@@ -58,14 +58,14 @@ impl DefUseChains {
                         .insert(result.clone());
                 }
 
-                for used_value in Self::get_used_values(inst) {
+                for used_value in inst.operands() {
                     uses.entry(used_value.clone())
                         .or_insert_with(HashSet::new)
                         .insert(loc.clone());
                     uses_at
                         .entry(loc.clone())
                         .or_insert_with(HashSet::new)
-                        .insert(used_value);
+                        .insert(used_value.clone());
                 }
             }
         }
@@ -78,34 +78,6 @@ impl DefUseChains {
         }
     }
 
-    fn get_used_values(inst: &Instruction) -> Vec<Value> {
-        let mut values = Vec::new();
-
-        match inst {
-            Instruction::Add { left, right, .. }
-            | Instruction::Sub { left, right, .. }
-            | Instruction::Mul { left, right, .. }
-            | Instruction::Div { left, right, .. }
-            | Instruction::Mod { left, right, .. } => {
-                values.push(left.clone());
-                values.push(right.clone());
-            }
-            Instruction::StorageStore { value, .. } => {
-                values.push(value.clone());
-            }
-            Instruction::Call { args, value, .. } => {
-                values.extend(args.clone());
-                if let Some(v) = value {
-                    values.push(v.clone());
-                }
-            }
-
-            _ => {}
-        }
-
-        values
-    }
-
     pub fn get_uses(&self, value: &Value) -> Option<&HashSet<Location>> {
         self.uses.get(value)
     }
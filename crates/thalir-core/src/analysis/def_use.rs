@@ -1,8 +1,9 @@
 use crate::{
     block::{BlockId, Terminator},
+    cursor::{Edit, EditListener},
     function::Function,
     instructions::Instruction,
-    values::ValueId,
+    values::{Value, ValueId},
 };
 use std::collections::{HashMap, HashSet};
 
@@ -12,6 +13,8 @@ pub struct DefUseChains {
     uses: HashMap<ValueId, Vec<Use>>,
     inst_defs: HashMap<(BlockId, usize), Vec<ValueId>>,
     inst_uses: HashMap<(BlockId, usize), Vec<ValueId>>,
+    term_uses: HashMap<BlockId, Vec<ValueId>>,
+    block_lens: HashMap<BlockId, usize>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -51,8 +54,11 @@ impl DefUseChains {
         let mut uses: HashMap<ValueId, Vec<Use>> = HashMap::new();
         let mut inst_defs = HashMap::new();
         let mut inst_uses = HashMap::new();
+        let mut term_uses = HashMap::new();
+        let mut block_lens = HashMap::new();
 
         for (&block_id, block) in &function.body.blocks {
+            block_lens.insert(block_id, block.instructions.len());
             for (idx, inst) in block.instructions.iter().enumerate() {
                 let inst_key = (block_id, idx);
 
@@ -69,10 +75,11 @@ impl DefUseChains {
                 inst_uses.insert(inst_key, used_values.keys().copied().collect());
             }
 
-            let term_uses = Self::extract_terminator_uses(&block.terminator, block_id);
-            for (value_id, use_site) in &term_uses {
+            let used_by_terminator = Self::extract_terminator_uses(&block.terminator, block_id);
+            for (value_id, use_site) in &used_by_terminator {
                 uses.entry(*value_id).or_default().push(use_site.clone());
             }
+            term_uses.insert(block_id, used_by_terminator.keys().copied().collect());
         }
 
         Self {
@@ -80,541 +87,197 @@ impl DefUseChains {
             uses,
             inst_defs,
             inst_uses,
+            term_uses,
+            block_lens,
         }
     }
 
-    fn extract_defs(
-        inst: &Instruction,
-        block: BlockId,
-        idx: usize,
-    ) -> HashMap<ValueId, Definition> {
-        let mut defs = HashMap::new();
-
-        match inst {
-            Instruction::Add { result, .. }
-            | Instruction::Sub { result, .. }
-            | Instruction::Mul { result, .. }
-            | Instruction::Div { result, .. }
-            | Instruction::Mod { result, .. }
-            | Instruction::Pow { result, .. }
-            | Instruction::CheckedAdd { result, .. }
-            | Instruction::CheckedSub { result, .. }
-            | Instruction::CheckedMul { result, .. }
-            | Instruction::CheckedDiv { result, .. }
-            | Instruction::And { result, .. }
-            | Instruction::Or { result, .. }
-            | Instruction::Xor { result, .. }
-            | Instruction::Not { result, .. }
-            | Instruction::Shl { result, .. }
-            | Instruction::Shr { result, .. }
-            | Instruction::Sar { result, .. }
-            | Instruction::Eq { result, .. }
-            | Instruction::Ne { result, .. }
-            | Instruction::Lt { result, .. }
-            | Instruction::Gt { result, .. }
-            | Instruction::Le { result, .. }
-            | Instruction::Ge { result, .. }
-            | Instruction::Load { result, .. }
-            | Instruction::StorageLoad { result, .. }
-            | Instruction::MappingLoad { result, .. }
-            | Instruction::ArrayLoad { result, .. }
-            | Instruction::ArrayLength { result, .. }
-            | Instruction::Allocate { result, .. }
-            | Instruction::Call { result, .. }
-            | Instruction::DelegateCall { result, .. }
-            | Instruction::Phi { result, .. } => {
-                if let Some(id) = result.as_register() {
-                    let def_kind = if matches!(inst, Instruction::Phi { .. }) {
-                        DefKind::Phi
-                    } else {
-                        DefKind::Instruction
-                    };
-
-                    defs.insert(
-                        id,
-                        Definition {
-                            block,
-                            instruction: idx,
-                            kind: def_kind,
-                        },
-                    );
-                }
+    /* Sites are keyed by (block, index), not by a stable identity, so an insert or remove
+     * renumbers every later instruction of that block. It is a rebuild of the block's tail, not
+     * a constant-time update; only the other blocks are spared a rescan. */
+    pub fn apply(&mut self, edit: Edit<'_>) {
+        match edit {
+            Edit::Inserted { block, index, inst } => {
+                let len = self.block_lens.get(&block).copied().unwrap_or(0);
+                for idx in (index..len).rev() {
+                    self.relocate(block, idx, idx + 1);
+                }
+                self.record(block, index, inst);
+                self.block_lens.insert(block, len + 1);
             }
-            _ => {}
-        }
-
-        defs
-    }
-
-    fn extract_uses(inst: &Instruction, block: BlockId, idx: usize) -> HashMap<ValueId, Use> {
-        let mut uses = HashMap::new();
-
-        match inst {
-            Instruction::Add { left, right, .. }
-            | Instruction::Sub { left, right, .. }
-            | Instruction::Mul { left, right, .. }
-            | Instruction::Div { left, right, .. }
-            | Instruction::Mod { left, right, .. } => {
-                if let Some(id) = left.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: idx,
-                            kind: UseKind::Operand,
-                        },
-                    );
-                }
-                if let Some(id) = right.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: idx,
-                            kind: UseKind::Operand,
-                        },
-                    );
+            Edit::Removed { block, index, .. } => {
+                let len = self.block_lens.get(&block).copied().unwrap_or(0);
+                self.forget(block, index);
+                for idx in index + 1..len {
+                    self.relocate(block, idx, idx - 1);
                 }
+                self.block_lens.insert(block, len.saturating_sub(1));
             }
-            Instruction::Pow { base, exp, .. } => {
-                if let Some(id) = base.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: idx,
-                            kind: UseKind::Operand,
-                        },
-                    );
-                }
-                if let Some(id) = exp.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: idx,
-                            kind: UseKind::Operand,
-                        },
-                    );
-                }
+            Edit::Replaced { block, index, inst } => {
+                self.forget(block, index);
+                self.record(block, index, inst);
             }
-
-            Instruction::CheckedAdd { left, right, .. }
-            | Instruction::CheckedSub { left, right, .. }
-            | Instruction::CheckedMul { left, right, .. }
-            | Instruction::CheckedDiv { left, right, .. } => {
-                if let Some(id) = left.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: idx,
-                            kind: UseKind::Operand,
-                        },
-                    );
-                }
-                if let Some(id) = right.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: idx,
-                            kind: UseKind::Operand,
-                        },
-                    );
-                }
-            }
-
-            Instruction::And { left, right, .. }
-            | Instruction::Or { left, right, .. }
-            | Instruction::Xor { left, right, .. } => {
-                if let Some(id) = left.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: idx,
-                            kind: UseKind::Operand,
-                        },
-                    );
-                }
-                if let Some(id) = right.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: idx,
-                            kind: UseKind::Operand,
-                        },
-                    );
-                }
-            }
-            Instruction::Not { operand, .. } => {
-                if let Some(id) = operand.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: idx,
-                            kind: UseKind::Operand,
-                        },
-                    );
-                }
-            }
-            Instruction::Shl { value, shift, .. }
-            | Instruction::Shr { value, shift, .. }
-            | Instruction::Sar { value, shift, .. } => {
-                if let Some(id) = value.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: idx,
-                            kind: UseKind::Operand,
-                        },
-                    );
-                }
-                if let Some(id) = shift.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: idx,
-                            kind: UseKind::Operand,
-                        },
-                    );
-                }
+            Edit::Terminator { block, terminator } => {
+                for value in self.term_uses.remove(&block).unwrap_or_default() {
+                    self.drop_use(value, block, usize::MAX);
+                }
+                let used = Self::extract_terminator_uses(terminator, block);
+                for (value_id, use_site) in &used {
+                    self.uses
+                        .entry(*value_id)
+                        .or_default()
+                        .push(use_site.clone());
+                }
+                self.term_uses.insert(block, used.keys().copied().collect());
             }
+        }
+    }
 
-            Instruction::Eq { left, right, .. }
-            | Instruction::Ne { left, right, .. }
-            | Instruction::Lt { left, right, .. }
-            | Instruction::Gt { left, right, .. }
-            | Instruction::Le { left, right, .. }
-            | Instruction::Ge { left, right, .. } => {
-                if let Some(id) = left.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: idx,
-                            kind: UseKind::Operand,
-                        },
-                    );
-                }
-                if let Some(id) = right.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: idx,
-                            kind: UseKind::Operand,
-                        },
-                    );
-                }
-            }
-            Instruction::Store {
-                value, location, ..
-            } => {
-                if let Some(id) = value.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: idx,
-                            kind: UseKind::StoreValue,
-                        },
-                    );
-                }
+    fn record(&mut self, block: BlockId, idx: usize, inst: &Instruction) {
+        let defs = Self::extract_defs(inst, block, idx);
+        for (value_id, def) in &defs {
+            self.definitions.insert(*value_id, def.clone());
+        }
+        self.inst_defs
+            .insert((block, idx), defs.keys().copied().collect());
+
+        let used_values = Self::extract_uses(inst, block, idx);
+        for (value_id, use_site) in &used_values {
+            self.uses
+                .entry(*value_id)
+                .or_default()
+                .push(use_site.clone());
+        }
+        self.inst_uses
+            .insert((block, idx), used_values.keys().copied().collect());
+    }
 
-                match location {
-                    crate::values::Location::Memory { base, offset } => {
-                        if let Some(id) = base.as_register() {
-                            uses.insert(
-                                id,
-                                Use {
-                                    block,
-                                    instruction: idx,
-                                    kind: UseKind::Address,
-                                },
-                            );
-                        }
-                        if let Some(id) = offset.as_register() {
-                            uses.insert(
-                                id,
-                                Use {
-                                    block,
-                                    instruction: idx,
-                                    kind: UseKind::Address,
-                                },
-                            );
-                        }
-                    }
-                    crate::values::Location::Storage { slot }
-                    | crate::values::Location::Calldata { offset: slot }
-                    | crate::values::Location::ReturnData { offset: slot } => {
-                        if let Some(id) = slot.as_register() {
-                            uses.insert(
-                                id,
-                                Use {
-                                    block,
-                                    instruction: idx,
-                                    kind: UseKind::Address,
-                                },
-                            );
-                        }
-                    }
-                    _ => {}
-                }
+    fn forget(&mut self, block: BlockId, idx: usize) {
+        for value in self.inst_defs.remove(&(block, idx)).unwrap_or_default() {
+            if self
+                .definitions
+                .get(&value)
+                .is_some_and(|def| def.block == block && def.instruction == idx)
+            {
+                self.definitions.remove(&value);
             }
-            Instruction::StorageStore { key, value } => {
-                match key {
-                    crate::instructions::StorageKey::Dynamic(v)
-                    | crate::instructions::StorageKey::Computed(v) => {
-                        if let Some(id) = v.as_register() {
-                            uses.insert(
-                                id,
-                                Use {
-                                    block,
-                                    instruction: idx,
-                                    kind: UseKind::Address,
-                                },
-                            );
-                        }
-                    }
-                    crate::instructions::StorageKey::MappingKey { key: k, .. }
-                    | crate::instructions::StorageKey::ArrayElement { index: k, .. } => {
-                        if let Some(id) = k.as_register() {
-                            uses.insert(
-                                id,
-                                Use {
-                                    block,
-                                    instruction: idx,
-                                    kind: UseKind::Address,
-                                },
-                            );
-                        }
-                    }
-                    _ => {}
-                }
+        }
+        for value in self.inst_uses.remove(&(block, idx)).unwrap_or_default() {
+            self.drop_use(value, block, idx);
+        }
+    }
 
-                if let Some(id) = value.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: idx,
-                            kind: UseKind::StoreValue,
-                        },
-                    );
-                }
-            }
-            Instruction::Load { location, .. } => match location {
-                crate::values::Location::Memory { base, offset } => {
-                    if let Some(id) = base.as_register() {
-                        uses.insert(
-                            id,
-                            Use {
-                                block,
-                                instruction: idx,
-                                kind: UseKind::Address,
-                            },
-                        );
-                    }
-                    if let Some(id) = offset.as_register() {
-                        uses.insert(
-                            id,
-                            Use {
-                                block,
-                                instruction: idx,
-                                kind: UseKind::Address,
-                            },
-                        );
-                    }
-                }
-                crate::values::Location::Storage { slot }
-                | crate::values::Location::Calldata { offset: slot }
-                | crate::values::Location::ReturnData { offset: slot } => {
-                    if let Some(id) = slot.as_register() {
-                        uses.insert(
-                            id,
-                            Use {
-                                block,
-                                instruction: idx,
-                                kind: UseKind::Address,
-                            },
-                        );
-                    }
-                }
-                _ => {}
-            },
-            Instruction::StorageLoad { key, .. } => match key {
-                crate::instructions::StorageKey::Dynamic(v)
-                | crate::instructions::StorageKey::Computed(v) => {
-                    if let Some(id) = v.as_register() {
-                        uses.insert(
-                            id,
-                            Use {
-                                block,
-                                instruction: idx,
-                                kind: UseKind::Address,
-                            },
-                        );
-                    }
-                }
-                crate::instructions::StorageKey::MappingKey { key: k, .. }
-                | crate::instructions::StorageKey::ArrayElement { index: k, .. } => {
-                    if let Some(id) = k.as_register() {
-                        uses.insert(
-                            id,
-                            Use {
-                                block,
-                                instruction: idx,
-                                kind: UseKind::Address,
-                            },
-                        );
-                    }
-                }
-                _ => {}
-            },
-            Instruction::Return {
-                value: Some(val), ..
-            } => {
-                if let Some(id) = val.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: idx,
-                            kind: UseKind::Return,
-                        },
-                    );
-                }
+    fn drop_use(&mut self, value: ValueId, block: BlockId, idx: usize) {
+        if let Some(sites) = self.uses.get_mut(&value) {
+            sites.retain(|site| site.block != block || site.instruction != idx);
+            if sites.is_empty() {
+                self.uses.remove(&value);
             }
+        }
+    }
 
-            Instruction::Call { args, .. } | Instruction::DelegateCall { args, .. } => {
-                for arg in args {
-                    if let Some(id) = arg.as_register() {
-                        uses.insert(
-                            id,
-                            Use {
-                                block,
-                                instruction: idx,
-                                kind: UseKind::Operand,
-                            },
-                        );
+    fn relocate(&mut self, block: BlockId, from: usize, to: usize) {
+        if let Some(defined) = self.inst_defs.remove(&(block, from)) {
+            for value in &defined {
+                if let Some(def) = self.definitions.get_mut(value) {
+                    if def.block == block && def.instruction == from {
+                        def.instruction = to;
                     }
                 }
             }
-            Instruction::Phi { values, .. } => {
-                for (_, value) in values {
-                    if let Some(id) = value.as_register() {
-                        uses.insert(
-                            id,
-                            Use {
-                                block,
-                                instruction: idx,
-                                kind: UseKind::Operand,
-                            },
-                        );
+            self.inst_defs.insert((block, to), defined);
+        }
+        if let Some(used) = self.inst_uses.remove(&(block, from)) {
+            for value in &used {
+                for site in self.uses.get_mut(value).into_iter().flatten() {
+                    if site.block == block && site.instruction == from {
+                        site.instruction = to;
                     }
                 }
             }
-            _ => {}
+            self.inst_uses.insert((block, to), used);
         }
+    }
 
-        uses
+    fn extract_defs(
+        inst: &Instruction,
+        block: BlockId,
+        idx: usize,
+    ) -> HashMap<ValueId, Definition> {
+        let kind = if matches!(inst, Instruction::Phi { .. }) {
+            DefKind::Phi
+        } else {
+            DefKind::Instruction
+        };
+        inst.result()
+            .and_then(Value::as_register)
+            .map(|id| {
+                (
+                    id,
+                    Definition {
+                        block,
+                        instruction: idx,
+                        kind,
+                    },
+                )
+            })
+            .into_iter()
+            .collect()
     }
 
-    fn extract_terminator_uses(terminator: &Terminator, block: BlockId) -> HashMap<ValueId, Use> {
+    /* Calls read their target address and value as much as their arguments, so only stores and
+     * loads single out the operands they address through. */
+    fn extract_uses(inst: &Instruction, block: BlockId, idx: usize) -> HashMap<ValueId, Use> {
+        let operands = inst.operands();
+        let stored = operands.len().saturating_sub(1);
         let mut uses = HashMap::new();
-
-        match terminator {
-            Terminator::Return(Some(value)) => {
-                if let Some(id) = value.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: usize::MAX,
-                            kind: UseKind::Operand,
-                        },
-                    );
-                }
-            }
-            Terminator::Branch {
-                condition,
-                then_args,
-                else_args,
-                ..
-            } => {
-                if let Some(id) = condition.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: usize::MAX,
-                            kind: UseKind::Operand,
-                        },
-                    );
-                }
-                for arg in then_args.iter().chain(else_args.iter()) {
-                    if let Some(id) = arg.as_register() {
-                        uses.insert(
-                            id,
-                            Use {
-                                block,
-                                instruction: usize::MAX,
-                                kind: UseKind::Operand,
-                            },
-                        );
-                    }
-                }
-            }
-            Terminator::Jump(_, args) => {
-                for arg in args {
-                    if let Some(id) = arg.as_register() {
-                        uses.insert(
-                            id,
-                            Use {
-                                block,
-                                instruction: usize::MAX,
-                                kind: UseKind::Operand,
-                            },
-                        );
-                    }
-                }
-            }
-            Terminator::Switch { value, cases, .. } => {
-                if let Some(id) = value.as_register() {
-                    uses.insert(
-                        id,
-                        Use {
-                            block,
-                            instruction: usize::MAX,
-                            kind: UseKind::Operand,
-                        },
-                    );
-                }
-                for (case_val, _) in cases {
-                    if let Some(id) = case_val.as_register() {
-                        uses.insert(
-                            id,
-                            Use {
-                                block,
-                                instruction: usize::MAX,
-                                kind: UseKind::Operand,
-                            },
-                        );
+        for (position, value) in operands.into_iter().enumerate() {
+            let Some(id) = value.as_register() else {
+                continue;
+            };
+            let kind = match inst {
+                Instruction::Load { .. }
+                | Instruction::StorageLoad { .. }
+                | Instruction::TransientLoad { .. } => UseKind::Address,
+                Instruction::Store { .. }
+                | Instruction::StorageStore { .. }
+                | Instruction::TransientStore { .. } => {
+                    if position == stored {
+                        UseKind::StoreValue
+                    } else {
+                        UseKind::Address
                     }
                 }
-            }
-            _ => {}
+                Instruction::Return { .. } => UseKind::Return,
+                _ => UseKind::Operand,
+            };
+            uses.entry(id).or_insert(Use {
+                block,
+                instruction: idx,
+                kind,
+            });
         }
-
         uses
     }
 
+    fn extract_terminator_uses(terminator: &Terminator, block: BlockId) -> HashMap<ValueId, Use> {
+        terminator
+            .operands()
+            .into_iter()
+            .filter_map(Value::as_register)
+            .map(|id| {
+                (
+                    id,
+                    Use {
+                        block,
+                        instruction: usize::MAX,
+                        kind: UseKind::Operand,
+                    },
+                )
+            })
+            .collect()
+    }
+
     pub fn get_def(&self, value: ValueId) -> Option<&Definition> {
         self.definitions.get(&value)
     }
@@ -659,6 +322,12 @@ impl DefUseChains {
     }
 }
 
+impl EditListener for DefUseChains {
+    fn on_edit(&mut self, edit: Edit<'_>) {
+        self.apply(edit);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -708,4 +377,81 @@ mod tests {
             "v4 should not be dead (used in return)"
         );
     }
+
+    fn snapshot(
+        chains: &DefUseChains,
+    ) -> (
+        HashMap<ValueId, Definition>,
+        HashMap<ValueId, HashSet<Use>>,
+        HashMap<(BlockId, usize), HashSet<ValueId>>,
+    ) {
+        let uses = chains
+            .uses
+            .iter()
+            .map(|(value, sites)| (*value, sites.iter().cloned().collect()))
+            .collect();
+        let inst_uses = chains
+            .inst_uses
+            .iter()
+            .filter(|(_, used)| !used.is_empty())
+            .map(|(key, used)| (*key, used.iter().copied().collect()))
+            .collect();
+        (chains.definitions.clone(), uses, inst_uses)
+    }
+
+    #[test]
+    fn test_incremental_edits_match_rebuild() {
+        use crate::cursor::{FuncCursor, IRCursor, IRCursorMut};
+        use crate::types::Type;
+        use crate::values::{TempId, Value};
+
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("TestContract");
+        let mut func_builder = contract_builder.function("test");
+        func_builder.param("a", Type::Uint(256));
+        let a = func_builder.get_param(0);
+        let mut entry_builder = func_builder.entry_block();
+        let x = entry_builder.add(a.clone(), a.clone(), Type::Uint(256));
+        let y = entry_builder.mul(x.clone(), a.clone(), Type::Uint(256));
+        let z = entry_builder.sub(y.clone(), x.clone(), Type::Uint(256));
+        entry_builder.return_value(z.clone()).unwrap();
+        let mut function = func_builder.build().unwrap();
+
+        let entry = function.body.entry_block;
+        let mut chains = DefUseChains::build(&function);
+        {
+            let mut cursor = FuncCursor::new(&mut function).with_listener(&mut chains);
+            let extra = Value::Temp(TempId(1000));
+
+            cursor.goto_inst(entry, 1);
+            cursor
+                .insert_inst(Instruction::Add {
+                    result: extra.clone(),
+                    left: x.clone(),
+                    right: x,
+                    ty: Type::Uint(256),
+                })
+                .unwrap();
+
+            cursor.goto_inst(entry, 3);
+            cursor.replace_inst(Instruction::Sub {
+                result: z,
+                left: y,
+                right: extra,
+                ty: Type::Uint(256),
+            });
+
+            cursor.goto_inst(entry, 0);
+            cursor
+                .insert_inst(Instruction::Not {
+                    result: Value::Temp(TempId(1001)),
+                    operand: a,
+                })
+                .unwrap();
+            cursor.goto_inst(entry, 0);
+            cursor.remove_inst();
+        }
+
+        assert_eq!(snapshot(&chains), snapshot(&DefUseChains::build(&function)));
+    }
 }
@@ -0,0 +1,85 @@
+use super::callbacks::CallbackAnalysis;
+use super::division_guard::DivisionGuardAnalysis;
+use super::event_consistency::EventConsistencyAnalysis;
+use super::findings::Finding;
+use super::inheritance::InheritanceAnalysis;
+use super::mapping_roles::MappingRoleAnalysis;
+use super::pause::PauseAnalysis;
+use super::precision_loss::PrecisionLossAnalysis;
+use super::randomness::RandomnessAnalysis;
+use super::selector_collision::SelectorCollisionAnalysis;
+use super::signature_replay::SignatureReplayAnalysis;
+use super::specification::SpecificationAnalysis;
+use super::storage_usage::StorageUsageAnalysis;
+use super::time_dependence::TimeDependenceAnalysis;
+use super::unused_values::UnusedValueAnalysis;
+use crate::contract::Contract;
+
+type ContractDetector = fn(&Contract) -> Vec<Finding>;
+
+/* Built-in detectors that run one contract at a time, by the name their findings carry and
+ * `[passes]` in thalir.toml refers to. */
+pub const CONTRACT_DETECTORS: &[(&str, ContractDetector)] = &[
+    (
+        DivisionGuardAnalysis::DETECTOR,
+        DivisionGuardAnalysis::findings,
+    ),
+    (
+        PrecisionLossAnalysis::DETECTOR,
+        PrecisionLossAnalysis::findings,
+    ),
+    (
+        SignatureReplayAnalysis::DETECTOR,
+        SignatureReplayAnalysis::findings,
+    ),
+    (
+        SpecificationAnalysis::DETECTOR,
+        SpecificationAnalysis::findings,
+    ),
+    (MappingRoleAnalysis::DETECTOR, MappingRoleAnalysis::findings),
+    (
+        EventConsistencyAnalysis::DETECTOR,
+        EventConsistencyAnalysis::findings,
+    ),
+    (PauseAnalysis::DETECTOR, PauseAnalysis::findings),
+    (
+        TimeDependenceAnalysis::DETECTOR,
+        TimeDependenceAnalysis::findings,
+    ),
+    (RandomnessAnalysis::DETECTOR, RandomnessAnalysis::findings),
+    (UnusedValueAnalysis::DETECTOR, UnusedValueAnalysis::findings),
+    (CallbackAnalysis::DETECTOR, CallbackAnalysis::findings),
+];
+
+/* Names of the built-in detectors that look across all contracts at once. */
+pub const WORKSPACE_DETECTORS: &[&str] = &[
+    InheritanceAnalysis::SHADOWING,
+    InheritanceAnalysis::STORAGE_GAP,
+    SelectorCollisionAnalysis::DETECTOR,
+    StorageUsageAnalysis::DETECTOR,
+];
+
+/* Runs every enabled built-in detector over `contracts`, workspace-wide checks included.
+ * Signature packs need a database and are matched separately. */
+pub fn run_detectors(contracts: &[Contract], enabled: impl Fn(&str) -> bool) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for contract in contracts {
+        for &(name, detect) in CONTRACT_DETECTORS {
+            if enabled(name) {
+                findings.extend(detect(contract));
+            }
+        }
+    }
+    findings.extend(
+        InheritanceAnalysis::findings(contracts)
+            .into_iter()
+            .filter(|finding| enabled(&finding.detector)),
+    );
+    if enabled(SelectorCollisionAnalysis::DETECTOR) {
+        findings.extend(SelectorCollisionAnalysis::findings(contracts));
+    }
+    if enabled(StorageUsageAnalysis::DETECTOR) {
+        findings.extend(StorageUsageAnalysis::findings(contracts));
+    }
+    findings
+}
@@ -0,0 +1,610 @@
+use super::control_flow::ControlFlowGraph;
+use super::dominator::DominatorTree;
+use super::findings::{Finding, Severity};
+use super::util::storage_key_slot;
+use crate::{
+    block::{BlockId, Terminator},
+    contract::Contract,
+    function::Function,
+    instructions::{CallTarget, Instruction, StorageKey},
+    metadata::InstructionLocation,
+    types::Type,
+    values::{Constant, Value},
+};
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum DivisorInfluence {
+    Internal,
+    Storage,
+    External,
+}
+
+impl fmt::Display for DivisorInfluence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DivisorInfluence::Internal => write!(f, "computed internally"),
+            DivisorInfluence::Storage => write!(f, "read from storage"),
+            DivisorInfluence::External => write!(f, "externally controlled"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnguardedDivision {
+    pub function: String,
+    pub location: InstructionLocation,
+    pub operation: String,
+    pub influence: DivisorInfluence,
+}
+
+impl UnguardedDivision {
+    pub fn severity(&self) -> Severity {
+        match self.influence {
+            DivisorInfluence::External => Severity::High,
+            DivisorInfluence::Storage => Severity::Medium,
+            DivisorInfluence::Internal => Severity::Low,
+        }
+    }
+
+    pub fn to_finding(&self, contract: &str) -> Finding {
+        Finding::new(
+            DivisionGuardAnalysis::DETECTOR,
+            self.severity(),
+            contract,
+            &self.function,
+            format!(
+                "{} by a divisor that is not proven nonzero ({})",
+                self.operation, self.influence
+            ),
+        )
+        .at(self.location.clone())
+    }
+}
+
+pub struct DivisionGuardAnalysis;
+
+impl DivisionGuardAnalysis {
+    pub const DETECTOR: &'static str = "unguarded-division";
+
+    pub fn analyze_contract(contract: &Contract) -> Vec<UnguardedDivision> {
+        let mut results: Vec<UnguardedDivision> = contract
+            .functions
+            .values()
+            .flat_map(Self::analyze_function)
+            .collect();
+        results.sort_by(|a, b| b.influence.cmp(&a.influence));
+        results
+    }
+
+    pub fn analyze_function(function: &Function) -> Vec<UnguardedDivision> {
+        let facts = DivisorFacts::collect(function);
+        let mut results = Vec::new();
+
+        for (&block_id, block) in &function.body.blocks {
+            for (index, inst) in block.instructions.iter().enumerate() {
+                let (operation, divisor) = match inst {
+                    Instruction::Div { right, .. } | Instruction::CheckedDiv { right, .. } => {
+                        ("division", right)
+                    }
+                    Instruction::Sdiv { right, .. } => ("signed division", right),
+                    Instruction::Mod { right, .. } => ("modulo", right),
+                    Instruction::Smod { right, .. } => ("signed modulo", right),
+                    _ => continue,
+                };
+
+                let at = (block_id, index);
+                if !facts
+                    .interval(divisor, at, &mut HashSet::new())
+                    .contains_zero()
+                {
+                    continue;
+                }
+
+                results.push(UnguardedDivision {
+                    function: function.name().to_string(),
+                    location: InstructionLocation {
+                        block: block_id,
+                        index,
+                    },
+                    operation: operation.to_string(),
+                    influence: facts.influence(divisor),
+                });
+            }
+        }
+
+        results
+    }
+
+    pub fn findings(contract: &Contract) -> Vec<Finding> {
+        Self::analyze_contract(contract)
+            .iter()
+            .map(|division| division.to_finding(&contract.name))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Interval {
+    lo: BigUint,
+    hi: Option<BigUint>,
+}
+
+impl Interval {
+    fn unknown() -> Self {
+        Self {
+            lo: BigUint::zero(),
+            hi: None,
+        }
+    }
+
+    fn exact(value: BigUint) -> Self {
+        Self {
+            lo: value.clone(),
+            hi: Some(value),
+        }
+    }
+
+    fn contains_zero(&self) -> bool {
+        self.lo.is_zero()
+    }
+
+    fn at_least(mut self, bound: BigUint) -> Self {
+        if bound > self.lo {
+            self.lo = bound;
+        }
+        self
+    }
+
+    fn add(&self, other: &Interval) -> Self {
+        Self {
+            lo: &self.lo + &other.lo,
+            hi: self.hi.as_ref().zip(other.hi.as_ref()).map(|(a, b)| a + b),
+        }
+    }
+
+    fn mul(&self, other: &Interval) -> Self {
+        Self {
+            lo: &self.lo * &other.lo,
+            hi: self.hi.as_ref().zip(other.hi.as_ref()).map(|(a, b)| a * b),
+        }
+    }
+
+    /* Unchecked arithmetic wraps, so a bound only survives when the whole interval provably fits
+     * in the result type. */
+    fn wrapping(self, ty: &Type) -> Self {
+        let fits = match (ty, &self.hi) {
+            (Type::Uint(bits), Some(hi)) => hi.bits() <= u64::from(*bits),
+            _ => false,
+        };
+        if fits {
+            self
+        } else {
+            Self::unknown()
+        }
+    }
+
+    fn union(&self, other: &Interval) -> Self {
+        Self {
+            lo: self.lo.clone().min(other.lo.clone()),
+            hi: self
+                .hi
+                .as_ref()
+                .zip(other.hi.as_ref())
+                .map(|(a, b)| a.max(b).clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Origin<'a> {
+    Slot(BigUint),
+    Value(&'a Value),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum GuardScope {
+    After(BlockId, usize),
+    Region(BlockId),
+}
+
+struct Guard<'a> {
+    condition: &'a Value,
+    holds: bool,
+    scope: GuardScope,
+}
+
+struct DivisorFacts<'a> {
+    defs: HashMap<&'a Value, &'a Instruction>,
+    guards: Vec<Guard<'a>>,
+    dominators: DominatorTree,
+}
+
+impl<'a> DivisorFacts<'a> {
+    fn collect(function: &'a Function) -> Self {
+        let cfg = ControlFlowGraph::build(function);
+        let mut defs = HashMap::new();
+        let mut guards = Vec::new();
+
+        for (&block_id, block) in &function.body.blocks {
+            for (index, inst) in block.instructions.iter().enumerate() {
+                if let Some(result) = inst.result() {
+                    defs.insert(result, inst);
+                }
+                if let Instruction::Require { condition, .. }
+                | Instruction::Assert { condition, .. } = inst
+                {
+                    guards.push(Guard {
+                        condition,
+                        holds: true,
+                        scope: GuardScope::After(block_id, index),
+                    });
+                }
+            }
+
+            if let Terminator::Branch {
+                condition,
+                then_block,
+                else_block,
+                ..
+            } = &block.terminator
+            {
+                if then_block == else_block {
+                    continue;
+                }
+                for (target, holds) in [(*then_block, true), (*else_block, false)] {
+                    if cfg.predecessors(target) == [block_id] {
+                        guards.push(Guard {
+                            condition,
+                            holds,
+                            scope: GuardScope::Region(target),
+                        });
+                    }
+                }
+            }
+        }
+
+        Self {
+            defs,
+            guards,
+            dominators: DominatorTree::build(function),
+        }
+    }
+
+    fn applies(&self, scope: GuardScope, (block, index): (BlockId, usize)) -> bool {
+        match scope {
+            GuardScope::After(guard_block, guard_index) => {
+                (guard_block == block && guard_index < index)
+                    || (guard_block != block && self.dominators.dominates(guard_block, block))
+            }
+            GuardScope::Region(region) => self.dominators.dominates(region, block),
+        }
+    }
+
+    fn origin(&self, value: &'a Value) -> Origin<'a> {
+        match self.defs.get(value) {
+            Some(Instruction::StorageLoad {
+                key: key @ StorageKey::Slot(_),
+                ..
+            }) => match storage_key_slot(key) {
+                Some(slot) => Origin::Slot(slot),
+                None => Origin::Value(value),
+            },
+            _ => Origin::Value(value),
+        }
+    }
+
+    fn interval(
+        &self,
+        value: &'a Value,
+        at: (BlockId, usize),
+        visiting: &mut HashSet<&'a Value>,
+    ) -> Interval {
+        let base = match value {
+            Value::Constant(Constant::Uint(n, _)) => Interval::exact(n.clone()),
+            Value::Constant(Constant::Int(n, _)) => Interval::exact(n.magnitude().clone()),
+            _ if !visiting.insert(value) => Interval::unknown(),
+            _ => {
+                let interval = match self.defs.get(value).copied() {
+                    Some(Instruction::CheckedAdd { left, right, .. }) => self
+                        .interval(left, at, visiting)
+                        .add(&self.interval(right, at, visiting)),
+                    Some(Instruction::Add {
+                        left, right, ty, ..
+                    }) => self
+                        .interval(left, at, visiting)
+                        .add(&self.interval(right, at, visiting))
+                        .wrapping(ty),
+                    Some(Instruction::CheckedMul { left, right, .. }) => self
+                        .interval(left, at, visiting)
+                        .mul(&self.interval(right, at, visiting)),
+                    Some(Instruction::Mul {
+                        left, right, ty, ..
+                    }) => self
+                        .interval(left, at, visiting)
+                        .mul(&self.interval(right, at, visiting))
+                        .wrapping(ty),
+                    Some(Instruction::Select {
+                        then_val, else_val, ..
+                    }) => self
+                        .interval(then_val, at, visiting)
+                        .union(&self.interval(else_val, at, visiting)),
+                    Some(Instruction::Assign { value: source, .. }) => {
+                        self.interval(source, at, visiting)
+                    }
+                    _ => Interval::unknown(),
+                };
+                visiting.remove(value);
+                interval
+            }
+        };
+
+        self.refine(value, base, at)
+    }
+
+    fn refine(&self, value: &'a Value, mut interval: Interval, at: (BlockId, usize)) -> Interval {
+        let origin = self.origin(value);
+        for guard in &self.guards {
+            if !self.applies(guard.scope, at) {
+                continue;
+            }
+            if let Some(bound) = self.lower_bound(guard.condition, guard.holds, &origin) {
+                interval = interval.at_least(bound);
+            }
+        }
+        interval
+    }
+
+    fn lower_bound(
+        &self,
+        condition: &'a Value,
+        holds: bool,
+        origin: &Origin<'a>,
+    ) -> Option<BigUint> {
+        let inst = *self.defs.get(condition)?;
+        let is_divisor = |operand: &'a Value| self.origin(operand) == *origin;
+        let constant = |operand: &Value| match operand {
+            Value::Constant(Constant::Uint(n, _)) => Some(n.clone()),
+            _ => None,
+        };
+
+        match (inst, holds) {
+            (Instruction::Not { operand, .. }, _) => self.lower_bound(operand, !holds, origin),
+            (Instruction::Ne { left, right, .. }, true)
+            | (Instruction::Eq { left, right, .. }, false) => {
+                let zero_other = |other: &Value| constant(other).is_some_and(|n| n.is_zero());
+                if (is_divisor(left) && zero_other(right))
+                    || (is_divisor(right) && zero_other(left))
+                {
+                    Some(BigUint::one())
+                } else {
+                    None
+                }
+            }
+            (Instruction::Eq { left, right, .. }, true) => {
+                if is_divisor(left) {
+                    constant(right)
+                } else if is_divisor(right) {
+                    constant(left)
+                } else {
+                    None
+                }
+            }
+            (Instruction::Gt { left, right, .. } | Instruction::Sgt { left, right, .. }, true)
+            | (
+                Instruction::Lt {
+                    left: right,
+                    right: left,
+                    ..
+                }
+                | Instruction::Slt {
+                    left: right,
+                    right: left,
+                    ..
+                },
+                true,
+            ) => {
+                if is_divisor(left) {
+                    constant(right).map(|n| n + 1u32)
+                } else {
+                    None
+                }
+            }
+            (Instruction::Ge { left, right, .. } | Instruction::Sge { left, right, .. }, true)
+            | (
+                Instruction::Le {
+                    left: right,
+                    right: left,
+                    ..
+                }
+                | Instruction::Sle {
+                    left: right,
+                    right: left,
+                    ..
+                },
+                true,
+            ) => {
+                if is_divisor(left) {
+                    constant(right)
+                } else {
+                    None
+                }
+            }
+            (Instruction::Le { left, right, .. }, false) => {
+                if is_divisor(left) {
+                    constant(right).map(|n| n + 1u32)
+                } else {
+                    None
+                }
+            }
+            (Instruction::Lt { left, right, .. }, false) => {
+                if is_divisor(left) {
+                    constant(right)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn influence(&self, divisor: &'a Value) -> DivisorInfluence {
+        let mut influence = DivisorInfluence::Internal;
+        let mut visited = HashSet::new();
+        let mut worklist = vec![divisor];
+
+        while let Some(value) = worklist.pop() {
+            if !visited.insert(value) {
+                continue;
+            }
+            if matches!(value, Value::Param(_)) {
+                return DivisorInfluence::External;
+            }
+            let inst = match self.defs.get(value) {
+                Some(inst) => *inst,
+                None => continue,
+            };
+            match inst {
+                Instruction::GetContext { .. }
+                | Instruction::Call {
+                    target: CallTarget::External(_),
+                    ..
+                }
+                | Instruction::DelegateCall { .. }
+                | Instruction::StaticCall { .. } => return DivisorInfluence::External,
+                Instruction::StorageLoad { .. }
+                | Instruction::MappingLoad { .. }
+                | Instruction::ArrayLoad { .. } => {
+                    influence = influence.max(DivisorInfluence::Storage);
+                }
+                _ => {}
+            }
+            worklist.extend(inst.operands());
+        }
+
+        influence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+
+    #[test]
+    fn test_required_divisor_is_not_reported() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Pool");
+        contract_builder.state_variable("shares", Type::Uint(256), 0);
+
+        let mut guarded = contract_builder.function("price");
+        guarded.param("amount", Type::Uint(256));
+        let amount = guarded.get_param(0);
+        let mut entry = guarded.entry_block();
+        let shares = entry.storage_load(BigUint::from(0u32));
+        let zero = entry.constant_uint(0, 256);
+        let positive = entry.gt(shares, zero);
+        entry.require(positive, "no shares");
+        let shares = entry.storage_load(BigUint::from(0u32));
+        let quotient = entry.div(amount, shares, Type::Uint(256));
+        entry.return_value(quotient).unwrap();
+        guarded.build().unwrap();
+
+        let mut unguarded = contract_builder.function("split");
+        unguarded.param("amount", Type::Uint(256));
+        unguarded.param("parts", Type::Uint(256));
+        let amount = unguarded.get_param(0);
+        let parts = unguarded.get_param(1);
+        let mut entry = unguarded.entry_block();
+        let shares = entry.storage_load(BigUint::from(0u32));
+        let per_share = entry.mod_(amount.clone(), shares, Type::Uint(256));
+        let per_part = entry.div(amount, parts, Type::Uint(256));
+        let one = entry.constant_uint(1, 256);
+        let safe = entry.div(per_part.clone(), one, Type::Uint(256));
+        let total = entry.add(per_share, safe, Type::Uint(256));
+        entry.return_value(total).unwrap();
+        unguarded.build().unwrap();
+
+        let contract = contract_builder.build().unwrap();
+        let results = DivisionGuardAnalysis::analyze_contract(&contract);
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.function == "split"));
+        assert_eq!(results[0].influence, DivisorInfluence::External);
+        assert_eq!(results[0].operation, "division");
+        assert_eq!(results[1].influence, DivisorInfluence::Storage);
+        assert_eq!(results[1].operation, "modulo");
+        assert_eq!(results[0].severity(), Severity::High);
+    }
+
+    #[test]
+    fn test_branch_on_zero_guards_else_region() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Vault");
+
+        let mut func = contract_builder.function("ratio");
+        func.param("numerator", Type::Uint(256));
+        func.param("denominator", Type::Uint(256));
+        let numerator = func.get_param(0);
+        let denominator = func.get_param(1);
+        let fail_id = func.create_block_id();
+        let ok_id = func.create_block_id();
+        let tail_id = func.create_block_id();
+        {
+            let mut entry = func.entry_block();
+            let zero = entry.constant_uint(0, 256);
+            let is_zero = entry.eq(denominator.clone(), zero);
+            entry.branch(is_zero, fail_id, ok_id).unwrap();
+        }
+        {
+            let mut fail = func.switch_to_block(fail_id).unwrap();
+            fail.revert("zero denominator").unwrap();
+        }
+        {
+            let mut ok = func.switch_to_block(ok_id).unwrap();
+            ok.jump(tail_id).unwrap();
+        }
+        {
+            let mut tail = func.switch_to_block(tail_id).unwrap();
+            let quotient = tail.div(numerator, denominator, Type::Uint(256));
+            tail.return_value(quotient).unwrap();
+        }
+        func.build().unwrap();
+
+        let contract = contract_builder.build().unwrap();
+        assert!(DivisionGuardAnalysis::analyze_contract(&contract).is_empty());
+        assert!(DivisionGuardAnalysis::findings(&contract).is_empty());
+    }
+
+    #[test]
+    fn test_unchecked_increment_may_wrap_to_zero() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Vault");
+
+        let mut func = contract_builder.function("share");
+        func.param("amount", Type::Uint(256));
+        func.param("parts", Type::Uint(256));
+        let amount = func.get_param(0);
+        let parts = func.get_param(1);
+        let mut entry = func.entry_block();
+        let one = entry.constant_uint(1, 256);
+        let wrapped = entry.add(parts.clone(), one.clone(), Type::Uint(256));
+        let unchecked = entry.div(amount.clone(), wrapped, Type::Uint(256));
+        let checked = entry.checked_add(parts, one.clone(), Type::Uint(256));
+        let safe = entry.div(amount, checked, Type::Uint(256));
+        let two = entry.add(one.clone(), one, Type::Uint(256));
+        let constant = entry.div(safe, two, Type::Uint(256));
+        let total = entry.add(unchecked, constant, Type::Uint(256));
+        entry.return_value(total).unwrap();
+        func.build().unwrap();
+
+        let contract = contract_builder.build().unwrap();
+        let results = DivisionGuardAnalysis::analyze_contract(&contract);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].location.index, 1);
+    }
+}
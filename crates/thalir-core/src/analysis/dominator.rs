@@ -1,3 +1,4 @@
+use super::control_flow::ControlFlowGraph;
 use crate::{block::BlockId, function::Function};
 use std::collections::{HashMap, HashSet};
 
@@ -14,6 +15,7 @@ impl DominatorTree {
         let mut children: HashMap<BlockId, Vec<BlockId>> = HashMap::new();
 
         let blocks = Self::reverse_postorder(function, entry);
+        let cfg = ControlFlowGraph::build(function);
 
         if blocks.len() <= 1 {
             return Self { idom, children };
@@ -32,7 +34,7 @@ impl DominatorTree {
             changed = false;
 
             for &block in &blocks[1..] {
-                let preds = function.body.blocks[&block].predecessors();
+                let preds = cfg.predecessors(block);
 
                 if preds.is_empty() {
                     continue;
@@ -40,7 +42,7 @@ impl DominatorTree {
 
                 let mut new_dom = None;
                 for pred in preds {
-                    if let Some(pred_dom) = doms.get(&pred) {
+                    if let Some(pred_dom) = doms.get(pred) {
                         if let Some(acc) = new_dom {
                             new_dom = Some(Self::intersect(&acc, pred_dom));
                         } else {
@@ -65,33 +67,15 @@ impl DominatorTree {
                 continue;
             }
 
-            let dominators = &doms[&block];
+            let immediate = doms[&block]
+                .iter()
+                .copied()
+                .filter(|&candidate| candidate != block)
+                .max_by_key(|candidate| doms.get(candidate).map_or(0, HashSet::len));
 
-            for &candidate in dominators {
-                if candidate == block {
-                    continue;
-                }
-
-                let mut is_immediate = true;
-                for &other in dominators {
-                    if other == block || other == candidate {
-                        continue;
-                    }
-
-                    if doms
-                        .get(&candidate)
-                        .map_or(false, |c_doms| c_doms.contains(&other))
-                    {
-                        is_immediate = false;
-                        break;
-                    }
-                }
-
-                if is_immediate {
-                    idom.insert(block, candidate);
-                    children.entry(candidate).or_default().push(block);
-                    break;
-                }
+            if let Some(candidate) = immediate {
+                idom.insert(block, candidate);
+                children.entry(candidate).or_default().push(block);
             }
         }
 
@@ -239,4 +223,95 @@ mod tests {
         assert_eq!(dom_tree.idom(b2), Some(entry));
         assert_eq!(dom_tree.idom(end), Some(entry));
     }
+
+    #[test]
+    fn test_chain_inside_diamond() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("TestContract");
+
+        let mut func_builder = contract_builder.function("test");
+
+        let entry = {
+            let entry_builder = func_builder.entry_block();
+            entry_builder.block_id()
+        };
+
+        let a = func_builder.create_block_id();
+        let b = func_builder.create_block_id();
+        let other = func_builder.create_block_id();
+        let end = func_builder.create_block_id();
+
+        let mut entry_builder = func_builder.switch_to_block(entry).unwrap();
+        let cond = entry_builder.constant_bool(true);
+        entry_builder.branch(cond, a, other).unwrap();
+
+        let mut a_builder = func_builder.switch_to_block(a).unwrap();
+        a_builder.jump(b).unwrap();
+
+        let mut b_builder = func_builder.switch_to_block(b).unwrap();
+        b_builder.jump(end).unwrap();
+
+        let mut other_builder = func_builder.switch_to_block(other).unwrap();
+        other_builder.jump(end).unwrap();
+
+        let mut end_builder = func_builder.switch_to_block(end).unwrap();
+        end_builder.return_void().unwrap();
+
+        let function = func_builder.build().unwrap();
+
+        let dom_tree = DominatorTree::build(&function);
+
+        assert!(dom_tree.dominates(a, b));
+        assert!(!dom_tree.dominates(a, end));
+        assert!(!dom_tree.dominates(b, end));
+
+        assert_eq!(dom_tree.idom(a), Some(entry));
+        assert_eq!(dom_tree.idom(b), Some(a));
+        assert_eq!(dom_tree.idom(other), Some(entry));
+        assert_eq!(dom_tree.idom(end), Some(entry));
+        assert_eq!(dom_tree.children(a), &[b]);
+    }
+
+    #[test]
+    fn test_loop_with_back_edge() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("TestContract");
+
+        let mut func_builder = contract_builder.function("test");
+
+        let entry = {
+            let entry_builder = func_builder.entry_block();
+            entry_builder.block_id()
+        };
+
+        let loop_header = func_builder.create_block_id();
+        let loop_body = func_builder.create_block_id();
+        let exit = func_builder.create_block_id();
+
+        let mut entry_builder = func_builder.switch_to_block(entry).unwrap();
+        entry_builder.jump(loop_header).unwrap();
+
+        let mut header_builder = func_builder.switch_to_block(loop_header).unwrap();
+        let cond = header_builder.constant_bool(true);
+        header_builder.branch(cond, loop_body, exit).unwrap();
+
+        let mut body_builder = func_builder.switch_to_block(loop_body).unwrap();
+        body_builder.jump(loop_header).unwrap();
+
+        let mut exit_builder = func_builder.switch_to_block(exit).unwrap();
+        exit_builder.return_void().unwrap();
+
+        let function = func_builder.build().unwrap();
+
+        let dom_tree = DominatorTree::build(&function);
+
+        assert!(dom_tree.dominates(loop_header, loop_body));
+        assert!(dom_tree.dominates(loop_header, exit));
+        assert!(!dom_tree.dominates(loop_body, loop_header));
+        assert!(!dom_tree.dominates(loop_body, exit));
+
+        assert_eq!(dom_tree.idom(loop_header), Some(entry));
+        assert_eq!(dom_tree.idom(loop_body), Some(loop_header));
+        assert_eq!(dom_tree.idom(exit), Some(loop_header));
+    }
 }
@@ -0,0 +1,349 @@
+use super::control_flow::ControlFlowGraph;
+use super::findings::{Finding, Severity};
+use super::mapping_roles::{mapping_writes, MappingRoleAnalysis};
+use crate::{
+    block::{BlockId, Terminator},
+    contract::{Contract, EventId},
+    function::Function,
+    instructions::Instruction,
+    metadata::InstructionLocation,
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MismatchKind {
+    UnloggedMutation,
+    StatelessEvent,
+}
+
+impl fmt::Display for MismatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MismatchKind::UnloggedMutation => {
+                write!(f, "changed without an event on the same path")
+            }
+            MismatchKind::StatelessEvent => {
+                write!(f, "emitted on a path that changes no tracked state")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventStateMismatch {
+    pub function: String,
+    pub location: InstructionLocation,
+    pub kind: MismatchKind,
+    /* The state variable written, or the event emitted, depending on `kind`. */
+    pub subject: String,
+}
+
+impl EventStateMismatch {
+    pub fn severity(&self) -> Severity {
+        Severity::Low
+    }
+
+    pub fn to_finding(&self, contract: &str) -> Finding {
+        let subject = match self.kind {
+            MismatchKind::UnloggedMutation => format!("State variable `{}`", self.subject),
+            MismatchKind::StatelessEvent => format!("Event `{}`", self.subject),
+        };
+        Finding::new(
+            EventConsistencyAnalysis::DETECTOR,
+            self.severity(),
+            contract,
+            &self.function,
+            format!("{} is {}", subject, self.kind),
+        )
+        .at(self.location.clone())
+    }
+}
+
+pub struct EventConsistencyAnalysis;
+
+impl EventConsistencyAnalysis {
+    pub const DETECTOR: &'static str = "event-state-consistency";
+
+    /* Tracked state is every mapping tagged with a financial role. A write to it needs an event
+     * on every returning path through the write; paths that revert are discarded with their
+     * writes. Events count as financial once some function emits them alongside tracked writes,
+     * and each emission of one needs a tracked write on at least one returning path through it. */
+    pub fn analyze_contract(contract: &Contract) -> Vec<EventStateMismatch> {
+        let tracked: HashMap<BigUint, String> = MappingRoleAnalysis::roles(contract)
+            .into_iter()
+            .map(|tag| (tag.slot, tag.variable))
+            .collect();
+        if tracked.is_empty() {
+            return Vec::new();
+        }
+
+        let financial: HashSet<EventId> = contract
+            .functions
+            .values()
+            .filter(|function| {
+                mapping_writes(function)
+                    .iter()
+                    .any(|write| tracked.contains_key(&write.slot))
+            })
+            .flat_map(|function| emitted_events(function).into_iter().map(|(_, id)| id))
+            .collect();
+
+        contract
+            .functions
+            .values()
+            .filter(|function| !function.metadata.is_constructor)
+            .flat_map(|function| Self::analyze_function(contract, function, &tracked, &financial))
+            .collect()
+    }
+
+    fn analyze_function(
+        contract: &Contract,
+        function: &Function,
+        tracked: &HashMap<BigUint, String>,
+        financial: &HashSet<EventId>,
+    ) -> Vec<EventStateMismatch> {
+        let writes: Vec<(InstructionLocation, &String)> = mapping_writes(function)
+            .into_iter()
+            .filter_map(|write| {
+                tracked
+                    .get(&write.slot)
+                    .map(|variable| (write.location, variable))
+            })
+            .collect();
+        let events = emitted_events(function);
+
+        let cfg = ControlFlowGraph::build(function);
+        let event_blocks: HashSet<BlockId> = events.iter().map(|(loc, _)| loc.block).collect();
+        let write_blocks: HashSet<BlockId> = writes.iter().map(|(loc, _)| loc.block).collect();
+        let logged_before = propagate(function, &cfg, &event_blocks, Direction::Forward, Meet::All);
+        let logged_after = propagate(
+            function,
+            &cfg,
+            &event_blocks,
+            Direction::Backward,
+            Meet::All,
+        );
+        let written_before =
+            propagate(function, &cfg, &write_blocks, Direction::Forward, Meet::Any);
+        let written_after = propagate(
+            function,
+            &cfg,
+            &write_blocks,
+            Direction::Backward,
+            Meet::Any,
+        );
+
+        let mut results = Vec::new();
+        for (location, variable) in &writes {
+            let block = location.block;
+            if !(event_blocks.contains(&block) || logged_before[&block] || logged_after[&block]) {
+                results.push(EventStateMismatch {
+                    function: function.name().to_string(),
+                    location: location.clone(),
+                    kind: MismatchKind::UnloggedMutation,
+                    subject: (*variable).clone(),
+                });
+            }
+        }
+        for (location, id) in &events {
+            let block = location.block;
+            if !financial.contains(id)
+                || write_blocks.contains(&block)
+                || written_before[&block]
+                || written_after[&block]
+            {
+                continue;
+            }
+            let name = contract
+                .events
+                .iter()
+                .find(|event| event.id == *id)
+                .map(|event| event.name.clone())
+                .unwrap_or_else(|| format!("event{}", id.0));
+            results.push(EventStateMismatch {
+                function: function.name().to_string(),
+                location: location.clone(),
+                kind: MismatchKind::StatelessEvent,
+                subject: name,
+            });
+        }
+        results
+    }
+
+    pub fn findings(contract: &Contract) -> Vec<Finding> {
+        Self::analyze_contract(contract)
+            .iter()
+            .map(|mismatch| mismatch.to_finding(&contract.name))
+            .collect()
+    }
+}
+
+fn emitted_events(function: &Function) -> Vec<(InstructionLocation, EventId)> {
+    let mut events = Vec::new();
+    for (&block_id, block) in &function.body.blocks {
+        for (index, inst) in block.instructions.iter().enumerate() {
+            if let Instruction::EmitEvent { event, .. } = inst {
+                events.push((
+                    InstructionLocation {
+                        block: block_id,
+                        index,
+                    },
+                    *event,
+                ));
+            }
+        }
+    }
+    events
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Forward,
+    Backward,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Meet {
+    All,
+    Any,
+}
+
+/* Whether a marked block lies on all (or any) paths reaching a block from the entry, when going
+ * forward, or leaving it for a `return`, when going backward. The block's own mark is excluded
+ * so callers can decide how to treat it. */
+fn propagate(
+    function: &Function,
+    cfg: &ControlFlowGraph,
+    marked: &HashSet<BlockId>,
+    direction: Direction,
+    meet: Meet,
+) -> HashMap<BlockId, bool> {
+    let blocks: Vec<BlockId> = function.body.blocks.keys().copied().collect();
+    let mut boundary: HashMap<BlockId, bool> =
+        blocks.iter().map(|&b| (b, meet == Meet::All)).collect();
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &block in &blocks {
+            let neighbours = match direction {
+                Direction::Forward => cfg.predecessors(block),
+                Direction::Backward => cfg.successors(block),
+            };
+            let through =
+                |n: &BlockId| marked.contains(n) || boundary.get(n).copied().unwrap_or(false);
+            let value = if direction == Direction::Forward && block == cfg.entry() {
+                false
+            } else if neighbours.is_empty() {
+                match direction {
+                    Direction::Forward => meet == Meet::All,
+                    Direction::Backward => {
+                        let returns = matches!(
+                            function.body.blocks.get(&block).map(|b| &b.terminator),
+                            Some(Terminator::Return(_))
+                        );
+                        meet == Meet::All && !returns
+                    }
+                }
+            } else {
+                match meet {
+                    Meet::All => neighbours.iter().all(through),
+                    Meet::Any => neighbours.iter().any(through),
+                }
+            };
+            if boundary[&block] != value {
+                boundary.insert(block, value);
+                changed = true;
+            }
+        }
+    }
+    boundary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
+    use crate::values::{Constant, Value};
+
+    #[test]
+    fn test_reports_unlogged_writes_and_stateless_events() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Token");
+        contract_builder.state_variable(
+            "balanceOf",
+            Type::Mapping(Box::new(Type::Address), Box::new(Type::Uint(256))),
+            0,
+        );
+        let transfer_event = contract_builder
+            .event("Transfer")
+            .indexed("from", Type::Address)
+            .data("value", Type::Uint(256))
+            .build();
+        let event_id = transfer_event.id;
+        contract_builder.add_event(transfer_event);
+        let slot = Value::Constant(Constant::Uint(BigUint::from(0u32), 256));
+
+        let mut transfer = contract_builder.function("transfer");
+        transfer.param("amount", Type::Uint(256));
+        let amount = transfer.get_param(0);
+        let mut entry = transfer.entry_block();
+        let sender = entry.msg_sender();
+        let balance = entry.mapping_load(slot.clone(), sender.clone());
+        let debited = entry.checked_sub(balance, amount.clone(), Type::Uint(256));
+        entry.mapping_store(slot.clone(), sender.clone(), debited);
+        entry.emit_event(event_id, vec![sender], vec![amount]);
+        entry.return_void().unwrap();
+        transfer.build().unwrap();
+
+        let mut burn = contract_builder.function("burn");
+        burn.param("amount", Type::Uint(256));
+        burn.param("quiet", Type::Bool);
+        let amount = burn.get_param(0);
+        let quiet = burn.get_param(1);
+        let loud_id = burn.create_block_id();
+        let done_id = burn.create_block_id();
+        {
+            let mut entry = burn.entry_block();
+            let sender = entry.msg_sender();
+            let balance = entry.mapping_load(slot.clone(), sender.clone());
+            let debited = entry.checked_sub(balance, amount.clone(), Type::Uint(256));
+            entry.mapping_store(slot.clone(), sender, debited);
+            entry.branch(quiet, done_id, loud_id).unwrap();
+        }
+        {
+            let mut loud = burn.switch_to_block(loud_id).unwrap();
+            let sender = loud.msg_sender();
+            loud.emit_event(event_id, vec![sender], vec![amount]);
+            loud.jump(done_id).unwrap();
+        }
+        {
+            let mut done = burn.switch_to_block(done_id).unwrap();
+            done.return_void().unwrap();
+        }
+        burn.build().unwrap();
+
+        let mut announce = contract_builder.function("announce");
+        let mut entry = announce.entry_block();
+        let sender = entry.msg_sender();
+        let zero = entry.constant_uint(0, 256);
+        entry.emit_event(event_id, vec![sender], vec![zero]);
+        entry.return_void().unwrap();
+        announce.build().unwrap();
+
+        let contract = contract_builder.build().unwrap();
+        let report = EventConsistencyAnalysis::analyze_contract(&contract);
+        let kinds: Vec<(&str, MismatchKind)> = report
+            .iter()
+            .map(|m| (m.function.as_str(), m.kind))
+            .collect();
+
+        assert_eq!(report.len(), 2);
+        assert!(kinds.contains(&("burn", MismatchKind::UnloggedMutation)));
+        assert!(kinds.contains(&("announce", MismatchKind::StatelessEvent)));
+    }
+}
@@ -0,0 +1,105 @@
+use super::scoring::Score;
+use super::trust_zones::TrustZone;
+use crate::hash::hex;
+use crate::metadata::InstructionLocation;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Low => write!(f, "low"),
+            Severity::Medium => write!(f, "medium"),
+            Severity::High => write!(f, "high"),
+            Severity::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    pub detector: String,
+    pub severity: Severity,
+    pub contract: String,
+    pub function: String,
+    pub location: Option<InstructionLocation>,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<Score>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<TrustZone>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stable_id: Option<String>,
+}
+
+impl Finding {
+    pub fn new(
+        detector: &str,
+        severity: Severity,
+        contract: &str,
+        function: &str,
+        message: String,
+    ) -> Self {
+        Self {
+            detector: detector.to_string(),
+            severity,
+            contract: contract.to_string(),
+            function: function.to_string(),
+            location: None,
+            message,
+            provenance: None,
+            score: None,
+            zone: None,
+            stable_id: None,
+        }
+    }
+
+    pub fn at(mut self, location: InstructionLocation) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    pub fn with_provenance(mut self, provenance: String) -> Self {
+        self.provenance = Some(provenance);
+        self
+    }
+
+    /* Identifies the finding across runs. Block IDs and instruction indices shift whenever
+     * unrelated code changes, so the location is left out, and so are the severity, score and zone,
+     * since a rescored or reattributed finding is still the same finding. */
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        for part in [&self.detector, &self.contract, &self.function, &self.message] {
+            hasher.update(part.as_bytes());
+            hasher.update([0u8]);
+        }
+        hex(&hasher.finalize()[..16])
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}: {}::{}",
+            self.severity, self.detector, self.contract, self.function
+        )?;
+        if let Some(location) = &self.location {
+            write!(f, " ({}:{})", location.block, location.index)?;
+        }
+        write!(f, " - {}", self.message)
+    }
+}
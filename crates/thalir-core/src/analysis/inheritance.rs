@@ -0,0 +1,282 @@
+use super::findings::{Finding, Severity};
+use crate::contract::{Contract, StorageSlot};
+use crate::values::SourceLocation;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InheritanceIssue {
+    ShadowedStateVariable {
+        contract: String,
+        base: String,
+        variable: String,
+        declared_at: Option<SourceLocation>,
+        shadowed_at: Option<SourceLocation>,
+    },
+    MissingStorageGap {
+        base: String,
+        derived: String,
+        base_at: Option<SourceLocation>,
+        derived_at: Option<SourceLocation>,
+    },
+}
+
+impl InheritanceIssue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            InheritanceIssue::ShadowedStateVariable { .. } => Severity::Medium,
+            InheritanceIssue::MissingStorageGap { .. } => Severity::Low,
+        }
+    }
+
+    pub fn to_finding(&self) -> Finding {
+        match self {
+            InheritanceIssue::ShadowedStateVariable {
+                contract,
+                base,
+                variable,
+                declared_at,
+                shadowed_at,
+            } => Finding::new(
+                InheritanceAnalysis::SHADOWING,
+                self.severity(),
+                contract,
+                "",
+                format!(
+                    "State variable `{}` declared at {} shadows `{}.{}` declared at {}",
+                    variable,
+                    site(declared_at),
+                    base,
+                    variable,
+                    site(shadowed_at)
+                ),
+            ),
+            InheritanceIssue::MissingStorageGap {
+                base,
+                derived,
+                base_at,
+                derived_at,
+            } => Finding::new(
+                InheritanceAnalysis::STORAGE_GAP,
+                self.severity(),
+                base,
+                "",
+                format!(
+                    "Upgradeable base `{}` declared at {} has no `__gap` array but is inherited by `{}` declared at {}",
+                    base,
+                    site(base_at),
+                    derived,
+                    site(derived_at)
+                ),
+            ),
+        }
+    }
+}
+
+fn site(location: &Option<SourceLocation>) -> String {
+    match location {
+        Some(location) => format!("{}:{}", location.file, location.line),
+        None => "an unknown location".to_string(),
+    }
+}
+
+pub struct InheritanceAnalysis;
+
+impl InheritanceAnalysis {
+    pub const SHADOWING: &'static str = "shadowed-state-variable";
+    pub const STORAGE_GAP: &'static str = "missing-storage-gap";
+
+    /* Bases are resolved by name within the workspace; a base that was not transformed alongside
+     * its children is skipped rather than guessed at. */
+    pub fn analyze_workspace(contracts: &[Contract]) -> Vec<InheritanceIssue> {
+        let by_name: HashMap<&str, &Contract> = contracts
+            .iter()
+            .map(|contract| (contract.name.as_str(), contract))
+            .collect();
+
+        let mut issues = Vec::new();
+        let mut gaps_reported = HashSet::new();
+
+        for contract in contracts {
+            let ancestors = ancestors(contract, &by_name);
+
+            /* Private base variables are out of the child's sight and storage gaps are meant to be
+             * redeclared, so neither counts as shadowed. */
+            for var in contract
+                .storage_layout
+                .slots
+                .iter()
+                .filter(|var| !is_gap(var))
+            {
+                let shadowed = ancestors.iter().find_map(|base| {
+                    base.storage_layout
+                        .slots
+                        .iter()
+                        .find(|other| other.name == var.name && !other.is_private)
+                        .map(|other| (base, other))
+                });
+                if let Some((base, other)) = shadowed {
+                    issues.push(InheritanceIssue::ShadowedStateVariable {
+                        contract: contract.name.clone(),
+                        base: base.name.clone(),
+                        variable: var.name.clone(),
+                        declared_at: var.location.clone(),
+                        shadowed_at: other.location.clone(),
+                    });
+                }
+            }
+
+            for base in &ancestors {
+                if is_upgradeable(base, &by_name)
+                    && !base.storage_layout.slots.is_empty()
+                    && !base.storage_layout.slots.iter().any(is_gap)
+                    && gaps_reported.insert(base.name.clone())
+                {
+                    issues.push(InheritanceIssue::MissingStorageGap {
+                        base: base.name.clone(),
+                        derived: contract.name.clone(),
+                        base_at: base.metadata.location.clone(),
+                        derived_at: contract.metadata.location.clone(),
+                    });
+                }
+            }
+        }
+
+        issues
+    }
+
+    pub fn findings(contracts: &[Contract]) -> Vec<Finding> {
+        Self::analyze_workspace(contracts)
+            .iter()
+            .map(InheritanceIssue::to_finding)
+            .collect()
+    }
+}
+
+/* Every transitive base, nearest first, each listed once. */
+pub(super) fn ancestors<'a>(
+    contract: &Contract,
+    by_name: &HashMap<&str, &'a Contract>,
+) -> Vec<&'a Contract> {
+    let mut seen = HashSet::new();
+    let mut order = Vec::new();
+    let mut queue: Vec<&str> = contract.metadata.bases.iter().map(String::as_str).collect();
+    let mut next = 0;
+    while next < queue.len() {
+        let name = queue[next];
+        next += 1;
+        if name == contract.name || !seen.insert(name) {
+            continue;
+        }
+        if let Some(base) = by_name.get(name) {
+            order.push(*base);
+            queue.extend(base.metadata.bases.iter().map(String::as_str));
+        }
+    }
+    order
+}
+
+fn is_upgradeable(contract: &Contract, by_name: &HashMap<&str, &Contract>) -> bool {
+    let marked = |contract: &Contract| {
+        contract.metadata.security_flags.is_upgradeable
+            || contract.name.ends_with("Upgradeable")
+            || contract.name == "Initializable"
+            || contract
+                .functions
+                .keys()
+                .any(|name| name.starts_with("initialize") || name.starts_with("__"))
+    };
+    marked(contract)
+        || ancestors(contract, by_name).into_iter().any(marked)
+        || contract
+            .metadata
+            .bases
+            .iter()
+            .any(|base| base == "Initializable" || base.ends_with("Upgradeable"))
+}
+
+fn is_gap(var: &StorageSlot) -> bool {
+    var.name.starts_with("__gap")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
+
+    fn location(line: u32) -> SourceLocation {
+        SourceLocation::new("Vault.sol".to_string(), line, 4, 0, 0)
+    }
+
+    #[test]
+    fn test_shadowing_and_missing_gap_cite_both_sites() {
+        let mut builder = IRBuilder::new();
+
+        let mut base = builder.contract("OwnableUpgradeable");
+        base.location(location(1));
+        base.state_variable_at("owner", Type::Address, 0, location(2));
+        let base = base.build().unwrap();
+
+        let mut gapped = builder.contract("PausableUpgradeable");
+        gapped.state_variable_at("paused", Type::Bool, 0, location(10));
+        gapped.state_variable_at(
+            "__gap",
+            Type::Array(Box::new(Type::Uint(256)), Some(49)),
+            1,
+            location(11),
+        );
+        let gapped = gapped.build().unwrap();
+
+        let mut derived = builder.contract("Vault");
+        derived
+            .location(location(20))
+            .inherits("OwnableUpgradeable")
+            .inherits("PausableUpgradeable");
+        derived.state_variable_at("owner", Type::Address, 0, location(21));
+        let derived = derived.build().unwrap();
+
+        let contracts = vec![base, gapped, derived];
+        let issues = InheritanceAnalysis::analyze_workspace(&contracts);
+        assert_eq!(issues.len(), 2);
+
+        assert!(issues.contains(&InheritanceIssue::ShadowedStateVariable {
+            contract: "Vault".to_string(),
+            base: "OwnableUpgradeable".to_string(),
+            variable: "owner".to_string(),
+            declared_at: Some(location(21)),
+            shadowed_at: Some(location(2)),
+        }));
+
+        let gap = issues
+            .iter()
+            .find(|issue| matches!(issue, InheritanceIssue::MissingStorageGap { .. }))
+            .unwrap()
+            .to_finding();
+        assert_eq!(gap.contract, "OwnableUpgradeable");
+        assert!(gap.message.contains("Vault.sol:1") && gap.message.contains("Vault.sol:20"));
+    }
+
+    #[test]
+    fn test_private_and_gap_variables_are_not_shadowed() {
+        let mut builder = IRBuilder::new();
+
+        let mut base = builder.contract("Ownable");
+        base.state_variable("_owner", Type::Address, 0)
+            .private_state_variable("_owner");
+        base.state_variable("__gap", Type::Array(Box::new(Type::Uint(256)), Some(49)), 1);
+        let base = base.build().unwrap();
+
+        let mut derived = builder.contract("Vault");
+        derived.inherits("Ownable");
+        derived.state_variable("_owner", Type::Address, 50);
+        derived.state_variable(
+            "__gap",
+            Type::Array(Box::new(Type::Uint(256)), Some(49)),
+            51,
+        );
+        let derived = derived.build().unwrap();
+
+        assert!(InheritanceAnalysis::analyze_workspace(&[base, derived]).is_empty());
+    }
+}
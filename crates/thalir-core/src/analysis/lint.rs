@@ -0,0 +1,378 @@
+use super::util::storage_key_slot;
+use crate::{
+    block::{BlockId, Terminator},
+    contract::Contract,
+    function::Function,
+    instructions::{CallTarget, Instruction, StorageKey},
+    metadata::InstructionLocation,
+    values::{Constant, Value},
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+
+/* Hygiene checks over lowered IR. Unlike the security detectors these say nothing about
+ * exploitability; they point at IR that is pointless or suspicious so it can be cleaned up before
+ * review. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintRule {
+    UnreachableBlock,
+    EmptyFunction,
+    ConstantCondition,
+    DeadStore,
+}
+
+impl LintRule {
+    pub const ALL: [LintRule; 4] = [
+        LintRule::UnreachableBlock,
+        LintRule::EmptyFunction,
+        LintRule::ConstantCondition,
+        LintRule::DeadStore,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintRule::UnreachableBlock => "unreachable-block",
+            LintRule::EmptyFunction => "empty-function",
+            LintRule::ConstantCondition => "constant-condition",
+            LintRule::DeadStore => "dead-store",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+
+    pub fn default_level(&self) -> LintLevel {
+        match self {
+            LintRule::EmptyFunction => LintLevel::Allow,
+            _ => LintLevel::Warn,
+        }
+    }
+}
+
+impl fmt::Display for LintRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl fmt::Display for LintLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintLevel::Allow => write!(f, "allow"),
+            LintLevel::Warn => write!(f, "warn"),
+            LintLevel::Deny => write!(f, "deny"),
+        }
+    }
+}
+
+/* Per-rule overrides keyed by rule name, e.g. the `[lint]` table of a `thalir.toml`. Rules that
+ * are not listed keep their default level. */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LintConfig {
+    pub levels: BTreeMap<String, LintLevel>,
+}
+
+impl LintConfig {
+    pub fn level(&self, rule: LintRule) -> LintLevel {
+        self.levels
+            .get(rule.name())
+            .copied()
+            .unwrap_or_else(|| rule.default_level())
+    }
+
+    pub fn set(&mut self, rule: LintRule, level: LintLevel) {
+        self.levels.insert(rule.name().to_string(), level);
+    }
+
+    pub fn unknown_rules(&self) -> Vec<&str> {
+        self.levels
+            .keys()
+            .map(String::as_str)
+            .filter(|name| LintRule::from_name(name).is_none())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lint {
+    pub rule: LintRule,
+    pub level: LintLevel,
+    pub contract: String,
+    pub function: String,
+    pub location: Option<InstructionLocation>,
+    pub message: String,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}: {}::{}",
+            self.level, self.rule, self.contract, self.function
+        )?;
+        if let Some(location) = &self.location {
+            write!(f, " ({}:{})", location.block, location.index)?;
+        }
+        write!(f, " - {}", self.message)
+    }
+}
+
+pub struct Linter {
+    config: LintConfig,
+}
+
+impl Linter {
+    pub fn new(config: LintConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn check_contract(&self, contract: &Contract) -> Vec<Lint> {
+        let _span = tracing::info_span!("lint", contract = %contract.name).entered();
+        contract
+            .functions
+            .values()
+            .flat_map(|function| self.check_function(&contract.name, function))
+            .collect()
+    }
+
+    pub fn check_function(&self, contract: &str, function: &Function) -> Vec<Lint> {
+        let mut raw = Vec::new();
+        unreachable_blocks(function, &mut raw);
+        empty_function(function, &mut raw);
+        constant_conditions(function, &mut raw);
+        dead_stores(function, &mut raw);
+
+        raw.into_iter()
+            .filter_map(|(rule, location, message)| {
+                let level = self.config.level(rule);
+                (level != LintLevel::Allow).then(|| Lint {
+                    rule,
+                    level,
+                    contract: contract.to_string(),
+                    function: function.name().to_string(),
+                    location,
+                    message,
+                })
+            })
+            .collect()
+    }
+}
+
+type RawLint = (LintRule, Option<InstructionLocation>, String);
+
+fn unreachable_blocks(function: &Function, out: &mut Vec<RawLint>) {
+    let entry = function.entry_block();
+    let mut reached: HashSet<BlockId> = HashSet::from([entry]);
+    let mut queue = VecDeque::from([entry]);
+    while let Some(block_id) = queue.pop_front() {
+        let Some(block) = function.body.get_block(block_id) else {
+            continue;
+        };
+        for succ in block.successors() {
+            if reached.insert(succ) {
+                queue.push_back(succ);
+            }
+        }
+    }
+
+    for &block_id in function.body.blocks.keys() {
+        if !reached.contains(&block_id) {
+            out.push((
+                LintRule::UnreachableBlock,
+                Some(InstructionLocation {
+                    block: block_id,
+                    index: 0,
+                }),
+                format!("{} is not reachable from the entry block", block_id),
+            ));
+        }
+    }
+}
+
+/* Fallback and receive bodies are legitimately empty, and modifiers may carry the real work. */
+fn empty_function(function: &Function, out: &mut Vec<RawLint>) {
+    let metadata = &function.metadata;
+    if metadata.is_fallback
+        || metadata.is_receive
+        || metadata.is_constructor
+        || !function.modifiers.is_empty()
+    {
+        return;
+    }
+    let empty = function.body.blocks.values().all(|block| {
+        block.instructions.is_empty() && matches!(block.terminator, Terminator::Return(None))
+    });
+    if empty {
+        out.push((
+            LintRule::EmptyFunction,
+            None,
+            "function body does nothing".to_string(),
+        ));
+    }
+}
+
+fn constant_conditions(function: &Function, out: &mut Vec<RawLint>) {
+    for (&block_id, block) in &function.body.blocks {
+        for (index, inst) in block.instructions.iter().enumerate() {
+            let (check, condition) = match inst {
+                Instruction::Require { condition, .. } => ("require", condition),
+                Instruction::Assert { condition, .. } => ("assert", condition),
+                _ => continue,
+            };
+            /* `require(false)` is an idiomatic revert; only the always-true form is noise. */
+            if let Value::Constant(Constant::Bool(true)) = condition {
+                out.push((
+                    LintRule::ConstantCondition,
+                    Some(InstructionLocation {
+                        block: block_id,
+                        index,
+                    }),
+                    format!("{} on a condition that always holds", check),
+                ));
+            }
+        }
+
+        if let Terminator::Branch {
+            condition: Value::Constant(Constant::Bool(taken)),
+            then_block,
+            else_block,
+            ..
+        } = &block.terminator
+        {
+            let (live, dead) = if *taken {
+                (then_block, else_block)
+            } else {
+                (else_block, then_block)
+            };
+            out.push((
+                LintRule::ConstantCondition,
+                Some(InstructionLocation {
+                    block: block_id,
+                    index: block.instructions.len(),
+                }),
+                format!("branch always goes to {}; {} is never taken", live, dead),
+            ));
+        }
+    }
+}
+
+/* A store to a fixed slot that is overwritten later in the same block, with no storage read or
+ * call in between that could observe it. Mapping and array element keys are skipped because the
+ * element differs with the key operand even when the base slot matches. */
+fn dead_stores(function: &Function, out: &mut Vec<RawLint>) {
+    for (&block_id, block) in &function.body.blocks {
+        let mut pending: HashMap<BigUint, usize> = HashMap::new();
+        for (index, inst) in block.instructions.iter().enumerate() {
+            match inst {
+                Instruction::StorageStore { key, .. } => {
+                    let Some(slot) = fixed_slot(key) else {
+                        continue;
+                    };
+                    if let Some(earlier) = pending.insert(slot.clone(), index) {
+                        out.push((
+                            LintRule::DeadStore,
+                            Some(InstructionLocation {
+                                block: block_id,
+                                index: earlier,
+                            }),
+                            format!(
+                                "store to slot {} is overwritten at {}:{} before it is read",
+                                slot, block_id, index
+                            ),
+                        ));
+                    }
+                }
+                Instruction::StorageLoad { key, .. } => match fixed_slot(key) {
+                    Some(slot) => {
+                        pending.remove(&slot);
+                    }
+                    None => pending.clear(),
+                },
+                Instruction::StorageDelete { .. }
+                | Instruction::MappingLoad { .. }
+                | Instruction::ArrayLoad { .. }
+                | Instruction::ArrayLength { .. }
+                | Instruction::DelegateCall { .. }
+                | Instruction::StaticCall { .. } => pending.clear(),
+                Instruction::Call { target, .. } if !matches!(target, CallTarget::Builtin(_)) => {
+                    pending.clear()
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn fixed_slot(key: &StorageKey) -> Option<BigUint> {
+    match key {
+        StorageKey::MappingKey { .. } | StorageKey::ArrayElement { .. } => None,
+        _ => storage_key_slot(key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
+
+    #[test]
+    fn test_rules_fire_and_respect_configured_levels() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Messy");
+        contract_builder.state_variable("count", Type::Uint(256), 0);
+
+        let mut bump = contract_builder.function("bump");
+        let orphan = bump.create_block_id();
+        let done = bump.create_block_id();
+        let mut entry = bump.entry_block();
+        let one = entry.constant_uint(1, 256);
+        let two = entry.constant_uint(2, 256);
+        entry.storage_store(BigUint::from(0u32), one);
+        entry.storage_store(BigUint::from(0u32), two);
+        let always = entry.constant_bool(true);
+        entry.branch(always, done, done).unwrap();
+        bump.switch_to_block(orphan).unwrap().return_void().unwrap();
+        bump.switch_to_block(done).unwrap().return_void().unwrap();
+        bump.build().unwrap();
+
+        let mut noop = contract_builder.function("noop");
+        noop.entry_block().return_void().unwrap();
+        noop.build().unwrap();
+
+        let contract = contract_builder.build().unwrap();
+        let rules = |lints: &[Lint]| lints.iter().map(|lint| lint.rule).collect::<HashSet<_>>();
+
+        let defaults = Linter::new(LintConfig::default()).check_contract(&contract);
+        assert_eq!(
+            rules(&defaults),
+            HashSet::from([
+                LintRule::UnreachableBlock,
+                LintRule::ConstantCondition,
+                LintRule::DeadStore
+            ])
+        );
+
+        let mut config = LintConfig::default();
+        config.set(LintRule::EmptyFunction, LintLevel::Deny);
+        config.set(LintRule::DeadStore, LintLevel::Allow);
+        let lints = Linter::new(config).check_contract(&contract);
+        assert!(!rules(&lints).contains(&LintRule::DeadStore));
+        assert!(lints
+            .iter()
+            .any(|lint| lint.rule == LintRule::EmptyFunction && lint.level == LintLevel::Deny));
+    }
+}
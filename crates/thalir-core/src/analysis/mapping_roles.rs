@@ -0,0 +1,413 @@
+use super::findings::{Finding, Severity};
+use super::util::constant_slot;
+use crate::{
+    contract::Contract,
+    function::Function,
+    instructions::{ContextVariable, Instruction},
+    metadata::{InstructionLocation, MappingRole, MappingTag},
+    types::Type,
+    values::Value,
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeySource {
+    Sender,
+    AddressParam,
+    Other,
+}
+
+impl KeySource {
+    fn is_account(self) -> bool {
+        !matches!(self, KeySource::Other)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Adjustment {
+    Increase,
+    Decrease,
+    Assignment,
+}
+
+/* One write to a mapping entry, with the root slot it resolves to and where each key came from,
+ * outermost key first. */
+pub(crate) struct MappingWrite<'a> {
+    pub(crate) slot: BigUint,
+    keys: Vec<KeySource>,
+    adjustment: Adjustment,
+    stores_account: bool,
+    pub(crate) location: InstructionLocation,
+    function: &'a Function,
+}
+
+impl MappingWrite<'_> {
+    fn account_keyed(&self) -> bool {
+        self.keys.iter().any(|key| key.is_account())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnloggedBalanceChange {
+    pub function: String,
+    pub variable: String,
+    pub location: InstructionLocation,
+}
+
+impl UnloggedBalanceChange {
+    pub fn to_finding(&self, contract: &str) -> Finding {
+        Finding::new(
+            MappingRoleAnalysis::DETECTOR,
+            Severity::Low,
+            contract,
+            &self.function,
+            format!(
+                "Balance table `{}` is decreased without emitting an event",
+                self.variable
+            ),
+        )
+        .at(self.location.clone())
+    }
+}
+
+pub struct MappingRoleAnalysis;
+
+impl MappingRoleAnalysis {
+    pub const DETECTOR: &'static str = "unlogged-balance-change";
+
+    /* A `mapping(address => uint)` becomes a balance once some function adds to or subtracts
+     * from an entry keyed by an account. A `mapping(address => mapping(address => uint))` written
+     * under an account key is an allowance, and a mapping to `address` that is assigned the
+     * caller or an address parameter records ownership. */
+    pub fn analyze_contract(contract: &Contract) -> Vec<MappingTag> {
+        let writes: Vec<MappingWrite> = contract
+            .functions
+            .values()
+            .flat_map(mapping_writes)
+            .collect();
+
+        contract
+            .storage_layout
+            .slots
+            .iter()
+            .filter_map(|var| {
+                let Type::Mapping(key, value) = &var.var_type else {
+                    return None;
+                };
+                let writes: Vec<&MappingWrite> =
+                    writes.iter().filter(|w| w.slot == var.slot).collect();
+                let role = classify(key, value, &writes)?;
+
+                let mut adjusted_in: Vec<String> = Vec::new();
+                for write in &writes {
+                    let name = write.function.name().to_string();
+                    if !adjusted_in.contains(&name) {
+                        adjusted_in.push(name);
+                    }
+                }
+                Some(MappingTag {
+                    variable: var.name.clone(),
+                    slot: var.slot.clone(),
+                    role,
+                    keyed_by_sender: writes.iter().any(|w| w.keys.contains(&KeySource::Sender)),
+                    adjusted_in,
+                })
+            })
+            .collect()
+    }
+
+    /* Records the recognised roles in the contract metadata so later passes can query them. */
+    pub fn tag(contract: &mut Contract) {
+        contract.metadata.mapping_roles = Self::analyze_contract(contract);
+    }
+
+    /* The tags already recorded in the metadata, or a fresh analysis when none were recorded. */
+    pub fn roles(contract: &Contract) -> Vec<MappingTag> {
+        if contract.metadata.mapping_roles.is_empty() {
+            Self::analyze_contract(contract)
+        } else {
+            contract.metadata.mapping_roles.clone()
+        }
+    }
+
+    /* Functions that lower a tagged balance but emit no event at all, leaving off-chain
+     * indexers with no record of the change. */
+    pub fn unlogged_decreases(contract: &Contract) -> Vec<UnloggedBalanceChange> {
+        let tags = Self::roles(contract);
+        let balances: HashMap<&BigUint, &str> = tags
+            .iter()
+            .filter(|tag| tag.role == MappingRole::Balance)
+            .map(|tag| (&tag.slot, tag.variable.as_str()))
+            .collect();
+        if balances.is_empty() {
+            return Vec::new();
+        }
+
+        let mut results = Vec::new();
+        for function in contract.functions.values() {
+            let emits = function
+                .body
+                .blocks
+                .values()
+                .flat_map(|b| &b.instructions)
+                .any(|inst| matches!(inst, Instruction::EmitEvent { .. }));
+            if emits || function.metadata.is_constructor {
+                continue;
+            }
+            for write in mapping_writes(function) {
+                if write.adjustment != Adjustment::Decrease {
+                    continue;
+                }
+                if let Some(variable) = balances.get(&write.slot) {
+                    results.push(UnloggedBalanceChange {
+                        function: function.name().to_string(),
+                        variable: variable.to_string(),
+                        location: write.location,
+                    });
+                }
+            }
+        }
+        results
+    }
+
+    pub fn findings(contract: &Contract) -> Vec<Finding> {
+        Self::unlogged_decreases(contract)
+            .iter()
+            .map(|change| change.to_finding(&contract.name))
+            .collect()
+    }
+}
+
+fn classify(key: &Type, value: &Type, writes: &[&MappingWrite]) -> Option<MappingRole> {
+    match (key, value) {
+        (Type::Address, Type::Mapping(inner, amount))
+            if matches!(**inner, Type::Address)
+                && matches!(**amount, Type::Uint(_))
+                && writes
+                    .iter()
+                    .any(|w| w.keys.len() == 2 && w.account_keyed()) =>
+        {
+            Some(MappingRole::Allowance)
+        }
+        (Type::Address, Type::Uint(_))
+            if writes
+                .iter()
+                .any(|w| w.adjustment != Adjustment::Assignment && w.account_keyed()) =>
+        {
+            Some(MappingRole::Balance)
+        }
+        (_, Type::Address) if writes.iter().any(|w| w.stores_account) => {
+            Some(MappingRole::Ownership)
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn mapping_writes(function: &Function) -> Vec<MappingWrite<'_>> {
+    let mut defs: HashMap<&Value, &Instruction> = HashMap::new();
+    for inst in function.body.blocks.values().flat_map(|b| &b.instructions) {
+        if let Some(result) = inst.result() {
+            defs.insert(result, inst);
+        }
+    }
+
+    let mut writes = Vec::new();
+    for (&block_id, block) in &function.body.blocks {
+        for (index, inst) in block.instructions.iter().enumerate() {
+            let Instruction::MappingStore {
+                mapping,
+                key,
+                value,
+            } = inst
+            else {
+                continue;
+            };
+            let Some((slot, mut path)) = resolve_entry(mapping, &defs) else {
+                continue;
+            };
+            path.push(key);
+
+            writes.push(MappingWrite {
+                slot,
+                keys: path
+                    .iter()
+                    .map(|key| key_source(key, function, &defs))
+                    .collect(),
+                adjustment: adjustment(mapping, key, value, &defs),
+                stores_account: key_source(value, function, &defs).is_account(),
+                location: InstructionLocation {
+                    block: block_id,
+                    index,
+                },
+                function,
+            });
+        }
+    }
+    writes
+}
+
+/* The root slot of a possibly nested mapping access and the outer keys leading to it. */
+fn resolve_entry<'a>(
+    mapping: &'a Value,
+    defs: &HashMap<&'a Value, &'a Instruction>,
+) -> Option<(BigUint, Vec<&'a Value>)> {
+    if let Some(slot) = constant_slot(mapping) {
+        return Some((slot, Vec::new()));
+    }
+    match *defs.get(mapping)? {
+        Instruction::MappingLoad { mapping, key, .. } => {
+            let (slot, mut path) = resolve_entry(mapping, defs)?;
+            path.push(key);
+            Some((slot, path))
+        }
+        _ => None,
+    }
+}
+
+fn key_source(
+    value: &Value,
+    function: &Function,
+    defs: &HashMap<&Value, &Instruction>,
+) -> KeySource {
+    match value {
+        Value::Param(id) => match function.signature.params.get(id.0 as usize) {
+            Some(param) if matches!(param.param_type, Type::Address) => KeySource::AddressParam,
+            _ => KeySource::Other,
+        },
+        _ => match defs.get(value) {
+            Some(Instruction::GetContext {
+                var: ContextVariable::MsgSender,
+                ..
+            }) => KeySource::Sender,
+            Some(Instruction::Cast { value, .. } | Instruction::Assign { value, .. }) => {
+                key_source(value, function, defs)
+            }
+            _ => KeySource::Other,
+        },
+    }
+}
+
+/* Whether the stored value adds to or subtracts from the entry it overwrites. */
+fn adjustment(
+    mapping: &Value,
+    key: &Value,
+    value: &Value,
+    defs: &HashMap<&Value, &Instruction>,
+) -> Adjustment {
+    let reads_entry = |operand: &Value| {
+        matches!(
+            defs.get(operand),
+            Some(Instruction::MappingLoad { mapping: m, key: k, .. }) if m == mapping && k == key
+        )
+    };
+    match defs.get(value) {
+        Some(
+            Instruction::Add { left, right, .. } | Instruction::CheckedAdd { left, right, .. },
+        ) if reads_entry(left) || reads_entry(right) => Adjustment::Increase,
+        Some(Instruction::Sub { left, .. } | Instruction::CheckedSub { left, .. })
+            if reads_entry(left) =>
+        {
+            Adjustment::Decrease
+        }
+        _ => Adjustment::Assignment,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+
+    fn address_to_uint() -> Type {
+        Type::Mapping(Box::new(Type::Address), Box::new(Type::Uint(256)))
+    }
+
+    fn slot(n: u32) -> Value {
+        Value::Constant(crate::values::Constant::Uint(BigUint::from(n), 256))
+    }
+
+    #[test]
+    fn test_tags_balances_allowances_and_owners() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Token");
+        contract_builder.state_variable("balanceOf", address_to_uint(), 0);
+        contract_builder.state_variable(
+            "allowance",
+            Type::Mapping(Box::new(Type::Address), Box::new(address_to_uint())),
+            1,
+        );
+        contract_builder.state_variable(
+            "ownerOf",
+            Type::Mapping(Box::new(Type::Uint(256)), Box::new(Type::Address)),
+            2,
+        );
+        contract_builder.state_variable("points", address_to_uint(), 3);
+
+        let mut transfer = contract_builder.function("transfer");
+        transfer
+            .param("to", Type::Address)
+            .param("amount", Type::Uint(256));
+        let to = transfer.get_param(0);
+        let amount = transfer.get_param(1);
+        let mut entry = transfer.entry_block();
+        let sender = entry.msg_sender();
+        let from_balance = entry.mapping_load(slot(0), sender.clone());
+        let debited = entry.checked_sub(from_balance, amount.clone(), Type::Uint(256));
+        entry.mapping_store(slot(0), sender.clone(), debited);
+        let to_balance = entry.mapping_load(slot(0), to.clone());
+        let credited = entry.checked_add(to_balance, amount.clone(), Type::Uint(256));
+        entry.mapping_store(slot(0), to, credited);
+        entry.return_void().unwrap();
+        transfer.build().unwrap();
+
+        let mut approve = contract_builder.function("approve");
+        approve
+            .param("spender", Type::Address)
+            .param("amount", Type::Uint(256));
+        let spender = approve.get_param(0);
+        let amount = approve.get_param(1);
+        let mut entry = approve.entry_block();
+        let sender = entry.msg_sender();
+        let owned = entry.mapping_load(slot(1), sender);
+        entry.mapping_store(owned, spender, amount);
+        entry.return_void().unwrap();
+        approve.build().unwrap();
+
+        let mut mint = contract_builder.function("mint");
+        mint.param("id", Type::Uint(256));
+        let id = mint.get_param(0);
+        let mut entry = mint.entry_block();
+        let sender = entry.msg_sender();
+        entry.mapping_store(slot(2), id.clone(), sender);
+        entry.mapping_store(slot(3), id, slot(7));
+        entry.return_void().unwrap();
+        mint.build().unwrap();
+
+        let mut contract = contract_builder.build().unwrap();
+        MappingRoleAnalysis::tag(&mut contract);
+        let role = |name: &str| {
+            contract
+                .metadata
+                .mapping_roles
+                .iter()
+                .find(|tag| tag.variable == name)
+                .map(|tag| tag.role)
+        };
+
+        assert_eq!(role("balanceOf"), Some(MappingRole::Balance));
+        assert_eq!(role("allowance"), Some(MappingRole::Allowance));
+        assert_eq!(role("ownerOf"), Some(MappingRole::Ownership));
+        assert_eq!(role("points"), None);
+
+        let balance = &contract.metadata.mapping_roles[0];
+        assert!(balance.keyed_by_sender);
+        assert_eq!(balance.adjusted_in, vec!["transfer".to_string()]);
+
+        let unlogged = MappingRoleAnalysis::unlogged_decreases(&contract);
+        assert_eq!(unlogged.len(), 1);
+        assert_eq!(unlogged[0].function, "transfer");
+        assert_eq!(unlogged[0].variable, "balanceOf");
+    }
+}
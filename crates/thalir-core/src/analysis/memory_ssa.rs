@@ -0,0 +1,424 @@
+use super::alias::{AliasAnalysis, AliasResult};
+use super::dominator::DominatorTree;
+use crate::{
+    block::BlockId,
+    function::Function,
+    instructions::{CallTarget, ContextVariable, Instruction, StorageKey},
+    values::{Constant, Location, Value},
+};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/* The state an instruction observes or changes, each versioned on its own so a memory write never
+ * clobbers a storage read. Storage also stands for the rest of the world state, balances and code,
+ * which only calls change. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MemoryRegion {
+    Storage,
+    Transient,
+    Memory,
+}
+
+impl MemoryRegion {
+    pub const ALL: [MemoryRegion; 3] = [
+        MemoryRegion::Storage,
+        MemoryRegion::Transient,
+        MemoryRegion::Memory,
+    ];
+
+    /* Regions the instruction's result depends on. */
+    pub fn read_by(inst: &Instruction) -> Vec<MemoryRegion> {
+        match inst {
+            Instruction::StorageLoad { .. }
+            | Instruction::MappingLoad { .. }
+            | Instruction::StaticCall { .. }
+            | Instruction::GetBalance { .. }
+            | Instruction::GetCode { .. }
+            | Instruction::GetCodeSize { .. }
+            | Instruction::GetCodeHash { .. }
+            | Instruction::GetContext {
+                var: ContextVariable::ThisBalance,
+                ..
+            } => vec![MemoryRegion::Storage],
+            Instruction::TransientLoad { .. } => vec![MemoryRegion::Transient],
+            Instruction::ArrayLoad { .. }
+            | Instruction::ArrayLength { .. }
+            | Instruction::ArrayPush { .. }
+            | Instruction::ArrayPop { .. } => vec![MemoryRegion::Storage, MemoryRegion::Memory],
+            Instruction::Load { location, .. } | Instruction::Copy { src: location, .. } => {
+                Self::of_location(location).into_iter().collect()
+            }
+            Instruction::Keccak256 { .. }
+            | Instruction::Sha256 { .. }
+            | Instruction::Ripemd160 { .. }
+            | Instruction::MemoryCopy { .. }
+            | Instruction::MemorySize { .. } => vec![MemoryRegion::Memory],
+            _ if Self::is_call(inst) => Self::ALL.to_vec(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn written_by(inst: &Instruction) -> Vec<MemoryRegion> {
+        match inst {
+            Instruction::StorageStore { .. }
+            | Instruction::StorageDelete { .. }
+            | Instruction::MappingStore { .. }
+            | Instruction::Selfdestruct { .. } => vec![MemoryRegion::Storage],
+            Instruction::TransientStore { .. } => vec![MemoryRegion::Transient],
+            /* Arrays may live in either storage or memory; the instruction does not say which. */
+            Instruction::ArrayStore { .. }
+            | Instruction::ArrayPush { .. }
+            | Instruction::ArrayPop { .. } => vec![MemoryRegion::Storage, MemoryRegion::Memory],
+            Instruction::Store { location, .. } | Instruction::Copy { dest: location, .. } => {
+                vec![Self::of_location(location).unwrap_or(MemoryRegion::Memory)]
+            }
+            Instruction::Allocate { .. }
+            | Instruction::MemoryAlloc { .. }
+            | Instruction::MemoryCopy { .. } => vec![MemoryRegion::Memory],
+            _ if Self::is_call(inst) => Self::ALL.to_vec(),
+            _ => Vec::new(),
+        }
+    }
+
+    /* Calldata never changes, so reading it depends on no region. */
+    fn of_location(location: &Location) -> Option<MemoryRegion> {
+        match location {
+            Location::Storage { .. } => Some(MemoryRegion::Storage),
+            Location::Calldata { .. } => None,
+            Location::Stack { .. } | Location::Memory { .. } | Location::ReturnData { .. } => {
+                Some(MemoryRegion::Memory)
+            }
+        }
+    }
+
+    /* Internal calls count as well: the callee may touch any of it. */
+    fn is_call(inst: &Instruction) -> bool {
+        match inst {
+            Instruction::Call { target, .. } => !matches!(target, CallTarget::Builtin(_)),
+            Instruction::DelegateCall { .. }
+            | Instruction::Create { .. }
+            | Instruction::Create2 { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MemoryAccessId(pub u32);
+
+impl fmt::Display for MemoryAccessId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "mem{}", self.0)
+    }
+}
+
+/* A version of one region. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoryAccess {
+    /* The region as the function found it. */
+    LiveOnEntry(MemoryRegion),
+    /* The write by instruction `index` of `block`, made over `previous`. */
+    Def {
+        region: MemoryRegion,
+        block: BlockId,
+        index: usize,
+        previous: MemoryAccessId,
+    },
+    /* Where versions arriving from different predecessors meet. */
+    Phi {
+        region: MemoryRegion,
+        block: BlockId,
+        incoming: Vec<(BlockId, MemoryAccessId)>,
+    },
+}
+
+impl MemoryAccess {
+    pub fn region(&self) -> MemoryRegion {
+        match self {
+            MemoryAccess::LiveOnEntry(region)
+            | MemoryAccess::Def { region, .. }
+            | MemoryAccess::Phi { region, .. } => *region,
+        }
+    }
+}
+
+/* Def-use chains for state, built like SSA for values: each write to a region defines a new
+ * version, phis sit on the iterated dominance frontier of the writes, and every read is linked
+ * to the version it observes. Two reads seeing the same version read the same state, which is
+ * what lets value numbering merge loads across blocks. Unreachable blocks get no accesses. */
+#[derive(Debug, Clone, Default)]
+pub struct MemorySSA {
+    accesses: Vec<MemoryAccess>,
+    defs: HashMap<(BlockId, usize, MemoryRegion), MemoryAccessId>,
+    uses: HashMap<(BlockId, usize, MemoryRegion), MemoryAccessId>,
+    phis: HashMap<(BlockId, MemoryRegion), MemoryAccessId>,
+}
+
+impl MemorySSA {
+    pub fn build(function: &Function) -> Self {
+        let dom_tree = DominatorTree::build(function);
+        let entry = function.entry_block();
+        let reachable = reachable(function, entry);
+        let mut ssa = Self::default();
+
+        for region in MemoryRegion::ALL {
+            let mut worklist: Vec<BlockId> = reachable
+                .iter()
+                .copied()
+                .filter(|id| {
+                    function.body.blocks.get(id).is_some_and(|block| {
+                        block
+                            .instructions
+                            .iter()
+                            .any(|inst| MemoryRegion::written_by(inst).contains(&region))
+                    })
+                })
+                .collect();
+            worklist.sort();
+            while let Some(block) = worklist.pop() {
+                let mut frontier: Vec<BlockId> = dom_tree
+                    .dominance_frontier(block, function)
+                    .into_iter()
+                    .filter(|id| reachable.contains(id))
+                    .collect();
+                frontier.sort();
+                for join in frontier {
+                    if !ssa.phis.contains_key(&(join, region)) {
+                        let phi = ssa.push(MemoryAccess::Phi {
+                            region,
+                            block: join,
+                            incoming: Vec::new(),
+                        });
+                        ssa.phis.insert((join, region), phi);
+                        worklist.push(join);
+                    }
+                }
+            }
+
+            let live_on_entry = ssa.push(MemoryAccess::LiveOnEntry(region));
+            ssa.rename(function, &dom_tree, region, entry, live_on_entry);
+        }
+
+        for access in &mut ssa.accesses {
+            if let MemoryAccess::Phi { incoming, .. } = access {
+                incoming.sort();
+            }
+        }
+        ssa
+    }
+
+    fn push(&mut self, access: MemoryAccess) -> MemoryAccessId {
+        self.accesses.push(access);
+        MemoryAccessId(self.accesses.len() as u32 - 1)
+    }
+
+    fn rename(
+        &mut self,
+        function: &Function,
+        dom_tree: &DominatorTree,
+        region: MemoryRegion,
+        block: BlockId,
+        incoming: MemoryAccessId,
+    ) {
+        let Some(data) = function.body.blocks.get(&block) else {
+            return;
+        };
+        let mut current = self.phis.get(&(block, region)).copied().unwrap_or(incoming);
+
+        for (index, inst) in data.instructions.iter().enumerate() {
+            if MemoryRegion::read_by(inst).contains(&region) {
+                self.uses.insert((block, index, region), current);
+            }
+            if MemoryRegion::written_by(inst).contains(&region) {
+                current = self.push(MemoryAccess::Def {
+                    region,
+                    block,
+                    index,
+                    previous: current,
+                });
+                self.defs.insert((block, index, region), current);
+            }
+        }
+
+        for successor in data.successors() {
+            if let Some(phi) = self.phis.get(&(successor, region)) {
+                if let MemoryAccess::Phi { incoming, .. } = &mut self.accesses[phi.0 as usize] {
+                    if !incoming.iter().any(|(pred, _)| *pred == block) {
+                        incoming.push((block, current));
+                    }
+                }
+            }
+        }
+
+        let mut children = dom_tree.children(block).to_vec();
+        children.sort();
+        for child in children {
+            self.rename(function, dom_tree, region, child, current);
+        }
+    }
+
+    pub fn access(&self, id: MemoryAccessId) -> &MemoryAccess {
+        &self.accesses[id.0 as usize]
+    }
+
+    pub fn accesses(&self) -> impl Iterator<Item = (MemoryAccessId, &MemoryAccess)> {
+        self.accesses
+            .iter()
+            .enumerate()
+            .map(|(i, access)| (MemoryAccessId(i as u32), access))
+    }
+
+    /* The version written by instruction `index` of `block`. */
+    pub fn def_at(
+        &self,
+        block: BlockId,
+        index: usize,
+        region: MemoryRegion,
+    ) -> Option<MemoryAccessId> {
+        self.defs.get(&(block, index, region)).copied()
+    }
+
+    /* The version instruction `index` of `block` reads. */
+    pub fn reaching(
+        &self,
+        block: BlockId,
+        index: usize,
+        region: MemoryRegion,
+    ) -> Option<MemoryAccessId> {
+        self.uses.get(&(block, index, region)).copied()
+    }
+
+    pub fn phi(&self, block: BlockId, region: MemoryRegion) -> Option<MemoryAccessId> {
+        self.phis.get(&(block, region)).copied()
+    }
+
+    /* The nearest version the read may actually observe: writes the storage alias rules prove
+     * disjoint from the read's key are skipped, so a load of one slot sees past stores to
+     * others. The walk stops at phis and at the function entry. */
+    pub fn clobbering(
+        &self,
+        function: &Function,
+        block: BlockId,
+        index: usize,
+        region: MemoryRegion,
+    ) -> Option<MemoryAccessId> {
+        let mut current = self.reaching(block, index, region)?;
+        let Some(read) = storage_key(&function.body.blocks[&block].instructions[index]) else {
+            return Some(current);
+        };
+        while let MemoryAccess::Def {
+            block,
+            index,
+            previous,
+            ..
+        } = self.access(current)
+        {
+            let disjoint = storage_key(&function.body.blocks[block].instructions[*index])
+                .is_some_and(|write| {
+                    AliasAnalysis::query_storage(&read, &write) == AliasResult::NoAlias
+                });
+            if !disjoint {
+                break;
+            }
+            current = *previous;
+        }
+        Some(current)
+    }
+}
+
+/* The key a keyed storage access reaches, with mapping accesses on a constant slot put in the
+ * same terms as the transformer's `StorageKey::MappingKey`. */
+pub(crate) fn storage_key(inst: &Instruction) -> Option<StorageKey> {
+    match inst {
+        Instruction::StorageLoad { key, .. }
+        | Instruction::StorageStore { key, .. }
+        | Instruction::StorageDelete { key }
+        | Instruction::TransientLoad { key, .. }
+        | Instruction::TransientStore { key, .. } => Some(key.clone()),
+        Instruction::MappingLoad {
+            mapping: Value::Constant(Constant::Uint(base, _)),
+            key,
+            ..
+        }
+        | Instruction::MappingStore {
+            mapping: Value::Constant(Constant::Uint(base, _)),
+            key,
+            ..
+        } => Some(StorageKey::MappingKey {
+            base: base.clone(),
+            key: key.clone(),
+        }),
+        _ => None,
+    }
+}
+
+fn reachable(function: &Function, entry: BlockId) -> HashSet<BlockId> {
+    let mut seen = HashSet::new();
+    let mut worklist = vec![entry];
+    while let Some(block) = worklist.pop() {
+        if !seen.insert(block) {
+            continue;
+        }
+        if let Some(data) = function.body.blocks.get(&block) {
+            worklist.extend(data.successors());
+        }
+    }
+    seen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
+
+    #[test]
+    fn test_versions_meet_at_joins_and_skip_disjoint_slots() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Vault");
+        let mut func_builder = contract_builder.function("settle");
+        func_builder.param("amount", Type::Uint(256));
+
+        let entry = func_builder.entry_block().block_id();
+        let paid = func_builder.create_block_id();
+        let done = func_builder.create_block_id();
+
+        let amount = func_builder.get_param(0);
+        let mut entry_builder = func_builder.switch_to_block(entry).unwrap();
+        let owner = entry_builder.storage_load(0u32.into());
+        let zero = entry_builder.constant_uint(0, 256);
+        let positive = entry_builder.gt(amount.clone(), zero);
+        entry_builder.branch(positive, paid, done).unwrap();
+
+        let mut paid_builder = func_builder.switch_to_block(paid).unwrap();
+        paid_builder.storage_store(1u32.into(), amount);
+        paid_builder.jump(done).unwrap();
+
+        let mut done_builder = func_builder.switch_to_block(done).unwrap();
+        done_builder.storage_load(0u32.into());
+        done_builder.storage_load(1u32.into());
+        done_builder.return_value(owner).unwrap();
+
+        let function = func_builder.build().unwrap();
+        let ssa = MemorySSA::build(&function);
+
+        let store = ssa.def_at(paid, 0, MemoryRegion::Storage).unwrap();
+        let phi = ssa.phi(done, MemoryRegion::Storage).unwrap();
+        let MemoryAccess::Phi { incoming, .. } = ssa.access(phi) else {
+            panic!("expected a phi at the join");
+        };
+        assert_eq!(incoming.len(), 2);
+        assert!(incoming.contains(&(paid, store)));
+        assert!(ssa.phi(done, MemoryRegion::Memory).is_none());
+
+        assert_eq!(ssa.reaching(done, 0, MemoryRegion::Storage), Some(phi));
+        assert_eq!(
+            ssa.clobbering(&function, entry, 0, MemoryRegion::Storage),
+            ssa.reaching(entry, 0, MemoryRegion::Storage)
+        );
+        assert!(matches!(
+            ssa.access(ssa.reaching(entry, 0, MemoryRegion::Storage).unwrap()),
+            MemoryAccess::LiveOnEntry(MemoryRegion::Storage)
+        ));
+    }
+}
@@ -5,23 +5,99 @@
  * the foundation for pattern matching and verification.
  */
 
+pub mod address_book;
 pub mod alias;
+pub mod annotations;
+pub mod baseline;
 pub mod cache;
+pub mod callbacks;
+pub mod cancel;
 pub mod cfg;
+pub mod context_usage;
 pub mod control_flow;
+pub mod cost;
 pub mod cursor;
 pub mod dataflow;
 pub mod def_use;
+pub mod detectors;
+pub mod division_guard;
 pub mod dominator;
+pub mod event_consistency;
+pub mod findings;
+pub mod inheritance;
+pub mod lint;
+pub mod mapping_roles;
+pub mod memory_ssa;
 pub mod pass;
 pub mod passes;
+pub mod pause;
 pub mod pattern;
+pub mod precision_loss;
+pub mod pruning;
+pub mod randomness;
+pub mod reentrancy_guard;
+pub mod scoring;
+pub mod selector_collision;
+pub mod signature_replay;
+pub mod signatures;
+pub mod similarity;
+pub mod specification;
+pub mod storage_usage;
+pub mod summary;
+pub mod time_dependence;
+pub mod trust_zones;
+pub mod unused_values;
+mod util;
+pub mod xref;
 
+pub use address_book::{AddressBook, AddressEntry, AddressLabels, AddressUse};
 pub use alias::{AliasAnalysis, AliasResult, AliasSet, PointsToSet};
-pub use cache::{AnalysisCache, CacheKey};
+pub use annotations::{AnalysisAnnotations, Annotation, AnnotationStore};
+pub use baseline::{Baseline, Suppressions};
+pub use cache::{AnalysisCache, CacheKey, ContractAnalyses, DiskCache};
+pub use callbacks::{
+    CallbackAnalysis, CallbackConfig, CallbackInterface, CallbackModel, CallbackReentrancy,
+    HookKind, ReentryEdge,
+};
+pub use cancel::{CancellationToken, Cancelled};
+pub use context_usage::{
+    CapabilityIssue, ContextRead, ContextUsageAnalysis, ContextUsageMatrix, FunctionContextUsage,
+};
 pub use control_flow::{ControlFlowGraph, Loop};
+pub use cost::{
+    CostModel, CustomCostModel, EvmCostModel, GasEstimate, GasEstimationPass, GasSchedule,
+};
 pub use cursor::{CursorPosition, IRCursor, ScannerCursor};
 pub use def_use::{DefKind, DefUseChains, Definition, Use, UseKind};
+pub use detectors::{run_detectors, CONTRACT_DETECTORS, WORKSPACE_DETECTORS};
+pub use division_guard::{DivisionGuardAnalysis, DivisorInfluence, UnguardedDivision};
 pub use dominator::DominatorTree;
+pub use event_consistency::{EventConsistencyAnalysis, EventStateMismatch, MismatchKind};
+pub use findings::{Finding, Severity};
+pub use inheritance::{InheritanceAnalysis, InheritanceIssue};
+pub use lint::{Lint, LintConfig, LintLevel, LintRule, Linter};
+pub use mapping_roles::{MappingRoleAnalysis, UnloggedBalanceChange};
+pub use memory_ssa::{MemoryAccess, MemoryAccessId, MemoryRegion, MemorySSA};
 pub use pass::{AnalysisID, AnalysisPass, Pass, PassManager};
+pub use pause::{PauseAnalysis, PauseCoverage, PauseGuard, PauseMatrix};
 pub use pattern::{Pattern, PatternBuilder, PatternMatcher};
+pub use precision_loss::{PrecisionLoss, PrecisionLossAnalysis, PrecisionLossKind};
+pub use pruning::{ContractPruning, PruneReport};
+pub use randomness::{EntropySource, RandomnessAnalysis, WeakRandomness};
+pub use reentrancy_guard::{ReentrancyGuardAnalysis, ReentrancyLock};
+pub use scoring::{rank, Score, ScoreFactor, SeverityScorer};
+pub use selector_collision::{
+    CollisionKind, SelectorCollision, SelectorCollisionAnalysis, SelectorOwner,
+};
+pub use signature_replay::{ReplayField, ReplayableSignature, SignatureReplayAnalysis};
+pub use signatures::{
+    Signature, SignatureDatabase, SignatureMatch, SignatureMatcher, SignaturePack,
+};
+pub use similarity::{FunctionFingerprint, SimilarityIndex, SimilarityMatch};
+pub use specification::{SpecClaim, SpecDrift, SpecificationAnalysis};
+pub use storage_usage::{SlotUsage, StorageAccess, StorageHygiene, StorageUsageAnalysis};
+pub use summary::{FunctionSummary, SummaryCall};
+pub use time_dependence::{TimeDependence, TimeDependenceAnalysis, TimeSource, TimeUse};
+pub use trust_zones::{TrustCrossing, TrustZone, TrustZones};
+pub use unused_values::{UnusedKind, UnusedValue, UnusedValueAnalysis};
+pub use xref::{ReferenceKind, Symbol, SymbolIndex, SymbolKind, SymbolReference};
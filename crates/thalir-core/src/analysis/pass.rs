@@ -1,7 +1,9 @@
+use super::cancel::CancellationToken;
 use crate::{contract::Contract, function::Function};
 use anyhow::Result;
 use std::any::Any;
 use std::collections::HashMap;
+use std::fmt;
 use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -20,6 +22,15 @@ pub enum AnalysisID {
     Custom(&'static str),
 }
 
+impl fmt::Display for AnalysisID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalysisID::Custom(name) => f.write_str(name),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
 pub trait Pass: Send + Sync {
     fn name(&self) -> &'static str;
 
@@ -76,6 +87,7 @@ pub struct PassManager {
     statistics: Vec<PassStatistics>,
     collect_stats: bool,
     valid_analyses: HashMap<String, Vec<AnalysisID>>,
+    cancellation: CancellationToken,
 }
 
 impl PassManager {
@@ -86,6 +98,7 @@ impl PassManager {
             statistics: Vec::new(),
             collect_stats: false,
             valid_analyses: HashMap::new(),
+            cancellation: CancellationToken::new(),
         }
     }
 
@@ -93,6 +106,16 @@ impl PassManager {
         self.collect_stats = true;
     }
 
+    /* Checked before every pass and between functions while computing analyses. Long-running
+     * passes should also poll `cancellation()` themselves. */
+    pub fn set_cancellation(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
     pub fn register_pass<P: Pass + 'static>(&mut self, pass: P) {
         self.passes.push(Box::new(pass));
     }
@@ -100,6 +123,14 @@ impl PassManager {
     pub fn run_all(&mut self, contract: &mut Contract) -> Result<()> {
         for i in 0..self.passes.len() {
             let mut pass = std::mem::replace(&mut self.passes[i], Box::new(DummyPass));
+            let _span =
+                tracing::info_span!("analyze", pass = pass.name(), contract = %contract.name)
+                    .entered();
+            if let Err(cancelled) = self.cancellation.check() {
+                tracing::warn!(%cancelled, "stopping before pass");
+                self.passes[i] = pass;
+                return Err(cancelled.into());
+            }
 
             let start = if self.collect_stats {
                 Some(Instant::now())
@@ -116,7 +147,18 @@ impl PassManager {
             pass.run_on_contract(contract, self)?;
 
             if pass.modifies_ir() {
-                self.invalidate_analyses(&contract.name, &pass.preserved_analyses());
+                let preserved = pass.preserved_analyses();
+                self.invalidate_analyses(&contract.name, &preserved);
+                for block in contract
+                    .functions
+                    .values_mut()
+                    .flat_map(|function| function.body.blocks.values_mut())
+                {
+                    block
+                        .metadata
+                        .annotations
+                        .retain(|id| preserved.contains(&id));
+                }
             }
 
             if let Some(start) = start {
@@ -194,6 +236,7 @@ impl PassManager {
         });
 
         if let Some(idx) = pass_idx {
+            let cancellation = self.cancellation.clone();
             let mut pass = self.passes.remove(idx);
 
             let results: Box<dyn Any + Send + Sync> = if let Some(analysis_pass) = pass
@@ -202,6 +245,7 @@ impl PassManager {
             {
                 let mut typed_results = HashMap::new();
                 for (func_name, function) in &contract.functions {
+                    cancellation.check()?;
                     typed_results.insert(func_name.clone(), analysis_pass.analyze(function)?);
                 }
                 Box::new(typed_results)
@@ -211,6 +255,7 @@ impl PassManager {
             {
                 let mut typed_results = HashMap::new();
                 for (func_name, function) in &contract.functions {
+                    cancellation.check()?;
                     typed_results.insert(func_name.clone(), analysis_pass.analyze(function)?);
                 }
                 Box::new(typed_results)
@@ -220,6 +265,7 @@ impl PassManager {
             {
                 let mut typed_results = HashMap::new();
                 for (func_name, function) in &contract.functions {
+                    cancellation.check()?;
                     typed_results.insert(func_name.clone(), analysis_pass.analyze(function)?);
                 }
                 Box::new(typed_results)
@@ -229,6 +275,7 @@ impl PassManager {
             {
                 let mut typed_results = HashMap::new();
                 for (func_name, function) in &contract.functions {
+                    cancellation.check()?;
                     typed_results.insert(func_name.clone(), analysis_pass.analyze(function)?);
                 }
                 Box::new(typed_results)
@@ -429,6 +476,23 @@ mod tests {
         assert_eq!(manager.passes.len(), 1);
     }
 
+    #[test]
+    fn test_cancelled_manager_stops_before_running_passes() {
+        use crate::analysis::cancel::Cancelled;
+
+        let mut manager = PassManager::new();
+        manager.register_pass(crate::analysis::passes::DominatorAnalysisPass);
+        let token = CancellationToken::new();
+        manager.set_cancellation(token.clone());
+        token.cancel();
+
+        let mut contract = Contract::new("Vault".to_string());
+        let err = manager.run_all(&mut contract).unwrap_err();
+        assert_eq!(err.downcast_ref::<Cancelled>(), Some(&Cancelled::Requested));
+        assert_eq!(manager.passes.len(), 1);
+        assert_eq!(manager.passes[0].name(), "dominator-analysis");
+    }
+
     #[test]
     fn test_analysis_caching() {
         let mut manager = PassManager::new();
@@ -0,0 +1,319 @@
+use super::findings::{Finding, Severity};
+use super::util::storage_key_slot;
+use super::storage_usage::StorageAccess;
+use crate::{
+    block::Terminator,
+    contract::Contract,
+    function::{Function, Mutability},
+    instructions::{CallTarget, Instruction},
+    types::Type,
+    values::Value,
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PauseGuard {
+    Modifier(String),
+    FlagCheck(String),
+    Call(String),
+}
+
+impl fmt::Display for PauseGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PauseGuard::Modifier(name) => write!(f, "modifier {}", name),
+            PauseGuard::FlagCheck(flag) => write!(f, "checks {}", flag),
+            PauseGuard::Call(name) => write!(f, "calls {}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PauseCoverage {
+    pub function: String,
+    pub state_changing: bool,
+    /* Writes the pause flag itself, as `pause` and `unpause` do. */
+    pub toggles_pause: bool,
+    pub guard: Option<PauseGuard>,
+}
+
+impl PauseCoverage {
+    pub fn is_gap(&self) -> bool {
+        self.state_changing && !self.toggles_pause && self.guard.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PauseMatrix {
+    pub flags: Vec<String>,
+    pub entries: Vec<PauseCoverage>,
+}
+
+impl PauseMatrix {
+    pub fn is_pausable(&self) -> bool {
+        !self.flags.is_empty() || self.entries.iter().any(|entry| entry.guard.is_some())
+    }
+
+    pub fn gaps(&self) -> impl Iterator<Item = &PauseCoverage> {
+        self.entries.iter().filter(|entry| entry.is_gap())
+    }
+}
+
+impl fmt::Display for PauseMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .entries
+            .iter()
+            .map(|entry| entry.function.len())
+            .chain(Some("entry point".len()))
+            .max()
+            .unwrap_or(0);
+        writeln!(f, "{:<width$}  mutates  pause guard", "entry point")?;
+        for entry in &self.entries {
+            let guard = match (&entry.guard, entry.toggles_pause) {
+                (Some(guard), _) => guard.to_string(),
+                (None, true) => "toggles pause".to_string(),
+                (None, false) if entry.state_changing => "MISSING".to_string(),
+                (None, false) => "-".to_string(),
+            };
+            writeln!(
+                f,
+                "{:<width$}  {:<7}  {}",
+                entry.function,
+                if entry.state_changing { "yes" } else { "no" },
+                guard
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub struct PauseAnalysis;
+
+impl PauseAnalysis {
+    pub const DETECTOR: &'static str = "unpaused-entry-point";
+
+    /* A pause flag is a bool state variable named like one. An entry point is covered when it
+     * carries a pause-named modifier, requires or branches on a value loaded from a flag, or
+     * calls an internal function that does either. */
+    pub fn analyze_contract(contract: &Contract) -> PauseMatrix {
+        let flags: Vec<(&BigUint, &str)> = contract
+            .storage_layout
+            .slots
+            .iter()
+            .filter(|var| matches!(var.var_type, Type::Bool) && is_pause_name(&var.name))
+            .map(|var| (&var.slot, var.name.as_str()))
+            .collect();
+
+        let entries = contract
+            .entry_points()
+            .into_iter()
+            .filter_map(|entry| {
+                let function = contract.functions.get(&entry.name)?;
+                let access = StorageAccess::of_function(function);
+                let read_only = matches!(function.mutability, Mutability::View | Mutability::Pure);
+                let guard = entry
+                    .guards
+                    .modifiers
+                    .iter()
+                    .find(|name| is_pause_name(name))
+                    .map(|name| PauseGuard::Modifier(name.clone()))
+                    .or_else(|| body_guard(contract, function, &flags, &mut HashSet::new()));
+                Some(PauseCoverage {
+                    function: entry.name,
+                    state_changing: !read_only
+                        && (function.metadata.modifies_state
+                            || !access.writes.is_empty()
+                            || access.unresolved_writes
+                            || function.is_payable()),
+                    toggles_pause: flags.iter().any(|(slot, _)| access.writes.contains(*slot)),
+                    guard,
+                })
+            })
+            .collect();
+
+        PauseMatrix {
+            flags: flags.iter().map(|(_, name)| name.to_string()).collect(),
+            entries,
+        }
+    }
+
+    /* Gaps are only reported for contracts that show some sign of being pausable. */
+    pub fn findings(contract: &Contract) -> Vec<Finding> {
+        let matrix = Self::analyze_contract(contract);
+        if !matrix.is_pausable() {
+            return Vec::new();
+        }
+        matrix
+            .gaps()
+            .map(|entry| {
+                Finding::new(
+                    Self::DETECTOR,
+                    Severity::Medium,
+                    &contract.name,
+                    &entry.function,
+                    "State-changing entry point is not covered by the pause check".to_string(),
+                )
+            })
+            .collect()
+    }
+}
+
+fn is_pause_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["pause", "stopped", "halt", "frozen", "freeze", "emergency"]
+        .iter()
+        .any(|word| name.contains(word))
+}
+
+fn body_guard(
+    contract: &Contract,
+    function: &Function,
+    flags: &[(&BigUint, &str)],
+    visited: &mut HashSet<String>,
+) -> Option<PauseGuard> {
+    if !visited.insert(function.name().to_string()) {
+        return None;
+    }
+
+    let mut derived: HashSet<&Value> = HashSet::new();
+    let mut checked_flag = None;
+    loop {
+        let before = derived.len();
+        for inst in function.body.blocks.values().flat_map(|b| &b.instructions) {
+            let Some(result) = inst.result() else {
+                continue;
+            };
+            let from_flag = match inst {
+                Instruction::StorageLoad { key, .. } => storage_key_slot(key)
+                    .and_then(|slot| flags.iter().find(|(flag, _)| **flag == slot))
+                    .map(|(_, name)| {
+                        checked_flag.get_or_insert(*name);
+                    })
+                    .is_some(),
+                _ => inst.operands().iter().any(|value| derived.contains(value)),
+            };
+            if from_flag {
+                derived.insert(result);
+            }
+        }
+        if derived.len() == before {
+            break;
+        }
+    }
+
+    let checks_flag = function.body.blocks.values().any(|block| {
+        let conditions = block.instructions.iter().filter_map(|inst| match inst {
+            Instruction::Require { condition, .. }
+            | Instruction::Assert { condition, .. }
+            | Instruction::Branch { condition, .. } => Some(condition),
+            _ => None,
+        });
+        let branch = match &block.terminator {
+            Terminator::Branch { condition, .. } => Some(condition),
+            _ => None,
+        };
+        conditions
+            .chain(branch)
+            .any(|condition| derived.contains(condition))
+    });
+    if checks_flag {
+        return checked_flag.map(|flag| PauseGuard::FlagCheck(flag.to_string()));
+    }
+
+    for inst in function.body.blocks.values().flat_map(|b| &b.instructions) {
+        let Instruction::Call {
+            target: CallTarget::Internal(name),
+            ..
+        } = inst
+        else {
+            continue;
+        };
+        let callee = contract
+            .functions
+            .get(name)
+            .or_else(|| contract.functions.values().find(|f| f.name() == name));
+        let guarded = match callee {
+            Some(callee) => body_guard(contract, callee, flags, visited).is_some(),
+            None => is_pause_name(name),
+        };
+        if guarded {
+            return Some(PauseGuard::Call(name.clone()));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::function::Visibility;
+
+    #[test]
+    fn test_matrix_reports_unguarded_entry_points() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Vault");
+        contract_builder.state_variable("paused", Type::Bool, 0);
+        contract_builder.state_variable("total", Type::Uint(256), 1);
+
+        let mut pause = contract_builder.function("pause");
+        pause.visibility(Visibility::External);
+        let mut entry = pause.entry_block();
+        let yes = entry.constant_bool(true);
+        entry.storage_store(BigUint::from(0u32), yes);
+        entry.return_void().unwrap();
+        pause.build().unwrap();
+
+        let mut require_live = contract_builder.function("_requireNotPaused");
+        require_live.visibility(Visibility::Internal);
+        let mut entry = require_live.entry_block();
+        let paused = entry.storage_load(BigUint::from(0u32));
+        let live = entry.not(paused);
+        entry.require(live, "paused");
+        entry.return_void().unwrap();
+        require_live.build().unwrap();
+
+        for (name, guarded) in [("deposit", true), ("withdraw", false)] {
+            let mut function = contract_builder.function(name);
+            function.visibility(Visibility::External);
+            let mut entry = function.entry_block();
+            if guarded {
+                entry.call_internal("_requireNotPaused", Vec::new());
+            }
+            let one = entry.constant_uint(1, 256);
+            entry.storage_store(BigUint::from(1u32), one);
+            entry.return_void().unwrap();
+            function.build().unwrap();
+        }
+
+        let mut total = contract_builder.function("total");
+        total
+            .visibility(Visibility::External)
+            .mutability(Mutability::View);
+        let mut entry = total.entry_block();
+        let value = entry.storage_load(BigUint::from(1u32));
+        entry.return_value(value).unwrap();
+        total.build().unwrap();
+
+        let contract = contract_builder.build().unwrap();
+        let matrix = PauseAnalysis::analyze_contract(&contract);
+        assert_eq!(matrix.flags, vec!["paused".to_string()]);
+
+        let row = |name: &str| matrix.entries.iter().find(|e| e.function == name).unwrap();
+        assert!(row("pause").toggles_pause);
+        assert_eq!(
+            row("deposit").guard,
+            Some(PauseGuard::Call("_requireNotPaused".to_string()))
+        );
+        assert!(!row("total").state_changing);
+
+        let gaps: Vec<&str> = matrix.gaps().map(|e| e.function.as_str()).collect();
+        assert_eq!(gaps, vec!["withdraw"]);
+        assert_eq!(PauseAnalysis::findings(&contract).len(), 1);
+        assert!(matrix.to_string().contains("MISSING"));
+    }
+}
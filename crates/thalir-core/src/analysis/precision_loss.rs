@@ -0,0 +1,411 @@
+use super::findings::{Finding, Severity};
+use super::util::{constant_slot, normalized, storage_key_slot};
+use crate::{
+    block::{BlockId, Terminator},
+    contract::Contract,
+    format::format_value,
+    function::Function,
+    instructions::Instruction,
+    metadata::InstructionLocation,
+    values::{Constant, Value},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+const FINANCIAL_TERMS: &[&str] = &[
+    "amount",
+    "balance",
+    "borrow",
+    "collateral",
+    "debt",
+    "deposit",
+    "fee",
+    "interest",
+    "liquidity",
+    "price",
+    "rate",
+    "repay",
+    "reserve",
+    "reward",
+    "share",
+    "stake",
+    "supply",
+    "swap",
+    "withdraw",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PrecisionLossKind {
+    DivideBeforeMultiply,
+    TruncatedIntermediate,
+}
+
+impl fmt::Display for PrecisionLossKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrecisionLossKind::DivideBeforeMultiply => write!(f, "division before multiplication"),
+            PrecisionLossKind::TruncatedIntermediate => {
+                write!(f, "truncated quotient in a scaled sum")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrecisionLoss {
+    pub function: String,
+    pub location: InstructionLocation,
+    pub division: InstructionLocation,
+    pub kind: PrecisionLossKind,
+    pub dividend: String,
+    pub divisor: String,
+    pub multiplier: String,
+    pub financial: bool,
+    pub suggestion: Option<String>,
+}
+
+impl PrecisionLoss {
+    pub fn severity(&self) -> Severity {
+        match (self.kind, self.financial) {
+            (PrecisionLossKind::DivideBeforeMultiply, true) => Severity::Medium,
+            (PrecisionLossKind::DivideBeforeMultiply, false)
+            | (PrecisionLossKind::TruncatedIntermediate, true) => Severity::Low,
+            (PrecisionLossKind::TruncatedIntermediate, false) => Severity::Info,
+        }
+    }
+
+    pub fn to_finding(&self, contract: &str) -> Finding {
+        let mut message = format!(
+            "{}: {} / {} is truncated before being multiplied by {}",
+            self.kind, self.dividend, self.divisor, self.multiplier
+        );
+        if let Some(suggestion) = &self.suggestion {
+            message.push_str("; ");
+            message.push_str(suggestion);
+        }
+        Finding::new(
+            PrecisionLossAnalysis::DETECTOR,
+            self.severity(),
+            contract,
+            &self.function,
+            message,
+        )
+        .at(self.location.clone())
+    }
+}
+
+pub struct PrecisionLossAnalysis;
+
+impl PrecisionLossAnalysis {
+    pub const DETECTOR: &'static str = "precision-loss";
+
+    pub fn analyze_contract(contract: &Contract) -> Vec<PrecisionLoss> {
+        contract
+            .functions
+            .values()
+            .flat_map(|function| Self::analyze_function(contract, function))
+            .collect()
+    }
+
+    pub fn findings(contract: &Contract) -> Vec<Finding> {
+        Self::analyze_contract(contract)
+            .iter()
+            .map(|loss| loss.to_finding(&contract.name))
+            .collect()
+    }
+
+    pub fn analyze_function(contract: &Contract, function: &Function) -> Vec<PrecisionLoss> {
+        let mut defs: HashMap<&Value, Definition> = HashMap::new();
+        let mut uses: HashMap<&Value, usize> = HashMap::new();
+
+        for (&block_id, block) in &function.body.blocks {
+            for (index, inst) in block.instructions.iter().enumerate() {
+                if let Some(result) = inst.result() {
+                    defs.insert(result, (block_id, index, inst));
+                }
+                for operand in inst.operands() {
+                    *uses.entry(operand).or_default() += 1;
+                }
+            }
+            match &block.terminator {
+                Terminator::Return(Some(value))
+                | Terminator::Branch {
+                    condition: value, ..
+                } => {
+                    *uses.entry(value).or_default() += 1;
+                }
+                _ => {}
+            }
+        }
+
+        let financial = is_financial(contract, function);
+        let mut results = Vec::new();
+
+        for (&block_id, block) in &function.body.blocks {
+            for (index, inst) in block.instructions.iter().enumerate() {
+                let (left, right) = match inst {
+                    Instruction::Mul { left, right, .. }
+                    | Instruction::CheckedMul { left, right, .. } => (left, right),
+                    _ => continue,
+                };
+
+                for (factor, multiplier) in [(left, right), (right, left)] {
+                    let (kind, (div_block, div_index, division)) =
+                        match truncating_source(&defs, factor) {
+                            Some(source) => source,
+                            None => continue,
+                        };
+                    let (quotient, dividend, divisor) = match division {
+                        Instruction::Div {
+                            result,
+                            left,
+                            right,
+                            ..
+                        }
+                        | Instruction::Sdiv {
+                            result,
+                            left,
+                            right,
+                            ..
+                        }
+                        | Instruction::CheckedDiv {
+                            result,
+                            left,
+                            right,
+                            ..
+                        } => (result, left, right),
+                        _ => continue,
+                    };
+
+                    if same_origin(&defs, divisor, multiplier) {
+                        continue;
+                    }
+
+                    let suggestion = match kind {
+                        PrecisionLossKind::DivideBeforeMultiply
+                            if uses.get(quotient).copied().unwrap_or(0) == 1 =>
+                        {
+                            Some(format!(
+                                "compute ({} * {}) / {} instead if the product cannot overflow",
+                                format_value(dividend),
+                                format_value(multiplier),
+                                format_value(divisor)
+                            ))
+                        }
+                        _ => None,
+                    };
+
+                    results.push(PrecisionLoss {
+                        function: function.name().to_string(),
+                        location: InstructionLocation {
+                            block: block_id,
+                            index,
+                        },
+                        division: InstructionLocation {
+                            block: div_block,
+                            index: div_index,
+                        },
+                        kind,
+                        dividend: format_value(dividend),
+                        divisor: format_value(divisor),
+                        multiplier: format_value(multiplier),
+                        financial,
+                        suggestion,
+                    });
+                }
+            }
+        }
+
+        results
+    }
+}
+
+type Definition<'a> = (BlockId, usize, &'a Instruction);
+
+fn is_division(inst: &Instruction) -> bool {
+    matches!(
+        inst,
+        Instruction::Div { .. } | Instruction::Sdiv { .. } | Instruction::CheckedDiv { .. }
+    )
+}
+
+fn conversion_source(inst: &Instruction) -> Option<&Value> {
+    match inst {
+        Instruction::Assign { value, .. }
+        | Instruction::Cast { value, .. }
+        | Instruction::ZeroExtend { value, .. }
+        | Instruction::SignExtend { value, .. }
+        | Instruction::Truncate { value, .. } => Some(value),
+        _ => None,
+    }
+}
+
+fn definition<'a>(
+    defs: &HashMap<&'a Value, Definition<'a>>,
+    mut value: &'a Value,
+) -> Option<Definition<'a>> {
+    let mut seen = HashSet::new();
+    loop {
+        let def = *defs.get(value)?;
+        match conversion_source(def.2) {
+            Some(source) if seen.insert(value) => value = source,
+            Some(_) => return None,
+            None => return Some(def),
+        }
+    }
+}
+
+fn truncating_source<'a>(
+    defs: &HashMap<&'a Value, Definition<'a>>,
+    factor: &'a Value,
+) -> Option<(PrecisionLossKind, Definition<'a>)> {
+    let def = definition(defs, factor)?;
+    if is_division(def.2) {
+        return Some((PrecisionLossKind::DivideBeforeMultiply, def));
+    }
+
+    let mut visited = HashSet::new();
+    let mut worklist = vec![def];
+    while let Some((_, _, inst)) = worklist.pop() {
+        let (left, right) = match inst {
+            Instruction::Add { left, right, .. }
+            | Instruction::Sub { left, right, .. }
+            | Instruction::CheckedAdd { left, right, .. }
+            | Instruction::CheckedSub { left, right, .. } => (left, right),
+            _ => continue,
+        };
+        for operand in [left, right] {
+            if !visited.insert(operand) {
+                continue;
+            }
+            match definition(defs, operand) {
+                Some(def) if is_division(def.2) => {
+                    return Some((PrecisionLossKind::TruncatedIntermediate, def));
+                }
+                Some(def) => worklist.push(def),
+                None => {}
+            }
+        }
+    }
+    None
+}
+
+fn same_origin<'a>(defs: &HashMap<&'a Value, Definition<'a>>, a: &'a Value, b: &'a Value) -> bool {
+    let root = |value: &'a Value| -> Value {
+        let mut value = value;
+        let mut seen = HashSet::new();
+        while let Some(source) = defs.get(value).and_then(|def| conversion_source(def.2)) {
+            if !seen.insert(value) {
+                break;
+            }
+            value = source;
+        }
+        let slot = match defs.get(value) {
+            Some((_, _, Instruction::StorageLoad { key, .. })) => storage_key_slot(key),
+            _ => None,
+        };
+        match slot {
+            Some(slot) => Value::Constant(Constant::Uint(slot, 256)),
+            None => value.clone(),
+        }
+    };
+    root(a) == root(b)
+}
+
+fn is_financial(contract: &Contract, function: &Function) -> bool {
+    let mentions = |name: &str| {
+        let name = normalized(name);
+        FINANCIAL_TERMS.iter().any(|term| name.contains(term))
+    };
+    if mentions(function.name()) {
+        return true;
+    }
+
+    let mut slots = HashSet::new();
+    for block in function.body.blocks.values() {
+        for inst in &block.instructions {
+            match inst {
+                Instruction::StorageLoad { key, .. } | Instruction::StorageStore { key, .. } => {
+                    slots.extend(storage_key_slot(key));
+                }
+                Instruction::MappingLoad { mapping, .. }
+                | Instruction::MappingStore { mapping, .. } => {
+                    slots.extend(constant_slot(mapping));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    contract
+        .storage_layout
+        .slots
+        .iter()
+        .any(|slot| slots.contains(&slot.slot) && mentions(&slot.name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
+
+    #[test]
+    fn test_divide_before_multiply_in_reward_math() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Staking");
+
+        let mut func = contract_builder.function("pendingReward");
+        func.param("stake", Type::Uint(256));
+        func.param("rate", Type::Uint(256));
+        let stake = func.get_param(0);
+        let rate = func.get_param(1);
+        let mut entry = func.entry_block();
+        let precision = entry.constant_uint(1_000_000, 256);
+        let per_unit = entry.div(stake, precision, Type::Uint(256));
+        let reward = entry.mul(per_unit, rate, Type::Uint(256));
+        entry.return_value(reward).unwrap();
+        func.build().unwrap();
+
+        let contract = contract_builder.build().unwrap();
+        let results = PrecisionLossAnalysis::analyze_contract(&contract);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].kind, PrecisionLossKind::DivideBeforeMultiply);
+        assert!(results[0].financial);
+        assert_eq!(results[0].severity(), Severity::Medium);
+        assert_eq!(results[0].division.index + 1, results[0].location.index);
+        assert!(results[0].suggestion.is_some());
+    }
+
+    #[test]
+    fn test_rounding_down_is_not_reported() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Grid");
+
+        let mut func = contract_builder.function("snap");
+        func.param("x", Type::Uint(256));
+        func.param("y", Type::Uint(256));
+        let x = func.get_param(0);
+        let y = func.get_param(1);
+        let mut entry = func.entry_block();
+        let step = entry.constant_uint(100, 256);
+        let cells = entry.div(x, step.clone(), Type::Uint(256));
+        let snapped = entry.mul(cells.clone(), step, Type::Uint(256));
+        let offset = entry.add(cells, y.clone(), Type::Uint(256));
+        let scaled = entry.mul(offset, y, Type::Uint(256));
+        let total = entry.add(snapped, scaled, Type::Uint(256));
+        entry.return_value(total).unwrap();
+        func.build().unwrap();
+
+        let contract = contract_builder.build().unwrap();
+        let results = PrecisionLossAnalysis::analyze_contract(&contract);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].kind, PrecisionLossKind::TruncatedIntermediate);
+        assert!(!results[0].financial);
+        assert_eq!(results[0].severity(), Severity::Info);
+        assert!(results[0].suggestion.is_none());
+    }
+}
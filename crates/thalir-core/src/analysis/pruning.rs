@@ -0,0 +1,203 @@
+use crate::{
+    contract::Contract,
+    instructions::{CallTarget, Instruction},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+/* Which contracts of a workspace the analyzed targets reach, and which they never do. */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruneReport {
+    pub kept: Vec<String>,
+    pub pruned: Vec<String>,
+    /* Files none of whose contracts were kept, which are imported for nothing the targets use. */
+    pub unused_files: Vec<String>,
+}
+
+impl PruneReport {
+    pub fn keeps(&self, contract: &str) -> bool {
+        self.kept.iter().any(|kept| kept == contract)
+    }
+}
+
+pub struct ContractPruning;
+
+impl ContractPruning {
+    /* Keeps the roots and everything they reach through `references`. A root that names no
+     * contract is ignored. */
+    pub fn prune(contracts: &[Contract], roots: &[&str]) -> PruneReport {
+        let names: HashSet<&str> = contracts.iter().map(|c| c.name.as_str()).collect();
+        let by_name: HashMap<&str, &Contract> =
+            contracts.iter().map(|c| (c.name.as_str(), c)).collect();
+
+        let mut reached: HashSet<&str> = HashSet::new();
+        let mut queue: VecDeque<&str> = roots
+            .iter()
+            .copied()
+            .filter(|root| names.contains(root))
+            .collect();
+        while let Some(name) = queue.pop_front() {
+            if !reached.insert(name) {
+                continue;
+            }
+            let Some(contract) = by_name.get(name) else {
+                continue;
+            };
+            for referenced in Self::references(contract, &names) {
+                if let Some((&known, _)) = by_name.get_key_value(referenced.as_str()) {
+                    queue.push_back(known);
+                }
+            }
+        }
+
+        let mut report = PruneReport::default();
+        let mut files: HashMap<&str, bool> = HashMap::new();
+        for contract in contracts {
+            let kept = reached.contains(contract.name.as_str());
+            if kept {
+                report.kept.push(contract.name.clone());
+            } else {
+                report.pruned.push(contract.name.clone());
+            }
+            if let Some(file) = &contract.metadata.source_file {
+                *files.entry(file.as_str()).or_default() |= kept;
+            }
+        }
+        report.unused_files = files
+            .into_iter()
+            .filter(|(_, kept)| !kept)
+            .map(|(file, _)| file.to_string())
+            .collect();
+        report.unused_files.sort();
+        report
+    }
+
+    /* The other contracts `contract` names. With its source at hand that is every identifier in
+     * the declaration outside comments and strings, which covers bases, `new`, casts, `using` and
+     * library calls alike. Without it, only bases and `Library.function` calls are visible. */
+    pub fn references(contract: &Contract, names: &HashSet<&str>) -> BTreeSet<String> {
+        let mut found: BTreeSet<String> = contract.metadata.bases.iter().cloned().collect();
+
+        let declaration = contract
+            .metadata
+            .source_code
+            .as_deref()
+            .zip(contract.metadata.location.as_ref())
+            .and_then(|(source, location)| source.get(location.start_byte..location.end_byte));
+        match declaration {
+            Some(text) => found.extend(
+                identifiers(text)
+                    .filter(|word| names.contains(word))
+                    .map(str::to_string),
+            ),
+            None => {
+                for inst in contract
+                    .functions
+                    .values()
+                    .flat_map(|f| f.body.blocks.values())
+                    .flat_map(|b| &b.instructions)
+                {
+                    if let Instruction::Call {
+                        target: CallTarget::Internal(name) | CallTarget::Library(name),
+                        ..
+                    } = inst
+                    {
+                        if let Some((library, _)) = name.split_once('.') {
+                            found.insert(library.to_string());
+                        }
+                    }
+                }
+            }
+        }
+        found.remove(&contract.name);
+        found
+    }
+}
+
+/* Identifiers in Solidity source, skipping comments and string literals. */
+fn identifiers(text: &str) -> impl Iterator<Item = &str> {
+    let bytes = text.as_bytes();
+    let mut words = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = text[i..].find('\n').map_or(bytes.len(), |end| i + end);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = text[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + end + 4);
+            }
+            quote @ (b'"' | b'\'') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' || c == b'$' => {
+                let start = i;
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'$')
+                {
+                    i += 1;
+                }
+                words.push(&text[start..i]);
+            }
+            _ => i += 1,
+        }
+    }
+    words.into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::values::SourceLocation;
+
+    fn declared(builder: &mut IRBuilder, name: &str, source: &str, file: &str) -> Contract {
+        let start = source.find(&format!("contract {}", name)).unwrap();
+        let end = start + source[start..].find("\n}").unwrap() + 2;
+        let mut contract = builder.contract(name).build().unwrap();
+        contract.metadata.source_file = Some(file.to_string());
+        contract.metadata.source_code = Some(source.to_string());
+        contract.metadata.location = Some(SourceLocation {
+            file: file.to_string(),
+            line: 1,
+            column: 0,
+            end_line: None,
+            end_column: None,
+            start_byte: start,
+            end_byte: end,
+        });
+        contract
+    }
+
+    #[test]
+    fn test_prune_keeps_what_the_targets_reach() {
+        let vault = "contract Vault {\n    // unlike Unused, this one is real\n    Token token = new Token();\n    string name = \"Unused\";\n}\n";
+        let lib = "contract Token {\n    function f() { Math.max(1, 2); }\n}\ncontract Math {\n}\ncontract Unused {\n}\n";
+
+        let mut builder = IRBuilder::new();
+        let contracts = vec![
+            declared(&mut builder, "Vault", vault, "src/Vault.sol"),
+            declared(&mut builder, "Token", lib, "lib/Token.sol"),
+            declared(&mut builder, "Math", lib, "lib/Token.sol"),
+            declared(&mut builder, "Unused", lib, "lib/Token.sol"),
+            declared(
+                &mut builder,
+                "Orphan",
+                "contract Orphan {\n}\n",
+                "lib/Orphan.sol",
+            ),
+        ];
+
+        let report = ContractPruning::prune(&contracts, &["Vault"]);
+        assert_eq!(report.kept, ["Vault", "Token", "Math"]);
+        assert_eq!(report.pruned, ["Unused", "Orphan"]);
+        assert_eq!(report.unused_files, ["lib/Orphan.sol"]);
+        assert!(report.keeps("Math"));
+    }
+}
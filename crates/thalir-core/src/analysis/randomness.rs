@@ -0,0 +1,284 @@
+use super::findings::{Finding, Severity};
+use crate::{
+    block::Terminator,
+    contract::Contract,
+    function::Function,
+    instructions::{BuiltinFunction, CallTarget, ContextVariable, Instruction},
+    metadata::InstructionLocation,
+    values::Value,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EntropySource {
+    Timestamp,
+    BlockNumber,
+    Difficulty,
+    PrevRandao,
+    BlockHash,
+}
+
+impl fmt::Display for EntropySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntropySource::Timestamp => write!(f, "block.timestamp"),
+            EntropySource::BlockNumber => write!(f, "block.number"),
+            EntropySource::Difficulty => write!(f, "block.difficulty"),
+            EntropySource::PrevRandao => write!(f, "block.prevrandao"),
+            EntropySource::BlockHash => write!(f, "blockhash"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeakRandomness {
+    pub function: String,
+    pub location: InstructionLocation,
+    pub sources: Vec<EntropySource>,
+    /* The function reads like minting, a lottery or some other selection. */
+    pub selection_context: bool,
+    /* The function checks a revealed value against a stored commitment, or hashes a block
+     * number fixed by an earlier transaction. */
+    pub commit_reveal: bool,
+}
+
+impl WeakRandomness {
+    pub fn severity(&self) -> Severity {
+        if self.commit_reveal {
+            Severity::Info
+        } else if self.selection_context {
+            Severity::High
+        } else {
+            Severity::Medium
+        }
+    }
+
+    pub fn to_finding(&self, contract: &str) -> Finding {
+        let sources: Vec<String> = self.sources.iter().map(|s| s.to_string()).collect();
+        Finding::new(
+            RandomnessAnalysis::DETECTOR,
+            self.severity(),
+            contract,
+            &self.function,
+            format!(
+                "Selection by modulo over {}, which block producers can predict or influence",
+                sources.join(", ")
+            ),
+        )
+        .at(self.location.clone())
+    }
+}
+
+pub struct RandomnessAnalysis;
+
+impl RandomnessAnalysis {
+    pub const DETECTOR: &'static str = "weak-randomness";
+
+    pub fn analyze_contract(contract: &Contract) -> Vec<WeakRandomness> {
+        contract
+            .functions
+            .values()
+            .flat_map(Self::analyze_function)
+            .collect()
+    }
+
+    /* Reports every modulo whose operand derives from block data, including through hashing
+     * and arithmetic. */
+    pub fn analyze_function(function: &Function) -> Vec<WeakRandomness> {
+        let mut defs: HashMap<&Value, &Instruction> = HashMap::new();
+        let mut entropy: HashMap<&Value, Vec<EntropySource>> = HashMap::new();
+        for inst in function.body.blocks.values().flat_map(|b| &b.instructions) {
+            if let Some(result) = inst.result() {
+                defs.insert(result, inst);
+                if let Some(source) = entropy_source(inst) {
+                    entropy.insert(result, vec![source]);
+                }
+            }
+        }
+
+        loop {
+            let mut changed = false;
+            for inst in function.body.blocks.values().flat_map(|b| &b.instructions) {
+                let Some(result) = inst.result() else {
+                    continue;
+                };
+                let mut sources = entropy.get(result).cloned().unwrap_or_default();
+                for operand in inst.operands() {
+                    for source in entropy.get(operand).into_iter().flatten() {
+                        if !sources.contains(source) {
+                            sources.push(*source);
+                        }
+                    }
+                }
+                if !sources.is_empty() && entropy.get(result).map(Vec::len) != Some(sources.len()) {
+                    entropy.insert(result, sources);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let selection_context = is_selection_name(function.name());
+        let commit_reveal = commit_reveal(function, &defs);
+        let mut results = Vec::new();
+        for (&block_id, block) in &function.body.blocks {
+            for (index, inst) in block.instructions.iter().enumerate() {
+                let (Instruction::Mod { left, .. } | Instruction::Smod { left, .. }) = inst else {
+                    continue;
+                };
+                if let Some(sources) = entropy.get(left) {
+                    results.push(WeakRandomness {
+                        function: function.name().to_string(),
+                        location: InstructionLocation {
+                            block: block_id,
+                            index,
+                        },
+                        sources: sources.clone(),
+                        selection_context,
+                        commit_reveal,
+                    });
+                }
+            }
+        }
+        results
+    }
+
+    pub fn findings(contract: &Contract) -> Vec<Finding> {
+        Self::analyze_contract(contract)
+            .iter()
+            .filter(|weak| !weak.commit_reveal)
+            .map(|weak| weak.to_finding(&contract.name))
+            .collect()
+    }
+}
+
+fn entropy_source(inst: &Instruction) -> Option<EntropySource> {
+    match inst {
+        Instruction::GetContext { var, .. } => match var {
+            ContextVariable::BlockTimestamp => Some(EntropySource::Timestamp),
+            ContextVariable::BlockNumber => Some(EntropySource::BlockNumber),
+            ContextVariable::BlockDifficulty => Some(EntropySource::Difficulty),
+            ContextVariable::PrevRandao => Some(EntropySource::PrevRandao),
+            _ => None,
+        },
+        Instruction::Call {
+            target: CallTarget::Builtin(BuiltinFunction::BlockHash),
+            ..
+        } => Some(EntropySource::BlockHash),
+        _ => None,
+    }
+}
+
+fn is_selection_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    [
+        "mint", "lottery", "draw", "pick", "winner", "random", "raffle", "roll", "select",
+    ]
+    .iter()
+    .any(|word| name.contains(word))
+}
+
+fn commit_reveal(function: &Function, defs: &HashMap<&Value, &Instruction>) -> bool {
+    let loaded = |value: &Value| {
+        matches!(
+            defs.get(value),
+            Some(Instruction::StorageLoad { .. } | Instruction::MappingLoad { .. })
+        )
+    };
+    let hashed = |value: &Value| matches!(defs.get(value), Some(Instruction::Keccak256 { .. }));
+
+    let stored_block_hash = function
+        .body
+        .blocks
+        .values()
+        .flat_map(|b| &b.instructions)
+        .any(|inst| match inst {
+            Instruction::Call {
+                target: CallTarget::Builtin(BuiltinFunction::BlockHash),
+                args,
+                ..
+            } => args.first().is_some_and(loaded),
+            _ => false,
+        });
+
+    let checks_commitment = function.body.blocks.values().any(|block| {
+        let conditions = block.instructions.iter().filter_map(|inst| match inst {
+            Instruction::Require { condition, .. }
+            | Instruction::Assert { condition, .. }
+            | Instruction::Branch { condition, .. } => Some(condition),
+            _ => None,
+        });
+        let branch = match &block.terminator {
+            Terminator::Branch { condition, .. } => Some(condition),
+            _ => None,
+        };
+        conditions
+            .chain(branch)
+            .any(|condition| match defs.get(condition) {
+                Some(Instruction::Eq { left, right, .. }) => {
+                    (hashed(left) && loaded(right)) || (loaded(left) && hashed(right))
+                }
+                _ => false,
+            })
+    });
+
+    stored_block_hash || checks_commitment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{IRBuilder, InstBuilderExt};
+    use crate::types::Type;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_flags_block_entropy_unless_committed() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Lottery");
+        contract_builder.state_variable("commitBlock", Type::Uint(256), 0);
+
+        let mut draw = contract_builder.function("drawWinner");
+        let mut entry = draw.entry_block();
+        let seed = entry.block_prevrandao();
+        let now = entry.block_timestamp();
+        let mixed = entry.add(seed, now, Type::Uint(256));
+        let len = entry.constant_uint(32, 256);
+        let hash = entry.keccak256(mixed, len);
+        let players = entry.constant_uint(10, 256);
+        let winner = entry.mod_(hash, players, Type::Uint(256));
+        entry.return_value(winner).unwrap();
+        draw.build().unwrap();
+
+        let mut reveal = contract_builder.function("reveal");
+        let mut entry = reveal.entry_block();
+        let committed = entry.storage_load(BigUint::from(0u32));
+        let hash = entry.block_hash(committed);
+        let players = entry.constant_uint(10, 256);
+        let winner = entry.mod_(hash, players, Type::Uint(256));
+        entry.return_value(winner).unwrap();
+        reveal.build().unwrap();
+
+        let contract = contract_builder.build().unwrap();
+        let report = RandomnessAnalysis::analyze_contract(&contract);
+        assert_eq!(report.len(), 2);
+
+        let draw = report.iter().find(|w| w.function == "drawWinner").unwrap();
+        assert_eq!(
+            draw.sources,
+            vec![EntropySource::PrevRandao, EntropySource::Timestamp]
+        );
+        assert_eq!(draw.severity(), Severity::High);
+
+        let reveal = report.iter().find(|w| w.function == "reveal").unwrap();
+        assert!(reveal.commit_reveal);
+
+        let findings = RandomnessAnalysis::findings(&contract);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].function, "drawWinner");
+    }
+}
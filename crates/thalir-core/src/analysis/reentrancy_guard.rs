@@ -0,0 +1,393 @@
+/*! Reentrancy guards: what makes a function refuse to be entered a second time.
+ *
+ * A `nonReentrant`-style modifier guards the whole function. Modifier bodies are not lowered, so
+ * one is recognised by its name, which covers OpenZeppelin's `ReentrancyGuard` and its transient
+ * variant. A lock written out in the IR is recognised by its shape instead: a storage or transient
+ * slot is checked, set to a constant, and set to a different constant after the calls it protects.
+ * The check and the set may also sit in an internal function the guarded one calls, the way
+ * `_nonReentrantBefore` and `_nonReentrantAfter` split them.
+ */
+
+use super::control_flow::ControlFlowGraph;
+use crate::{
+    block::Terminator,
+    contract::Contract,
+    function::Function,
+    instructions::{CallTarget, Instruction, StorageKey},
+    metadata::{InstructionLocation, ReentrancyGuardType},
+    values::{Constant, Value},
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/* A lock held over part of a function, from the instruction that sets it to the one that releases
+ * it. A modifier has no region: it holds over the whole body. */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReentrancyLock {
+    pub kind: ReentrancyGuardType,
+    /* The modifier, or the state variable the lock lives in. */
+    pub name: String,
+    pub region: Option<(InstructionLocation, InstructionLocation)>,
+}
+
+impl ReentrancyLock {
+    pub fn holds(&self, cfg: &ControlFlowGraph, location: &InstructionLocation) -> bool {
+        match &self.region {
+            None => true,
+            Some((set, release)) => {
+                let at = (location.clone(), 0);
+                before(cfg, &(set.clone(), 1), &at) && before(cfg, &at, &(release.clone(), 1))
+            }
+        }
+    }
+}
+
+pub struct ReentrancyGuardAnalysis;
+
+impl ReentrancyGuardAnalysis {
+    pub fn locks(contract: &Contract, function: &Function) -> Vec<ReentrancyLock> {
+        let mut locks: Vec<ReentrancyLock> = contract
+            .guards(function)
+            .modifiers
+            .into_iter()
+            .filter(|name| is_guard_name(name))
+            .map(|name| ReentrancyLock {
+                kind: ReentrancyGuardType::NonReentrantModifier,
+                name,
+                region: None,
+            })
+            .collect();
+
+        let cfg = ControlFlowGraph::build(function);
+        let mut by_slot: HashMap<&LockSlot, Vec<&Event>> = HashMap::new();
+        let events = events(contract, function, true);
+        for event in &events {
+            by_slot.entry(&event.slot).or_default().push(event);
+        }
+        for (slot, events) in by_slot {
+            let (writes, checks): (Vec<&Event>, Vec<&Event>) =
+                events.into_iter().partition(|e| e.write.is_some());
+            let region = writes.iter().find_map(|set| {
+                if !checks.iter().any(|check| before(&cfg, &check.at, &set.at)) {
+                    return None;
+                }
+                let release = writes.iter().find(|release| {
+                    release.write != set.write && before(&cfg, &set.at, &release.at)
+                })?;
+                Some((set.at.0.clone(), release.at.0.clone()))
+            });
+            if let Some(region) = region {
+                locks.push(ReentrancyLock {
+                    kind: if slot.transient {
+                        ReentrancyGuardType::TransientLock
+                    } else {
+                        ReentrancyGuardType::Mutex
+                    },
+                    name: slot_name(contract, slot),
+                    region: Some(region),
+                });
+            }
+        }
+        locks
+    }
+
+    /* Whether some lock is held while the instruction at `location` runs. */
+    pub fn protects(
+        contract: &Contract,
+        function: &Function,
+        location: &InstructionLocation,
+    ) -> bool {
+        let locks = Self::locks(contract, function);
+        if locks.iter().any(|lock| lock.region.is_none()) {
+            return true;
+        }
+        let cfg = ControlFlowGraph::build(function);
+        locks.iter().any(|lock| lock.holds(&cfg, location))
+    }
+}
+
+fn is_guard_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.contains("reentran") || name == "lock" || name == "mutex"
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct LockSlot {
+    transient: bool,
+    slot: BigUint,
+}
+
+/* A check of the slot, or a write to it: `Some(None)` for a delete, which writes zero. */
+struct Event {
+    slot: LockSlot,
+    write: Option<Option<Constant>>,
+    /* Events an internal call contributes share its location; the second field keeps a callee's
+     * check ahead of its write. */
+    at: (InstructionLocation, u8),
+}
+
+fn events(contract: &Contract, function: &Function, follow_calls: bool) -> Vec<Event> {
+    let mut derived: HashMap<&Value, LockSlot> = HashMap::new();
+    loop {
+        let before = derived.len();
+        for inst in function.body.blocks.values().flat_map(|b| &b.instructions) {
+            let Some(result) = inst.result() else {
+                continue;
+            };
+            let slot = match inst {
+                Instruction::StorageLoad {
+                    key: StorageKey::Slot(slot),
+                    ..
+                } => Some(LockSlot {
+                    transient: false,
+                    slot: slot.clone(),
+                }),
+                Instruction::TransientLoad {
+                    key: StorageKey::Slot(slot),
+                    ..
+                } => Some(LockSlot {
+                    transient: true,
+                    slot: slot.clone(),
+                }),
+                _ => inst
+                    .operands()
+                    .iter()
+                    .find_map(|value| derived.get(value).cloned()),
+            };
+            if let Some(slot) = slot {
+                derived.entry(result).or_insert(slot);
+            }
+        }
+        if derived.len() == before {
+            break;
+        }
+    }
+
+    let mut events = Vec::new();
+    for (&block_id, block) in &function.body.blocks {
+        let at = |index: usize, order: u8| {
+            (
+                InstructionLocation {
+                    block: block_id,
+                    index,
+                },
+                order,
+            )
+        };
+        for (index, inst) in block.instructions.iter().enumerate() {
+            match inst {
+                Instruction::Require { condition, .. }
+                | Instruction::Assert { condition, .. }
+                | Instruction::Branch { condition, .. } => {
+                    if let Some(slot) = derived.get(condition) {
+                        events.push(Event {
+                            slot: slot.clone(),
+                            write: None,
+                            at: at(index, 0),
+                        });
+                    }
+                }
+                Instruction::StorageStore {
+                    key: StorageKey::Slot(slot),
+                    value: Value::Constant(constant),
+                }
+                | Instruction::TransientStore {
+                    key: StorageKey::Slot(slot),
+                    value: Value::Constant(constant),
+                } => events.push(Event {
+                    slot: LockSlot {
+                        transient: matches!(inst, Instruction::TransientStore { .. }),
+                        slot: slot.clone(),
+                    },
+                    write: Some(Some(constant.clone())),
+                    at: at(index, 0),
+                }),
+                Instruction::StorageDelete {
+                    key: StorageKey::Slot(slot),
+                } => events.push(Event {
+                    slot: LockSlot {
+                        transient: false,
+                        slot: slot.clone(),
+                    },
+                    write: Some(None),
+                    at: at(index, 0),
+                }),
+                Instruction::Call {
+                    target: CallTarget::Internal(name),
+                    ..
+                } if follow_calls => {
+                    let Some(callee) = contract
+                        .functions
+                        .get(name)
+                        .or_else(|| contract.functions.values().find(|f| f.name() == name))
+                    else {
+                        continue;
+                    };
+                    /* A callee is summarised per slot: whether it checks it, and the value it
+                     * writes when it writes only one. */
+                    let mut checked: HashSet<LockSlot> = HashSet::new();
+                    let mut written: HashMap<LockSlot, Vec<Option<Constant>>> = HashMap::new();
+                    for event in events(contract, callee, false) {
+                        match event.write {
+                            None => {
+                                checked.insert(event.slot);
+                            }
+                            Some(value) => {
+                                let values = written.entry(event.slot).or_default();
+                                if !values.contains(&value) {
+                                    values.push(value);
+                                }
+                            }
+                        }
+                    }
+                    for slot in checked {
+                        events.push(Event {
+                            slot,
+                            write: None,
+                            at: at(index, 0),
+                        });
+                    }
+                    for (slot, mut values) in written {
+                        if values.len() == 1 {
+                            events.push(Event {
+                                slot,
+                                write: values.pop(),
+                                at: at(index, 1),
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Terminator::Branch { condition, .. } = &block.terminator {
+            if let Some(slot) = derived.get(condition) {
+                events.push(Event {
+                    slot: slot.clone(),
+                    write: None,
+                    at: at(block.instructions.len(), 0),
+                });
+            }
+        }
+    }
+    events
+}
+
+/* Within a block by position; across blocks, `a` comes first when control can get from its block
+ * to `b`'s. */
+fn before(
+    cfg: &ControlFlowGraph,
+    a: &(InstructionLocation, u8),
+    b: &(InstructionLocation, u8),
+) -> bool {
+    if a.0.block == b.0.block {
+        (a.0.index, a.1) < (b.0.index, b.1)
+    } else {
+        cfg.has_path(a.0.block, b.0.block)
+    }
+}
+
+fn slot_name(contract: &Contract, lock: &LockSlot) -> String {
+    match contract
+        .storage_layout
+        .slots
+        .iter()
+        .find(|var| !lock.transient && var.slot == lock.slot)
+    {
+        Some(var) => var.name.clone(),
+        None if lock.transient => format!("transient slot {}", lock.slot),
+        None => format!("slot {}", lock.slot),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::function::Visibility;
+    use crate::types::Type;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_locks_are_recognised_by_modifier_and_by_shape() {
+        let mut builder = IRBuilder::new();
+        let mut contract = builder.contract("Vault");
+        contract.state_variable("locked", Type::Bool, 0);
+
+        let mut before = contract.function("_nonReentrantBefore");
+        before.visibility(Visibility::Private);
+        let mut entry = before.entry_block();
+        let locked = entry.storage_load(BigUint::from(0u32));
+        let free = entry.not(locked);
+        entry.require(free, "reentrant call");
+        let on = entry.constant_bool(true);
+        entry.storage_store(BigUint::from(0u32), on);
+        entry.return_void().unwrap();
+        before.build().unwrap();
+
+        for (name, locked) in [("withdraw", true), ("sweep", false)] {
+            let mut function = contract.function(name);
+            function.visibility(Visibility::External);
+            let mut entry = function.entry_block();
+            if locked {
+                entry.call_internal("_nonReentrantBefore", Vec::new());
+            }
+            let to = entry.msg_sender();
+            let amount = entry.constant_uint(1, 256);
+            entry.call_external(to, amount.clone(), Vec::new(), None);
+            let off = entry.constant_bool(false);
+            entry.storage_store(BigUint::from(0u32), off);
+            entry.return_void().unwrap();
+            function.build().unwrap();
+        }
+
+        let mut guarded = contract.function("claim");
+        guarded
+            .visibility(Visibility::External)
+            .modifier("nonReentrant");
+        guarded.entry_block().return_void().unwrap();
+        guarded.build().unwrap();
+
+        let contract = contract.build().unwrap();
+        let call = |name: &str| {
+            let function = &contract.functions[name];
+            let block = function.entry_block();
+            let index = function.body.blocks[&block]
+                .instructions
+                .iter()
+                .position(|inst| {
+                    matches!(
+                        inst,
+                        Instruction::Call {
+                            target: CallTarget::External(_),
+                            ..
+                        }
+                    )
+                })
+                .unwrap();
+            (function, InstructionLocation { block, index })
+        };
+
+        let (withdraw, at) = call("withdraw");
+        let locks = ReentrancyGuardAnalysis::locks(&contract, withdraw);
+        assert_eq!(locks.len(), 1);
+        assert!(matches!(locks[0].kind, ReentrancyGuardType::Mutex));
+        assert_eq!(locks[0].name, "locked");
+        assert!(ReentrancyGuardAnalysis::protects(&contract, withdraw, &at));
+
+        /* Releasing a lock that was never taken guards nothing. */
+        let (sweep, at) = call("sweep");
+        assert!(!ReentrancyGuardAnalysis::protects(&contract, sweep, &at));
+
+        let claim = &contract.functions["claim"];
+        let locks = ReentrancyGuardAnalysis::locks(&contract, claim);
+        assert!(matches!(
+            locks[..],
+            [ReentrancyLock {
+                kind: ReentrancyGuardType::NonReentrantModifier,
+                ..
+            }]
+        ));
+    }
+}
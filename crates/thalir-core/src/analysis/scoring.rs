@@ -0,0 +1,442 @@
+use super::findings::{Finding, Severity};
+use super::trust_zones::TrustZones;
+use crate::{
+    contract::Contract,
+    entry_point::caller_checks,
+    function::Function,
+    instructions::{CallTarget, ContextVariable, Instruction},
+    values::{Value, ValueId},
+};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ScoreFactor {
+    /* Every entry point reaching the function checks the caller first. */
+    CallerRestricted,
+    /* Some entry point reaching the function lets anyone call it. */
+    Unauthenticated,
+    /* No entry point reaches the function. */
+    Unreachable,
+    /* The flagged instruction uses a parameter, msg.value, calldata or an external call result. */
+    AttackerControlled,
+    /* The flagged instruction uses a value returned by a call into the other trust zone. */
+    CrossesTrustBoundary,
+}
+
+impl ScoreFactor {
+    pub fn weight(&self) -> i32 {
+        match self {
+            ScoreFactor::CallerRestricted => -20,
+            ScoreFactor::Unauthenticated => 10,
+            ScoreFactor::Unreachable => -30,
+            ScoreFactor::AttackerControlled => 10,
+            ScoreFactor::CrossesTrustBoundary => 5,
+        }
+    }
+}
+
+impl fmt::Display for ScoreFactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoreFactor::CallerRestricted => write!(f, "caller-restricted"),
+            ScoreFactor::Unauthenticated => write!(f, "unauthenticated"),
+            ScoreFactor::Unreachable => write!(f, "unreachable"),
+            ScoreFactor::AttackerControlled => write!(f, "attacker-controlled"),
+            ScoreFactor::CrossesTrustBoundary => write!(f, "crosses-trust-boundary"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Score {
+    pub value: u8,
+    /* The severity the detector assigned before context was applied. */
+    pub base: Severity,
+    pub factors: Vec<ScoreFactor>,
+}
+
+impl Score {
+    pub fn new(base: Severity, factors: Vec<ScoreFactor>) -> Self {
+        let value = factors
+            .iter()
+            .fold(base_points(base), |points, factor| points + factor.weight());
+        Self {
+            value: value.clamp(0, 100) as u8,
+            base,
+            factors,
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self.value {
+            90.. => Severity::Critical,
+            70..=89 => Severity::High,
+            45..=69 => Severity::Medium,
+            20..=44 => Severity::Low,
+            _ => Severity::Info,
+        }
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (base {}", self.value, self.base)?;
+        for factor in &self.factors {
+            write!(f, ", {}", factor)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/* Each severity starts in the middle of its band, so one modifier moves a finding within it and
+ * two in the same direction move it to the next. */
+fn base_points(severity: Severity) -> i32 {
+    match severity {
+        Severity::Info => 10,
+        Severity::Low => 30,
+        Severity::Medium => 55,
+        Severity::High => 75,
+        Severity::Critical => 95,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Reach {
+    open: bool,
+    restricted: bool,
+}
+
+/* Rescores findings from any detector with the same context, so their order does not depend on
+ * how generous each detector is with severities. */
+pub struct SeverityScorer<'a> {
+    contracts: &'a [Contract],
+    reach: HashMap<(&'a str, &'a str), Reach>,
+    zones: Option<&'a TrustZones>,
+}
+
+impl<'a> SeverityScorer<'a> {
+    pub fn new(contracts: &'a [Contract]) -> Self {
+        let mut reach = HashMap::new();
+        for contract in contracts {
+            for (key, value) in reachability(contract) {
+                reach.insert((contract.name.as_str(), key), value);
+            }
+        }
+        Self {
+            contracts,
+            reach,
+            zones: None,
+        }
+    }
+
+    /* Values returned across a zone boundary then count as a factor of their own. */
+    pub fn with_zones(mut self, zones: &'a TrustZones) -> Self {
+        self.zones = Some(zones);
+        self
+    }
+
+    pub fn factors(&self, finding: &Finding) -> Vec<ScoreFactor> {
+        let Some(contract) = self.contracts.iter().find(|c| c.name == finding.contract) else {
+            return Vec::new();
+        };
+        let Some(function) = find_function(contract, &finding.function) else {
+            return Vec::new();
+        };
+
+        let mut factors = Vec::new();
+        let reach = self
+            .reach
+            .get(&(finding.contract.as_str(), finding.function.as_str()))
+            .copied()
+            .unwrap_or_default();
+        if reach.open {
+            factors.push(ScoreFactor::Unauthenticated);
+        } else if reach.restricted {
+            factors.push(ScoreFactor::CallerRestricted);
+        } else {
+            factors.push(ScoreFactor::Unreachable);
+        }
+
+        let flagged = finding.location.as_ref().and_then(|location| {
+            function
+                .body
+                .blocks
+                .get(&location.block)?
+                .instructions
+                .get(location.index)
+        });
+        if let Some(inst) = flagged {
+            let tainted = attacker_values(function);
+            if inst
+                .operands()
+                .iter()
+                .any(|value| is_param(value) || tainted.contains(value))
+            {
+                factors.push(ScoreFactor::AttackerControlled);
+            }
+            if let Some(zones) = self.zones {
+                let crossed = derived_values(
+                    function,
+                    |_| false,
+                    |inst| zones.crossing(&contract.name, inst).is_some(),
+                );
+                if inst.operands().iter().any(|value| crossed.contains(value)) {
+                    factors.push(ScoreFactor::CrossesTrustBoundary);
+                }
+            }
+        }
+        factors
+    }
+
+    /* Replaces the detector's severity with the scored one; the base stays on the score. */
+    pub fn score(&self, mut finding: Finding) -> Finding {
+        let base = finding.score.as_ref().map_or(finding.severity, |s| s.base);
+        let score = Score::new(base, self.factors(&finding));
+        finding.severity = score.severity();
+        finding.score = Some(score);
+        finding
+    }
+
+    pub fn score_all(&self, findings: Vec<Finding>) -> Vec<Finding> {
+        let mut scored: Vec<Finding> = findings.into_iter().map(|f| self.score(f)).collect();
+        rank(&mut scored);
+        scored
+    }
+}
+
+/* Highest score first. Unscored findings rank by their severity's base points, and ties fall
+ * back to the finding's identity so the order is the same on every run. */
+pub fn rank(findings: &mut [Finding]) {
+    findings.sort_by_cached_key(|finding| {
+        let points = finding
+            .score
+            .as_ref()
+            .map_or(base_points(finding.severity), |score| score.value as i32);
+        (
+            Reverse(points),
+            finding.contract.clone(),
+            finding.function.clone(),
+            finding.detector.clone(),
+            finding
+                .location
+                .as_ref()
+                .map(|location| (location.block, location.index)),
+        )
+    });
+}
+
+fn find_function<'c>(contract: &'c Contract, name: &str) -> Option<&'c Function> {
+    contract
+        .functions
+        .get(name)
+        .or_else(|| contract.functions.values().find(|f| f.name() == name))
+}
+
+/* Access control is anything that checks the caller: an access-style modifier, a condition on
+ * msg.sender in the entry point itself, or one in an internal function it calls directly. */
+fn reachability(contract: &Contract) -> HashMap<&str, Reach> {
+    let calls: HashMap<&str, Vec<&str>> = contract
+        .functions
+        .values()
+        .map(|function| {
+            let callees = function
+                .body
+                .blocks
+                .values()
+                .flat_map(|b| &b.instructions)
+                .filter_map(|inst| match inst {
+                    Instruction::Call {
+                        target: CallTarget::Internal(name),
+                        ..
+                    } => Some(name.as_str()),
+                    _ => None,
+                })
+                .collect();
+            (function.name(), callees)
+        })
+        .collect();
+
+    let mut reach: HashMap<&str, Reach> = HashMap::new();
+    for entry in contract.entry_points() {
+        let Some(function) = contract.functions.get(&entry.name) else {
+            continue;
+        };
+        let restricted = entry.guards.modifiers.iter().any(|m| is_access_name(m))
+            || entry.guards.caller_checks > 0
+            || calls
+                .get(function.name())
+                .into_iter()
+                .flatten()
+                .any(|callee| {
+                    find_function(contract, callee).is_some_and(|f| caller_checks(f) > 0)
+                });
+
+        let mut queue = VecDeque::from([function.name()]);
+        let mut visited = HashSet::new();
+        while let Some(name) = queue.pop_front() {
+            if !visited.insert(name) {
+                continue;
+            }
+            let Some(reached) = find_function(contract, name) else {
+                continue;
+            };
+            let state = reach.entry(reached.name()).or_default();
+            if restricted {
+                state.restricted = true;
+            } else {
+                state.open = true;
+            }
+            queue.extend(calls.get(name).into_iter().flatten().copied());
+        }
+    }
+    reach
+}
+
+fn is_access_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["only", "auth", "owner", "admin", "role", "governance"]
+        .iter()
+        .any(|word| name.contains(word))
+}
+
+fn is_param(value: &Value) -> bool {
+    matches!(value, Value::Param(_) | Value::Register(ValueId::Param(_)))
+}
+
+fn attacker_values(function: &Function) -> HashSet<&Value> {
+    derived_values(function, is_param, |inst| match inst {
+        Instruction::GetContext { var, .. } => {
+            matches!(var, ContextVariable::MsgValue | ContextVariable::MsgData)
+        }
+        Instruction::Call {
+            target: CallTarget::External(_),
+            ..
+        }
+        | Instruction::DelegateCall { .. }
+        | Instruction::StaticCall { .. } => true,
+        _ => false,
+    })
+}
+
+/* Results of `is_source` instructions and everything computed from them or from a `seed`. */
+fn derived_values<'f>(
+    function: &'f Function,
+    seed: impl Fn(&Value) -> bool,
+    is_source: impl Fn(&Instruction) -> bool,
+) -> HashSet<&'f Value> {
+    let mut tainted: HashSet<&Value> = HashSet::new();
+    loop {
+        let before = tainted.len();
+        for inst in function.body.blocks.values().flat_map(|b| &b.instructions) {
+            let Some(result) = inst.result() else {
+                continue;
+            };
+            if is_source(inst)
+                || inst
+                    .operands()
+                    .iter()
+                    .any(|value| seed(value) || tainted.contains(value))
+            {
+                tainted.insert(result);
+            }
+        }
+        if tainted.len() == before {
+            break;
+        }
+    }
+    tainted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::function::Visibility;
+    use crate::metadata::InstructionLocation;
+    use crate::types::Type;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_context_moves_findings_between_bands() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Vault");
+        contract_builder.state_variable("owner", Type::Address, 0);
+
+        let mut only_owner = contract_builder.function("_checkOwner");
+        only_owner.visibility(Visibility::Internal);
+        let mut entry = only_owner.entry_block();
+        let owner = entry.storage_load(BigUint::from(0u32));
+        let sender = entry.msg_sender();
+        let is_owner = entry.eq(sender, owner);
+        entry.require(is_owner, "not owner");
+        entry.return_void().unwrap();
+        only_owner.build().unwrap();
+
+        for (name, guarded) in [("sweep", true), ("split", false)] {
+            let mut function = contract_builder.function(name);
+            function
+                .param("parts", Type::Uint(256))
+                .visibility(Visibility::External);
+            let parts = function.get_param(0);
+            let mut entry = function.entry_block();
+            if guarded {
+                entry.call_internal("_checkOwner", Vec::new());
+            }
+            let total = entry.storage_load(BigUint::from(1u32));
+            let share = entry.div(total, parts, Type::Uint(256));
+            entry.return_value(share).unwrap();
+            function.build().unwrap();
+        }
+
+        let contracts = vec![contract_builder.build().unwrap()];
+        let division = |name: &str| {
+            let function = find_function(&contracts[0], name).unwrap();
+            let block = function.entry_block();
+            let index = function.body.blocks[&block]
+                .instructions
+                .iter()
+                .position(|inst| matches!(inst, Instruction::Div { .. }))
+                .unwrap();
+            Finding::new(
+                "division-guard",
+                Severity::Medium,
+                "Vault",
+                name,
+                "Division by an unchecked value".to_string(),
+            )
+            .at(InstructionLocation { block, index })
+        };
+
+        let scorer = SeverityScorer::new(&contracts);
+        let ranked = scorer.score_all(vec![division("sweep"), division("split")]);
+
+        assert_eq!(ranked[0].function, "split");
+        assert_eq!(ranked[0].severity, Severity::High);
+        assert_eq!(
+            ranked[0].score.as_ref().unwrap().factors,
+            vec![
+                ScoreFactor::Unauthenticated,
+                ScoreFactor::AttackerControlled
+            ]
+        );
+
+        let sweep = ranked[1].score.as_ref().unwrap();
+        assert_eq!(sweep.base, Severity::Medium);
+        assert_eq!(
+            sweep.factors,
+            vec![
+                ScoreFactor::CallerRestricted,
+                ScoreFactor::AttackerControlled
+            ]
+        );
+        assert_eq!(sweep.value, 45);
+        assert_eq!(ranked[1].severity, Severity::Medium);
+
+        /* Scoring twice starts from the detector's severity again. */
+        let rescored = scorer.score(ranked[1].clone());
+        assert_eq!(rescored.score.as_ref().unwrap().value, sweep.value);
+    }
+}
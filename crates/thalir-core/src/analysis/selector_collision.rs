@@ -0,0 +1,346 @@
+use super::findings::{Finding, Severity};
+use super::inheritance::ancestors;
+use crate::contract::Contract;
+use crate::entry_point::EntryKind;
+use crate::function::Function;
+use crate::instructions::{CallTarget, Instruction};
+use crate::obfuscation::policy::abi_signature;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CollisionKind {
+    /* A proxy's own function answers a selector the implementation also defines, so calls through
+     * the proxy never reach the implementation's version. */
+    ProxyShadowsImplementation,
+    /* Two facets of a diamond register the same selector; `diamondCut` rejects the second or
+     * silently replaces the first. */
+    FacetClash,
+}
+
+impl fmt::Display for CollisionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollisionKind::ProxyShadowsImplementation => write!(f, "proxy shadows implementation"),
+            CollisionKind::FacetClash => write!(f, "facet clash"),
+        }
+    }
+}
+
+/* One side of a collision: an external function and the signature its selector was taken from,
+ * when the parameter types have an ABI encoding. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectorOwner {
+    pub contract: String,
+    pub function: String,
+    pub signature: Option<String>,
+}
+
+impl SelectorOwner {
+    fn describe(&self) -> String {
+        match &self.signature {
+            Some(signature) => format!("{}.{}", self.contract, signature),
+            None => format!("{}.{}", self.contract, self.function),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectorCollision {
+    pub kind: CollisionKind,
+    pub selector: u32,
+    /* The proxy, or the facet that comes first in the workspace. */
+    pub first: SelectorOwner,
+    pub second: SelectorOwner,
+}
+
+impl SelectorCollision {
+    /* Different signatures hashing to one selector. Sharing a signature is at least deliberate
+     * looking; a clash is almost always either an accident or a planted backdoor. */
+    pub fn is_clash(&self) -> bool {
+        self.first.signature != self.second.signature
+    }
+
+    pub fn severity(&self) -> Severity {
+        if self.is_clash() {
+            Severity::High
+        } else {
+            Severity::Medium
+        }
+    }
+
+    pub fn to_finding(&self) -> Finding {
+        let message = match (self.kind, self.is_clash()) {
+            (CollisionKind::ProxyShadowsImplementation, true) => format!(
+                "Proxy function `{}` has selector 0x{:08x}, the same as `{}`; calls meant for the implementation run the proxy's function instead",
+                self.first.describe(),
+                self.selector,
+                self.second.describe()
+            ),
+            (CollisionKind::ProxyShadowsImplementation, false) => format!(
+                "Proxy function `{}` shadows `{}` (0x{:08x}); the implementation's version is unreachable through the proxy",
+                self.first.describe(),
+                self.second.describe(),
+                self.selector
+            ),
+            (CollisionKind::FacetClash, _) => format!(
+                "Facets `{}` and `{}` both register selector 0x{:08x}",
+                self.first.describe(),
+                self.second.describe(),
+                self.selector
+            ),
+        };
+        Finding::new(
+            SelectorCollisionAnalysis::DETECTOR,
+            self.severity(),
+            &self.first.contract,
+            &self.first.function,
+            message,
+        )
+    }
+}
+
+impl fmt::Display for SelectorCollision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "0x{:08x} {}: {} / {}",
+            self.selector,
+            self.kind,
+            self.first.describe(),
+            self.second.describe()
+        )
+    }
+}
+
+pub struct SelectorCollisionAnalysis;
+
+impl SelectorCollisionAnalysis {
+    pub const DETECTOR: &'static str = "selector-collision";
+
+    /* Which contract a proxy forwards to is a deployment fact the source does not carry, so every
+     * other implemented contract in the workspace is a candidate. Contracts the proxy inherits
+     * from, or that inherit from it, share its functions by construction and are left out, as are
+     * interfaces and abstract contracts with no code. Facets are recognised by the `Facet` suffix
+     * the EIP-2535 reference implementation and its forks use. */
+    pub fn analyze_workspace(contracts: &[Contract]) -> Vec<SelectorCollision> {
+        let by_name: HashMap<&str, &Contract> = contracts
+            .iter()
+            .map(|contract| (contract.name.as_str(), contract))
+            .collect();
+        let related = |a: &Contract, b: &Contract| {
+            ancestors(a, &by_name).iter().any(|c| c.name == b.name)
+                || ancestors(b, &by_name).iter().any(|c| c.name == a.name)
+        };
+
+        let mut collisions = Vec::new();
+        for proxy in contracts.iter().filter(|c| is_proxy(c)) {
+            for implementation in contracts {
+                if implementation.name == proxy.name
+                    || is_proxy(implementation)
+                    || !is_implemented(implementation)
+                    || related(proxy, implementation)
+                {
+                    continue;
+                }
+                collisions.extend(collide(
+                    CollisionKind::ProxyShadowsImplementation,
+                    proxy,
+                    implementation,
+                ));
+            }
+        }
+
+        let facets: Vec<&Contract> = contracts
+            .iter()
+            .filter(|c| c.name.ends_with("Facet") && is_implemented(c))
+            .collect();
+        for (i, first) in facets.iter().enumerate() {
+            for second in &facets[i + 1..] {
+                if !related(first, second) {
+                    collisions.extend(collide(CollisionKind::FacetClash, first, second));
+                }
+            }
+        }
+
+        collisions
+    }
+
+    pub fn findings(contracts: &[Contract]) -> Vec<Finding> {
+        Self::analyze_workspace(contracts)
+            .iter()
+            .map(SelectorCollision::to_finding)
+            .collect()
+    }
+}
+
+fn collide(kind: CollisionKind, first: &Contract, second: &Contract) -> Vec<SelectorCollision> {
+    let theirs = selectors(second);
+    selectors(first)
+        .into_iter()
+        .filter_map(|(selector, owner)| {
+            theirs
+                .iter()
+                .find(|(other, _)| *other == selector)
+                .map(|(_, other)| SelectorCollision {
+                    kind,
+                    selector,
+                    first: owner,
+                    second: other.clone(),
+                })
+        })
+        .collect()
+}
+
+fn selectors(contract: &Contract) -> Vec<(u32, SelectorOwner)> {
+    contract
+        .entry_points()
+        .into_iter()
+        .filter(|entry| entry.kind == EntryKind::Function)
+        .filter_map(|entry| {
+            let function = contract.functions.get(&entry.name)?;
+            let params = function.signature.params.iter().map(|p| &p.param_type);
+            Some((
+                entry.selector?,
+                SelectorOwner {
+                    contract: contract.name.clone(),
+                    function: entry.name,
+                    signature: abi_signature(&function.signature.name, params),
+                },
+            ))
+        })
+        .collect()
+}
+
+/* A contract whose fallback forwards with `delegatecall`, directly or through an internal helper
+ * such as OpenZeppelin's `_delegate`. That helper is usually inline assembly, which the IR keeps
+ * only as a flag, so assembly reached from the fallback counts too. */
+fn is_proxy(contract: &Contract) -> bool {
+    contract
+        .functions
+        .values()
+        .filter(|function| function.metadata.is_fallback)
+        .any(|fallback| delegates(contract, fallback, &mut HashSet::new()))
+}
+
+fn delegates<'a>(
+    contract: &'a Contract,
+    function: &'a Function,
+    visited: &mut HashSet<&'a str>,
+) -> bool {
+    if !visited.insert(function.name()) {
+        return false;
+    }
+    if function.metadata.has_assembly {
+        return true;
+    }
+    function
+        .body
+        .blocks
+        .values()
+        .flat_map(|block| &block.instructions)
+        .any(|inst| match inst {
+            Instruction::DelegateCall { .. } => true,
+            Instruction::Call {
+                target: CallTarget::Internal(callee),
+                ..
+            } => contract
+                .functions
+                .values()
+                .find(|f| f.name() == callee)
+                .is_some_and(|callee| delegates(contract, callee, visited)),
+            _ => false,
+        })
+}
+
+fn is_implemented(contract: &Contract) -> bool {
+    contract.functions.values().any(|function| {
+        function
+            .body
+            .blocks
+            .values()
+            .any(|block| !block.instructions.is_empty())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{IRBuilder, InstBuilderExt};
+    use crate::function::Visibility;
+    use crate::types::Type;
+
+    fn external(contract: &mut crate::builder::ContractBuilder<'_>, name: &str, params: &[Type]) {
+        let mut function = contract.function(name);
+        function.visibility(Visibility::External);
+        for (i, ty) in params.iter().enumerate() {
+            function.param(&format!("arg{}", i), ty.clone());
+        }
+        let mut entry = function.entry_block();
+        let one = entry.constant_uint(1, 256);
+        entry.storage_store(0u32.into(), one);
+        entry.return_void().unwrap();
+        function.build().unwrap();
+    }
+
+    #[test]
+    fn test_proxy_clash_shadowing_and_facets() {
+        let mut builder = IRBuilder::new();
+
+        let mut proxy = builder.contract("Proxy");
+        external(&mut proxy, "collate_propagate_storage", &[Type::Bytes(16)]);
+        external(&mut proxy, "upgradeTo", &[Type::Address]);
+        let mut fallback = proxy.function("fallback");
+        fallback.fallback();
+        let mut entry = fallback.entry_block();
+        let target = entry.storage_load(1u32.into());
+        let selector = entry.msg_sig();
+        entry.delegate_call(target, selector, vec![]);
+        entry.return_void().unwrap();
+        fallback.build().unwrap();
+        let proxy = proxy.build().unwrap();
+
+        let mut token = builder.contract("Token");
+        external(&mut token, "burn", &[Type::Uint(256)]);
+        external(&mut token, "upgradeTo", &[Type::Address]);
+        external(&mut token, "mint", &[Type::Uint(256)]);
+        let token = token.build().unwrap();
+
+        let mut loupe = builder.contract("LoupeFacet");
+        external(&mut loupe, "facets", &[]);
+        external(&mut loupe, "mint", &[Type::Uint(256)]);
+        let loupe = loupe.build().unwrap();
+        let mut vault = builder.contract("VaultFacet");
+        external(&mut vault, "mint", &[Type::Uint(256)]);
+        let vault = vault.build().unwrap();
+
+        let collisions =
+            SelectorCollisionAnalysis::analyze_workspace(&[proxy, token, loupe, vault]);
+
+        let clash = collisions
+            .iter()
+            .find(|c| c.second.function == "burn")
+            .unwrap();
+        assert_eq!(clash.selector, 0x42966c68);
+        assert_eq!(clash.kind, CollisionKind::ProxyShadowsImplementation);
+        assert_eq!(clash.severity(), Severity::High);
+
+        let shadow = collisions
+            .iter()
+            .find(|c| c.second.function == "upgradeTo")
+            .unwrap();
+        assert_eq!(shadow.severity(), Severity::Medium);
+        assert_eq!(shadow.first.contract, "Proxy");
+
+        let facets: Vec<&SelectorCollision> = collisions
+            .iter()
+            .filter(|c| c.kind == CollisionKind::FacetClash)
+            .collect();
+        assert_eq!(facets.len(), 1);
+        assert_eq!(facets[0].first.contract, "LoupeFacet");
+        assert_eq!(facets[0].second.contract, "VaultFacet");
+
+        assert_eq!(collisions.len(), 3, "{:#?}", collisions);
+    }
+}
@@ -0,0 +1,346 @@
+use super::findings::{Finding, Severity};
+use super::util::{constant_slot, normalized, storage_key_slot};
+use crate::{
+    contract::Contract,
+    function::Function,
+    instructions::{CallTarget, ContextVariable, Instruction},
+    metadata::InstructionLocation,
+    values::Value,
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ReplayField {
+    Nonce,
+    ChainId,
+    ContractAddress,
+}
+
+impl fmt::Display for ReplayField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayField::Nonce => write!(f, "nonce"),
+            ReplayField::ChainId => write!(f, "block.chainid"),
+            ReplayField::ContractAddress => write!(f, "address(this)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayableSignature {
+    pub function: String,
+    pub location: InstructionLocation,
+    pub missing: Vec<ReplayField>,
+}
+
+impl ReplayableSignature {
+    pub fn severity(&self) -> Severity {
+        if self.missing.contains(&ReplayField::Nonce) {
+            Severity::High
+        } else {
+            Severity::Medium
+        }
+    }
+
+    pub fn to_finding(&self, contract: &str) -> Finding {
+        let missing: Vec<String> = self.missing.iter().map(|f| f.to_string()).collect();
+        Finding::new(
+            SignatureReplayAnalysis::DETECTOR,
+            self.severity(),
+            contract,
+            &self.function,
+            format!(
+                "Recovered signer from a digest that does not bind {}; the signature can be replayed",
+                missing.join(", ")
+            ),
+        )
+        .at(self.location.clone())
+    }
+}
+
+pub struct SignatureReplayAnalysis;
+
+impl SignatureReplayAnalysis {
+    pub const DETECTOR: &'static str = "signature-replay";
+
+    pub fn analyze_contract(contract: &Contract) -> Vec<ReplayableSignature> {
+        contract
+            .functions
+            .values()
+            .flat_map(|function| Self::analyze_function(contract, function))
+            .collect()
+    }
+
+    pub fn findings(contract: &Contract) -> Vec<Finding> {
+        Self::analyze_contract(contract)
+            .iter()
+            .map(|sig| sig.to_finding(&contract.name))
+            .collect()
+    }
+
+    pub fn analyze_function(contract: &Contract, function: &Function) -> Vec<ReplayableSignature> {
+        let mut defs: HashMap<&Value, &Instruction> = HashMap::new();
+        let mut written_slots: HashSet<BigUint> = HashSet::new();
+        let mut marked_keys: HashSet<&Value> = HashSet::new();
+
+        for block in function.body.blocks.values() {
+            for inst in &block.instructions {
+                if let Some(result) = inst.result() {
+                    defs.insert(result, inst);
+                }
+                match inst {
+                    Instruction::StorageStore { key, .. } => {
+                        if let Some(slot) = storage_key_slot(key) {
+                            written_slots.insert(slot);
+                        }
+                    }
+                    Instruction::MappingStore { mapping, key, .. } => {
+                        if let Some(slot) = constant_slot(mapping) {
+                            written_slots.insert(slot);
+                        }
+                        marked_keys.insert(key);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut results = Vec::new();
+
+        for (&block_id, block) in &function.body.blocks {
+            for (index, inst) in block.instructions.iter().enumerate() {
+                let digest = match recovered_digest(inst) {
+                    Some(digest) => digest,
+                    None => continue,
+                };
+
+                let mut covered = HashSet::new();
+                if marked_keys.contains(&digest) {
+                    covered.insert(ReplayField::Nonce);
+                }
+
+                for source in payload_slice(digest, &defs) {
+                    classify_source(contract, source, &written_slots, &mut covered);
+                }
+
+                let missing: Vec<ReplayField> = [
+                    ReplayField::Nonce,
+                    ReplayField::ChainId,
+                    ReplayField::ContractAddress,
+                ]
+                .into_iter()
+                .filter(|field| !covered.contains(field))
+                .collect();
+
+                if !missing.is_empty() {
+                    results.push(ReplayableSignature {
+                        function: function.name().to_string(),
+                        location: InstructionLocation {
+                            block: block_id,
+                            index,
+                        },
+                        missing,
+                    });
+                }
+            }
+        }
+
+        results
+    }
+}
+
+fn recovered_digest(inst: &Instruction) -> Option<&Value> {
+    match inst {
+        Instruction::EcRecover { hash, .. } => Some(hash),
+        Instruction::Call {
+            target: CallTarget::Internal(name) | CallTarget::Library(name),
+            args,
+            ..
+        } if is_recover_name(name) => args.first(),
+        _ => None,
+    }
+}
+
+fn is_recover_name(name: &str) -> bool {
+    let method = name.rsplit('.').next().unwrap_or(name);
+    matches!(method, "ecrecover" | "recover" | "tryRecover")
+}
+
+fn payload_slice<'a>(
+    digest: &'a Value,
+    defs: &HashMap<&'a Value, &'a Instruction>,
+) -> Vec<&'a Instruction> {
+    let mut visited: HashSet<&Value> = HashSet::new();
+    let mut worklist = vec![digest];
+    let mut slice = Vec::new();
+
+    while let Some(value) = worklist.pop() {
+        if !visited.insert(value) {
+            continue;
+        }
+        if let Some(&inst) = defs.get(value) {
+            slice.push(inst);
+            worklist.extend(inst.operands());
+        }
+    }
+
+    slice
+}
+
+fn classify_source(
+    contract: &Contract,
+    inst: &Instruction,
+    written_slots: &HashSet<BigUint>,
+    covered: &mut HashSet<ReplayField>,
+) {
+    match inst {
+        Instruction::GetContext {
+            var: ContextVariable::ChainId,
+            ..
+        } => {
+            covered.insert(ReplayField::ChainId);
+        }
+        Instruction::GetContext {
+            var: ContextVariable::ThisAddress,
+            ..
+        } => {
+            covered.insert(ReplayField::ContractAddress);
+        }
+        Instruction::StorageLoad { key, .. } => {
+            if let Some(slot) = storage_key_slot(key) {
+                classify_slot(contract, &slot, written_slots, covered);
+            }
+        }
+        Instruction::MappingLoad { mapping, .. } => {
+            if let Some(slot) = constant_slot(mapping) {
+                classify_slot(contract, &slot, written_slots, covered);
+            }
+        }
+        Instruction::Call {
+            target: CallTarget::Internal(name) | CallTarget::Library(name),
+            ..
+        } => {
+            if is_domain_separator_name(name) {
+                covered.insert(ReplayField::ChainId);
+                covered.insert(ReplayField::ContractAddress);
+            } else if is_nonce_name(name) {
+                covered.insert(ReplayField::Nonce);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn classify_slot(
+    contract: &Contract,
+    slot: &BigUint,
+    written_slots: &HashSet<BigUint>,
+    covered: &mut HashSet<ReplayField>,
+) {
+    if written_slots.contains(slot) {
+        covered.insert(ReplayField::Nonce);
+    }
+
+    let layout = &contract.storage_layout;
+    let names = layout
+        .slots
+        .iter()
+        .filter(|s| &s.slot == slot)
+        .map(|s| s.name.as_str())
+        .chain(
+            layout
+                .mappings
+                .iter()
+                .filter(|m| &m.base_slot == slot)
+                .map(|m| m.name.as_str()),
+        );
+
+    for name in names {
+        if is_nonce_name(name) {
+            covered.insert(ReplayField::Nonce);
+        }
+        if is_domain_separator_name(name) {
+            covered.insert(ReplayField::ChainId);
+            covered.insert(ReplayField::ContractAddress);
+        }
+    }
+}
+
+fn is_nonce_name(name: &str) -> bool {
+    normalized(name).contains("nonce")
+}
+
+fn is_domain_separator_name(name: &str) -> bool {
+    let name = normalized(name);
+    name.contains("domainseparator") || name.contains("hashtypeddata")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{IRBuilder, InstBuilderExt};
+    use crate::types::Type;
+
+    #[test]
+    fn test_reports_missing_replay_fields() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Vault");
+        contract_builder.state_variable("nonces", Type::Uint(256), 0);
+
+        let mut func_builder = contract_builder.function("withdrawWithSig");
+        let mut entry = func_builder.entry_block();
+
+        let amount = entry.constant_uint(100, 256);
+        let chain_id = entry.block_chainid();
+        let encoded = entry.call_internal("abi.encode", vec![amount, chain_id]);
+        let digest = entry.call_internal("keccak256", vec![encoded]);
+        let v = entry.constant_uint(27, 8);
+        let r = entry.constant_uint(1, 256);
+        let s = entry.constant_uint(2, 256);
+        entry.ecrecover(digest, v, r, s);
+        entry.return_void().unwrap();
+
+        func_builder.build().unwrap();
+        let contract = contract_builder.build().unwrap();
+
+        let results = SignatureReplayAnalysis::analyze_contract(&contract);
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].missing,
+            vec![ReplayField::Nonce, ReplayField::ContractAddress]
+        );
+        assert_eq!(results[0].severity(), Severity::High);
+    }
+
+    #[test]
+    fn test_permit_with_nonce_and_domain_is_clean() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Token");
+        contract_builder.state_variable("nonces", Type::Uint(256), 3);
+
+        let mut func_builder = contract_builder.function("permit");
+        let mut entry = func_builder.entry_block();
+
+        let owner = entry.msg_sender();
+        let nonces = entry.constant_uint(3, 256);
+        let nonce = entry.mapping_load(nonces.clone(), owner.clone());
+        let one = entry.constant_uint(1, 256);
+        let next = entry.add(nonce.clone(), one, Type::Uint(256));
+        entry.mapping_store(nonces, owner.clone(), next);
+
+        let encoded = entry.call_internal("abi.encode", vec![owner, nonce]);
+        let struct_hash = entry.call_internal("keccak256", vec![encoded]);
+        let digest = entry.call_internal("_hashTypedDataV4", vec![struct_hash]);
+        let signature = entry.constant_uint(0, 256);
+        entry.call_internal("ECDSA.recover", vec![digest, signature]);
+        entry.return_void().unwrap();
+
+        func_builder.build().unwrap();
+        let contract = contract_builder.build().unwrap();
+
+        assert!(SignatureReplayAnalysis::findings(&contract).is_empty());
+    }
+}
@@ -0,0 +1,296 @@
+use super::findings::{Finding, Severity};
+use super::similarity::{normalized_opcode, FunctionFingerprint};
+use crate::{contract::Contract, function::Function, metadata::InstructionLocation};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignaturePack {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    pub signatures: Vec<Signature>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Signature {
+    pub id: String,
+    pub title: String,
+    pub severity: Severity,
+    #[serde(default)]
+    pub description: String,
+    pub matcher: SignatureMatcher,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SignatureMatcher {
+    Sequence {
+        opcodes: Vec<String>,
+    },
+    Fingerprint {
+        fingerprint: FunctionFingerprint,
+        #[serde(default = "default_threshold")]
+        threshold: f64,
+    },
+}
+
+fn default_threshold() -> f64 {
+    0.9
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureMatch {
+    pub pack: String,
+    pub pack_version: String,
+    pub signature_id: String,
+    pub title: String,
+    pub severity: Severity,
+    pub contract: String,
+    pub function: String,
+    pub location: Option<InstructionLocation>,
+    pub score: f64,
+}
+
+impl SignatureMatch {
+    pub fn provenance(&self) -> String {
+        format!("{}@{}#{}", self.pack, self.pack_version, self.signature_id)
+    }
+
+    pub fn to_finding(&self) -> Finding {
+        let mut finding = Finding::new(
+            SignatureDatabase::DETECTOR,
+            self.severity,
+            &self.contract,
+            &self.function,
+            format!(
+                "{} (signature {}, score {:.2})",
+                self.title, self.signature_id, self.score
+            ),
+        )
+        .with_provenance(self.provenance());
+        finding.location = self.location.clone();
+        finding
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SignatureDatabase {
+    packs: Vec<SignaturePack>,
+}
+
+impl SignatureDatabase {
+    pub const DETECTOR: &'static str = "known-signature";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load_dir(path: &Path) -> Result<Self> {
+        let mut entries: Vec<_> = std::fs::read_dir(path)
+            .with_context(|| format!("reading signature directory {}", path.display()))?
+            .collect::<std::io::Result<Vec<_>>>()?
+            .into_iter()
+            .map(|entry| entry.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        entries.sort();
+
+        let mut database = Self::new();
+        for file in entries {
+            database.add_pack(Self::load_pack(&file)?);
+        }
+        Ok(database)
+    }
+
+    pub fn load_pack(path: &Path) -> Result<SignaturePack> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("reading signature pack {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("parsing signature pack {}", path.display()))
+    }
+
+    pub fn add_pack(&mut self, pack: SignaturePack) {
+        if let Some(existing) = self.packs.iter_mut().find(|p| p.name == pack.name) {
+            if version_key(&pack.version) > version_key(&existing.version) {
+                *existing = pack;
+            }
+            return;
+        }
+        self.packs.push(pack);
+    }
+
+    pub fn packs(&self) -> &[SignaturePack] {
+        &self.packs
+    }
+
+    pub fn signature_count(&self) -> usize {
+        self.packs.iter().map(|p| p.signatures.len()).sum()
+    }
+
+    pub fn match_contract(&self, contract: &Contract) -> Vec<SignatureMatch> {
+        let mut matches = Vec::new();
+
+        for function in contract.functions.values() {
+            let fingerprint = FunctionFingerprint::compute(&contract.name, function);
+
+            for pack in &self.packs {
+                for signature in &pack.signatures {
+                    let hit = match &signature.matcher {
+                        SignatureMatcher::Sequence { opcodes } => {
+                            match_sequence(function, opcodes).map(|loc| (Some(loc), 1.0))
+                        }
+                        SignatureMatcher::Fingerprint {
+                            fingerprint: known,
+                            threshold,
+                        } => {
+                            let score = fingerprint.similarity(known);
+                            (score >= *threshold).then_some((None, score))
+                        }
+                    };
+
+                    if let Some((location, score)) = hit {
+                        matches.push(SignatureMatch {
+                            pack: pack.name.clone(),
+                            pack_version: pack.version.clone(),
+                            signature_id: signature.id.clone(),
+                            title: signature.title.clone(),
+                            severity: signature.severity,
+                            contract: contract.name.clone(),
+                            function: function.name().to_string(),
+                            location,
+                            score,
+                        });
+                    }
+                }
+            }
+        }
+
+        matches
+    }
+
+    pub fn findings(&self, contract: &Contract) -> Vec<Finding> {
+        self.match_contract(contract)
+            .iter()
+            .map(SignatureMatch::to_finding)
+            .collect()
+    }
+}
+
+fn match_sequence(function: &Function, opcodes: &[String]) -> Option<InstructionLocation> {
+    if opcodes.is_empty() {
+        return None;
+    }
+
+    let mut remaining = opcodes.iter().peekable();
+    for (&block_id, block) in &function.body.blocks {
+        for (index, inst) in block.instructions.iter().enumerate() {
+            let expected = match remaining.peek() {
+                Some(expected) => expected,
+                None => break,
+            };
+            if opcode_matches(expected, &normalized_opcode(inst)) {
+                remaining.next();
+                if remaining.peek().is_none() {
+                    return Some(InstructionLocation {
+                        block: block_id,
+                        index,
+                    });
+                }
+            }
+        }
+    }
+
+    None
+}
+
+fn opcode_matches(expected: &str, actual: &str) -> bool {
+    expected == "*" || actual == expected || actual.starts_with(&format!("{}:", expected))
+}
+
+fn version_key(version: &str) -> Vec<u64> {
+    version
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+
+    fn reentrant_contract() -> Contract {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Bank");
+
+        let mut func_builder = contract_builder.function("withdraw");
+        let mut entry = func_builder.entry_block();
+        let sender = entry.msg_sender();
+        let balances = entry.constant_uint(0, 256);
+        let balance = entry.mapping_load(balances.clone(), sender.clone());
+        let selector = entry.constant_uint(0, 32);
+        entry.call_external(sender.clone(), selector, vec![], Some(balance));
+        let zero = entry.constant_uint(0, 256);
+        entry.mapping_store(balances, sender, zero);
+        entry.return_void().unwrap();
+        func_builder.build().unwrap();
+
+        contract_builder.build().unwrap()
+    }
+
+    fn pack(version: &str, opcodes: &[&str]) -> SignaturePack {
+        SignaturePack {
+            name: "core".to_string(),
+            version: version.to_string(),
+            description: String::new(),
+            signatures: vec![Signature {
+                id: "THAL-0001".to_string(),
+                title: "State write after value transfer".to_string(),
+                severity: Severity::High,
+                description: String::new(),
+                matcher: SignatureMatcher::Sequence {
+                    opcodes: opcodes.iter().map(|s| s.to_string()).collect(),
+                },
+            }],
+        }
+    }
+
+    #[test]
+    fn test_sequence_signature_matches_with_provenance() {
+        let mut database = SignatureDatabase::new();
+        database.add_pack(pack("1.0.0", &["Call:external", "MappingStore"]));
+
+        let findings = database.findings(&reentrant_contract());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].provenance.as_deref(),
+            Some("core@1.0.0#THAL-0001")
+        );
+        assert!(findings[0].location.is_some());
+    }
+
+    #[test]
+    fn test_newer_pack_version_replaces_older() {
+        let dir = tempfile::tempdir().unwrap();
+        let older = pack("1.2.0", &["Call:external", "MappingStore"]);
+        let newer = pack("1.10.0", &["MappingStore", "Call:external"]);
+        std::fs::write(
+            dir.path().join("a.json"),
+            serde_json::to_string(&newer).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("b.json"),
+            serde_json::to_string(&older).unwrap(),
+        )
+        .unwrap();
+
+        let database = SignatureDatabase::load_dir(dir.path()).unwrap();
+        assert_eq!(database.packs().len(), 1);
+        assert_eq!(database.packs()[0].version, "1.10.0");
+        assert!(database.match_contract(&reentrant_contract()).is_empty());
+    }
+}
@@ -0,0 +1,271 @@
+use crate::{
+    contract::Contract,
+    function::Function,
+    instructions::{CallTarget, Instruction},
+    transform::Canonicalizer,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+const NGRAM_SIZE: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionFingerprint {
+    pub contract: String,
+    pub function: String,
+    pub structural_hash: String,
+    pub instruction_count: usize,
+    pub ngrams: BTreeSet<u64>,
+}
+
+impl FunctionFingerprint {
+    pub fn compute(contract: &str, function: &Function) -> Self {
+        let canonicalizer = Canonicalizer::new().erase_names(true);
+        let canonical = canonicalizer.canonicalize_function(function);
+
+        let mut tokens = Vec::new();
+        for block in canonical.body.blocks.values() {
+            tokens.push("block".to_string());
+            for inst in &block.instructions {
+                tokens.push(normalized_opcode(inst));
+            }
+            tokens.push(block.terminator.opcode_name().to_string());
+        }
+
+        let ngrams = if tokens.len() < NGRAM_SIZE {
+            std::iter::once(fnv1a(&tokens.join(" "))).collect()
+        } else {
+            tokens
+                .windows(NGRAM_SIZE)
+                .map(|window| fnv1a(&window.join(" ")))
+                .collect()
+        };
+
+        Self {
+            contract: contract.to_string(),
+            function: function.name().to_string(),
+            structural_hash: canonicalizer.function_fingerprint(function),
+            instruction_count: canonical
+                .body
+                .blocks
+                .values()
+                .map(|b| b.instructions.len())
+                .sum(),
+            ngrams,
+        }
+    }
+
+    pub fn similarity(&self, other: &FunctionFingerprint) -> f64 {
+        if self.structural_hash == other.structural_hash {
+            return 1.0;
+        }
+
+        let union = self.ngrams.union(&other.ngrams).count();
+        if union == 0 {
+            return 0.0;
+        }
+        let intersection = self.ngrams.intersection(&other.ngrams).count();
+        intersection as f64 / union as f64
+    }
+
+    pub fn qualified_name(&self) -> String {
+        format!("{}::{}", self.contract, self.function)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarityMatch {
+    pub left: String,
+    pub right: String,
+    pub score: f64,
+    pub exact: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SimilarityIndex {
+    entries: Vec<FunctionFingerprint>,
+}
+
+impl SimilarityIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_contract(&mut self, contract: &Contract) {
+        for function in contract.functions.values() {
+            self.entries
+                .push(FunctionFingerprint::compute(&contract.name, function));
+        }
+    }
+
+    pub fn add_fingerprint(&mut self, fingerprint: FunctionFingerprint) {
+        self.entries.push(fingerprint);
+    }
+
+    pub fn entries(&self) -> &[FunctionFingerprint] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn find_similar(
+        &self,
+        query: &FunctionFingerprint,
+        threshold: f64,
+    ) -> Vec<SimilarityMatch> {
+        let mut matches: Vec<SimilarityMatch> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.qualified_name() != query.qualified_name())
+            .filter_map(|entry| match_pair(query, entry, threshold))
+            .collect();
+        sort_matches(&mut matches);
+        matches
+    }
+
+    pub fn near_duplicates(&self, threshold: f64) -> Vec<SimilarityMatch> {
+        let mut matches = Vec::new();
+        for (i, left) in self.entries.iter().enumerate() {
+            for right in &self.entries[i + 1..] {
+                if let Some(m) = match_pair(left, right, threshold) {
+                    matches.push(m);
+                }
+            }
+        }
+        sort_matches(&mut matches);
+        matches
+    }
+
+    pub fn match_against(&self, corpus: &SimilarityIndex, threshold: f64) -> Vec<SimilarityMatch> {
+        let mut matches: Vec<SimilarityMatch> = self
+            .entries
+            .iter()
+            .flat_map(|entry| {
+                corpus
+                    .entries
+                    .iter()
+                    .filter_map(move |known| match_pair(entry, known, threshold))
+            })
+            .collect();
+        sort_matches(&mut matches);
+        matches
+    }
+}
+
+fn match_pair(
+    left: &FunctionFingerprint,
+    right: &FunctionFingerprint,
+    threshold: f64,
+) -> Option<SimilarityMatch> {
+    let score = left.similarity(right);
+    if score < threshold {
+        return None;
+    }
+    Some(SimilarityMatch {
+        left: left.qualified_name(),
+        right: right.qualified_name(),
+        score,
+        exact: left.structural_hash == right.structural_hash,
+    })
+}
+
+fn sort_matches(matches: &mut [SimilarityMatch]) {
+    matches.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.left.cmp(&b.left))
+            .then_with(|| a.right.cmp(&b.right))
+    });
+}
+
+pub(crate) fn normalized_opcode(inst: &Instruction) -> String {
+    match inst {
+        Instruction::Call { target, value, .. } => {
+            let kind = match target {
+                CallTarget::Internal(_) => "internal",
+                CallTarget::External(_) => "external",
+                CallTarget::Library(_) => "library",
+                CallTarget::Builtin(builtin) => return format!("Call:{:?}", builtin),
+            };
+            if value.is_some() {
+                format!("Call:{}:value", kind)
+            } else {
+                format!("Call:{}", kind)
+            }
+        }
+        Instruction::GetContext { var, .. } => format!("GetContext:{:?}", var),
+        other => other.opcode_name().to_string(),
+    }
+}
+
+fn fnv1a(text: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
+    use num_bigint::BigUint;
+
+    fn build_contract(name: &str, extra_store: bool) -> Contract {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract(name);
+
+        let mut func_builder = contract_builder.function("withdraw");
+        let mut entry = func_builder.entry_block();
+        let sender = entry.msg_sender();
+        let balance = entry.mapping_load(entry.constant_uint(0, 256), sender.clone());
+        let amount = entry.msg_value();
+        let selector = entry.constant_uint(0, 32);
+        entry.call_external(sender.clone(), selector, vec![], Some(balance.clone()));
+        let remaining = entry.sub(balance, amount, Type::Uint(256));
+        entry.mapping_store(entry.constant_uint(0, 256), sender, remaining.clone());
+        if extra_store {
+            entry.storage_store(BigUint::from(1u32), remaining);
+        }
+        entry.return_void().unwrap();
+        func_builder.build().unwrap();
+
+        contract_builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_identical_structure_is_exact_match() {
+        let mut index = SimilarityIndex::new();
+        index.add_contract(&build_contract("VaultA", false));
+        index.add_contract(&build_contract("VaultB", false));
+
+        let matches = index.near_duplicates(0.9);
+        assert_eq!(matches.len(), 1);
+        assert!(matches[0].exact);
+        assert_eq!(matches[0].left, "VaultA::withdraw");
+    }
+
+    #[test]
+    fn test_small_edit_is_near_duplicate() {
+        let mut corpus = SimilarityIndex::new();
+        corpus.add_contract(&build_contract("KnownReentrancy", false));
+
+        let mut workspace = SimilarityIndex::new();
+        workspace.add_contract(&build_contract("Target", true));
+
+        let matches = workspace.match_against(&corpus, 0.5);
+        assert_eq!(matches.len(), 1);
+        assert!(!matches[0].exact);
+        assert!(matches[0].score < 1.0);
+    }
+}
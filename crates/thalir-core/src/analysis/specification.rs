@@ -0,0 +1,414 @@
+use super::findings::{Finding, Severity};
+use super::util::{constant_slot, normalized, storage_key_slot};
+use crate::{
+    block::Terminator,
+    contract::Contract,
+    function::Function,
+    instructions::{CallTarget, ContextVariable, Instruction},
+    metadata::NatSpec,
+    values::Value,
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpecClaim {
+    Guard(String),
+    Invariant(String),
+}
+
+impl SpecClaim {
+    pub fn from_natspec(natspec: &NatSpec) -> Vec<SpecClaim> {
+        let mut claims = Vec::new();
+        for (tag, text) in &natspec.custom {
+            match tag.as_str() {
+                "custom:security" => claims.extend(
+                    text.split(|c: char| c.is_whitespace() || c == ',')
+                        .filter(|word| !word.is_empty())
+                        .map(|word| SpecClaim::Guard(word.to_string())),
+                ),
+                "custom:invariant" if !text.is_empty() => {
+                    claims.push(SpecClaim::Invariant(text.clone()))
+                }
+                _ => {}
+            }
+        }
+        claims
+    }
+}
+
+impl fmt::Display for SpecClaim {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecClaim::Guard(name) => write!(f, "@custom:security {}", name),
+            SpecClaim::Invariant(text) => write!(f, "@custom:invariant {}", text),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpecDrift {
+    pub function: String,
+    pub claim: SpecClaim,
+    pub reason: String,
+}
+
+impl SpecDrift {
+    pub fn severity(&self) -> Severity {
+        match &self.claim {
+            SpecClaim::Guard(_) => Severity::High,
+            SpecClaim::Invariant(_) => Severity::Medium,
+        }
+    }
+
+    pub fn to_finding(&self, contract: &str) -> Finding {
+        Finding::new(
+            SpecificationAnalysis::DETECTOR,
+            self.severity(),
+            contract,
+            &self.function,
+            format!(
+                "Documented `{}` is not enforced: {}",
+                self.claim, self.reason
+            ),
+        )
+    }
+}
+
+pub struct SpecificationAnalysis;
+
+impl SpecificationAnalysis {
+    pub const DETECTOR: &'static str = "natspec-drift";
+
+    pub fn analyze_contract(contract: &Contract) -> Vec<SpecDrift> {
+        let contract_invariants: Vec<String> = contract
+            .metadata
+            .natspec
+            .as_ref()
+            .map(SpecClaim::from_natspec)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|claim| match claim {
+                SpecClaim::Invariant(text) => Some(text),
+                SpecClaim::Guard(_) => None,
+            })
+            .collect();
+
+        let mut drifts = Vec::new();
+
+        for text in &contract_invariants {
+            if invariant_slots(contract, text).is_empty() {
+                drifts.push(SpecDrift {
+                    function: String::new(),
+                    claim: SpecClaim::Invariant(text.clone()),
+                    reason: "it names no state variable of this contract".to_string(),
+                });
+            }
+        }
+
+        for function in contract.functions.values() {
+            let facts = FunctionFacts::collect(function);
+            let function_claims = function
+                .metadata
+                .natspec
+                .as_ref()
+                .map(SpecClaim::from_natspec)
+                .unwrap_or_default();
+
+            for claim in &function_claims {
+                let reason = match claim {
+                    SpecClaim::Guard(name) => facts.check_guard(name),
+                    SpecClaim::Invariant(text) => facts.check_invariant(contract, text),
+                };
+                if let Some(reason) = reason {
+                    drifts.push(SpecDrift {
+                        function: function.name().to_string(),
+                        claim: claim.clone(),
+                        reason,
+                    });
+                }
+            }
+
+            for text in &contract_invariants {
+                if let Some(reason) = facts.check_invariant(contract, text) {
+                    drifts.push(SpecDrift {
+                        function: function.name().to_string(),
+                        claim: SpecClaim::Invariant(text.clone()),
+                        reason,
+                    });
+                }
+            }
+        }
+
+        drifts
+    }
+
+    pub fn findings(contract: &Contract) -> Vec<Finding> {
+        Self::analyze_contract(contract)
+            .iter()
+            .map(|drift| drift.to_finding(&contract.name))
+            .collect()
+    }
+}
+
+struct FunctionFacts<'a> {
+    checked_sources: Vec<&'a Instruction>,
+    has_checks: bool,
+    called: HashSet<String>,
+    written_slots: HashSet<BigUint>,
+    stores_before_external_call: bool,
+}
+
+impl<'a> FunctionFacts<'a> {
+    fn collect(function: &'a Function) -> Self {
+        let mut defs: HashMap<&Value, &Instruction> = HashMap::new();
+        let mut conditions: Vec<&Value> = Vec::new();
+        let mut called = HashSet::new();
+        let mut written_slots = HashSet::new();
+        let mut seen_store = false;
+        let mut stores_before_external_call = false;
+        let mut seen_external_call = false;
+
+        for block in function.body.blocks.values() {
+            for inst in &block.instructions {
+                if let Some(result) = inst.result() {
+                    defs.insert(result, inst);
+                }
+                match inst {
+                    Instruction::Require { condition, .. }
+                    | Instruction::Assert { condition, .. } => conditions.push(condition),
+                    Instruction::Call {
+                        target: CallTarget::Internal(name) | CallTarget::Library(name),
+                        ..
+                    } => {
+                        called.insert(normalized(name));
+                    }
+                    Instruction::Call {
+                        target: CallTarget::External(_),
+                        ..
+                    }
+                    | Instruction::DelegateCall { .. } => {
+                        if !seen_external_call {
+                            stores_before_external_call = seen_store;
+                        }
+                        seen_external_call = true;
+                    }
+                    Instruction::StorageStore { key, .. } => {
+                        seen_store = true;
+                        if let Some(slot) = storage_key_slot(key) {
+                            written_slots.insert(slot);
+                        }
+                    }
+                    Instruction::MappingStore { mapping, .. } => {
+                        seen_store = true;
+                        if let Some(slot) = constant_slot(mapping) {
+                            written_slots.insert(slot);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if let Terminator::Branch { condition, .. } = &block.terminator {
+                conditions.push(condition);
+            }
+        }
+
+        let mut visited: HashSet<&Value> = HashSet::new();
+        let mut checked_sources = Vec::new();
+        let mut worklist = conditions.clone();
+        while let Some(value) = worklist.pop() {
+            if !visited.insert(value) {
+                continue;
+            }
+            if let Some(&inst) = defs.get(value) {
+                checked_sources.push(inst);
+                worklist.extend(inst.operands());
+            }
+        }
+
+        Self {
+            checked_sources,
+            has_checks: !conditions.is_empty(),
+            called,
+            written_slots,
+            stores_before_external_call,
+        }
+    }
+
+    fn calls_matching(&self, needles: &[&str]) -> bool {
+        self.called
+            .iter()
+            .any(|name| needles.iter().any(|needle| name.contains(needle)))
+    }
+
+    fn checks_context(&self, var: ContextVariable) -> bool {
+        self.checked_sources.iter().any(
+            |inst| matches!(inst, Instruction::GetContext { var: checked, .. } if *checked == var),
+        )
+    }
+
+    fn checks_storage(&self) -> bool {
+        self.checked_sources.iter().any(|inst| {
+            matches!(
+                inst,
+                Instruction::StorageLoad { .. } | Instruction::MappingLoad { .. }
+            )
+        })
+    }
+
+    fn check_guard(&self, name: &str) -> Option<String> {
+        let guard = normalized(name);
+        if self.called.contains(&guard) {
+            return None;
+        }
+
+        if guard.starts_with("only") {
+            if self.checks_context(ContextVariable::MsgSender)
+                || self.calls_matching(&["checkowner", "checkrole", "onlyowner", "onlyrole"])
+            {
+                return None;
+            }
+            return Some("no check on msg.sender guards this function".to_string());
+        }
+
+        if guard == "nonreentrant" {
+            if self.calls_matching(&["nonreentrant", "reentrancyguard"]) {
+                return None;
+            }
+            if self.checks_storage() && self.stores_before_external_call {
+                return None;
+            }
+            return Some("no storage lock is checked and set before external calls".to_string());
+        }
+
+        if guard.contains("paused") {
+            if self.checks_storage() || self.calls_matching(&["paused"]) {
+                return None;
+            }
+            return Some("no pause flag is checked".to_string());
+        }
+
+        if self.has_checks {
+            None
+        } else {
+            Some("the function contains no require, assert, or branch".to_string())
+        }
+    }
+
+    fn check_invariant(&self, contract: &Contract, text: &str) -> Option<String> {
+        let slots = invariant_slots(contract, text);
+        let written: Vec<&str> = slots
+            .iter()
+            .filter(|(slot, _)| self.written_slots.contains(slot))
+            .map(|(_, name)| name.as_str())
+            .collect();
+
+        if written.is_empty() || self.has_checks {
+            return None;
+        }
+
+        Some(format!(
+            "writes {} without any require, assert, or branch",
+            written.join(", ")
+        ))
+    }
+}
+
+fn invariant_slots(contract: &Contract, text: &str) -> Vec<(BigUint, String)> {
+    let identifiers: HashSet<&str> = text
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.chars().next().is_some_and(|c| !c.is_ascii_digit()))
+        .collect();
+
+    let layout = &contract.storage_layout;
+    layout
+        .slots
+        .iter()
+        .map(|s| (s.slot.clone(), s.name.clone()))
+        .chain(
+            layout
+                .mappings
+                .iter()
+                .map(|m| (m.base_slot.clone(), m.name.clone())),
+        )
+        .filter(|(_, name)| identifiers.contains(name.as_str()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
+
+    fn natspec(lines: &[&str]) -> NatSpec {
+        NatSpec::parse(lines).unwrap()
+    }
+
+    #[test]
+    fn test_only_owner_claim_without_sender_check_drifts() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Vault");
+        contract_builder.state_variable("owner", Type::Address, 0);
+        contract_builder.state_variable("fee", Type::Uint(256), 1);
+
+        let mut guarded = contract_builder.function("setFee");
+        guarded.natspec(natspec(&["/// @custom:security onlyOwner"]));
+        guarded.param("newFee", Type::Uint(256));
+        let new_fee = guarded.get_param(0);
+        let mut entry = guarded.entry_block();
+        let sender = entry.msg_sender();
+        let owner = entry.storage_load(BigUint::from(0u32));
+        let is_owner = entry.eq(sender, owner);
+        entry.require(is_owner, "not owner");
+        entry.storage_store(BigUint::from(1u32), new_fee);
+        entry.return_void().unwrap();
+        guarded.build().unwrap();
+
+        let mut unguarded = contract_builder.function("setFeeUnsafe");
+        unguarded.natspec(natspec(&["/// @custom:security onlyOwner"]));
+        unguarded.param("newFee", Type::Uint(256));
+        let new_fee = unguarded.get_param(0);
+        let mut entry = unguarded.entry_block();
+        entry.storage_store(BigUint::from(1u32), new_fee);
+        entry.return_void().unwrap();
+        unguarded.build().unwrap();
+
+        let contract = contract_builder.build().unwrap();
+        let drifts = SpecificationAnalysis::analyze_contract(&contract);
+        assert_eq!(drifts.len(), 1);
+        assert_eq!(drifts[0].function, "setFeeUnsafe");
+        assert_eq!(drifts[0].claim, SpecClaim::Guard("onlyOwner".to_string()));
+    }
+
+    #[test]
+    fn test_contract_invariant_checks_writers() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Supply");
+        contract_builder.state_variable("totalSupply", Type::Uint(256), 0);
+        contract_builder.natspec(natspec(&[
+            "/// @custom:invariant totalSupply <= 1000000",
+            "/// @custom:invariant reserves > 0",
+        ]));
+
+        let mut mint = contract_builder.function("mint");
+        mint.param("amount", Type::Uint(256));
+        let amount = mint.get_param(0);
+        let mut entry = mint.entry_block();
+        entry.storage_store(BigUint::from(0u32), amount);
+        entry.return_void().unwrap();
+        mint.build().unwrap();
+
+        let contract = contract_builder.build().unwrap();
+        let findings = SpecificationAnalysis::findings(&contract);
+        assert_eq!(findings.len(), 2);
+        assert!(findings
+            .iter()
+            .any(|f| f.function == "mint" && f.message.contains("totalSupply")));
+        assert!(findings
+            .iter()
+            .any(|f| f.function.is_empty() && f.message.contains("reserves")));
+    }
+}
@@ -0,0 +1,375 @@
+use super::findings::{Finding, Severity};
+use super::inheritance::ancestors;
+use super::util::{constant_slot, storage_key_slot};
+use crate::{
+    contract::{Contract, StorageSlot},
+    function::Function,
+    instructions::Instruction,
+    types::Type,
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SlotUsage {
+    Unused,
+    WriteOnly,
+    ReadOnly,
+}
+
+impl fmt::Display for SlotUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlotUsage::Unused => write!(f, "is never read or written"),
+            SlotUsage::WriteOnly => write!(f, "is written but never read"),
+            SlotUsage::ReadOnly => {
+                write!(
+                    f,
+                    "is never written after construction; consider constant or immutable"
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageHygiene {
+    pub variable: String,
+    pub slot: BigUint,
+    #[serde(default)]
+    pub offset: u8,
+    pub usage: SlotUsage,
+}
+
+impl StorageHygiene {
+    pub fn severity(&self) -> Severity {
+        match self.usage {
+            SlotUsage::WriteOnly => Severity::Low,
+            SlotUsage::Unused | SlotUsage::ReadOnly => Severity::Info,
+        }
+    }
+
+    pub fn to_finding(&self, contract: &str) -> Finding {
+        Finding::new(
+            StorageUsageAnalysis::DETECTOR,
+            self.severity(),
+            contract,
+            "",
+            match self.offset {
+                0 => format!(
+                    "State variable `{}` (slot {}) {}",
+                    self.variable, self.slot, self.usage
+                ),
+                offset => format!(
+                    "State variable `{}` (slot {}, offset {}) {}",
+                    self.variable, self.slot, offset, self.usage
+                ),
+            },
+        )
+    }
+}
+
+/* Which slots a function touches. Accesses whose slot is not a constant are only recorded as
+ * unresolved; delegatecalls and assembly, which can reach any slot, also make the access opaque. */
+#[derive(Debug, Clone, Default)]
+pub struct StorageAccess {
+    pub reads: HashSet<BigUint>,
+    pub writes: HashSet<BigUint>,
+    pub unresolved_reads: bool,
+    pub unresolved_writes: bool,
+    pub opaque: bool,
+}
+
+impl StorageAccess {
+    pub fn of_function(function: &Function) -> Self {
+        let mut access = Self::default();
+        for inst in function.body.blocks.values().flat_map(|b| &b.instructions) {
+            match inst {
+                Instruction::StorageLoad { key, .. } => access.read(storage_key_slot(key)),
+                Instruction::StorageStore { key, .. } | Instruction::StorageDelete { key } => {
+                    access.write(storage_key_slot(key))
+                }
+                Instruction::MappingLoad { mapping: base, .. }
+                | Instruction::ArrayLoad { array: base, .. }
+                | Instruction::ArrayLength { array: base, .. } => access.read(constant_slot(base)),
+                Instruction::MappingStore { mapping: base, .. }
+                | Instruction::ArrayStore { array: base, .. }
+                | Instruction::ArrayPush { array: base, .. } => access.write(constant_slot(base)),
+                Instruction::ArrayPop { array, .. } => {
+                    access.read(constant_slot(array));
+                    access.write(constant_slot(array));
+                }
+                Instruction::DelegateCall { .. } => access.make_opaque(),
+                _ => {}
+            }
+        }
+        if function.metadata.has_assembly {
+            access.make_opaque();
+        }
+        access
+    }
+
+    fn make_opaque(&mut self) {
+        self.unresolved_reads = true;
+        self.unresolved_writes = true;
+        self.opaque = true;
+    }
+
+    fn read(&mut self, slot: Option<BigUint>) {
+        match slot {
+            Some(slot) => {
+                self.reads.insert(slot);
+            }
+            None => self.unresolved_reads = true,
+        }
+    }
+
+    fn write(&mut self, slot: Option<BigUint>) {
+        match slot {
+            Some(slot) => {
+                self.writes.insert(slot);
+            }
+            None => self.unresolved_writes = true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Touch {
+    No,
+    May,
+    Yes,
+}
+
+pub struct StorageUsageAnalysis;
+
+impl StorageUsageAnalysis {
+    pub const DETECTOR: &'static str = "storage-usage";
+
+    pub fn analyze_contract(contract: &Contract) -> Vec<StorageHygiene> {
+        Self::classify(contract, &[contract])
+    }
+
+    /* Bases and children share one storage, so each contract's variables are judged by the code
+     * of everything on its inheritance chain, and reported only where they are declared. */
+    pub fn analyze_workspace(contracts: &[Contract]) -> Vec<(String, StorageHygiene)> {
+        let by_name: HashMap<&str, &Contract> = contracts
+            .iter()
+            .map(|contract| (contract.name.as_str(), contract))
+            .collect();
+        let chains: Vec<Vec<&Contract>> = contracts
+            .iter()
+            .map(|contract| ancestors(contract, &by_name))
+            .collect();
+
+        contracts
+            .iter()
+            .zip(&chains)
+            .flat_map(|(contract, bases)| {
+                let children = contracts
+                    .iter()
+                    .zip(&chains)
+                    .filter(|(_, chain)| chain.iter().any(|base| base.name == contract.name))
+                    .map(|(child, _)| child);
+                let family: Vec<&Contract> = std::iter::once(contract)
+                    .chain(bases.iter().copied())
+                    .chain(children)
+                    .collect();
+                Self::classify(contract, &family)
+                    .into_iter()
+                    .filter(|hygiene| {
+                        !bases.iter().any(|base| {
+                            base.storage_layout.slots.iter().any(|var| {
+                                var.name == hygiene.variable
+                                    && var.slot == hygiene.slot
+                                    && var.offset == hygiene.offset
+                            })
+                        })
+                    })
+                    .map(|hygiene| (contract.name.clone(), hygiene))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    pub fn findings(contracts: &[Contract]) -> Vec<Finding> {
+        Self::analyze_workspace(contracts)
+            .iter()
+            .map(|(contract, hygiene)| hygiene.to_finding(contract))
+            .collect()
+    }
+
+    /* Variables are told apart by slot and offset, but accesses only name a slot, so one that
+     * hits a slot shared by packed variables may have touched any of them. Constructor writes
+     * count as initialisation, so a slot set only there is reported as a constant candidate
+     * rather than as written. A verdict that an access could contradict is withheld. */
+    fn classify(contract: &Contract, family: &[&Contract]) -> Vec<StorageHygiene> {
+        let mut runtime = StorageAccess::default();
+        let mut initialised = HashSet::new();
+
+        for function in family.iter().flat_map(|member| member.functions.values()) {
+            let access = StorageAccess::of_function(function);
+            runtime.reads.extend(access.reads);
+            runtime.unresolved_reads |= access.unresolved_reads;
+            runtime.opaque |= access.opaque;
+            if function.metadata.is_constructor {
+                initialised.extend(access.writes);
+            } else {
+                runtime.writes.extend(access.writes);
+                runtime.unresolved_writes |= access.unresolved_writes;
+            }
+        }
+
+        let layout = &contract.storage_layout.slots;
+        layout
+            .iter()
+            .filter_map(|var| {
+                let shared = layout
+                    .iter()
+                    .any(|other| other.slot == var.slot && other.offset != var.offset);
+                let touch = |resolved: &HashSet<BigUint>, unresolved: bool| {
+                    if resolved.contains(&var.slot) {
+                        if shared {
+                            Touch::May
+                        } else {
+                            Touch::Yes
+                        }
+                    } else if unresolved && (runtime.opaque || may_alias(var)) {
+                        Touch::May
+                    } else {
+                        Touch::No
+                    }
+                };
+                let read = touch(&runtime.reads, runtime.unresolved_reads);
+                let written = touch(&runtime.writes, runtime.unresolved_writes);
+                let stored = written.max(touch(&initialised, false));
+                let usage = match (read, stored, written) {
+                    (Touch::No, Touch::No, _) => SlotUsage::Unused,
+                    (Touch::No, Touch::Yes, _) => SlotUsage::WriteOnly,
+                    (Touch::Yes, _, Touch::No) => SlotUsage::ReadOnly,
+                    _ => return None,
+                };
+                Some(StorageHygiene {
+                    variable: var.name.clone(),
+                    slot: var.slot.clone(),
+                    offset: var.offset,
+                    usage,
+                })
+            })
+            .collect()
+    }
+}
+
+/* Whether a computed slot could land in this variable's storage: only mappings, arrays, structs
+ * and byte strings keep data at slots derived from their own. */
+fn may_alias(var: &StorageSlot) -> bool {
+    matches!(
+        var.var_type,
+        Type::Mapping(..) | Type::Array(..) | Type::Struct(_) | Type::String
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{IRBuilder, InstBuilderExt};
+    use crate::types::Type;
+
+    #[test]
+    fn test_slots_classified_by_reads_and_writes() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Registry");
+        contract_builder.state_variable("owner", Type::Address, 0);
+        contract_builder.state_variable("lastCaller", Type::Address, 1);
+        contract_builder.state_variable("count", Type::Uint(256), 2);
+        contract_builder.state_variable("legacy", Type::Uint(256), 3);
+
+        let mut constructor = contract_builder.function("constructor");
+        constructor.constructor();
+        let mut entry = constructor.entry_block();
+        let sender = entry.msg_sender();
+        entry.storage_store(BigUint::from(0u32), sender);
+        entry.return_void().unwrap();
+        constructor.build().unwrap();
+
+        let mut bump = contract_builder.function("bump");
+        let mut entry = bump.entry_block();
+        let owner = entry.storage_load(BigUint::from(0u32));
+        let sender = entry.msg_sender();
+        let is_owner = entry.eq(sender.clone(), owner);
+        entry.require(is_owner, "not owner");
+        let count = entry.storage_load(BigUint::from(2u32));
+        let one = entry.constant_uint(1, 256);
+        let next = entry.add(count, one, Type::Uint(256));
+        entry.storage_store(BigUint::from(2u32), next);
+        entry.storage_store(BigUint::from(1u32), sender);
+        entry.return_void().unwrap();
+        bump.build().unwrap();
+
+        let contract = contract_builder.build().unwrap();
+        let report = StorageUsageAnalysis::analyze_contract(&contract);
+        let usage = |name: &str| {
+            report
+                .iter()
+                .find(|hygiene| hygiene.variable == name)
+                .map(|hygiene| hygiene.usage)
+        };
+
+        assert_eq!(usage("owner"), Some(SlotUsage::ReadOnly));
+        assert_eq!(usage("lastCaller"), Some(SlotUsage::WriteOnly));
+        assert_eq!(usage("count"), None);
+        assert_eq!(usage("legacy"), Some(SlotUsage::Unused));
+    }
+
+    #[test]
+    fn test_packed_inherited_and_aliased_usage() {
+        let mut builder = IRBuilder::new();
+
+        let mut base = builder.contract("Ownable");
+        base.allocate_state_variable("owner", Type::Address, None);
+        base.allocate_state_variable("paused", Type::Bool, None);
+        base.allocate_state_variable("count", Type::Uint(256), None);
+        base.allocate_state_variable(
+            "balances",
+            Type::Mapping(Box::new(Type::Address), Box::new(Type::Uint(256))),
+            None,
+        );
+        base.allocate_state_variable("legacy", Type::Uint(256), None);
+        let mut owner = base.function("owner");
+        owner.returns(Type::Address);
+        let mut entry = owner.entry_block();
+        let value = entry.storage_load(BigUint::from(0u32));
+        entry.return_value(value).unwrap();
+        owner.build().unwrap();
+        let base = base.build().unwrap();
+
+        let mut derived = builder.contract("Vault");
+        derived.inherits("Ownable");
+        let mut set_count = derived.function("setCount");
+        set_count.param("slot", Type::Uint(256));
+        let slot = set_count.get_param(0);
+        let mut entry = set_count.entry_block();
+        let one = entry.constant_uint(1, 256);
+        entry.storage_store(BigUint::from(1u32), one.clone());
+        entry.storage_store_dynamic(slot, one);
+        entry.return_void().unwrap();
+        set_count.build().unwrap();
+        let derived = derived.build().unwrap();
+
+        let report = StorageUsageAnalysis::analyze_workspace(&[base, derived]);
+        let usage = |name: &str| {
+            report
+                .iter()
+                .find(|(contract, hygiene)| contract == "Ownable" && hygiene.variable == name)
+                .map(|(_, hygiene)| hygiene.usage)
+        };
+
+        assert_eq!(usage("owner"), None);
+        assert_eq!(usage("paused"), None);
+        assert_eq!(usage("count"), Some(SlotUsage::WriteOnly));
+        assert_eq!(usage("balances"), None);
+        assert_eq!(usage("legacy"), Some(SlotUsage::Unused));
+    }
+}
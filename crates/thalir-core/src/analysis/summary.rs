@@ -0,0 +1,83 @@
+use super::storage_usage::StorageAccess;
+use crate::{
+    contract::Contract,
+    entry_point::GuardSummary,
+    function::Function,
+    instructions::{CallTarget, Instruction},
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SummaryCall {
+    Internal(String),
+    Library(String),
+    External,
+    DelegateCall,
+    StaticCall,
+    Create,
+}
+
+impl fmt::Display for SummaryCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SummaryCall::Internal(name) => write!(f, "internal {}", name),
+            SummaryCall::Library(name) => write!(f, "library {}", name),
+            SummaryCall::External => write!(f, "external"),
+            SummaryCall::DelegateCall => write!(f, "delegatecall"),
+            SummaryCall::StaticCall => write!(f, "staticcall"),
+            SummaryCall::Create => write!(f, "create"),
+        }
+    }
+}
+
+/* What a function touches, for a reader skimming its IR: the slots it reads and writes, what it
+ * calls, in order of first appearance, and what guards it. */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionSummary {
+    pub reads: Vec<BigUint>,
+    pub writes: Vec<BigUint>,
+    pub unresolved_reads: bool,
+    pub unresolved_writes: bool,
+    pub calls: Vec<SummaryCall>,
+    pub guards: GuardSummary,
+}
+
+impl FunctionSummary {
+    pub fn of_function(contract: &Contract, function: &Function) -> Self {
+        let access = StorageAccess::of_function(function);
+        let mut reads: Vec<BigUint> = access.reads.into_iter().collect();
+        let mut writes: Vec<BigUint> = access.writes.into_iter().collect();
+        reads.sort();
+        writes.sort();
+
+        let mut calls = Vec::new();
+        for inst in function.body.blocks.values().flat_map(|b| &b.instructions) {
+            let call = match inst {
+                Instruction::Call { target, .. } => match target {
+                    CallTarget::Internal(name) => SummaryCall::Internal(name.clone()),
+                    CallTarget::Library(name) => SummaryCall::Library(name.clone()),
+                    CallTarget::External(_) => SummaryCall::External,
+                    CallTarget::Builtin(_) => continue,
+                },
+                Instruction::DelegateCall { .. } => SummaryCall::DelegateCall,
+                Instruction::StaticCall { .. } => SummaryCall::StaticCall,
+                Instruction::Create { .. } | Instruction::Create2 { .. } => SummaryCall::Create,
+                _ => continue,
+            };
+            if !calls.contains(&call) {
+                calls.push(call);
+            }
+        }
+
+        Self {
+            reads,
+            writes,
+            unresolved_reads: access.unresolved_reads,
+            unresolved_writes: access.unresolved_writes,
+            calls,
+            guards: contract.guards(function),
+        }
+    }
+}
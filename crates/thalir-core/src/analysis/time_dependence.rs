@@ -0,0 +1,282 @@
+use super::findings::{Finding, Severity};
+use crate::{
+    contract::Contract,
+    function::Function,
+    instructions::{ContextVariable, Instruction},
+    metadata::InstructionLocation,
+    values::Value,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TimeSource {
+    Timestamp,
+    BlockNumber,
+}
+
+impl fmt::Display for TimeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeSource::Timestamp => write!(f, "block.timestamp"),
+            TimeSource::BlockNumber => write!(f, "block.number"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TimeUse {
+    DeadlineCheck,
+    StrictEquality,
+    Randomness,
+    FinancialMath,
+}
+
+impl fmt::Display for TimeUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeUse::DeadlineCheck => write!(f, "an ordering check against a deadline"),
+            TimeUse::StrictEquality => write!(f, "a strict equality that miners can dodge"),
+            TimeUse::Randomness => write!(f, "a source of randomness"),
+            TimeUse::FinancialMath => write!(f, "accrual arithmetic"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeDependence {
+    pub function: String,
+    pub source: TimeSource,
+    pub kind: TimeUse,
+    pub location: InstructionLocation,
+    /* Instructions the value flows through, from the context read to the classified use. */
+    pub path: Vec<InstructionLocation>,
+}
+
+impl TimeDependence {
+    pub fn severity(&self) -> Severity {
+        match self.kind {
+            TimeUse::Randomness => Severity::High,
+            TimeUse::StrictEquality => Severity::Medium,
+            TimeUse::FinancialMath => Severity::Low,
+            TimeUse::DeadlineCheck => Severity::Info,
+        }
+    }
+
+    pub fn is_risky(&self) -> bool {
+        self.kind != TimeUse::DeadlineCheck
+    }
+
+    pub fn to_finding(&self, contract: &str) -> Finding {
+        let path: Vec<String> = self
+            .path
+            .iter()
+            .map(|loc| format!("{}:{}", loc.block, loc.index))
+            .collect();
+        Finding::new(
+            TimeDependenceAnalysis::DETECTOR,
+            self.severity(),
+            contract,
+            &self.function,
+            format!(
+                "{} is used as {} (via {})",
+                self.source,
+                self.kind,
+                path.join(" -> ")
+            ),
+        )
+        .at(self.location.clone())
+    }
+}
+
+pub struct TimeDependenceAnalysis;
+
+impl TimeDependenceAnalysis {
+    pub const DETECTOR: &'static str = "time-dependence";
+
+    pub fn analyze_contract(contract: &Contract) -> Vec<TimeDependence> {
+        contract
+            .functions
+            .values()
+            .flat_map(Self::analyze_function)
+            .collect()
+    }
+
+    /* Follows each block.timestamp or block.number read forward through arithmetic, casts and
+     * assignments. Ordering comparisons are deadline checks, equality comparisons are strict
+     * checks, modulo and hashing are randomness, and multiplication, division or
+     * exponentiation is accrual math. Each of these ends the walk along its branch. */
+    pub fn analyze_function(function: &Function) -> Vec<TimeDependence> {
+        let mut users: HashMap<&Value, Vec<(InstructionLocation, &Instruction)>> = HashMap::new();
+        let mut sources = Vec::new();
+        for (&block_id, block) in &function.body.blocks {
+            for (index, inst) in block.instructions.iter().enumerate() {
+                let location = InstructionLocation {
+                    block: block_id,
+                    index,
+                };
+                for operand in inst.operands() {
+                    users
+                        .entry(operand)
+                        .or_default()
+                        .push((location.clone(), inst));
+                }
+                let source = match inst {
+                    Instruction::GetContext {
+                        var: ContextVariable::BlockTimestamp,
+                        result,
+                    } => Some((TimeSource::Timestamp, result)),
+                    Instruction::GetContext {
+                        var: ContextVariable::BlockNumber,
+                        result,
+                    } => Some((TimeSource::BlockNumber, result)),
+                    _ => None,
+                };
+                if let Some((source, result)) = source {
+                    sources.push((source, result, location));
+                }
+            }
+        }
+
+        let mut results = Vec::new();
+        let mut reported: HashSet<(TimeSource, InstructionLocation)> = HashSet::new();
+        for (source, value, origin) in sources {
+            let mut parents: HashMap<InstructionLocation, InstructionLocation> = HashMap::new();
+            let mut visited: HashSet<&Value> = HashSet::new();
+            let mut queue = VecDeque::from([(value, origin.clone())]);
+
+            while let Some((value, from)) = queue.pop_front() {
+                if !visited.insert(value) {
+                    continue;
+                }
+                for (location, inst) in users.get(value).into_iter().flatten() {
+                    if location == &origin || parents.contains_key(location) {
+                        continue;
+                    }
+                    parents.insert(location.clone(), from.clone());
+                    match classify(inst) {
+                        Some(kind) => {
+                            if reported.insert((source, location.clone())) {
+                                results.push(TimeDependence {
+                                    function: function.name().to_string(),
+                                    source,
+                                    kind,
+                                    location: location.clone(),
+                                    path: path_to(location, &origin, &parents),
+                                });
+                            }
+                        }
+                        None => {
+                            if let Some(result) = inst.result() {
+                                queue.push_back((result, location.clone()));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        results
+    }
+
+    pub fn findings(contract: &Contract) -> Vec<Finding> {
+        Self::analyze_contract(contract)
+            .iter()
+            .filter(|dependence| dependence.is_risky())
+            .map(|dependence| dependence.to_finding(&contract.name))
+            .collect()
+    }
+}
+
+fn classify(inst: &Instruction) -> Option<TimeUse> {
+    match inst {
+        Instruction::Lt { .. }
+        | Instruction::Gt { .. }
+        | Instruction::Le { .. }
+        | Instruction::Ge { .. }
+        | Instruction::Slt { .. }
+        | Instruction::Sgt { .. }
+        | Instruction::Sle { .. }
+        | Instruction::Sge { .. } => Some(TimeUse::DeadlineCheck),
+        Instruction::Eq { .. } | Instruction::Ne { .. } => Some(TimeUse::StrictEquality),
+        Instruction::Mod { .. }
+        | Instruction::Smod { .. }
+        | Instruction::Keccak256 { .. }
+        | Instruction::Sha256 { .. } => Some(TimeUse::Randomness),
+        Instruction::Mul { .. }
+        | Instruction::CheckedMul { .. }
+        | Instruction::Div { .. }
+        | Instruction::CheckedDiv { .. }
+        | Instruction::Sdiv { .. }
+        | Instruction::Pow { .. } => Some(TimeUse::FinancialMath),
+        _ => None,
+    }
+}
+
+fn path_to(
+    location: &InstructionLocation,
+    origin: &InstructionLocation,
+    parents: &HashMap<InstructionLocation, InstructionLocation>,
+) -> Vec<InstructionLocation> {
+    let mut path = vec![location.clone()];
+    let mut current = location;
+    while current != origin {
+        match parents.get(current) {
+            Some(parent) => {
+                path.push(parent.clone());
+                current = parent;
+            }
+            None => break,
+        }
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_classifies_timestamp_uses() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Pool");
+        contract_builder.state_variable("lastUpdate", Type::Uint(256), 0);
+
+        let mut accrue = contract_builder.function("accrue");
+        accrue.param("deadline", Type::Uint(256));
+        let deadline = accrue.get_param(0);
+        let mut entry = accrue.entry_block();
+        let now = entry.block_timestamp();
+        let live = entry.lt(now.clone(), deadline);
+        entry.require(live, "expired");
+        let last = entry.storage_load(BigUint::from(0u32));
+        let elapsed = entry.sub(now.clone(), last, Type::Uint(256));
+        let rate = entry.constant_uint(5, 256);
+        let interest = entry.mul(elapsed, rate, Type::Uint(256));
+        entry.storage_store(BigUint::from(1u32), interest);
+        let players = entry.constant_uint(10, 256);
+        let winner = entry.mod_(now, players, Type::Uint(256));
+        entry.return_value(winner).unwrap();
+        accrue.build().unwrap();
+
+        let contract = contract_builder.build().unwrap();
+        let report = TimeDependenceAnalysis::analyze_contract(&contract);
+        let kinds: Vec<TimeUse> = report.iter().map(|d| d.kind).collect();
+        assert_eq!(report.len(), 3);
+        assert!(kinds.contains(&TimeUse::DeadlineCheck));
+        assert!(kinds.contains(&TimeUse::Randomness));
+
+        let accrual = report
+            .iter()
+            .find(|d| d.kind == TimeUse::FinancialMath)
+            .unwrap();
+        assert_eq!(accrual.source, TimeSource::Timestamp);
+        assert_eq!(accrual.path.len(), 3);
+
+        assert_eq!(TimeDependenceAnalysis::findings(&contract).len(), 2);
+    }
+}
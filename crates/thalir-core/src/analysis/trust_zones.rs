@@ -0,0 +1,169 @@
+use super::findings::Finding;
+use crate::{
+    contract::Contract,
+    instructions::{CallTarget, Instruction},
+    metadata::InstructionLocation,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/* Whose code a contract is: the protocol under review, or a dependency it trusts. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustZone {
+    #[default]
+    Project,
+    Dependency,
+}
+
+impl fmt::Display for TrustZone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrustZone::Project => write!(f, "project"),
+            TrustZone::Dependency => write!(f, "dependency"),
+        }
+    }
+}
+
+/* A call from code in one zone into a contract of the other. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustCrossing {
+    pub function: String,
+    pub location: InstructionLocation,
+    pub callee: String,
+    pub from: TrustZone,
+    pub to: TrustZone,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TrustZones {
+    zones: HashMap<String, TrustZone>,
+}
+
+impl TrustZones {
+    /* `is_dependency` decides by source file; a contract without one is project code. */
+    pub fn classify(contracts: &[Contract], is_dependency: impl Fn(&str) -> bool) -> Self {
+        let zones = contracts
+            .iter()
+            .map(|contract| {
+                let zone = match contract.metadata.source_file.as_deref() {
+                    Some(file) if is_dependency(file) => TrustZone::Dependency,
+                    _ => TrustZone::Project,
+                };
+                (contract.name.clone(), zone)
+            })
+            .collect();
+        Self { zones }
+    }
+
+    pub fn zone(&self, contract: &str) -> TrustZone {
+        self.zones.get(contract).copied().unwrap_or_default()
+    }
+
+    /* The zone of the contract `inst` calls into, when that differs from `contract`'s. Only
+     * library and qualified internal calls name their callee; an external call's target is an
+     * address, so it never counts. */
+    pub fn crossing(&self, contract: &str, inst: &Instruction) -> Option<(String, TrustZone)> {
+        let Instruction::Call {
+            target: CallTarget::Internal(name) | CallTarget::Library(name),
+            ..
+        } = inst
+        else {
+            return None;
+        };
+        let (callee, _) = name.split_once('.')?;
+        let to = *self.zones.get(callee)?;
+        (to != self.zone(contract)).then(|| (callee.to_string(), to))
+    }
+
+    pub fn crossings(&self, contract: &Contract) -> Vec<TrustCrossing> {
+        let from = self.zone(&contract.name);
+        let mut crossings = Vec::new();
+        for function in contract.functions.values() {
+            for (&block, body) in &function.body.blocks {
+                for (index, inst) in body.instructions.iter().enumerate() {
+                    if let Some((callee, to)) = self.crossing(&contract.name, inst) {
+                        crossings.push(TrustCrossing {
+                            function: function.name().to_string(),
+                            location: InstructionLocation { block, index },
+                            callee,
+                            from,
+                            to,
+                        });
+                    }
+                }
+            }
+        }
+        crossings
+    }
+
+    /* Tags each finding with the zone of the contract it was reported in. */
+    pub fn attribute(&self, findings: Vec<Finding>) -> Vec<Finding> {
+        findings
+            .into_iter()
+            .map(|mut finding| {
+                finding.zone = Some(self.zone(&finding.contract));
+                finding
+            })
+            .collect()
+    }
+
+    /* Drops findings inside dependency code. A finding in a project contract stays even when the
+     * code it flags was inherited from a dependency, since the project chose to deploy it. */
+    pub fn project_findings(&self, findings: Vec<Finding>) -> Vec<Finding> {
+        findings
+            .into_iter()
+            .filter(|finding| self.zone(&finding.contract) == TrustZone::Project)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::findings::Severity;
+    use crate::builder::IRBuilder;
+    use crate::function::Visibility;
+    use crate::types::Type;
+
+    #[test]
+    fn test_zones_follow_source_files_and_calls_across_them() {
+        let mut builder = IRBuilder::new();
+        let mut contract = builder.contract("Vault");
+        let mut function = contract.function("deposit");
+        function
+            .param("amount", Type::Uint(256))
+            .visibility(Visibility::External);
+        let amount = function.get_param(0);
+        let mut entry = function.entry_block();
+        let scaled = entry.call_internal("Math.mulDiv", vec![amount]);
+        entry.call_internal("_mint", vec![scaled]);
+        entry.return_void().unwrap();
+        function.build().unwrap();
+        let mut vault = contract.build().unwrap();
+        vault.metadata.source_file = Some("src/Vault.sol".to_string());
+
+        let mut math = builder.contract("Math").build().unwrap();
+        math.metadata.source_file = Some("lib/Math.sol".to_string());
+
+        let contracts = vec![vault, math];
+        let zones = TrustZones::classify(&contracts, |file| file.starts_with("lib/"));
+        assert_eq!(zones.zone("Vault"), TrustZone::Project);
+        assert_eq!(zones.zone("Math"), TrustZone::Dependency);
+
+        let crossings = zones.crossings(&contracts[0]);
+        assert_eq!(crossings.len(), 1);
+        assert_eq!(crossings[0].callee, "Math");
+        assert_eq!(crossings[0].to, TrustZone::Dependency);
+
+        let findings = zones.attribute(vec![
+            Finding::new("d", Severity::Low, "Vault", "deposit", String::new()),
+            Finding::new("d", Severity::Low, "Math", "mulDiv", String::new()),
+        ]);
+        assert_eq!(findings[1].zone, Some(TrustZone::Dependency));
+        let kept = zones.project_findings(findings);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].contract, "Vault");
+    }
+}
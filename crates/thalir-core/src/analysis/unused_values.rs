@@ -0,0 +1,228 @@
+use super::def_use::DefUseChains;
+use super::findings::{Finding, Severity};
+use crate::{
+    contract::Contract,
+    function::Function,
+    instructions::{CallTarget, Instruction},
+    metadata::InstructionLocation,
+    types::Type,
+    values::{ParamId, ValueId},
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnusedKind {
+    Parameter { index: usize, name: String },
+    CallResult { callee: String, returns: Type },
+}
+
+impl fmt::Display for UnusedKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnusedKind::Parameter { name, .. } => write!(f, "Parameter `{}` is never used", name),
+            UnusedKind::CallResult { callee, .. } => {
+                write!(f, "Result of `{}` is never used", callee)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnusedValue {
+    pub function: String,
+    pub kind: UnusedKind,
+    /* The ignoring call; parameters have none. */
+    pub location: Option<InstructionLocation>,
+}
+
+impl UnusedValue {
+    /* An ignored fee, amount or bound, or an ignored success flag, usually means a refactor
+     * dropped a check rather than left a harmless leftover. */
+    pub fn severity(&self) -> Severity {
+        let significant = match &self.kind {
+            UnusedKind::Parameter { name, .. } => is_value_name(name),
+            UnusedKind::CallResult { returns, .. } => matches!(returns, Type::Bool),
+        };
+        if significant {
+            Severity::Medium
+        } else {
+            Severity::Low
+        }
+    }
+
+    pub fn to_finding(&self, contract: &str) -> Finding {
+        let finding = Finding::new(
+            UnusedValueAnalysis::DETECTOR,
+            self.severity(),
+            contract,
+            &self.function,
+            self.kind.to_string(),
+        );
+        match &self.location {
+            Some(location) => finding.at(location.clone()),
+            None => finding,
+        }
+    }
+}
+
+pub struct UnusedValueAnalysis;
+
+impl UnusedValueAnalysis {
+    pub const DETECTOR: &'static str = "unused-value";
+
+    pub fn analyze_contract(contract: &Contract) -> Vec<UnusedValue> {
+        contract
+            .functions
+            .values()
+            .flat_map(|function| Self::analyze_function(contract, function))
+            .collect()
+    }
+
+    /* Unnamed parameters are unused on purpose, and functions without a body or with inline
+     * assembly, which may read parameters the IR does not see, are left alone. A parameter passed
+     * to a modifier counts as used. Internal call results count only when the callee returns
+     * something. */
+    pub fn analyze_function(contract: &Contract, function: &Function) -> Vec<UnusedValue> {
+        let has_body = function
+            .body
+            .blocks
+            .values()
+            .any(|block| !block.instructions.is_empty());
+        if !has_body || function.metadata.has_assembly {
+            return Vec::new();
+        }
+
+        let chains = DefUseChains::build(function);
+        let mut results = Vec::new();
+
+        for (index, param) in function.signature.params.iter().enumerate() {
+            let id = ValueId::Param(ParamId(index as u32));
+            let in_modifier = function
+                .modifiers
+                .iter()
+                .flat_map(|modifier| &modifier.arguments)
+                .any(|argument| argument.as_register() == Some(id));
+            if param.name.is_empty() || chains.is_used(id) || in_modifier {
+                continue;
+            }
+            results.push(UnusedValue {
+                function: function.name().to_string(),
+                kind: UnusedKind::Parameter {
+                    index,
+                    name: param.name.clone(),
+                },
+                location: None,
+            });
+        }
+
+        for (&block_id, block) in &function.body.blocks {
+            for (index, inst) in block.instructions.iter().enumerate() {
+                let Instruction::Call {
+                    result,
+                    target: CallTarget::Internal(callee),
+                    ..
+                } = inst
+                else {
+                    continue;
+                };
+                let Some(returns) = contract
+                    .functions
+                    .values()
+                    .find(|f| f.name() == callee)
+                    .and_then(|f| f.signature.returns.first())
+                else {
+                    continue;
+                };
+                if result.as_register().is_some_and(|id| chains.is_used(id)) {
+                    continue;
+                }
+                results.push(UnusedValue {
+                    function: function.name().to_string(),
+                    kind: UnusedKind::CallResult {
+                        callee: callee.clone(),
+                        returns: returns.clone(),
+                    },
+                    location: Some(InstructionLocation {
+                        block: block_id,
+                        index,
+                    }),
+                });
+            }
+        }
+        results
+    }
+
+    pub fn findings(contract: &Contract) -> Vec<Finding> {
+        Self::analyze_contract(contract)
+            .iter()
+            .map(|unused| unused.to_finding(&contract.name))
+            .collect()
+    }
+}
+
+fn is_value_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    [
+        "fee", "amount", "min", "max", "deadline", "slippage", "price", "rate", "limit",
+    ]
+    .iter()
+    .any(|word| name.contains(word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+
+    #[test]
+    fn test_ignored_fee_and_success_flag() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Router");
+
+        let mut check = contract_builder.function("_checkSlippage");
+        check.param("out", Type::Uint(256)).returns(Type::Bool);
+        let out = check.get_param(0);
+        let mut entry = check.entry_block();
+        let zero = entry.constant_uint(0, 256);
+        let ok = entry.gt(out, zero);
+        entry.return_value(ok).unwrap();
+        check.build().unwrap();
+
+        let mut swap = contract_builder.function("swap");
+        swap.param("amountIn", Type::Uint(256))
+            .param("fee", Type::Uint(256))
+            .param("", Type::Address);
+        let amount = swap.get_param(0);
+        let mut entry = swap.entry_block();
+        entry.call_internal("_checkSlippage", vec![amount.clone()]);
+        entry.storage_store(0u32.into(), amount);
+        entry.return_void().unwrap();
+        swap.build().unwrap();
+
+        let contract = contract_builder.build().unwrap();
+        let unused = UnusedValueAnalysis::analyze_function(&contract, &contract.functions["swap"]);
+
+        assert_eq!(unused.len(), 2, "{:?}", unused);
+        assert_eq!(
+            unused[0].kind,
+            UnusedKind::Parameter {
+                index: 1,
+                name: "fee".to_string()
+            }
+        );
+        assert_eq!(unused[0].severity(), Severity::Medium);
+        assert!(matches!(
+            &unused[1].kind,
+            UnusedKind::CallResult { callee, returns: Type::Bool } if callee == "_checkSlippage"
+        ));
+        assert_eq!(unused[1].location.as_ref().unwrap().index, 0);
+        assert_eq!(unused[1].severity(), Severity::Medium);
+
+        assert!(UnusedValueAnalysis::analyze_function(
+            &contract,
+            &contract.functions["_checkSlippage"]
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,33 @@
+/*! Helpers the analyses share for matching names and resolving storage keys. */
+
+use crate::{
+    instructions::StorageKey,
+    values::{Constant, Value},
+};
+use num_bigint::BigUint;
+
+/* Lowercase with underscores dropped, so `_nonces` and `Nonces` compare equal. */
+pub(crate) fn normalized(name: &str) -> String {
+    name.chars()
+        .filter(|c| *c != '_')
+        .collect::<String>()
+        .to_lowercase()
+}
+
+pub(crate) fn constant_slot(value: &Value) -> Option<BigUint> {
+    match value {
+        Value::Constant(Constant::Uint(slot, _)) => Some(slot.clone()),
+        _ => None,
+    }
+}
+
+/* The declared slot a key reads from: the slot itself, or the base slot of a mapping or array. */
+pub(crate) fn storage_key_slot(key: &StorageKey) -> Option<BigUint> {
+    match key {
+        StorageKey::Slot(slot) => Some(slot.clone()),
+        StorageKey::MappingKey { base, .. } | StorageKey::ArrayElement { base, .. } => {
+            Some(base.clone())
+        }
+        StorageKey::Dynamic(value) | StorageKey::Computed(value) => constant_slot(value),
+    }
+}
@@ -0,0 +1,365 @@
+use super::util::{constant_slot, storage_key_slot};
+use crate::{
+    block::Terminator,
+    contract::Contract,
+    instructions::{CallTarget, Instruction},
+    metadata::InstructionLocation,
+    values::SourceLocation,
+};
+use indexmap::IndexMap;
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SymbolKind {
+    Function,
+    Event,
+    Error,
+    StorageVariable,
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolKind::Function => write!(f, "function"),
+            SymbolKind::Event => write!(f, "event"),
+            SymbolKind::Error => write!(f, "error"),
+            SymbolKind::StorageVariable => write!(f, "storage variable"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ReferenceKind {
+    Call,
+    Read,
+    Write,
+    Emit,
+    Revert,
+}
+
+impl fmt::Display for ReferenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReferenceKind::Call => write!(f, "call"),
+            ReferenceKind::Read => write!(f, "read"),
+            ReferenceKind::Write => write!(f, "write"),
+            ReferenceKind::Emit => write!(f, "emit"),
+            ReferenceKind::Revert => write!(f, "revert"),
+        }
+    }
+}
+
+/* A use of a symbol. A revert terminator is located one past the last instruction of its block. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolReference {
+    pub contract: String,
+    pub function: String,
+    pub location: InstructionLocation,
+    pub kind: ReferenceKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Symbol {
+    pub kind: SymbolKind,
+    pub name: String,
+    pub contract: String,
+    /* Where the symbol is declared in source, when the IR kept it. */
+    pub location: Option<SourceLocation>,
+    pub references: Vec<SymbolReference>,
+}
+
+impl Symbol {
+    pub fn qualified_name(&self) -> String {
+        format!("{}.{}", self.contract, self.name)
+    }
+}
+
+/* Every function, event, custom error and state variable across a set of contracts, with the
+ * sites that use it.
+ *
+ * A call resolves to the function of that name in the calling contract, or failing that in any
+ * other contract of the workspace. Storage accesses are matched by slot, so an access to a packed
+ * slot counts against every variable sharing it, and accesses whose slot is not a constant are
+ * left out. Custom errors have no declaration in the IR: an error is known only by the revert and
+ * require reasons written as a call, like `Unauthorized()`, and its symbol has no location. */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SymbolIndex {
+    symbols: Vec<Symbol>,
+    by_name: IndexMap<String, Vec<usize>>,
+}
+
+impl SymbolIndex {
+    pub fn new(contracts: &[Contract]) -> Self {
+        let mut index = Self::default();
+        for contract in contracts {
+            index.add_definitions(contract);
+        }
+        for contract in contracts {
+            index.add_references(contract);
+        }
+        index
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    /* Symbols named `query`, which may be qualified as `Contract.name`. */
+    pub fn lookup(&self, query: &str) -> Vec<&Symbol> {
+        let (contract, name) = match query.split_once('.') {
+            Some((contract, name)) => (Some(contract), name),
+            None => (None, query),
+        };
+        self.by_name
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(|&i| &self.symbols[i])
+            .filter(|symbol| contract.map_or(true, |c| symbol.contract == c))
+            .collect()
+    }
+
+    /* The declaration a go-to-definition request on `name` inside `contract` lands on: the
+     * contract's own symbol if it has one, otherwise the first in the workspace. */
+    pub fn definition(&self, contract: &str, name: &str) -> Option<&Symbol> {
+        let candidates = self.lookup(name);
+        candidates
+            .iter()
+            .find(|symbol| symbol.contract == contract)
+            .or_else(|| candidates.first())
+            .copied()
+    }
+
+    fn add_definitions(&mut self, contract: &Contract) {
+        for function in contract.functions.values() {
+            self.define(SymbolKind::Function, function.name(), contract, None);
+        }
+        for event in &contract.events {
+            self.define(SymbolKind::Event, &event.name, contract, None);
+        }
+        for slot in &contract.storage_layout.slots {
+            self.define(
+                SymbolKind::StorageVariable,
+                &slot.name,
+                contract,
+                slot.location.clone(),
+            );
+        }
+    }
+
+    fn define(
+        &mut self,
+        kind: SymbolKind,
+        name: &str,
+        contract: &Contract,
+        location: Option<SourceLocation>,
+    ) -> usize {
+        if let Some(existing) = self.find(kind, &contract.name, name) {
+            return existing;
+        }
+        self.symbols.push(Symbol {
+            kind,
+            name: name.to_string(),
+            contract: contract.name.clone(),
+            location,
+            references: Vec::new(),
+        });
+        let id = self.symbols.len() - 1;
+        self.by_name.entry(name.to_string()).or_default().push(id);
+        id
+    }
+
+    fn find(&self, kind: SymbolKind, contract: &str, name: &str) -> Option<usize> {
+        self.by_name
+            .get(name)?
+            .iter()
+            .copied()
+            .find(|&i| self.symbols[i].kind == kind && self.symbols[i].contract == contract)
+    }
+
+    fn resolve(&self, kind: SymbolKind, contract: &str, name: &str) -> Option<usize> {
+        self.find(kind, contract, name).or_else(|| {
+            self.by_name
+                .get(name)?
+                .iter()
+                .copied()
+                .find(|&i| self.symbols[i].kind == kind)
+        })
+    }
+
+    fn slot_references(
+        &self,
+        contract: &Contract,
+        slot: Option<BigUint>,
+        kind: ReferenceKind,
+    ) -> Vec<(usize, ReferenceKind)> {
+        let Some(slot) = slot else {
+            return Vec::new();
+        };
+        contract
+            .storage_layout
+            .slots
+            .iter()
+            .filter(|var| var.slot == slot)
+            .filter_map(|var| self.find(SymbolKind::StorageVariable, &contract.name, &var.name))
+            .map(|symbol| (symbol, kind))
+            .collect()
+    }
+
+    fn add_references(&mut self, contract: &Contract) {
+        for function in contract.functions.values() {
+            for (block_id, block) in &function.body.blocks {
+                let mut sites: Vec<(usize, ReferenceKind, usize)> = Vec::new();
+                for (index, inst) in block.instructions.iter().enumerate() {
+                    for (symbol, kind) in self.referenced_by(contract, inst) {
+                        sites.push((symbol, kind, index));
+                    }
+                }
+                if let Terminator::Revert(reason) = &block.terminator {
+                    if let Some(error) = error_name(reason) {
+                        let symbol = self.define(SymbolKind::Error, error, contract, None);
+                        sites.push((symbol, ReferenceKind::Revert, block.instructions.len()));
+                    }
+                }
+
+                for (symbol, kind, index) in sites {
+                    self.symbols[symbol].references.push(SymbolReference {
+                        contract: contract.name.clone(),
+                        function: function.name().to_string(),
+                        location: InstructionLocation {
+                            block: *block_id,
+                            index,
+                        },
+                        kind,
+                    });
+                }
+            }
+        }
+    }
+
+    fn referenced_by(
+        &mut self,
+        contract: &Contract,
+        inst: &Instruction,
+    ) -> Vec<(usize, ReferenceKind)> {
+        match inst {
+            Instruction::Call {
+                target: CallTarget::Internal(name) | CallTarget::Library(name),
+                ..
+            } => self
+                .resolve(SymbolKind::Function, &contract.name, name)
+                .map(|symbol| vec![(symbol, ReferenceKind::Call)])
+                .unwrap_or_default(),
+            Instruction::EmitEvent { event, .. } => contract
+                .events
+                .iter()
+                .find(|definition| definition.id == *event)
+                .and_then(|definition| {
+                    self.find(SymbolKind::Event, &contract.name, &definition.name)
+                })
+                .map(|symbol| vec![(symbol, ReferenceKind::Emit)])
+                .unwrap_or_default(),
+            Instruction::Require { message, .. } | Instruction::Revert { message } => {
+                match error_name(message) {
+                    Some(error) => vec![(
+                        self.define(SymbolKind::Error, error, contract, None),
+                        ReferenceKind::Revert,
+                    )],
+                    None => Vec::new(),
+                }
+            }
+            Instruction::StorageLoad { key, .. } => {
+                self.slot_references(contract, storage_key_slot(key), ReferenceKind::Read)
+            }
+            Instruction::StorageStore { key, .. } | Instruction::StorageDelete { key } => {
+                self.slot_references(contract, storage_key_slot(key), ReferenceKind::Write)
+            }
+            Instruction::MappingLoad { mapping: base, .. }
+            | Instruction::ArrayLoad { array: base, .. }
+            | Instruction::ArrayLength { array: base, .. } => {
+                self.slot_references(contract, constant_slot(base), ReferenceKind::Read)
+            }
+            Instruction::MappingStore { mapping: base, .. }
+            | Instruction::ArrayStore { array: base, .. }
+            | Instruction::ArrayPush { array: base, .. }
+            | Instruction::ArrayPop { array: base, .. } => {
+                self.slot_references(contract, constant_slot(base), ReferenceKind::Write)
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/* `Unauthorized()` or `InsufficientBalance(amount)` names an error; a plain string reason does not. */
+fn error_name(reason: &str) -> Option<&str> {
+    let (name, rest) = reason.split_once('(')?;
+    let is_identifier = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    (is_identifier && rest.ends_with(')')).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
+
+    #[test]
+    fn test_references_resolve_across_contracts() {
+        let mut builder = IRBuilder::new();
+
+        let mut base = builder.contract("Ownable");
+        base.state_variable("owner", Type::Address, 0);
+        let mut check = base.function("_checkOwner");
+        let mut entry = check.entry_block();
+        let owner = entry.storage_load(0u32.into());
+        let sender = entry.msg_sender();
+        let is_owner = entry.eq(sender, owner);
+        entry.require(is_owner, "Unauthorized()");
+        entry.return_void().unwrap();
+        check.build().unwrap();
+        let ownable = base.build().unwrap();
+
+        let mut derived = builder.contract("Vault");
+        derived.state_variable("owner", Type::Address, 0);
+        let mut sweep = derived.function("sweep");
+        let mut entry = sweep.entry_block();
+        entry.call_internal("_checkOwner", Vec::new());
+        let zero = entry.constant_uint(0, 160);
+        entry.storage_store(0u32.into(), zero);
+        let yes = entry.constant_bool(true);
+        entry.require(yes, "not owner");
+        entry.return_void().unwrap();
+        sweep.build().unwrap();
+        let vault = derived.build().unwrap();
+
+        let index = SymbolIndex::new(&[ownable, vault]);
+
+        let owners = index.lookup("owner");
+        assert_eq!(owners.len(), 2);
+        let vault_owner = index.lookup("Vault.owner");
+        assert_eq!(vault_owner.len(), 1);
+        assert_eq!(vault_owner[0].references.len(), 1);
+        assert_eq!(vault_owner[0].references[0].kind, ReferenceKind::Write);
+        assert_eq!(
+            index.lookup("Ownable.owner")[0].references[0].function,
+            "_checkOwner"
+        );
+
+        let check = index.definition("Vault", "_checkOwner").unwrap();
+        assert_eq!(check.contract, "Ownable");
+        assert_eq!(check.references.len(), 1);
+        assert_eq!(check.references[0].contract, "Vault");
+        assert_eq!(check.references[0].location.index, 0);
+
+        let error = &index.lookup("Unauthorized")[0];
+        assert_eq!(error.kind, SymbolKind::Error);
+        assert_eq!(error.references.len(), 1);
+        assert!(index.lookup("not owner").is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::analysis::annotations::AnnotationStore;
 use crate::instructions::Instruction;
 use crate::types::Type;
 use crate::values::{SourceLocation, Value};
@@ -115,6 +116,18 @@ pub enum Terminator {
 }
 
 impl Terminator {
+    pub fn opcode_name(&self) -> &'static str {
+        match self {
+            Terminator::Jump(..) => "Jump",
+            Terminator::Branch { .. } => "Branch",
+            Terminator::Switch { .. } => "Switch",
+            Terminator::Return(_) => "Return",
+            Terminator::Revert(_) => "Revert",
+            Terminator::Panic(_) => "Panic",
+            Terminator::Invalid => "Invalid",
+        }
+    }
+
     pub fn successors(&self) -> Vec<BlockId> {
         match self {
             Terminator::Jump(target, _) => vec![*target],
@@ -135,6 +148,52 @@ impl Terminator {
         }
     }
 
+    /* The values the terminator passes on or branches on, the same way `Instruction::operands`
+     * lists an instruction's. */
+    pub fn operands(&self) -> Vec<&Value> {
+        match self {
+            Terminator::Jump(_, args) => args.iter().collect(),
+            Terminator::Branch {
+                condition,
+                then_args,
+                else_args,
+                ..
+            } => std::iter::once(condition)
+                .chain(then_args)
+                .chain(else_args)
+                .collect(),
+            Terminator::Switch { value, cases, .. } => std::iter::once(value)
+                .chain(cases.iter().map(|(case, _)| case))
+                .collect(),
+            Terminator::Return(value) => value.iter().collect(),
+            Terminator::Revert(_) | Terminator::Panic(_) | Terminator::Invalid => vec![],
+        }
+    }
+
+    pub fn operands_mut(&mut self) -> Vec<&mut Value> {
+        match self {
+            Terminator::Jump(_, args) => args.iter_mut().collect(),
+            Terminator::Branch {
+                condition,
+                then_args,
+                else_args,
+                ..
+            } => std::iter::once(condition)
+                .chain(then_args)
+                .chain(else_args)
+                .collect(),
+            Terminator::Switch { value, cases, .. } => std::iter::once(value)
+                .chain(cases.iter_mut().map(|(case, _)| case))
+                .collect(),
+            Terminator::Return(value) => value.iter_mut().collect(),
+            Terminator::Revert(_) | Terminator::Panic(_) | Terminator::Invalid => vec![],
+        }
+    }
+
+    pub fn values(&self) -> Vec<&Value> {
+        self.operands()
+    }
+
     pub fn is_return(&self) -> bool {
         matches!(self, Terminator::Return(_))
     }
@@ -153,6 +212,10 @@ pub struct BlockMetadata {
     pub dominators: Vec<BlockId>,
     pub is_reachable: bool,
     pub instruction_locations: HashMap<usize, SourceLocation>,
+    #[serde(skip)]
+    pub annotations: AnnotationStore,
+    #[serde(default)]
+    pub stable_id: Option<String>,
 }
 
 impl BlockMetadata {
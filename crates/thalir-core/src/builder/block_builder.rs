@@ -1,13 +1,14 @@
 use super::{
     inst_builder::{InstBuilder, InstBuilderBase, InstBuilderExt},
+    type_check::TypeMismatch,
     IRContext, IRRegistry,
 };
 use crate::{
-    block::{BasicBlock, BlockId, Terminator},
+    block::{BasicBlock, BlockId, BlockParam, Terminator},
     contract::EventId,
-    instructions::{CallTarget, ContextVariable, Instruction, StorageKey},
+    instructions::{BuiltinFunction, CallTarget, ContextVariable, Instruction, StorageKey},
     types::Type,
-    values::{Constant, SourceLocation, Value},
+    values::{BlockParamId, Constant, Location, SourceLocation, Value},
     Result,
 };
 use num_bigint::{BigInt, BigUint};
@@ -16,12 +17,14 @@ use std::collections::HashMap;
 pub struct BlockBuilder<'a> {
     pub block_id: BlockId,
     function_name: String,
+    params: Vec<BlockParam>,
     instructions: Vec<Instruction>,
     context: &'a mut IRContext,
     registry: &'a mut IRRegistry,
     is_sealed: bool,
     current_source_location: Option<SourceLocation>,
     instruction_locations: HashMap<usize, SourceLocation>,
+    type_error: Option<crate::IrError>,
 }
 
 impl<'a> BlockBuilder<'a> {
@@ -34,12 +37,14 @@ impl<'a> BlockBuilder<'a> {
         Self {
             block_id,
             function_name,
+            params: Vec::new(),
             instructions: Vec::new(),
             context,
             registry,
             is_sealed: false,
             current_source_location: None,
             instruction_locations: HashMap::new(),
+            type_error: None,
         }
     }
 
@@ -59,6 +64,18 @@ impl<'a> BlockBuilder<'a> {
     }
 
     fn push_instruction(&mut self, inst: Instruction) {
+        if self.context.strict_types() && self.type_error.is_none() {
+            if let Err(mismatch) = self.context.type_table().check(&inst) {
+                let instruction = format!(
+                    "{} {}[{}] {}",
+                    self.function_name,
+                    self.block_id,
+                    self.instructions.len(),
+                    inst.opcode_name()
+                );
+                self.type_error = Some(type_error(instruction, mismatch));
+            }
+        }
         self.record_instruction_location();
         self.instructions.push(inst);
     }
@@ -67,6 +84,18 @@ impl<'a> BlockBuilder<'a> {
         self.block_id
     }
 
+    /* Declares the next block parameter. Predecessors pass its value as the matching argument of
+     * the jump or branch that reaches this block. */
+    pub fn param(&mut self, name: &str, ty: Type) -> Value {
+        let value = Value::BlockParam(BlockParamId {
+            block: self.block_id,
+            index: self.params.len() as u32,
+        });
+        self.context.type_table().insert(value.clone(), ty.clone());
+        self.params.push(BlockParam::new(name, ty));
+        value
+    }
+
     pub fn add(&mut self, left: Value, right: Value, ty: Type) -> Value {
         let result = self.new_temp();
         self.push_instruction(Instruction::Add {
@@ -122,6 +151,28 @@ impl<'a> BlockBuilder<'a> {
         result
     }
 
+    pub fn sdiv(&mut self, left: Value, right: Value, ty: Type) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Sdiv {
+            result: result.clone(),
+            left,
+            right,
+            ty,
+        });
+        result
+    }
+
+    pub fn smod(&mut self, left: Value, right: Value, ty: Type) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Smod {
+            result: result.clone(),
+            left,
+            right,
+            ty,
+        });
+        result
+    }
+
     pub fn pow(&mut self, base: Value, exp: Value) -> Value {
         let result = self.new_temp();
         self.push_instruction(Instruction::Pow {
@@ -294,6 +345,46 @@ impl<'a> BlockBuilder<'a> {
         result
     }
 
+    pub fn slt(&mut self, left: Value, right: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Slt {
+            result: result.clone(),
+            left,
+            right,
+        });
+        result
+    }
+
+    pub fn sgt(&mut self, left: Value, right: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Sgt {
+            result: result.clone(),
+            left,
+            right,
+        });
+        result
+    }
+
+    pub fn sle(&mut self, left: Value, right: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Sle {
+            result: result.clone(),
+            left,
+            right,
+        });
+        result
+    }
+
+    pub fn sge(&mut self, left: Value, right: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Sge {
+            result: result.clone(),
+            left,
+            right,
+        });
+        result
+    }
+
     pub fn select(&mut self, condition: Value, then_val: Value, else_val: Value) -> Value {
         let result = self.new_temp();
         self.push_instruction(Instruction::Select {
@@ -524,6 +615,10 @@ impl<'a> BlockBuilder<'a> {
         self.seal_with_terminator(Terminator::Jump(target, Vec::new()))
     }
 
+    pub fn jump_with_args(&mut self, target: BlockId, args: Vec<Value>) -> Result<()> {
+        self.seal_with_terminator(Terminator::Jump(target, args))
+    }
+
     pub fn branch(
         &mut self,
         condition: Value,
@@ -539,6 +634,23 @@ impl<'a> BlockBuilder<'a> {
         })
     }
 
+    pub fn branch_with_args(
+        &mut self,
+        condition: Value,
+        then_block: BlockId,
+        then_args: Vec<Value>,
+        else_block: BlockId,
+        else_args: Vec<Value>,
+    ) -> Result<()> {
+        self.seal_with_terminator(Terminator::Branch {
+            condition,
+            then_block,
+            then_args,
+            else_block,
+            else_args,
+        })
+    }
+
     pub fn return_value(&mut self, value: Value) -> Result<()> {
         self.seal_with_terminator(Terminator::Return(Some(value)))
     }
@@ -562,8 +674,18 @@ impl<'a> BlockBuilder<'a> {
                 self.block_id
             )));
         }
+        if let Some(error) = self.type_error.take() {
+            return Err(error);
+        }
+        if self.context.strict_types() {
+            if let Err(mismatch) = self.context.type_table().check_terminator(&terminator) {
+                let instruction = format!("{} {} terminator", self.function_name, self.block_id);
+                return Err(type_error(instruction, mismatch));
+            }
+        }
 
         let mut block = BasicBlock::new(self.block_id);
+        block.params = self.params.clone();
         block.instructions = self.instructions.clone();
         block.terminator = terminator;
 
@@ -596,6 +718,14 @@ impl<'a> BlockBuilder<'a> {
     }
 }
 
+fn type_error(instruction: String, mismatch: TypeMismatch) -> crate::IrError {
+    crate::IrError::TypeError {
+        instruction,
+        expected: mismatch.expected,
+        found: mismatch.found.to_string(),
+    }
+}
+
 impl<'a> InstBuilderBase<'a> for BlockBuilder<'a> {
     fn new_temp(&mut self) -> Value {
         let temp_id = self.context.ssa().new_temp();
@@ -643,6 +773,26 @@ impl<'a> InstBuilderExt<'a> for BlockBuilder<'a> {
         self.push_instruction(Instruction::StorageStore { key, value });
     }
 
+    fn storage_delete(&mut self, slot: BigUint) {
+        let key = StorageKey::Slot(slot);
+        self.push_instruction(Instruction::StorageDelete { key });
+    }
+
+    fn transient_load(&mut self, slot: BigUint) -> Value {
+        let result = self.new_temp();
+        let key = StorageKey::Slot(slot);
+        self.push_instruction(Instruction::TransientLoad {
+            result: result.clone(),
+            key,
+        });
+        result
+    }
+
+    fn transient_store(&mut self, slot: BigUint, value: Value) {
+        let key = StorageKey::Slot(slot);
+        self.push_instruction(Instruction::TransientStore { key, value });
+    }
+
     fn mapping_load(&mut self, mapping: Value, key: Value) -> Value {
         let result = self.new_temp();
         self.push_instruction(Instruction::MappingLoad {
@@ -661,6 +811,17 @@ impl<'a> InstBuilderExt<'a> for BlockBuilder<'a> {
         });
     }
 
+    fn mapping_slot(&mut self, mapping: Value, key: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Call {
+            result: result.clone(),
+            target: CallTarget::Builtin(BuiltinFunction::MappingSlot),
+            args: vec![mapping, key],
+            value: None,
+        });
+        result
+    }
+
     fn array_load(&mut self, array: Value, index: Value) -> Value {
         let result = self.new_temp();
         self.push_instruction(Instruction::ArrayLoad {
@@ -755,6 +916,15 @@ impl<'a> InstBuilderExt<'a> for BlockBuilder<'a> {
         result
     }
 
+    fn block_prevrandao(&mut self) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::GetContext {
+            result: result.clone(),
+            var: ContextVariable::PrevRandao,
+        });
+        result
+    }
+
     fn block_gaslimit(&mut self) -> Value {
         let result = self.new_temp();
         self.push_instruction(Instruction::GetContext {
@@ -827,6 +997,92 @@ impl<'a> InstBuilderExt<'a> for BlockBuilder<'a> {
         result
     }
 
+    fn block_blobbasefee(&mut self) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::GetContext {
+            result: result.clone(),
+            var: ContextVariable::BlobBaseFee,
+        });
+        result
+    }
+
+    /* Takes an index like `blockhash`, so it is a builtin call rather than a context read. */
+    fn blob_hash(&mut self, index: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Call {
+            result: result.clone(),
+            target: CallTarget::Builtin(BuiltinFunction::BlobHash),
+            args: vec![index],
+            value: None,
+        });
+        result
+    }
+
+    fn block_hash(&mut self, number: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Call {
+            result: result.clone(),
+            target: CallTarget::Builtin(BuiltinFunction::BlockHash),
+            args: vec![number],
+            value: None,
+        });
+        result
+    }
+
+    fn this_address(&mut self) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::GetContext {
+            result: result.clone(),
+            var: ContextVariable::ThisAddress,
+        });
+        result
+    }
+
+    fn self_balance(&mut self) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::GetContext {
+            result: result.clone(),
+            var: ContextVariable::ThisBalance,
+        });
+        result
+    }
+
+    fn balance(&mut self, address: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::GetBalance {
+            result: result.clone(),
+            address,
+        });
+        result
+    }
+
+    fn code(&mut self, address: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::GetCode {
+            result: result.clone(),
+            address,
+        });
+        result
+    }
+
+    fn code_size(&mut self, address: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::GetCodeSize {
+            result: result.clone(),
+            address,
+        });
+        result
+    }
+
+    fn code_hash(&mut self, address: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::GetCodeHash {
+            result: result.clone(),
+            address,
+        });
+        result
+    }
+
     fn call_internal(&mut self, name: &str, args: Vec<Value>) -> Value {
         let result = self.new_temp();
         self.push_instruction(Instruction::Call {
@@ -870,6 +1126,31 @@ impl<'a> InstBuilderExt<'a> for BlockBuilder<'a> {
         result
     }
 
+    fn create(&mut self, code: Value, value: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Create {
+            result: result.clone(),
+            code,
+            value,
+        });
+        result
+    }
+
+    fn create2(&mut self, code: Value, salt: Value, value: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Create2 {
+            result: result.clone(),
+            code,
+            salt,
+            value,
+        });
+        result
+    }
+
+    fn selfdestruct(&mut self, beneficiary: Value) {
+        self.push_instruction(Instruction::Selfdestruct { beneficiary });
+    }
+
     fn emit_event(&mut self, event: EventId, topics: Vec<Value>, data: Vec<Value>) {
         self.push_instruction(Instruction::EmitEvent {
             event,
@@ -1005,6 +1286,23 @@ impl<'a> InstBuilderExt<'a> for BlockBuilder<'a> {
         result
     }
 
+    fn load(&mut self, location: Location) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Load {
+            result: result.clone(),
+            location,
+        });
+        result
+    }
+
+    fn store(&mut self, location: Location, value: Value) {
+        self.push_instruction(Instruction::Store { location, value });
+    }
+
+    fn copy(&mut self, dest: Location, src: Location, size: Value) {
+        self.push_instruction(Instruction::Copy { dest, src, size });
+    }
+
     fn cast(&mut self, value: Value, to: Type) -> Value {
         let result = self.new_temp();
         self.push_instruction(Instruction::Cast {
@@ -1102,6 +1400,28 @@ impl<'a> InstBuilder<'a> for BlockBuilder<'a> {
         result
     }
 
+    fn sdiv(&mut self, left: Value, right: Value, ty: Type) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Sdiv {
+            result: result.clone(),
+            left,
+            right,
+            ty,
+        });
+        result
+    }
+
+    fn smod(&mut self, left: Value, right: Value, ty: Type) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Smod {
+            result: result.clone(),
+            left,
+            right,
+            ty,
+        });
+        result
+    }
+
     fn pow(&mut self, base: Value, exp: Value) -> Value {
         let result = self.new_temp();
         self.push_instruction(Instruction::Pow {
@@ -1241,6 +1561,46 @@ impl<'a> InstBuilder<'a> for BlockBuilder<'a> {
         result
     }
 
+    fn slt(&mut self, left: Value, right: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Slt {
+            result: result.clone(),
+            left,
+            right,
+        });
+        result
+    }
+
+    fn sgt(&mut self, left: Value, right: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Sgt {
+            result: result.clone(),
+            left,
+            right,
+        });
+        result
+    }
+
+    fn sle(&mut self, left: Value, right: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Sle {
+            result: result.clone(),
+            left,
+            right,
+        });
+        result
+    }
+
+    fn sge(&mut self, left: Value, right: Value) -> Value {
+        let result = self.new_temp();
+        self.push_instruction(Instruction::Sge {
+            result: result.clone(),
+            left,
+            right,
+        });
+        result
+    }
+
     fn jump(&mut self, target: BlockId, args: Vec<Value>) {
         self.push_instruction(Instruction::Jump { target, args });
     }
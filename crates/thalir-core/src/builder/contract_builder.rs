@@ -1,9 +1,15 @@
 use super::{FunctionBuilder, IRContext, IRRegistry};
 use crate::{
-    contract::{Contract, EventDefinition, EventId},
-    types::Type,
+    contract::{
+        ConstantDefinition, Contract, EventDefinition, EventId, StorageLayout, StorageSlot,
+    },
+    metadata::NatSpec,
+    types::{Type, TypeRegistry},
+    values::{Constant, SourceLocation},
+    version::{EvmVersion, VersionPragma},
     Result,
 };
+use num_bigint::BigUint;
 
 pub struct ContractBuilder<'a> {
     contract_name: String,
@@ -43,6 +49,50 @@ impl<'a> ContractBuilder<'a> {
         self
     }
 
+    pub fn state_variable_at(
+        &mut self,
+        name: &str,
+        ty: Type,
+        slot: u32,
+        location: SourceLocation,
+    ) -> &mut Self {
+        if let Some(contract) = self.registry.get_contract_mut(&self.contract_name) {
+            contract
+                .storage_layout
+                .add_variable(name.to_string(), ty, slot);
+            if let Some(var) = contract.storage_layout.slots.last_mut() {
+                var.location = Some(location);
+            }
+        }
+        self
+    }
+
+    /* Structs, enums and contract interfaces shared by every contract the builder makes. */
+    pub fn types(&mut self) -> &mut TypeRegistry {
+        self.context.types()
+    }
+
+    /* Lets the layout pick the slot and offset, packing small value types the way solc does. */
+    pub fn allocate_state_variable(
+        &mut self,
+        name: &str,
+        ty: Type,
+        location: Option<SourceLocation>,
+    ) -> (BigUint, u8) {
+        let types = self.context.types();
+        match self.registry.get_contract_mut(&self.contract_name) {
+            Some(contract) => {
+                let layout = &mut contract.storage_layout;
+                let position = layout.allocate(name.to_string(), ty, types);
+                if let Some(var) = layout.slots.last_mut() {
+                    var.location = location;
+                }
+                position
+            }
+            None => (BigUint::from(0u32), 0),
+        }
+    }
+
     pub fn event(&mut self, name: &str) -> EventBuilder {
         let event_id = EventId(self.context.next_id() as u32);
         let event_builder = EventBuilder {
@@ -69,6 +119,98 @@ impl<'a> ContractBuilder<'a> {
         self
     }
 
+    pub fn natspec(&mut self, natspec: NatSpec) -> &mut Self {
+        if let Some(contract) = self.registry.get_contract_mut(&self.contract_name) {
+            contract.metadata.natspec = Some(natspec);
+        }
+        self
+    }
+
+    pub fn pragma(&mut self, pragma: VersionPragma) -> &mut Self {
+        if let Some(contract) = self.registry.get_contract_mut(&self.contract_name) {
+            contract.metadata.pragma = Some(pragma);
+        }
+        self
+    }
+
+    pub fn evm_version(&mut self, evm_version: EvmVersion) -> &mut Self {
+        if let Some(contract) = self.registry.get_contract_mut(&self.contract_name) {
+            contract.metadata.evm_version = Some(evm_version);
+        }
+        self
+    }
+
+    pub fn constant(&mut self, name: &str, ty: Type, value: Constant) -> &mut Self {
+        if let Some(contract) = self.registry.get_contract_mut(&self.contract_name) {
+            contract.constants.push(ConstantDefinition {
+                name: name.to_string(),
+                const_type: ty,
+                value,
+            });
+        }
+        self
+    }
+
+    /* Marks the most recently declared state variable called `name` as private. */
+    pub fn private_state_variable(&mut self, name: &str) -> &mut Self {
+        if let Some(contract) = self.registry.get_contract_mut(&self.contract_name) {
+            if let Some(var) = contract
+                .storage_layout
+                .slots
+                .iter_mut()
+                .rev()
+                .find(|var| var.name == name)
+            {
+                var.is_private = true;
+            }
+        }
+        self
+    }
+
+    /* Bases must be registered before the state variables are allocated: own variables are laid
+     * out after the linearized bases' ones. */
+    pub fn inherits(&mut self, base: &str) -> &mut Self {
+        if let Some(contract) = self.registry.get_contract_mut(&self.contract_name) {
+            contract.metadata.bases.push(base.to_string());
+        }
+        let inherited = self.inherited_layout();
+        if let Some(contract) = self.registry.get_contract_mut(&self.contract_name) {
+            contract.storage_layout.inherited = inherited;
+        }
+        self
+    }
+
+    /* The contract and its registered bases, most derived first. */
+    pub fn linearized(&self) -> Vec<String> {
+        self.registry.linearize(&self.contract_name)
+    }
+
+    fn inherited_layout(&mut self) -> Vec<StorageSlot> {
+        let linearized = self.registry.linearize(&self.contract_name);
+        let types = self.context.types();
+        let mut layout = StorageLayout::default();
+        for base in linearized.iter().skip(1).rev() {
+            let Some(base) = self.registry.get_contract(base) else {
+                continue;
+            };
+            for var in &base.storage_layout.slots {
+                layout.allocate(var.name.clone(), var.var_type.clone(), types);
+                if let Some(placed) = layout.slots.last_mut() {
+                    placed.location = var.location.clone();
+                    placed.is_private = var.is_private;
+                }
+            }
+        }
+        layout.slots
+    }
+
+    pub fn location(&mut self, location: SourceLocation) -> &mut Self {
+        if let Some(contract) = self.registry.get_contract_mut(&self.contract_name) {
+            contract.metadata.location = Some(location);
+        }
+        self
+    }
+
     pub fn build(self) -> Result<Contract> {
         self.registry
             .get_contract(&self.contract_name)
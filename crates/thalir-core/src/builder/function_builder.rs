@@ -1,7 +1,9 @@
 use super::{BlockBuilder, IRContext, IRRegistry};
 use crate::{
     block::BlockId,
+    contract::{ModifierBody, ModifierDefinition, ModifierId, ModifierRef},
     function::{Function, FunctionSignature, Mutability, Parameter, Visibility},
+    metadata::NatSpec,
     types::Type,
     values::{ParamId, Value},
     Result,
@@ -31,6 +33,7 @@ impl<'a> FunctionBuilder<'a> {
         };
 
         let function = Function::new(signature);
+        context.type_table().set_params(Vec::new());
 
         Self {
             contract_name,
@@ -43,6 +46,10 @@ impl<'a> FunctionBuilder<'a> {
     }
 
     pub fn param(&mut self, name: &str, ty: Type) -> &mut Self {
+        let index = self.function.signature.params.len() as u32;
+        self.context
+            .type_table()
+            .insert(Value::Param(ParamId(index)), ty.clone());
         self.function
             .signature
             .params
@@ -74,9 +81,53 @@ impl<'a> FunctionBuilder<'a> {
         self
     }
 
-    pub fn modifier(&mut self, _name: &str) -> &mut Self {
-        self.function.modifiers.push(crate::contract::ModifierRef {
-            id: crate::contract::ModifierId(0),
+    pub fn natspec(&mut self, natspec: NatSpec) -> &mut Self {
+        self.function.metadata.natspec = Some(natspec);
+        self
+    }
+
+    pub fn selector(&mut self, selector: u32) -> &mut Self {
+        self.function.metadata.selector = Some(selector);
+        self
+    }
+
+    pub fn constructor(&mut self) -> &mut Self {
+        self.function.metadata.is_constructor = true;
+        self
+    }
+
+    pub fn fallback(&mut self) -> &mut Self {
+        self.function.metadata.is_fallback = true;
+        self
+    }
+
+    pub fn receive(&mut self) -> &mut Self {
+        self.function.metadata.is_receive = true;
+        self.function.mutability = Mutability::Payable;
+        self
+    }
+
+    /* Modifiers are known by name only; the first function to use one declares it on the
+     * contract, so `Contract::guards` can name it. */
+    pub fn modifier(&mut self, name: &str) -> &mut Self {
+        let Some(contract) = self.registry.get_contract_mut(&self.contract_name) else {
+            return self;
+        };
+        let id = match contract.modifiers.iter().find(|m| m.name == name) {
+            Some(definition) => definition.id,
+            None => {
+                let id = ModifierId(contract.modifiers.len() as u32);
+                contract.modifiers.push(ModifierDefinition {
+                    id,
+                    name: name.to_string(),
+                    parameters: Vec::new(),
+                    body: ModifierBody {},
+                });
+                id
+            }
+        };
+        self.function.modifiers.push(ModifierRef {
+            id,
             arguments: Vec::new(),
         });
         self
@@ -171,7 +222,8 @@ impl<'a> FunctionBuilder<'a> {
         &self.function
     }
 
-    pub fn build(self) -> Result<Function> {
+    pub fn build(mut self) -> Result<Function> {
+        crate::stable_id::assign(&self.contract_name, &mut self.function);
         let qualified_name = format!("{}::{}", self.contract_name, self.function.signature.name);
         self.registry
             .add_function(self.contract_name.clone(), self.function)?;
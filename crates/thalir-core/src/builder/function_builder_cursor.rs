@@ -331,6 +331,25 @@ impl<'a> FunctionInstBuilder<'a> {
         Ok(())
     }
 
+    pub fn tload(&mut self, key: Value) -> Value {
+        let result = self.next_value();
+        let inst = Instruction::TransientLoad {
+            result: result.clone(),
+            key: StorageKey::Dynamic(key),
+        };
+        self.insert_inst(inst);
+        result
+    }
+
+    pub fn tstore(&mut self, key: Value, value: Value) -> Result<()> {
+        let inst = Instruction::TransientStore {
+            key: StorageKey::Dynamic(key),
+            value,
+        };
+        self.insert_inst(inst);
+        Ok(())
+    }
+
     pub fn branch(
         &mut self,
         condition: Value,
@@ -1,4 +1,9 @@
-use crate::{block::BlockId, contract::EventId, types::Type, values::Value};
+use crate::{
+    block::BlockId,
+    contract::EventId,
+    types::Type,
+    values::{Location, Value},
+};
 use num_bigint::BigUint;
 
 pub trait InstBuilderBase<'f>: Sized {
@@ -18,10 +23,18 @@ pub trait InstBuilderExt<'f>: InstBuilderBase<'f> {
 
     fn storage_store_dynamic(&mut self, slot: Value, value: Value);
 
+    fn storage_delete(&mut self, slot: BigUint);
+
+    fn transient_load(&mut self, slot: BigUint) -> Value;
+
+    fn transient_store(&mut self, slot: BigUint, value: Value);
+
     fn mapping_load(&mut self, mapping: Value, key: Value) -> Value;
 
     fn mapping_store(&mut self, mapping: Value, key: Value, value: Value);
 
+    fn mapping_slot(&mut self, mapping: Value, key: Value) -> Value;
+
     fn array_load(&mut self, array: Value, index: Value) -> Value;
 
     fn array_store(&mut self, array: Value, index: Value, value: Value);
@@ -44,6 +57,8 @@ pub trait InstBuilderExt<'f>: InstBuilderBase<'f> {
 
     fn block_difficulty(&mut self) -> Value;
 
+    fn block_prevrandao(&mut self) -> Value;
+
     fn block_gaslimit(&mut self) -> Value;
 
     fn block_coinbase(&mut self) -> Value;
@@ -58,6 +73,24 @@ pub trait InstBuilderExt<'f>: InstBuilderBase<'f> {
 
     fn block_basefee(&mut self) -> Value;
 
+    fn block_blobbasefee(&mut self) -> Value;
+
+    fn blob_hash(&mut self, index: Value) -> Value;
+
+    fn block_hash(&mut self, number: Value) -> Value;
+
+    fn this_address(&mut self) -> Value;
+
+    fn self_balance(&mut self) -> Value;
+
+    fn balance(&mut self, address: Value) -> Value;
+
+    fn code(&mut self, address: Value) -> Value;
+
+    fn code_size(&mut self, address: Value) -> Value;
+
+    fn code_hash(&mut self, address: Value) -> Value;
+
     fn gas_left(&mut self) -> Value;
 
     fn call_internal(&mut self, name: &str, args: Vec<Value>) -> Value;
@@ -74,6 +107,12 @@ pub trait InstBuilderExt<'f>: InstBuilderBase<'f> {
 
     fn static_call(&mut self, target: Value, selector: Value, args: Vec<Value>) -> Value;
 
+    fn create(&mut self, code: Value, value: Value) -> Value;
+
+    fn create2(&mut self, code: Value, salt: Value, value: Value) -> Value;
+
+    fn selfdestruct(&mut self, beneficiary: Value);
+
     fn emit_event(&mut self, event: EventId, topics: Vec<Value>, data: Vec<Value>);
 
     fn keccak256(&mut self, data: Value, len: Value) -> Value;
@@ -104,6 +143,12 @@ pub trait InstBuilderExt<'f>: InstBuilderBase<'f> {
 
     fn memory_size(&mut self) -> Value;
 
+    fn load(&mut self, location: Location) -> Value;
+
+    fn store(&mut self, location: Location, value: Value);
+
+    fn copy(&mut self, dest: Location, src: Location, size: Value);
+
     fn cast(&mut self, value: Value, to: Type) -> Value;
 
     fn zext(&mut self, value: Value, to: Type) -> Value;
@@ -124,6 +169,10 @@ pub trait InstBuilder<'f>: InstBuilderBase<'f> {
 
     fn mod_(&mut self, left: Value, right: Value, ty: Type) -> Value;
 
+    fn sdiv(&mut self, left: Value, right: Value, ty: Type) -> Value;
+
+    fn smod(&mut self, left: Value, right: Value, ty: Type) -> Value;
+
     fn pow(&mut self, base: Value, exp: Value) -> Value;
 
     fn and(&mut self, left: Value, right: Value) -> Value;
@@ -152,6 +201,14 @@ pub trait InstBuilder<'f>: InstBuilderBase<'f> {
 
     fn ge(&mut self, left: Value, right: Value) -> Value;
 
+    fn slt(&mut self, left: Value, right: Value) -> Value;
+
+    fn sgt(&mut self, left: Value, right: Value) -> Value;
+
+    fn sle(&mut self, left: Value, right: Value) -> Value;
+
+    fn sge(&mut self, left: Value, right: Value) -> Value;
+
     fn jump(&mut self, target: BlockId, args: Vec<Value>);
 
     fn branch(
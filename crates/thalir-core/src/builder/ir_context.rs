@@ -1,3 +1,4 @@
+use super::type_check::TypeTable;
 use crate::{
     block::BlockId,
     types::TypeRegistry,
@@ -64,6 +65,8 @@ pub struct IRContext {
     current_function: Option<String>,
     current_block: Option<BlockId>,
     errors: Vec<String>,
+    strict_types: bool,
+    type_table: TypeTable,
 }
 
 impl IRContext {
@@ -77,6 +80,8 @@ impl IRContext {
             current_function: None,
             current_block: None,
             errors: Vec::new(),
+            strict_types: false,
+            type_table: TypeTable::new(),
         }
     }
 
@@ -130,6 +135,20 @@ impl IRContext {
         &mut self.type_registry
     }
 
+    /* In strict mode block builders check operand types as instructions are added and fail the
+     * block when it is sealed. */
+    pub fn set_strict_types(&mut self, strict: bool) {
+        self.strict_types = strict;
+    }
+
+    pub fn strict_types(&self) -> bool {
+        self.strict_types
+    }
+
+    pub fn type_table(&mut self) -> &mut TypeTable {
+        &mut self.type_table
+    }
+
     pub fn add_error(&mut self, error: String) {
         self.errors.push(error);
     }
@@ -150,5 +169,6 @@ impl IRContext {
         self.current_function = None;
         self.current_block = None;
         self.errors.clear();
+        self.type_table.clear();
     }
 }
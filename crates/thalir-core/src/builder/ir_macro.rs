@@ -0,0 +1,441 @@
+/*! `ir!`, a shorthand for writing functions in tests.
+ *
+ * ```
+ * use thalir_core::ir;
+ *
+ * let function = ir! {
+ *     pub fn withdraw(u256) -> u256;
+ *     block0: {
+ *         v0 = sload(0);
+ *         v1 = ge(v0, p0);
+ *         br v1, block1, block2
+ *     }
+ *     block1: {
+ *         v2 = sub(v0, p0, u256);
+ *         sstore(0, v2);
+ *         ret v2
+ *     }
+ *     block2: { revert "insufficient" }
+ * };
+ * assert_eq!(function.body.blocks.len(), 3);
+ * ```
+ *
+ * `pN` is parameter N, `vN` is temp N, and integers, `true` and `false` are constants. The
+ * signature line is optional; without it the function is `f` with no parameters, and `pub fn`
+ * makes it external. The first block is the entry. Arithmetic takes an optional type, `u256`
+ * by default. Mistakes panic with the offending statement, which is what a test wants.
+ */
+
+use crate::block::{BasicBlock, BlockId, Terminator};
+use crate::function::{Function, FunctionSignature, Parameter, Visibility};
+use crate::instructions::{CallTarget, ContextVariable, Instruction, StorageKey};
+use crate::types::Type;
+use crate::values::{Constant, ParamId, TempId, Value};
+use num_bigint::BigUint;
+
+#[macro_export]
+macro_rules! ir {
+    (@blocks $f:ident;) => {};
+    (@blocks $f:ident; $block:ident : { $($body:tt)* } $($rest:tt)*) => {
+        $f.block(stringify!($block));
+        $crate::ir!(@stmts $f; $($body)*);
+        $crate::ir!(@blocks $f; $($rest)*);
+    };
+
+    (@stmts $f:ident;) => {};
+    (@stmts $f:ident; $result:ident = $op:ident ( $($arg:tt),* $(,)? ) ; $($rest:tt)*) => {
+        $f.push(Some(stringify!($result)), stringify!($op), &[$(stringify!($arg)),*]);
+        $crate::ir!(@stmts $f; $($rest)*);
+    };
+    (@stmts $f:ident; $op:ident ( $($arg:tt),* $(,)? ) ; $($rest:tt)*) => {
+        $f.push(None, stringify!($op), &[$(stringify!($arg)),*]);
+        $crate::ir!(@stmts $f; $($rest)*);
+    };
+    (@stmts $f:ident; $term:ident $($arg:tt),* $(;)?) => {
+        $f.terminate(stringify!($term), &[$(stringify!($arg)),*]);
+    };
+
+    (pub fn $name:ident ( $($param:ident),* $(,)? ) $(-> $ret:ident)? ; $($blocks:tt)*) => {{
+        let mut f = $crate::builder::ir_macro::IrSketch::new(
+            stringify!($name),
+            &[$(stringify!($param)),*],
+            &[$(stringify!($ret))?],
+        );
+        f.external();
+        $crate::ir!(@blocks f; $($blocks)*);
+        f.finish()
+    }};
+    (fn $name:ident ( $($param:ident),* $(,)? ) $(-> $ret:ident)? ; $($blocks:tt)*) => {{
+        let mut f = $crate::builder::ir_macro::IrSketch::new(
+            stringify!($name),
+            &[$(stringify!($param)),*],
+            &[$(stringify!($ret))?],
+        );
+        $crate::ir!(@blocks f; $($blocks)*);
+        f.finish()
+    }};
+    ($($blocks:tt)*) => {{
+        let mut f = $crate::builder::ir_macro::IrSketch::new("f", &[], &[]);
+        $crate::ir!(@blocks f; $($blocks)*);
+        f.finish()
+    }};
+}
+
+/* What `ir!` expands to. The macro only splits statements; this reads them. */
+#[doc(hidden)]
+pub struct IrSketch {
+    function: Function,
+    current: Option<BasicBlock>,
+    entry: Option<BlockId>,
+}
+
+impl IrSketch {
+    pub fn new(name: &str, params: &[&str], returns: &[&str]) -> Self {
+        let function = Function::new(FunctionSignature {
+            name: name.to_string(),
+            params: params
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| Parameter::new(format!("p{}", i), ty_of(ty)))
+                .collect(),
+            returns: returns.iter().map(|ty| ty_of(ty)).collect(),
+            is_payable: false,
+        });
+        Self {
+            function,
+            current: None,
+            entry: None,
+        }
+    }
+
+    pub fn external(&mut self) {
+        self.function.visibility = Visibility::External;
+    }
+
+    pub fn block(&mut self, name: &str) {
+        self.close();
+        let id = block_of(name);
+        self.entry.get_or_insert(id);
+        self.current = Some(BasicBlock::new(id));
+    }
+
+    pub fn push(&mut self, result: Option<&str>, op: &str, args: &[&str]) {
+        let result = result.map(value_of).unwrap_or(Value::Undefined);
+        let inst = instruction(result, op, args)
+            .unwrap_or_else(|| panic!("ir!: cannot read `{}({})`", op, args.join(", ")));
+        self.block_mut().instructions.push(inst);
+    }
+
+    pub fn terminate(&mut self, term: &str, args: &[&str]) {
+        let terminator = match (term, args) {
+            ("ret", []) => Some(Terminator::Return(None)),
+            ("ret", [value]) => Some(Terminator::Return(Some(value_of(value)))),
+            ("jump", [target]) => Some(Terminator::Jump(block_of(target), Vec::new())),
+            ("br", [condition, then_block, else_block]) => Some(Terminator::Branch {
+                condition: value_of(condition),
+                then_block: block_of(then_block),
+                then_args: Vec::new(),
+                else_block: block_of(else_block),
+                else_args: Vec::new(),
+            }),
+            ("revert", [message]) => Some(Terminator::Revert(unquote(message))),
+            _ => None,
+        };
+        self.block_mut().terminator =
+            terminator.unwrap_or_else(|| panic!("ir!: cannot read `{} {}`", term, args.join(", ")));
+    }
+
+    pub fn finish(mut self) -> Function {
+        self.close();
+        if let Some(entry) = self.entry {
+            self.function.body.entry_block = entry;
+        }
+        self.function.analyze_metadata();
+        self.function
+    }
+
+    fn block_mut(&mut self) -> &mut BasicBlock {
+        self.current
+            .as_mut()
+            .expect("ir!: statements must sit inside a `blockN: { ... }`")
+    }
+
+    fn close(&mut self) {
+        if let Some(block) = self.current.take() {
+            if self.entry == Some(block.id) {
+                self.function.body.blocks.clear();
+            }
+            self.function.body.insert_block(block);
+        }
+    }
+}
+
+fn instruction(result: Value, op: &str, args: &[&str]) -> Option<Instruction> {
+    let arg = |i: usize| args.get(i).map(|a| value_of(a));
+    let ty = |i: usize| args.get(i).map_or(Type::Uint(256), |a| ty_of(a));
+    let (left, right) = (arg(0), arg(1));
+
+    /* Two operands and a result, with or without a type. */
+    macro_rules! binary {
+        ($($name:literal => $variant:ident $({ $ty:ident })?),* $(,)?) => {
+            match op {
+                $($name => {
+                    return Some(Instruction::$variant {
+                        result,
+                        left: left?,
+                        right: right?,
+                        $($ty: ty(2),)?
+                    })
+                })*
+                _ => {}
+            }
+        };
+    }
+    binary! {
+        "add" => Add { ty }, "sub" => Sub { ty }, "mul" => Mul { ty }, "div" => Div { ty },
+        "mod" => Mod { ty }, "sdiv" => Sdiv { ty }, "smod" => Smod { ty },
+        "checked_add" => CheckedAdd { ty }, "checked_sub" => CheckedSub { ty },
+        "checked_mul" => CheckedMul { ty }, "checked_div" => CheckedDiv { ty },
+        "and" => And, "or" => Or, "xor" => Xor, "eq" => Eq, "ne" => Ne, "lt" => Lt, "gt" => Gt,
+        "le" => Le, "ge" => Ge, "slt" => Slt, "sgt" => Sgt, "sle" => Sle, "sge" => Sge,
+    }
+
+    let inst = match op {
+        "not" => Instruction::Not {
+            result,
+            operand: left?,
+        },
+        "shl" => Instruction::Shl {
+            result,
+            value: left?,
+            shift: right?,
+        },
+        "shr" => Instruction::Shr {
+            result,
+            value: left?,
+            shift: right?,
+        },
+        "sar" => Instruction::Sar {
+            result,
+            value: left?,
+            shift: right?,
+        },
+        "select" => Instruction::Select {
+            result,
+            condition: left?,
+            then_val: right?,
+            else_val: arg(2)?,
+        },
+        "cast" => Instruction::Cast {
+            result,
+            value: left?,
+            to: ty_of(args.get(1)?),
+        },
+        "copy" => Instruction::Assign {
+            result,
+            value: left?,
+        },
+        "sload" => Instruction::StorageLoad {
+            result,
+            key: key_of(left?),
+        },
+        "sstore" => Instruction::StorageStore {
+            key: key_of(left?),
+            value: right?,
+        },
+        "tload" => Instruction::TransientLoad {
+            result,
+            key: key_of(left?),
+        },
+        "tstore" => Instruction::TransientStore {
+            key: key_of(left?),
+            value: right?,
+        },
+        "mapping_load" => Instruction::MappingLoad {
+            result,
+            mapping: left?,
+            key: right?,
+        },
+        "mapping_store" => Instruction::MappingStore {
+            mapping: left?,
+            key: right?,
+            value: arg(2)?,
+        },
+        "keccak256" => Instruction::Keccak256 {
+            result,
+            data: left?,
+            len: right?,
+        },
+        "balance" => Instruction::GetBalance {
+            result,
+            address: left?,
+        },
+        "call" => Instruction::Call {
+            result,
+            target: CallTarget::Internal(args.first()?.to_string()),
+            args: args[1..].iter().map(|a| value_of(a)).collect(),
+            value: None,
+        },
+        "ext_call" => Instruction::Call {
+            result,
+            target: CallTarget::External(left?),
+            args: args[1..].iter().map(|a| value_of(a)).collect(),
+            value: None,
+        },
+        "delegatecall" => Instruction::DelegateCall {
+            result,
+            target: left?,
+            selector: right?,
+            args: args.iter().skip(2).map(|a| value_of(a)).collect(),
+        },
+        "require" => Instruction::Require {
+            condition: left?,
+            message: unquote(args.get(1).unwrap_or(&"")),
+        },
+        "assert" => Instruction::Assert {
+            condition: left?,
+            message: unquote(args.get(1).unwrap_or(&"")),
+        },
+        "selfdestruct" => Instruction::Selfdestruct { beneficiary: left? },
+        context => Instruction::GetContext {
+            result,
+            var: match context {
+                "sender" => ContextVariable::MsgSender,
+                "value" => ContextVariable::MsgValue,
+                "sig" => ContextVariable::MsgSig,
+                "origin" => ContextVariable::TxOrigin,
+                "timestamp" => ContextVariable::BlockTimestamp,
+                "number" => ContextVariable::BlockNumber,
+                "prevrandao" => ContextVariable::PrevRandao,
+                "chainid" => ContextVariable::ChainId,
+                "gasleft" => ContextVariable::GasLeft,
+                "this" => ContextVariable::ThisAddress,
+                "selfbalance" => ContextVariable::ThisBalance,
+                _ => return None,
+            },
+        },
+    };
+    Some(inst)
+}
+
+/* `pN`, `vN`, integers (decimal or `0x`), `true` and `false`. */
+fn value_of(text: &str) -> Value {
+    let text = text.trim();
+    let numbered = |prefix: &str| {
+        text.strip_prefix(prefix)
+            .and_then(|n| n.parse::<u32>().ok())
+    };
+    if let Some(n) = numbered("p") {
+        Value::Param(ParamId(n))
+    } else if let Some(n) = numbered("v") {
+        Value::Temp(TempId(n))
+    } else if let Ok(flag) = text.parse::<bool>() {
+        Value::Constant(Constant::Bool(flag))
+    } else {
+        let digits = text.replace('_', "");
+        let number = match digits.strip_prefix("0x") {
+            Some(hex) => BigUint::parse_bytes(hex.as_bytes(), 16),
+            None => BigUint::parse_bytes(digits.as_bytes(), 10),
+        };
+        Value::Constant(Constant::Uint(
+            number.unwrap_or_else(|| panic!("ir!: `{}` is not a value", text)),
+            256,
+        ))
+    }
+}
+
+fn key_of(slot: Value) -> StorageKey {
+    match slot {
+        Value::Constant(Constant::Uint(slot, _)) => StorageKey::Slot(slot),
+        dynamic => StorageKey::Dynamic(dynamic),
+    }
+}
+
+fn block_of(text: &str) -> BlockId {
+    text.trim()
+        .strip_prefix("block")
+        .and_then(|n| n.parse().ok())
+        .map(BlockId)
+        .unwrap_or_else(|| panic!("ir!: `{}` is not a block name", text))
+}
+
+/* `u256`, `i128`, `bool`, `address`, `bytes32` and the like. */
+fn ty_of(text: &str) -> Type {
+    let text = text.trim();
+    let bits = |prefix: &str| text.strip_prefix(prefix)?.parse().ok();
+    match text {
+        "bool" => Type::Bool,
+        "address" => Type::Address,
+        "string" => Type::String,
+        _ => bits("u")
+            .map(Type::Uint)
+            .or_else(|| bits("i").map(Type::Int))
+            .or_else(|| bits("bytes").map(|n: u16| Type::Bytes(n as u8)))
+            .unwrap_or_else(|| panic!("ir!: `{}` is not a type", text)),
+    }
+}
+
+fn unquote(text: &str) -> String {
+    text.trim().trim_matches('"').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block::{BlockId, Terminator};
+    use crate::function::Visibility;
+    use crate::instructions::{Instruction, StorageKey};
+    use crate::types::Type;
+    use crate::values::{ParamId, TempId, Value};
+
+    #[test]
+    fn test_ir_macro_builds_blocks_and_instructions() {
+        let function = crate::ir! {
+            block0: { v0 = add(p0, p1, u128); ret v0 }
+        };
+        assert_eq!(function.name(), "f");
+        let entry = &function.body.blocks[&BlockId(0)];
+        assert!(matches!(
+            &entry.instructions[0],
+            Instruction::Add {
+                result: Value::Temp(TempId(0)),
+                left: Value::Param(ParamId(0)),
+                ty: Type::Uint(128),
+                ..
+            }
+        ));
+        assert!(matches!(
+            entry.terminator,
+            Terminator::Return(Some(Value::Temp(TempId(0))))
+        ));
+
+        let function = crate::ir! {
+            pub fn sweep(address) -> bool;
+            block1: {
+                v0 = sload(0);
+                v1 = eq(v0, p0);
+                br v1, block2, block3
+            }
+            block2: {
+                sstore(0, 0);
+                v2 = ext_call(p0, 0xa9059cbb, v0);
+                ret true
+            }
+            block3: { revert "not owner" }
+        };
+        assert_eq!(function.visibility, Visibility::External);
+        assert_eq!(function.entry_block(), BlockId(1));
+        assert_eq!(function.body.blocks.len(), 3);
+        assert!(function.metadata.can_reenter);
+        assert!(matches!(
+            &function.body.blocks[&BlockId(2)].instructions[0],
+            Instruction::StorageStore {
+                key: StorageKey::Slot(_),
+                ..
+            }
+        ));
+        assert!(matches!(
+            &function.body.blocks[&BlockId(3)].terminator,
+            Terminator::Revert(message) if message == "not owner"
+        ));
+    }
+}
@@ -7,17 +7,16 @@ use crate::{
     IrError, Result,
 };
 use indexmap::IndexMap;
-use std::collections::HashMap;
 
 #[derive(Debug, Default)]
 pub struct IRRegistry {
     contracts: IndexMap<String, Contract>,
-    functions: HashMap<String, Function>,
-    blocks: HashMap<BlockId, BasicBlock>,
-    instructions: HashMap<String, Instruction>,
-    values: HashMap<String, Value>,
-    function_to_contract: HashMap<String, String>,
-    block_to_function: HashMap<BlockId, String>,
+    functions: IndexMap<String, Function>,
+    blocks: IndexMap<BlockId, BasicBlock>,
+    instructions: IndexMap<String, Instruction>,
+    values: IndexMap<String, Value>,
+    function_to_contract: IndexMap<String, String>,
+    block_to_function: IndexMap<BlockId, String>,
 }
 
 impl IRRegistry {
@@ -49,6 +48,63 @@ impl IRRegistry {
         self.contracts.iter()
     }
 
+    /* C3 linearization of `name` and its registered bases, most derived first, the order solc
+     * resolves overrides in. A base listed later in `is` is more derived than one listed earlier.
+     * Unregistered bases are left out, and an inconsistent hierarchy stops at the point where the
+     * merge gets stuck. */
+    pub fn linearize(&self, name: &str) -> Vec<String> {
+        self.linearize_from(name, &mut Vec::new())
+    }
+
+    fn linearize_from(&self, name: &str, visiting: &mut Vec<String>) -> Vec<String> {
+        let mut order = vec![name.to_string()];
+        let Some(contract) = self.contracts.get(name) else {
+            return order;
+        };
+        if visiting.iter().any(|seen| seen == name) {
+            return order;
+        }
+        visiting.push(name.to_string());
+
+        let bases: Vec<String> = contract
+            .metadata
+            .bases
+            .iter()
+            .rev()
+            .filter(|base| self.contracts.contains_key(base.as_str()))
+            .cloned()
+            .collect();
+        let mut sequences: Vec<Vec<String>> = bases
+            .iter()
+            .map(|base| self.linearize_from(base, visiting))
+            .collect();
+        sequences.push(bases);
+        visiting.pop();
+
+        loop {
+            sequences.retain(|sequence| !sequence.is_empty());
+            let head = sequences
+                .iter()
+                .map(|sequence| &sequence[0])
+                .find(|candidate| {
+                    !sequences
+                        .iter()
+                        .any(|sequence| sequence[1..].contains(candidate))
+                })
+                .cloned();
+            let Some(head) = head else {
+                break;
+            };
+            for sequence in &mut sequences {
+                if sequence[0] == head {
+                    sequence.remove(0);
+                }
+            }
+            order.push(head);
+        }
+        order
+    }
+
     pub fn add_function(&mut self, contract_name: String, mut function: Function) -> Result<()> {
         let qualified_name = format!("{}::{}", contract_name, function.signature.name);
 
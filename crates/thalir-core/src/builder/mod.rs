@@ -12,7 +12,9 @@ pub mod function_builder;
 pub mod function_builder_cursor;
 pub mod inst_builder;
 pub mod ir_context;
+pub mod ir_macro;
 pub mod ir_registry;
+pub mod type_check;
 
 pub use block_builder::BlockBuilder;
 pub use contract_builder::ContractBuilder;
@@ -22,6 +24,7 @@ pub use function_builder_cursor::{FunctionBuilderCursor, FunctionInstBuilder};
 pub use inst_builder::{InstBuilder, InstBuilderBase, InstBuilderExt};
 pub use ir_context::{IRContext, SSATracker, SourceMapping};
 pub use ir_registry::{IRRegistry, RegistryStats};
+pub use type_check::{TypeMismatch, TypeTable};
 
 use crate::{IrError, Result};
 
@@ -43,6 +46,12 @@ impl IRBuilder {
         ContractBuilder::new(name.to_string(), &mut self.context, &mut self.registry)
     }
 
+    /* Checks operand types as IR is built; see `IRContext::set_strict_types`. */
+    pub fn strict_types(&mut self, strict: bool) -> &mut Self {
+        self.context.set_strict_types(strict);
+        self
+    }
+
     pub fn registry(&self) -> &IRRegistry {
         &self.registry
     }
@@ -0,0 +1,238 @@
+use crate::{
+    block::Terminator,
+    instructions::{ContextVariable, Instruction},
+    types::Type,
+    values::{Constant, ParamId, Value},
+};
+use std::collections::HashMap;
+
+/* Operand and result types the builder checks in strict mode. Types come from constants, the
+ * parameters of the function being built and the instructions that produced each value. A value
+ * whose type is unknown, such as a storage load, passes every check. */
+#[derive(Debug, Default)]
+pub struct TypeTable {
+    values: HashMap<Value, Type>,
+}
+
+/* The first operand of an instruction whose type does not fit. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeMismatch {
+    pub expected: String,
+    pub found: Type,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Integer,
+    Bool,
+    Address,
+    Bytes,
+    Other,
+}
+
+fn kind(ty: &Type) -> Kind {
+    match ty {
+        Type::Uint(_) | Type::Int(_) => Kind::Integer,
+        Type::Bool => Kind::Bool,
+        Type::Address | Type::Contract(_) => Kind::Address,
+        Type::Bytes(_) | Type::Bytes4 | Type::Bytes20 | Type::Bytes32 => Kind::Bytes,
+        _ => Kind::Other,
+    }
+}
+
+fn context_type(var: ContextVariable) -> Type {
+    match var {
+        ContextVariable::MsgSender
+        | ContextVariable::TxOrigin
+        | ContextVariable::BlockCoinbase
+        | ContextVariable::ThisAddress => Type::Address,
+        ContextVariable::MsgSig => Type::Bytes4,
+        ContextVariable::MsgData => Type::Bytes(0),
+        _ => Type::Uint(256),
+    }
+}
+
+impl TypeTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, value: Value, ty: Type) {
+        self.values.insert(value, ty);
+    }
+
+    pub fn get(&self, value: &Value) -> Option<Type> {
+        match value {
+            Value::Constant(constant) => match constant {
+                Constant::Bool(_) => Some(Type::Bool),
+                Constant::Uint(_, bits) => Some(Type::Uint(*bits)),
+                Constant::Int(_, bits) => Some(Type::Int(*bits)),
+                Constant::Address(_) => Some(Type::Address),
+                _ => None,
+            },
+            _ => self.values.get(value).cloned(),
+        }
+    }
+
+    /* Parameters are numbered per function, so a new function starts without the old ones. */
+    pub fn set_params(&mut self, params: impl IntoIterator<Item = Type>) {
+        self.values
+            .retain(|value, _| !matches!(value, Value::Param(_)));
+        for (index, ty) in params.into_iter().enumerate() {
+            self.values.insert(Value::Param(ParamId(index as u32)), ty);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /* Checks the operands of `inst` and, if they fit, records the type of its result. */
+    pub fn check(&mut self, inst: &Instruction) -> Result<(), TypeMismatch> {
+        let result_type = match inst {
+            Instruction::Add {
+                left, right, ty, ..
+            }
+            | Instruction::Sub {
+                left, right, ty, ..
+            }
+            | Instruction::Mul {
+                left, right, ty, ..
+            }
+            | Instruction::Div {
+                left, right, ty, ..
+            }
+            | Instruction::Mod {
+                left, right, ty, ..
+            }
+            | Instruction::Sdiv {
+                left, right, ty, ..
+            }
+            | Instruction::Smod {
+                left, right, ty, ..
+            }
+            | Instruction::CheckedAdd {
+                left, right, ty, ..
+            }
+            | Instruction::CheckedSub {
+                left, right, ty, ..
+            }
+            | Instruction::CheckedMul {
+                left, right, ty, ..
+            }
+            | Instruction::CheckedDiv {
+                left, right, ty, ..
+            } => {
+                self.expect(left, ty)?;
+                self.expect(right, ty)?;
+                Some(ty.clone())
+            }
+            Instruction::Pow { base, exp, .. } => {
+                self.expect_integer(base)?;
+                self.expect_integer(exp)?;
+                self.get(base)
+            }
+            Instruction::And { left, right, .. }
+            | Instruction::Or { left, right, .. }
+            | Instruction::Xor { left, right, .. } => {
+                self.expect_same(left, right)?;
+                self.get(left).or_else(|| self.get(right))
+            }
+            Instruction::Not { operand, .. } => self.get(operand),
+            Instruction::Shl { value, shift, .. }
+            | Instruction::Shr { value, shift, .. }
+            | Instruction::Sar { value, shift, .. } => {
+                self.expect_integer(shift)?;
+                self.get(value)
+            }
+            Instruction::Eq { left, right, .. }
+            | Instruction::Ne { left, right, .. }
+            | Instruction::Lt { left, right, .. }
+            | Instruction::Gt { left, right, .. }
+            | Instruction::Le { left, right, .. }
+            | Instruction::Ge { left, right, .. }
+            | Instruction::Slt { left, right, .. }
+            | Instruction::Sgt { left, right, .. }
+            | Instruction::Sle { left, right, .. }
+            | Instruction::Sge { left, right, .. } => {
+                self.expect_same(left, right)?;
+                Some(Type::Bool)
+            }
+            Instruction::Select {
+                condition,
+                then_val,
+                else_val,
+                ..
+            } => {
+                self.expect(condition, &Type::Bool)?;
+                self.expect_same(then_val, else_val)?;
+                self.get(then_val).or_else(|| self.get(else_val))
+            }
+            Instruction::Require { condition, .. }
+            | Instruction::Assert { condition, .. }
+            | Instruction::Branch { condition, .. } => {
+                self.expect(condition, &Type::Bool)?;
+                None
+            }
+            Instruction::Cast { to, .. }
+            | Instruction::ZeroExtend { to, .. }
+            | Instruction::SignExtend { to, .. }
+            | Instruction::Truncate { to, .. } => Some(to.clone()),
+            Instruction::GetContext { var, .. } => Some(context_type(*var)),
+            Instruction::GetBalance { address, .. } => {
+                self.expect(address, &Type::Address)?;
+                Some(Type::Uint(256))
+            }
+            Instruction::ArrayLength { .. } => Some(Type::Uint(256)),
+            Instruction::Keccak256 { .. } | Instruction::Sha256 { .. } => Some(Type::Bytes32),
+            Instruction::Ripemd160 { .. } => Some(Type::Bytes20),
+            Instruction::EcRecover { .. } => Some(Type::Address),
+            Instruction::Assign { value, .. } => self.get(value),
+            _ => None,
+        };
+
+        if let (Some(result), Some(ty)) = (inst.result(), result_type) {
+            self.values.insert(result.clone(), ty);
+        }
+        Ok(())
+    }
+
+    pub fn check_terminator(&self, terminator: &Terminator) -> Result<(), TypeMismatch> {
+        match terminator {
+            Terminator::Branch { condition, .. } => self.expect(condition, &Type::Bool),
+            _ => Ok(()),
+        }
+    }
+
+    fn expect(&self, value: &Value, expected: &Type) -> Result<(), TypeMismatch> {
+        match self.get(value) {
+            Some(found) if kind(&found) != kind(expected) => Err(TypeMismatch {
+                expected: expected.to_string(),
+                found,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn expect_integer(&self, value: &Value) -> Result<(), TypeMismatch> {
+        match self.get(value) {
+            Some(found) if kind(&found) != Kind::Integer => Err(TypeMismatch {
+                expected: "an integer".to_string(),
+                found,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /* Both operands of a comparison or bitwise operation share a kind: integers of any width,
+     * booleans, addresses or fixed bytes. */
+    fn expect_same(&self, left: &Value, right: &Value) -> Result<(), TypeMismatch> {
+        match (self.get(left), self.get(right)) {
+            (Some(expected), Some(found)) if kind(&expected) != kind(&found) => Err(TypeMismatch {
+                expected: expected.to_string(),
+                found,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use crate::instructions::{BuiltinFunction, ContextVariable};
+use crate::version::EvmVersion;
+use serde::{Deserialize, Serialize};
+
+/* How a chain answers a context read. `Fixed` reads compile and succeed but always return the same
+ * value, so nothing downstream can depend on them; `Unsupported` reads fail to compile or revert. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContextSupport {
+    Native,
+    Fixed(u64),
+    Unsupported,
+}
+
+impl ContextSupport {
+    pub fn varies(&self) -> bool {
+        matches!(self, ContextSupport::Native)
+    }
+}
+
+/* The context reads whose meaning differs between Ethereum and the rollups it hosts. Everything
+ * not listed here (msg.*, tx.*, block.number, block.timestamp, ...) is treated as native, and fork
+ * gating is layered on top by `EvmVersion`. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ChainCapabilities {
+    pub name: String,
+    pub randomness: ContextSupport,
+    pub basefee: ContextSupport,
+    pub blob_basefee: ContextSupport,
+    pub blob_hash: ContextSupport,
+}
+
+impl Default for ChainCapabilities {
+    fn default() -> Self {
+        Self::ethereum()
+    }
+}
+
+impl ChainCapabilities {
+    pub fn ethereum() -> Self {
+        Self {
+            name: "ethereum".to_string(),
+            randomness: ContextSupport::Native,
+            basefee: ContextSupport::Native,
+            blob_basefee: ContextSupport::Native,
+            blob_hash: ContextSupport::Native,
+        }
+    }
+
+    /* Nitro pins DIFFICULTY/PREVRANDAO to 1, has no blob transactions, and reverts on BLOBBASEFEE. */
+    pub fn arbitrum() -> Self {
+        Self {
+            name: "arbitrum".to_string(),
+            randomness: ContextSupport::Fixed(1),
+            basefee: ContextSupport::Native,
+            blob_basefee: ContextSupport::Unsupported,
+            blob_hash: ContextSupport::Fixed(0),
+        }
+    }
+
+    /* OP Stack chains relay L1 randomness; since Ecotone BLOBBASEFEE is 1 and BLOBHASH is 0. */
+    pub fn optimism() -> Self {
+        Self {
+            name: "optimism".to_string(),
+            randomness: ContextSupport::Native,
+            basefee: ContextSupport::Native,
+            blob_basefee: ContextSupport::Fixed(1),
+            blob_hash: ContextSupport::Fixed(0),
+        }
+    }
+
+    /* zkSync Era returns a constant difficulty and its compiler rejects the blob opcodes. */
+    pub fn zksync() -> Self {
+        Self {
+            name: "zksync".to_string(),
+            randomness: ContextSupport::Fixed(2_500_000_000_000_000),
+            basefee: ContextSupport::Native,
+            blob_basefee: ContextSupport::Unsupported,
+            blob_hash: ContextSupport::Unsupported,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "ethereum" | "mainnet" => Some(Self::ethereum()),
+            "arbitrum" => Some(Self::arbitrum()),
+            "optimism" | "base" => Some(Self::optimism()),
+            "zksync" => Some(Self::zksync()),
+            _ => None,
+        }
+    }
+
+    /* Mainnets and their public testnets. */
+    pub fn for_chain_id(chain_id: u64) -> Option<Self> {
+        match chain_id {
+            1 | 11_155_111 | 17_000 => Some(Self::ethereum()),
+            42_161 | 42_170 | 421_614 => Some(Self::arbitrum()),
+            10 | 8_453 | 11_155_420 | 84_532 => Some(Self::optimism()),
+            324 | 300 => Some(Self::zksync()),
+            _ => None,
+        }
+    }
+
+    pub fn context_support(&self, var: ContextVariable, evm: EvmVersion) -> ContextSupport {
+        match var {
+            ContextVariable::BlockDifficulty | ContextVariable::PrevRandao => self.randomness,
+            ContextVariable::BlockBaseFee if evm < EvmVersion::London => {
+                ContextSupport::Unsupported
+            }
+            ContextVariable::BlockBaseFee => self.basefee,
+            ContextVariable::BlobBaseFee if evm < EvmVersion::Cancun => ContextSupport::Unsupported,
+            ContextVariable::BlobBaseFee => self.blob_basefee,
+            ContextVariable::ChainId if evm < EvmVersion::Istanbul => ContextSupport::Unsupported,
+            _ => ContextSupport::Native,
+        }
+    }
+
+    pub fn builtin_support(&self, builtin: BuiltinFunction, evm: EvmVersion) -> ContextSupport {
+        match builtin {
+            BuiltinFunction::BlobHash if evm < EvmVersion::Cancun => ContextSupport::Unsupported,
+            BuiltinFunction::BlobHash => self.blob_hash,
+            _ => ContextSupport::Native,
+        }
+    }
+}
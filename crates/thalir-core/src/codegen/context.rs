@@ -1,11 +1,30 @@
 use cranelift_codegen::ir::{self as clif_ir};
 use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use indexmap::IndexSet;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 use crate::types::Type;
 
+/* How a word crosses into a runtime helper. Words are i128 inside a function whatever the ABI;
+ * only the parameters and results of runtime calls change shape. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WordAbi {
+    /* One i128 per word. */
+    #[default]
+    I128,
+    /* Two i64 per word, low half first. */
+    I64Pair,
+    /* A pointer to the word's little-endian bytes and their length. A helper that returns a word
+     * writes it through one more handle, passed after the arguments. */
+    Handle,
+}
+
 pub struct CodegenContext<'a> {
     pub func: &'a mut clif_ir::Function,
     pub builder_context: FunctionBuilderContext,
+    pub word_abi: WordAbi,
 }
 
 impl<'a> CodegenContext<'a> {
@@ -13,9 +32,15 @@ impl<'a> CodegenContext<'a> {
         Self {
             func,
             builder_context: FunctionBuilderContext::new(),
+            word_abi: WordAbi::default(),
         }
     }
 
+    pub fn with_word_abi(mut self, word_abi: WordAbi) -> Self {
+        self.word_abi = word_abi;
+        self
+    }
+
     pub fn func_builder(&mut self) -> FunctionBuilder<'_> {
         FunctionBuilder::new(self.func, &mut self.builder_context)
     }
@@ -33,3 +58,46 @@ impl<'a> CodegenContext<'a> {
         }
     }
 }
+
+/* The external name namespaces calls to contract functions and library functions lower to. */
+pub const INTERNAL_NAMESPACE: u32 = 4;
+pub const LIBRARY_NAMESPACE: u32 = 5;
+
+/* Gives every callee one external name for the whole module, numbered in the order callees are
+ * first seen, so distinct callees never share an index and every call to the same function can be
+ * linked to it later. */
+#[derive(Debug, Default, Clone)]
+pub struct CalleeNames {
+    names: HashMap<u32, IndexSet<String>>,
+}
+
+impl CalleeNames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn internal(&mut self, name: &str) -> clif_ir::UserExternalName {
+        self.intern(INTERNAL_NAMESPACE, name)
+    }
+
+    pub fn library(&mut self, name: &str) -> clif_ir::UserExternalName {
+        self.intern(LIBRARY_NAMESPACE, name)
+    }
+
+    /* The callee an external name was given to, if this registry gave it out. */
+    pub fn resolve(&self, name: &clif_ir::UserExternalName) -> Option<&str> {
+        self.names
+            .get(&name.namespace)?
+            .get_index(name.index as usize)
+            .map(String::as_str)
+    }
+
+    fn intern(&mut self, namespace: u32, name: &str) -> clif_ir::UserExternalName {
+        let names = self.names.entry(namespace).or_default();
+        let index = match names.get_index_of(name) {
+            Some(index) => index,
+            None => names.insert_full(name.to_string()).0,
+        };
+        clif_ir::UserExternalName::new(namespace, index as u32)
+    }
+}
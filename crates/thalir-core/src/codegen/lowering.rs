@@ -3,10 +3,12 @@ use cranelift_codegen::ir::immediates::Offset32;
 use cranelift_codegen::ir::types;
 use cranelift_codegen::ir::{self as clif_ir, InstBuilder, MemFlags};
 use cranelift_frontend::FunctionBuilder;
-use std::collections::HashMap;
+use num_bigint::BigUint;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
     block::Terminator,
+    codegen::context::{CalleeNames, WordAbi},
     instructions::{CallTarget, ContextVariable, Instruction, Size, StorageKey},
     types::Type,
     values::{Constant, Value, VarId},
@@ -14,11 +16,15 @@ use crate::{
 };
 use cranelift_frontend::Variable;
 
+const WORD_BYTES: u32 = 16;
+
 pub fn lower_instruction(
     inst: &Instruction,
     _variables: &HashMap<VarId, Variable>,
     ssa_values: &mut HashMap<Value, clif_ir::Value>,
     builder: &mut FunctionBuilder,
+    callees: &mut CalleeNames,
+    abi: WordAbi,
 ) -> Result<()> {
     match inst {
         Instruction::Add {
@@ -27,9 +33,9 @@ pub fn lower_instruction(
             right,
             ..
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
-            let res = builder.ins().iadd(*left, *right);
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder.ins().iadd(left, right);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Sub {
@@ -38,9 +44,9 @@ pub fn lower_instruction(
             right,
             ..
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
-            let res = builder.ins().isub(*left, *right);
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder.ins().isub(left, right);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Mul {
@@ -49,9 +55,9 @@ pub fn lower_instruction(
             right,
             ..
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
-            let res = builder.ins().imul(*left, *right);
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder.ins().imul(left, right);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Div {
@@ -60,9 +66,9 @@ pub fn lower_instruction(
             right,
             ..
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
-            let res = builder.ins().udiv(*left, *right);
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder.ins().udiv(left, right);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Mod {
@@ -71,9 +77,31 @@ pub fn lower_instruction(
             right,
             ..
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
-            let res = builder.ins().urem(*left, *right);
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder.ins().urem(left, right);
+            ssa_values.insert(result.clone(), res);
+        }
+        Instruction::Sdiv {
+            result,
+            left,
+            right,
+            ..
+        } => {
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder.ins().sdiv(left, right);
+            ssa_values.insert(result.clone(), res);
+        }
+        Instruction::Smod {
+            result,
+            left,
+            right,
+            ..
+        } => {
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder.ins().srem(left, right);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Eq {
@@ -81,9 +109,9 @@ pub fn lower_instruction(
             left,
             right,
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
-            let res = builder.ins().icmp(IntCC::Equal, *left, *right);
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder.ins().icmp(IntCC::Equal, left, right);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Ne {
@@ -91,9 +119,9 @@ pub fn lower_instruction(
             left,
             right,
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
-            let res = builder.ins().icmp(IntCC::NotEqual, *left, *right);
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder.ins().icmp(IntCC::NotEqual, left, right);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Lt {
@@ -101,9 +129,9 @@ pub fn lower_instruction(
             left,
             right,
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
-            let res = builder.ins().icmp(IntCC::SignedLessThan, *left, *right);
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder.ins().icmp(IntCC::UnsignedLessThan, left, right);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Gt {
@@ -111,9 +139,9 @@ pub fn lower_instruction(
             left,
             right,
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
-            let res = builder.ins().icmp(IntCC::SignedGreaterThan, *left, *right);
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder.ins().icmp(IntCC::UnsignedGreaterThan, left, right);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Le {
@@ -121,11 +149,11 @@ pub fn lower_instruction(
             left,
             right,
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
             let res = builder
                 .ins()
-                .icmp(IntCC::SignedLessThanOrEqual, *left, *right);
+                .icmp(IntCC::UnsignedLessThanOrEqual, left, right);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Ge {
@@ -133,11 +161,55 @@ pub fn lower_instruction(
             left,
             right,
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder
+                .ins()
+                .icmp(IntCC::UnsignedGreaterThanOrEqual, left, right);
+            ssa_values.insert(result.clone(), res);
+        }
+        Instruction::Slt {
+            result,
+            left,
+            right,
+        } => {
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder.ins().icmp(IntCC::SignedLessThan, left, right);
+            ssa_values.insert(result.clone(), res);
+        }
+        Instruction::Sgt {
+            result,
+            left,
+            right,
+        } => {
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder.ins().icmp(IntCC::SignedGreaterThan, left, right);
+            ssa_values.insert(result.clone(), res);
+        }
+        Instruction::Sle {
+            result,
+            left,
+            right,
+        } => {
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
             let res = builder
                 .ins()
-                .icmp(IntCC::SignedGreaterThanOrEqual, *left, *right);
+                .icmp(IntCC::SignedLessThanOrEqual, left, right);
+            ssa_values.insert(result.clone(), res);
+        }
+        Instruction::Sge {
+            result,
+            left,
+            right,
+        } => {
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder
+                .ins()
+                .icmp(IntCC::SignedGreaterThanOrEqual, left, right);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Select {
@@ -146,10 +218,10 @@ pub fn lower_instruction(
             then_val,
             else_val,
         } => {
-            let cond = ssa_values.get(condition).unwrap();
-            let then_v = ssa_values.get(then_val).unwrap();
-            let else_v = ssa_values.get(else_val).unwrap();
-            let res = builder.ins().select(*cond, *then_v, *else_v);
+            let cond = lookup(ssa_values, condition, inst)?;
+            let then_v = lookup(ssa_values, then_val, inst)?;
+            let else_v = lookup(ssa_values, else_val, inst)?;
+            let res = builder.ins().select(cond, then_v, else_v);
             ssa_values.insert(result.clone(), res);
         }
 
@@ -158,9 +230,9 @@ pub fn lower_instruction(
             left,
             right,
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
-            let res = builder.ins().band(*left, *right);
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder.ins().band(left, right);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Or {
@@ -168,9 +240,9 @@ pub fn lower_instruction(
             left,
             right,
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
-            let res = builder.ins().bor(*left, *right);
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder.ins().bor(left, right);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Xor {
@@ -178,14 +250,14 @@ pub fn lower_instruction(
             left,
             right,
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
-            let res = builder.ins().bxor(*left, *right);
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let res = builder.ins().bxor(left, right);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Not { result, operand } => {
-            let operand = ssa_values.get(operand).unwrap();
-            let res = builder.ins().bnot(*operand);
+            let operand = lookup(ssa_values, operand, inst)?;
+            let res = builder.ins().bnot(operand);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Shl {
@@ -193,9 +265,9 @@ pub fn lower_instruction(
             value,
             shift,
         } => {
-            let value = ssa_values.get(value).unwrap();
-            let shift = ssa_values.get(shift).unwrap();
-            let res = builder.ins().ishl(*value, *shift);
+            let value = lookup(ssa_values, value, inst)?;
+            let shift = lookup(ssa_values, shift, inst)?;
+            let res = builder.ins().ishl(value, shift);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Shr {
@@ -203,9 +275,9 @@ pub fn lower_instruction(
             value,
             shift,
         } => {
-            let value = ssa_values.get(value).unwrap();
-            let shift = ssa_values.get(shift).unwrap();
-            let res = builder.ins().ushr(*value, *shift);
+            let value = lookup(ssa_values, value, inst)?;
+            let shift = lookup(ssa_values, shift, inst)?;
+            let res = builder.ins().ushr(value, shift);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Sar {
@@ -213,16 +285,16 @@ pub fn lower_instruction(
             value,
             shift,
         } => {
-            let value = ssa_values.get(value).unwrap();
-            let shift = ssa_values.get(shift).unwrap();
-            let res = builder.ins().sshr(*value, *shift);
+            let value = lookup(ssa_values, value, inst)?;
+            let shift = lookup(ssa_values, shift, inst)?;
+            let res = builder.ins().sshr(value, shift);
             ssa_values.insert(result.clone(), res);
         }
 
         Instruction::Pow { result, base, exp } => {
-            let base = ssa_values.get(base).unwrap();
-            let exp = ssa_values.get(exp).unwrap();
-            let res = emit_runtime_call(builder, 0, 0, &[*base, *exp])?;
+            let base = lookup(ssa_values, base, inst)?;
+            let exp = lookup(ssa_values, exp, inst)?;
+            let res = emit_runtime_call(builder, abi, 0, 0, &[base, exp])?;
             ssa_values.insert(result.clone(), res);
         }
 
@@ -230,11 +302,15 @@ pub fn lower_instruction(
             result,
             left,
             right,
-            ..
+            ty,
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
-            let (res, overflow) = builder.ins().sadd_overflow(*left, *right);
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let (res, overflow) = if ty.is_signed() {
+                builder.ins().sadd_overflow(left, right)
+            } else {
+                builder.ins().uadd_overflow(left, right)
+            };
 
             let overflow_block = builder.create_block();
             let continue_block = builder.create_block();
@@ -253,11 +329,15 @@ pub fn lower_instruction(
             result,
             left,
             right,
-            ..
+            ty,
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
-            let (res, overflow) = builder.ins().ssub_overflow(*left, *right);
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let (res, overflow) = if ty.is_signed() {
+                builder.ins().ssub_overflow(left, right)
+            } else {
+                builder.ins().usub_overflow(left, right)
+            };
 
             let overflow_block = builder.create_block();
             let continue_block = builder.create_block();
@@ -276,11 +356,15 @@ pub fn lower_instruction(
             result,
             left,
             right,
-            ..
+            ty,
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
-            let (res, overflow) = builder.ins().smul_overflow(*left, *right);
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
+            let (res, overflow) = if ty.is_signed() {
+                builder.ins().smul_overflow(left, right)
+            } else {
+                builder.ins().umul_overflow(left, right)
+            };
 
             let overflow_block = builder.create_block();
             let continue_block = builder.create_block();
@@ -297,18 +381,18 @@ pub fn lower_instruction(
         }
 
         Instruction::Load { result, location } => {
-            let addr = get_location_address(location, ssa_values, builder)?;
+            let addr = get_location_address(location, ssa_values, builder, inst)?;
             let res = builder
                 .ins()
                 .load(types::I128, MemFlags::trusted(), addr, Offset32::new(0));
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Store { location, value } => {
-            let addr = get_location_address(location, ssa_values, builder)?;
-            let value = ssa_values.get(value).unwrap();
+            let addr = get_location_address(location, ssa_values, builder, inst)?;
+            let value = lookup(ssa_values, value, inst)?;
             builder
                 .ins()
-                .store(MemFlags::trusted(), *value, addr, Offset32::new(0));
+                .store(MemFlags::trusted(), value, addr, Offset32::new(0));
         }
         Instruction::Allocate {
             result,
@@ -317,31 +401,41 @@ pub fn lower_instruction(
         } => {
             let size_val = match size {
                 Size::Static(s) => builder.ins().iconst(types::I64, *s as i64),
-                Size::Dynamic(v) => *ssa_values.get(v).unwrap(),
+                Size::Dynamic(v) => lookup(ssa_values, v, inst)?,
             };
-            let res = emit_runtime_call(builder, 0, 1, &[size_val])?;
+            let res = emit_runtime_call(builder, abi, 0, 1, &[size_val])?;
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Copy { dest, src, size } => {
-            let dest_addr = get_location_address(dest, ssa_values, builder)?;
-            let src_addr = get_location_address(src, ssa_values, builder)?;
-            let size = ssa_values.get(size).unwrap();
-            emit_runtime_call_void(builder, 0, 2, &[dest_addr, src_addr, *size])?;
+            let dest_addr = get_location_address(dest, ssa_values, builder, inst)?;
+            let src_addr = get_location_address(src, ssa_values, builder, inst)?;
+            let size = lookup(ssa_values, size, inst)?;
+            emit_runtime_call_void(builder, abi, 0, 2, &[dest_addr, src_addr, size])?;
         }
 
         Instruction::StorageLoad { result, key } => {
-            let key_val = get_storage_key_value(key, ssa_values, builder)?;
-            let res = emit_runtime_call(builder, 1, 0, &[key_val])?;
+            let key_val = get_storage_key_value(key, ssa_values, builder, inst)?;
+            let res = emit_runtime_call(builder, abi, 1, 0, &[key_val])?;
             ssa_values.insert(result.clone(), res);
         }
         Instruction::StorageStore { key, value } => {
-            let key_val = get_storage_key_value(key, ssa_values, builder)?;
-            let value = ssa_values.get(value).unwrap();
-            emit_runtime_call_void(builder, 1, 1, &[key_val, *value])?;
+            let key_val = get_storage_key_value(key, ssa_values, builder, inst)?;
+            let value = lookup(ssa_values, value, inst)?;
+            emit_runtime_call_void(builder, abi, 1, 1, &[key_val, value])?;
         }
         Instruction::StorageDelete { key } => {
-            let key_val = get_storage_key_value(key, ssa_values, builder)?;
-            emit_runtime_call_void(builder, 1, 2, &[key_val])?;
+            let key_val = get_storage_key_value(key, ssa_values, builder, inst)?;
+            emit_runtime_call_void(builder, abi, 1, 2, &[key_val])?;
+        }
+        Instruction::TransientLoad { result, key } => {
+            let key_val = get_storage_key_value(key, ssa_values, builder, inst)?;
+            let res = emit_runtime_call(builder, abi, 1, 3, &[key_val])?;
+            ssa_values.insert(result.clone(), res);
+        }
+        Instruction::TransientStore { key, value } => {
+            let key_val = get_storage_key_value(key, ssa_values, builder, inst)?;
+            let value = lookup(ssa_values, value, inst)?;
+            emit_runtime_call_void(builder, abi, 1, 4, &[key_val, value])?;
         }
 
         Instruction::MappingLoad {
@@ -349,9 +443,9 @@ pub fn lower_instruction(
             mapping,
             key,
         } => {
-            let mapping = ssa_values.get(mapping).unwrap();
-            let key = ssa_values.get(key).unwrap();
-            let res = emit_runtime_call(builder, 2, 0, &[*mapping, *key])?;
+            let mapping = lookup(ssa_values, mapping, inst)?;
+            let key = lookup(ssa_values, key, inst)?;
+            let res = emit_runtime_call(builder, abi, 2, 0, &[mapping, key])?;
             ssa_values.insert(result.clone(), res);
         }
         Instruction::MappingStore {
@@ -359,22 +453,58 @@ pub fn lower_instruction(
             key,
             value,
         } => {
-            let mapping = ssa_values.get(mapping).unwrap();
-            let key = ssa_values.get(key).unwrap();
-            let value = ssa_values.get(value).unwrap();
-            emit_runtime_call_void(builder, 2, 1, &[*mapping, *key, *value])?;
+            let mapping = lookup(ssa_values, mapping, inst)?;
+            let key = lookup(ssa_values, key, inst)?;
+            let value = lookup(ssa_values, value, inst)?;
+            emit_runtime_call_void(builder, abi, 2, 1, &[mapping, key, value])?;
         }
 
+        /* State arrays are addressed by their slot constant and live in storage, not memory. */
+        Instruction::ArrayLoad {
+            result,
+            array: Value::Constant(Constant::Uint(base, _)),
+            index,
+        } => {
+            let key = StorageKey::ArrayElement {
+                base: base.clone(),
+                index: index.clone(),
+            };
+            let key_val = get_storage_key_value(&key, ssa_values, builder, inst)?;
+            let res = emit_runtime_call(builder, abi, 1, 0, &[key_val])?;
+            ssa_values.insert(result.clone(), res);
+        }
+        Instruction::ArrayStore {
+            array: Value::Constant(Constant::Uint(base, _)),
+            index,
+            value,
+        } => {
+            let key = StorageKey::ArrayElement {
+                base: base.clone(),
+                index: index.clone(),
+            };
+            let key_val = get_storage_key_value(&key, ssa_values, builder, inst)?;
+            let value = lookup(ssa_values, value, inst)?;
+            emit_runtime_call_void(builder, abi, 1, 1, &[key_val, value])?;
+        }
+        Instruction::ArrayLength {
+            result,
+            array: Value::Constant(Constant::Uint(base, _)),
+        } => {
+            let key = StorageKey::Slot(base.clone());
+            let key_val = get_storage_key_value(&key, ssa_values, builder, inst)?;
+            let res = emit_runtime_call(builder, abi, 1, 0, &[key_val])?;
+            ssa_values.insert(result.clone(), res);
+        }
         Instruction::ArrayLoad {
             result,
             array,
             index,
         } => {
-            let array = ssa_values.get(array).unwrap();
-            let index = ssa_values.get(index).unwrap();
+            let array = lookup(ssa_values, array, inst)?;
+            let index = lookup(ssa_values, index, inst)?;
             let element_size = builder.ins().iconst(types::I64, 32);
-            let offset = builder.ins().imul(*index, element_size);
-            let addr = builder.ins().iadd(*array, offset);
+            let offset = builder.ins().imul(index, element_size);
+            let addr = builder.ins().iadd(array, offset);
             let res = builder
                 .ins()
                 .load(types::I128, MemFlags::trusted(), addr, Offset32::new(0));
@@ -385,20 +515,20 @@ pub fn lower_instruction(
             index,
             value,
         } => {
-            let array = ssa_values.get(array).unwrap();
-            let index = ssa_values.get(index).unwrap();
-            let value = ssa_values.get(value).unwrap();
+            let array = lookup(ssa_values, array, inst)?;
+            let index = lookup(ssa_values, index, inst)?;
+            let value = lookup(ssa_values, value, inst)?;
             let element_size = builder.ins().iconst(types::I64, 32);
-            let offset = builder.ins().imul(*index, element_size);
-            let addr = builder.ins().iadd(*array, offset);
+            let offset = builder.ins().imul(index, element_size);
+            let addr = builder.ins().iadd(array, offset);
             builder
                 .ins()
-                .store(MemFlags::trusted(), *value, addr, Offset32::new(0));
+                .store(MemFlags::trusted(), value, addr, Offset32::new(0));
         }
         Instruction::ArrayLength { result, array } => {
-            let array = ssa_values.get(array).unwrap();
+            let array = lookup(ssa_values, array, inst)?;
             let offset = builder.ins().iconst(types::I64, -32);
-            let len_addr = builder.ins().iadd(*array, offset);
+            let len_addr = builder.ins().iadd(array, offset);
             let res =
                 builder
                     .ins()
@@ -406,13 +536,13 @@ pub fn lower_instruction(
             ssa_values.insert(result.clone(), res);
         }
         Instruction::ArrayPush { array, value } => {
-            let array = ssa_values.get(array).unwrap();
-            let value = ssa_values.get(value).unwrap();
-            emit_runtime_call_void(builder, 3, 0, &[*array, *value])?;
+            let array = lookup(ssa_values, array, inst)?;
+            let value = lookup(ssa_values, value, inst)?;
+            emit_runtime_call_void(builder, abi, 3, 0, &[array, value])?;
         }
         Instruction::ArrayPop { result, array } => {
-            let array = ssa_values.get(array).unwrap();
-            let res = emit_runtime_call(builder, 3, 1, &[*array])?;
+            let array = lookup(ssa_values, array, inst)?;
+            let res = emit_runtime_call(builder, abi, 3, 1, &[array])?;
             ssa_values.insert(result.clone(), res);
         }
 
@@ -422,12 +552,12 @@ pub fn lower_instruction(
             args,
             value,
         } => {
-            let args_vals: Vec<_> = args
-                .iter()
-                .map(|arg| *ssa_values.get(arg).unwrap())
-                .collect();
-            let value_val = value.as_ref().map(|v| *ssa_values.get(v).unwrap());
-            let res = emit_call(builder, target, &args_vals, value_val)?;
+            let args_vals = lookup_all(ssa_values, args, inst)?;
+            let value_val = value
+                .as_ref()
+                .map(|v| lookup(ssa_values, v, inst))
+                .transpose()?;
+            let res = emit_call(builder, callees, abi, target, &args_vals, value_val)?;
             ssa_values.insert(result.clone(), res);
         }
         Instruction::DelegateCall {
@@ -436,14 +566,11 @@ pub fn lower_instruction(
             selector: _,
             args,
         } => {
-            let target = ssa_values.get(target).unwrap();
-            let args_vals: Vec<_> = args
-                .iter()
-                .map(|arg| *ssa_values.get(arg).unwrap())
-                .collect();
-            let mut all_args = vec![*target];
+            let target = lookup(ssa_values, target, inst)?;
+            let args_vals = lookup_all(ssa_values, args, inst)?;
+            let mut all_args = vec![target];
             all_args.extend(args_vals);
-            let res = emit_runtime_call(builder, 6, 1, &all_args)?;
+            let res = emit_runtime_call(builder, abi, 6, 1, &all_args)?;
             ssa_values.insert(result.clone(), res);
         }
         Instruction::StaticCall {
@@ -452,14 +579,11 @@ pub fn lower_instruction(
             selector: _,
             args,
         } => {
-            let target = ssa_values.get(target).unwrap();
-            let args_vals: Vec<_> = args
-                .iter()
-                .map(|arg| *ssa_values.get(arg).unwrap())
-                .collect();
-            let mut all_args = vec![*target];
+            let target = lookup(ssa_values, target, inst)?;
+            let args_vals = lookup_all(ssa_values, args, inst)?;
+            let mut all_args = vec![target];
             all_args.extend(args_vals);
-            let res = emit_runtime_call(builder, 6, 2, &all_args)?;
+            let res = emit_runtime_call(builder, abi, 6, 2, &all_args)?;
             ssa_values.insert(result.clone(), res);
         }
 
@@ -468,9 +592,9 @@ pub fn lower_instruction(
             code,
             value,
         } => {
-            let code = ssa_values.get(code).unwrap();
-            let value = ssa_values.get(value).unwrap();
-            let res = emit_runtime_call(builder, 8, 0, &[*code, *value])?;
+            let code = lookup(ssa_values, code, inst)?;
+            let value = lookup(ssa_values, value, inst)?;
+            let res = emit_runtime_call(builder, abi, 8, 0, &[code, value])?;
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Create2 {
@@ -479,16 +603,16 @@ pub fn lower_instruction(
             salt,
             value,
         } => {
-            let code = ssa_values.get(code).unwrap();
-            let salt = ssa_values.get(salt).unwrap();
-            let value = ssa_values.get(value).unwrap();
-            let res = emit_runtime_call(builder, 8, 1, &[*code, *salt, *value])?;
+            let code = lookup(ssa_values, code, inst)?;
+            let salt = lookup(ssa_values, salt, inst)?;
+            let value = lookup(ssa_values, value, inst)?;
+            let res = emit_runtime_call(builder, abi, 8, 1, &[code, salt, value])?;
             ssa_values.insert(result.clone(), res);
         }
 
         Instruction::Selfdestruct { beneficiary } => {
-            let beneficiary = ssa_values.get(beneficiary).unwrap();
-            emit_runtime_call_void(builder, 9, 0, &[*beneficiary])?;
+            let beneficiary = lookup(ssa_values, beneficiary, inst)?;
+            emit_runtime_call_void(builder, abi, 9, 0, &[beneficiary])?;
         }
 
         Instruction::GetContext { result, var } => {
@@ -496,42 +620,42 @@ pub fn lower_instruction(
             ssa_values.insert(result.clone(), res);
         }
         Instruction::GetBalance { result, address } => {
-            let address = ssa_values.get(address).unwrap();
-            let res = emit_runtime_call(builder, 10, 0, &[*address])?;
+            let address = lookup(ssa_values, address, inst)?;
+            let res = emit_runtime_call(builder, abi, 10, 0, &[address])?;
             ssa_values.insert(result.clone(), res);
         }
         Instruction::GetCode { result, address } => {
-            let address = ssa_values.get(address).unwrap();
-            let res = emit_runtime_call(builder, 10, 1, &[*address])?;
+            let address = lookup(ssa_values, address, inst)?;
+            let res = emit_runtime_call(builder, abi, 10, 1, &[address])?;
             ssa_values.insert(result.clone(), res);
         }
         Instruction::GetCodeSize { result, address } => {
-            let address = ssa_values.get(address).unwrap();
-            let res = emit_runtime_call(builder, 10, 2, &[*address])?;
+            let address = lookup(ssa_values, address, inst)?;
+            let res = emit_runtime_call(builder, abi, 10, 2, &[address])?;
             ssa_values.insert(result.clone(), res);
         }
         Instruction::GetCodeHash { result, address } => {
-            let address = ssa_values.get(address).unwrap();
-            let res = emit_runtime_call(builder, 10, 3, &[*address])?;
+            let address = lookup(ssa_values, address, inst)?;
+            let res = emit_runtime_call(builder, abi, 10, 3, &[address])?;
             ssa_values.insert(result.clone(), res);
         }
 
         Instruction::Keccak256 { result, data, len } => {
-            let data = ssa_values.get(data).unwrap();
-            let len = ssa_values.get(len).unwrap();
-            let res = emit_runtime_call(builder, 11, 0, &[*data, *len])?;
+            let data = lookup(ssa_values, data, inst)?;
+            let len = lookup(ssa_values, len, inst)?;
+            let res = emit_runtime_call(builder, abi, 11, 0, &[data, len])?;
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Sha256 { result, data, len } => {
-            let data = ssa_values.get(data).unwrap();
-            let len = ssa_values.get(len).unwrap();
-            let res = emit_runtime_call(builder, 11, 1, &[*data, *len])?;
+            let data = lookup(ssa_values, data, inst)?;
+            let len = lookup(ssa_values, len, inst)?;
+            let res = emit_runtime_call(builder, abi, 11, 1, &[data, len])?;
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Ripemd160 { result, data, len } => {
-            let data = ssa_values.get(data).unwrap();
-            let len = ssa_values.get(len).unwrap();
-            let res = emit_runtime_call(builder, 11, 2, &[*data, *len])?;
+            let data = lookup(ssa_values, data, inst)?;
+            let len = lookup(ssa_values, len, inst)?;
+            let res = emit_runtime_call(builder, abi, 11, 2, &[data, len])?;
             ssa_values.insert(result.clone(), res);
         }
         Instruction::EcRecover {
@@ -541,11 +665,11 @@ pub fn lower_instruction(
             r,
             s,
         } => {
-            let hash = ssa_values.get(hash).unwrap();
-            let v = ssa_values.get(v).unwrap();
-            let r = ssa_values.get(r).unwrap();
-            let s = ssa_values.get(s).unwrap();
-            let res = emit_runtime_call(builder, 11, 3, &[*hash, *v, *r, *s])?;
+            let hash = lookup(ssa_values, hash, inst)?;
+            let v = lookup(ssa_values, v, inst)?;
+            let r = lookup(ssa_values, r, inst)?;
+            let s = lookup(ssa_values, s, inst)?;
+            let res = emit_runtime_call(builder, abi, 11, 3, &[hash, v, r, s])?;
             ssa_values.insert(result.clone(), res);
         }
 
@@ -554,33 +678,33 @@ pub fn lower_instruction(
             topics,
             data,
         } => {
-            let topics_vals: Vec<_> = topics.iter().map(|t| *ssa_values.get(t).unwrap()).collect();
-            let data_vals: Vec<_> = data.iter().map(|d| *ssa_values.get(d).unwrap()).collect();
-            emit_event(builder, *event, &topics_vals, &data_vals)?;
+            let topics_vals = lookup_all(ssa_values, topics, inst)?;
+            let data_vals = lookup_all(ssa_values, data, inst)?;
+            emit_event(builder, abi, *event, &topics_vals, &data_vals)?;
         }
 
         Instruction::Cast { result, value, to } => {
-            let value = ssa_values.get(value).unwrap();
+            let value = lookup(ssa_values, value, inst)?;
             let clif_type = convert_type(to)?;
-            let res = builder.ins().bitcast(clif_type, MemFlags::new(), *value);
+            let res = builder.ins().bitcast(clif_type, MemFlags::new(), value);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::ZeroExtend { result, value, to } => {
-            let value = ssa_values.get(value).unwrap();
+            let value = lookup(ssa_values, value, inst)?;
             let clif_type = convert_type(to)?;
-            let res = builder.ins().uextend(clif_type, *value);
+            let res = builder.ins().uextend(clif_type, value);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::SignExtend { result, value, to } => {
-            let value = ssa_values.get(value).unwrap();
+            let value = lookup(ssa_values, value, inst)?;
             let clif_type = convert_type(to)?;
-            let res = builder.ins().sextend(clif_type, *value);
+            let res = builder.ins().sextend(clif_type, value);
             ssa_values.insert(result.clone(), res);
         }
         Instruction::Truncate { result, value, to } => {
-            let value = ssa_values.get(value).unwrap();
+            let value = lookup(ssa_values, value, inst)?;
             let clif_type = convert_type(to)?;
-            let res = builder.ins().ireduce(clif_type, *value);
+            let res = builder.ins().ireduce(clif_type, value);
             ssa_values.insert(result.clone(), res);
         }
 
@@ -588,13 +712,13 @@ pub fn lower_instruction(
             condition,
             message: _,
         } => {
-            let cond = ssa_values.get(condition).unwrap();
+            let cond = lookup(ssa_values, condition, inst)?;
             let trap_block = builder.create_block();
             let continue_block = builder.create_block();
 
             builder
                 .ins()
-                .brif(*cond, continue_block, &[], trap_block, &[]);
+                .brif(cond, continue_block, &[], trap_block, &[]);
 
             builder.switch_to_block(trap_block);
             builder.ins().trap(clif_ir::TrapCode::unwrap_user(1));
@@ -605,13 +729,13 @@ pub fn lower_instruction(
             condition,
             message: _,
         } => {
-            let cond = ssa_values.get(condition).unwrap();
+            let cond = lookup(ssa_values, condition, inst)?;
             let trap_block = builder.create_block();
             let continue_block = builder.create_block();
 
             builder
                 .ins()
-                .brif(*cond, continue_block, &[], trap_block, &[]);
+                .brif(cond, continue_block, &[], trap_block, &[]);
 
             builder.switch_to_block(trap_block);
             builder.ins().trap(clif_ir::TrapCode::unwrap_user(2));
@@ -625,16 +749,16 @@ pub fn lower_instruction(
             right,
             ty,
         } => {
-            let left = ssa_values.get(left).unwrap();
-            let right = ssa_values.get(right).unwrap();
+            let left = lookup(ssa_values, left, inst)?;
+            let right = lookup(ssa_values, right, inst)?;
 
             let zero = builder.ins().iconst(convert_type(ty)?, 0);
-            let is_zero = builder.ins().icmp(IntCC::Equal, *right, zero);
+            let is_zero = builder.ins().icmp(IntCC::Equal, right, zero);
             builder
                 .ins()
                 .trapnz(is_zero, clif_ir::TrapCode::unwrap_user(10));
 
-            let res = builder.ins().udiv(*left, *right);
+            let res = builder.ins().udiv(left, right);
             ssa_values.insert(result.clone(), res);
         }
 
@@ -652,35 +776,143 @@ pub fn lower_instruction(
         }
 
         Instruction::MemoryAlloc { result, size } => {
-            let size = ssa_values.get(size).unwrap();
-            let res = emit_runtime_call(builder, 20, 1, &[*size])?;
+            let size = lookup(ssa_values, size, inst)?;
+            let res = emit_runtime_call(builder, abi, 20, 1, &[size])?;
             ssa_values.insert(result.clone(), res);
         }
         Instruction::MemoryCopy { dest, src, size } => {
-            let dest = ssa_values.get(dest).unwrap();
-            let src = ssa_values.get(src).unwrap();
-            let size = ssa_values.get(size).unwrap();
-            emit_runtime_call(builder, 21, 3, &[*dest, *src, *size])?;
+            let dest = lookup(ssa_values, dest, inst)?;
+            let src = lookup(ssa_values, src, inst)?;
+            let size = lookup(ssa_values, size, inst)?;
+            emit_runtime_call(builder, abi, 21, 3, &[dest, src, size])?;
         }
         Instruction::MemorySize { result } => {
-            let res = emit_runtime_call(builder, 22, 0, &[])?;
+            let res = emit_runtime_call(builder, abi, 22, 0, &[])?;
             ssa_values.insert(result.clone(), res);
         }
 
         Instruction::Assign { result, value } => {
-            let value = ssa_values.get(value).unwrap();
-            ssa_values.insert(result.clone(), *value);
+            let value = lookup(ssa_values, value, inst)?;
+            ssa_values.insert(result.clone(), value);
         }
         Instruction::Phi { result, values } => {
             if let Some((_, first_val)) = values.first() {
-                let val = ssa_values.get(first_val).unwrap();
-                ssa_values.insert(result.clone(), *val);
+                let val = lookup(ssa_values, first_val, inst)?;
+                ssa_values.insert(result.clone(), val);
             }
         }
     }
     Ok(())
 }
 
+/* `string` state variables use solc's short/long encoding, so whole-value reads, writes and
+ * deletes of their slot go through runtime helpers that decode into, or encode from, a memory
+ * pointer instead of moving the raw slot word. Returns whether `inst` was handled here. */
+pub fn lower_byte_array_access(
+    inst: &Instruction,
+    byte_arrays: &HashSet<BigUint>,
+    ssa_values: &mut HashMap<Value, clif_ir::Value>,
+    builder: &mut FunctionBuilder,
+    abi: WordAbi,
+) -> Result<bool> {
+    match inst {
+        Instruction::StorageLoad {
+            result,
+            key: key @ StorageKey::Slot(slot),
+        } if byte_arrays.contains(slot) => {
+            let key_val = get_storage_key_value(key, ssa_values, builder, inst)?;
+            let res = emit_runtime_call(builder, abi, 1, 6, &[key_val])?;
+            ssa_values.insert(result.clone(), res);
+        }
+        Instruction::StorageStore {
+            key: key @ StorageKey::Slot(slot),
+            value,
+        } if byte_arrays.contains(slot) => {
+            let key_val = get_storage_key_value(key, ssa_values, builder, inst)?;
+            let value = lookup(ssa_values, value, inst)?;
+            emit_runtime_call_void(builder, abi, 1, 7, &[key_val, value])?;
+        }
+        Instruction::StorageDelete {
+            key: key @ StorageKey::Slot(slot),
+        } if byte_arrays.contains(slot) => {
+            let key_val = get_storage_key_value(key, ssa_values, builder, inst)?;
+            emit_runtime_call_void(builder, abi, 1, 8, &[key_val])?;
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/* Defines the integer, boolean and address constants among `values` that are not defined yet.
+ * Constants are defined where they are first used, so callers drop them from `ssa_values` when
+ * they switch blocks; a constant defined in one block does not dominate its siblings. Other
+ * constants stay undefined and are reported by whatever reads them. */
+pub fn lower_constants<'v>(
+    values: impl IntoIterator<Item = &'v Value>,
+    ssa_values: &mut HashMap<Value, clif_ir::Value>,
+    builder: &mut FunctionBuilder,
+) -> Result<()> {
+    for value in values {
+        let Value::Constant(constant) = value else {
+            continue;
+        };
+        if ssa_values.contains_key(value) {
+            continue;
+        }
+        let (ty, bits) = match constant {
+            Constant::Bool(b) => (types::I8, *b as u128),
+            Constant::Uint(val, width) => {
+                let mut bits = 0u128;
+                for (i, &byte) in val.to_bytes_le().iter().enumerate().take(16) {
+                    bits |= (byte as u128) << (i * 8);
+                }
+                (convert_type(&Type::Uint(*width))?, bits)
+            }
+            Constant::Int(val, width) => {
+                let fill = if val.sign() == num_bigint::Sign::Minus {
+                    0xff
+                } else {
+                    0
+                };
+                let mut bytes = val.to_signed_bytes_le();
+                bytes.resize(16, fill);
+                let mut bits = 0u128;
+                for (i, &byte) in bytes.iter().enumerate().take(16) {
+                    bits |= (byte as u128) << (i * 8);
+                }
+                (convert_type(&Type::Int(*width))?, bits)
+            }
+            Constant::Address(bytes) => {
+                let mut bits = 0u128;
+                for (i, &byte) in bytes.iter().enumerate().take(16) {
+                    bits |= (byte as u128) << (i * 8);
+                }
+                (types::I128, bits)
+            }
+            _ => continue,
+        };
+        let res = iconst_bits(builder, ty, bits);
+        ssa_values.insert(value.clone(), res);
+    }
+    Ok(())
+}
+
+/* `iconst` takes at most 64 bits and must be masked to the width of its type, so 128-bit
+ * constants are built from two halves. */
+fn iconst_bits(builder: &mut FunctionBuilder, ty: types::Type, bits: u128) -> clif_ir::Value {
+    if ty == types::I128 {
+        let lo = builder.ins().iconst(types::I64, bits as u64 as i64);
+        let hi = builder.ins().iconst(types::I64, (bits >> 64) as u64 as i64);
+        return builder.ins().iconcat(lo, hi);
+    }
+    let mask = if ty.bits() >= 64 {
+        u64::MAX
+    } else {
+        (1u64 << ty.bits()) - 1
+    };
+    builder.ins().iconst(ty, (bits as u64 & mask) as i64)
+}
+
 pub fn lower_terminator(
     term: &Terminator,
     ssa_values: &HashMap<Value, clif_ir::Value>,
@@ -688,25 +920,31 @@ pub fn lower_terminator(
     block_map: &std::collections::HashMap<crate::block::BlockId, clif_ir::Block>,
 ) -> Result<()> {
     match term {
-        Terminator::Jump(block_id, ..) => {
-            let block = block_map.get(block_id).unwrap();
-            builder.ins().jump(*block, &[]);
+        Terminator::Jump(block_id, args) => {
+            let block = lookup_block(block_map, *block_id, term)?;
+            let args = lookup_all(ssa_values, args, term)?;
+            builder.ins().jump(block, &args);
         }
         Terminator::Branch {
             condition,
             then_block,
+            then_args,
             else_block,
-            ..
+            else_args,
         } => {
-            let cond = ssa_values.get(condition).unwrap();
-            let then_dest = block_map.get(then_block).unwrap();
-            let else_dest = block_map.get(else_block).unwrap();
-            builder.ins().brif(*cond, *then_dest, &[], *else_dest, &[]);
+            let cond = lookup(ssa_values, condition, term)?;
+            let then_dest = lookup_block(block_map, *then_block, term)?;
+            let then_args = lookup_all(ssa_values, then_args, term)?;
+            let else_dest = lookup_block(block_map, *else_block, term)?;
+            let else_args = lookup_all(ssa_values, else_args, term)?;
+            builder
+                .ins()
+                .brif(cond, then_dest, &then_args, else_dest, &else_args);
         }
         Terminator::Return(value) => {
-            let return_value = value.as_ref().and_then(|v| ssa_values.get(v));
-            if let Some(val) = return_value {
-                builder.ins().return_(&[*val]);
+            if let Some(value) = value {
+                let value = lookup(ssa_values, value, term)?;
+                builder.ins().return_(&[value]);
             } else {
                 builder.ins().return_(&[]);
             }
@@ -716,12 +954,12 @@ pub fn lower_terminator(
             cases,
             default,
         } => {
-            let value = ssa_values.get(value).unwrap();
-            let default_block = block_map.get(default).unwrap();
+            let value = lookup(ssa_values, value, term)?;
+            let default_block = lookup_block(block_map, *default, term)?;
 
             let mut next_block = None;
             for (case_val, case_block_id) in cases.iter().rev() {
-                let case_block = block_map.get(case_block_id).unwrap();
+                let case_block = lookup_block(block_map, *case_block_id, term)?;
 
                 let case_value = match case_val.as_constant() {
                     Some(Constant::Uint(val, _)) => {
@@ -745,20 +983,18 @@ pub fn lower_terminator(
                     builder.ins().iconst(types::I64, case_value)
                 };
                 let case_const = builder.ins().sextend(types::I128, case_const);
-                let cmp = builder.ins().icmp(IntCC::Equal, *value, case_const);
+                let cmp = builder.ins().icmp(IntCC::Equal, value, case_const);
 
                 if let Some(nb) = next_block {
-                    builder.ins().brif(cmp, *case_block, &[], nb, &[]);
+                    builder.ins().brif(cmp, case_block, &[], nb, &[]);
                 } else {
-                    builder
-                        .ins()
-                        .brif(cmp, *case_block, &[], *default_block, &[]);
+                    builder.ins().brif(cmp, case_block, &[], default_block, &[]);
                 }
-                next_block = Some(*case_block);
+                next_block = Some(case_block);
             }
 
             if next_block.is_none() {
-                builder.ins().jump(*default_block, &[]);
+                builder.ins().jump(default_block, &[]);
             }
         }
         Terminator::Revert(_msg) => {
@@ -774,7 +1010,48 @@ pub fn lower_terminator(
     Ok(())
 }
 
-fn convert_type(ty: &Type) -> Result<types::Type> {
+/* The Cranelift value an IR value was lowered to. A value that no parameter or earlier instruction
+ * defined is an error naming the value and the instruction or terminator that read it. */
+fn lookup(
+    ssa_values: &HashMap<Value, clif_ir::Value>,
+    value: &Value,
+    reader: &dyn std::fmt::Debug,
+) -> Result<clif_ir::Value> {
+    ssa_values
+        .get(value)
+        .copied()
+        .ok_or_else(|| IrError::UndefinedValue {
+            value: format!("{:?}", value),
+            instruction: format!("{:?}", reader),
+        })
+}
+
+fn lookup_all(
+    ssa_values: &HashMap<Value, clif_ir::Value>,
+    values: &[Value],
+    reader: &dyn std::fmt::Debug,
+) -> Result<Vec<clif_ir::Value>> {
+    values
+        .iter()
+        .map(|value| lookup(ssa_values, value, reader))
+        .collect()
+}
+
+fn lookup_block(
+    block_map: &HashMap<crate::block::BlockId, clif_ir::Block>,
+    block: crate::block::BlockId,
+    term: &Terminator,
+) -> Result<clif_ir::Block> {
+    block_map
+        .get(&block)
+        .copied()
+        .ok_or_else(|| IrError::UndefinedBlock {
+            block: block.to_string(),
+            instruction: format!("{:?}", term),
+        })
+}
+
+pub(crate) fn convert_type(ty: &Type) -> Result<types::Type> {
     match ty {
         Type::Bool => Ok(types::I8),
         Type::Uint(8) => Ok(types::I8),
@@ -794,10 +1071,11 @@ fn convert_type(ty: &Type) -> Result<types::Type> {
         Type::Bytes20 => Ok(types::I128),
         Type::Bytes32 => Ok(types::I128),
         Type::Bytes(n) if *n <= 32 => Ok(types::I128),
-        _ => Err(IrError::TypeError(format!(
-            "Cannot convert type {:?} to Cranelift",
-            ty
-        ))),
+        _ => Err(IrError::TypeError {
+            instruction: "type lowering".to_string(),
+            expected: "a type with a Cranelift representation".to_string(),
+            found: ty.to_string(),
+        }),
     }
 }
 
@@ -805,17 +1083,14 @@ fn get_location_address(
     location: &crate::values::Location,
     ssa_values: &HashMap<Value, clif_ir::Value>,
     builder: &mut FunctionBuilder,
+    inst: &Instruction,
 ) -> Result<clif_ir::Value> {
     use crate::values::Location;
     match location {
         Location::Memory { base, offset } => {
-            let base_val = ssa_values
-                .get(base)
-                .ok_or_else(|| IrError::InvalidInstruction("Base value not found".into()))?;
-            let offset_val = ssa_values
-                .get(offset)
-                .ok_or_else(|| IrError::InvalidInstruction("Offset value not found".into()))?;
-            Ok(builder.ins().iadd(*base_val, *offset_val))
+            let base_val = lookup(ssa_values, base, inst)?;
+            let offset_val = lookup(ssa_values, offset, inst)?;
+            Ok(builder.ins().iadd(base_val, offset_val))
         }
         Location::Storage { .. }
         | Location::Stack { .. }
@@ -830,6 +1105,7 @@ fn get_storage_key_value(
     key: &StorageKey,
     ssa_values: &HashMap<Value, clif_ir::Value>,
     builder: &mut FunctionBuilder,
+    inst: &Instruction,
 ) -> Result<clif_ir::Value> {
     match key {
         StorageKey::Slot(slot) => {
@@ -842,14 +1118,7 @@ fn get_storage_key_value(
             let val = builder.ins().iconst(types::I64, slot_val);
             Ok(builder.ins().uextend(types::I128, val))
         }
-        StorageKey::Dynamic(val) => ssa_values
-            .get(val)
-            .copied()
-            .ok_or_else(|| IrError::InvalidInstruction("Dynamic key value not found".into())),
-        StorageKey::Computed(val) => ssa_values
-            .get(val)
-            .copied()
-            .ok_or_else(|| IrError::InvalidInstruction("Computed key value not found".into())),
+        StorageKey::Dynamic(val) | StorageKey::Computed(val) => lookup(ssa_values, val, inst),
         StorageKey::MappingKey { base, key } => {
             let bytes = base.to_bytes_le();
             let mut result = 0u128;
@@ -859,11 +1128,9 @@ fn get_storage_key_value(
             let base_val = result as i64;
             let base_const_64 = builder.ins().iconst(types::I64, base_val);
             let base_const = builder.ins().uextend(types::I128, base_const_64);
-            let key_val = ssa_values
-                .get(key)
-                .ok_or_else(|| IrError::InvalidInstruction("Mapping key not found".into()))?;
+            let key_val = lookup(ssa_values, key, inst)?;
 
-            emit_runtime_call(builder, 11, 0, &[*key_val, base_const])
+            emit_runtime_call(builder, abi, 11, 0, &[key_val, base_const])
         }
         StorageKey::ArrayElement { base, index } => {
             let bytes = base.to_bytes_le();
@@ -874,67 +1141,104 @@ fn get_storage_key_value(
             let base_val = result as i64;
             let base_const_64 = builder.ins().iconst(types::I64, base_val);
             let base_const = builder.ins().uextend(types::I128, base_const_64);
-            let index_val = ssa_values
-                .get(index)
-                .ok_or_else(|| IrError::InvalidInstruction("Array index not found".into()))?;
-            Ok(builder.ins().iadd(base_const, *index_val))
+            let index_val = lookup(ssa_values, index, inst)?;
+            /* Elements live at keccak256(base) + index; the runtime derives the data slot. */
+            let data_slot = emit_runtime_call(builder, abi, 1, 5, &[base_const])?;
+            Ok(builder.ins().iadd(data_slot, index_val))
         }
     }
 }
 
 fn emit_runtime_call(
     builder: &mut FunctionBuilder,
+    abi: WordAbi,
     namespace: u32,
     index: u32,
     args: &[clif_ir::Value],
 ) -> Result<clif_ir::Value> {
-    let sig = builder
-        .func
-        .import_signature(cranelift_codegen::ir::Signature {
-            params: args
-                .iter()
-                .map(|_| cranelift_codegen::ir::AbiParam::new(types::I128))
-                .collect(),
-            returns: vec![cranelift_codegen::ir::AbiParam::new(types::I128)],
-            call_conv: cranelift_codegen::isa::CallConv::SystemV,
-        });
-
-    let user_ref = builder
-        .func
-        .declare_imported_user_function(clif_ir::UserExternalName { namespace, index });
-
-    let func_ref = builder
-        .func
-        .import_function(cranelift_codegen::ir::ExtFuncData {
-            name: cranelift_codegen::ir::ExternalName::user(user_ref),
-            signature: sig,
-            colocated: false,
-        });
-
-    let call = builder.ins().call(func_ref, args);
-    Ok(builder.inst_results(call)[0])
+    let name = clif_ir::UserExternalName { namespace, index };
+    emit_word_call(builder, abi, name, args, true)
+        .ok_or_else(|| IrError::CraneliftError(format!("Runtime call {} returned no word", name)))
 }
 
 fn emit_runtime_call_void(
     builder: &mut FunctionBuilder,
+    abi: WordAbi,
     namespace: u32,
     index: u32,
     args: &[clif_ir::Value],
 ) -> Result<()> {
+    let name = clif_ir::UserExternalName { namespace, index };
+    emit_word_call(builder, abi, name, args, false);
+    Ok(())
+}
+
+/* Calls a runtime helper with `args` as words, returning one if `returns_word`. Inside a function
+ * every word is an i128; `abi` decides the shape it takes at the call. */
+fn emit_word_call(
+    builder: &mut FunctionBuilder,
+    abi: WordAbi,
+    name: clif_ir::UserExternalName,
+    args: &[clif_ir::Value],
+    returns_word: bool,
+) -> Option<clif_ir::Value> {
+    let mut params = Vec::new();
+    let mut call_args = Vec::new();
+    for &arg in args {
+        let word = if builder.func.dfg.value_type(arg) != types::I128 {
+            builder.ins().uextend(types::I128, arg)
+        } else {
+            arg
+        };
+        match abi {
+            WordAbi::I128 => {
+                params.push(types::I128);
+                call_args.push(word);
+            }
+            WordAbi::I64Pair => {
+                let (lo, hi) = builder.ins().isplit(word);
+                params.extend([types::I64, types::I64]);
+                call_args.extend([lo, hi]);
+            }
+            WordAbi::Handle => {
+                let slot = word_slot(builder);
+                builder.ins().stack_store(word, slot, 0);
+                params.extend([types::I64, types::I64]);
+                call_args.extend(word_handle(builder, slot));
+            }
+        }
+    }
+
+    let mut returns = Vec::new();
+    let mut result_slot = None;
+    if returns_word {
+        match abi {
+            WordAbi::I128 => returns.push(types::I128),
+            WordAbi::I64Pair => returns.extend([types::I64, types::I64]),
+            WordAbi::Handle => {
+                let slot = word_slot(builder);
+                params.extend([types::I64, types::I64]);
+                call_args.extend(word_handle(builder, slot));
+                result_slot = Some(slot);
+            }
+        }
+    }
+
     let sig = builder
         .func
         .import_signature(cranelift_codegen::ir::Signature {
-            params: args
-                .iter()
-                .map(|_| cranelift_codegen::ir::AbiParam::new(types::I128))
+            params: params
+                .into_iter()
+                .map(cranelift_codegen::ir::AbiParam::new)
+                .collect(),
+            returns: returns
+                .into_iter()
+                .map(cranelift_codegen::ir::AbiParam::new)
                 .collect(),
-            returns: vec![],
             call_conv: cranelift_codegen::isa::CallConv::SystemV,
         });
 
-    let user_ref = builder
-        .func
-        .declare_imported_user_function(clif_ir::UserExternalName { namespace, index });
+    let user_ref = builder.func.declare_imported_user_function(name);
 
     let func_ref = builder
         .func
@@ -944,12 +1248,37 @@ fn emit_runtime_call_void(
             colocated: false,
         });
 
-    builder.ins().call(func_ref, args);
-    Ok(())
+    let call = builder.ins().call(func_ref, &call_args);
+    if !returns_word {
+        return None;
+    }
+    let results = builder.inst_results(call).to_vec();
+    Some(match abi {
+        WordAbi::I128 => results[0],
+        WordAbi::I64Pair => builder.ins().iconcat(results[0], results[1]),
+        WordAbi::Handle => builder.ins().stack_load(types::I128, result_slot?, 0),
+    })
+}
+
+fn word_slot(builder: &mut FunctionBuilder) -> clif_ir::StackSlot {
+    builder.create_sized_stack_slot(clif_ir::StackSlotData::new(
+        clif_ir::StackSlotKind::ExplicitSlot,
+        WORD_BYTES,
+        4,
+    ))
+}
+
+/* The pointer and length a handle passes for the word in `slot`. */
+fn word_handle(builder: &mut FunctionBuilder, slot: clif_ir::StackSlot) -> [clif_ir::Value; 2] {
+    let ptr = builder.ins().stack_addr(types::I64, slot, 0);
+    let len = builder.ins().iconst(types::I64, WORD_BYTES as i64);
+    [ptr, len]
 }
 
 fn emit_call(
     builder: &mut FunctionBuilder,
+    callees: &mut CalleeNames,
+    abi: WordAbi,
     target: &CallTarget,
     args: &[clif_ir::Value],
     value: Option<clif_ir::Value>,
@@ -969,10 +1298,7 @@ fn emit_call(
 
             let user_ref = builder
                 .func
-                .declare_imported_user_function(clif_ir::UserExternalName {
-                    namespace: 4,
-                    index: name.len() as u32,
-                });
+                .declare_imported_user_function(callees.internal(name));
 
             let func_ref = builder
                 .func
@@ -1011,10 +1337,13 @@ fn emit_call(
                 all_args.push(val);
             }
             all_args.extend_from_slice(args);
-            emit_runtime_call(builder, 6, 0, &all_args)
+            emit_runtime_call(builder, abi, 6, 0, &all_args)
+        }
+        CallTarget::Library(name) => {
+            let name = callees.library(name);
+            emit_runtime_call(builder, abi, name.namespace, name.index, args)
         }
-        CallTarget::Library(name) => emit_runtime_call(builder, 5, name.len() as u32, args),
-        CallTarget::Builtin(_) => emit_runtime_call(builder, 7, 0, args),
+        CallTarget::Builtin(_) => emit_runtime_call(builder, abi, 7, 0, args),
     }
 }
 
@@ -1026,7 +1355,7 @@ fn emit_get_context(builder: &mut FunctionBuilder, var: ContextVariable) -> Resu
         ContextVariable::MsgSig => 84,
         ContextVariable::BlockNumber => 116,
         ContextVariable::BlockTimestamp => 148,
-        ContextVariable::BlockDifficulty => 180,
+        ContextVariable::BlockDifficulty | ContextVariable::PrevRandao => 180,
         ContextVariable::BlockGasLimit => 212,
         ContextVariable::BlockCoinbase => 244,
         ContextVariable::ChainId => 264,
@@ -1036,9 +1365,19 @@ fn emit_get_context(builder: &mut FunctionBuilder, var: ContextVariable) -> Resu
         ContextVariable::GasLeft => 380,
         ContextVariable::ThisAddress => 412,
         ContextVariable::ThisBalance => 432,
+        ContextVariable::BlobBaseFee => 464,
     };
 
-    let ctx_ptr = builder.block_params(builder.current_block().unwrap())[0];
+    /* The runtime passes its context pointer as the first parameter of every block. */
+    let ctx_ptr = builder
+        .current_block()
+        .and_then(|block| builder.block_params(block).first().copied())
+        .ok_or_else(|| {
+            IrError::InvalidInstruction(format!(
+                "{:?} read outside a block with a context parameter",
+                var
+            ))
+        })?;
     Ok(builder.ins().load(
         types::I128,
         MemFlags::trusted(),
@@ -1049,6 +1388,7 @@ fn emit_get_context(builder: &mut FunctionBuilder, var: ContextVariable) -> Resu
 
 fn emit_event(
     builder: &mut FunctionBuilder,
+    abi: WordAbi,
     event_id: crate::contract::EventId,
     topics: &[clif_ir::Value],
     data: &[clif_ir::Value],
@@ -1080,6 +1420,6 @@ fn emit_event(
         })
         .collect();
 
-    emit_runtime_call_void(builder, 12, 0, &args_i128)?;
+    emit_runtime_call_void(builder, abi, 12, 0, &args_i128)?;
     Ok(())
 }
@@ -9,6 +9,6 @@ pub mod context;
 pub mod lowering;
 pub mod module;
 
-pub use context::CodegenContext;
+pub use context::{CalleeNames, CodegenContext, WordAbi};
 pub use lowering::lower_instruction;
-pub use module::ModuleBuilder;
+pub use module::{compile_contract, CompiledModule, ModuleBuilder};
@@ -1,23 +1,43 @@
 use cranelift::prelude::EntityRef;
-use cranelift_codegen::ir::Function;
+use cranelift_codegen::ir::{ExternalName, Function, UserExternalName};
 use cranelift_codegen::isa;
 use cranelift_codegen::settings;
 use cranelift_codegen::Context;
 use cranelift_frontend::Variable;
-use cranelift_module::Module;
+use cranelift_module::{FuncId, FuncOrDataId, Linkage, Module};
 use cranelift_object::{ObjectBuilder, ObjectModule};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use crate::{
-    codegen::context::CodegenContext,
-    codegen::lowering::{lower_instruction, lower_terminator},
+    codegen::context::{CalleeNames, CodegenContext, WordAbi, INTERNAL_NAMESPACE},
+    codegen::lowering::{
+        convert_type, lower_byte_array_access, lower_constants, lower_instruction, lower_terminator,
+    },
     contract::Contract,
-    values::VarId,
+    values::{BlockParamId, Value, VarId},
     IrError, Result,
 };
 
+/* A contract compiled to a relocatable object file. */
+#[derive(Debug, Clone)]
+pub struct CompiledModule {
+    pub object: Vec<u8>,
+    /* The symbols the object defines, one per contract function. */
+    pub functions: Vec<String>,
+    /* The symbols the object calls but does not define: runtime helpers, libraries and internal
+     * functions of other contracts. */
+    pub imports: Vec<String>,
+}
+
+/* Compiles every function of `contract` for the default target. */
+pub fn compile_contract(contract: &Contract) -> Result<CompiledModule> {
+    ModuleBuilder::new()?.compile_contract(contract)
+}
+
 pub struct ModuleBuilder {
     module: ObjectModule,
+    callees: CalleeNames,
+    word_abi: WordAbi,
 }
 
 impl ModuleBuilder {
@@ -39,23 +59,38 @@ impl ModuleBuilder {
         .unwrap();
         let module = ObjectModule::new(object_builder);
 
-        Ok(Self { module })
+        Ok(Self {
+            module,
+            callees: CalleeNames::new(),
+            word_abi: WordAbi::default(),
+        })
+    }
+
+    pub fn with_word_abi(mut self, word_abi: WordAbi) -> Self {
+        self.word_abi = word_abi;
+        self
     }
 
-    pub fn compile_contract(mut self, contract: &Contract) -> Result<Vec<u8>> {
+    pub fn callees(&self) -> &CalleeNames {
+        &self.callees
+    }
+
+    pub fn compile_contract(mut self, contract: &Contract) -> Result<CompiledModule> {
         let mut func_ids = HashMap::new();
+        let mut linked = HashMap::new();
+        let mut imports = BTreeSet::new();
 
         for (name, function) in &contract.functions {
             let mut sig = self.module.make_signature();
             for param in &function.signature.params {
-                sig.params.push(cranelift_codegen::ir::AbiParam::new(
-                    param.param_type.to_cranelift().unwrap(),
-                ));
+                sig.params
+                    .push(cranelift_codegen::ir::AbiParam::new(convert_type(
+                        &param.param_type,
+                    )?));
             }
             for ret in &function.signature.returns {
-                sig.returns.push(cranelift_codegen::ir::AbiParam::new(
-                    ret.to_cranelift().unwrap(),
-                ));
+                sig.returns
+                    .push(cranelift_codegen::ir::AbiParam::new(convert_type(ret)?));
             }
 
             let func_id = self
@@ -65,8 +100,18 @@ impl ModuleBuilder {
                     IrError::CraneliftError(format!("Failed to declare function: {}", e))
                 })?;
             func_ids.insert(name.clone(), func_id);
+            linked.insert(function.name().to_string(), func_id);
+            /* The contract's own functions take the first internal names, in declaration order,
+             * whether or not anything calls them. */
+            self.callees.internal(function.name());
         }
 
+        let byte_arrays: HashSet<_> = contract
+            .storage_layout
+            .byte_array_slots()
+            .cloned()
+            .collect();
+
         for (name, function) in &contract.functions {
             let func_id = func_ids.get(name).unwrap();
             let mut clif_func = Function::new();
@@ -77,16 +122,40 @@ impl ModuleBuilder {
                 .signature
                 .clone();
 
-            let mut ctx = CodegenContext::new(&mut clif_func);
+            let mut ctx = CodegenContext::new(&mut clif_func).with_word_abi(self.word_abi);
+            let abi = ctx.word_abi;
             let mut func_builder = ctx.func_builder();
 
             let mut block_map = HashMap::new();
             let mut variables = HashMap::new();
             let mut ssa_values = HashMap::new();
 
-            for (block_id, _) in &function.body.blocks {
+            /* The entry block's parameters are the function's; every other block gets Cranelift
+             * parameters for its own, which the jumps and branches into it fill. */
+            for (block_id, block) in &function.body.blocks {
                 let clif_block = func_builder.create_block();
                 block_map.insert(*block_id, clif_block);
+                if *block_id == function.body.entry_block {
+                    continue;
+                }
+                for (index, param) in block.params.iter().enumerate() {
+                    let ty = param
+                        .param_type
+                        .to_cranelift()
+                        .ok_or_else(|| IrError::TypeError {
+                            instruction: format!("{} parameter {}", block_id, param.name),
+                            expected: "a type with a Cranelift representation".to_string(),
+                            found: param.param_type.to_string(),
+                        })?;
+                    let value = func_builder.append_block_param(clif_block, ty);
+                    ssa_values.insert(
+                        Value::BlockParam(BlockParamId {
+                            block: *block_id,
+                            index: index as u32,
+                        }),
+                        value,
+                    );
+                }
             }
 
             let entry_clif_block = block_map.get(&function.body.entry_block).unwrap();
@@ -95,7 +164,7 @@ impl ModuleBuilder {
 
             for (i, param) in function.signature.params.iter().enumerate() {
                 let var = Variable::new(i);
-                func_builder.declare_var(var, param.param_type.to_cranelift().unwrap());
+                func_builder.declare_var(var, convert_type(&param.param_type)?);
                 let val = func_builder.block_params(*entry_clif_block)[i];
                 func_builder.def_var(var, val);
                 variables.insert(VarId(i as u32), var);
@@ -108,12 +177,34 @@ impl ModuleBuilder {
             for (block_id, block) in &function.body.blocks {
                 let clif_block = *block_map.get(block_id).unwrap();
                 func_builder.switch_to_block(clif_block);
+                ssa_values.retain(|value, _| !matches!(value, Value::Constant(_)));
 
                 for inst in &block.instructions {
-                    lower_instruction(inst, &variables, &mut ssa_values, &mut func_builder)?;
+                    lower_constants(inst.operands(), &mut ssa_values, &mut func_builder)?;
+                    if !lower_byte_array_access(
+                        inst,
+                        &byte_arrays,
+                        &mut ssa_values,
+                        &mut func_builder,
+                        abi,
+                    )? {
+                        lower_instruction(
+                            inst,
+                            &variables,
+                            &mut ssa_values,
+                            &mut func_builder,
+                            &mut self.callees,
+                            abi,
+                        )?;
+                    }
                 }
 
                 if !matches!(block.terminator, crate::block::Terminator::Invalid) {
+                    lower_constants(
+                        block.terminator.operands(),
+                        &mut ssa_values,
+                        &mut func_builder,
+                    )?;
                     lower_terminator(
                         &block.terminator,
                         &ssa_values,
@@ -125,6 +216,7 @@ impl ModuleBuilder {
 
             func_builder.seal_all_blocks();
             func_builder.finalize();
+            self.link_calls(&mut clif_func, &linked, &mut imports)?;
 
             let mut context = Context::for_function(clif_func);
             self.module
@@ -139,6 +231,74 @@ impl ModuleBuilder {
             .emit()
             .map_err(|e| IrError::CraneliftError(format!("Failed to emit object: {}", e)))?;
 
-        Ok(obj_bytes)
+        Ok(CompiledModule {
+            object: obj_bytes,
+            functions: contract.functions.keys().cloned().collect(),
+            imports: imports.into_iter().collect(),
+        })
+    }
+
+    /* Lowering names callees by runtime namespace; the object writer only knows the module's own
+     * declarations. Calls to functions of the contract point at their definitions and everything
+     * else at an imported symbol, declared with the signature of its first call. */
+    fn link_calls(
+        &mut self,
+        func: &mut Function,
+        linked: &HashMap<String, FuncId>,
+        imports: &mut BTreeSet<String>,
+    ) -> Result<()> {
+        let mut signatures = HashMap::new();
+        for data in func.dfg.ext_funcs.values() {
+            if let ExternalName::User(user_ref) = data.name {
+                signatures
+                    .entry(user_ref)
+                    .or_insert_with(|| func.dfg.signatures[data.signature].clone());
+            }
+        }
+
+        let names: Vec<_> = func
+            .params
+            .user_named_funcs()
+            .iter()
+            .map(|(user_ref, name)| (user_ref, name.clone()))
+            .collect();
+        for (user_ref, name) in names {
+            /* Only internal and library calls have callee names; the rest are runtime helpers. */
+            let symbol = match self.callees.resolve(&name) {
+                Some(callee) if name.namespace == INTERNAL_NAMESPACE => {
+                    if let Some(func_id) = linked.get(callee) {
+                        func.params.reset_user_func_name(
+                            user_ref,
+                            UserExternalName::new(0, func_id.as_u32()),
+                        );
+                        continue;
+                    }
+                    callee.to_string()
+                }
+                Some(callee) => callee.to_string(),
+                None => format!("thalir_rt_{}_{}", name.namespace, name.index),
+            };
+            let func_id = match self.module.get_name(&symbol) {
+                Some(FuncOrDataId::Func(func_id)) => func_id,
+                _ => {
+                    let signature = signatures
+                        .get(&user_ref)
+                        .cloned()
+                        .unwrap_or_else(|| self.module.make_signature());
+                    self.module
+                        .declare_function(&symbol, Linkage::Import, &signature)
+                        .map_err(|e| {
+                            IrError::CraneliftError(format!(
+                                "Failed to declare import {}: {}",
+                                symbol, e
+                            ))
+                        })?
+                }
+            };
+            imports.insert(symbol);
+            func.params
+                .reset_user_func_name(user_ref, UserExternalName::new(0, func_id.as_u32()));
+        }
+        Ok(())
     }
 }
@@ -1,8 +1,13 @@
+use crate::extensions::ByteArrayEncoding;
 use crate::function::Function;
+use crate::metadata::{MappingTag, NatSpec};
 use crate::source_location::SourceFiles;
-use crate::types::Type;
+use crate::types::{StructDefinition, Type, TypeRegistry};
+use crate::values::{ConstantPool, SourceLocation};
+use crate::version::{EvmVersion, VersionPragma};
 use indexmap::IndexMap;
 use num_bigint::BigUint;
+use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +46,15 @@ impl Contract {
     pub fn get_function(&self, name: &str) -> Option<&Function> {
         self.functions.get(name)
     }
+
+    /* Constants from every function body, pooled across the whole contract. */
+    pub fn constant_pool(&self) -> ConstantPool {
+        let mut pool = ConstantPool::new();
+        for function in self.functions.values() {
+            function.body.collect_constants(&mut pool);
+        }
+        pool
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -51,6 +65,29 @@ pub struct ContractMetadata {
     pub source_hash: Option<[u8; 32]>,
     pub source_file: Option<String>,
     pub source_code: Option<String>,
+    #[serde(default)]
+    pub natspec: Option<NatSpec>,
+    #[serde(default)]
+    pub pragma: Option<VersionPragma>,
+    #[serde(default)]
+    pub evm_version: Option<EvmVersion>,
+    /* Direct bases in declaration order, as written in the `is` list. */
+    #[serde(default)]
+    pub bases: Vec<String>,
+    #[serde(default)]
+    pub location: Option<SourceLocation>,
+    /* Semantic roles recognised for mapping state variables, filled in by `MappingRoleAnalysis`. */
+    #[serde(default)]
+    pub mapping_roles: Vec<MappingTag>,
+}
+
+impl ContractMetadata {
+    pub fn checked_arithmetic(&self) -> bool {
+        self.pragma
+            .as_ref()
+            .map(VersionPragma::checked_arithmetic)
+            .unwrap_or(true)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -78,6 +115,10 @@ pub struct StorageLayout {
     pub mappings: Vec<MappingLayout>,
     pub arrays: Vec<ArrayLayout>,
     pub structs: Vec<StructLayout>,
+    /* Variables of the linearized bases as they sit in this contract, most basic first. `slots`
+     * holds only the contract's own variables, laid out after these. */
+    #[serde(default)]
+    pub inherited: Vec<StorageSlot>,
 }
 
 impl StorageLayout {
@@ -88,8 +129,136 @@ impl StorageLayout {
             var_type: ty,
             name,
             packed_with: Vec::new(),
+            location: None,
+            is_private: false,
         });
     }
+
+    /* Appends a state variable where solc would put it: value types share a slot while they fit,
+     * filled from the low-order end, while structs, arrays, mappings and strings start a fresh slot
+     * and push whatever follows onto the next one. Returns the slot and byte offset used. */
+    pub fn allocate(&mut self, name: String, ty: Type, types: &TypeRegistry) -> (BigUint, u8) {
+        let (slot, offset) = self.next_position(&ty, types);
+        let entry = PackedVariable {
+            name: name.clone(),
+            offset,
+            size: packed_size(&ty).unwrap_or(32) as u8,
+            var_type: ty.clone(),
+        };
+        let mut packed_with: Vec<PackedVariable> = self
+            .inherited
+            .iter()
+            .filter(|var| var.slot == slot)
+            .map(StorageSlot::as_packed)
+            .collect();
+        for var in self.slots.iter_mut().filter(|var| var.slot == slot) {
+            var.packed_with.push(entry.clone());
+            packed_with.push(var.as_packed());
+        }
+        self.slots.push(StorageSlot {
+            slot: slot.clone(),
+            offset,
+            var_type: ty,
+            name,
+            packed_with,
+            location: None,
+            is_private: false,
+        });
+        (slot, offset)
+    }
+
+    /* The fields of a struct laid out from slot 0, so each slot is an offset from wherever the
+     * struct itself starts. */
+    pub fn of_struct(def: &StructDefinition, types: &TypeRegistry) -> Self {
+        let mut layout = Self::default();
+        for field in &def.fields {
+            layout.allocate(field.name.clone(), field.field_type.clone(), types);
+        }
+        layout
+    }
+
+    fn last(&self) -> Option<&StorageSlot> {
+        self.slots.last().or(self.inherited.last())
+    }
+
+    fn next_position(&self, ty: &Type, types: &TypeRegistry) -> (BigUint, u8) {
+        let Some(last) = self.last() else {
+            return (BigUint::from(0u32), 0);
+        };
+        let used = last.offset as usize;
+        match (packed_size(&last.var_type), packed_size(ty)) {
+            (Some(last_size), Some(size)) if used + last_size + size <= 32 => {
+                (last.slot.clone(), (used + last_size) as u8)
+            }
+            _ => (self.end_slot(types), 0),
+        }
+    }
+
+    /* The first slot past every variable laid out so far. */
+    pub fn end_slot(&self, types: &TypeRegistry) -> BigUint {
+        match self.last() {
+            Some(last) => &last.slot + BigUint::from(slot_count(&last.var_type, types)),
+            None => BigUint::from(0u32),
+        }
+    }
+
+    /* Slots of `string` variables, which use solc's short/long byte-array encoding. Dynamic
+     * `bytes` share the encoding but resolve to `bytes32` in the IR and cannot be told apart. */
+    pub fn byte_array_slots(&self) -> impl Iterator<Item = &BigUint> {
+        self.slots
+            .iter()
+            .filter(|var| matches!(var.var_type, Type::String))
+            .map(|var| &var.slot)
+    }
+
+    /* Whether `word`, read from `slot`, is a byte array holding its data inline. `None` when no
+     * byte array lives at `slot` or the word is not a valid encoding. */
+    pub fn holds_short_data(&self, slot: &BigUint, word: &BigUint) -> Option<bool> {
+        if !self.byte_array_slots().any(|s| s == slot) {
+            return None;
+        }
+        ByteArrayEncoding::decode(word).map(|encoding| encoding.is_short())
+    }
+
+    /* Every variable whose bytes live in `slot`, lowest offset first. */
+    pub fn variables_in(&self, slot: &BigUint) -> Vec<&StorageSlot> {
+        let mut vars: Vec<&StorageSlot> =
+            self.slots.iter().filter(|var| &var.slot == slot).collect();
+        vars.sort_by_key(|var| var.offset);
+        vars
+    }
+}
+
+/* Bytes a value type takes when packed; `None` for types that always own whole slots. */
+pub fn packed_size(ty: &Type) -> Option<usize> {
+    match ty {
+        Type::Bool | Type::Enum(_) => Some(1),
+        Type::Uint(bits) | Type::Int(bits) => Some((*bits as usize).div_ceil(8)),
+        Type::Address | Type::Contract(_) | Type::Bytes20 => Some(20),
+        Type::Bytes(n) => Some(*n as usize),
+        Type::Bytes4 => Some(4),
+        Type::Bytes32 => Some(32),
+        Type::Function(_) => Some(24),
+        _ => None,
+    }
+}
+
+fn slot_count(ty: &Type, types: &TypeRegistry) -> usize {
+    match ty {
+        Type::Array(elem, Some(len)) => match packed_size(elem) {
+            Some(size) => len.div_ceil(32 / size.max(1)),
+            None => len * slot_count(elem, types),
+        },
+        Type::Struct(id) => match types.structs.get(id) {
+            Some(def) => StorageLayout::of_struct(def, types)
+                .end_slot(types)
+                .to_usize()
+                .unwrap_or(1)
+                .max(1),
+            None => 1,
+        },
+        _ => 1,
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -99,6 +268,22 @@ pub struct StorageSlot {
     pub var_type: Type,
     pub name: String,
     pub packed_with: Vec<PackedVariable>,
+    #[serde(default)]
+    pub location: Option<SourceLocation>,
+    /* Private variables are invisible to children, which may declare the same name. */
+    #[serde(default)]
+    pub is_private: bool,
+}
+
+impl StorageSlot {
+    fn as_packed(&self) -> PackedVariable {
+        PackedVariable {
+            name: self.name.clone(),
+            offset: self.offset,
+            size: packed_size(&self.var_type).unwrap_or(32) as u8,
+            var_type: self.var_type.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,3 +1,12 @@
+/*! Shared cursor API for walking and editing function bodies.
+ *
+ * Scanners, pattern matchers and rewriting passes all need the same notion of "where am I in
+ * this function". Every cursor in the crate speaks `IRCursor` for navigation and anchoring;
+ * cursors that own mutable blocks additionally implement `IRCursorMut`. Inserted instructions
+ * land before the cursor, so repeated inserts keep their program order. Edits are reported to an
+ * optional `EditListener` so derived analyses can follow along instead of being rebuilt.
+ */
+
 use crate::{
     block::{BasicBlock, BlockId, Terminator},
     function::Function,
@@ -6,183 +15,333 @@ use crate::{
     IrError, Result,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum CursorPosition {
+    #[default]
     Nowhere,
     Before(BlockId),
     After(BlockId),
     At(BlockId, usize),
 }
 
-impl Default for CursorPosition {
-    fn default() -> Self {
-        CursorPosition::Nowhere
+impl CursorPosition {
+    pub fn block(&self) -> Option<BlockId> {
+        match *self {
+            CursorPosition::Before(block)
+            | CursorPosition::After(block)
+            | CursorPosition::At(block, _) => Some(block),
+            CursorPosition::Nowhere => None,
+        }
     }
 }
 
-pub struct FuncCursor<'a> {
-    position: CursorPosition,
-    function: &'a mut Function,
-}
+pub trait IRCursor {
+    fn position(&self) -> CursorPosition;
 
-impl<'a> FuncCursor<'a> {
-    pub fn new(function: &'a mut Function) -> Self {
-        Self {
-            position: CursorPosition::Nowhere,
-            function,
+    fn set_position(&mut self, pos: CursorPosition);
+
+    fn block(&self, id: BlockId) -> Option<&BasicBlock>;
+
+    fn layout(&self) -> Vec<BlockId>;
+
+    fn current_block(&self) -> Option<BlockId> {
+        self.position().block()
+    }
+
+    fn current_block_ref(&self) -> Option<&BasicBlock> {
+        self.block(self.current_block()?)
+    }
+
+    fn current_inst(&self) -> Option<&Instruction> {
+        match self.position() {
+            CursorPosition::At(block, idx) => self.block(block)?.instructions.get(idx),
+            _ => None,
         }
     }
 
-    pub fn position(&self) -> CursorPosition {
-        self.position
+    fn window(&self) -> &[Instruction] {
+        match self.position() {
+            CursorPosition::At(block, idx) => self
+                .block(block)
+                .and_then(|b| b.instructions.get(idx..))
+                .unwrap_or(&[]),
+            CursorPosition::Before(block) => self
+                .block(block)
+                .map(|b| b.instructions.as_slice())
+                .unwrap_or(&[]),
+            _ => &[],
+        }
     }
 
-    pub fn set_position(&mut self, pos: CursorPosition) {
-        self.position = pos;
+    fn goto_top(&mut self, block: BlockId) {
+        self.set_position(CursorPosition::Before(block));
     }
 
-    pub fn goto_top(&mut self, block: BlockId) {
-        self.position = CursorPosition::Before(block);
+    fn goto_bottom(&mut self, block: BlockId) {
+        self.set_position(CursorPosition::After(block));
     }
 
-    pub fn goto_bottom(&mut self, block: BlockId) {
-        self.position = CursorPosition::After(block);
+    fn goto_inst(&mut self, block: BlockId, index: usize) {
+        self.set_position(CursorPosition::At(block, index));
     }
 
-    pub fn goto_inst(&mut self, block: BlockId, index: usize) {
-        self.position = CursorPosition::At(block, index);
+    fn goto_first_inst(&mut self, block: BlockId) {
+        let len = match self.block(block) {
+            Some(b) => b.instructions.len(),
+            None => return,
+        };
+        if len > 0 {
+            self.goto_inst(block, 0);
+        } else {
+            self.goto_bottom(block);
+        }
     }
 
-    pub fn next_inst(&mut self) -> Option<()> {
-        match self.position {
-            CursorPosition::Nowhere => None,
-            CursorPosition::Before(block) => {
-                if self.get_block(block)?.instructions.is_empty() {
-                    self.position = CursorPosition::After(block);
-                } else {
-                    self.position = CursorPosition::At(block, 0);
-                }
-                Some(())
-            }
-            CursorPosition::At(block, idx) => {
-                let block_data = self.get_block(block)?;
-                if idx + 1 < block_data.instructions.len() {
-                    self.position = CursorPosition::At(block, idx + 1);
-                } else {
-                    self.position = CursorPosition::After(block);
-                }
-                Some(())
-            }
-            CursorPosition::After(_) => None,
+    fn goto_last_inst(&mut self, block: BlockId) {
+        let len = match self.block(block) {
+            Some(b) => b.instructions.len(),
+            None => return,
+        };
+        if len > 0 {
+            self.goto_inst(block, len - 1);
+        } else {
+            self.goto_bottom(block);
         }
     }
 
-    pub fn prev_inst(&mut self) -> Option<()> {
-        match self.position {
-            CursorPosition::Nowhere => None,
-            CursorPosition::Before(_) => None,
-            CursorPosition::At(block, 0) => {
-                self.position = CursorPosition::Before(block);
-                Some(())
-            }
+    fn next_inst(&mut self) -> Option<&Instruction> {
+        match self.position() {
+            CursorPosition::Before(block) => self.goto_first_inst(block),
             CursorPosition::At(block, idx) => {
-                self.position = CursorPosition::At(block, idx - 1);
-                Some(())
-            }
-            CursorPosition::After(block) => {
-                let block_data = self.get_block(block)?;
-                if block_data.instructions.is_empty() {
-                    self.position = CursorPosition::Before(block);
+                let len = self.block(block)?.instructions.len();
+                if idx + 1 < len {
+                    self.goto_inst(block, idx + 1);
                 } else {
-                    self.position = CursorPosition::At(block, block_data.instructions.len() - 1);
+                    self.goto_bottom(block);
                 }
-                Some(())
             }
+            CursorPosition::After(_) | CursorPosition::Nowhere => return None,
         }
+        self.current_inst()
     }
 
-    pub fn insert_inst(&mut self, inst: Instruction) -> Result<()> {
-        match self.position {
-            CursorPosition::Nowhere => {
-                return Err(IrError::BuilderError(
-                    "Cannot insert at Nowhere position".into(),
-                ));
-            }
-            CursorPosition::Before(block) => {
-                let block_data = self
-                    .get_block_mut(block)
-                    .ok_or_else(|| IrError::BuilderError(format!("Block {:?} not found", block)))?;
-                block_data.instructions.insert(0, inst);
-
-                self.position = CursorPosition::At(block, 0);
-            }
-            CursorPosition::At(block, idx) => {
-                let block_data = self
-                    .get_block_mut(block)
-                    .ok_or_else(|| IrError::BuilderError(format!("Block {:?} not found", block)))?;
-                block_data.instructions.insert(idx, inst);
-            }
-            CursorPosition::After(block) => {
-                let block_data = self
-                    .get_block_mut(block)
-                    .ok_or_else(|| IrError::BuilderError(format!("Block {:?} not found", block)))?;
-                let new_idx = block_data.instructions.len();
-                block_data.instructions.push(inst);
-
-                self.position = CursorPosition::At(block, new_idx);
-            }
+    fn prev_inst(&mut self) -> Option<&Instruction> {
+        match self.position() {
+            CursorPosition::After(block) => self.goto_last_inst(block),
+            CursorPosition::At(block, 0) => self.goto_top(block),
+            CursorPosition::At(block, idx) => self.goto_inst(block, idx - 1),
+            CursorPosition::Before(_) | CursorPosition::Nowhere => return None,
         }
-        Ok(())
+        self.current_inst()
     }
 
-    pub fn set_terminator(&mut self, term: Terminator) -> Result<()> {
-        let block_id = match self.position {
-            CursorPosition::Before(b) | CursorPosition::At(b, _) | CursorPosition::After(b) => b,
+    fn next_block(&mut self) -> Option<BlockId> {
+        let current = self.current_block()?;
+        let layout = self.layout();
+        let idx = layout.iter().position(|&b| b == current)?;
+        let next = *layout.get(idx + 1)?;
+        self.goto_top(next);
+        Some(next)
+    }
+
+    fn prev_block(&mut self) -> Option<BlockId> {
+        let current = self.current_block()?;
+        let layout = self.layout();
+        let idx = layout.iter().position(|&b| b == current)?;
+        let prev = *layout.get(idx.checked_sub(1)?)?;
+        self.goto_top(prev);
+        Some(prev)
+    }
+
+    fn find_next(&mut self, pred: impl Fn(&Instruction) -> bool) -> Option<&Instruction>
+    where
+        Self: Sized,
+    {
+        let block = self.current_block()?;
+        let start = match self.position() {
+            CursorPosition::Before(_) => 0,
+            CursorPosition::At(_, idx) => idx + 1,
+            _ => return None,
+        };
+        let found = self
+            .block(block)?
+            .instructions
+            .iter()
+            .enumerate()
+            .skip(start)
+            .find(|(_, inst)| pred(inst))
+            .map(|(idx, _)| idx)?;
+        self.goto_inst(block, found);
+        self.current_inst()
+    }
+
+    fn find_prev(&mut self, pred: impl Fn(&Instruction) -> bool) -> Option<&Instruction>
+    where
+        Self: Sized,
+    {
+        let block = self.current_block()?;
+        let end = match self.position() {
+            CursorPosition::After(_) => self.block(block)?.instructions.len(),
+            CursorPosition::At(_, idx) => idx,
+            _ => return None,
+        };
+        let found = self.block(block)?.instructions[..end]
+            .iter()
+            .rposition(&pred)?;
+        self.goto_inst(block, found);
+        self.current_inst()
+    }
+
+    fn is_terminated(&self) -> bool {
+        self.current_block_ref()
+            .map(|b| b.is_terminated())
+            .unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Edit<'e> {
+    Inserted {
+        block: BlockId,
+        index: usize,
+        inst: &'e Instruction,
+    },
+    Removed {
+        block: BlockId,
+        index: usize,
+        inst: &'e Instruction,
+    },
+    Replaced {
+        block: BlockId,
+        index: usize,
+        inst: &'e Instruction,
+    },
+    Terminator {
+        block: BlockId,
+        terminator: &'e Terminator,
+    },
+}
+
+pub trait EditListener {
+    fn on_edit(&mut self, edit: Edit<'_>);
+}
+
+pub trait IRCursorMut: IRCursor {
+    fn block_mut(&mut self, id: BlockId) -> Option<&mut BasicBlock>;
+
+    fn notify(&mut self, _edit: Edit<'_>) {}
+
+    fn insert_inst(&mut self, inst: Instruction) -> Result<()> {
+        let block_id = self
+            .current_block()
+            .ok_or_else(|| IrError::BuilderError("Cursor not positioned".to_string()))?;
+        let len = self
+            .block(block_id)
+            .ok_or_else(|| IrError::BuilderError(format!("Block {:?} not found", block_id)))?
+            .instructions
+            .len();
+
+        let (index, next) = match self.position() {
+            CursorPosition::Before(_) if len == 0 => (0, CursorPosition::After(block_id)),
+            CursorPosition::Before(_) => (0, CursorPosition::At(block_id, 1)),
+            CursorPosition::At(_, idx) if idx < len => (idx, CursorPosition::At(block_id, idx + 1)),
+            CursorPosition::At(..) | CursorPosition::After(_) => {
+                (len, CursorPosition::After(block_id))
+            }
             CursorPosition::Nowhere => {
-                return Err(IrError::BuilderError("No current block".into()));
+                return Err(IrError::BuilderError("Cursor not positioned".to_string()))
             }
         };
 
-        let block = self
-            .get_block_mut(block_id)
-            .ok_or_else(|| IrError::BuilderError(format!("Block {:?} not found", block_id)))?;
-
-        if !matches!(block.terminator, Terminator::Invalid) {
-            return Err(IrError::BuilderError(format!(
-                "Block {:?} already has terminator",
-                block_id
-            )));
+        self.notify(Edit::Inserted {
+            block: block_id,
+            index,
+            inst: &inst,
+        });
+        if let Some(block) = self.block_mut(block_id) {
+            block.instructions.insert(index, inst);
         }
-
-        block.terminator = term;
+        self.set_position(next);
         Ok(())
     }
 
-    pub fn is_terminated(&self) -> bool {
-        let block_id = match self.position {
-            CursorPosition::Before(b) | CursorPosition::At(b, _) | CursorPosition::After(b) => b,
-            CursorPosition::Nowhere => return false,
+    fn remove_inst(&mut self) -> Option<Instruction> {
+        let (block_id, idx) = match self.position() {
+            CursorPosition::At(block, idx) => (block, idx),
+            _ => return None,
         };
+        let block = self.block_mut(block_id)?;
+        if idx >= block.instructions.len() {
+            return None;
+        }
+        let removed = block.instructions.remove(idx);
+        if idx >= block.instructions.len() {
+            self.goto_bottom(block_id);
+        }
+        self.notify(Edit::Removed {
+            block: block_id,
+            index: idx,
+            inst: &removed,
+        });
+        Some(removed)
+    }
 
-        self.get_block(block_id)
-            .map(|b| b.is_terminated())
-            .unwrap_or(false)
+    fn replace_inst(&mut self, inst: Instruction) -> Option<Instruction> {
+        let (block_id, idx) = match self.position() {
+            CursorPosition::At(block, idx) => (block, idx),
+            _ => return None,
+        };
+        self.block(block_id)?.instructions.get(idx)?;
+        self.notify(Edit::Replaced {
+            block: block_id,
+            index: idx,
+            inst: &inst,
+        });
+        let slot = self.block_mut(block_id)?.instructions.get_mut(idx)?;
+        Some(std::mem::replace(slot, inst))
     }
 
-    fn get_block(&self, block_id: BlockId) -> Option<&BasicBlock> {
-        self.function.body.blocks.get(&block_id)
+    fn set_terminator(&mut self, term: Terminator) -> Result<()> {
+        let block_id = self
+            .current_block()
+            .ok_or_else(|| IrError::BuilderError("No current block".into()))?;
+        self.block(block_id)
+            .ok_or_else(|| IrError::BuilderError(format!("Block {:?} not found", block_id)))?;
+        self.notify(Edit::Terminator {
+            block: block_id,
+            terminator: &term,
+        });
+        if let Some(block) = self.block_mut(block_id) {
+            block.terminator = term;
+        }
+        Ok(())
     }
+}
 
-    fn get_block_mut(&mut self, block_id: BlockId) -> Option<&mut BasicBlock> {
-        self.function.body.blocks.get_mut(&block_id)
+pub struct FuncCursor<'a> {
+    position: CursorPosition,
+    function: &'a mut Function,
+    listener: Option<&'a mut dyn EditListener>,
+}
+
+impl<'a> FuncCursor<'a> {
+    pub fn new(function: &'a mut Function) -> Self {
+        Self {
+            position: CursorPosition::Nowhere,
+            function,
+            listener: None,
+        }
+    }
+
+    pub fn with_listener(mut self, listener: &'a mut dyn EditListener) -> Self {
+        self.listener = Some(listener);
+        self
     }
 
     pub fn ins(&mut self) -> InstBuilder<'_, 'a> {
         InstBuilder::new(self)
     }
-}
 
-impl<'a> FuncCursor<'a> {
     pub fn at_bottom(mut self, block: BlockId) -> Self {
         self.goto_bottom(block);
         self
@@ -193,3 +352,109 @@ impl<'a> FuncCursor<'a> {
         self
     }
 }
+
+impl<'a> IRCursor for FuncCursor<'a> {
+    fn position(&self) -> CursorPosition {
+        self.position
+    }
+
+    fn set_position(&mut self, pos: CursorPosition) {
+        self.position = pos;
+    }
+
+    fn block(&self, id: BlockId) -> Option<&BasicBlock> {
+        self.function.body.blocks.get(&id)
+    }
+
+    fn layout(&self) -> Vec<BlockId> {
+        self.function.body.blocks.keys().copied().collect()
+    }
+}
+
+impl<'a> IRCursorMut for FuncCursor<'a> {
+    fn block_mut(&mut self, id: BlockId) -> Option<&mut BasicBlock> {
+        self.function.body.blocks.get_mut(&id)
+    }
+
+    fn notify(&mut self, edit: Edit<'_>) {
+        if let Some(listener) = self.listener.as_mut() {
+            listener.on_edit(edit);
+        }
+    }
+
+    fn set_terminator(&mut self, term: Terminator) -> Result<()> {
+        let block_id = self
+            .current_block()
+            .ok_or_else(|| IrError::BuilderError("No current block".into()))?;
+        let block = self
+            .block(block_id)
+            .ok_or_else(|| IrError::BuilderError(format!("Block {:?} not found", block_id)))?;
+
+        if !matches!(block.terminator, Terminator::Invalid) {
+            return Err(IrError::BuilderError(format!(
+                "Block {:?} already has terminator",
+                block_id
+            )));
+        }
+
+        self.notify(Edit::Terminator {
+            block: block_id,
+            terminator: &term,
+        });
+        if let Some(block) = self.block_mut(block_id) {
+            block.terminator = term;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
+    use crate::values::{Constant, Value};
+
+    #[test]
+    fn test_inserts_keep_program_order_and_anchor() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Cursor");
+        let mut func = contract_builder.function("f");
+        let mut entry = func.entry_block();
+        entry.return_void().unwrap();
+        func.build().unwrap();
+        let mut contract = contract_builder.build().unwrap();
+        let function = contract.functions.get_mut("f").unwrap();
+        let entry_id = function.body.entry_block;
+
+        let one = Value::Constant(Constant::Uint(1u32.into(), 256));
+        let mut cursor = FuncCursor::new(function).at_top(entry_id);
+        let a = cursor
+            .ins()
+            .add(one.clone(), one.clone(), Type::Uint(256))
+            .unwrap();
+        let b = cursor.ins().mul(a.clone(), one, Type::Uint(256)).unwrap();
+        cursor.ins().sub(b, a, Type::Uint(256)).unwrap();
+
+        cursor.goto_top(entry_id);
+        let found = cursor.find_next(|inst| matches!(inst, Instruction::Mul { .. }));
+        assert!(found.is_some());
+        assert_eq!(cursor.position(), CursorPosition::At(entry_id, 1));
+        assert!(matches!(
+            cursor.window(),
+            [Instruction::Mul { .. }, Instruction::Sub { .. }]
+        ));
+
+        assert!(matches!(
+            cursor.remove_inst(),
+            Some(Instruction::Mul { .. })
+        ));
+        assert!(matches!(
+            cursor.current_inst(),
+            Some(Instruction::Sub { .. })
+        ));
+        assert!(matches!(cursor.prev_inst(), Some(Instruction::Add { .. })));
+        assert!(cursor.prev_inst().is_none());
+        assert_eq!(cursor.position(), CursorPosition::Before(entry_id));
+    }
+}
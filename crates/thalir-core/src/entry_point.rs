@@ -0,0 +1,195 @@
+use crate::block::Terminator;
+use crate::contract::Contract;
+use crate::function::Function;
+use crate::hash::selector_u32;
+use crate::instructions::{ContextVariable, Instruction};
+use crate::obfuscation::policy::abi_signature;
+use crate::values::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EntryKind {
+    Function,
+    Fallback,
+    Receive,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuardSummary {
+    pub modifiers: Vec<String>,
+    /* Require, assert and branch conditions computed from msg.sender or tx.origin. */
+    pub caller_checks: usize,
+}
+
+impl GuardSummary {
+    pub fn is_guarded(&self) -> bool {
+        !self.modifiers.is_empty() || self.caller_checks > 0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryPoint {
+    pub name: String,
+    pub kind: EntryKind,
+    pub selector: Option<u32>,
+    pub payable: bool,
+    pub guards: GuardSummary,
+}
+
+impl Contract {
+    /* Functions a transaction can start in, in declaration order. These are the roots for any
+     * analysis that walks the call graph from the outside in. */
+    pub fn entry_points(&self) -> Vec<EntryPoint> {
+        self.functions
+            .iter()
+            .filter(|(_, function)| function.is_entry_point())
+            .map(|(name, function)| self.entry_point(name, function))
+            .collect()
+    }
+
+    fn entry_point(&self, name: &str, function: &Function) -> EntryPoint {
+        let kind = if function.metadata.is_receive {
+            EntryKind::Receive
+        } else if function.metadata.is_fallback {
+            EntryKind::Fallback
+        } else {
+            EntryKind::Function
+        };
+
+        let selector = match kind {
+            EntryKind::Function => function.metadata.selector.or_else(|| {
+                let params = function.signature.params.iter().map(|p| &p.param_type);
+                abi_signature(&function.signature.name, params).map(|sig| selector_u32(&sig))
+            }),
+            EntryKind::Fallback | EntryKind::Receive => None,
+        };
+
+        EntryPoint {
+            name: name.to_string(),
+            kind,
+            selector,
+            payable: function.is_payable(),
+            guards: self.guards(function),
+        }
+    }
+
+    /* The modifiers `function` names from this contract and the caller checks in its own body. */
+    pub fn guards(&self, function: &Function) -> GuardSummary {
+        let modifiers = function
+            .modifiers
+            .iter()
+            .filter_map(|modifier| {
+                self.modifiers
+                    .iter()
+                    .find(|definition| definition.id == modifier.id)
+                    .map(|definition| definition.name.clone())
+            })
+            .collect();
+
+        GuardSummary {
+            modifiers,
+            caller_checks: caller_checks(function),
+        }
+    }
+}
+
+pub(crate) fn caller_checks(function: &Function) -> usize {
+    let mut derived: HashSet<Value> = HashSet::new();
+    loop {
+        let before = derived.len();
+        for inst in function.body.blocks.values().flat_map(|b| &b.instructions) {
+            let Some(result) = inst.result() else {
+                continue;
+            };
+            let from_caller = match inst {
+                Instruction::GetContext { var, .. } => {
+                    matches!(var, ContextVariable::MsgSender | ContextVariable::TxOrigin)
+                }
+                _ => inst
+                    .operands()
+                    .into_iter()
+                    .any(|value| derived.contains(value)),
+            };
+            if from_caller {
+                derived.insert(result.clone());
+            }
+        }
+        if derived.len() == before {
+            break;
+        }
+    }
+
+    function
+        .body
+        .blocks
+        .values()
+        .flat_map(|block| {
+            let checks = block.instructions.iter().filter_map(|inst| match inst {
+                Instruction::Require { condition, .. }
+                | Instruction::Assert { condition, .. }
+                | Instruction::Branch { condition, .. } => Some(condition),
+                _ => None,
+            });
+            let branch = match &block.terminator {
+                Terminator::Branch { condition, .. } => Some(condition),
+                _ => None,
+            };
+            checks.chain(branch).collect::<Vec<_>>()
+        })
+        .filter(|condition| derived.contains(*condition))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::IRBuilder;
+    use crate::entry_point::EntryKind;
+    use crate::function::Visibility;
+    use crate::hash::selector_u32;
+    use crate::types::Type;
+
+    #[test]
+    fn test_entry_points_cover_public_fallback_and_receive() {
+        let mut builder = IRBuilder::new();
+        let mut contract = builder.contract("Vault");
+
+        let mut withdraw = contract.function("withdraw");
+        withdraw
+            .param("owner", Type::Address)
+            .visibility(Visibility::External);
+        let owner = withdraw.get_param(0);
+        let mut entry = withdraw.entry_block();
+        let sender = entry.msg_sender();
+        let is_owner = entry.eq(sender, owner);
+        entry.require(is_owner, "not owner");
+        entry.return_void().unwrap();
+        withdraw.build().unwrap();
+
+        let mut helper = contract.function("helper");
+        helper.visibility(Visibility::Internal);
+        helper.entry_block().return_void().unwrap();
+        helper.build().unwrap();
+
+        let mut receive = contract.function("receive");
+        receive.receive().visibility(Visibility::External);
+        receive.entry_block().return_void().unwrap();
+        receive.build().unwrap();
+
+        let contract = contract.build().unwrap();
+        let entries = contract.entry_points();
+        assert_eq!(entries.len(), 2);
+
+        let withdraw = entries.iter().find(|e| e.name == "withdraw").unwrap();
+        assert_eq!(withdraw.kind, EntryKind::Function);
+        assert_eq!(withdraw.selector, Some(selector_u32("withdraw(address)")));
+        assert!(!withdraw.payable);
+        assert_eq!(withdraw.guards.caller_checks, 1);
+
+        let receive = entries.iter().find(|e| e.name == "receive").unwrap();
+        assert_eq!(receive.kind, EntryKind::Receive);
+        assert_eq!(receive.selector, None);
+        assert!(receive.payable);
+        assert!(!receive.guards.is_guarded());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::instructions::{BuiltinFunction, CallTarget, ContextVariable, Instruction};
 use crate::values::Value;
 use serde::{Deserialize, Serialize};
 
@@ -9,7 +10,18 @@ pub enum EvmOp {
     GetBlockHash(Value),
     GetChainId,
     GetBaseFee,
+    GetBlobBaseFee,
+    GetBlobHash(Value),
+    GetSelfBalance,
     SelfDestruct(Value),
+
+    /* MCOPY: the copy behaves as if `src` were first read into a buffer, so overlapping ranges
+     * are well defined (memmove, not memcpy). */
+    MemoryCopy {
+        dest: Value,
+        src: Value,
+        size: Value,
+    },
     Log {
         topics: Vec<Value>,
         data: Value,
@@ -30,6 +42,45 @@ pub enum EvmOp {
     },
 }
 
+impl EvmOp {
+    /* The core instruction for ops that map onto one directly. Logs, creates and calls need
+     * event, selector or salt information the op does not carry, so they return `None`. */
+    pub fn to_instruction(&self, result: Value) -> Option<Instruction> {
+        let context = |var| Instruction::GetContext {
+            result: result.clone(),
+            var,
+        };
+        let builtin = |builtin, arg: &Value| Instruction::Call {
+            result: result.clone(),
+            target: CallTarget::Builtin(builtin),
+            args: vec![arg.clone()],
+            value: None,
+        };
+        Some(match self {
+            EvmOp::GetTxOrigin => context(ContextVariable::TxOrigin),
+            EvmOp::GetGasPrice => context(ContextVariable::TxGasPrice),
+            EvmOp::GetGasLeft => context(ContextVariable::GasLeft),
+            EvmOp::GetChainId => context(ContextVariable::ChainId),
+            EvmOp::GetBaseFee => context(ContextVariable::BlockBaseFee),
+            EvmOp::GetBlobBaseFee => context(ContextVariable::BlobBaseFee),
+            EvmOp::GetSelfBalance => context(ContextVariable::ThisBalance),
+            EvmOp::GetBlockHash(number) => builtin(BuiltinFunction::BlockHash, number),
+            EvmOp::GetBlobHash(index) => builtin(BuiltinFunction::BlobHash, index),
+            EvmOp::SelfDestruct(beneficiary) => Instruction::Selfdestruct {
+                beneficiary: beneficiary.clone(),
+            },
+            EvmOp::MemoryCopy { dest, src, size } => Instruction::MemoryCopy {
+                dest: dest.clone(),
+                src: src.clone(),
+                size: size.clone(),
+            },
+            EvmOp::Log { .. } | EvmOp::CreateContract { .. } | EvmOp::ExternalCall { .. } => {
+                return None
+            }
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ExternalCallType {
     Call,
@@ -1,5 +1,7 @@
+use crate::instructions::StorageKey;
 use crate::values::Value;
 use num_bigint::BigUint;
+use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -97,3 +99,60 @@ pub fn compute_mapping_slot(base: &BigUint, key: &[u8]) -> BigUint {
 pub fn compute_array_slot(base: &BigUint, index: usize) -> BigUint {
     base.clone() + BigUint::from(index)
 }
+
+/* How solc keeps a `string` or `bytes` at its slot p. Up to 31 bytes sit left-aligned in p itself
+ * with 2 * len in the lowest byte; anything longer starts at keccak256(p) and p holds 2 * len + 1.
+ * The low bit of the slot word tells the two apart. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ByteArrayEncoding {
+    Short { len: u8 },
+    Long { len: BigUint },
+}
+
+impl ByteArrayEncoding {
+    pub const MAX_SHORT_LEN: usize = 31;
+
+    /* `None` for words solc never writes: an even word whose length byte exceeds 31. */
+    pub fn decode(word: &BigUint) -> Option<Self> {
+        if word.bit(0) {
+            return Some(ByteArrayEncoding::Long { len: word >> 1u32 });
+        }
+        let len = (word & BigUint::from(0xffu32)).to_u8()? / 2;
+        (len as usize <= Self::MAX_SHORT_LEN).then_some(ByteArrayEncoding::Short { len })
+    }
+
+    pub fn for_length(len: usize) -> Self {
+        if len <= Self::MAX_SHORT_LEN {
+            ByteArrayEncoding::Short { len: len as u8 }
+        } else {
+            ByteArrayEncoding::Long {
+                len: BigUint::from(len),
+            }
+        }
+    }
+
+    pub fn is_short(&self) -> bool {
+        matches!(self, ByteArrayEncoding::Short { .. })
+    }
+
+    /* The word solc writes at p for `data`. */
+    pub fn slot_word(data: &[u8]) -> BigUint {
+        if data.len() > Self::MAX_SHORT_LEN {
+            return BigUint::from(data.len()) * 2u32 + 1u32;
+        }
+        let mut word = [0u8; 32];
+        word[..data.len()].copy_from_slice(data);
+        word[31] = (data.len() * 2) as u8;
+        BigUint::from_bytes_be(&word)
+    }
+
+    /* The first data slot and how many follow it; short data has no slots of its own. */
+    pub fn data_slots(&self, base: &BigUint) -> Option<(BigUint, BigUint)> {
+        match self {
+            ByteArrayEncoding::Short { .. } => None,
+            ByteArrayEncoding::Long { len } => {
+                Some((StorageKey::array_data_slot(base), (len + 31u32) / 32u32))
+            }
+        }
+    }
+}
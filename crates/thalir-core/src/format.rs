@@ -2,6 +2,7 @@ use crate::{
     block::BasicBlock,
     contract::Contract,
     function::Function,
+    hash::hex,
     instructions::Instruction,
     types::Type,
     values::{Constant, Value},
@@ -170,6 +171,59 @@ fn format_instruction(inst: &Instruction) -> String {
                 format_value(right)
             )
         }
+        Instruction::Sdiv {
+            result,
+            left,
+            right,
+            ty,
+        } => {
+            format!(
+                "{} = sdiv.{} {}, {}",
+                format_value(result),
+                format_type_short(ty),
+                format_value(left),
+                format_value(right)
+            )
+        }
+        Instruction::CheckedAdd {
+            result,
+            left,
+            right,
+            ty,
+        }
+        | Instruction::CheckedSub {
+            result,
+            left,
+            right,
+            ty,
+        }
+        | Instruction::CheckedMul {
+            result,
+            left,
+            right,
+            ty,
+        }
+        | Instruction::CheckedDiv {
+            result,
+            left,
+            right,
+            ty,
+        } => {
+            let op = match inst {
+                Instruction::CheckedAdd { .. } => "checked_add",
+                Instruction::CheckedSub { .. } => "checked_sub",
+                Instruction::CheckedMul { .. } => "checked_mul",
+                _ => "checked_div",
+            };
+            format!(
+                "{} = {}.{} {}, {}",
+                format_value(result),
+                op,
+                format_type_short(ty),
+                format_value(left),
+                format_value(right)
+            )
+        }
 
         Instruction::Eq {
             result,
@@ -243,6 +297,54 @@ fn format_instruction(inst: &Instruction) -> String {
                 format_value(right)
             )
         }
+        Instruction::Slt {
+            result,
+            left,
+            right,
+        } => {
+            format!(
+                "{} = icmp slt {}, {}",
+                format_value(result),
+                format_value(left),
+                format_value(right)
+            )
+        }
+        Instruction::Sgt {
+            result,
+            left,
+            right,
+        } => {
+            format!(
+                "{} = icmp sgt {}, {}",
+                format_value(result),
+                format_value(left),
+                format_value(right)
+            )
+        }
+        Instruction::Sle {
+            result,
+            left,
+            right,
+        } => {
+            format!(
+                "{} = icmp sle {}, {}",
+                format_value(result),
+                format_value(left),
+                format_value(right)
+            )
+        }
+        Instruction::Sge {
+            result,
+            left,
+            right,
+        } => {
+            format!(
+                "{} = icmp sge {}, {}",
+                format_value(result),
+                format_value(left),
+                format_value(right)
+            )
+        }
 
         Instruction::StorageLoad { result, key } => {
             format!(
@@ -258,6 +360,20 @@ fn format_instruction(inst: &Instruction) -> String {
                 format_value(value)
             )
         }
+        Instruction::TransientLoad { result, key } => {
+            format!(
+                "{} = transient_load {}",
+                format_value(result),
+                format_storage_key(key)
+            )
+        }
+        Instruction::TransientStore { key, value } => {
+            format!(
+                "transient_store {}, {}",
+                format_storage_key(key),
+                format_value(value)
+            )
+        }
 
         Instruction::MappingLoad {
             result,
@@ -293,10 +409,12 @@ fn format_instruction(inst: &Instruction) -> String {
                 ContextVariable::BlockNumber => "block.number",
                 ContextVariable::BlockTimestamp => "block.timestamp",
                 ContextVariable::BlockDifficulty => "block.difficulty",
+                ContextVariable::PrevRandao => "block.prevrandao",
                 ContextVariable::BlockGasLimit => "block.gaslimit",
                 ContextVariable::BlockCoinbase => "block.coinbase",
                 ContextVariable::ChainId => "block.chainid",
                 ContextVariable::BlockBaseFee => "block.basefee",
+                ContextVariable::BlobBaseFee => "block.blobbasefee",
                 ContextVariable::GasLeft => "gasleft",
                 ContextVariable::TxOrigin => "tx.origin",
                 ContextVariable::TxGasPrice => "tx.gasprice",
@@ -404,7 +522,7 @@ fn format_terminator(term: &crate::block::Terminator) -> String {
     }
 }
 
-fn format_value(val: &Value) -> String {
+pub(crate) fn format_value(val: &Value) -> String {
     match val {
         Value::Variable(id) => id.to_string(),
         Value::Temp(id) => id.to_string(),
@@ -419,7 +537,7 @@ fn format_constant(c: &Constant) -> String {
         Constant::Bool(b) => b.to_string(),
         Constant::Uint(val, bits) => format!("{}u{}", val, bits),
         Constant::Int(val, bits) => format!("{}i{}", val, bits),
-        Constant::Address(addr) => format!("0x{}", hex::encode(addr)),
+        Constant::Address(addr) => format!("0x{}", hex(addr)),
         _ => format!("{}", c),
     }
 }
@@ -452,9 +570,3 @@ fn format_storage_key(key: &crate::instructions::StorageKey) -> String {
         _ => format!("{:?}", key),
     }
 }
-
-mod hex {
-    pub fn encode(bytes: &[u8]) -> String {
-        bytes.iter().map(|b| format!("{:02x}", b)).collect()
-    }
-}
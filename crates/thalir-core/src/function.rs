@@ -1,6 +1,9 @@
 use crate::block::{BasicBlock, BlockId};
 use crate::contract::ModifierRef;
+use crate::instructions::Instruction;
+use crate::metadata::NatSpec;
 use crate::types::Type;
+use crate::values::{ConstantPool, Value};
 use cranelift::codegen::ir as clif_ir;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
@@ -35,6 +38,19 @@ impl Function {
         self.body.entry_block()
     }
 
+    /* Reachable by a transaction: public and external functions plus fallback and receive.
+     * Constructors only run at deployment and are not counted. */
+    pub fn is_entry_point(&self) -> bool {
+        !self.metadata.is_constructor
+            && (self.metadata.is_fallback
+                || self.metadata.is_receive
+                || matches!(self.visibility, Visibility::Public | Visibility::External))
+    }
+
+    pub fn is_payable(&self) -> bool {
+        self.mutability == Mutability::Payable || self.signature.is_payable
+    }
+
     pub fn analyze_metadata(&mut self) {
         let (calls_external, modifies_state) = self
             .body
@@ -136,6 +152,13 @@ impl FunctionBody {
         id
     }
 
+    /* Adds a block under its own ID, for callers that read block numbers from elsewhere (text IR,
+     * another function) instead of allocating them here. */
+    pub fn insert_block(&mut self, block: BasicBlock) {
+        self.next_block_id = self.next_block_id.max(block.id.0 + 1);
+        self.blocks.insert(block.id, block);
+    }
+
     pub fn get_block(&self, id: BlockId) -> Option<&BasicBlock> {
         self.blocks.get(&id)
     }
@@ -154,6 +177,32 @@ impl FunctionBody {
         self.locals.push(var);
         id
     }
+
+    /* Distinct constants among the body's operands. Operands still hold their constants inline,
+     * so the table is built from them on every call rather than stored next to them. */
+    pub fn constant_pool(&self) -> ConstantPool {
+        let mut pool = ConstantPool::new();
+        self.collect_constants(&mut pool);
+        pool
+    }
+
+    pub fn collect_constants(&self, pool: &mut ConstantPool) {
+        let mut intern = |value: &Value| {
+            if let Value::Constant(constant) = value {
+                pool.intern(constant);
+            }
+        };
+        for block in self.blocks.values() {
+            let operands = block
+                .instructions
+                .iter()
+                .flat_map(Instruction::operands)
+                .chain(block.terminator.operands());
+            for value in operands {
+                intern(value);
+            }
+        }
+    }
 }
 
 impl Default for FunctionBody {
@@ -203,4 +252,12 @@ pub struct FunctionMetadata {
     pub has_assembly: bool,
     pub calls_external: bool,
     pub modifies_state: bool,
+    #[serde(default)]
+    pub natspec: Option<NatSpec>,
+    #[serde(default)]
+    pub selector: Option<u32>,
+    #[serde(default)]
+    pub is_outlined: bool,
+    #[serde(default)]
+    pub stable_id: Option<String>,
 }
@@ -0,0 +1,57 @@
+/*! Keccak-256, function selectors and hex, shared by the ABI codec, storage slot derivation,
+ * entry points and the obfuscation attestations.
+ */
+
+use num_bigint::BigUint;
+use tiny_keccak::{Hasher, Keccak};
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut keccak = Keccak::v256();
+    let mut hash = [0u8; 32];
+    keccak.update(data);
+    keccak.finalize(&mut hash);
+    hash
+}
+
+/* The hash of `value` as a big-endian 32-byte word, the way slots are hashed; anything wider
+ * than a word keeps its low 256 bits. */
+pub fn keccak256_word(value: &BigUint) -> BigUint {
+    let mut word = [0u8; 32];
+    let bytes = value.to_bytes_be();
+    let start = 32usize.saturating_sub(bytes.len());
+    word[start..].copy_from_slice(&bytes[bytes.len().saturating_sub(32)..]);
+    BigUint::from_bytes_be(&keccak256(&word))
+}
+
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = keccak256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/* The selector as the IR stores it in function metadata and compares it in dispatch. */
+pub fn selector_u32(signature: &str) -> u32 {
+    u32::from_be_bytes(selector(signature))
+}
+
+/* Lowercase hex without a `0x` prefix. */
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_known_digests() {
+        assert_eq!(
+            hex(&keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(selector_u32("transfer(address,uint256)"), 0xa905_9cbb);
+        assert_eq!(
+            keccak256_word(&BigUint::from(2u32)).to_str_radix(16),
+            "405787fa12a823e0f2b7631cc41b3ba8828b3321ca811111fa75cd3aa3bb5ace"
+        );
+    }
+}
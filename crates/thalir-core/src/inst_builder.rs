@@ -1,6 +1,6 @@
 use crate::{
     block::BlockId,
-    cursor::FuncCursor,
+    cursor::{FuncCursor, IRCursorMut},
     instructions::Instruction,
     types::Type,
     values::{Constant, Value},
@@ -227,6 +227,26 @@ impl<'b, 'a> InstBuilder<'b, 'a> {
         self.cursor.insert_inst(inst)
     }
 
+    pub fn transient_load(self, key: Value) -> Result<Value> {
+        use crate::instructions::StorageKey;
+        let result = self.next_value();
+        let inst = Instruction::TransientLoad {
+            result: result.clone(),
+            key: StorageKey::Dynamic(key),
+        };
+        self.cursor.insert_inst(inst)?;
+        Ok(result)
+    }
+
+    pub fn transient_store(self, key: Value, value: Value) -> Result<()> {
+        use crate::instructions::StorageKey;
+        let inst = Instruction::TransientStore {
+            key: StorageKey::Dynamic(key),
+            value,
+        };
+        self.cursor.insert_inst(inst)
+    }
+
     fn next_value(&self) -> Value {
         use crate::values::TempId;
         use std::sync::atomic::{AtomicU32, Ordering};
@@ -1,6 +1,7 @@
 use crate::contract::EventId;
+use crate::hash;
 use crate::types::Type;
-use crate::values::{Location, Value};
+use crate::values::{Constant, Location, Value};
 use num_bigint::BigUint;
 use serde::{Deserialize, Serialize};
 
@@ -36,6 +37,18 @@ pub enum Instruction {
         right: Value,
         ty: Type,
     },
+    Sdiv {
+        result: Value,
+        left: Value,
+        right: Value,
+        ty: Type,
+    },
+    Smod {
+        result: Value,
+        left: Value,
+        right: Value,
+        ty: Type,
+    },
     Pow {
         result: Value,
         base: Value,
@@ -132,6 +145,26 @@ pub enum Instruction {
         left: Value,
         right: Value,
     },
+    Slt {
+        result: Value,
+        left: Value,
+        right: Value,
+    },
+    Sgt {
+        result: Value,
+        left: Value,
+        right: Value,
+    },
+    Sle {
+        result: Value,
+        left: Value,
+        right: Value,
+    },
+    Sge {
+        result: Value,
+        left: Value,
+        right: Value,
+    },
 
     Select {
         result: Value,
@@ -171,6 +204,15 @@ pub enum Instruction {
         key: StorageKey,
     },
 
+    TransientLoad {
+        result: Value,
+        key: StorageKey,
+    },
+    TransientStore {
+        key: StorageKey,
+        value: Value,
+    },
+
     MappingLoad {
         result: Value,
         mapping: Value,
@@ -367,6 +409,22 @@ pub enum Size {
     Dynamic(Value),
 }
 
+impl Size {
+    pub fn value(&self) -> Option<&Value> {
+        match self {
+            Size::Static(_) => None,
+            Size::Dynamic(value) => Some(value),
+        }
+    }
+
+    pub fn value_mut(&mut self) -> Option<&mut Value> {
+        match self {
+            Size::Static(_) => None,
+            Size::Dynamic(value) => Some(value),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum StorageKey {
     Slot(BigUint),
@@ -376,6 +434,57 @@ pub enum StorageKey {
     ArrayElement { base: BigUint, index: Value },
 }
 
+impl StorageKey {
+    /* A dynamic storage array keeps its length at slot `p` and its elements from keccak256(p)
+     * upward, so `ArrayElement { base: p, index: i }` is the word at keccak256(p) + i. */
+    pub fn array_data_slot(base: &BigUint) -> BigUint {
+        hash::keccak256_word(base)
+    }
+
+    /* The slot of the declared variable this key reaches through. */
+    pub fn root_slot(&self) -> Option<&BigUint> {
+        match self {
+            StorageKey::Slot(slot)
+            | StorageKey::MappingKey { base: slot, .. }
+            | StorageKey::ArrayElement { base: slot, .. } => Some(slot),
+            StorageKey::Dynamic(_) | StorageKey::Computed(_) => None,
+        }
+    }
+
+    /* The concrete slot, when every part of the derivation is a constant. */
+    pub fn resolved_slot(&self) -> Option<BigUint> {
+        match self {
+            StorageKey::Slot(slot) => Some(slot.clone()),
+            StorageKey::ArrayElement {
+                base,
+                index: Value::Constant(Constant::Uint(index, _)),
+            } => Some((Self::array_data_slot(base) + index) % (BigUint::from(1u32) << 256)),
+            _ => None,
+        }
+    }
+
+    /* The value the key is computed from; fixed slots and bases are not values. */
+    pub fn value(&self) -> Option<&Value> {
+        match self {
+            StorageKey::Slot(_) => None,
+            StorageKey::Dynamic(value)
+            | StorageKey::Computed(value)
+            | StorageKey::MappingKey { key: value, .. }
+            | StorageKey::ArrayElement { index: value, .. } => Some(value),
+        }
+    }
+
+    pub fn value_mut(&mut self) -> Option<&mut Value> {
+        match self {
+            StorageKey::Slot(_) => None,
+            StorageKey::Dynamic(value)
+            | StorageKey::Computed(value)
+            | StorageKey::MappingKey { key: value, .. }
+            | StorageKey::ArrayElement { index: value, .. } => Some(value),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CallTarget {
     Internal(String),
@@ -384,12 +493,33 @@ pub enum CallTarget {
     Builtin(BuiltinFunction),
 }
 
+impl CallTarget {
+    /* The callee address of an external call, the only target that is a value. */
+    pub fn address(&self) -> Option<&Value> {
+        match self {
+            CallTarget::External(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    pub fn address_mut(&mut self) -> Option<&mut Value> {
+        match self {
+            CallTarget::External(address) => Some(address),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BuiltinFunction {
     AddMod,
     MulMod,
     BlockHash,
+    BlobHash,
     GasLeft,
+    /* The slot a mapping keeps `key` at, keccak256(key . slot), for values the IR goes on to
+     * address inside, such as a struct field or an inner mapping. */
+    MappingSlot,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -401,10 +531,12 @@ pub enum ContextVariable {
     BlockNumber,
     BlockTimestamp,
     BlockDifficulty,
+    PrevRandao,
     BlockGasLimit,
     BlockCoinbase,
     ChainId,
     BlockBaseFee,
+    BlobBaseFee,
     TxOrigin,
     TxGasPrice,
     GasLeft,
@@ -415,6 +547,89 @@ pub enum ContextVariable {
 use crate::block::BlockId;
 
 impl Instruction {
+    /* The variant name, for matching and fingerprinting instructions by kind. */
+    pub fn opcode_name(&self) -> &'static str {
+        match self {
+            Instruction::Add { .. } => "Add",
+            Instruction::Sub { .. } => "Sub",
+            Instruction::Mul { .. } => "Mul",
+            Instruction::Div { .. } => "Div",
+            Instruction::Mod { .. } => "Mod",
+            Instruction::Sdiv { .. } => "Sdiv",
+            Instruction::Smod { .. } => "Smod",
+            Instruction::Pow { .. } => "Pow",
+            Instruction::CheckedAdd { .. } => "CheckedAdd",
+            Instruction::CheckedSub { .. } => "CheckedSub",
+            Instruction::CheckedMul { .. } => "CheckedMul",
+            Instruction::CheckedDiv { .. } => "CheckedDiv",
+            Instruction::And { .. } => "And",
+            Instruction::Or { .. } => "Or",
+            Instruction::Xor { .. } => "Xor",
+            Instruction::Not { .. } => "Not",
+            Instruction::Shl { .. } => "Shl",
+            Instruction::Shr { .. } => "Shr",
+            Instruction::Sar { .. } => "Sar",
+            Instruction::Eq { .. } => "Eq",
+            Instruction::Ne { .. } => "Ne",
+            Instruction::Lt { .. } => "Lt",
+            Instruction::Gt { .. } => "Gt",
+            Instruction::Le { .. } => "Le",
+            Instruction::Ge { .. } => "Ge",
+            Instruction::Slt { .. } => "Slt",
+            Instruction::Sgt { .. } => "Sgt",
+            Instruction::Sle { .. } => "Sle",
+            Instruction::Sge { .. } => "Sge",
+            Instruction::Select { .. } => "Select",
+            Instruction::Load { .. } => "Load",
+            Instruction::Store { .. } => "Store",
+            Instruction::Allocate { .. } => "Allocate",
+            Instruction::Copy { .. } => "Copy",
+            Instruction::StorageLoad { .. } => "StorageLoad",
+            Instruction::StorageStore { .. } => "StorageStore",
+            Instruction::StorageDelete { .. } => "StorageDelete",
+            Instruction::TransientLoad { .. } => "TransientLoad",
+            Instruction::TransientStore { .. } => "TransientStore",
+            Instruction::MappingLoad { .. } => "MappingLoad",
+            Instruction::MappingStore { .. } => "MappingStore",
+            Instruction::ArrayLoad { .. } => "ArrayLoad",
+            Instruction::ArrayStore { .. } => "ArrayStore",
+            Instruction::ArrayLength { .. } => "ArrayLength",
+            Instruction::ArrayPush { .. } => "ArrayPush",
+            Instruction::ArrayPop { .. } => "ArrayPop",
+            Instruction::Call { .. } => "Call",
+            Instruction::DelegateCall { .. } => "DelegateCall",
+            Instruction::StaticCall { .. } => "StaticCall",
+            Instruction::Create { .. } => "Create",
+            Instruction::Create2 { .. } => "Create2",
+            Instruction::Selfdestruct { .. } => "Selfdestruct",
+            Instruction::GetContext { .. } => "GetContext",
+            Instruction::GetBalance { .. } => "GetBalance",
+            Instruction::GetCode { .. } => "GetCode",
+            Instruction::GetCodeSize { .. } => "GetCodeSize",
+            Instruction::GetCodeHash { .. } => "GetCodeHash",
+            Instruction::Keccak256 { .. } => "Keccak256",
+            Instruction::Sha256 { .. } => "Sha256",
+            Instruction::Ripemd160 { .. } => "Ripemd160",
+            Instruction::EcRecover { .. } => "EcRecover",
+            Instruction::EmitEvent { .. } => "EmitEvent",
+            Instruction::Cast { .. } => "Cast",
+            Instruction::ZeroExtend { .. } => "ZeroExtend",
+            Instruction::SignExtend { .. } => "SignExtend",
+            Instruction::Truncate { .. } => "Truncate",
+            Instruction::Assert { .. } => "Assert",
+            Instruction::Require { .. } => "Require",
+            Instruction::Revert { .. } => "Revert",
+            Instruction::Assign { .. } => "Assign",
+            Instruction::Phi { .. } => "Phi",
+            Instruction::Jump { .. } => "Jump",
+            Instruction::Branch { .. } => "Branch",
+            Instruction::Return { .. } => "Return",
+            Instruction::MemoryAlloc { .. } => "MemoryAlloc",
+            Instruction::MemoryCopy { .. } => "MemoryCopy",
+            Instruction::MemorySize { .. } => "MemorySize",
+        }
+    }
+
     pub fn result(&self) -> Option<&Value> {
         match self {
             Instruction::Add { result, .. }
@@ -422,6 +637,74 @@ impl Instruction {
             | Instruction::Mul { result, .. }
             | Instruction::Div { result, .. }
             | Instruction::Mod { result, .. }
+            | Instruction::Sdiv { result, .. }
+            | Instruction::Smod { result, .. }
+            | Instruction::Pow { result, .. }
+            | Instruction::CheckedAdd { result, .. }
+            | Instruction::CheckedSub { result, .. }
+            | Instruction::CheckedMul { result, .. }
+            | Instruction::CheckedDiv { result, .. }
+            | Instruction::And { result, .. }
+            | Instruction::Or { result, .. }
+            | Instruction::Xor { result, .. }
+            | Instruction::Not { result, .. }
+            | Instruction::Shl { result, .. }
+            | Instruction::Shr { result, .. }
+            | Instruction::Sar { result, .. }
+            | Instruction::Select { result, .. }
+            | Instruction::Eq { result, .. }
+            | Instruction::Ne { result, .. }
+            | Instruction::Lt { result, .. }
+            | Instruction::Gt { result, .. }
+            | Instruction::Le { result, .. }
+            | Instruction::Ge { result, .. }
+            | Instruction::Slt { result, .. }
+            | Instruction::Sgt { result, .. }
+            | Instruction::Sle { result, .. }
+            | Instruction::Sge { result, .. }
+            | Instruction::Load { result, .. }
+            | Instruction::Allocate { result, .. }
+            | Instruction::StorageLoad { result, .. }
+            | Instruction::TransientLoad { result, .. }
+            | Instruction::MappingLoad { result, .. }
+            | Instruction::ArrayLoad { result, .. }
+            | Instruction::ArrayLength { result, .. }
+            | Instruction::ArrayPop { result, .. }
+            | Instruction::Call { result, .. }
+            | Instruction::DelegateCall { result, .. }
+            | Instruction::StaticCall { result, .. }
+            | Instruction::Create { result, .. }
+            | Instruction::Create2 { result, .. }
+            | Instruction::GetContext { result, .. }
+            | Instruction::GetBalance { result, .. }
+            | Instruction::GetCode { result, .. }
+            | Instruction::GetCodeSize { result, .. }
+            | Instruction::GetCodeHash { result, .. }
+            | Instruction::Keccak256 { result, .. }
+            | Instruction::Sha256 { result, .. }
+            | Instruction::Ripemd160 { result, .. }
+            | Instruction::EcRecover { result, .. }
+            | Instruction::Cast { result, .. }
+            | Instruction::ZeroExtend { result, .. }
+            | Instruction::SignExtend { result, .. }
+            | Instruction::Truncate { result, .. }
+            | Instruction::Assign { result, .. }
+            | Instruction::Phi { result, .. }
+            | Instruction::MemoryAlloc { result, .. }
+            | Instruction::MemorySize { result, .. } => Some(result),
+            _ => None,
+        }
+    }
+
+    pub fn result_mut(&mut self) -> Option<&mut Value> {
+        match self {
+            Instruction::Add { result, .. }
+            | Instruction::Sub { result, .. }
+            | Instruction::Mul { result, .. }
+            | Instruction::Div { result, .. }
+            | Instruction::Mod { result, .. }
+            | Instruction::Sdiv { result, .. }
+            | Instruction::Smod { result, .. }
             | Instruction::Pow { result, .. }
             | Instruction::CheckedAdd { result, .. }
             | Instruction::CheckedSub { result, .. }
@@ -434,15 +717,21 @@ impl Instruction {
             | Instruction::Shl { result, .. }
             | Instruction::Shr { result, .. }
             | Instruction::Sar { result, .. }
+            | Instruction::Select { result, .. }
             | Instruction::Eq { result, .. }
             | Instruction::Ne { result, .. }
             | Instruction::Lt { result, .. }
             | Instruction::Gt { result, .. }
             | Instruction::Le { result, .. }
             | Instruction::Ge { result, .. }
+            | Instruction::Slt { result, .. }
+            | Instruction::Sgt { result, .. }
+            | Instruction::Sle { result, .. }
+            | Instruction::Sge { result, .. }
             | Instruction::Load { result, .. }
             | Instruction::Allocate { result, .. }
             | Instruction::StorageLoad { result, .. }
+            | Instruction::TransientLoad { result, .. }
             | Instruction::MappingLoad { result, .. }
             | Instruction::ArrayLoad { result, .. }
             | Instruction::ArrayLength { result, .. }
@@ -473,12 +762,295 @@ impl Instruction {
         }
     }
 
+    /* The values the instruction reads, in field order. The result is not included, so
+     * renaming a definition goes through `result` and renaming its uses through this. */
+    pub fn operands(&self) -> Vec<&Value> {
+        match self {
+            Instruction::Add { left, right, .. }
+            | Instruction::Sub { left, right, .. }
+            | Instruction::Mul { left, right, .. }
+            | Instruction::Div { left, right, .. }
+            | Instruction::Mod { left, right, .. }
+            | Instruction::Sdiv { left, right, .. }
+            | Instruction::Smod { left, right, .. }
+            | Instruction::CheckedAdd { left, right, .. }
+            | Instruction::CheckedSub { left, right, .. }
+            | Instruction::CheckedMul { left, right, .. }
+            | Instruction::CheckedDiv { left, right, .. }
+            | Instruction::And { left, right, .. }
+            | Instruction::Or { left, right, .. }
+            | Instruction::Xor { left, right, .. }
+            | Instruction::Eq { left, right, .. }
+            | Instruction::Ne { left, right, .. }
+            | Instruction::Lt { left, right, .. }
+            | Instruction::Gt { left, right, .. }
+            | Instruction::Le { left, right, .. }
+            | Instruction::Ge { left, right, .. }
+            | Instruction::Slt { left, right, .. }
+            | Instruction::Sgt { left, right, .. }
+            | Instruction::Sle { left, right, .. }
+            | Instruction::Sge { left, right, .. } => vec![left, right],
+            Instruction::Pow { base, exp, .. } => vec![base, exp],
+            Instruction::Not { operand, .. } => vec![operand],
+            Instruction::Shl { value, shift, .. }
+            | Instruction::Shr { value, shift, .. }
+            | Instruction::Sar { value, shift, .. } => vec![value, shift],
+            Instruction::Select {
+                condition,
+                then_val,
+                else_val,
+                ..
+            } => vec![condition, then_val, else_val],
+            Instruction::Load { location, .. } => location.values(),
+            Instruction::Store { location, value } => location
+                .values()
+                .into_iter()
+                .chain(std::iter::once(value))
+                .collect(),
+            Instruction::Allocate { size, .. } => size.value().into_iter().collect(),
+            Instruction::Copy { dest, src, size } => dest
+                .values()
+                .into_iter()
+                .chain(src.values())
+                .chain(std::iter::once(size))
+                .collect(),
+            Instruction::StorageLoad { key, .. }
+            | Instruction::StorageDelete { key }
+            | Instruction::TransientLoad { key, .. } => key.value().into_iter().collect(),
+            Instruction::StorageStore { key, value }
+            | Instruction::TransientStore { key, value } => key
+                .value()
+                .into_iter()
+                .chain(std::iter::once(value))
+                .collect(),
+            Instruction::MappingLoad { mapping, key, .. } => vec![mapping, key],
+            Instruction::MappingStore {
+                mapping,
+                key,
+                value,
+            } => vec![mapping, key, value],
+            Instruction::ArrayLoad { array, index, .. } => vec![array, index],
+            Instruction::ArrayStore {
+                array,
+                index,
+                value,
+            } => vec![array, index, value],
+            Instruction::ArrayLength { array, .. } | Instruction::ArrayPop { array, .. } => {
+                vec![array]
+            }
+            Instruction::ArrayPush { array, value } => vec![array, value],
+            Instruction::Call {
+                target,
+                args,
+                value,
+                ..
+            } => target
+                .address()
+                .into_iter()
+                .chain(args)
+                .chain(value)
+                .collect(),
+            Instruction::DelegateCall {
+                target,
+                selector,
+                args,
+                ..
+            }
+            | Instruction::StaticCall {
+                target,
+                selector,
+                args,
+                ..
+            } => [target, selector].into_iter().chain(args).collect(),
+            Instruction::Create { code, value, .. } => vec![code, value],
+            Instruction::Create2 {
+                code, salt, value, ..
+            } => vec![code, salt, value],
+            Instruction::Selfdestruct { beneficiary } => vec![beneficiary],
+            Instruction::GetContext { .. }
+            | Instruction::Revert { .. }
+            | Instruction::MemorySize { .. } => Vec::new(),
+            Instruction::GetBalance { address, .. }
+            | Instruction::GetCode { address, .. }
+            | Instruction::GetCodeSize { address, .. }
+            | Instruction::GetCodeHash { address, .. } => vec![address],
+            Instruction::Keccak256 { data, len, .. }
+            | Instruction::Sha256 { data, len, .. }
+            | Instruction::Ripemd160 { data, len, .. } => vec![data, len],
+            Instruction::EcRecover { hash, v, r, s, .. } => vec![hash, v, r, s],
+            Instruction::EmitEvent { topics, data, .. } => topics.iter().chain(data).collect(),
+            Instruction::Cast { value, .. }
+            | Instruction::ZeroExtend { value, .. }
+            | Instruction::SignExtend { value, .. }
+            | Instruction::Truncate { value, .. }
+            | Instruction::Assign { value, .. } => vec![value],
+            Instruction::Assert { condition, .. } | Instruction::Require { condition, .. } => {
+                vec![condition]
+            }
+            Instruction::Phi { values, .. } => values.iter().map(|(_, value)| value).collect(),
+            Instruction::Jump { args, .. } => args.iter().collect(),
+            Instruction::Branch {
+                condition,
+                then_args,
+                else_args,
+                ..
+            } => std::iter::once(condition)
+                .chain(then_args)
+                .chain(else_args)
+                .collect(),
+            Instruction::Return { value } => value.iter().collect(),
+            Instruction::MemoryAlloc { size, .. } => vec![size],
+            Instruction::MemoryCopy { dest, src, size } => vec![dest, src, size],
+        }
+    }
+
+    pub fn operands_mut(&mut self) -> Vec<&mut Value> {
+        match self {
+            Instruction::Add { left, right, .. }
+            | Instruction::Sub { left, right, .. }
+            | Instruction::Mul { left, right, .. }
+            | Instruction::Div { left, right, .. }
+            | Instruction::Mod { left, right, .. }
+            | Instruction::Sdiv { left, right, .. }
+            | Instruction::Smod { left, right, .. }
+            | Instruction::CheckedAdd { left, right, .. }
+            | Instruction::CheckedSub { left, right, .. }
+            | Instruction::CheckedMul { left, right, .. }
+            | Instruction::CheckedDiv { left, right, .. }
+            | Instruction::And { left, right, .. }
+            | Instruction::Or { left, right, .. }
+            | Instruction::Xor { left, right, .. }
+            | Instruction::Eq { left, right, .. }
+            | Instruction::Ne { left, right, .. }
+            | Instruction::Lt { left, right, .. }
+            | Instruction::Gt { left, right, .. }
+            | Instruction::Le { left, right, .. }
+            | Instruction::Ge { left, right, .. }
+            | Instruction::Slt { left, right, .. }
+            | Instruction::Sgt { left, right, .. }
+            | Instruction::Sle { left, right, .. }
+            | Instruction::Sge { left, right, .. } => vec![left, right],
+            Instruction::Pow { base, exp, .. } => vec![base, exp],
+            Instruction::Not { operand, .. } => vec![operand],
+            Instruction::Shl { value, shift, .. }
+            | Instruction::Shr { value, shift, .. }
+            | Instruction::Sar { value, shift, .. } => vec![value, shift],
+            Instruction::Select {
+                condition,
+                then_val,
+                else_val,
+                ..
+            } => vec![condition, then_val, else_val],
+            Instruction::Load { location, .. } => location.values_mut(),
+            Instruction::Store { location, value } => location
+                .values_mut()
+                .into_iter()
+                .chain(std::iter::once(value))
+                .collect(),
+            Instruction::Allocate { size, .. } => size.value_mut().into_iter().collect(),
+            Instruction::Copy { dest, src, size } => dest
+                .values_mut()
+                .into_iter()
+                .chain(src.values_mut())
+                .chain(std::iter::once(size))
+                .collect(),
+            Instruction::StorageLoad { key, .. }
+            | Instruction::StorageDelete { key }
+            | Instruction::TransientLoad { key, .. } => key.value_mut().into_iter().collect(),
+            Instruction::StorageStore { key, value }
+            | Instruction::TransientStore { key, value } => key
+                .value_mut()
+                .into_iter()
+                .chain(std::iter::once(value))
+                .collect(),
+            Instruction::MappingLoad { mapping, key, .. } => vec![mapping, key],
+            Instruction::MappingStore {
+                mapping,
+                key,
+                value,
+            } => vec![mapping, key, value],
+            Instruction::ArrayLoad { array, index, .. } => vec![array, index],
+            Instruction::ArrayStore {
+                array,
+                index,
+                value,
+            } => vec![array, index, value],
+            Instruction::ArrayLength { array, .. } | Instruction::ArrayPop { array, .. } => {
+                vec![array]
+            }
+            Instruction::ArrayPush { array, value } => vec![array, value],
+            Instruction::Call {
+                target,
+                args,
+                value,
+                ..
+            } => target
+                .address_mut()
+                .into_iter()
+                .chain(args)
+                .chain(value)
+                .collect(),
+            Instruction::DelegateCall {
+                target,
+                selector,
+                args,
+                ..
+            }
+            | Instruction::StaticCall {
+                target,
+                selector,
+                args,
+                ..
+            } => [target, selector].into_iter().chain(args).collect(),
+            Instruction::Create { code, value, .. } => vec![code, value],
+            Instruction::Create2 {
+                code, salt, value, ..
+            } => vec![code, salt, value],
+            Instruction::Selfdestruct { beneficiary } => vec![beneficiary],
+            Instruction::GetContext { .. }
+            | Instruction::Revert { .. }
+            | Instruction::MemorySize { .. } => Vec::new(),
+            Instruction::GetBalance { address, .. }
+            | Instruction::GetCode { address, .. }
+            | Instruction::GetCodeSize { address, .. }
+            | Instruction::GetCodeHash { address, .. } => vec![address],
+            Instruction::Keccak256 { data, len, .. }
+            | Instruction::Sha256 { data, len, .. }
+            | Instruction::Ripemd160 { data, len, .. } => vec![data, len],
+            Instruction::EcRecover { hash, v, r, s, .. } => vec![hash, v, r, s],
+            Instruction::EmitEvent { topics, data, .. } => topics.iter_mut().chain(data).collect(),
+            Instruction::Cast { value, .. }
+            | Instruction::ZeroExtend { value, .. }
+            | Instruction::SignExtend { value, .. }
+            | Instruction::Truncate { value, .. }
+            | Instruction::Assign { value, .. } => vec![value],
+            Instruction::Assert { condition, .. } | Instruction::Require { condition, .. } => {
+                vec![condition]
+            }
+            Instruction::Phi { values, .. } => values.iter_mut().map(|(_, value)| value).collect(),
+            Instruction::Jump { args, .. } => args.iter_mut().collect(),
+            Instruction::Branch {
+                condition,
+                then_args,
+                else_args,
+                ..
+            } => std::iter::once(condition)
+                .chain(then_args)
+                .chain(else_args)
+                .collect(),
+            Instruction::Return { value } => value.iter_mut().collect(),
+            Instruction::MemoryAlloc { size, .. } => vec![size],
+            Instruction::MemoryCopy { dest, src, size } => vec![dest, src, size],
+        }
+    }
+
     pub fn is_state_changing(&self) -> bool {
         matches!(
             self,
             Instruction::Store { .. }
                 | Instruction::StorageStore { .. }
                 | Instruction::StorageDelete { .. }
+                | Instruction::TransientStore { .. }
                 | Instruction::MappingStore { .. }
                 | Instruction::ArrayStore { .. }
                 | Instruction::ArrayPush { .. }
@@ -519,6 +1091,8 @@ impl Instruction {
             self,
             Instruction::Div { .. }
                 | Instruction::Mod { .. }
+                | Instruction::Sdiv { .. }
+                | Instruction::Smod { .. }
                 | Instruction::CheckedAdd { .. }
                 | Instruction::CheckedSub { .. }
                 | Instruction::CheckedMul { .. }
@@ -5,43 +5,66 @@
  * semantics while exposing the patterns auditors care about.
  */
 
+pub mod abi;
 pub mod analysis;
 pub mod block;
 pub mod builder;
+pub mod chain;
 pub mod codegen;
 pub mod contract;
 pub mod cursor;
+pub mod entry_point;
 pub mod extensions;
 pub mod format;
 pub mod function;
+pub mod hash;
 pub mod inst_builder;
 pub mod instructions;
 pub mod ir_persist;
 pub mod metadata;
 pub mod obfuscation;
+pub mod profiling;
+pub mod review;
 pub mod source_location;
+pub mod stable_id;
+pub mod transform;
 pub mod types;
 pub mod values;
+pub mod version;
+pub mod visit;
 
 pub use block::{BasicBlock, BlockId, BlockParam, Terminator};
 pub use builder::{ContractBuilder, FunctionBuilder};
+pub use chain::{ChainCapabilities, ContextSupport};
 pub use contract::{Contract, ContractMetadata, StorageLayout};
+pub use cursor::{CursorPosition, Edit, EditListener, IRCursor, IRCursorMut};
+pub use entry_point::{EntryKind, EntryPoint, GuardSummary};
 pub use function::{Function, FunctionBody, FunctionSignature, Mutability, Visibility};
 pub use instructions::Instruction;
-pub use metadata::{OptimizationHints, SecurityMetadata};
+pub use metadata::{MappingRole, MappingTag, NatSpec, OptimizationHints, SecurityMetadata};
 pub use obfuscation::{
     ObfuscationConfig, ObfuscationLevel, ObfuscationMapping, ObfuscationPass, VulnerabilityMapper,
 };
 pub use source_location::SourceFiles;
 pub use types::{Type, TypeRegistry};
 pub use values::{Constant, Location, SourceLocation, Value};
+pub use version::{EvmVersion, SolcVersion, VersionPragma};
+pub use visit::{map_operands, visit_operands, InstructionFolder, InstructionVisitor};
 
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum IrError {
-    #[error("Type error: {0}")]
-    TypeError(String),
+    #[error("Type error in {instruction}: expected {expected}, found {found}")]
+    TypeError {
+        instruction: String,
+        expected: String,
+        found: String,
+    },
+    #[error("Undefined value {value} read by {instruction}")]
+    UndefinedValue { value: String, instruction: String },
+    #[error("Undefined block {block} targeted by {instruction}")]
+    UndefinedBlock { block: String, instruction: String },
     #[error("Invalid instruction: {0}")]
     InvalidInstruction(String),
     #[error("Builder error: {0}")]
@@ -88,6 +88,7 @@ pub enum ReentrancyGuardType {
     Mutex,
     CheckEffectsInteraction,
     NonReentrantModifier,
+    TransientLock,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -163,6 +164,22 @@ pub enum VulnerabilityPattern {
     },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MappingRole {
+    Balance,
+    Allowance,
+    Ownership,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappingTag {
+    pub variable: String,
+    pub slot: BigUint,
+    pub role: MappingRole,
+    pub keyed_by_sender: bool,
+    pub adjusted_in: Vec<String>,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct OptimizationHints {
     pub pure_functions: HashSet<String>,
@@ -279,3 +296,152 @@ impl SecurityMetadata {
         functions
     }
 }
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NatSpec {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub notice: Option<String>,
+    pub dev: Option<String>,
+    pub params: Vec<(String, String)>,
+    pub returns: Vec<String>,
+    pub custom: Vec<(String, String)>,
+}
+
+impl NatSpec {
+    pub fn parse(comments: &[&str]) -> Option<Self> {
+        let mut lines = Vec::new();
+        for comment in comments {
+            let comment = comment.trim();
+            if let Some(line) = comment.strip_prefix("///") {
+                lines.push(line.trim().to_string());
+            } else if let Some(body) = comment.strip_prefix("/**") {
+                let body = body.strip_suffix("*/").unwrap_or(body);
+                for line in body.lines() {
+                    let line = line.trim();
+                    let line = line.strip_prefix('*').unwrap_or(line);
+                    lines.push(line.trim().to_string());
+                }
+            }
+        }
+
+        let mut natspec = NatSpec::default();
+        let mut current: Option<(String, String)> = None;
+        for line in lines {
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(tagged) = line.strip_prefix('@') {
+                if let Some((tag, text)) = current.take() {
+                    natspec.push_tag(&tag, text);
+                }
+                let (tag, text) = tagged
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((tagged, ""));
+                current = Some((tag.to_string(), text.trim().to_string()));
+            } else {
+                match current.as_mut() {
+                    Some((_, text)) => {
+                        if !text.is_empty() {
+                            text.push(' ');
+                        }
+                        text.push_str(&line);
+                    }
+                    None => current = Some(("notice".to_string(), line)),
+                }
+            }
+        }
+        if let Some((tag, text)) = current {
+            natspec.push_tag(&tag, text);
+        }
+
+        if natspec.is_empty() {
+            None
+        } else {
+            Some(natspec)
+        }
+    }
+
+    fn push_tag(&mut self, tag: &str, text: String) {
+        let append = |slot: &mut Option<String>, text: String| match slot {
+            Some(existing) => {
+                existing.push(' ');
+                existing.push_str(&text);
+            }
+            None => *slot = Some(text),
+        };
+
+        match tag {
+            "title" => append(&mut self.title, text),
+            "author" => append(&mut self.author, text),
+            "notice" => append(&mut self.notice, text),
+            "dev" => append(&mut self.dev, text),
+            "param" => {
+                let (name, description) = text
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((text.as_str(), ""));
+                self.params
+                    .push((name.to_string(), description.trim().to_string()));
+            }
+            "return" => self.returns.push(text),
+            other => self.custom.push((other.to_string(), text)),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.author.is_none()
+            && self.notice.is_none()
+            && self.dev.is_none()
+            && self.params.is_empty()
+            && self.returns.is_empty()
+            && self.custom.is_empty()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(title) = &self.title {
+            lines.push(format!("@title {}", title));
+        }
+        if let Some(author) = &self.author {
+            lines.push(format!("@author {}", author));
+        }
+        if let Some(notice) = &self.notice {
+            lines.push(format!("@notice {}", notice));
+        }
+        if let Some(dev) = &self.dev {
+            lines.push(format!("@dev {}", dev));
+        }
+        for (name, description) in &self.params {
+            lines.push(format!("@param {} {}", name, description));
+        }
+        for description in &self.returns {
+            lines.push(format!("@return {}", description));
+        }
+        for (tag, text) in &self.custom {
+            lines.push(format!("@{} {}", tag, text));
+        }
+        lines
+    }
+
+    /* Applies `rewrite` to every piece of free text, leaving tags and `@param` names alone. */
+    pub fn rewrite_text(&mut self, rewrite: impl Fn(&str) -> String) {
+        for text in [
+            &mut self.title,
+            &mut self.author,
+            &mut self.notice,
+            &mut self.dev,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *text = rewrite(text);
+        }
+        for (_, text) in self.params.iter_mut().chain(self.custom.iter_mut()) {
+            *text = rewrite(text);
+        }
+        for text in &mut self.returns {
+            *text = rewrite(text);
+        }
+    }
+}
@@ -1,15 +1,85 @@
-use super::ObfuscationMapping;
+use super::{MappingError, ObfuscationMapping};
+use serde_json::Value;
 use std::collections::HashMap;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    /* A single JSON document or JSON Lines, one document per line. */
+    Json,
+    Sarif,
+}
+
+impl ReportFormat {
+    pub fn detect(report: &str) -> Self {
+        match serde_json::from_str::<Value>(report) {
+            Ok(Value::Object(map))
+                if map.contains_key("runs")
+                    && (map.contains_key("version")
+                        || map
+                            .get("$schema")
+                            .and_then(Value::as_str)
+                            .is_some_and(|schema| schema.contains("sarif"))) =>
+            {
+                ReportFormat::Sarif
+            }
+            Ok(_) => ReportFormat::Json,
+            Err(_) if is_json_lines(report) => ReportFormat::Json,
+            Err(_) => ReportFormat::Text,
+        }
+    }
+
+    /* Object keys whose values are schema vocabulary or content hashes rather than text about
+     * the contract, and must come back unchanged. */
+    fn preserved_keys(self) -> &'static [&'static str] {
+        match self {
+            ReportFormat::Text => &[],
+            ReportFormat::Json => &["detector", "severity"],
+            ReportFormat::Sarif => &[
+                "$schema",
+                "version",
+                "ruleId",
+                "ruleIndex",
+                "guid",
+                "kind",
+                "level",
+                "mimeType",
+                "fingerprints",
+                "partialFingerprints",
+                "hashes",
+            ],
+        }
+    }
+}
+
+fn is_json_lines(report: &str) -> bool {
+    let mut lines = report
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .peekable();
+    lines.peek().is_some() && lines.all(|line| serde_json::from_str::<Value>(line).is_ok())
+}
+
 pub struct VulnerabilityMapper {
     mapping: HashMap<String, String>,
 }
 
 impl VulnerabilityMapper {
-    pub fn from_mapping(mapping: ObfuscationMapping) -> Self {
-        Self {
+    pub fn from_mapping(mapping: ObfuscationMapping) -> Result<Self, MappingError> {
+        mapping.verify(None)?;
+        Ok(Self {
             mapping: mapping.mapping,
-        }
+        })
+    }
+
+    pub fn from_mapping_with_key(
+        mapping: ObfuscationMapping,
+        key: &[u8],
+    ) -> Result<Self, MappingError> {
+        mapping.verify(Some(key))?;
+        Ok(Self {
+            mapping: mapping.mapping,
+        })
     }
 
     pub fn deobfuscate_identifier(&self, obfuscated: &str) -> Option<String> {
@@ -32,12 +102,90 @@ impl VulnerabilityMapper {
     pub fn deobfuscate_reports(&self, reports: &[String]) -> Vec<String> {
         reports.iter().map(|r| self.deobfuscate_report(r)).collect()
     }
+
+    /* Replaces whole identifiers only, so `fn_1` inside `fn_12` or `prefix_fn_1` is left alone.
+     * Dots, slashes and colons separate identifiers, which lets file URIs such as
+     * `contract_0.sol` and qualified names such as `contract_0::fn_0` come back too. */
+    pub fn deobfuscate_text(&self, text: &str) -> String {
+        let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '$';
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find(is_ident) {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest.find(|c: char| !is_ident(c)).unwrap_or(rest.len());
+            let word = &rest[..end];
+            result.push_str(self.mapping.get(word).map(String::as_str).unwrap_or(word));
+            rest = &rest[end..];
+        }
+        result.push_str(rest);
+        result
+    }
+
+    /* Restores names inside a structured report and leaves its shape, key order and numbers
+     * untouched. Plain text falls back to `deobfuscate_report`. */
+    pub fn deobfuscate_formatted(
+        &self,
+        report: &str,
+        format: ReportFormat,
+    ) -> Result<String, serde_json::Error> {
+        match format {
+            ReportFormat::Text => Ok(self.deobfuscate_report(report)),
+            ReportFormat::Json if serde_json::from_str::<Value>(report).is_err() => {
+                let mut lines = Vec::new();
+                for line in report.lines() {
+                    if line.trim().is_empty() {
+                        lines.push(String::new());
+                        continue;
+                    }
+                    let mut value: Value = serde_json::from_str(line)?;
+                    self.deobfuscate_value(&mut value, format);
+                    lines.push(serde_json::to_string(&value)?);
+                }
+                let mut output = lines.join("\n");
+                if report.ends_with('\n') {
+                    output.push('\n');
+                }
+                Ok(output)
+            }
+            ReportFormat::Json | ReportFormat::Sarif => {
+                let mut value: Value = serde_json::from_str(report)?;
+                self.deobfuscate_value(&mut value, format);
+                let mut output = serde_json::to_string_pretty(&value)?;
+                if report.ends_with('\n') {
+                    output.push('\n');
+                }
+                Ok(output)
+            }
+        }
+    }
+
+    pub fn deobfuscate_value(&self, value: &mut Value, format: ReportFormat) {
+        match value {
+            Value::String(text) => *text = self.deobfuscate_text(text),
+            Value::Array(items) => {
+                for item in items {
+                    self.deobfuscate_value(item, format);
+                }
+            }
+            Value::Object(map) => {
+                for (key, item) in map.iter_mut() {
+                    if !format.preserved_keys().contains(&key.as_str()) {
+                        self.deobfuscate_value(item, format);
+                    }
+                }
+            }
+            Value::Null | Value::Bool(_) | Value::Number(_) => {}
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::obfuscation::MappingMetadata;
+    use crate::obfuscation::{MappingMetadata, MAPPING_FORMAT_VERSION};
+
+    const KEY: &[u8] = b"test-key";
 
     fn create_test_mapping() -> ObfuscationMapping {
         let mut mapping = HashMap::new();
@@ -46,20 +194,24 @@ mod tests {
         mapping.insert("var_0".to_string(), "liquidityPoolReserves".to_string());
         mapping.insert("fn_1".to_string(), "transfer".to_string());
 
-        ObfuscationMapping {
+        let mut mapping = ObfuscationMapping {
             mapping,
             metadata: MappingMetadata {
+                version: MAPPING_FORMAT_VERSION,
                 created_at: "2024-01-01T00:00:00Z".to_string(),
                 obfuscation_level: "minimal".to_string(),
-                hash_salt: None,
+                salt_fingerprint: None,
+                hmac: None,
             },
-        }
+        };
+        mapping.sign(KEY);
+        mapping
     }
 
     #[test]
     fn test_deobfuscate_identifier() {
         let mapping = create_test_mapping();
-        let mapper = VulnerabilityMapper::from_mapping(mapping);
+        let mapper = VulnerabilityMapper::from_mapping_with_key(mapping, KEY).unwrap();
 
         assert_eq!(
             mapper.deobfuscate_identifier("contract_0"),
@@ -79,7 +231,7 @@ mod tests {
     #[test]
     fn test_deobfuscate_simple_report() {
         let mapping = create_test_mapping();
-        let mapper = VulnerabilityMapper::from_mapping(mapping);
+        let mapper = VulnerabilityMapper::from_mapping_with_key(mapping, KEY).unwrap();
 
         let obfuscated = "Reentrancy in contract_0::fn_0 at position [7]";
         let expected = "Reentrancy in NovelBondingCurve::calculateBondingCurve at position [7]";
@@ -90,7 +242,7 @@ mod tests {
     #[test]
     fn test_deobfuscate_complex_report() {
         let mapping = create_test_mapping();
-        let mapper = VulnerabilityMapper::from_mapping(mapping);
+        let mapper = VulnerabilityMapper::from_mapping_with_key(mapping, KEY).unwrap();
 
         let obfuscated = "Function fn_1 in contract_0 accesses var_0 unsafely";
         let expected =
@@ -102,7 +254,7 @@ mod tests {
     #[test]
     fn test_deobfuscate_multiple_reports() {
         let mapping = create_test_mapping();
-        let mapper = VulnerabilityMapper::from_mapping(mapping);
+        let mapper = VulnerabilityMapper::from_mapping_with_key(mapping, KEY).unwrap();
 
         let reports = vec![
             "Issue in fn_0".to_string(),
@@ -120,7 +272,7 @@ mod tests {
     #[test]
     fn test_deobfuscate_preserves_unknown_identifiers() {
         let mapping = create_test_mapping();
-        let mapper = VulnerabilityMapper::from_mapping(mapping);
+        let mapper = VulnerabilityMapper::from_mapping_with_key(mapping, KEY).unwrap();
 
         let obfuscated = "Unknown identifier unknown_fn in contract_0";
         let result = mapper.deobfuscate_report(obfuscated);
@@ -132,7 +284,7 @@ mod tests {
     #[test]
     fn test_deobfuscate_handles_position_markers() {
         let mapping = create_test_mapping();
-        let mapper = VulnerabilityMapper::from_mapping(mapping);
+        let mapper = VulnerabilityMapper::from_mapping_with_key(mapping, KEY).unwrap();
 
         let obfuscated = "At [5] in fn_0, call to contract_0::fn_1 detected";
         let result = mapper.deobfuscate_report(obfuscated);
@@ -142,4 +294,120 @@ mod tests {
             "At [5] in calculateBondingCurve, call to NovelBondingCurve::transfer detected"
         );
     }
+
+    #[test]
+    fn test_deobfuscate_text_matches_whole_identifiers() {
+        let mapping = create_test_mapping();
+        let mapper = VulnerabilityMapper::from_mapping_with_key(mapping, KEY).unwrap();
+
+        assert_eq!(
+            mapper.deobfuscate_text("contract_0.sol: contract_0::fn_1 calls fn_12"),
+            "NovelBondingCurve.sol: NovelBondingCurve::transfer calls fn_12"
+        );
+    }
+
+    #[test]
+    fn test_deobfuscate_sarif_preserves_structure() {
+        let mapping = create_test_mapping();
+        let mapper = VulnerabilityMapper::from_mapping_with_key(mapping, KEY).unwrap();
+
+        let sarif = r#"{
+  "version": "2.1.0",
+  "runs": [
+    {
+      "results": [
+        {
+          "ruleId": "fn_0",
+          "message": { "text": "Reentrancy in contract_0::fn_0" },
+          "locations": [
+            {
+              "physicalLocation": {
+                "artifactLocation": { "uri": "src/contract_0.sol" },
+                "region": { "startLine": 12 }
+              },
+              "logicalLocations": [{ "fullyQualifiedName": "contract_0.fn_0" }]
+            }
+          ]
+        }
+      ]
+    }
+  ]
+}"#;
+        assert_eq!(ReportFormat::detect(sarif), ReportFormat::Sarif);
+
+        let output = mapper
+            .deobfuscate_formatted(sarif, ReportFormat::Sarif)
+            .unwrap();
+        let value: Value = serde_json::from_str(&output).unwrap();
+        let result = &value["runs"][0]["results"][0];
+        assert_eq!(result["ruleId"], "fn_0");
+        assert_eq!(
+            result["message"]["text"],
+            "Reentrancy in NovelBondingCurve::calculateBondingCurve"
+        );
+        let location = &result["locations"][0];
+        assert_eq!(
+            location["physicalLocation"]["artifactLocation"]["uri"],
+            "src/NovelBondingCurve.sol"
+        );
+        assert_eq!(location["physicalLocation"]["region"]["startLine"], 12);
+        assert_eq!(
+            location["logicalLocations"][0]["fullyQualifiedName"],
+            "NovelBondingCurve.calculateBondingCurve"
+        );
+        assert!(output.find("\"version\"").unwrap() < output.find("\"runs\"").unwrap());
+    }
+
+    #[test]
+    fn test_deobfuscate_json_lines() {
+        let mapping = create_test_mapping();
+        let mapper = VulnerabilityMapper::from_mapping_with_key(mapping, KEY).unwrap();
+
+        let lines =
+            "{\"detector\":\"reentrancy\",\"function\":\"fn_1\"}\n{\"contract\":\"contract_0\"}\n";
+        assert_eq!(ReportFormat::detect(lines), ReportFormat::Json);
+        assert_eq!(
+            mapper
+                .deobfuscate_formatted(lines, ReportFormat::Json)
+                .unwrap(),
+            "{\"detector\":\"reentrancy\",\"function\":\"transfer\"}\n{\"contract\":\"NovelBondingCurve\"}\n"
+        );
+        assert_eq!(ReportFormat::detect("Issue in fn_0"), ReportFormat::Text);
+    }
+
+    #[test]
+    fn test_tampered_mapping_is_rejected() {
+        let mut mapping = create_test_mapping();
+        mapping.metadata.hmac = None;
+        assert_eq!(
+            VulnerabilityMapper::from_mapping_with_key(mapping.clone(), KEY).err(),
+            Some(MappingError::Unsigned)
+        );
+
+        mapping.sign(KEY);
+        assert!(VulnerabilityMapper::from_mapping_with_key(mapping.clone(), KEY).is_ok());
+        assert_eq!(
+            VulnerabilityMapper::from_mapping_with_key(mapping.clone(), b"other").err(),
+            Some(MappingError::SignatureMismatch)
+        );
+
+        mapping
+            .mapping
+            .insert("fn_1".to_string(), "withdrawAll".to_string());
+        assert_eq!(
+            VulnerabilityMapper::from_mapping_with_key(mapping, KEY).err(),
+            Some(MappingError::SignatureMismatch)
+        );
+    }
+
+    #[test]
+    fn test_legacy_mapping_is_told_apart_from_tampering() {
+        let mut mapping = create_test_mapping();
+        mapping.metadata.version = 0;
+        mapping.metadata.hmac = None;
+        assert_eq!(
+            VulnerabilityMapper::from_mapping_with_key(mapping, KEY).err(),
+            Some(MappingError::Legacy)
+        );
+    }
 }
@@ -0,0 +1,399 @@
+/*! Self-check that an obfuscated listing is the original with names swapped.
+ *
+ * Clients only hand over obfuscated IR if they can trust that nothing but identifiers changed.
+ * The check walks both listings line by line: every line must tokenize identically except for
+ * renamed identifiers, which must reverse through the mapping, and redacted string literals.
+ * The outcome is sealed into an attestation signed with HMAC-SHA256 under a shared key.
+ */
+
+use super::ObfuscationMapping;
+use crate::hash::hex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscrepancyKind {
+    LineCount,
+    Structure,
+    Unmapped,
+    WrongMapping,
+    InconsistentRename,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Discrepancy {
+    pub line: usize,
+    pub kind: DiscrepancyKind,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub instructions: usize,
+    pub renamed_tokens: usize,
+    pub redacted_strings: usize,
+    pub unused_mappings: usize,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl IntegrityReport {
+    pub fn is_verified(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token<'a> {
+    Word(&'a str),
+    Literal(&'a str),
+    Punct(char),
+}
+
+pub fn verify_obfuscation(
+    original: &str,
+    obfuscated: &str,
+    mapping: &ObfuscationMapping,
+) -> IntegrityReport {
+    let mut report = IntegrityReport::default();
+    let original_lines = significant_lines(original);
+    let obfuscated_lines = significant_lines(obfuscated);
+
+    if original_lines.len() != obfuscated_lines.len() {
+        report.discrepancies.push(Discrepancy {
+            line: 0,
+            kind: DiscrepancyKind::LineCount,
+            detail: format!(
+                "original has {} lines, obfuscated has {}",
+                original_lines.len(),
+                obfuscated_lines.len()
+            ),
+        });
+    }
+
+    let mut renames: HashMap<&str, &str> = HashMap::new();
+    let mut used: HashSet<&str> = HashSet::new();
+
+    for ((_, before), (line, after)) in original_lines.iter().zip(&obfuscated_lines) {
+        if is_instruction(after) {
+            report.instructions += 1;
+        }
+
+        let expected = tokenize(before);
+        let found = tokenize(after);
+        if expected.len() != found.len() {
+            report.discrepancies.push(Discrepancy {
+                line: *line,
+                kind: DiscrepancyKind::Structure,
+                detail: format!("expected `{}`, found `{}`", before.trim(), after.trim()),
+            });
+            continue;
+        }
+
+        for (expected, found) in expected.iter().zip(&found) {
+            if expected == found {
+                continue;
+            }
+            match (expected, found) {
+                (Token::Literal(_), Token::Literal(_)) => report.redacted_strings += 1,
+                (Token::Word(original), Token::Word(renamed)) => {
+                    match mapping.deobfuscate(renamed) {
+                        Some(reversed) if reversed == *original => {
+                            report.renamed_tokens += 1;
+                            used.insert(*renamed);
+                            if let Some(previous) = renames.insert(*original, *renamed) {
+                                if previous != *renamed {
+                                    report.discrepancies.push(Discrepancy {
+                                        line: *line,
+                                        kind: DiscrepancyKind::InconsistentRename,
+                                        detail: format!(
+                                            "`{}` renamed to both `{}` and `{}`",
+                                            original, previous, renamed
+                                        ),
+                                    });
+                                }
+                            }
+                        }
+                        Some(reversed) => report.discrepancies.push(Discrepancy {
+                            line: *line,
+                            kind: DiscrepancyKind::WrongMapping,
+                            detail: format!(
+                                "`{}` reverses to `{}`, expected `{}`",
+                                renamed, reversed, original
+                            ),
+                        }),
+                        None if looks_obfuscated(renamed) => {
+                            report.discrepancies.push(Discrepancy {
+                                line: *line,
+                                kind: DiscrepancyKind::Unmapped,
+                                detail: format!("`{}` has no entry in the mapping", renamed),
+                            })
+                        }
+                        None => report.discrepancies.push(Discrepancy {
+                            line: *line,
+                            kind: DiscrepancyKind::Structure,
+                            detail: format!("expected `{}`, found `{}`", original, renamed),
+                        }),
+                    }
+                }
+                _ => {
+                    report.discrepancies.push(Discrepancy {
+                        line: *line,
+                        kind: DiscrepancyKind::Structure,
+                        detail: format!("expected `{}`, found `{}`", before.trim(), after.trim()),
+                    });
+                    break;
+                }
+            }
+        }
+    }
+
+    report.unused_mappings = mapping
+        .mapping
+        .keys()
+        .filter(|key| !used.contains(key.as_str()))
+        .count();
+    report
+}
+
+/* Blank lines move with formatting, so they are dropped before lines are paired. Comments stay:
+ * slot notes and headers carry names, and anything altered there must be caught like code. Line
+ * numbers refer to the obfuscated input. */
+fn significant_lines(text: &str) -> Vec<(usize, &str)> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty())
+        .collect()
+}
+
+fn is_instruction(line: &str) -> bool {
+    let line = line.trim();
+    !(line.starts_with("contract ")
+        || line.starts_with("function ")
+        || line.starts_with("slot ")
+        || line == "}"
+        || line.ends_with(':'))
+}
+
+fn tokenize(line: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if c == '"' {
+            let mut end = line.len();
+            for (index, c) in chars.by_ref() {
+                if c == '"' {
+                    end = index + 1;
+                    break;
+                }
+            }
+            tokens.push(Token::Literal(&line[start..end]));
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut end = start + c.len_utf8();
+            while let Some(&(index, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_') {
+                    break;
+                }
+                end = index + c.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Word(&line[start..end]));
+        } else {
+            tokens.push(Token::Punct(c));
+        }
+    }
+
+    tokens
+}
+
+fn looks_obfuscated(word: &str) -> bool {
+    let counted = ["contract_", "fn_", "var_", "event_"].iter().any(|prefix| {
+        word.strip_prefix(prefix)
+            .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+    });
+    let hashed = ["c_", "f_", "v_", "e_"].iter().any(|prefix| {
+        word.strip_prefix(prefix)
+            .is_some_and(|h| h.len() == 6 && h.chars().all(|c| c.is_ascii_hexdigit()))
+    });
+    counted || hashed
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationClaims {
+    pub version: u32,
+    pub created_at: String,
+    pub original_sha256: String,
+    pub obfuscated_sha256: String,
+    pub mapping_sha256: String,
+    pub verified: bool,
+    pub instructions: usize,
+    pub renamed_tokens: usize,
+    pub redacted_strings: usize,
+    pub discrepancies: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObfuscationAttestation {
+    #[serde(flatten)]
+    pub claims: AttestationClaims,
+    pub signature: String,
+}
+
+impl ObfuscationAttestation {
+    pub fn sign(
+        report: &IntegrityReport,
+        original: &[u8],
+        obfuscated: &[u8],
+        mapping: &[u8],
+        key: &[u8],
+    ) -> Self {
+        let claims = AttestationClaims {
+            version: 1,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            original_sha256: hex(&Sha256::digest(original)),
+            obfuscated_sha256: hex(&Sha256::digest(obfuscated)),
+            mapping_sha256: hex(&Sha256::digest(mapping)),
+            verified: report.is_verified(),
+            instructions: report.instructions,
+            renamed_tokens: report.renamed_tokens,
+            redacted_strings: report.redacted_strings,
+            discrepancies: report.discrepancies.len(),
+        };
+        let signature = Self::signature_for(&claims, key);
+        Self { claims, signature }
+    }
+
+    pub fn verify_signature(&self, key: &[u8]) -> bool {
+        constant_time_eq(
+            Self::signature_for(&self.claims, key).as_bytes(),
+            self.signature.as_bytes(),
+        )
+    }
+
+    fn signature_for(claims: &AttestationClaims, key: &[u8]) -> String {
+        let payload = serde_json::to_vec(claims).expect("attestation claims always serialize");
+        hex(&hmac_sha256(key, &payload))
+    }
+}
+
+pub(crate) fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK: usize = 64;
+    let mut block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let inner_pad: Vec<u8> = block.iter().map(|b| b ^ 0x36).collect();
+    let outer_pad: Vec<u8> = block.iter().map(|b| b ^ 0x5c).collect();
+
+    let inner = Sha256::new()
+        .chain_update(&inner_pad)
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(&outer_pad)
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+/* Signature checks must not stop at the first differing byte, or the time taken leaks how much
+ * of a forged signature was right. */
+pub(crate) fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::obfuscation::{MappingMetadata, MAPPING_FORMAT_VERSION};
+
+    const ORIGINAL: &str = "contract Vault {\n\n  function %withdraw(i256) public  {\n  block0(v0: i256):\n    v1 = sload iconst.i256 0  // owner\n    require v1, \"not owner\"\n    return\n  }\n}\n";
+    const OBFUSCATED: &str = "contract contract_0 {\n\n  function %fn_0(i256) public  {\n  block0(v0: i256):\n    v1 = sload iconst.i256 0  // var_0\n    require v1, \"error_0\"\n    return\n  }\n}\n";
+
+    fn mapping() -> ObfuscationMapping {
+        ObfuscationMapping {
+            mapping: [
+                ("contract_0", "Vault"),
+                ("fn_0", "withdraw"),
+                ("var_0", "owner"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+            metadata: MappingMetadata {
+                version: MAPPING_FORMAT_VERSION,
+                created_at: "2024-01-01T00:00:00Z".to_string(),
+                obfuscation_level: "minimal".to_string(),
+                salt_fingerprint: None,
+                hmac: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_renamed_listing_verifies_and_signs() {
+        let report = verify_obfuscation(ORIGINAL, OBFUSCATED, &mapping());
+        assert!(report.is_verified(), "{:?}", report.discrepancies);
+        assert_eq!(report.instructions, 3);
+        assert_eq!(report.renamed_tokens, 3);
+        assert_eq!(report.redacted_strings, 1);
+        assert_eq!(report.unused_mappings, 0);
+
+        let mut attestation = ObfuscationAttestation::sign(
+            &report,
+            ORIGINAL.as_bytes(),
+            OBFUSCATED.as_bytes(),
+            b"{}",
+            b"shared-key",
+        );
+        assert!(attestation.verify_signature(b"shared-key"));
+        assert!(!attestation.verify_signature(b"other-key"));
+
+        attestation.claims.renamed_tokens += 1;
+        assert!(!attestation.verify_signature(b"shared-key"));
+    }
+
+    #[test]
+    fn test_reordered_and_unmapped_tokens_are_reported() {
+        let reordered = OBFUSCATED
+            .replace("    return\n", "")
+            .replace("  block0", "    return\n  block0")
+            .replace("%fn_0", "%fn_1");
+        let report = verify_obfuscation(ORIGINAL, &reordered, &mapping());
+
+        let kinds: Vec<_> = report.discrepancies.iter().map(|d| d.kind).collect();
+        assert!(!report.is_verified());
+        assert!(kinds.contains(&DiscrepancyKind::Unmapped));
+        assert!(kinds.contains(&DiscrepancyKind::Structure));
+    }
+
+    #[test]
+    fn test_altered_comments_are_reported() {
+        let leaked = OBFUSCATED.replace("// var_0", "// owner balance");
+        let report = verify_obfuscation(ORIGINAL, &leaked, &mapping());
+        assert!(!report.is_verified());
+        assert_eq!(report.discrepancies[0].line, 5);
+        assert_eq!(report.discrepancies[0].kind, DiscrepancyKind::Structure);
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+    }
+}
@@ -1,8 +1,31 @@
+use super::integrity::{constant_time_eq, hmac_sha256};
 use super::NameObfuscator;
+use crate::hash::hex;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
+use thiserror::Error;
+
+/* Signing key used when the caller passes none. Keys are never written into a mapping, so a
+ * tampered file cannot carry the key that re-signs it. */
+pub const MAPPING_KEY_ENV: &str = "THALIR_MAPPING_KEY";
+
+/* Mappings written before signing existed carry no version and deserialize as 0. */
+pub const MAPPING_FORMAT_VERSION: u32 = 1;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MappingError {
+    #[error("Mapping predates signed mappings; re-export it with this version and a signing key before de-obfuscating")]
+    Legacy,
+    #[error("Mapping is not signed; re-export it with a signing key before de-obfuscating")]
+    Unsigned,
+    #[error("No key to verify the mapping with; supply the signing key or set THALIR_MAPPING_KEY")]
+    MissingKey,
+    #[error("Mapping signature does not match its contents; the file was modified or signed with a different key")]
+    SignatureMismatch,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ObfuscationMapping {
@@ -12,25 +35,77 @@ pub struct ObfuscationMapping {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MappingMetadata {
+    #[serde(default)]
+    pub version: u32,
     pub created_at: String,
     pub obfuscation_level: String,
-    pub hash_salt: Option<String>,
+    /* A digest of the salt, enough to tell whether two rounds share one without revealing it.
+     * Older mappings named this `hash_salt`; the obfuscator never filled it in. */
+    #[serde(default, alias = "hash_salt", skip_serializing_if = "Option::is_none")]
+    pub salt_fingerprint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hmac: Option<String>,
 }
 
 impl ObfuscationMapping {
     pub fn from_obfuscator(obfuscator: &NameObfuscator) -> Self {
-        let mapping = obfuscator.export_mapping();
-
         Self {
-            mapping,
+            mapping: obfuscator.export_mapping(),
             metadata: MappingMetadata {
+                version: MAPPING_FORMAT_VERSION,
                 created_at: chrono::Utc::now().to_rfc3339(),
                 obfuscation_level: "standard".to_string(),
-                hash_salt: None,
+                salt_fingerprint: obfuscator.hash_salt().map(salt_fingerprint),
+                hmac: None,
             },
         }
     }
 
+    pub fn sign(&mut self, key: &[u8]) {
+        self.metadata.hmac = Some(self.compute_hmac(key));
+    }
+
+    /* Without an explicit key the one in `THALIR_MAPPING_KEY` is used. Every mapping must be
+     * signed; an unsigned one is rejected rather than trusted. */
+    pub fn verify(&self, key: Option<&[u8]>) -> std::result::Result<(), MappingError> {
+        if self.metadata.version < MAPPING_FORMAT_VERSION {
+            return Err(MappingError::Legacy);
+        }
+        let hmac = self.metadata.hmac.as_ref().ok_or(MappingError::Unsigned)?;
+        let from_env = key_from_env();
+        let key = key
+            .or(from_env.as_deref())
+            .ok_or(MappingError::MissingKey)?;
+        if constant_time_eq(hmac.as_bytes(), self.compute_hmac(key).as_bytes()) {
+            Ok(())
+        } else {
+            Err(MappingError::SignatureMismatch)
+        }
+    }
+
+    fn compute_hmac(&self, key: &[u8]) -> String {
+        #[derive(Serialize)]
+        struct Signed<'m> {
+            mapping: BTreeMap<&'m str, &'m str>,
+            created_at: &'m str,
+            obfuscation_level: &'m str,
+            salt_fingerprint: Option<&'m str>,
+        }
+
+        let signed = Signed {
+            mapping: self
+                .mapping
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect(),
+            created_at: &self.metadata.created_at,
+            obfuscation_level: &self.metadata.obfuscation_level,
+            salt_fingerprint: self.metadata.salt_fingerprint.as_deref(),
+        };
+        let payload = serde_json::to_vec(&signed).expect("mapping contents always serialize");
+        hex(&hmac_sha256(key, &payload))
+    }
+
     pub fn save_to_file(&self, path: &Path) -> Result<()> {
         let json = serde_json::to_string_pretty(self)?;
         std::fs::write(path, json)?;
@@ -48,6 +123,17 @@ impl ObfuscationMapping {
     }
 }
 
+pub fn key_from_env() -> Option<Vec<u8>> {
+    std::env::var(MAPPING_KEY_ENV)
+        .ok()
+        .filter(|key| !key.is_empty())
+        .map(String::into_bytes)
+}
+
+pub fn salt_fingerprint(salt: &str) -> String {
+    hex(&Sha256::digest(salt.as_bytes()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,9 +150,11 @@ mod tests {
         let obf_mapping = ObfuscationMapping {
             mapping,
             metadata: MappingMetadata {
+                version: MAPPING_FORMAT_VERSION,
                 created_at: "2024-01-01T00:00:00Z".to_string(),
                 obfuscation_level: "minimal".to_string(),
-                hash_salt: None,
+                salt_fingerprint: None,
+                hmac: None,
             },
         };
 
@@ -91,9 +179,11 @@ mod tests {
         let obf_mapping = ObfuscationMapping {
             mapping,
             metadata: MappingMetadata {
+                version: MAPPING_FORMAT_VERSION,
                 created_at: "2024-01-01T00:00:00Z".to_string(),
                 obfuscation_level: "standard".to_string(),
-                hash_salt: Some("test-salt".to_string()),
+                salt_fingerprint: Some(salt_fingerprint("test-salt")),
+                hmac: None,
             },
         };
 
@@ -103,7 +193,10 @@ mod tests {
 
         assert_eq!(loaded.deobfuscate("c_abc123"), Some("TestContract"));
         assert_eq!(loaded.deobfuscate("f_def456"), Some("testFunction"));
-        assert_eq!(loaded.metadata.hash_salt, Some("test-salt".to_string()));
+        assert_eq!(
+            loaded.metadata.salt_fingerprint,
+            Some(salt_fingerprint("test-salt"))
+        );
     }
 
     #[test]
@@ -125,4 +218,59 @@ mod tests {
         assert_eq!(mapping.deobfuscate("fn_0"), Some("function1"));
         assert_eq!(mapping.deobfuscate("var_0"), Some("storage1"));
     }
+
+    #[test]
+    fn test_salt_is_never_serialized_or_used_as_key() {
+        let mut obfuscator = NameObfuscator::new(ObfuscationConfig {
+            level: ObfuscationLevel::Standard,
+            retain_mapping: true,
+            hash_salt: Some("engagement-salt".to_string()),
+            ..Default::default()
+        });
+        obfuscator.obfuscate_contract_name("Vault");
+
+        let mut mapping = ObfuscationMapping::from_obfuscator(&obfuscator);
+        let json = serde_json::to_string(&mapping).unwrap();
+        assert!(!json.contains("engagement-salt"));
+        assert_eq!(
+            mapping.verify(Some(b"engagement-salt")),
+            Err(MappingError::Unsigned)
+        );
+
+        mapping.sign(b"shared-key");
+        assert_eq!(mapping.verify(Some(b"shared-key")), Ok(()));
+        assert_eq!(
+            mapping.verify(Some(b"engagement-salt")),
+            Err(MappingError::SignatureMismatch)
+        );
+    }
+
+    #[test]
+    fn test_pre_signing_mapping_loads_as_legacy() {
+        let json = r#"{
+            "mapping": { "contract_0": "Vault", "fn_0": "withdraw" },
+            "metadata": {
+                "created_at": "2024-01-01T00:00:00Z",
+                "obfuscation_level": "standard",
+                "hash_salt": null
+            }
+        }"#;
+
+        let mapping: ObfuscationMapping = serde_json::from_str(json).unwrap();
+        assert_eq!(mapping.metadata.version, 0);
+        assert_eq!(mapping.deobfuscate("fn_0"), Some("withdraw"));
+        assert_eq!(
+            mapping.verify(Some(b"shared-key")),
+            Err(MappingError::Legacy)
+        );
+
+        let mut reexported = mapping.clone();
+        reexported.metadata.version = MAPPING_FORMAT_VERSION;
+        assert_eq!(
+            reexported.verify(Some(b"shared-key")),
+            Err(MappingError::Unsigned)
+        );
+        reexported.sign(b"shared-key");
+        assert_eq!(reexported.verify(Some(b"shared-key")), Ok(()));
+    }
 }
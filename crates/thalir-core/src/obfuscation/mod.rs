@@ -3,26 +3,40 @@
  * Proprietary code needs auditing but can't be shared openly. Hash identifiers and remove metadata
  * while preserving security-relevant behavior, then map findings back to original names when reporting
  * vulnerabilities.
+ *
+ * Deliveries obfuscated with the same salt keep each name across audit rounds, so `RoundDiff` can
+ * tell an auditor which functions changed since the last round without naming them.
  */
 
 pub mod deobfuscator;
+pub mod integrity;
 pub mod mapping_store;
 pub mod name_obfuscator;
 pub mod pass;
+pub mod policy;
+pub mod round_diff;
 pub mod string_sanitizer;
 
-pub use deobfuscator::VulnerabilityMapper;
-pub use mapping_store::{MappingMetadata, ObfuscationMapping};
+pub use deobfuscator::{ReportFormat, VulnerabilityMapper};
+pub use integrity::{verify_obfuscation, IntegrityReport, ObfuscationAttestation};
+pub use mapping_store::{
+    MappingError, MappingMetadata, ObfuscationMapping, MAPPING_FORMAT_VERSION, MAPPING_KEY_ENV,
+};
 pub use name_obfuscator::NameObfuscator;
 pub use pass::ObfuscationPass;
+pub use policy::{EventSignatures, MetadataPolicy};
+pub use round_diff::{ChangeKind, FunctionChange, RoundDiff, RoundKeyError};
 pub use string_sanitizer::StringSanitizer;
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ObfuscationLevel {
+    #[serde(alias = "none")]
     None,
+    #[serde(alias = "minimal")]
     Minimal,
+    #[serde(alias = "standard")]
     Standard,
 }
 
@@ -32,6 +46,28 @@ impl Default for ObfuscationLevel {
     }
 }
 
+/* Classes switched on here get readable pseudonyms (`ContractA`, `funcAlpha`, `var_7`) in place of
+ * the level's counters or hashes, so findings can be discussed aloud in audit meetings. Aliases are
+ * assigned in encounter order and recorded in the mapping like any other name. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AliasClasses {
+    pub contracts: bool,
+    pub functions: bool,
+    pub storage: bool,
+    pub events: bool,
+}
+
+impl AliasClasses {
+    pub fn all() -> Self {
+        Self {
+            contracts: true,
+            functions: true,
+            storage: true,
+            events: true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ObfuscationConfig {
     pub level: ObfuscationLevel,
@@ -40,6 +76,10 @@ pub struct ObfuscationConfig {
     pub strip_string_constants: bool,
     pub strip_error_messages: bool,
     pub strip_metadata: bool,
+    #[serde(default)]
+    pub metadata: Option<MetadataPolicy>,
+    #[serde(default)]
+    pub aliases: AliasClasses,
 }
 
 impl Default for ObfuscationConfig {
@@ -51,6 +91,8 @@ impl Default for ObfuscationConfig {
             strip_string_constants: false,
             strip_error_messages: false,
             strip_metadata: false,
+            metadata: None,
+            aliases: AliasClasses::default(),
         }
     }
 }
@@ -64,6 +106,8 @@ impl ObfuscationConfig {
             strip_string_constants: true,
             strip_error_messages: true,
             strip_metadata: true,
+            metadata: None,
+            aliases: AliasClasses::default(),
         }
     }
 
@@ -75,6 +119,17 @@ impl ObfuscationConfig {
             strip_string_constants: false,
             strip_error_messages: false,
             strip_metadata: false,
+            metadata: None,
+            aliases: AliasClasses::default(),
+        }
+    }
+
+    /* An explicit policy wins; otherwise `strip_metadata` keeps its all-or-nothing meaning. */
+    pub fn metadata_policy(&self) -> MetadataPolicy {
+        match self.metadata {
+            Some(policy) => policy,
+            None if self.strip_metadata => MetadataPolicy::strip_all(),
+            None => MetadataPolicy::retain_all(),
         }
     }
 }
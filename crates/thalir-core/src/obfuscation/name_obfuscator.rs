@@ -9,6 +9,7 @@ pub struct NameObfuscator {
     contract_counter: usize,
     function_counter: usize,
     storage_counter: usize,
+    event_counter: usize,
 }
 
 impl NameObfuscator {
@@ -20,6 +21,7 @@ impl NameObfuscator {
             contract_counter: 0,
             function_counter: 0,
             storage_counter: 0,
+            event_counter: 0,
         }
     }
 
@@ -30,6 +32,11 @@ impl NameObfuscator {
 
         let obfuscated = match self.config.level {
             ObfuscationLevel::None => name.to_string(),
+            _ if self.config.aliases.contracts => {
+                let result = format!("Contract{}", letters(self.contract_counter));
+                self.contract_counter += 1;
+                result
+            }
             ObfuscationLevel::Minimal => {
                 let result = format!("contract_{}", self.contract_counter);
                 self.contract_counter += 1;
@@ -38,11 +45,7 @@ impl NameObfuscator {
             ObfuscationLevel::Standard => self.hash_name(name, "c"),
         };
 
-        if self.config.retain_mapping {
-            self.mapping.insert(name.to_string(), obfuscated.clone());
-            self.reverse_mapping
-                .insert(obfuscated.clone(), name.to_string());
-        }
+        self.remember(name, &obfuscated);
 
         obfuscated
     }
@@ -54,6 +57,11 @@ impl NameObfuscator {
 
         let obfuscated = match self.config.level {
             ObfuscationLevel::None => name.to_string(),
+            _ if self.config.aliases.functions => {
+                let result = format!("func{}", nato(self.function_counter));
+                self.function_counter += 1;
+                result
+            }
             ObfuscationLevel::Minimal => {
                 let result = format!("fn_{}", self.function_counter);
                 self.function_counter += 1;
@@ -62,11 +70,7 @@ impl NameObfuscator {
             ObfuscationLevel::Standard => self.hash_name(name, "f"),
         };
 
-        if self.config.retain_mapping {
-            self.mapping.insert(name.to_string(), obfuscated.clone());
-            self.reverse_mapping
-                .insert(obfuscated.clone(), name.to_string());
-        }
+        self.remember(name, &obfuscated);
 
         obfuscated
     }
@@ -78,6 +82,11 @@ impl NameObfuscator {
 
         let obfuscated = match self.config.level {
             ObfuscationLevel::None => name.to_string(),
+            _ if self.config.aliases.storage => {
+                let result = format!("var_{}", self.storage_counter);
+                self.storage_counter += 1;
+                result
+            }
             ObfuscationLevel::Minimal => {
                 let result = format!("var_{}", self.storage_counter);
                 self.storage_counter += 1;
@@ -86,15 +95,47 @@ impl NameObfuscator {
             ObfuscationLevel::Standard => self.hash_name(name, "v"),
         };
 
-        if self.config.retain_mapping {
-            self.mapping.insert(name.to_string(), obfuscated.clone());
-            self.reverse_mapping
-                .insert(obfuscated.clone(), name.to_string());
+        self.remember(name, &obfuscated);
+
+        obfuscated
+    }
+
+    pub fn obfuscate_event_name(&mut self, name: &str) -> String {
+        if let Some(obfuscated) = self.mapping.get(name) {
+            return obfuscated.clone();
         }
 
+        let obfuscated = match self.config.level {
+            ObfuscationLevel::None => name.to_string(),
+            _ if self.config.aliases.events => {
+                let result = format!("Event{}", letters(self.event_counter));
+                self.event_counter += 1;
+                result
+            }
+            ObfuscationLevel::Minimal => {
+                let result = format!("event_{}", self.event_counter);
+                self.event_counter += 1;
+                result
+            }
+            ObfuscationLevel::Standard => self.hash_name(name, "e"),
+        };
+
+        self.remember(name, &obfuscated);
+
         obfuscated
     }
 
+    /* The forward cache is always kept, since counters and aliases would otherwise hand a name
+     * seen twice a second pseudonym. Only the exported reverse mapping depends on the config. */
+    fn remember(&mut self, name: &str, obfuscated: &str) {
+        self.mapping
+            .insert(name.to_string(), obfuscated.to_string());
+        if self.config.retain_mapping {
+            self.reverse_mapping
+                .insert(obfuscated.to_string(), name.to_string());
+        }
+    }
+
     fn hash_name(&self, name: &str, prefix: &str) -> String {
         let mut hasher = Sha256::new();
 
@@ -108,6 +149,10 @@ impl NameObfuscator {
         format!("{}_{:02x}{:02x}{:02x}", prefix, hash[0], hash[1], hash[2])
     }
 
+    pub fn hash_salt(&self) -> Option<&str> {
+        self.config.hash_salt.as_deref()
+    }
+
     pub fn export_mapping(&self) -> HashMap<String, String> {
         self.reverse_mapping.clone()
     }
@@ -125,9 +170,36 @@ impl NameObfuscator {
     }
 }
 
+const NATO: [&str; 26] = [
+    "Alpha", "Bravo", "Charlie", "Delta", "Echo", "Foxtrot", "Golf", "Hotel", "India", "Juliett",
+    "Kilo", "Lima", "Mike", "November", "Oscar", "Papa", "Quebec", "Romeo", "Sierra", "Tango",
+    "Uniform", "Victor", "Whiskey", "Xray", "Yankee", "Zulu",
+];
+
+fn letters(mut index: usize) -> String {
+    let mut out = Vec::new();
+    loop {
+        out.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    out.reverse();
+    String::from_utf8(out).unwrap()
+}
+
+fn nato(index: usize) -> String {
+    match index / NATO.len() {
+        0 => NATO[index].to_string(),
+        round => format!("{}{}", NATO[index % NATO.len()], round + 1),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::obfuscation::AliasClasses;
 
     #[test]
     fn test_minimal_obfuscation() {
@@ -150,6 +222,58 @@ mod tests {
         assert_eq!(obfuscator.obfuscate_storage_name("allowances"), "var_1");
     }
 
+    #[test]
+    fn test_readable_aliases_per_class() {
+        let config = ObfuscationConfig {
+            level: ObfuscationLevel::Standard,
+            retain_mapping: true,
+            aliases: AliasClasses {
+                contracts: true,
+                functions: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let mut obfuscator = NameObfuscator::new(config);
+
+        assert_eq!(obfuscator.obfuscate_contract_name("MyToken"), "ContractA");
+        assert_eq!(obfuscator.obfuscate_contract_name("Vault"), "ContractB");
+        assert_eq!(obfuscator.obfuscate_function_name("transfer"), "funcAlpha");
+        assert_eq!(obfuscator.obfuscate_function_name("approve"), "funcBravo");
+        assert_eq!(obfuscator.obfuscate_function_name("transfer"), "funcAlpha");
+        assert!(obfuscator
+            .obfuscate_storage_name("balances")
+            .starts_with("v_"));
+        assert_eq!(obfuscator.deobfuscate("funcBravo"), Some("approve"));
+
+        assert_eq!(letters(27), "AB");
+        assert_eq!(nato(26), "Alpha2");
+    }
+
+    #[test]
+    fn test_aliases_are_stable_without_a_retained_mapping() {
+        let config = ObfuscationConfig {
+            level: ObfuscationLevel::Minimal,
+            retain_mapping: false,
+            aliases: AliasClasses {
+                functions: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let mut obfuscator = NameObfuscator::new(config);
+
+        assert_eq!(obfuscator.obfuscate_function_name("transfer"), "funcAlpha");
+        assert_eq!(obfuscator.obfuscate_function_name("approve"), "funcBravo");
+        assert_eq!(obfuscator.obfuscate_function_name("transfer"), "funcAlpha");
+        assert_eq!(obfuscator.obfuscate_storage_name("balances"), "var_0");
+        assert_eq!(obfuscator.obfuscate_storage_name("balances"), "var_0");
+        assert!(obfuscator.export_mapping().is_empty());
+        assert_eq!(obfuscator.deobfuscate("funcAlpha"), None);
+    }
+
     #[test]
     fn test_standard_obfuscation() {
         let config = ObfuscationConfig {
@@ -1,14 +1,22 @@
-use super::{NameObfuscator, ObfuscationConfig, ObfuscationMapping, StringSanitizer};
+use super::policy::abi_signature;
+use super::{
+    EventSignatures, MetadataPolicy, NameObfuscator, ObfuscationConfig, ObfuscationMapping,
+    StringSanitizer,
+};
 use crate::analysis::{AnalysisID, Pass, PassManager};
 use crate::contract::Contract;
-use crate::function::Function;
+use crate::function::{Function, Visibility};
+use crate::hash::{keccak256, selector_u32};
 use crate::instructions::Instruction;
+use crate::values::SourceLocation;
+use crate::SourceFiles;
 use anyhow::Result;
 use indexmap::IndexMap;
 use std::any::Any;
+use std::collections::HashMap;
 
 pub struct ObfuscationPass {
-    config: ObfuscationConfig,
+    policy: MetadataPolicy,
     obfuscator: NameObfuscator,
     sanitizer: StringSanitizer,
 }
@@ -16,9 +24,9 @@ pub struct ObfuscationPass {
 impl ObfuscationPass {
     pub fn new(config: ObfuscationConfig) -> Self {
         Self {
+            policy: config.metadata_policy(),
             obfuscator: NameObfuscator::new(config.clone()),
-            sanitizer: StringSanitizer::new(config.clone()),
-            config,
+            sanitizer: StringSanitizer::new(config),
         }
     }
 
@@ -26,12 +34,18 @@ impl ObfuscationPass {
         ObfuscationMapping::from_obfuscator(&self.obfuscator)
     }
 
-    fn obfuscate_functions(&mut self, contract: &mut Contract) -> Result<()> {
+    fn obfuscate_functions(
+        &mut self,
+        contract: &mut Contract,
+        renamed: &mut HashMap<String, String>,
+    ) -> Result<()> {
         let mut new_functions = IndexMap::new();
 
         for (old_name, mut func) in contract.functions.drain(..) {
+            func.metadata.selector = self.retained_selector(&func);
             let new_name = self.obfuscator.obfuscate_function_name(&old_name);
             func.signature.name = new_name.clone();
+            renamed.insert(old_name, new_name.clone());
 
             self.obfuscate_function_body(&mut func)?;
 
@@ -43,19 +57,95 @@ impl ObfuscationPass {
     }
 
     fn obfuscate_function_body(&mut self, func: &mut Function) -> Result<()> {
+        let mut params = HashMap::new();
         for (i, param) in func.signature.params.iter_mut().enumerate() {
-            param.name = format!("p{}", i);
+            let name = format!("p{}", i);
+            params.insert(std::mem::replace(&mut param.name, name.clone()), name);
+        }
+
+        if !self.policy.natspec {
+            func.metadata.natspec = None;
+        } else if let Some(natspec) = &mut func.metadata.natspec {
+            for (i, (name, _)) in natspec.params.iter_mut().enumerate() {
+                *name = format!("p{}", i);
+            }
+            natspec.rewrite_text(|text| rename_identifiers(text, &params));
         }
 
         for (_block_id, block) in &mut func.body.blocks {
             for inst in &mut block.instructions {
                 self.sanitize_instruction_strings(inst);
             }
+            self.apply_location_policy(&mut block.metadata.instruction_locations);
         }
 
         Ok(())
     }
 
+    fn retained_selector(&self, func: &Function) -> Option<u32> {
+        if !self.policy.function_selectors
+            || !matches!(func.visibility, Visibility::Public | Visibility::External)
+        {
+            return None;
+        }
+        let params = func.signature.params.iter().map(|p| &p.param_type);
+        abi_signature(&func.signature.name, params).map(|sig| selector_u32(&sig))
+    }
+
+    fn apply_location_policy(&self, locations: &mut HashMap<usize, SourceLocation>) {
+        if !self.policy.source_lines && !self.policy.source_files {
+            locations.clear();
+            return;
+        }
+
+        for location in locations.values_mut() {
+            if !self.policy.source_files {
+                location.file.clear();
+            }
+            if !self.policy.source_lines {
+                location.line = 0;
+                location.column = 0;
+                location.end_line = None;
+                location.end_column = None;
+                location.start_byte = 0;
+                location.end_byte = 0;
+            }
+        }
+    }
+
+    /* Hashed events keep their topic0, which is public on-chain anyway and lets known standard
+     * events still be recognised; events whose parameters have no ABI spelling fall back to a
+     * pseudonym. */
+    fn obfuscate_events(&mut self, contract: &mut Contract, renamed: &mut HashMap<String, String>) {
+        if self.policy.event_signatures == EventSignatures::Keep {
+            return;
+        }
+
+        for event in &mut contract.events {
+            let params = event.parameters.iter().map(|p| &p.param_type);
+            let topic = match self.policy.event_signatures {
+                EventSignatures::Hash => {
+                    abi_signature(&event.name, params).map(|sig| keccak256(sig.as_bytes()))
+                }
+                _ => None,
+            };
+
+            let name = match topic {
+                Some(hash) => format!(
+                    "0x{}",
+                    hash.iter()
+                        .map(|b| format!("{:02x}", b))
+                        .collect::<String>()
+                ),
+                None => self.obfuscator.obfuscate_event_name(&event.name),
+            };
+            renamed.insert(std::mem::replace(&mut event.name, name.clone()), name);
+            for (i, param) in event.parameters.iter_mut().enumerate() {
+                param.name = format!("p{}", i);
+            }
+        }
+    }
+
     fn sanitize_instruction_strings(&mut self, inst: &mut Instruction) {
         match inst {
             Instruction::Require { message, .. } => {
@@ -71,30 +161,70 @@ impl ObfuscationPass {
         }
     }
 
-    fn obfuscate_storage(&mut self, contract: &mut Contract) -> Result<()> {
+    fn obfuscate_storage(
+        &mut self,
+        contract: &mut Contract,
+        renamed: &mut HashMap<String, String>,
+    ) -> Result<()> {
         let layout = &mut contract.storage_layout;
-
-        for slot in &mut layout.slots {
-            slot.name = self.obfuscator.obfuscate_storage_name(&slot.name);
+        let names = layout
+            .slots
+            .iter_mut()
+            .map(|slot| &mut slot.name)
+            .chain(layout.mappings.iter_mut().map(|mapping| &mut mapping.name))
+            .chain(layout.arrays.iter_mut().map(|array| &mut array.name))
+            .chain(layout.structs.iter_mut().flat_map(|struct_layout| {
+                std::iter::once(&mut struct_layout.name)
+                    .chain(struct_layout.fields.iter_mut().map(|field| &mut field.name))
+            }));
+
+        for name in names {
+            let new_name = self.obfuscator.obfuscate_storage_name(name);
+            renamed.insert(std::mem::replace(name, new_name.clone()), new_name);
         }
 
-        for mapping in &mut layout.mappings {
-            mapping.name = self.obfuscator.obfuscate_storage_name(&mapping.name);
-        }
+        Ok(())
+    }
 
-        for array in &mut layout.arrays {
-            array.name = self.obfuscator.obfuscate_storage_name(&array.name);
+    /* Retained NatSpec is free text that names the contract's functions, variables and events, so
+     * those names are rewritten to their pseudonyms as well. */
+    fn rename_in_natspec(contract: &mut Contract, renamed: &HashMap<String, String>) {
+        let natspecs = contract.metadata.natspec.iter_mut().chain(
+            contract
+                .functions
+                .values_mut()
+                .filter_map(|func| func.metadata.natspec.as_mut()),
+        );
+        for natspec in natspecs {
+            natspec.rewrite_text(|text| rename_identifiers(text, renamed));
         }
+    }
+}
 
-        for struct_layout in &mut layout.structs {
-            struct_layout.name = self.obfuscator.obfuscate_storage_name(&struct_layout.name);
-            for field in &mut struct_layout.fields {
-                field.name = self.obfuscator.obfuscate_storage_name(&field.name);
+/* Replaces whole identifiers only, so renaming `owner` leaves `owners` alone. */
+fn rename_identifiers(text: &str, renamed: &HashMap<String, String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let push_word = |out: &mut String, word: &str| {
+        out.push_str(renamed.get(word).map_or(word, String::as_str));
+    };
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        let in_word = c.is_alphanumeric() || c == '_' || c == '$';
+        match (in_word, start) {
+            (true, None) => start = Some(i),
+            (true, Some(_)) => {}
+            (false, Some(from)) => {
+                push_word(&mut out, &text[from..i]);
+                start = None;
+                out.push(c);
             }
+            (false, None) => out.push(c),
         }
-
-        Ok(())
     }
+    if let Some(from) = start {
+        push_word(&mut out, &text[from..]);
+    }
+    out
 }
 
 impl Pass for ObfuscationPass {
@@ -111,15 +241,30 @@ impl Pass for ObfuscationPass {
         contract: &mut Contract,
         _manager: &mut PassManager,
     ) -> Result<()> {
-        contract.name = self.obfuscator.obfuscate_contract_name(&contract.name);
+        let mut renamed = HashMap::new();
+        let name = self.obfuscator.obfuscate_contract_name(&contract.name);
+        renamed.insert(std::mem::replace(&mut contract.name, name.clone()), name);
 
-        self.obfuscate_functions(contract)?;
+        self.obfuscate_functions(contract, &mut renamed)?;
 
-        self.obfuscate_storage(contract)?;
+        self.obfuscate_storage(contract, &mut renamed)?;
 
-        if self.config.strip_metadata {
+        self.obfuscate_events(contract, &mut renamed);
+
+        if self.policy.natspec {
+            Self::rename_in_natspec(contract, &renamed);
+        }
+
+        if !self.policy.source_files {
             contract.metadata.source_file = None;
+        }
+        if !self.policy.source_code {
             contract.metadata.source_code = None;
+            contract.metadata.source_hash = None;
+            contract.source_files = SourceFiles::new();
+        }
+        if !self.policy.natspec {
+            contract.metadata.natspec = None;
         }
 
         Ok(())
@@ -169,10 +314,13 @@ mod tests {
                     var_type: Type::Uint(256),
                     name: "balance".to_string(),
                     packed_with: Vec::new(),
+                    location: None,
+                    is_private: false,
                 }],
                 mappings: Vec::new(),
                 arrays: Vec::new(),
                 structs: Vec::new(),
+                inherited: Vec::new(),
             },
             events: Vec::new(),
             modifiers: Vec::new(),
@@ -265,6 +413,10 @@ mod tests {
         let mut contract = create_test_contract();
         let mut manager = PassManager::new();
 
+        let natspec = crate::metadata::NatSpec::parse(&["/// @notice Moves tokens"]);
+        contract.metadata.natspec = natspec.clone();
+        contract.functions["transfer"].metadata.natspec = natspec;
+
         let config = ObfuscationConfig {
             level: ObfuscationLevel::Minimal,
             strip_metadata: true,
@@ -276,6 +428,119 @@ mod tests {
 
         assert!(contract.metadata.source_file.is_none());
         assert!(contract.metadata.source_code.is_none());
+        assert!(contract.metadata.natspec.is_none());
+        assert!(contract.functions[0].metadata.natspec.is_none());
+    }
+
+    #[test]
+    fn test_selective_metadata_policy() {
+        use crate::contract::{EventDefinition, EventId, EventParameter};
+
+        let mut contract = create_test_contract();
+        let mut manager = PassManager::new();
+
+        let natspec = crate::metadata::NatSpec::parse(&["/// @notice Moves tokens"]);
+        contract.functions["transfer"].metadata.natspec = natspec;
+        let entry = contract.functions["transfer"].body.entry_block;
+        contract.functions["transfer"].body.blocks[&entry]
+            .metadata
+            .set_location(
+                0,
+                SourceLocation::new("Token.sol".to_string(), 12, 4, 210, 240),
+            );
+        contract.events.push(EventDefinition {
+            id: EventId(0),
+            name: "Transfer".to_string(),
+            parameters: [
+                ("from", Type::Address),
+                ("to", Type::Address),
+                ("value", Type::Uint(256)),
+            ]
+            .into_iter()
+            .map(|(name, param_type)| EventParameter {
+                name: name.to_string(),
+                param_type,
+                indexed: false,
+            })
+            .collect(),
+            anonymous: false,
+        });
+
+        let config = ObfuscationConfig {
+            level: ObfuscationLevel::Minimal,
+            strip_metadata: true,
+            metadata: Some(MetadataPolicy {
+                source_lines: true,
+                function_selectors: true,
+                event_signatures: EventSignatures::Hash,
+                ..MetadataPolicy::strip_all()
+            }),
+            ..Default::default()
+        };
+
+        let mut pass = ObfuscationPass::new(config);
+        pass.run_on_contract(&mut contract, &mut manager).unwrap();
+
+        let function = &contract.functions[0];
+        let location = function.body.blocks[&entry]
+            .metadata
+            .get_location(0)
+            .unwrap();
+        assert_eq!((location.file.as_str(), location.line), ("", 12));
+        assert_eq!(function.metadata.selector, Some(selector_u32("transfer()")));
+        assert!(function.metadata.natspec.is_none());
+        assert!(contract.metadata.source_file.is_none());
+
+        assert_eq!(
+            contract.events[0].name,
+            "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+        );
+        assert_eq!(contract.events[0].parameters[2].name, "p2");
+    }
+
+    #[test]
+    fn test_retained_natspec_uses_pseudonyms() {
+        use crate::function::Parameter;
+
+        let mut contract = create_test_contract();
+        let mut manager = PassManager::new();
+        contract.metadata.natspec = crate::metadata::NatSpec::parse(&[
+            "/// @notice TestContract keeps a balance per account",
+        ]);
+        let function = &mut contract.functions["transfer"];
+        function
+            .signature
+            .params
+            .push(Parameter::new("amount", Type::Uint(256)));
+        function.metadata.natspec = crate::metadata::NatSpec::parse(&[
+            "/// @notice Moves amount out of balance via transfer",
+            "/// @param amount How much; balances are unaffected",
+        ]);
+
+        let config = ObfuscationConfig {
+            level: ObfuscationLevel::Minimal,
+            ..Default::default()
+        };
+        let mut pass = ObfuscationPass::new(config);
+        pass.run_on_contract(&mut contract, &mut manager).unwrap();
+
+        let natspec = contract.metadata.natspec.as_ref().unwrap();
+        assert_eq!(
+            natspec.notice.as_deref(),
+            Some("contract_0 keeps a var_0 per account")
+        );
+        let natspec = contract.functions[0].metadata.natspec.as_ref().unwrap();
+        assert_eq!(
+            natspec.notice.as_deref(),
+            Some("Moves p0 out of var_0 via fn_0")
+        );
+        assert_eq!(
+            natspec.params,
+            [(
+                "p0".to_string(),
+                "How much; balances are unaffected".to_string()
+            )]
+        );
     }
 
     #[test]
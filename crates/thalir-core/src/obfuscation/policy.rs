@@ -0,0 +1,80 @@
+use crate::abi::{self, AbiType};
+use crate::types::{Type, TypeRegistry};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventSignatures {
+    Keep,
+    Hash,
+    Strip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataPolicy {
+    pub source_lines: bool,
+    pub source_files: bool,
+    pub source_code: bool,
+    pub natspec: bool,
+    pub function_selectors: bool,
+    pub event_signatures: EventSignatures,
+}
+
+impl MetadataPolicy {
+    pub fn retain_all() -> Self {
+        Self {
+            source_lines: true,
+            source_files: true,
+            source_code: true,
+            natspec: true,
+            function_selectors: true,
+            event_signatures: EventSignatures::Keep,
+        }
+    }
+
+    pub fn strip_all() -> Self {
+        Self {
+            source_lines: false,
+            source_files: false,
+            source_code: false,
+            natspec: false,
+            function_selectors: false,
+            event_signatures: EventSignatures::Strip,
+        }
+    }
+}
+
+impl Default for MetadataPolicy {
+    fn default() -> Self {
+        Self::retain_all()
+    }
+}
+
+/* Canonical ABI spelling as used for selectors and event topics. Types with no ABI encoding
+ * (mappings, storage pointers, raw Cranelift types) make the whole signature unavailable. */
+pub fn abi_type(ty: &Type) -> Option<String> {
+    AbiType::from_ir(ty, &TypeRegistry::default()).map(|ty| ty.to_string())
+}
+
+pub fn abi_signature<'t>(name: &str, params: impl IntoIterator<Item = &'t Type>) -> Option<String> {
+    let params = params
+        .into_iter()
+        .map(|ty| AbiType::from_ir(ty, &TypeRegistry::default()))
+        .collect::<Option<Vec<_>>>()?;
+    Some(abi::signature(name, &params))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_abi_signature_and_selector() {
+        let signature = abi_signature("transfer", &[Type::Address, Type::Uint(256)]).unwrap();
+        assert_eq!(signature, "transfer(address,uint256)");
+        assert_eq!(crate::hash::selector_u32(&signature), 0xa905_9cbb);
+
+        let mapping = Type::Mapping(Box::new(Type::Address), Box::new(Type::Uint(256)));
+        assert!(abi_signature("f", &[mapping]).is_none());
+    }
+}
@@ -0,0 +1,202 @@
+use super::ObfuscationMapping;
+use crate::contract::Contract;
+use crate::transform::Canonicalizer;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum RoundKeyError {
+    #[error("The rounds were obfuscated with different salts, so their names do not correspond")]
+    SaltMismatch,
+    #[error("Round `{0}` was obfuscated with counters or aliases, which shift when code is added; use hashed names for both rounds")]
+    OrderDependentNames(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeKind::Added => write!(f, "added"),
+            ChangeKind::Removed => write!(f, "removed"),
+            ChangeKind::Modified => write!(f, "modified"),
+        }
+    }
+}
+
+/* A function that differs between rounds, under its obfuscated names. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionChange {
+    pub contract: String,
+    pub function: String,
+    pub kind: ChangeKind,
+}
+
+/* What changed between two obfuscated deliveries of the same codebase.
+ *
+ * Hashed names are a function of the original name and the salt, so with one salt across rounds a
+ * function keeps its obfuscated name and the rounds can be lined up by name alone, without the
+ * mapping. Bodies are compared by canonical fingerprint, so renumbered values and reordered
+ * blocks do not count as changes. A contract present in only one round reports each of its
+ * functions as added or removed. */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoundDiff {
+    pub changes: Vec<FunctionChange>,
+    pub unchanged: usize,
+}
+
+impl RoundDiff {
+    pub fn new(previous: &[Contract], current: &[Contract]) -> Self {
+        let canonicalizer = Canonicalizer::new();
+        let mut diff = Self::default();
+
+        for old in previous {
+            let new = current.iter().find(|c| c.name == old.name);
+            for (name, function) in &old.functions {
+                let kind = match new.and_then(|c| c.functions.get(name)) {
+                    None => ChangeKind::Removed,
+                    Some(updated)
+                        if canonicalizer.function_fingerprint(updated)
+                            != canonicalizer.function_fingerprint(function) =>
+                    {
+                        ChangeKind::Modified
+                    }
+                    Some(_) => {
+                        diff.unchanged += 1;
+                        continue;
+                    }
+                };
+                diff.push(&old.name, name, kind);
+            }
+        }
+
+        for new in current {
+            let old = previous.iter().find(|c| c.name == new.name);
+            for name in new.functions.keys() {
+                if old.map_or(true, |c| !c.functions.contains_key(name)) {
+                    diff.push(&new.name, name, ChangeKind::Added);
+                }
+            }
+        }
+        diff
+    }
+
+    /* Checks that the rounds' mappings make their names comparable: the same salt, and hashed
+     * rather than counted names, since a counter hands a function a different name as soon as one
+     * is added before it. Only the mappings' metadata and obfuscated names are read. */
+    pub fn check_mappings(
+        previous: &ObfuscationMapping,
+        current: &ObfuscationMapping,
+    ) -> Result<(), RoundKeyError> {
+        if previous.metadata.salt_fingerprint != current.metadata.salt_fingerprint {
+            return Err(RoundKeyError::SaltMismatch);
+        }
+        for (round, mapping) in [("previous", previous), ("current", current)] {
+            if !mapping.mapping.keys().all(|name| is_hashed(name)) {
+                return Err(RoundKeyError::OrderDependentNames(round.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    fn push(&mut self, contract: &str, function: &str, kind: ChangeKind) {
+        self.changes.push(FunctionChange {
+            contract: contract.to_string(),
+            function: function.to_string(),
+            kind,
+        });
+    }
+}
+
+/* `f_1a2b3c`: a class prefix and three hash bytes, as `NameObfuscator` writes them. */
+fn is_hashed(name: &str) -> bool {
+    name.split_once('_').is_some_and(|(prefix, hash)| {
+        matches!(prefix, "c" | "f" | "v" | "e")
+            && hash.len() == 6
+            && hash.chars().all(|c| c.is_ascii_hexdigit())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{Pass, PassManager};
+    use crate::builder::IRBuilder;
+    use crate::obfuscation::{ObfuscationConfig, ObfuscationLevel, ObfuscationPass};
+    use crate::types::Type;
+
+    fn round(fee: u64, with_sweep: bool, salt: &str) -> (Contract, ObfuscationMapping) {
+        let mut builder = IRBuilder::new();
+        let mut contract = builder.contract("Vault");
+        contract.state_variable("total", Type::Uint(256), 0);
+
+        let mut deposit = contract.function("deposit");
+        deposit.param("amount", Type::Uint(256));
+        let amount = deposit.get_param(0);
+        let mut entry = deposit.entry_block();
+        let fee = entry.constant_uint(fee, 256);
+        let net = entry.sub(amount, fee, Type::Uint(256));
+        entry.storage_store(0u32.into(), net);
+        entry.return_void().unwrap();
+        deposit.build().unwrap();
+
+        let mut assets = contract.function("totalAssets");
+        assets.returns(Type::Uint(256));
+        let mut entry = assets.entry_block();
+        let value = entry.storage_load(0u32.into());
+        entry.return_value(value).unwrap();
+        assets.build().unwrap();
+
+        if with_sweep {
+            let mut sweep = contract.function("sweep");
+            let mut entry = sweep.entry_block();
+            let zero = entry.constant_uint(0, 256);
+            entry.storage_store(0u32.into(), zero);
+            entry.return_void().unwrap();
+            sweep.build().unwrap();
+        }
+
+        let mut contract = contract.build().unwrap();
+        let mut pass = ObfuscationPass::new(ObfuscationConfig {
+            level: ObfuscationLevel::Standard,
+            retain_mapping: true,
+            hash_salt: Some(salt.to_string()),
+            ..Default::default()
+        });
+        pass.run_on_contract(&mut contract, &mut PassManager::new())
+            .unwrap();
+        (contract, pass.export_mapping())
+    }
+
+    #[test]
+    fn test_rounds_line_up_by_salted_name() {
+        let (previous, previous_mapping) = round(3, false, "engagement-7");
+        let (current, current_mapping) = round(5, true, "engagement-7");
+        RoundDiff::check_mappings(&previous_mapping, &current_mapping).unwrap();
+
+        let diff = RoundDiff::new(&[previous.clone()], &[current]);
+        assert_eq!(diff.unchanged, 1);
+        let kinds: Vec<ChangeKind> = diff.changes.iter().map(|c| c.kind).collect();
+        assert_eq!(kinds, vec![ChangeKind::Modified, ChangeKind::Added]);
+        for change in &diff.changes {
+            assert_eq!(change.contract, previous.name);
+            assert!(is_hashed(&change.function), "{}", change.function);
+        }
+
+        let (_, other_mapping) = round(3, false, "engagement-8");
+        assert_eq!(
+            RoundDiff::check_mappings(&previous_mapping, &other_mapping),
+            Err(RoundKeyError::SaltMismatch)
+        );
+    }
+}
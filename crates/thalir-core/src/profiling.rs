@@ -0,0 +1,179 @@
+/*! Memory instrumentation for pipeline phases.
+ *
+ * Processing thousands of contracts in one run is bounded by memory long before CPU, and batch
+ * sizes can only be tuned against real numbers. `TrackingAllocator` counts every allocation once a
+ * binary installs it as the global allocator; `MemoryProfiler` attributes those counts to named
+ * phases. Peak RSS comes from the operating system and is available without the allocator.
+ */
+
+use serde::Serialize;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+pub struct TrackingAllocator;
+
+impl TrackingAllocator {
+    fn grow(size: usize) {
+        let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(current, Ordering::Relaxed);
+        ALLOCATED.fetch_add(size, Ordering::Relaxed);
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn shrink(size: usize) {
+        CURRENT.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            Self::shrink(layout.size());
+            Self::grow(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MemoryStats {
+    pub current: usize,
+    pub peak: usize,
+    pub allocated: usize,
+    pub allocations: usize,
+}
+
+impl MemoryStats {
+    pub fn snapshot() -> Self {
+        Self {
+            current: CURRENT.load(Ordering::Relaxed),
+            peak: PEAK.load(Ordering::Relaxed),
+            allocated: ALLOCATED.load(Ordering::Relaxed),
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        }
+    }
+}
+
+pub fn tracking_enabled() -> bool {
+    ALLOCATIONS.load(Ordering::Relaxed) > 0
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PhaseReport {
+    pub phase: String,
+    pub elapsed: Duration,
+    pub allocated: usize,
+    pub allocations: usize,
+    pub peak_above_start: usize,
+    pub retained: isize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MemoryProfiler {
+    pub phases: Vec<PhaseReport>,
+}
+
+impl MemoryProfiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn phase<T>(&mut self, name: &str, f: impl FnOnce() -> T) -> T {
+        let _span = tracing::info_span!("phase", name).entered();
+        let before = MemoryStats::snapshot();
+        PEAK.store(before.current, Ordering::Relaxed);
+        let start = Instant::now();
+
+        let result = f();
+
+        let elapsed = start.elapsed();
+        let after = MemoryStats::snapshot();
+        self.phases.push(PhaseReport {
+            phase: name.to_string(),
+            elapsed,
+            allocated: after.allocated - before.allocated,
+            allocations: after.allocations - before.allocations,
+            peak_above_start: after.peak.saturating_sub(before.current),
+            retained: after.current as isize - before.current as isize,
+        });
+        PEAK.fetch_max(before.peak, Ordering::Relaxed);
+        result
+    }
+
+    pub fn peak(&self) -> usize {
+        self.phases
+            .iter()
+            .map(|phase| phase.peak_above_start)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+pub fn peak_rss() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phases_are_recorded_in_order() {
+        let mut profiler = MemoryProfiler::new();
+        let total: u64 = profiler.phase("transform", || (0..100u64).sum());
+        profiler.phase("emit", || ());
+
+        assert_eq!(total, 4950);
+
+        let names: Vec<_> = profiler.phases.iter().map(|p| p.phase.as_str()).collect();
+        assert_eq!(names, vec!["transform", "emit"]);
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
@@ -0,0 +1,263 @@
+/*! Review notes kept beside the IR in a sidecar file.
+ *
+ * Auditors attach a note, a status or both to a function, block or instruction by its stable ID,
+ * so the notes still land on the same code after the sources are recompiled. Sidecars from several
+ * reviewers merge into one, and validating a sidecar against the current IR finds the notes whose
+ * target has changed or gone, and the targets reviewers disagree on.
+ */
+
+use crate::{contract::Contract, stable_id};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+const REVIEW_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReviewStatus {
+    Reviewed,
+    Suspicious,
+    FollowUp,
+}
+
+impl fmt::Display for ReviewStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReviewStatus::Reviewed => write!(f, "reviewed"),
+            ReviewStatus::Suspicious => write!(f, "suspicious"),
+            ReviewStatus::FollowUp => write!(f, "follow-up"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewNote {
+    /* The stable ID of a function, block or instruction. */
+    pub target: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<ReviewStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub note: String,
+    /* Where the target was when the note was written, such as `Vault::withdraw block2[3]`, so a
+     * note whose target no longer resolves can still be placed by hand. */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl fmt::Display for ReviewNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(status) = self.status {
+            parts.push(format!("[{}]", status));
+        }
+        if !self.note.is_empty() {
+            parts.push(self.note.clone());
+        }
+        if let Some(author) = &self.author {
+            parts.push(format!("({})", author));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReviewProblem {
+    Unresolved {
+        target: String,
+        label: Option<String>,
+    },
+    Conflict {
+        target: String,
+        statuses: Vec<ReviewStatus>,
+    },
+}
+
+impl fmt::Display for ReviewProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReviewProblem::Unresolved {
+                target,
+                label: Some(label),
+            } => write!(f, "{} ({}) no longer resolves", target, label),
+            ReviewProblem::Unresolved {
+                target,
+                label: None,
+            } => {
+                write!(f, "{} does not resolve", target)
+            }
+            ReviewProblem::Conflict { target, statuses } => {
+                let statuses: Vec<String> = statuses.iter().map(ToString::to_string).collect();
+                write!(f, "{} is marked {}", target, statuses.join(" and "))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewFile {
+    pub version: u32,
+    pub notes: Vec<ReviewNote>,
+}
+
+impl Default for ReviewFile {
+    fn default() -> Self {
+        Self {
+            version: REVIEW_VERSION,
+            notes: Vec::new(),
+        }
+    }
+}
+
+impl ReviewFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("reading review notes {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("parsing review notes {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("writing review notes {}", path.display()))
+    }
+
+    /* Adds the notes of `other` this file does not already have, keeping both files' order, and
+     * returns how many were added. */
+    pub fn merge(&mut self, other: ReviewFile) -> usize {
+        let before = self.notes.len();
+        for note in other.notes {
+            if !self.notes.contains(&note) {
+                self.notes.push(note);
+            }
+        }
+        self.notes.len() - before
+    }
+
+    pub fn notes_for<'a>(&'a self, target: &'a str) -> impl Iterator<Item = &'a ReviewNote> {
+        self.notes.iter().filter(move |note| note.target == target)
+    }
+
+    /* Notes whose target is not in `contracts`, then targets given more than one status. */
+    pub fn validate(&self, contracts: &[Contract]) -> Vec<ReviewProblem> {
+        let targets = targets(contracts);
+        let mut problems = Vec::new();
+        let mut statuses: BTreeMap<&str, Vec<ReviewStatus>> = BTreeMap::new();
+        for note in &self.notes {
+            if !targets.contains_key(&note.target) {
+                problems.push(ReviewProblem::Unresolved {
+                    target: note.target.clone(),
+                    label: note.label.clone(),
+                });
+                continue;
+            }
+            if let Some(status) = note.status {
+                let seen = statuses.entry(note.target.as_str()).or_default();
+                if !seen.contains(&status) {
+                    seen.push(status);
+                }
+            }
+        }
+        problems.extend(
+            statuses
+                .into_iter()
+                .filter(|(_, statuses)| statuses.len() > 1)
+                .map(|(target, statuses)| ReviewProblem::Conflict {
+                    target: target.to_string(),
+                    statuses,
+                }),
+        );
+        problems
+    }
+}
+
+/* Every stable ID in `contracts` with a label naming what it points at. */
+pub fn targets(contracts: &[Contract]) -> HashMap<String, String> {
+    let mut targets = HashMap::new();
+    for contract in contracts {
+        for (name, function) in &contract.functions {
+            let label = format!("{}::{}", contract.name, name);
+            targets.insert(
+                stable_id::of_function(&contract.name, function),
+                label.clone(),
+            );
+            for block in function.body.blocks.values() {
+                let Some(id) = stable_id::of_block(&contract.name, function, block.id) else {
+                    continue;
+                };
+                for index in 0..block.instructions.len() {
+                    targets.insert(
+                        stable_id::instruction_id(&id, index),
+                        format!("{} {}[{}]", label, block.id, index),
+                    );
+                }
+                targets.insert(id, format!("{} {}", label, block.id));
+            }
+        }
+    }
+    targets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+
+    fn note(target: &str, status: ReviewStatus, author: &str) -> ReviewNote {
+        ReviewNote {
+            target: target.to_string(),
+            status: Some(status),
+            author: Some(author.to_string()),
+            note: String::new(),
+            label: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_and_validate_review_notes() {
+        let mut builder = IRBuilder::new();
+        let mut contract = builder.contract("Vault");
+        let mut function = contract.function("withdraw");
+        function.entry_block().return_void().unwrap();
+        function.build().unwrap();
+        let contracts = vec![contract.build().unwrap()];
+
+        let function_id = stable_id::of_function("Vault", &contracts[0].functions["withdraw"]);
+        let block = stable_id::block_id(&function_id, 0);
+        assert_eq!(targets(&contracts)[&block], "Vault::withdraw block0");
+
+        let mut mine = ReviewFile::default();
+        mine.notes
+            .push(note(&function_id, ReviewStatus::Reviewed, "ana"));
+        let mut theirs = ReviewFile::default();
+        theirs
+            .notes
+            .push(note(&function_id, ReviewStatus::Reviewed, "ana"));
+        theirs
+            .notes
+            .push(note(&function_id, ReviewStatus::Suspicious, "ben"));
+        theirs
+            .notes
+            .push(note("0000000000000000", ReviewStatus::FollowUp, "ben"));
+        assert_eq!(mine.merge(theirs), 2);
+        assert_eq!(mine.notes_for(&function_id).count(), 2);
+
+        assert_eq!(
+            mine.validate(&contracts),
+            [
+                ReviewProblem::Unresolved {
+                    target: "0000000000000000".to_string(),
+                    label: None,
+                },
+                ReviewProblem::Conflict {
+                    target: function_id,
+                    statuses: vec![ReviewStatus::Reviewed, ReviewStatus::Suspicious],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,221 @@
+/*! IDs for functions, blocks and instructions that survive recompiling unchanged code.
+ *
+ * Block numbers and function keys come from the order the transform happened to allocate them in,
+ * so anything recorded against them goes stale on the next run. A function's stable ID hashes its
+ * contract and signature instead; a block's is the function's ID plus the block's position in a
+ * walk of the CFG from the entry, and an instruction's adds its index in the block. The IDs are
+ * stamped into the metadata when a function is built, so serialized IR keeps them even after a
+ * pass renames or renumbers what they point at.
+ */
+
+use crate::{
+    analysis::findings::Finding,
+    block::BlockId,
+    contract::Contract,
+    function::Function,
+    hash::hex,
+    metadata::InstructionLocation,
+    obfuscation::policy::abi_signature,
+};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+/* The ABI signature, or for functions with non-ABI parameters (storage pointers, mappings) the
+ * IR types, which are just as stable. */
+pub fn signature(function: &Function) -> String {
+    let params = function.signature.params.iter().map(|p| &p.param_type);
+    abi_signature(&function.signature.name, params.clone()).unwrap_or_else(|| {
+        let params: Vec<String> = params.map(|ty| ty.to_string()).collect();
+        format!("{}({})", function.signature.name, params.join(","))
+    })
+}
+
+pub fn function_id(contract: &str, function: &Function) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(contract.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(signature(function).as_bytes());
+    hex(&hasher.finalize()[..8])
+}
+
+pub fn block_id(function_id: &str, position: usize) -> String {
+    format!("{}#{}", function_id, position)
+}
+
+pub fn instruction_id(block_id: &str, index: usize) -> String {
+    format!("{}:{}", block_id, index)
+}
+
+/* Blocks in depth-first order from the entry, taking successors in terminator order, followed by
+ * any the entry cannot reach in block order. */
+pub fn block_positions(function: &Function) -> HashMap<BlockId, usize> {
+    let mut order = Vec::new();
+    let mut visited = HashSet::new();
+    let mut stack = vec![function.body.entry_block];
+    while let Some(id) = stack.pop() {
+        let Some(block) = function.body.blocks.get(&id) else {
+            continue;
+        };
+        if !visited.insert(id) {
+            continue;
+        }
+        order.push(id);
+        stack.extend(block.terminator.successors().into_iter().rev());
+    }
+    let mut unreached: Vec<BlockId> = function
+        .body
+        .blocks
+        .keys()
+        .copied()
+        .filter(|id| !visited.contains(id))
+        .collect();
+    unreached.sort();
+    order.extend(unreached);
+
+    order
+        .into_iter()
+        .enumerate()
+        .map(|(position, id)| (id, position))
+        .collect()
+}
+
+/* Stamps IDs on a function and its blocks, keeping any it already carries. */
+pub fn assign(contract: &str, function: &mut Function) {
+    let id = of_function(contract, function);
+    function.metadata.stable_id = Some(id.clone());
+    for (block, position) in block_positions(function) {
+        if let Some(block) = function.body.blocks.get_mut(&block) {
+            block
+                .metadata
+                .stable_id
+                .get_or_insert_with(|| block_id(&id, position));
+        }
+    }
+}
+
+pub fn assign_contract(contract: &mut Contract) {
+    for function in contract.functions.values_mut() {
+        assign(&contract.name, function);
+    }
+}
+
+pub fn of_function(contract: &str, function: &Function) -> String {
+    function
+        .metadata
+        .stable_id
+        .clone()
+        .unwrap_or_else(|| function_id(contract, function))
+}
+
+pub fn of_block(contract: &str, function: &Function, block: BlockId) -> Option<String> {
+    let stored = function.body.blocks.get(&block)?.metadata.stable_id.clone();
+    stored.or_else(|| {
+        let position = *block_positions(function).get(&block)?;
+        Some(block_id(&of_function(contract, function), position))
+    })
+}
+
+pub fn of_location(
+    contract: &str,
+    function: &Function,
+    location: &InstructionLocation,
+) -> Option<String> {
+    of_block(contract, function, location.block).map(|id| instruction_id(&id, location.index))
+}
+
+/* Gives each finding the stable ID of what it points at: its instruction when it has a location,
+ * else its function. */
+pub fn anchor(contracts: &[Contract], findings: Vec<Finding>) -> Vec<Finding> {
+    let by_name: HashMap<&str, &Contract> =
+        contracts.iter().map(|c| (c.name.as_str(), c)).collect();
+    findings
+        .into_iter()
+        .map(|mut finding| {
+            let function = by_name.get(finding.contract.as_str()).and_then(|contract| {
+                contract.functions.get(&finding.function).or_else(|| {
+                    contract
+                        .functions
+                        .values()
+                        .find(|f| f.name() == finding.function)
+                })
+            });
+            if let Some(function) = function {
+                finding.stable_id = match &finding.location {
+                    Some(location) => of_location(&finding.contract, function, location),
+                    None => Some(of_function(&finding.contract, function)),
+                };
+            }
+            finding
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::findings::Severity;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
+
+    /* The same function with its two branch targets allocated in either order. */
+    fn build(pay_first: bool) -> Contract {
+        let mut builder = IRBuilder::new();
+        let mut contract = builder.contract("Vault");
+        let mut function = contract.function("withdraw");
+        function.param("amount", Type::Uint(256));
+        let amount = function.get_param(0);
+        let (pay, skip) = if pay_first {
+            let pay = function.create_block_id();
+            (pay, function.create_block_id())
+        } else {
+            let skip = function.create_block_id();
+            (function.create_block_id(), skip)
+        };
+        let mut entry = function.entry_block();
+        let zero = entry.constant_uint(0, 256);
+        let empty = entry.eq(amount, zero);
+        entry.branch(empty, skip, pay).unwrap();
+        function.block_with_id(pay).return_void().unwrap();
+        function.block_with_id(skip).return_void().unwrap();
+        function.build().unwrap();
+        contract.build().unwrap()
+    }
+
+    #[test]
+    fn test_ids_do_not_depend_on_allocation_order() {
+        let (first, second) = (build(true), build(false));
+        let target = |contract: &Contract, taken: bool| {
+            let function = &contract.functions["withdraw"];
+            let entry = &function.body.blocks[&function.entry_block()];
+            entry.terminator.successors()[if taken { 0 } else { 1 }]
+        };
+        let id_of = |contract: &Contract, taken: bool| {
+            let block = &contract.functions["withdraw"].body.blocks[&target(contract, taken)];
+            block.metadata.stable_id.clone()
+        };
+        assert_ne!(target(&first, true), target(&second, true));
+        for taken in [true, false] {
+            assert_eq!(id_of(&first, taken), id_of(&second, taken));
+        }
+
+        let function = &first.functions["withdraw"];
+        let function_id = function.metadata.stable_id.clone().unwrap();
+        assert_eq!(function_id, super::function_id("Vault", function));
+        assert_eq!(function_id.len(), 16);
+        /* The branch's taken side is walked first, whatever its number. */
+        assert_eq!(id_of(&first, true), Some(block_id(&function_id, 1)));
+        assert_eq!(id_of(&first, false), Some(block_id(&function_id, 2)));
+
+        let finding = Finding::new("d", Severity::Low, "Vault", "withdraw", String::new()).at(
+            InstructionLocation {
+                block: function.entry_block(),
+                index: 1,
+            },
+        );
+        let anchored = anchor(&[first.clone()], vec![finding]);
+        assert_eq!(
+            anchored[0].stable_id,
+            Some(instruction_id(&block_id(&function_id, 0), 1))
+        );
+    }
+}
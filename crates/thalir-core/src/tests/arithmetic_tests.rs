@@ -143,3 +143,32 @@ fn test_constants() {
     func.build().unwrap();
     contract.build().unwrap();
 }
+
+#[test]
+fn test_constant_pool_shares_identical_constants() {
+    let mut builder = IRBuilder::new();
+    let mut contract = builder.contract("PooledContract");
+    let mut func = contract.function("pooled");
+
+    func.param("a", Type::Uint(256)).returns(Type::Uint(256));
+    let a = func.get_param(0);
+
+    let mut entry = func.entry_block();
+    let ten = entry.constant_uint(10, 256);
+    let scaled = entry.mul(a.clone(), ten, Type::Uint(256));
+    let ten_again = entry.constant_uint(10, 256);
+    let offset = entry.add(scaled, ten_again, Type::Uint(256));
+    let one = entry.constant_uint(1, 256);
+    let result = entry.sub(offset, one, Type::Uint(256));
+    entry.return_value(result).unwrap();
+
+    let function = func.build().unwrap();
+    let pool = function.body.constant_pool();
+    assert_eq!(pool.len(), 2);
+
+    let ten = crate::values::Constant::Uint(BigUint::from(10u32), 256);
+    let id = pool.lookup(&ten).unwrap();
+    assert_eq!(pool.get(id), Some(&ten));
+    assert_eq!(pool.uses(id), 2);
+    assert_eq!(id.to_string(), "k0");
+}
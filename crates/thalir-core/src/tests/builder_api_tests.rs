@@ -1,5 +1,6 @@
 use crate::block::BlockId;
 use crate::builder::{FunctionCursor, IRBuilder, IRContext};
+use crate::cursor::IRCursor;
 use crate::types::Type;
 
 #[test]
@@ -173,3 +174,44 @@ fn test_multiple_contracts() {
     assert!(registry.get_function("Contract2::func2").is_some());
     assert!(registry.get_function("Contract1::func2").is_none());
 }
+
+#[test]
+fn test_strict_mode_reports_operand_type_errors() {
+    use crate::IrError;
+
+    let mut builder = IRBuilder::new();
+    builder.strict_types(true);
+    let mut contract = builder.contract("Strict");
+
+    let mut good = contract.function("good");
+    good.param("amount", Type::Uint(256));
+    let amount = good.get_param(0);
+    let mut entry = good.entry_block();
+    let one = entry.constant_uint(1, 8);
+    let sum = entry.add(amount, one, Type::Uint(256));
+    let limit = entry.constant_uint(100, 256);
+    let ok = entry.lt(sum, limit);
+    entry.require(ok, "too large");
+    entry.return_void().unwrap();
+    good.build().unwrap();
+
+    let mut bad = contract.function("bad");
+    bad.param("who", Type::Address);
+    let who = bad.get_param(0);
+    let mut entry = bad.entry_block();
+    let one = entry.constant_uint(1, 256);
+    entry.add(who, one, Type::Uint(256));
+    match entry.return_void() {
+        Err(IrError::TypeError {
+            instruction,
+            expected,
+            found,
+        }) => {
+            assert!(instruction.contains("Strict::bad"));
+            assert!(instruction.ends_with("Add"));
+            assert_eq!(expected, "uint256");
+            assert_eq!(found, "address");
+        }
+        other => panic!("expected a type error, got {:?}", other),
+    }
+}
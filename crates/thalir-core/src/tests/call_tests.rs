@@ -125,3 +125,58 @@ fn test_static_call() {
     func.build().unwrap();
     contract.build().unwrap();
 }
+
+#[test]
+fn test_operands_cover_call_targets_and_terminators() {
+    use crate::block::{BlockId, Terminator};
+    use crate::instructions::{CallTarget, Instruction};
+    use crate::values::{TempId, Value};
+
+    let temp = |id| Value::Temp(TempId(id));
+    let mut call = Instruction::Call {
+        result: temp(0),
+        target: CallTarget::External(temp(1)),
+        args: vec![temp(2), temp(3)],
+        value: Some(temp(4)),
+    };
+    assert_eq!(
+        call.operands(),
+        vec![&temp(1), &temp(2), &temp(3), &temp(4)]
+    );
+
+    for operand in call.operands_mut() {
+        if *operand == temp(2) {
+            *operand = temp(9);
+        }
+    }
+    assert_eq!(
+        call.operands(),
+        vec![&temp(1), &temp(9), &temp(3), &temp(4)]
+    );
+    assert_eq!(call.result(), Some(&temp(0)));
+
+    *call.result_mut().unwrap() = temp(5);
+    assert_eq!(call.result(), Some(&temp(5)));
+
+    let select = Instruction::Select {
+        result: temp(8),
+        condition: temp(5),
+        then_val: temp(6),
+        else_val: temp(7),
+    };
+    assert_eq!(select.result(), Some(&temp(8)));
+    assert_eq!(select.operands(), vec![&temp(5), &temp(6), &temp(7)]);
+
+    let mut branch = Terminator::Branch {
+        condition: temp(5),
+        then_block: BlockId(1),
+        then_args: vec![temp(6)],
+        else_block: BlockId(2),
+        else_args: Vec::new(),
+    };
+    assert_eq!(branch.successors(), vec![BlockId(1), BlockId(2)]);
+    for operand in branch.operands_mut() {
+        *operand = temp(7);
+    }
+    assert_eq!(branch.operands(), vec![&temp(7), &temp(7)]);
+}
@@ -207,3 +207,122 @@ fn test_multiple_functions() {
         .get_function_in_contract("MultiFunction", "get")
         .is_some());
 }
+
+#[test]
+fn test_storage_packing() {
+    use crate::contract::StorageLayout;
+    use crate::types::{StructDefinition, StructFieldDef, TypeRegistry};
+
+    let mut types = TypeRegistry::new();
+    let position = types.add_struct(StructDefinition {
+        name: "Position".to_string(),
+        fields: vec![
+            StructFieldDef {
+                name: "size".to_string(),
+                field_type: Type::Uint(128),
+            },
+            StructFieldDef {
+                name: "open".to_string(),
+                field_type: Type::Bool,
+            },
+            StructFieldDef {
+                name: "trader".to_string(),
+                field_type: Type::Address,
+            },
+        ],
+    });
+
+    let mut layout = StorageLayout::default();
+    let mut place = |name: &str, ty: Type| layout.allocate(name.to_string(), ty, &types);
+    assert_eq!(place("paused", Type::Bool), (BigUint::from(0u32), 0));
+    assert_eq!(place("owner", Type::Address), (BigUint::from(0u32), 1));
+    assert_eq!(place("fee", Type::Uint(96)), (BigUint::from(1u32), 0));
+    assert_eq!(
+        place(
+            "checkpoints",
+            Type::Array(Box::new(Type::Uint(64)), Some(5))
+        ),
+        (BigUint::from(2u32), 0)
+    );
+    assert_eq!(
+        place("position", Type::Struct(position)),
+        (BigUint::from(4u32), 0)
+    );
+    assert_eq!(place("flag", Type::Bool), (BigUint::from(6u32), 0));
+
+    let slot_zero = layout.variables_in(&BigUint::from(0u32));
+    assert_eq!(slot_zero.len(), 2);
+    assert_eq!(slot_zero[1].name, "owner");
+    assert_eq!(slot_zero[0].packed_with[0].name, "owner");
+    assert_eq!(layout.end_slot(&types), BigUint::from(7u32));
+}
+
+#[test]
+fn test_string_storage_encoding() {
+    use crate::contract::StorageLayout;
+    use crate::extensions::ByteArrayEncoding;
+    use crate::instructions::StorageKey;
+    use crate::types::TypeRegistry;
+
+    let mut layout = StorageLayout::default();
+    layout.allocate("owner".to_string(), Type::Address, &TypeRegistry::new());
+    layout.allocate("name".to_string(), Type::String, &TypeRegistry::new());
+    let name_slot = BigUint::from(1u32);
+
+    let short = ByteArrayEncoding::slot_word(b"Token");
+    assert_eq!(
+        ByteArrayEncoding::decode(&short),
+        Some(ByteArrayEncoding::Short { len: 5 })
+    );
+    assert_eq!(short.to_bytes_be()[..5], *b"Token");
+    assert_eq!(layout.holds_short_data(&name_slot, &short), Some(true));
+
+    let long = ByteArrayEncoding::slot_word(&[b'x'; 40]);
+    assert_eq!(long, BigUint::from(81u32));
+    let encoding = ByteArrayEncoding::decode(&long).unwrap();
+    assert_eq!(layout.holds_short_data(&name_slot, &long), Some(false));
+    assert_eq!(
+        encoding.data_slots(&name_slot),
+        Some((StorageKey::array_data_slot(&name_slot), BigUint::from(2u32)))
+    );
+
+    assert_eq!(layout.holds_short_data(&BigUint::from(0u32), &short), None);
+    assert_eq!(ByteArrayEncoding::decode(&BigUint::from(0x40u32)), None);
+}
+
+#[test]
+fn test_derived_layout_follows_linearized_bases() {
+    use num_traits::ToPrimitive;
+
+    let mut builder = IRBuilder::new();
+    let mut declare = |name: &str, bases: &[&str], var: &str, ty: Type| {
+        let mut contract = builder.contract(name);
+        for base in bases {
+            contract.inherits(base);
+        }
+        let position = contract.allocate_state_variable(var, ty, None);
+        contract.build().unwrap();
+        position
+    };
+
+    declare("Root", &[], "root", Type::Uint(128));
+    declare("Left", &["Root"], "left", Type::Uint(256));
+    declare("Right", &["Root"], "right", Type::Bool);
+    let own = declare("Diamond", &["Left", "Right"], "own", Type::Address);
+
+    /* Right is listed last, so it is the more derived base and its variables come after Left's. */
+    let diamond = builder.registry().get_contract("Diamond").unwrap();
+    assert_eq!(
+        builder.registry().linearize("Diamond"),
+        ["Diamond", "Right", "Left", "Root"]
+    );
+    let inherited: Vec<(&str, u32, u8)> = diamond
+        .storage_layout
+        .inherited
+        .iter()
+        .map(|var| (var.name.as_str(), var.slot.to_u32().unwrap(), var.offset))
+        .collect();
+    assert_eq!(inherited, [("root", 0, 0), ("left", 1, 0), ("right", 2, 0)]);
+    assert_eq!(own, (BigUint::from(2u32), 1));
+    assert_eq!(diamond.storage_layout.slots.len(), 1);
+}
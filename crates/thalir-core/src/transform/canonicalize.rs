@@ -0,0 +1,477 @@
+use crate::analysis::{AnalysisID, Pass, PassManager};
+use crate::block::{BlockId, Terminator};
+use crate::contract::Contract;
+use crate::function::Function;
+use crate::hash::hex;
+use crate::instructions::{CallTarget, Instruction};
+use crate::values::{TempId, Value, VarId};
+use anyhow::Result;
+use indexmap::IndexMap;
+use sha2::{Digest, Sha256};
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Default)]
+pub struct Canonicalizer {
+    erase_names: bool,
+}
+
+impl Canonicalizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn erase_names(mut self, erase: bool) -> Self {
+        self.erase_names = erase;
+        self
+    }
+
+    pub fn canonicalize_contract(&self, contract: &Contract) -> Contract {
+        let mut canonical = contract.clone();
+
+        let mut functions: Vec<(String, String, Function)> = contract
+            .functions
+            .iter()
+            .map(|(name, function)| {
+                let function = self.canonicalize_function(function);
+                let key = if self.erase_names {
+                    self.function_fingerprint(&function)
+                } else {
+                    name.clone()
+                };
+                (key, name.clone(), function)
+            })
+            .collect();
+        functions.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+
+        canonical.functions = functions
+            .into_iter()
+            .map(|(_, name, function)| (name, function))
+            .collect::<IndexMap<_, _>>();
+
+        if self.erase_names {
+            canonical.name = String::new();
+            for slot in &mut canonical.storage_layout.slots {
+                slot.name = String::new();
+            }
+        }
+
+        canonical
+    }
+
+    pub fn canonicalize_function(&self, function: &Function) -> Function {
+        let mut canonical = function.clone();
+        let order = block_order(function);
+        let block_map: HashMap<BlockId, BlockId> = order
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, BlockId(i as u32)))
+            .collect();
+
+        let mut renumber = ValueRenumbering::default();
+        for id in &order {
+            for inst in &function.body.blocks[id].instructions {
+                if let Some(result) = inst.result() {
+                    renumber.define(result);
+                }
+            }
+        }
+
+        let mut blocks = IndexMap::new();
+        for id in &order {
+            let mut block = function.body.blocks[id].clone();
+            block.id = block_map[id];
+
+            for inst in &mut block.instructions {
+                if let Some(result) = inst.result_mut() {
+                    renumber.rewrite(result);
+                }
+                for value in inst.operands_mut() {
+                    renumber.rewrite(value);
+                }
+                remap_instruction_blocks(inst, &block_map);
+                if self.erase_names {
+                    erase_instruction_names(inst);
+                }
+                order_commutative_operands(inst);
+            }
+
+            remap_terminator(
+                &mut block.terminator,
+                &block_map,
+                &mut |value: &mut Value| renumber.rewrite(value),
+            );
+            if self.erase_names {
+                if let Terminator::Revert(message) | Terminator::Panic(message) =
+                    &mut block.terminator
+                {
+                    message.clear();
+                }
+            }
+
+            block.metadata.predecessors = remap_ids(&block.metadata.predecessors, &block_map);
+            block.metadata.dominators = remap_ids(&block.metadata.dominators, &block_map);
+
+            blocks.insert(block.id, block);
+        }
+
+        canonical.body.blocks = blocks;
+        canonical.body.entry_block = block_map
+            .get(&function.body.entry_block)
+            .copied()
+            .unwrap_or(BlockId(0));
+        canonical.body.cranelift_func = None;
+
+        if self.erase_names {
+            canonical.signature.name = String::new();
+            for param in &mut canonical.signature.params {
+                param.name = String::new();
+            }
+            for local in &mut canonical.body.locals {
+                local.name = String::new();
+            }
+        }
+
+        canonical
+    }
+
+    pub fn function_fingerprint(&self, function: &Function) -> String {
+        let canonical = self.canonicalize_function(function);
+        let mut hasher = Sha256::new();
+        hash_function(&mut hasher, &canonical);
+        hex(&hasher.finalize())
+    }
+
+    pub fn contract_fingerprint(&self, contract: &Contract) -> String {
+        let canonical = self.canonicalize_contract(contract);
+        let mut hasher = Sha256::new();
+
+        hasher.update(canonical.name.as_bytes());
+        for slot in &canonical.storage_layout.slots {
+            hasher.update(format!(
+                "{}:{}:{:?}:{}",
+                slot.slot, slot.offset, slot.var_type, slot.name
+            ));
+        }
+        for (name, function) in &canonical.functions {
+            if !self.erase_names {
+                hasher.update(name.as_bytes());
+            }
+            hash_function(&mut hasher, function);
+        }
+
+        hex(&hasher.finalize())
+    }
+}
+
+fn hash_function(hasher: &mut Sha256, function: &Function) {
+    hasher.update(format!(
+        "{:?}|{:?}|{:?}|{:?}|{:?}",
+        function.signature.name,
+        function
+            .signature
+            .params
+            .iter()
+            .map(|p| (&p.name, &p.param_type))
+            .collect::<Vec<_>>(),
+        function.signature.returns,
+        function.visibility,
+        function.mutability
+    ));
+
+    for block in function.body.blocks.values() {
+        hasher.update(format!("{}", block.id));
+        for inst in &block.instructions {
+            hasher.update(format!("{:?}", inst));
+        }
+        hasher.update(format!("{:?}", block.terminator));
+    }
+}
+
+fn block_order(function: &Function) -> Vec<BlockId> {
+    let mut order = Vec::new();
+    let mut visited = HashSet::new();
+    let mut stack = vec![function.body.entry_block];
+
+    while let Some(id) = stack.pop() {
+        if !visited.insert(id) {
+            continue;
+        }
+        let block = match function.body.blocks.get(&id) {
+            Some(block) => block,
+            None => continue,
+        };
+        order.push(id);
+        for succ in block.terminator.successors().into_iter().rev() {
+            if !visited.contains(&succ) {
+                stack.push(succ);
+            }
+        }
+    }
+
+    for id in function.body.blocks.keys() {
+        if !visited.contains(id) {
+            order.push(*id);
+        }
+    }
+
+    order
+}
+
+fn remap_ids(ids: &[BlockId], block_map: &HashMap<BlockId, BlockId>) -> Vec<BlockId> {
+    ids.iter()
+        .map(|id| block_map.get(id).copied().unwrap_or(*id))
+        .collect()
+}
+
+#[derive(Default)]
+struct ValueRenumbering {
+    temps: HashMap<TempId, TempId>,
+    vars: HashMap<VarId, VarId>,
+}
+
+impl ValueRenumbering {
+    fn define(&mut self, value: &Value) {
+        match value {
+            Value::Temp(id) => {
+                let next = TempId(self.temps.len() as u32);
+                self.temps.entry(*id).or_insert(next);
+            }
+            Value::Variable(id) => {
+                let next = VarId(self.vars.len() as u32);
+                self.vars.entry(*id).or_insert(next);
+            }
+            _ => {}
+        }
+    }
+
+    fn rewrite(&mut self, value: &mut Value) {
+        self.define(value);
+        match value {
+            Value::Temp(id) => *id = self.temps[&*id],
+            Value::Variable(id) => *id = self.vars[&*id],
+            _ => {}
+        }
+    }
+}
+
+pub(super) fn remap_instruction_blocks(
+    inst: &mut Instruction,
+    block_map: &HashMap<BlockId, BlockId>,
+) {
+    let remap = |id: &mut BlockId| {
+        if let Some(new_id) = block_map.get(id) {
+            *id = *new_id;
+        }
+    };
+
+    match inst {
+        Instruction::Phi { values, .. } => {
+            for (block, _) in values {
+                remap(block);
+            }
+        }
+        Instruction::Jump { target, .. } => remap(target),
+        Instruction::Branch {
+            then_block,
+            else_block,
+            ..
+        } => {
+            remap(then_block);
+            remap(else_block);
+        }
+        _ => {}
+    }
+}
+
+pub(super) fn remap_terminator(
+    terminator: &mut Terminator,
+    block_map: &HashMap<BlockId, BlockId>,
+    rename: &mut impl FnMut(&mut Value),
+) {
+    let remap = |id: &mut BlockId| {
+        if let Some(new_id) = block_map.get(id) {
+            *id = *new_id;
+        }
+    };
+
+    terminator.operands_mut().into_iter().for_each(rename);
+    match terminator {
+        Terminator::Jump(target, _) => remap(target),
+        Terminator::Branch {
+            then_block,
+            else_block,
+            ..
+        } => {
+            remap(then_block);
+            remap(else_block);
+        }
+        Terminator::Switch { default, cases, .. } => {
+            remap(default);
+            for (_, target) in cases {
+                remap(target);
+            }
+        }
+        Terminator::Return(_)
+        | Terminator::Revert(_)
+        | Terminator::Panic(_)
+        | Terminator::Invalid => {}
+    }
+}
+
+fn erase_instruction_names(inst: &mut Instruction) {
+    match inst {
+        Instruction::Require { message, .. }
+        | Instruction::Assert { message, .. }
+        | Instruction::Revert { message } => message.clear(),
+        Instruction::Call {
+            target: CallTarget::Internal(name) | CallTarget::Library(name),
+            ..
+        } => name.clear(),
+        _ => {}
+    }
+}
+
+pub(super) fn order_commutative_operands(inst: &mut Instruction) {
+    match inst {
+        Instruction::Add { left, right, .. }
+        | Instruction::Mul { left, right, .. }
+        | Instruction::CheckedAdd { left, right, .. }
+        | Instruction::CheckedMul { left, right, .. }
+        | Instruction::And { left, right, .. }
+        | Instruction::Or { left, right, .. }
+        | Instruction::Xor { left, right, .. }
+        | Instruction::Eq { left, right, .. }
+        | Instruction::Ne { left, right, .. } => {
+            if operand_key(left) > operand_key(right) {
+                std::mem::swap(left, right);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn operand_key(value: &Value) -> (u8, u64, String) {
+    match value {
+        Value::Param(id) => (0, id.0 as u64, String::new()),
+        Value::Variable(id) => (1, id.0 as u64, String::new()),
+        Value::Temp(id) => (2, id.0 as u64, String::new()),
+        Value::Constant(constant) => (4, 0, format!("{:?}", constant)),
+        other => (3, 0, format!("{:?}", other)),
+    }
+}
+
+pub struct CanonicalizationPass {
+    canonicalizer: Canonicalizer,
+}
+
+impl CanonicalizationPass {
+    pub fn new(canonicalizer: Canonicalizer) -> Self {
+        Self { canonicalizer }
+    }
+}
+
+impl Pass for CanonicalizationPass {
+    fn name(&self) -> &'static str {
+        "canonicalize"
+    }
+
+    fn description(&self) -> &'static str {
+        "Renumber temps and blocks, order commutative operands, and sort functions"
+    }
+
+    fn run_on_contract(
+        &mut self,
+        contract: &mut Contract,
+        _manager: &mut PassManager,
+    ) -> Result<()> {
+        *contract = self.canonicalizer.canonicalize_contract(contract);
+        Ok(())
+    }
+
+    fn modifies_ir(&self) -> bool {
+        true
+    }
+
+    fn preserved_analyses(&self) -> Vec<AnalysisID> {
+        Vec::new()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
+
+    #[test]
+    fn test_fingerprint_ignores_numbering_and_operand_order() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Math");
+
+        let mut first = contract_builder.function("first");
+        first.param("a", Type::Uint(256));
+        first.param("b", Type::Uint(256));
+        let mut entry = first.entry_block();
+        let a = entry.constant_uint(1, 256);
+        let sum = entry.add(a, Value::Param(crate::values::ParamId(0)), Type::Uint(256));
+        entry.return_value(sum).unwrap();
+        let first = first.build().unwrap();
+
+        let mut second = contract_builder.function("second");
+        second.param("a", Type::Uint(256));
+        second.param("b", Type::Uint(256));
+        let mut entry = second.entry_block();
+        let _unused = entry.new_temp();
+        let a = entry.constant_uint(1, 256);
+        let sum = entry.add(Value::Param(crate::values::ParamId(0)), a, Type::Uint(256));
+        entry.return_value(sum).unwrap();
+        let second = second.build().unwrap();
+
+        let canonicalizer = Canonicalizer::new().erase_names(true);
+        assert_eq!(
+            canonicalizer.function_fingerprint(&first),
+            canonicalizer.function_fingerprint(&second)
+        );
+        assert_ne!(
+            Canonicalizer::new().function_fingerprint(&first),
+            Canonicalizer::new().function_fingerprint(&second)
+        );
+    }
+
+    #[test]
+    fn test_blocks_renumbered_in_dfs_order() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Flow");
+        let mut func_builder = contract_builder.function("f");
+
+        let entry = func_builder.entry_block().block_id();
+        let unused = func_builder.create_block_id();
+        let exit = func_builder.create_block_id();
+
+        let mut entry_builder = func_builder.switch_to_block(entry).unwrap();
+        entry_builder.jump(exit).unwrap();
+        let mut unused_builder = func_builder.switch_to_block(unused).unwrap();
+        unused_builder.return_void().unwrap();
+        let mut exit_builder = func_builder.switch_to_block(exit).unwrap();
+        exit_builder.return_void().unwrap();
+
+        let function = func_builder.build().unwrap();
+        let canonical = Canonicalizer::new().canonicalize_function(&function);
+
+        let order: Vec<BlockId> = canonical.body.blocks.keys().copied().collect();
+        assert_eq!(order, vec![BlockId(0), BlockId(1), BlockId(2)]);
+        assert!(matches!(
+            canonical.body.blocks[&BlockId(0)].terminator,
+            Terminator::Jump(BlockId(1), _)
+        ));
+    }
+}
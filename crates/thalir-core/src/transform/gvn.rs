@@ -0,0 +1,411 @@
+use super::canonicalize::order_commutative_operands;
+use crate::analysis::memory_ssa::storage_key;
+use crate::analysis::{
+    AliasAnalysis, AliasResult, AnalysisID, DominatorTree, MemoryAccess, MemoryRegion, MemorySSA,
+    Pass, PassManager,
+};
+use crate::block::BlockId;
+use crate::contract::Contract;
+use crate::function::Function;
+use crate::instructions::{ContextVariable, Instruction};
+use crate::values::Value;
+use anyhow::Result;
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+
+/* Removes instructions that recompute a value already available on every path to them. Blocks
+ * are walked down the dominator tree, so a value numbered in a block is reused by every block it
+ * dominates. Loads are keyed on the memory SSA version they observe, which lets two reads of a
+ * slot merge across blocks when no write that may alias comes between, and lets a load right
+ * after a store to the same key take the stored value. */
+#[derive(Debug, Clone, Default)]
+pub struct GlobalValueNumbering;
+
+impl GlobalValueNumbering {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn run_contract(&self, contract: &mut Contract) -> usize {
+        contract
+            .functions
+            .values_mut()
+            .map(|function| self.run_function(function))
+            .sum()
+    }
+
+    /* Returns the number of instructions removed. */
+    pub fn run_function(&self, function: &mut Function) -> usize {
+        let memory = MemorySSA::build(function);
+        let dom_tree = DominatorTree::build(function);
+        let mut numbering = Numbering {
+            function: &*function,
+            memory: &memory,
+            dom_tree: &dom_tree,
+            mutable: mutable_values(function),
+            table: HashMap::new(),
+            replacements: HashMap::new(),
+            redundant: HashSet::new(),
+        };
+        numbering.visit(function.entry_block());
+        let Numbering {
+            replacements,
+            redundant,
+            ..
+        } = numbering;
+        if redundant.is_empty() {
+            return 0;
+        }
+
+        let mut rewrite = |value: &mut Value| {
+            if let Some(replacement) = replacements.get(value) {
+                *value = replacement.clone();
+            }
+        };
+        for (id, block) in function.body.blocks.iter_mut() {
+            let mut index = 0;
+            block.instructions.retain(|_| {
+                index += 1;
+                !redundant.contains(&(*id, index - 1))
+            });
+            for inst in &mut block.instructions {
+                inst.operands_mut().into_iter().for_each(&mut rewrite);
+            }
+            block
+                .terminator
+                .operands_mut()
+                .into_iter()
+                .for_each(&mut rewrite);
+        }
+        function.body.cranelift_func = None;
+        redundant.len()
+    }
+}
+
+struct Numbering<'a> {
+    function: &'a Function,
+    memory: &'a MemorySSA,
+    dom_tree: &'a DominatorTree,
+    mutable: HashSet<Value>,
+    table: HashMap<String, Value>,
+    replacements: HashMap<Value, Value>,
+    redundant: HashSet<(BlockId, usize)>,
+}
+
+impl Numbering<'_> {
+    fn visit(&mut self, block: BlockId) {
+        let function = self.function;
+        let Some(data) = function.body.blocks.get(&block) else {
+            return;
+        };
+
+        let mut scope = Vec::new();
+        for (index, inst) in data.instructions.iter().enumerate() {
+            let Some(result @ Value::Temp(_)) = inst.result() else {
+                continue;
+            };
+            if self.mutable.contains(result) {
+                continue;
+            }
+            if let Some(stored) = self.forwarded(block, index) {
+                self.replacements.insert(result.clone(), stored);
+                self.redundant.insert((block, index));
+                continue;
+            }
+            let Some(key) = self.key(block, index) else {
+                continue;
+            };
+            match self.table.get(&key) {
+                Some(leader) => {
+                    self.replacements.insert(result.clone(), leader.clone());
+                    self.redundant.insert((block, index));
+                }
+                None => {
+                    self.table.insert(key.clone(), result.clone());
+                    scope.push(key);
+                }
+            }
+        }
+
+        let mut children = self.dom_tree.children(block).to_vec();
+        children.sort();
+        for child in children {
+            self.visit(child);
+        }
+        for key in scope {
+            self.table.remove(&key);
+        }
+    }
+
+    /* The expression with its operands already numbered, plus the state it reads. Locals and
+     * anything assigned more than once can change between two equal-looking instructions, so
+     * expressions over them get no number. */
+    fn key(&self, block: BlockId, index: usize) -> Option<String> {
+        let inst = &self.function.body.blocks[&block].instructions[index];
+        if !numberable(inst) {
+            return None;
+        }
+
+        let mut expr = inst.clone();
+        let mut stable = true;
+        for value in expr.operands_mut() {
+            if let Some(replacement) = self.replacements.get(value) {
+                *value = replacement.clone();
+            }
+            stable &= !matches!(value, Value::Variable(_)) && !self.mutable.contains(value);
+        }
+        if let Some(result) = expr.result_mut() {
+            *result = Value::Undefined;
+        }
+        if !stable {
+            return None;
+        }
+        order_commutative_operands(&mut expr);
+
+        let mut key = format!("{:?}", expr);
+        for region in MemoryRegion::read_by(inst) {
+            let version = self
+                .memory
+                .clobbering(self.function, block, index, region)?;
+            key.push_str(&format!("|{}", version));
+        }
+        Some(key)
+    }
+
+    /* The value a load observes when the nearest write it may see is a store to the same key. */
+    fn forwarded(&self, block: BlockId, index: usize) -> Option<Value> {
+        let inst = &self.function.body.blocks[&block].instructions[index];
+        let (region, read) = match inst {
+            Instruction::StorageLoad { .. } => (MemoryRegion::Storage, storage_key(inst)?),
+            Instruction::TransientLoad { .. } => (MemoryRegion::Transient, storage_key(inst)?),
+            _ => return None,
+        };
+        let clobber = self
+            .memory
+            .clobbering(self.function, block, index, region)?;
+        let MemoryAccess::Def {
+            block: store_block,
+            index: store_index,
+            ..
+        } = self.memory.access(clobber)
+        else {
+            return None;
+        };
+        let store = &self.function.body.blocks[store_block].instructions[*store_index];
+        let value = match store {
+            Instruction::StorageStore { value, .. } | Instruction::TransientStore { value, .. } => {
+                value
+            }
+            _ => return None,
+        };
+        let value = self.replacements.get(value).unwrap_or(value);
+        /* Keys that compare equal only name the same slot if their value cannot change between
+         * the store and the load. */
+        let aliases = read.value().into_iter().all(|key| self.immutable(key))
+            && storage_key(store).is_some_and(|write| {
+                AliasAnalysis::query_storage(&read, &write) == AliasResult::MustAlias
+            });
+        (aliases && self.immutable(value)).then(|| value.clone())
+    }
+
+    fn immutable(&self, value: &Value) -> bool {
+        matches!(
+            value,
+            Value::Temp(_) | Value::Param(_) | Value::BlockParam(_) | Value::Constant(_)
+        ) && !self.mutable.contains(value)
+    }
+}
+
+/* Instructions whose result is fixed by their operands and the state they read. Allocations and
+ * calls produce something new each time; `gasleft()` changes as it is read. */
+fn numberable(inst: &Instruction) -> bool {
+    match inst {
+        Instruction::GetContext { var, .. } => *var != ContextVariable::GasLeft,
+        Instruction::Add { .. }
+        | Instruction::Sub { .. }
+        | Instruction::Mul { .. }
+        | Instruction::Div { .. }
+        | Instruction::Mod { .. }
+        | Instruction::Sdiv { .. }
+        | Instruction::Smod { .. }
+        | Instruction::Pow { .. }
+        | Instruction::CheckedAdd { .. }
+        | Instruction::CheckedSub { .. }
+        | Instruction::CheckedMul { .. }
+        | Instruction::CheckedDiv { .. }
+        | Instruction::And { .. }
+        | Instruction::Or { .. }
+        | Instruction::Xor { .. }
+        | Instruction::Not { .. }
+        | Instruction::Shl { .. }
+        | Instruction::Shr { .. }
+        | Instruction::Sar { .. }
+        | Instruction::Eq { .. }
+        | Instruction::Ne { .. }
+        | Instruction::Lt { .. }
+        | Instruction::Gt { .. }
+        | Instruction::Le { .. }
+        | Instruction::Ge { .. }
+        | Instruction::Slt { .. }
+        | Instruction::Sgt { .. }
+        | Instruction::Sle { .. }
+        | Instruction::Sge { .. }
+        | Instruction::Select { .. }
+        | Instruction::Cast { .. }
+        | Instruction::ZeroExtend { .. }
+        | Instruction::SignExtend { .. }
+        | Instruction::Truncate { .. }
+        | Instruction::Keccak256 { .. }
+        | Instruction::Sha256 { .. }
+        | Instruction::Ripemd160 { .. }
+        | Instruction::EcRecover { .. }
+        | Instruction::Load { .. }
+        | Instruction::StorageLoad { .. }
+        | Instruction::TransientLoad { .. }
+        | Instruction::MappingLoad { .. }
+        | Instruction::ArrayLoad { .. }
+        | Instruction::ArrayLength { .. }
+        | Instruction::GetBalance { .. }
+        | Instruction::GetCode { .. }
+        | Instruction::GetCodeSize { .. }
+        | Instruction::GetCodeHash { .. }
+        | Instruction::MemorySize { .. } => true,
+        _ => false,
+    }
+}
+
+/* Values some instruction assigns that are not single-definition temps. */
+fn mutable_values(function: &Function) -> HashSet<Value> {
+    let mut defined = HashSet::new();
+    let mut mutable = HashSet::new();
+    for inst in function
+        .body
+        .blocks
+        .values()
+        .flat_map(|block| &block.instructions)
+    {
+        if let Some(result) = inst.result() {
+            if !matches!(result, Value::Temp(_)) || !defined.insert(result.clone()) {
+                mutable.insert(result.clone());
+            }
+        }
+    }
+    mutable
+}
+
+pub struct GvnPass {
+    gvn: GlobalValueNumbering,
+}
+
+impl GvnPass {
+    pub fn new() -> Self {
+        Self {
+            gvn: GlobalValueNumbering::new(),
+        }
+    }
+}
+
+impl Default for GvnPass {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pass for GvnPass {
+    fn name(&self) -> &'static str {
+        "gvn"
+    }
+
+    fn description(&self) -> &'static str {
+        "Remove instructions that recompute a value available on every path"
+    }
+
+    fn run_on_contract(
+        &mut self,
+        contract: &mut Contract,
+        _manager: &mut PassManager,
+    ) -> Result<()> {
+        self.gvn.run_contract(contract);
+        Ok(())
+    }
+
+    fn modifies_ir(&self) -> bool {
+        true
+    }
+
+    fn preserved_analyses(&self) -> Vec<AnalysisID> {
+        Vec::new()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
+
+    #[test]
+    fn test_redundant_loads_and_arithmetic_across_blocks() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Vault");
+        let mut func_builder = contract_builder.function("withdraw");
+        func_builder.param("amount", Type::Uint(256));
+        let amount = func_builder.get_param(0);
+
+        let entry = func_builder.entry_block().block_id();
+        let pay = func_builder.create_block_id();
+        let skip = func_builder.create_block_id();
+
+        let mut entry_builder = func_builder.switch_to_block(entry).unwrap();
+        let balance = entry_builder.storage_load(1u32.into());
+        let enough = entry_builder.ge(balance.clone(), amount.clone());
+        entry_builder.storage_store(2u32.into(), amount.clone());
+        entry_builder.branch(enough, pay, skip).unwrap();
+
+        /* Slot 1 is read again past a store to slot 2, and the difference recomputed. */
+        let mut pay_builder = func_builder.switch_to_block(pay).unwrap();
+        let again = pay_builder.storage_load(1u32.into());
+        let left = pay_builder.sub(again, amount.clone(), Type::Uint(256));
+        let last = pay_builder.storage_load(2u32.into());
+        pay_builder.storage_store(1u32.into(), left);
+        let after = pay_builder.storage_load(1u32.into());
+        let total = pay_builder.add(after, last, Type::Uint(256));
+        pay_builder.return_value(total).unwrap();
+
+        let mut skip_builder = func_builder.switch_to_block(skip).unwrap();
+        let kept = skip_builder.sub(balance, amount, Type::Uint(256));
+        skip_builder.return_value(kept).unwrap();
+
+        let mut function = func_builder.build().unwrap();
+        let removed = GlobalValueNumbering::new().run_function(&mut function);
+        assert_eq!(removed, 3);
+
+        let pay_block = &function.body.blocks[&pay];
+        assert_eq!(pay_block.instructions.len(), 3);
+        let Instruction::Sub { left, .. } = &pay_block.instructions[0] else {
+            panic!("expected the subtraction first");
+        };
+        assert_eq!(*left, balance_of(&function, entry));
+        let Instruction::Add { left, right, .. } = &pay_block.instructions[2] else {
+            panic!("expected the sum last");
+        };
+        assert!(matches!(left, Value::Temp(_)));
+        assert!(matches!(right, Value::Param(_)));
+        assert_eq!(function.body.blocks[&skip].instructions.len(), 1);
+    }
+
+    fn balance_of(function: &Function, entry: BlockId) -> Value {
+        function.body.blocks[&entry].instructions[0]
+            .result()
+            .unwrap()
+            .clone()
+    }
+}
@@ -0,0 +1,529 @@
+use super::canonicalize::{remap_instruction_blocks, remap_terminator};
+use crate::analysis::{AnalysisID, Pass, PassManager};
+use crate::block::{BlockId, BlockMetadata, BlockParam, Terminator};
+use crate::contract::Contract;
+use crate::function::{Function, Visibility};
+use crate::instructions::{CallTarget, Instruction};
+use crate::values::{BlockParamId, TempId, Value, VarId};
+use anyhow::Result;
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+
+type FunctionKey = (usize, String);
+
+#[derive(Debug, Clone)]
+pub struct Inliner {
+    budget: usize,
+}
+
+impl Default for Inliner {
+    fn default() -> Self {
+        Self { budget: 32 }
+    }
+}
+
+impl Inliner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /* Largest callee, in instructions plus terminators, that gets copied into its callers. */
+    pub fn budget(mut self, budget: usize) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn inline_contract(&self, contract: &mut Contract) -> usize {
+        self.inline_workspace(std::slice::from_mut(contract))
+    }
+
+    /* Callees are flattened before their callers, so one splice brings in a helper together with
+     * everything already inlined into it. Functions on a call cycle are never inlined. */
+    pub fn inline_workspace(&self, contracts: &mut [Contract]) -> usize {
+        let graph = CallGraph::build(contracts);
+        let mut inlined = 0;
+
+        for caller in graph.post_order() {
+            let sites = call_sites(contracts, &caller);
+            for (block, index, callee) in sites.into_iter().rev() {
+                let callee_fn = &contracts[callee.0].functions[&callee.1];
+                if graph.is_recursive(&callee) || !self.inlinable(callee_fn) {
+                    continue;
+                }
+                let callee_fn = callee_fn.clone();
+                let caller_fn = contracts[caller.0]
+                    .functions
+                    .get_mut(&caller.1)
+                    .expect("call graph node exists");
+                if splice(caller_fn, block, index, &callee_fn) {
+                    inlined += 1;
+                }
+            }
+
+            if let Some(caller_fn) = contracts[caller.0].functions.get_mut(&caller.1) {
+                caller_fn.body.cranelift_func = None;
+                caller_fn.analyze_metadata();
+            }
+        }
+
+        inlined
+    }
+
+    fn inlinable(&self, function: &Function) -> bool {
+        matches!(
+            function.visibility,
+            Visibility::Internal | Visibility::Private
+        ) && function.modifiers.is_empty()
+            && size(function) <= self.budget
+            && function.body.blocks.values().all(|block| {
+                block.params.is_empty()
+                    && !block.instructions.iter().any(|inst| {
+                        matches!(
+                            inst,
+                            Instruction::Jump { .. }
+                                | Instruction::Branch { .. }
+                                | Instruction::Return { .. }
+                        )
+                    })
+            })
+    }
+}
+
+fn size(function: &Function) -> usize {
+    function
+        .body
+        .blocks
+        .values()
+        .map(|block| block.instructions.len() + 1)
+        .sum()
+}
+
+fn resolve(contracts: &[Contract], caller: usize, target: &CallTarget) -> Option<FunctionKey> {
+    match target {
+        CallTarget::Internal(name) => contracts[caller]
+            .functions
+            .contains_key(name)
+            .then(|| (caller, name.clone())),
+        CallTarget::Library(name) => {
+            let (library, function) = name.split_once('.')?;
+            let index = contracts.iter().position(|c| c.name == library)?;
+            contracts[index]
+                .functions
+                .contains_key(function)
+                .then(|| (index, function.to_string()))
+        }
+        _ => None,
+    }
+}
+
+fn call_sites(contracts: &[Contract], caller: &FunctionKey) -> Vec<(BlockId, usize, FunctionKey)> {
+    let function = &contracts[caller.0].functions[&caller.1];
+    let mut sites = Vec::new();
+    for (&block_id, block) in &function.body.blocks {
+        for (index, inst) in block.instructions.iter().enumerate() {
+            if let Instruction::Call {
+                target,
+                value: None,
+                ..
+            } = inst
+            {
+                if let Some(callee) = resolve(contracts, caller.0, target) {
+                    sites.push((block_id, index, callee));
+                }
+            }
+        }
+    }
+    sites
+}
+
+struct CallGraph {
+    nodes: Vec<FunctionKey>,
+    edges: HashMap<FunctionKey, Vec<FunctionKey>>,
+}
+
+impl CallGraph {
+    fn build(contracts: &[Contract]) -> Self {
+        let mut nodes = Vec::new();
+        let mut edges = HashMap::new();
+        for (index, contract) in contracts.iter().enumerate() {
+            /* Entry points lead the root set so each externally reachable tree is walked whole;
+             * anything left over is unreachable from outside but still gets visited. */
+            let entries = contract.entry_points();
+            let roots = entries.iter().map(|entry| &entry.name);
+            let rest = contract
+                .functions
+                .iter()
+                .filter(|(_, function)| !function.is_entry_point())
+                .map(|(name, _)| name);
+            for name in roots.chain(rest) {
+                let key = (index, name.clone());
+                let callees = call_sites(contracts, &key)
+                    .into_iter()
+                    .map(|(_, _, callee)| callee)
+                    .collect();
+                edges.insert(key.clone(), callees);
+                nodes.push(key);
+            }
+        }
+        Self { nodes, edges }
+    }
+
+    fn post_order(&self) -> Vec<FunctionKey> {
+        let mut order = Vec::new();
+        let mut visited = HashSet::new();
+        for root in &self.nodes {
+            let mut stack = vec![(root.clone(), false)];
+            while let Some((node, expanded)) = stack.pop() {
+                if expanded {
+                    order.push(node);
+                    continue;
+                }
+                if !visited.insert(node.clone()) {
+                    continue;
+                }
+                stack.push((node.clone(), true));
+                for callee in self.edges.get(&node).into_iter().flatten() {
+                    if !visited.contains(callee) {
+                        stack.push((callee.clone(), false));
+                    }
+                }
+            }
+        }
+        order
+    }
+
+    fn is_recursive(&self, function: &FunctionKey) -> bool {
+        let mut visited = HashSet::new();
+        let mut worklist: Vec<&FunctionKey> =
+            self.edges.get(function).into_iter().flatten().collect();
+        while let Some(node) = worklist.pop() {
+            if node == function {
+                return true;
+            }
+            if visited.insert(node) {
+                worklist.extend(self.edges.get(node).into_iter().flatten());
+            }
+        }
+        false
+    }
+}
+
+/* Splits the caller block around the call, copies the callee blocks in with fresh temps and block
+ * ids, and binds parameters to the call arguments. Every return becomes a jump to the
+ * continuation passing the returned value, which arrives there as a block parameter that replaces
+ * the call result, so the result keeps a single definition. */
+fn splice(caller: &mut Function, block_id: BlockId, index: usize, callee: &Function) -> bool {
+    let (result, args) = match caller
+        .body
+        .blocks
+        .get(&block_id)
+        .and_then(|block| block.instructions.get(index))
+    {
+        Some(Instruction::Call { result, args, .. }) => (result.clone(), args.clone()),
+        _ => return false,
+    };
+    if args.len() != callee.signature.params.len() {
+        return false;
+    }
+
+    let (mut next_temp, mut next_var) = next_ids(caller);
+    let mut renamed: HashMap<Value, Value> = HashMap::new();
+    let mut rename = |value: &mut Value| {
+        let fresh = match &*value {
+            Value::Param(id) => match args.get(id.0 as usize) {
+                Some(arg) => arg.clone(),
+                None => return,
+            },
+            Value::Temp(_) => renamed
+                .entry(value.clone())
+                .or_insert_with(|| {
+                    next_temp += 1;
+                    Value::Temp(TempId(next_temp - 1))
+                })
+                .clone(),
+            Value::Variable(_) => renamed
+                .entry(value.clone())
+                .or_insert_with(|| {
+                    next_var += 1;
+                    Value::Variable(VarId(next_var - 1))
+                })
+                .clone(),
+            _ => return,
+        };
+        *value = fresh;
+    };
+
+    let continuation = caller.body.create_block();
+    let returned = callee.signature.returns.first().map(|ty| {
+        let param = Value::BlockParam(BlockParamId {
+            block: continuation,
+            index: 0,
+        });
+        (param, ty.clone())
+    });
+    let block_map: HashMap<BlockId, BlockId> = callee
+        .body
+        .blocks
+        .keys()
+        .map(|id| (*id, caller.body.create_block()))
+        .collect();
+    let callee_entry = block_map[&callee.body.entry_block];
+
+    let block = caller.body.blocks.get_mut(&block_id).expect("block exists");
+    let suffix = block.instructions.split_off(index + 1);
+    block.instructions.pop();
+    let terminator = std::mem::replace(
+        &mut block.terminator,
+        Terminator::Jump(callee_entry, Vec::new()),
+    );
+    let (prefix_locations, suffix_locations) =
+        std::mem::take(&mut block.metadata.instruction_locations)
+            .into_iter()
+            .filter(|(i, _)| *i != index)
+            .partition::<HashMap<_, _>, _>(|(i, _)| *i < index);
+    block.metadata.instruction_locations = prefix_locations;
+
+    let successors = terminator.successors();
+    let mut returning = Vec::new();
+    for (old_id, callee_block) in &callee.body.blocks {
+        let mut spliced = callee_block.clone();
+        spliced.id = block_map[old_id];
+        for inst in &mut spliced.instructions {
+            if let Some(result) = inst.result_mut() {
+                rename(result);
+            }
+            inst.operands_mut().into_iter().for_each(&mut rename);
+            remap_instruction_blocks(inst, &block_map);
+        }
+        spliced.terminator = match &callee_block.terminator {
+            Terminator::Return(value) => {
+                let args = match (value, &returned) {
+                    (Some(value), Some(_)) => {
+                        let mut value = value.clone();
+                        rename(&mut value);
+                        vec![value]
+                    }
+                    _ => Vec::new(),
+                };
+                returning.push(spliced.id);
+                Terminator::Jump(continuation, args)
+            }
+            other => {
+                let mut other = other.clone();
+                remap_terminator(&mut other, &block_map, &mut rename);
+                other
+            }
+        };
+        spliced.metadata = BlockMetadata {
+            predecessors: if *old_id == callee.body.entry_block {
+                vec![block_id]
+            } else {
+                callee_block
+                    .metadata
+                    .predecessors
+                    .iter()
+                    .filter_map(|id| block_map.get(id).copied())
+                    .collect()
+            },
+            instruction_locations: callee_block.metadata.instruction_locations.clone(),
+            ..Default::default()
+        };
+        caller.body.blocks.insert(spliced.id, spliced);
+    }
+
+    let tail = caller
+        .body
+        .blocks
+        .get_mut(&continuation)
+        .expect("continuation was created");
+    tail.instructions = suffix;
+    tail.terminator = terminator;
+    if let Some((_, ty)) = &returned {
+        tail.add_param(BlockParam::new("ret", ty.clone()));
+    }
+    tail.metadata.predecessors = returning;
+    tail.metadata.instruction_locations = suffix_locations
+        .into_iter()
+        .map(|(i, location)| (i - index - 1, location))
+        .collect();
+
+    if let Some((param, _)) = returned {
+        for block in caller.body.blocks.values_mut() {
+            let operands = block
+                .instructions
+                .iter_mut()
+                .flat_map(Instruction::operands_mut)
+                .chain(block.terminator.operands_mut());
+            for value in operands {
+                if *value == result {
+                    *value = param.clone();
+                }
+            }
+        }
+    }
+
+    for successor in successors {
+        if let Some(block) = caller.body.blocks.get_mut(&successor) {
+            for predecessor in &mut block.metadata.predecessors {
+                if *predecessor == block_id {
+                    *predecessor = continuation;
+                }
+            }
+            for inst in &mut block.instructions {
+                if let Instruction::Phi { values, .. } = inst {
+                    for (predecessor, _) in values {
+                        if *predecessor == block_id {
+                            *predecessor = continuation;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    true
+}
+
+pub(super) fn next_ids(function: &Function) -> (u32, u32) {
+    let (mut temp, mut var) = (0, 0);
+    for block in function.body.blocks.values() {
+        for inst in &block.instructions {
+            for value in inst.result().into_iter().chain(inst.operands()) {
+                match value {
+                    Value::Temp(id) => temp = temp.max(id.0 + 1),
+                    Value::Variable(id) => var = var.max(id.0 + 1),
+                    _ => {}
+                }
+            }
+        }
+    }
+    (temp, var)
+}
+
+pub struct InliningPass {
+    inliner: Inliner,
+}
+
+impl InliningPass {
+    pub fn new(inliner: Inliner) -> Self {
+        Self { inliner }
+    }
+}
+
+impl Pass for InliningPass {
+    fn name(&self) -> &'static str {
+        "inline"
+    }
+
+    fn description(&self) -> &'static str {
+        "Inline small internal and private functions into their callers"
+    }
+
+    fn run_on_contract(
+        &mut self,
+        contract: &mut Contract,
+        _manager: &mut PassManager,
+    ) -> Result<()> {
+        self.inliner.inline_contract(contract);
+        Ok(())
+    }
+
+    fn modifies_ir(&self) -> bool {
+        true
+    }
+
+    fn preserved_analyses(&self) -> Vec<AnalysisID> {
+        Vec::new()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IRBuilder;
+    use crate::types::Type;
+    use crate::values::ParamId;
+
+    #[test]
+    fn test_small_helper_is_inlined_and_large_one_kept() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Vault");
+
+        let mut double = contract_builder.function("double");
+        double.param("x", Type::Uint(256));
+        double.visibility(Visibility::Internal);
+        let mut entry = double.entry_block();
+        let two = entry.constant_uint(2, 256);
+        let product = entry.mul(Value::Param(ParamId(0)), two, Type::Uint(256));
+        entry.return_value(product).unwrap();
+        double.build().unwrap();
+
+        let mut deposit = contract_builder.function("deposit");
+        deposit.param("amount", Type::Uint(256));
+        let mut entry = deposit.entry_block();
+        let doubled = entry.call_internal("double", vec![Value::Param(ParamId(0))]);
+        let one = entry.constant_uint(1, 256);
+        let total = entry.add(doubled, one, Type::Uint(256));
+        entry.return_value(total).unwrap();
+        deposit.build().unwrap();
+
+        let contract = contract_builder.build().unwrap();
+
+        let mut kept = contract.clone();
+        assert_eq!(Inliner::new().budget(1).inline_contract(&mut kept), 0);
+
+        let mut flattened = contract;
+        assert_eq!(Inliner::new().inline_contract(&mut flattened), 1);
+
+        let deposit = &flattened.functions["deposit"];
+        let instructions: Vec<&Instruction> = deposit
+            .body
+            .blocks
+            .values()
+            .flat_map(|block| &block.instructions)
+            .collect();
+        assert!(!instructions
+            .iter()
+            .any(|inst| matches!(inst, Instruction::Call { .. })));
+        assert!(instructions.iter().any(|inst| matches!(
+            inst,
+            Instruction::Mul {
+                left: Value::Param(ParamId(0)),
+                ..
+            }
+        )));
+
+        let entry = &deposit.body.blocks[&deposit.body.entry_block];
+        let Terminator::Jump(inlined, _) = entry.terminator else {
+            panic!("entry should jump into the inlined body");
+        };
+        let Terminator::Jump(continuation, ref args) = deposit.body.blocks[&inlined].terminator
+        else {
+            panic!("inlined return should jump to the continuation");
+        };
+        assert_eq!(args.len(), 1);
+
+        let tail = &deposit.body.blocks[&continuation];
+        let returned = Value::BlockParam(BlockParamId {
+            block: continuation,
+            index: 0,
+        });
+        assert_eq!(tail.params.len(), 1);
+        assert!(!instructions
+            .iter()
+            .any(|inst| matches!(inst, Instruction::Assign { .. })));
+        assert!(matches!(
+            &tail.instructions[..],
+            [Instruction::Add { left, .. }] if *left == returned
+        ));
+        assert!(matches!(tail.terminator, Terminator::Return(Some(_))));
+    }
+}
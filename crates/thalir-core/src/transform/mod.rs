@@ -0,0 +1,18 @@
+/*! Rewrite IR into forms that are easier to compare and analyze.
+ *
+ * Two builds of the same contract rarely produce byte-identical IR: temp numbering, block order,
+ * and operand order all depend on how the source was walked. These passes normalize that noise away
+ * so diffs, integrity checks, and caches see structure rather than incidental numbering.
+ */
+
+pub mod canonicalize;
+pub mod gvn;
+pub mod inline;
+pub mod outline;
+pub mod specialize;
+
+pub use canonicalize::{CanonicalizationPass, Canonicalizer};
+pub use gvn::{GlobalValueNumbering, GvnPass};
+pub use inline::{Inliner, InliningPass};
+pub use outline::{Outliner, OutliningPass};
+pub use specialize::{ChainSpecializationPass, ChainSpecializer};
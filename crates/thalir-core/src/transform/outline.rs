@@ -0,0 +1,508 @@
+use super::inline::next_ids;
+use crate::analysis::{AnalysisID, Pass, PassManager};
+use crate::block::{BlockId, Terminator};
+use crate::builder::TypeTable;
+use crate::contract::Contract;
+use crate::function::{Function, FunctionSignature, Parameter};
+use crate::instructions::{CallTarget, Instruction};
+use crate::types::Type;
+use crate::values::{ParamId, TempId, Value};
+use anyhow::Result;
+use std::any::Any;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(Debug, Clone)]
+pub struct Outliner {
+    min_length: usize,
+}
+
+impl Default for Outliner {
+    fn default() -> Self {
+        Self { min_length: 3 }
+    }
+}
+
+impl Outliner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length.max(2);
+        self
+    }
+
+    /* Repeatedly factors out the duplicated region that removes the most instructions, until no
+     * region pays for the synthetic function it introduces. Returns the functions created. */
+    pub fn outline_contract(&self, contract: &mut Contract) -> Vec<String> {
+        let mut outlined = Vec::new();
+        while let Some(group) = self.best_group(contract) {
+            outlined.push(apply(contract, group));
+        }
+        outlined
+    }
+
+    /* Windows are grouped in key order and ties in savings go to the first group, so the same
+     * contract is always outlined the same way. */
+    fn best_group(&self, contract: &Contract) -> Option<Group> {
+        let mut windows: BTreeMap<String, Vec<(Site, Region)>> = BTreeMap::new();
+        for (name, function) in &contract.functions {
+            for (&block, data) in &function.body.blocks {
+                let len = data.instructions.len();
+                for start in 0..len.saturating_sub(self.min_length - 1) {
+                    let slice = &data.instructions[start..start + self.min_length];
+                    if let Some(region) = Region::canonical(slice) {
+                        let site = Site {
+                            function: name.clone(),
+                            block,
+                            start,
+                        };
+                        windows
+                            .entry(region.key.clone())
+                            .or_default()
+                            .push((site, region));
+                    }
+                }
+            }
+        }
+
+        windows
+            .into_values()
+            .filter_map(|occurrences| self.grow(contract, occurrences))
+            .min_by_key(|group| Reverse(group.savings()))
+    }
+
+    /* Extends a set of matching windows for as long as every occurrence keeps matching, then
+     * keeps the occurrences whose single live-out value (if any) lines up. Each occurrence's
+     * region is canonicalized once per length tried. */
+    fn grow(&self, contract: &Contract, windows: Vec<(Site, Region)>) -> Option<Group> {
+        let mut occurrences = non_overlapping(windows, self.min_length);
+        if occurrences.len() < 2 {
+            return None;
+        }
+
+        let slice = |site: &Site, len: usize| {
+            contract.functions[&site.function].body.blocks[&site.block]
+                .instructions
+                .get(site.start..site.start + len)
+        };
+        let mut len = self.min_length;
+        loop {
+            let next = len + 1;
+            let clash = occurrences.iter().enumerate().any(|(index, (site, _))| {
+                occurrences[..index]
+                    .iter()
+                    .any(|(other, _)| overlaps(site, other, next))
+            });
+            if clash {
+                break;
+            }
+            let extended: Option<Vec<Region>> = occurrences
+                .iter()
+                .map(|(site, _)| Region::canonical(slice(site, next)?))
+                .collect();
+            match extended {
+                Some(regions) if regions.windows(2).all(|pair| pair[0].key == pair[1].key) => {
+                    for ((_, region), extended) in occurrences.iter_mut().zip(regions) {
+                        *region = extended;
+                    }
+                    len = next;
+                }
+                _ => break,
+            }
+        }
+
+        let mut by_output: BTreeMap<Option<usize>, Vec<(Site, Region)>> = BTreeMap::new();
+        for (site, region) in occurrences {
+            let function = &contract.functions[&site.function];
+            let live = live_out(function, &site, len, &region);
+            if live.len() <= 1 {
+                by_output
+                    .entry(live.first().copied())
+                    .or_default()
+                    .push((site, region));
+            }
+        }
+
+        by_output
+            .into_iter()
+            .filter(|(_, occurrences)| occurrences.len() >= 2)
+            .map(|(output, occurrences)| Group {
+                len,
+                output,
+                occurrences,
+            })
+            .filter(|group| group.savings() > 0)
+            .min_by_key(|group| Reverse(group.savings()))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Site {
+    function: String,
+    block: BlockId,
+    start: usize,
+}
+
+struct Group {
+    len: usize,
+    output: Option<usize>,
+    occurrences: Vec<(Site, Region)>,
+}
+
+impl Group {
+    /* Each occurrence shrinks to a single call; the synthetic body costs one copy plus a return. */
+    fn savings(&self) -> isize {
+        let removed = (self.len as isize - 1) * self.occurrences.len() as isize;
+        removed - (self.len as isize + 1)
+    }
+}
+
+/* A region with caller values replaced positionally: values flowing in become parameters, values
+ * defined inside become temps, so two regions match exactly when their bodies are identical. */
+struct Region {
+    key: String,
+    body: Vec<Instruction>,
+    inputs: Vec<Value>,
+    locals: Vec<Value>,
+}
+
+impl Region {
+    fn canonical(instructions: &[Instruction]) -> Option<Self> {
+        let mut inputs: Vec<Value> = Vec::new();
+        let mut locals: Vec<Value> = Vec::new();
+        let mut body = Vec::with_capacity(instructions.len());
+
+        for inst in instructions {
+            if matches!(
+                inst,
+                Instruction::Phi { .. }
+                    | Instruction::Jump { .. }
+                    | Instruction::Branch { .. }
+                    | Instruction::Return { .. }
+            ) {
+                return None;
+            }
+
+            let result = inst.result().cloned();
+            if let Some(result) = &result {
+                if inputs.contains(result) {
+                    return None;
+                }
+            }
+
+            let mut rename = |value: &mut Value| {
+                if !is_renamed(value) {
+                    return;
+                }
+                if let Some(index) = locals.iter().position(|local| local == value) {
+                    *value = Value::Temp(TempId(index as u32));
+                } else if Some(&*value) == result.as_ref() {
+                    locals.push(value.clone());
+                    *value = Value::Temp(TempId(locals.len() as u32 - 1));
+                } else {
+                    let index = inputs
+                        .iter()
+                        .position(|input| input == value)
+                        .unwrap_or_else(|| {
+                            inputs.push(value.clone());
+                            inputs.len() - 1
+                        });
+                    *value = Value::Param(ParamId(index as u32));
+                }
+            };
+            let mut canonical = inst.clone();
+            if let Some(result) = canonical.result_mut() {
+                rename(result);
+            }
+            canonical.operands_mut().into_iter().for_each(&mut rename);
+            body.push(canonical);
+        }
+
+        Some(Self {
+            key: format!("{:?}", body),
+            body,
+            inputs,
+            locals,
+        })
+    }
+}
+
+fn is_renamed(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::Temp(_)
+            | Value::Variable(_)
+            | Value::Param(_)
+            | Value::BlockParam(_)
+            | Value::Register(_)
+    )
+}
+
+fn overlaps(site: &Site, other: &Site, len: usize) -> bool {
+    other.function == site.function
+        && other.block == site.block
+        && site.start < other.start + len
+        && other.start < site.start + len
+}
+
+fn non_overlapping(windows: Vec<(Site, Region)>, len: usize) -> Vec<(Site, Region)> {
+    let mut kept: Vec<(Site, Region)> = Vec::new();
+    for (site, region) in windows {
+        if !kept.iter().any(|(other, _)| overlaps(&site, other, len)) {
+            kept.push((site, region));
+        }
+    }
+    kept
+}
+
+/* Types of a function's values, as far as their defining instructions tell. */
+fn value_types(function: &Function) -> TypeTable {
+    let mut types = TypeTable::new();
+    types.set_params(
+        function
+            .signature
+            .params
+            .iter()
+            .map(|param| param.param_type.clone()),
+    );
+    for block in function.body.blocks.values() {
+        for inst in &block.instructions {
+            let _ = types.check(inst);
+        }
+    }
+    types
+}
+
+/* Indices of region locals that are read anywhere in the function outside the region. */
+fn live_out(function: &Function, site: &Site, len: usize, region: &Region) -> Vec<usize> {
+    let mut uses: HashSet<Value> = HashSet::new();
+    for (&block, data) in &function.body.blocks {
+        for (index, inst) in data.instructions.iter().enumerate() {
+            if block == site.block && (site.start..site.start + len).contains(&index) {
+                continue;
+            }
+            uses.extend(inst.operands().into_iter().cloned());
+        }
+        uses.extend(data.terminator.operands().into_iter().cloned());
+    }
+
+    region
+        .locals
+        .iter()
+        .enumerate()
+        .filter(|(_, local)| uses.contains(local))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn apply(contract: &mut Contract, group: Group) -> String {
+    let name = (0..)
+        .map(|n| format!("outlined_{}", n))
+        .find(|name| !contract.functions.contains_key(name))
+        .expect("unbounded name space");
+
+    let (first_site, first_region) = &group.occurrences[0];
+    let first_function = &contract.functions[&first_site.function];
+    /* Values whose definition says nothing about their type, such as storage loads, stay words. */
+    let types = value_types(first_function);
+    let type_of = |value: &Value| types.get(value).unwrap_or(Type::Uint(256));
+    let params = first_region
+        .inputs
+        .iter()
+        .enumerate()
+        .map(|(index, input)| Parameter::new(format!("arg{}", index), type_of(input)))
+        .collect();
+
+    let mut synthetic = Function::new(FunctionSignature {
+        name: name.clone(),
+        params,
+        returns: group
+            .output
+            .map(|index| type_of(&first_region.locals[index]))
+            .into_iter()
+            .collect(),
+        is_payable: false,
+    });
+    let entry = synthetic.body.entry_block;
+    let source_block = &first_function.body.blocks[&first_site.block];
+    let block = synthetic
+        .body
+        .blocks
+        .get_mut(&entry)
+        .expect("entry block exists");
+    block.instructions = first_region.body.clone();
+    block.terminator =
+        Terminator::Return(group.output.map(|index| Value::Temp(TempId(index as u32))));
+    block.metadata.instruction_locations = source_block
+        .metadata
+        .instruction_locations
+        .iter()
+        .filter(|(index, _)| (first_site.start..first_site.start + group.len).contains(*index))
+        .map(|(index, location)| (index - first_site.start, location.clone()))
+        .collect();
+    synthetic.metadata.is_outlined = true;
+    synthetic.analyze_metadata();
+
+    let mut occurrences = group.occurrences;
+    occurrences.sort_by(|a, b| b.0.start.cmp(&a.0.start));
+    for (site, region) in occurrences {
+        let function = contract
+            .functions
+            .get_mut(&site.function)
+            .expect("occurrence function exists");
+        let result = match group.output {
+            Some(index) => region.locals[index].clone(),
+            None => Value::Temp(TempId(next_ids(function).0)),
+        };
+        let block = function
+            .body
+            .blocks
+            .get_mut(&site.block)
+            .expect("occurrence block exists");
+        block.instructions.drain(site.start..site.start + group.len);
+        block.instructions.insert(
+            site.start,
+            Instruction::Call {
+                result,
+                target: CallTarget::Internal(name.clone()),
+                args: region.inputs,
+                value: None,
+            },
+        );
+        block.metadata.instruction_locations =
+            std::mem::take(&mut block.metadata.instruction_locations)
+                .into_iter()
+                .filter_map(|(index, location)| match index {
+                    i if i <= site.start => Some((i, location)),
+                    i if i < site.start + group.len => None,
+                    i => Some((i - group.len + 1, location)),
+                })
+                .collect();
+        function.body.cranelift_func = None;
+    }
+
+    contract.functions.insert(name.clone(), synthetic);
+    name
+}
+
+pub struct OutliningPass {
+    outliner: Outliner,
+}
+
+impl OutliningPass {
+    pub fn new(outliner: Outliner) -> Self {
+        Self { outliner }
+    }
+}
+
+impl Pass for OutliningPass {
+    fn name(&self) -> &'static str {
+        "outline"
+    }
+
+    fn description(&self) -> &'static str {
+        "Factor duplicated instruction regions into synthetic internal functions"
+    }
+
+    fn run_on_contract(
+        &mut self,
+        contract: &mut Contract,
+        _manager: &mut PassManager,
+    ) -> Result<()> {
+        self.outliner.outline_contract(contract);
+        Ok(())
+    }
+
+    fn modifies_ir(&self) -> bool {
+        true
+    }
+
+    fn preserved_analyses(&self) -> Vec<AnalysisID> {
+        Vec::new()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{IRBuilder, InstBuilderExt};
+
+    #[test]
+    fn test_duplicated_owner_check_is_outlined() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Owned");
+
+        for name in ["pause", "withdraw"] {
+            let mut function = contract_builder.function(name);
+            function.param("amount", Type::Uint(256));
+            let mut entry = function.entry_block();
+            let sender = entry.msg_sender();
+            let owner = entry.storage_load(0u32.into());
+            let is_owner = entry.eq(sender, owner);
+            entry.require(is_owner, "not owner");
+            entry.storage_store(1u32.into(), Value::Param(ParamId(0)));
+            entry.return_void().unwrap();
+            function.build().unwrap();
+        }
+        let mut contract = contract_builder.build().unwrap();
+
+        let outlined = Outliner::new().outline_contract(&mut contract);
+        assert_eq!(outlined, vec!["outlined_0".to_string()]);
+
+        let synthetic = &contract.functions["outlined_0"];
+        assert!(synthetic.metadata.is_outlined);
+        assert_eq!(synthetic.signature.params.len(), 1);
+
+        for name in ["pause", "withdraw"] {
+            let function = &contract.functions[name];
+            let entry = &function.body.blocks[&function.body.entry_block];
+            assert_eq!(entry.instructions.len(), 1);
+            assert!(matches!(
+                &entry.instructions[0],
+                Instruction::Call { target: CallTarget::Internal(target), args, .. }
+                    if target == "outlined_0" && args == &vec![Value::Param(ParamId(0))]
+            ));
+        }
+    }
+
+    #[test]
+    fn test_outlined_signature_uses_defining_types() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Owned");
+
+        for name in ["pause", "unpause", "withdraw"] {
+            let mut function = contract_builder.function(name);
+            let mut entry = function.entry_block();
+            let sender = entry.msg_sender();
+            let owner = entry.storage_load(0u32.into());
+            let is_owner = entry.eq(sender, owner);
+            entry.require(is_owner, name);
+            entry.return_void().unwrap();
+            function.build().unwrap();
+        }
+        let contract = contract_builder.build().unwrap();
+
+        let mut first = contract.clone();
+        let mut second = contract;
+        let outlined = Outliner::new().outline_contract(&mut first);
+        assert_eq!(outlined, Outliner::new().outline_contract(&mut second));
+        assert_eq!(
+            format!("{:?}", first.functions["outlined_0"].body),
+            format!("{:?}", second.functions["outlined_0"].body)
+        );
+
+        let synthetic = &first.functions["outlined_0"];
+        assert!(synthetic.signature.params.is_empty());
+        assert_eq!(synthetic.signature.returns, vec![Type::Bool]);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::analysis::{AnalysisID, Pass, PassManager};
+use crate::chain::{ChainCapabilities, ContextSupport};
+use crate::contract::Contract;
+use crate::function::Function;
+use crate::instructions::{CallTarget, Instruction};
+use crate::values::{Constant, Value};
+use crate::version::EvmVersion;
+use anyhow::Result;
+use num_bigint::BigUint;
+use std::any::Any;
+
+/* Folds context reads that the target chain pins to a constant, so taint and dependency analyses
+ * stop reporting e.g. `block.prevrandao` as a randomness source on Arbitrum. Reads the chain does
+ * not support at all are left alone; they are a deployment error, not a dependency. */
+#[derive(Debug, Clone)]
+pub struct ChainSpecializer {
+    chain: ChainCapabilities,
+    evm: EvmVersion,
+}
+
+impl ChainSpecializer {
+    pub fn new(chain: ChainCapabilities, evm: EvmVersion) -> Self {
+        Self { chain, evm }
+    }
+
+    pub fn specialize_contract(&self, contract: &mut Contract) -> usize {
+        contract
+            .functions
+            .values_mut()
+            .map(|function| self.specialize_function(function))
+            .sum()
+    }
+
+    pub fn specialize_function(&self, function: &mut Function) -> usize {
+        let mut folded = 0;
+        for block in function.body.blocks.values_mut() {
+            for inst in block.instructions.iter_mut() {
+                let (result, support) = match inst {
+                    Instruction::GetContext { result, var } => {
+                        (result.clone(), self.chain.context_support(*var, self.evm))
+                    }
+                    Instruction::Call {
+                        result,
+                        target: CallTarget::Builtin(builtin),
+                        ..
+                    } => (
+                        result.clone(),
+                        self.chain.builtin_support(*builtin, self.evm),
+                    ),
+                    _ => continue,
+                };
+                if let ContextSupport::Fixed(value) = support {
+                    *inst = Instruction::Assign {
+                        result,
+                        value: Value::Constant(Constant::Uint(BigUint::from(value), 256)),
+                    };
+                    folded += 1;
+                }
+            }
+        }
+        folded
+    }
+}
+
+pub struct ChainSpecializationPass {
+    specializer: ChainSpecializer,
+}
+
+impl ChainSpecializationPass {
+    pub fn new(specializer: ChainSpecializer) -> Self {
+        Self { specializer }
+    }
+}
+
+impl Pass for ChainSpecializationPass {
+    fn name(&self) -> &'static str {
+        "chain-specialize"
+    }
+
+    fn description(&self) -> &'static str {
+        "Fold context reads that the target chain returns as constants"
+    }
+
+    fn run_on_contract(
+        &mut self,
+        contract: &mut Contract,
+        _manager: &mut PassManager,
+    ) -> Result<()> {
+        self.specializer.specialize_contract(contract);
+        Ok(())
+    }
+
+    fn modifies_ir(&self) -> bool {
+        true
+    }
+
+    fn preserved_analyses(&self) -> Vec<AnalysisID> {
+        Vec::new()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{IRBuilder, InstBuilderExt};
+    use crate::instructions::ContextVariable;
+
+    #[test]
+    fn test_fixed_reads_fold_and_native_reads_stay() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Lottery");
+
+        let mut draw = contract_builder.function("draw");
+        let mut entry = draw.entry_block();
+        let seed = entry.block_prevrandao();
+        let index = entry.constant_uint(0, 256);
+        let blob = entry.blob_hash(index);
+        let fee = entry.block_blobbasefee();
+        let mixed = entry.xor(seed, blob);
+        let _ = entry.xor(mixed, fee);
+        entry.return_void().unwrap();
+        draw.build().unwrap();
+        let contract = contract_builder.build().unwrap();
+
+        let mut mainnet = contract.clone();
+        let specializer = ChainSpecializer::new(ChainCapabilities::ethereum(), EvmVersion::Cancun);
+        assert_eq!(specializer.specialize_contract(&mut mainnet), 0);
+
+        let mut arbitrum = contract;
+        let specializer = ChainSpecializer::new(ChainCapabilities::arbitrum(), EvmVersion::Cancun);
+        assert_eq!(specializer.specialize_contract(&mut arbitrum), 2);
+
+        let instructions = &arbitrum.functions["draw"]
+            .body
+            .blocks
+            .values()
+            .next()
+            .unwrap()
+            .instructions;
+        assert!(matches!(
+            &instructions[0],
+            Instruction::Assign {
+                value: Value::Constant(Constant::Uint(one, 256)),
+                ..
+            } if *one == BigUint::from(1u32)
+        ));
+        assert!(instructions.iter().any(|inst| matches!(
+            inst,
+            Instruction::GetContext {
+                var: ContextVariable::BlobBaseFee,
+                ..
+            }
+        )));
+        assert_eq!(
+            ChainCapabilities::for_chain_id(42_161)
+                .unwrap()
+                .context_support(ContextVariable::BlobBaseFee, EvmVersion::Cancun),
+            ContextSupport::Unsupported
+        );
+    }
+}
@@ -77,6 +77,10 @@ impl Type {
                 | Type::Array(_, Some(_))
         )
     }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Type::Int(_))
+    }
 }
 
 impl fmt::Display for Type {
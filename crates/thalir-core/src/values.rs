@@ -1,7 +1,9 @@
+use crate::hash::hex;
 use crate::types::Type;
 use num_bigint::{BigInt, BigUint};
 use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ValueId {
@@ -179,14 +181,107 @@ impl std::fmt::Display for Constant {
             Constant::Bool(b) => write!(f, "{}", b),
             Constant::Uint(val, bits) => write!(f, "{}u{}", val, bits),
             Constant::Int(val, bits) => write!(f, "{}i{}", val, bits),
-            Constant::Address(addr) => write!(f, "0x{}", hex::encode(addr)),
-            Constant::Bytes(bytes) => write!(f, "0x{}", hex::encode(bytes)),
+            Constant::Address(addr) => write!(f, "0x{}", hex(addr)),
+            Constant::Bytes(bytes) => write!(f, "0x{}", hex(bytes)),
             Constant::String(s) => write!(f, "\"{}\"", s),
             Constant::Null => write!(f, "null"),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ConstantId(pub u32);
+
+impl std::fmt::Display for ConstantId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "k{}", self.0)
+    }
+}
+
+/* Table of distinct constants in first-use order, built from a function's or a contract's
+ * operands. Each entry is stored once with the number of operands that refer to it. */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConstantPool {
+    entries: Vec<Constant>,
+    uses: Vec<usize>,
+    #[serde(skip)]
+    index: HashMap<Constant, ConstantId>,
+}
+
+impl ConstantPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, constant: &Constant) -> ConstantId {
+        if self.index.len() != self.entries.len() {
+            self.reindex();
+        }
+        let id = match self.index.get(constant) {
+            Some(id) => *id,
+            None => {
+                let id = ConstantId(self.entries.len() as u32);
+                self.entries.push(constant.clone());
+                self.uses.push(0);
+                self.index.insert(constant.clone(), id);
+                id
+            }
+        };
+        self.uses[id.0 as usize] += 1;
+        id
+    }
+
+    pub fn lookup(&self, constant: &Constant) -> Option<ConstantId> {
+        match self.index.get(constant) {
+            Some(id) => Some(*id),
+            None => self
+                .entries
+                .iter()
+                .position(|entry| entry == constant)
+                .map(|position| ConstantId(position as u32)),
+        }
+    }
+
+    pub fn get(&self, id: ConstantId) -> Option<&Constant> {
+        self.entries.get(id.0 as usize)
+    }
+
+    pub fn uses(&self, id: ConstantId) -> usize {
+        self.uses.get(id.0 as usize).copied().unwrap_or(0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ConstantId, &Constant, usize)> {
+        self.entries
+            .iter()
+            .zip(&self.uses)
+            .enumerate()
+            .map(|(i, (constant, uses))| (ConstantId(i as u32), constant, *uses))
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.uses.clear();
+        self.index.clear();
+    }
+
+    fn reindex(&mut self) {
+        self.index = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, constant)| (constant.clone(), ConstantId(i as u32)))
+            .collect();
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Location {
     Stack { offset: i32 },
@@ -196,6 +291,28 @@ pub enum Location {
     ReturnData { offset: Value },
 }
 
+impl Location {
+    pub fn values(&self) -> Vec<&Value> {
+        match self {
+            Location::Stack { .. } => Vec::new(),
+            Location::Memory { base, offset } => vec![base, offset],
+            Location::Storage { slot: value }
+            | Location::Calldata { offset: value }
+            | Location::ReturnData { offset: value } => vec![value],
+        }
+    }
+
+    pub fn values_mut(&mut self) -> Vec<&mut Value> {
+        match self {
+            Location::Stack { .. } => Vec::new(),
+            Location::Memory { base, offset } => vec![base, offset],
+            Location::Storage { slot: value }
+            | Location::Calldata { offset: value }
+            | Location::ReturnData { offset: value } => vec![value],
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ValueMetadata {
     pub is_tainted: bool,
@@ -280,9 +397,3 @@ impl SourceLocation {
         }
     }
 }
-
-mod hex {
-    pub fn encode(bytes: &[u8]) -> String {
-        bytes.iter().map(|b| format!("{:02x}", b)).collect()
-    }
-}
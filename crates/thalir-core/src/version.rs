@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SolcVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl SolcVersion {
+    pub const CHECKED_ARITHMETIC: SolcVersion = SolcVersion::new(0, 8, 0);
+    pub const SCOPED_USING_FOR: SolcVersion = SolcVersion::new(0, 7, 0);
+
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /* Missing components and wildcards count as zero, so `0.8` and `0.8.x` both read as 0.8.0. */
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.trim().trim_start_matches('v').split('.');
+        let mut next = |required: bool| match parts.next() {
+            Some("x" | "X" | "*") => Some(0),
+            Some(part) => part.parse().ok(),
+            None if required => None,
+            None => Some(0),
+        };
+        Some(Self::new(next(true)?, next(false)?, next(false)?))
+    }
+
+    pub fn checked_arithmetic(&self) -> bool {
+        *self >= Self::CHECKED_ARITHMETIC
+    }
+
+    /* Before 0.7 a `using A for B` directive also applied in contracts deriving from the one
+     * that declared it. */
+    pub fn inherits_using_for(&self) -> bool {
+        *self < Self::SCOPED_USING_FOR
+    }
+}
+
+impl fmt::Display for SolcVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionPragma {
+    pub requirement: String,
+    pub minimum: Option<SolcVersion>,
+}
+
+impl VersionPragma {
+    /* The lowest compiler admitted by the range decides semantics: a contract that may be built
+     * with 0.7 has to be read with wrapping arithmetic even if 0.8 is also allowed. */
+    pub fn parse(requirement: &str) -> Self {
+        let requirement = requirement.trim().to_string();
+        let minimum = requirement
+            .split("||")
+            .map(Self::alternative_minimum)
+            .collect::<Option<Vec<_>>>()
+            .and_then(|minimums| minimums.into_iter().min());
+        Self {
+            requirement,
+            minimum,
+        }
+    }
+
+    fn alternative_minimum(alternative: &str) -> Option<SolcVersion> {
+        let mut minimum = SolcVersion::new(0, 0, 0);
+        let mut operator = String::new();
+        for token in alternative.split_whitespace() {
+            let split = token
+                .find(|c: char| c.is_ascii_alphanumeric())
+                .unwrap_or(token.len());
+            operator.push_str(&token[..split]);
+            if split == token.len() {
+                continue;
+            }
+            let version = SolcVersion::parse(&token[split..])?;
+            let lower = match operator.as_str() {
+                "" | "=" | "^" | "~" | ">=" => Some(version),
+                ">" => Some(SolcVersion::new(
+                    version.major,
+                    version.minor,
+                    version.patch + 1,
+                )),
+                "<" | "<=" => None,
+                _ => return None,
+            };
+            if let Some(lower) = lower {
+                minimum = minimum.max(lower);
+            }
+            operator.clear();
+        }
+        Some(minimum)
+    }
+
+    pub fn checked_arithmetic(&self) -> bool {
+        self.minimum
+            .map(|version| version.checked_arithmetic())
+            .unwrap_or(true)
+    }
+
+    pub fn inherits_using_for(&self) -> bool {
+        self.minimum
+            .map(|version| version.inherits_using_for())
+            .unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EvmVersion {
+    Homestead,
+    TangerineWhistle,
+    SpuriousDragon,
+    Byzantium,
+    Constantinople,
+    Petersburg,
+    Istanbul,
+    Berlin,
+    London,
+    Paris,
+    Shanghai,
+    Cancun,
+    Prague,
+}
+
+impl EvmVersion {
+    pub const ALL: [EvmVersion; 13] = [
+        EvmVersion::Homestead,
+        EvmVersion::TangerineWhistle,
+        EvmVersion::SpuriousDragon,
+        EvmVersion::Byzantium,
+        EvmVersion::Constantinople,
+        EvmVersion::Petersburg,
+        EvmVersion::Istanbul,
+        EvmVersion::Berlin,
+        EvmVersion::London,
+        EvmVersion::Paris,
+        EvmVersion::Shanghai,
+        EvmVersion::Cancun,
+        EvmVersion::Prague,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EvmVersion::Homestead => "homestead",
+            EvmVersion::TangerineWhistle => "tangerineWhistle",
+            EvmVersion::SpuriousDragon => "spuriousDragon",
+            EvmVersion::Byzantium => "byzantium",
+            EvmVersion::Constantinople => "constantinople",
+            EvmVersion::Petersburg => "petersburg",
+            EvmVersion::Istanbul => "istanbul",
+            EvmVersion::Berlin => "berlin",
+            EvmVersion::London => "london",
+            EvmVersion::Paris => "paris",
+            EvmVersion::Shanghai => "shanghai",
+            EvmVersion::Cancun => "cancun",
+            EvmVersion::Prague => "prague",
+        }
+    }
+
+    /* The target solc picks when no `--evm-version` is given. */
+    pub fn default_for(compiler: SolcVersion) -> Self {
+        const DEFAULTS: [(SolcVersion, EvmVersion); 9] = [
+            (SolcVersion::new(0, 8, 30), EvmVersion::Prague),
+            (SolcVersion::new(0, 8, 25), EvmVersion::Cancun),
+            (SolcVersion::new(0, 8, 20), EvmVersion::Shanghai),
+            (SolcVersion::new(0, 8, 18), EvmVersion::Paris),
+            (SolcVersion::new(0, 8, 7), EvmVersion::London),
+            (SolcVersion::new(0, 8, 5), EvmVersion::Berlin),
+            (SolcVersion::new(0, 5, 14), EvmVersion::Istanbul),
+            (SolcVersion::new(0, 5, 5), EvmVersion::Petersburg),
+            (SolcVersion::new(0, 4, 21), EvmVersion::Byzantium),
+        ];
+        DEFAULTS
+            .iter()
+            .find(|(since, _)| compiler >= *since)
+            .map_or(EvmVersion::SpuriousDragon, |(_, evm)| *evm)
+    }
+
+    /* EIP-4399 repurposed DIFFICULTY (0x44) as PREVRANDAO at the merge. */
+    pub fn has_prevrandao(&self) -> bool {
+        *self >= EvmVersion::Paris
+    }
+}
+
+impl fmt::Display for EvmVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for EvmVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EvmVersion::ALL
+            .iter()
+            .copied()
+            .find(|version| version.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown EVM version `{}`", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pragma_minimum_and_defaults() {
+        let caret = VersionPragma::parse("^0.8.19");
+        assert_eq!(caret.minimum, Some(SolcVersion::new(0, 8, 19)));
+        assert!(caret.checked_arithmetic());
+
+        let range = VersionPragma::parse(">= 0.6.0 <0.9.0");
+        assert_eq!(range.minimum, Some(SolcVersion::new(0, 6, 0)));
+        assert!(!range.checked_arithmetic());
+
+        let either = VersionPragma::parse("0.7.6 || ^0.8.0");
+        assert_eq!(either.minimum, Some(SolcVersion::new(0, 7, 6)));
+
+        assert_eq!(
+            EvmVersion::default_for(SolcVersion::new(0, 8, 19)),
+            EvmVersion::Paris
+        );
+        assert!(!EvmVersion::default_for(SolcVersion::new(0, 7, 6)).has_prevrandao());
+        assert_eq!("tangerinewhistle".parse(), Ok(EvmVersion::TangerineWhistle));
+    }
+}
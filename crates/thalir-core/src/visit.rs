@@ -0,0 +1,1927 @@
+/*! Visitors and folders over instructions.
+ *
+ * Passes that only care about a few instructions override the methods they need and inherit a walk
+ * over everything else, instead of matching every variant themselves. Passes that only care about
+ * the values an instruction reads and defines use `Instruction::result` and
+ * `Instruction::operands`; the default walk reaches the same values in the same order.
+ */
+
+use crate::{
+    block::BlockId,
+    contract::EventId,
+    instructions::{CallTarget, ContextVariable, Instruction, Size, StorageKey},
+    types::Type,
+    values::{Location, Value},
+};
+
+/* Walks an instruction by reference. Each variant has a method taking its fields, and by default
+ * every method hands the values it holds to `visit_result` and `visit_operand`. */
+pub trait InstructionVisitor {
+    fn visit_instruction(&mut self, inst: &Instruction) {
+        walk_instruction(self, inst);
+    }
+
+    /* A value the instruction reads. */
+    fn visit_operand(&mut self, _value: &Value) {}
+
+    /* The value the instruction defines. */
+    fn visit_result(&mut self, _value: &Value) {}
+
+    fn visit_location(&mut self, location: &Location) {
+        walk_location(self, location);
+    }
+
+    fn visit_storage_key(&mut self, key: &StorageKey) {
+        walk_storage_key(self, key);
+    }
+
+    fn visit_add(&mut self, result: &Value, left: &Value, right: &Value, _ty: &Type) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_sub(&mut self, result: &Value, left: &Value, right: &Value, _ty: &Type) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_mul(&mut self, result: &Value, left: &Value, right: &Value, _ty: &Type) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_div(&mut self, result: &Value, left: &Value, right: &Value, _ty: &Type) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_mod(&mut self, result: &Value, left: &Value, right: &Value, _ty: &Type) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_sdiv(&mut self, result: &Value, left: &Value, right: &Value, _ty: &Type) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_smod(&mut self, result: &Value, left: &Value, right: &Value, _ty: &Type) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_pow(&mut self, result: &Value, base: &Value, exp: &Value) {
+        self.visit_result(result);
+        self.visit_operand(base);
+        self.visit_operand(exp);
+    }
+
+    fn visit_checked_add(&mut self, result: &Value, left: &Value, right: &Value, _ty: &Type) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_checked_sub(&mut self, result: &Value, left: &Value, right: &Value, _ty: &Type) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_checked_mul(&mut self, result: &Value, left: &Value, right: &Value, _ty: &Type) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_checked_div(&mut self, result: &Value, left: &Value, right: &Value, _ty: &Type) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_and(&mut self, result: &Value, left: &Value, right: &Value) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_or(&mut self, result: &Value, left: &Value, right: &Value) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_xor(&mut self, result: &Value, left: &Value, right: &Value) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_not(&mut self, result: &Value, operand: &Value) {
+        self.visit_result(result);
+        self.visit_operand(operand);
+    }
+
+    fn visit_shl(&mut self, result: &Value, value: &Value, shift: &Value) {
+        self.visit_result(result);
+        self.visit_operand(value);
+        self.visit_operand(shift);
+    }
+
+    fn visit_shr(&mut self, result: &Value, value: &Value, shift: &Value) {
+        self.visit_result(result);
+        self.visit_operand(value);
+        self.visit_operand(shift);
+    }
+
+    fn visit_sar(&mut self, result: &Value, value: &Value, shift: &Value) {
+        self.visit_result(result);
+        self.visit_operand(value);
+        self.visit_operand(shift);
+    }
+
+    fn visit_eq(&mut self, result: &Value, left: &Value, right: &Value) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_ne(&mut self, result: &Value, left: &Value, right: &Value) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_lt(&mut self, result: &Value, left: &Value, right: &Value) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_gt(&mut self, result: &Value, left: &Value, right: &Value) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_le(&mut self, result: &Value, left: &Value, right: &Value) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_ge(&mut self, result: &Value, left: &Value, right: &Value) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_slt(&mut self, result: &Value, left: &Value, right: &Value) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_sgt(&mut self, result: &Value, left: &Value, right: &Value) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_sle(&mut self, result: &Value, left: &Value, right: &Value) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_sge(&mut self, result: &Value, left: &Value, right: &Value) {
+        self.visit_result(result);
+        self.visit_operand(left);
+        self.visit_operand(right);
+    }
+
+    fn visit_select(
+        &mut self,
+        result: &Value,
+        condition: &Value,
+        then_val: &Value,
+        else_val: &Value,
+    ) {
+        self.visit_result(result);
+        self.visit_operand(condition);
+        self.visit_operand(then_val);
+        self.visit_operand(else_val);
+    }
+
+    fn visit_load(&mut self, result: &Value, location: &Location) {
+        self.visit_result(result);
+        self.visit_location(location);
+    }
+
+    fn visit_store(&mut self, location: &Location, value: &Value) {
+        self.visit_location(location);
+        self.visit_operand(value);
+    }
+
+    fn visit_allocate(&mut self, result: &Value, _ty: &Type, size: &Size) {
+        self.visit_result(result);
+        if let Size::Dynamic(value) = size {
+            self.visit_operand(value);
+        }
+    }
+
+    fn visit_copy(&mut self, dest: &Location, src: &Location, size: &Value) {
+        self.visit_location(dest);
+        self.visit_location(src);
+        self.visit_operand(size);
+    }
+
+    fn visit_storage_load(&mut self, result: &Value, key: &StorageKey) {
+        self.visit_result(result);
+        self.visit_storage_key(key);
+    }
+
+    fn visit_storage_store(&mut self, key: &StorageKey, value: &Value) {
+        self.visit_storage_key(key);
+        self.visit_operand(value);
+    }
+
+    fn visit_storage_delete(&mut self, key: &StorageKey) {
+        self.visit_storage_key(key);
+    }
+
+    fn visit_transient_load(&mut self, result: &Value, key: &StorageKey) {
+        self.visit_result(result);
+        self.visit_storage_key(key);
+    }
+
+    fn visit_transient_store(&mut self, key: &StorageKey, value: &Value) {
+        self.visit_storage_key(key);
+        self.visit_operand(value);
+    }
+
+    fn visit_mapping_load(&mut self, result: &Value, mapping: &Value, key: &Value) {
+        self.visit_result(result);
+        self.visit_operand(mapping);
+        self.visit_operand(key);
+    }
+
+    fn visit_mapping_store(&mut self, mapping: &Value, key: &Value, value: &Value) {
+        self.visit_operand(mapping);
+        self.visit_operand(key);
+        self.visit_operand(value);
+    }
+
+    fn visit_array_load(&mut self, result: &Value, array: &Value, index: &Value) {
+        self.visit_result(result);
+        self.visit_operand(array);
+        self.visit_operand(index);
+    }
+
+    fn visit_array_store(&mut self, array: &Value, index: &Value, value: &Value) {
+        self.visit_operand(array);
+        self.visit_operand(index);
+        self.visit_operand(value);
+    }
+
+    fn visit_array_length(&mut self, result: &Value, array: &Value) {
+        self.visit_result(result);
+        self.visit_operand(array);
+    }
+
+    fn visit_array_push(&mut self, array: &Value, value: &Value) {
+        self.visit_operand(array);
+        self.visit_operand(value);
+    }
+
+    fn visit_array_pop(&mut self, result: &Value, array: &Value) {
+        self.visit_result(result);
+        self.visit_operand(array);
+    }
+
+    fn visit_call(
+        &mut self,
+        result: &Value,
+        target: &CallTarget,
+        args: &[Value],
+        value: Option<&Value>,
+    ) {
+        self.visit_result(result);
+        if let CallTarget::External(address) = target {
+            self.visit_operand(address);
+        }
+        for value in args {
+            self.visit_operand(value);
+        }
+        if let Some(value) = value {
+            self.visit_operand(value);
+        }
+    }
+
+    fn visit_delegate_call(
+        &mut self,
+        result: &Value,
+        target: &Value,
+        selector: &Value,
+        args: &[Value],
+    ) {
+        self.visit_result(result);
+        self.visit_operand(target);
+        self.visit_operand(selector);
+        for value in args {
+            self.visit_operand(value);
+        }
+    }
+
+    fn visit_static_call(
+        &mut self,
+        result: &Value,
+        target: &Value,
+        selector: &Value,
+        args: &[Value],
+    ) {
+        self.visit_result(result);
+        self.visit_operand(target);
+        self.visit_operand(selector);
+        for value in args {
+            self.visit_operand(value);
+        }
+    }
+
+    fn visit_create(&mut self, result: &Value, code: &Value, value: &Value) {
+        self.visit_result(result);
+        self.visit_operand(code);
+        self.visit_operand(value);
+    }
+
+    fn visit_create2(&mut self, result: &Value, code: &Value, salt: &Value, value: &Value) {
+        self.visit_result(result);
+        self.visit_operand(code);
+        self.visit_operand(salt);
+        self.visit_operand(value);
+    }
+
+    fn visit_selfdestruct(&mut self, beneficiary: &Value) {
+        self.visit_operand(beneficiary);
+    }
+
+    fn visit_get_context(&mut self, result: &Value, _var: &ContextVariable) {
+        self.visit_result(result);
+    }
+
+    fn visit_get_balance(&mut self, result: &Value, address: &Value) {
+        self.visit_result(result);
+        self.visit_operand(address);
+    }
+
+    fn visit_get_code(&mut self, result: &Value, address: &Value) {
+        self.visit_result(result);
+        self.visit_operand(address);
+    }
+
+    fn visit_get_code_size(&mut self, result: &Value, address: &Value) {
+        self.visit_result(result);
+        self.visit_operand(address);
+    }
+
+    fn visit_get_code_hash(&mut self, result: &Value, address: &Value) {
+        self.visit_result(result);
+        self.visit_operand(address);
+    }
+
+    fn visit_keccak256(&mut self, result: &Value, data: &Value, len: &Value) {
+        self.visit_result(result);
+        self.visit_operand(data);
+        self.visit_operand(len);
+    }
+
+    fn visit_sha256(&mut self, result: &Value, data: &Value, len: &Value) {
+        self.visit_result(result);
+        self.visit_operand(data);
+        self.visit_operand(len);
+    }
+
+    fn visit_ripemd160(&mut self, result: &Value, data: &Value, len: &Value) {
+        self.visit_result(result);
+        self.visit_operand(data);
+        self.visit_operand(len);
+    }
+
+    fn visit_ec_recover(&mut self, result: &Value, hash: &Value, v: &Value, r: &Value, s: &Value) {
+        self.visit_result(result);
+        self.visit_operand(hash);
+        self.visit_operand(v);
+        self.visit_operand(r);
+        self.visit_operand(s);
+    }
+
+    fn visit_emit_event(&mut self, _event: &EventId, topics: &[Value], data: &[Value]) {
+        for value in topics {
+            self.visit_operand(value);
+        }
+        for value in data {
+            self.visit_operand(value);
+        }
+    }
+
+    fn visit_cast(&mut self, result: &Value, value: &Value, _to: &Type) {
+        self.visit_result(result);
+        self.visit_operand(value);
+    }
+
+    fn visit_zero_extend(&mut self, result: &Value, value: &Value, _to: &Type) {
+        self.visit_result(result);
+        self.visit_operand(value);
+    }
+
+    fn visit_sign_extend(&mut self, result: &Value, value: &Value, _to: &Type) {
+        self.visit_result(result);
+        self.visit_operand(value);
+    }
+
+    fn visit_truncate(&mut self, result: &Value, value: &Value, _to: &Type) {
+        self.visit_result(result);
+        self.visit_operand(value);
+    }
+
+    fn visit_assert(&mut self, condition: &Value, _message: &str) {
+        self.visit_operand(condition);
+    }
+
+    fn visit_require(&mut self, condition: &Value, _message: &str) {
+        self.visit_operand(condition);
+    }
+
+    fn visit_revert(&mut self, _message: &str) {}
+
+    fn visit_assign(&mut self, result: &Value, value: &Value) {
+        self.visit_result(result);
+        self.visit_operand(value);
+    }
+
+    fn visit_phi(&mut self, result: &Value, values: &[(BlockId, Value)]) {
+        self.visit_result(result);
+        for (_, value) in values {
+            self.visit_operand(value);
+        }
+    }
+
+    fn visit_jump(&mut self, _target: &BlockId, args: &[Value]) {
+        for value in args {
+            self.visit_operand(value);
+        }
+    }
+
+    fn visit_branch(
+        &mut self,
+        condition: &Value,
+        _then_block: &BlockId,
+        _else_block: &BlockId,
+        then_args: &[Value],
+        else_args: &[Value],
+    ) {
+        self.visit_operand(condition);
+        for value in then_args {
+            self.visit_operand(value);
+        }
+        for value in else_args {
+            self.visit_operand(value);
+        }
+    }
+
+    fn visit_return(&mut self, value: Option<&Value>) {
+        if let Some(value) = value {
+            self.visit_operand(value);
+        }
+    }
+
+    fn visit_memory_alloc(&mut self, result: &Value, size: &Value) {
+        self.visit_result(result);
+        self.visit_operand(size);
+    }
+
+    fn visit_memory_copy(&mut self, dest: &Value, src: &Value, size: &Value) {
+        self.visit_operand(dest);
+        self.visit_operand(src);
+        self.visit_operand(size);
+    }
+
+    fn visit_memory_size(&mut self, result: &Value) {
+        self.visit_result(result);
+    }
+}
+
+pub fn walk_instruction<V: InstructionVisitor + ?Sized>(visitor: &mut V, inst: &Instruction) {
+    match inst {
+        Instruction::Add {
+            result,
+            left,
+            right,
+            ty,
+        } => visitor.visit_add(result, left, right, ty),
+        Instruction::Sub {
+            result,
+            left,
+            right,
+            ty,
+        } => visitor.visit_sub(result, left, right, ty),
+        Instruction::Mul {
+            result,
+            left,
+            right,
+            ty,
+        } => visitor.visit_mul(result, left, right, ty),
+        Instruction::Div {
+            result,
+            left,
+            right,
+            ty,
+        } => visitor.visit_div(result, left, right, ty),
+        Instruction::Mod {
+            result,
+            left,
+            right,
+            ty,
+        } => visitor.visit_mod(result, left, right, ty),
+        Instruction::Sdiv {
+            result,
+            left,
+            right,
+            ty,
+        } => visitor.visit_sdiv(result, left, right, ty),
+        Instruction::Smod {
+            result,
+            left,
+            right,
+            ty,
+        } => visitor.visit_smod(result, left, right, ty),
+        Instruction::Pow { result, base, exp } => visitor.visit_pow(result, base, exp),
+        Instruction::CheckedAdd {
+            result,
+            left,
+            right,
+            ty,
+        } => visitor.visit_checked_add(result, left, right, ty),
+        Instruction::CheckedSub {
+            result,
+            left,
+            right,
+            ty,
+        } => visitor.visit_checked_sub(result, left, right, ty),
+        Instruction::CheckedMul {
+            result,
+            left,
+            right,
+            ty,
+        } => visitor.visit_checked_mul(result, left, right, ty),
+        Instruction::CheckedDiv {
+            result,
+            left,
+            right,
+            ty,
+        } => visitor.visit_checked_div(result, left, right, ty),
+        Instruction::And {
+            result,
+            left,
+            right,
+        } => visitor.visit_and(result, left, right),
+        Instruction::Or {
+            result,
+            left,
+            right,
+        } => visitor.visit_or(result, left, right),
+        Instruction::Xor {
+            result,
+            left,
+            right,
+        } => visitor.visit_xor(result, left, right),
+        Instruction::Not { result, operand } => visitor.visit_not(result, operand),
+        Instruction::Shl {
+            result,
+            value,
+            shift,
+        } => visitor.visit_shl(result, value, shift),
+        Instruction::Shr {
+            result,
+            value,
+            shift,
+        } => visitor.visit_shr(result, value, shift),
+        Instruction::Sar {
+            result,
+            value,
+            shift,
+        } => visitor.visit_sar(result, value, shift),
+        Instruction::Eq {
+            result,
+            left,
+            right,
+        } => visitor.visit_eq(result, left, right),
+        Instruction::Ne {
+            result,
+            left,
+            right,
+        } => visitor.visit_ne(result, left, right),
+        Instruction::Lt {
+            result,
+            left,
+            right,
+        } => visitor.visit_lt(result, left, right),
+        Instruction::Gt {
+            result,
+            left,
+            right,
+        } => visitor.visit_gt(result, left, right),
+        Instruction::Le {
+            result,
+            left,
+            right,
+        } => visitor.visit_le(result, left, right),
+        Instruction::Ge {
+            result,
+            left,
+            right,
+        } => visitor.visit_ge(result, left, right),
+        Instruction::Slt {
+            result,
+            left,
+            right,
+        } => visitor.visit_slt(result, left, right),
+        Instruction::Sgt {
+            result,
+            left,
+            right,
+        } => visitor.visit_sgt(result, left, right),
+        Instruction::Sle {
+            result,
+            left,
+            right,
+        } => visitor.visit_sle(result, left, right),
+        Instruction::Sge {
+            result,
+            left,
+            right,
+        } => visitor.visit_sge(result, left, right),
+        Instruction::Select {
+            result,
+            condition,
+            then_val,
+            else_val,
+        } => visitor.visit_select(result, condition, then_val, else_val),
+        Instruction::Load { result, location } => visitor.visit_load(result, location),
+        Instruction::Store { location, value } => visitor.visit_store(location, value),
+        Instruction::Allocate { result, ty, size } => visitor.visit_allocate(result, ty, size),
+        Instruction::Copy { dest, src, size } => visitor.visit_copy(dest, src, size),
+        Instruction::StorageLoad { result, key } => visitor.visit_storage_load(result, key),
+        Instruction::StorageStore { key, value } => visitor.visit_storage_store(key, value),
+        Instruction::StorageDelete { key } => visitor.visit_storage_delete(key),
+        Instruction::TransientLoad { result, key } => visitor.visit_transient_load(result, key),
+        Instruction::TransientStore { key, value } => visitor.visit_transient_store(key, value),
+        Instruction::MappingLoad {
+            result,
+            mapping,
+            key,
+        } => visitor.visit_mapping_load(result, mapping, key),
+        Instruction::MappingStore {
+            mapping,
+            key,
+            value,
+        } => visitor.visit_mapping_store(mapping, key, value),
+        Instruction::ArrayLoad {
+            result,
+            array,
+            index,
+        } => visitor.visit_array_load(result, array, index),
+        Instruction::ArrayStore {
+            array,
+            index,
+            value,
+        } => visitor.visit_array_store(array, index, value),
+        Instruction::ArrayLength { result, array } => visitor.visit_array_length(result, array),
+        Instruction::ArrayPush { array, value } => visitor.visit_array_push(array, value),
+        Instruction::ArrayPop { result, array } => visitor.visit_array_pop(result, array),
+        Instruction::Call {
+            result,
+            target,
+            args,
+            value,
+        } => visitor.visit_call(result, target, args, value.as_ref()),
+        Instruction::DelegateCall {
+            result,
+            target,
+            selector,
+            args,
+        } => visitor.visit_delegate_call(result, target, selector, args),
+        Instruction::StaticCall {
+            result,
+            target,
+            selector,
+            args,
+        } => visitor.visit_static_call(result, target, selector, args),
+        Instruction::Create {
+            result,
+            code,
+            value,
+        } => visitor.visit_create(result, code, value),
+        Instruction::Create2 {
+            result,
+            code,
+            salt,
+            value,
+        } => visitor.visit_create2(result, code, salt, value),
+        Instruction::Selfdestruct { beneficiary } => visitor.visit_selfdestruct(beneficiary),
+        Instruction::GetContext { result, var } => visitor.visit_get_context(result, var),
+        Instruction::GetBalance { result, address } => visitor.visit_get_balance(result, address),
+        Instruction::GetCode { result, address } => visitor.visit_get_code(result, address),
+        Instruction::GetCodeSize { result, address } => {
+            visitor.visit_get_code_size(result, address)
+        }
+        Instruction::GetCodeHash { result, address } => {
+            visitor.visit_get_code_hash(result, address)
+        }
+        Instruction::Keccak256 { result, data, len } => visitor.visit_keccak256(result, data, len),
+        Instruction::Sha256 { result, data, len } => visitor.visit_sha256(result, data, len),
+        Instruction::Ripemd160 { result, data, len } => visitor.visit_ripemd160(result, data, len),
+        Instruction::EcRecover {
+            result,
+            hash,
+            v,
+            r,
+            s,
+        } => visitor.visit_ec_recover(result, hash, v, r, s),
+        Instruction::EmitEvent {
+            event,
+            topics,
+            data,
+        } => visitor.visit_emit_event(event, topics, data),
+        Instruction::Cast { result, value, to } => visitor.visit_cast(result, value, to),
+        Instruction::ZeroExtend { result, value, to } => {
+            visitor.visit_zero_extend(result, value, to)
+        }
+        Instruction::SignExtend { result, value, to } => {
+            visitor.visit_sign_extend(result, value, to)
+        }
+        Instruction::Truncate { result, value, to } => visitor.visit_truncate(result, value, to),
+        Instruction::Assert { condition, message } => visitor.visit_assert(condition, message),
+        Instruction::Require { condition, message } => visitor.visit_require(condition, message),
+        Instruction::Revert { message } => visitor.visit_revert(message),
+        Instruction::Assign { result, value } => visitor.visit_assign(result, value),
+        Instruction::Phi { result, values } => visitor.visit_phi(result, values),
+        Instruction::Jump { target, args } => visitor.visit_jump(target, args),
+        Instruction::Branch {
+            condition,
+            then_block,
+            else_block,
+            then_args,
+            else_args,
+        } => visitor.visit_branch(condition, then_block, else_block, then_args, else_args),
+        Instruction::Return { value } => visitor.visit_return(value.as_ref()),
+        Instruction::MemoryAlloc { result, size } => visitor.visit_memory_alloc(result, size),
+        Instruction::MemoryCopy { dest, src, size } => visitor.visit_memory_copy(dest, src, size),
+        Instruction::MemorySize { result } => visitor.visit_memory_size(result),
+    }
+}
+
+pub fn walk_location<V: InstructionVisitor + ?Sized>(visitor: &mut V, location: &Location) {
+    match location {
+        Location::Stack { .. } => {}
+        Location::Memory { base, offset } => {
+            visitor.visit_operand(base);
+            visitor.visit_operand(offset);
+        }
+        Location::Storage { slot } => visitor.visit_operand(slot),
+        Location::Calldata { offset } | Location::ReturnData { offset } => {
+            visitor.visit_operand(offset)
+        }
+    }
+}
+
+pub fn walk_storage_key<V: InstructionVisitor + ?Sized>(visitor: &mut V, key: &StorageKey) {
+    match key {
+        StorageKey::Slot(_) => {}
+        StorageKey::Dynamic(value) | StorageKey::Computed(value) => visitor.visit_operand(value),
+        StorageKey::MappingKey { key, .. } => visitor.visit_operand(key),
+        StorageKey::ArrayElement { index, .. } => visitor.visit_operand(index),
+    }
+}
+
+/* Rebuilds an instruction by value. Each variant has a method taking its fields and returning the
+ * new instruction; by default it keeps the variant and passes every value through `fold_result`
+ * or `fold_operand`. */
+pub trait InstructionFolder {
+    fn fold_instruction(&mut self, inst: Instruction) -> Instruction {
+        rebuild_instruction(self, inst)
+    }
+
+    fn fold_operand(&mut self, value: Value) -> Value {
+        value
+    }
+
+    fn fold_result(&mut self, value: Value) -> Value {
+        value
+    }
+
+    fn fold_location(&mut self, location: Location) -> Location {
+        rebuild_location(self, location)
+    }
+
+    fn fold_storage_key(&mut self, key: StorageKey) -> StorageKey {
+        rebuild_storage_key(self, key)
+    }
+
+    fn fold_add(&mut self, result: Value, left: Value, right: Value, ty: Type) -> Instruction {
+        Instruction::Add {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+            ty,
+        }
+    }
+
+    fn fold_sub(&mut self, result: Value, left: Value, right: Value, ty: Type) -> Instruction {
+        Instruction::Sub {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+            ty,
+        }
+    }
+
+    fn fold_mul(&mut self, result: Value, left: Value, right: Value, ty: Type) -> Instruction {
+        Instruction::Mul {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+            ty,
+        }
+    }
+
+    fn fold_div(&mut self, result: Value, left: Value, right: Value, ty: Type) -> Instruction {
+        Instruction::Div {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+            ty,
+        }
+    }
+
+    fn fold_mod(&mut self, result: Value, left: Value, right: Value, ty: Type) -> Instruction {
+        Instruction::Mod {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+            ty,
+        }
+    }
+
+    fn fold_sdiv(&mut self, result: Value, left: Value, right: Value, ty: Type) -> Instruction {
+        Instruction::Sdiv {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+            ty,
+        }
+    }
+
+    fn fold_smod(&mut self, result: Value, left: Value, right: Value, ty: Type) -> Instruction {
+        Instruction::Smod {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+            ty,
+        }
+    }
+
+    fn fold_pow(&mut self, result: Value, base: Value, exp: Value) -> Instruction {
+        Instruction::Pow {
+            result: self.fold_result(result),
+            base: self.fold_operand(base),
+            exp: self.fold_operand(exp),
+        }
+    }
+
+    fn fold_checked_add(
+        &mut self,
+        result: Value,
+        left: Value,
+        right: Value,
+        ty: Type,
+    ) -> Instruction {
+        Instruction::CheckedAdd {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+            ty,
+        }
+    }
+
+    fn fold_checked_sub(
+        &mut self,
+        result: Value,
+        left: Value,
+        right: Value,
+        ty: Type,
+    ) -> Instruction {
+        Instruction::CheckedSub {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+            ty,
+        }
+    }
+
+    fn fold_checked_mul(
+        &mut self,
+        result: Value,
+        left: Value,
+        right: Value,
+        ty: Type,
+    ) -> Instruction {
+        Instruction::CheckedMul {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+            ty,
+        }
+    }
+
+    fn fold_checked_div(
+        &mut self,
+        result: Value,
+        left: Value,
+        right: Value,
+        ty: Type,
+    ) -> Instruction {
+        Instruction::CheckedDiv {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+            ty,
+        }
+    }
+
+    fn fold_and(&mut self, result: Value, left: Value, right: Value) -> Instruction {
+        Instruction::And {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+        }
+    }
+
+    fn fold_or(&mut self, result: Value, left: Value, right: Value) -> Instruction {
+        Instruction::Or {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+        }
+    }
+
+    fn fold_xor(&mut self, result: Value, left: Value, right: Value) -> Instruction {
+        Instruction::Xor {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+        }
+    }
+
+    fn fold_not(&mut self, result: Value, operand: Value) -> Instruction {
+        Instruction::Not {
+            result: self.fold_result(result),
+            operand: self.fold_operand(operand),
+        }
+    }
+
+    fn fold_shl(&mut self, result: Value, value: Value, shift: Value) -> Instruction {
+        Instruction::Shl {
+            result: self.fold_result(result),
+            value: self.fold_operand(value),
+            shift: self.fold_operand(shift),
+        }
+    }
+
+    fn fold_shr(&mut self, result: Value, value: Value, shift: Value) -> Instruction {
+        Instruction::Shr {
+            result: self.fold_result(result),
+            value: self.fold_operand(value),
+            shift: self.fold_operand(shift),
+        }
+    }
+
+    fn fold_sar(&mut self, result: Value, value: Value, shift: Value) -> Instruction {
+        Instruction::Sar {
+            result: self.fold_result(result),
+            value: self.fold_operand(value),
+            shift: self.fold_operand(shift),
+        }
+    }
+
+    fn fold_eq(&mut self, result: Value, left: Value, right: Value) -> Instruction {
+        Instruction::Eq {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+        }
+    }
+
+    fn fold_ne(&mut self, result: Value, left: Value, right: Value) -> Instruction {
+        Instruction::Ne {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+        }
+    }
+
+    fn fold_lt(&mut self, result: Value, left: Value, right: Value) -> Instruction {
+        Instruction::Lt {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+        }
+    }
+
+    fn fold_gt(&mut self, result: Value, left: Value, right: Value) -> Instruction {
+        Instruction::Gt {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+        }
+    }
+
+    fn fold_le(&mut self, result: Value, left: Value, right: Value) -> Instruction {
+        Instruction::Le {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+        }
+    }
+
+    fn fold_ge(&mut self, result: Value, left: Value, right: Value) -> Instruction {
+        Instruction::Ge {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+        }
+    }
+
+    fn fold_slt(&mut self, result: Value, left: Value, right: Value) -> Instruction {
+        Instruction::Slt {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+        }
+    }
+
+    fn fold_sgt(&mut self, result: Value, left: Value, right: Value) -> Instruction {
+        Instruction::Sgt {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+        }
+    }
+
+    fn fold_sle(&mut self, result: Value, left: Value, right: Value) -> Instruction {
+        Instruction::Sle {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+        }
+    }
+
+    fn fold_sge(&mut self, result: Value, left: Value, right: Value) -> Instruction {
+        Instruction::Sge {
+            result: self.fold_result(result),
+            left: self.fold_operand(left),
+            right: self.fold_operand(right),
+        }
+    }
+
+    fn fold_select(
+        &mut self,
+        result: Value,
+        condition: Value,
+        then_val: Value,
+        else_val: Value,
+    ) -> Instruction {
+        Instruction::Select {
+            result: self.fold_result(result),
+            condition: self.fold_operand(condition),
+            then_val: self.fold_operand(then_val),
+            else_val: self.fold_operand(else_val),
+        }
+    }
+
+    fn fold_load(&mut self, result: Value, location: Location) -> Instruction {
+        Instruction::Load {
+            result: self.fold_result(result),
+            location: self.fold_location(location),
+        }
+    }
+
+    fn fold_store(&mut self, location: Location, value: Value) -> Instruction {
+        Instruction::Store {
+            location: self.fold_location(location),
+            value: self.fold_operand(value),
+        }
+    }
+
+    fn fold_allocate(&mut self, result: Value, ty: Type, size: Size) -> Instruction {
+        Instruction::Allocate {
+            result: self.fold_result(result),
+            ty,
+            size: match size {
+                Size::Dynamic(value) => Size::Dynamic(self.fold_operand(value)),
+                other => other,
+            },
+        }
+    }
+
+    fn fold_copy(&mut self, dest: Location, src: Location, size: Value) -> Instruction {
+        Instruction::Copy {
+            dest: self.fold_location(dest),
+            src: self.fold_location(src),
+            size: self.fold_operand(size),
+        }
+    }
+
+    fn fold_storage_load(&mut self, result: Value, key: StorageKey) -> Instruction {
+        Instruction::StorageLoad {
+            result: self.fold_result(result),
+            key: self.fold_storage_key(key),
+        }
+    }
+
+    fn fold_storage_store(&mut self, key: StorageKey, value: Value) -> Instruction {
+        Instruction::StorageStore {
+            key: self.fold_storage_key(key),
+            value: self.fold_operand(value),
+        }
+    }
+
+    fn fold_storage_delete(&mut self, key: StorageKey) -> Instruction {
+        Instruction::StorageDelete {
+            key: self.fold_storage_key(key),
+        }
+    }
+
+    fn fold_transient_load(&mut self, result: Value, key: StorageKey) -> Instruction {
+        Instruction::TransientLoad {
+            result: self.fold_result(result),
+            key: self.fold_storage_key(key),
+        }
+    }
+
+    fn fold_transient_store(&mut self, key: StorageKey, value: Value) -> Instruction {
+        Instruction::TransientStore {
+            key: self.fold_storage_key(key),
+            value: self.fold_operand(value),
+        }
+    }
+
+    fn fold_mapping_load(&mut self, result: Value, mapping: Value, key: Value) -> Instruction {
+        Instruction::MappingLoad {
+            result: self.fold_result(result),
+            mapping: self.fold_operand(mapping),
+            key: self.fold_operand(key),
+        }
+    }
+
+    fn fold_mapping_store(&mut self, mapping: Value, key: Value, value: Value) -> Instruction {
+        Instruction::MappingStore {
+            mapping: self.fold_operand(mapping),
+            key: self.fold_operand(key),
+            value: self.fold_operand(value),
+        }
+    }
+
+    fn fold_array_load(&mut self, result: Value, array: Value, index: Value) -> Instruction {
+        Instruction::ArrayLoad {
+            result: self.fold_result(result),
+            array: self.fold_operand(array),
+            index: self.fold_operand(index),
+        }
+    }
+
+    fn fold_array_store(&mut self, array: Value, index: Value, value: Value) -> Instruction {
+        Instruction::ArrayStore {
+            array: self.fold_operand(array),
+            index: self.fold_operand(index),
+            value: self.fold_operand(value),
+        }
+    }
+
+    fn fold_array_length(&mut self, result: Value, array: Value) -> Instruction {
+        Instruction::ArrayLength {
+            result: self.fold_result(result),
+            array: self.fold_operand(array),
+        }
+    }
+
+    fn fold_array_push(&mut self, array: Value, value: Value) -> Instruction {
+        Instruction::ArrayPush {
+            array: self.fold_operand(array),
+            value: self.fold_operand(value),
+        }
+    }
+
+    fn fold_array_pop(&mut self, result: Value, array: Value) -> Instruction {
+        Instruction::ArrayPop {
+            result: self.fold_result(result),
+            array: self.fold_operand(array),
+        }
+    }
+
+    fn fold_call(
+        &mut self,
+        result: Value,
+        target: CallTarget,
+        args: Vec<Value>,
+        value: Option<Value>,
+    ) -> Instruction {
+        Instruction::Call {
+            result: self.fold_result(result),
+            target: match target {
+                CallTarget::External(address) => CallTarget::External(self.fold_operand(address)),
+                other => other,
+            },
+            args: args
+                .into_iter()
+                .map(|value| self.fold_operand(value))
+                .collect(),
+            value: value.map(|value| self.fold_operand(value)),
+        }
+    }
+
+    fn fold_delegate_call(
+        &mut self,
+        result: Value,
+        target: Value,
+        selector: Value,
+        args: Vec<Value>,
+    ) -> Instruction {
+        Instruction::DelegateCall {
+            result: self.fold_result(result),
+            target: self.fold_operand(target),
+            selector: self.fold_operand(selector),
+            args: args
+                .into_iter()
+                .map(|value| self.fold_operand(value))
+                .collect(),
+        }
+    }
+
+    fn fold_static_call(
+        &mut self,
+        result: Value,
+        target: Value,
+        selector: Value,
+        args: Vec<Value>,
+    ) -> Instruction {
+        Instruction::StaticCall {
+            result: self.fold_result(result),
+            target: self.fold_operand(target),
+            selector: self.fold_operand(selector),
+            args: args
+                .into_iter()
+                .map(|value| self.fold_operand(value))
+                .collect(),
+        }
+    }
+
+    fn fold_create(&mut self, result: Value, code: Value, value: Value) -> Instruction {
+        Instruction::Create {
+            result: self.fold_result(result),
+            code: self.fold_operand(code),
+            value: self.fold_operand(value),
+        }
+    }
+
+    fn fold_create2(
+        &mut self,
+        result: Value,
+        code: Value,
+        salt: Value,
+        value: Value,
+    ) -> Instruction {
+        Instruction::Create2 {
+            result: self.fold_result(result),
+            code: self.fold_operand(code),
+            salt: self.fold_operand(salt),
+            value: self.fold_operand(value),
+        }
+    }
+
+    fn fold_selfdestruct(&mut self, beneficiary: Value) -> Instruction {
+        Instruction::Selfdestruct {
+            beneficiary: self.fold_operand(beneficiary),
+        }
+    }
+
+    fn fold_get_context(&mut self, result: Value, var: ContextVariable) -> Instruction {
+        Instruction::GetContext {
+            result: self.fold_result(result),
+            var,
+        }
+    }
+
+    fn fold_get_balance(&mut self, result: Value, address: Value) -> Instruction {
+        Instruction::GetBalance {
+            result: self.fold_result(result),
+            address: self.fold_operand(address),
+        }
+    }
+
+    fn fold_get_code(&mut self, result: Value, address: Value) -> Instruction {
+        Instruction::GetCode {
+            result: self.fold_result(result),
+            address: self.fold_operand(address),
+        }
+    }
+
+    fn fold_get_code_size(&mut self, result: Value, address: Value) -> Instruction {
+        Instruction::GetCodeSize {
+            result: self.fold_result(result),
+            address: self.fold_operand(address),
+        }
+    }
+
+    fn fold_get_code_hash(&mut self, result: Value, address: Value) -> Instruction {
+        Instruction::GetCodeHash {
+            result: self.fold_result(result),
+            address: self.fold_operand(address),
+        }
+    }
+
+    fn fold_keccak256(&mut self, result: Value, data: Value, len: Value) -> Instruction {
+        Instruction::Keccak256 {
+            result: self.fold_result(result),
+            data: self.fold_operand(data),
+            len: self.fold_operand(len),
+        }
+    }
+
+    fn fold_sha256(&mut self, result: Value, data: Value, len: Value) -> Instruction {
+        Instruction::Sha256 {
+            result: self.fold_result(result),
+            data: self.fold_operand(data),
+            len: self.fold_operand(len),
+        }
+    }
+
+    fn fold_ripemd160(&mut self, result: Value, data: Value, len: Value) -> Instruction {
+        Instruction::Ripemd160 {
+            result: self.fold_result(result),
+            data: self.fold_operand(data),
+            len: self.fold_operand(len),
+        }
+    }
+
+    fn fold_ec_recover(
+        &mut self,
+        result: Value,
+        hash: Value,
+        v: Value,
+        r: Value,
+        s: Value,
+    ) -> Instruction {
+        Instruction::EcRecover {
+            result: self.fold_result(result),
+            hash: self.fold_operand(hash),
+            v: self.fold_operand(v),
+            r: self.fold_operand(r),
+            s: self.fold_operand(s),
+        }
+    }
+
+    fn fold_emit_event(
+        &mut self,
+        event: EventId,
+        topics: Vec<Value>,
+        data: Vec<Value>,
+    ) -> Instruction {
+        Instruction::EmitEvent {
+            event,
+            topics: topics
+                .into_iter()
+                .map(|value| self.fold_operand(value))
+                .collect(),
+            data: data
+                .into_iter()
+                .map(|value| self.fold_operand(value))
+                .collect(),
+        }
+    }
+
+    fn fold_cast(&mut self, result: Value, value: Value, to: Type) -> Instruction {
+        Instruction::Cast {
+            result: self.fold_result(result),
+            value: self.fold_operand(value),
+            to,
+        }
+    }
+
+    fn fold_zero_extend(&mut self, result: Value, value: Value, to: Type) -> Instruction {
+        Instruction::ZeroExtend {
+            result: self.fold_result(result),
+            value: self.fold_operand(value),
+            to,
+        }
+    }
+
+    fn fold_sign_extend(&mut self, result: Value, value: Value, to: Type) -> Instruction {
+        Instruction::SignExtend {
+            result: self.fold_result(result),
+            value: self.fold_operand(value),
+            to,
+        }
+    }
+
+    fn fold_truncate(&mut self, result: Value, value: Value, to: Type) -> Instruction {
+        Instruction::Truncate {
+            result: self.fold_result(result),
+            value: self.fold_operand(value),
+            to,
+        }
+    }
+
+    fn fold_assert(&mut self, condition: Value, message: String) -> Instruction {
+        Instruction::Assert {
+            condition: self.fold_operand(condition),
+            message,
+        }
+    }
+
+    fn fold_require(&mut self, condition: Value, message: String) -> Instruction {
+        Instruction::Require {
+            condition: self.fold_operand(condition),
+            message,
+        }
+    }
+
+    fn fold_revert(&mut self, message: String) -> Instruction {
+        Instruction::Revert { message }
+    }
+
+    fn fold_assign(&mut self, result: Value, value: Value) -> Instruction {
+        Instruction::Assign {
+            result: self.fold_result(result),
+            value: self.fold_operand(value),
+        }
+    }
+
+    fn fold_phi(&mut self, result: Value, values: Vec<(BlockId, Value)>) -> Instruction {
+        Instruction::Phi {
+            result: self.fold_result(result),
+            values: values
+                .into_iter()
+                .map(|(block, value)| (block, self.fold_operand(value)))
+                .collect(),
+        }
+    }
+
+    fn fold_jump(&mut self, target: BlockId, args: Vec<Value>) -> Instruction {
+        Instruction::Jump {
+            target,
+            args: args
+                .into_iter()
+                .map(|value| self.fold_operand(value))
+                .collect(),
+        }
+    }
+
+    fn fold_branch(
+        &mut self,
+        condition: Value,
+        then_block: BlockId,
+        else_block: BlockId,
+        then_args: Vec<Value>,
+        else_args: Vec<Value>,
+    ) -> Instruction {
+        Instruction::Branch {
+            condition: self.fold_operand(condition),
+            then_block,
+            else_block,
+            then_args: then_args
+                .into_iter()
+                .map(|value| self.fold_operand(value))
+                .collect(),
+            else_args: else_args
+                .into_iter()
+                .map(|value| self.fold_operand(value))
+                .collect(),
+        }
+    }
+
+    fn fold_return(&mut self, value: Option<Value>) -> Instruction {
+        Instruction::Return {
+            value: value.map(|value| self.fold_operand(value)),
+        }
+    }
+
+    fn fold_memory_alloc(&mut self, result: Value, size: Value) -> Instruction {
+        Instruction::MemoryAlloc {
+            result: self.fold_result(result),
+            size: self.fold_operand(size),
+        }
+    }
+
+    fn fold_memory_copy(&mut self, dest: Value, src: Value, size: Value) -> Instruction {
+        Instruction::MemoryCopy {
+            dest: self.fold_operand(dest),
+            src: self.fold_operand(src),
+            size: self.fold_operand(size),
+        }
+    }
+
+    fn fold_memory_size(&mut self, result: Value) -> Instruction {
+        Instruction::MemorySize {
+            result: self.fold_result(result),
+        }
+    }
+}
+
+pub fn rebuild_instruction<F: InstructionFolder + ?Sized>(
+    folder: &mut F,
+    inst: Instruction,
+) -> Instruction {
+    match inst {
+        Instruction::Add {
+            result,
+            left,
+            right,
+            ty,
+        } => folder.fold_add(result, left, right, ty),
+        Instruction::Sub {
+            result,
+            left,
+            right,
+            ty,
+        } => folder.fold_sub(result, left, right, ty),
+        Instruction::Mul {
+            result,
+            left,
+            right,
+            ty,
+        } => folder.fold_mul(result, left, right, ty),
+        Instruction::Div {
+            result,
+            left,
+            right,
+            ty,
+        } => folder.fold_div(result, left, right, ty),
+        Instruction::Mod {
+            result,
+            left,
+            right,
+            ty,
+        } => folder.fold_mod(result, left, right, ty),
+        Instruction::Sdiv {
+            result,
+            left,
+            right,
+            ty,
+        } => folder.fold_sdiv(result, left, right, ty),
+        Instruction::Smod {
+            result,
+            left,
+            right,
+            ty,
+        } => folder.fold_smod(result, left, right, ty),
+        Instruction::Pow { result, base, exp } => folder.fold_pow(result, base, exp),
+        Instruction::CheckedAdd {
+            result,
+            left,
+            right,
+            ty,
+        } => folder.fold_checked_add(result, left, right, ty),
+        Instruction::CheckedSub {
+            result,
+            left,
+            right,
+            ty,
+        } => folder.fold_checked_sub(result, left, right, ty),
+        Instruction::CheckedMul {
+            result,
+            left,
+            right,
+            ty,
+        } => folder.fold_checked_mul(result, left, right, ty),
+        Instruction::CheckedDiv {
+            result,
+            left,
+            right,
+            ty,
+        } => folder.fold_checked_div(result, left, right, ty),
+        Instruction::And {
+            result,
+            left,
+            right,
+        } => folder.fold_and(result, left, right),
+        Instruction::Or {
+            result,
+            left,
+            right,
+        } => folder.fold_or(result, left, right),
+        Instruction::Xor {
+            result,
+            left,
+            right,
+        } => folder.fold_xor(result, left, right),
+        Instruction::Not { result, operand } => folder.fold_not(result, operand),
+        Instruction::Shl {
+            result,
+            value,
+            shift,
+        } => folder.fold_shl(result, value, shift),
+        Instruction::Shr {
+            result,
+            value,
+            shift,
+        } => folder.fold_shr(result, value, shift),
+        Instruction::Sar {
+            result,
+            value,
+            shift,
+        } => folder.fold_sar(result, value, shift),
+        Instruction::Eq {
+            result,
+            left,
+            right,
+        } => folder.fold_eq(result, left, right),
+        Instruction::Ne {
+            result,
+            left,
+            right,
+        } => folder.fold_ne(result, left, right),
+        Instruction::Lt {
+            result,
+            left,
+            right,
+        } => folder.fold_lt(result, left, right),
+        Instruction::Gt {
+            result,
+            left,
+            right,
+        } => folder.fold_gt(result, left, right),
+        Instruction::Le {
+            result,
+            left,
+            right,
+        } => folder.fold_le(result, left, right),
+        Instruction::Ge {
+            result,
+            left,
+            right,
+        } => folder.fold_ge(result, left, right),
+        Instruction::Slt {
+            result,
+            left,
+            right,
+        } => folder.fold_slt(result, left, right),
+        Instruction::Sgt {
+            result,
+            left,
+            right,
+        } => folder.fold_sgt(result, left, right),
+        Instruction::Sle {
+            result,
+            left,
+            right,
+        } => folder.fold_sle(result, left, right),
+        Instruction::Sge {
+            result,
+            left,
+            right,
+        } => folder.fold_sge(result, left, right),
+        Instruction::Select {
+            result,
+            condition,
+            then_val,
+            else_val,
+        } => folder.fold_select(result, condition, then_val, else_val),
+        Instruction::Load { result, location } => folder.fold_load(result, location),
+        Instruction::Store { location, value } => folder.fold_store(location, value),
+        Instruction::Allocate { result, ty, size } => folder.fold_allocate(result, ty, size),
+        Instruction::Copy { dest, src, size } => folder.fold_copy(dest, src, size),
+        Instruction::StorageLoad { result, key } => folder.fold_storage_load(result, key),
+        Instruction::StorageStore { key, value } => folder.fold_storage_store(key, value),
+        Instruction::StorageDelete { key } => folder.fold_storage_delete(key),
+        Instruction::TransientLoad { result, key } => folder.fold_transient_load(result, key),
+        Instruction::TransientStore { key, value } => folder.fold_transient_store(key, value),
+        Instruction::MappingLoad {
+            result,
+            mapping,
+            key,
+        } => folder.fold_mapping_load(result, mapping, key),
+        Instruction::MappingStore {
+            mapping,
+            key,
+            value,
+        } => folder.fold_mapping_store(mapping, key, value),
+        Instruction::ArrayLoad {
+            result,
+            array,
+            index,
+        } => folder.fold_array_load(result, array, index),
+        Instruction::ArrayStore {
+            array,
+            index,
+            value,
+        } => folder.fold_array_store(array, index, value),
+        Instruction::ArrayLength { result, array } => folder.fold_array_length(result, array),
+        Instruction::ArrayPush { array, value } => folder.fold_array_push(array, value),
+        Instruction::ArrayPop { result, array } => folder.fold_array_pop(result, array),
+        Instruction::Call {
+            result,
+            target,
+            args,
+            value,
+        } => folder.fold_call(result, target, args, value),
+        Instruction::DelegateCall {
+            result,
+            target,
+            selector,
+            args,
+        } => folder.fold_delegate_call(result, target, selector, args),
+        Instruction::StaticCall {
+            result,
+            target,
+            selector,
+            args,
+        } => folder.fold_static_call(result, target, selector, args),
+        Instruction::Create {
+            result,
+            code,
+            value,
+        } => folder.fold_create(result, code, value),
+        Instruction::Create2 {
+            result,
+            code,
+            salt,
+            value,
+        } => folder.fold_create2(result, code, salt, value),
+        Instruction::Selfdestruct { beneficiary } => folder.fold_selfdestruct(beneficiary),
+        Instruction::GetContext { result, var } => folder.fold_get_context(result, var),
+        Instruction::GetBalance { result, address } => folder.fold_get_balance(result, address),
+        Instruction::GetCode { result, address } => folder.fold_get_code(result, address),
+        Instruction::GetCodeSize { result, address } => folder.fold_get_code_size(result, address),
+        Instruction::GetCodeHash { result, address } => folder.fold_get_code_hash(result, address),
+        Instruction::Keccak256 { result, data, len } => folder.fold_keccak256(result, data, len),
+        Instruction::Sha256 { result, data, len } => folder.fold_sha256(result, data, len),
+        Instruction::Ripemd160 { result, data, len } => folder.fold_ripemd160(result, data, len),
+        Instruction::EcRecover {
+            result,
+            hash,
+            v,
+            r,
+            s,
+        } => folder.fold_ec_recover(result, hash, v, r, s),
+        Instruction::EmitEvent {
+            event,
+            topics,
+            data,
+        } => folder.fold_emit_event(event, topics, data),
+        Instruction::Cast { result, value, to } => folder.fold_cast(result, value, to),
+        Instruction::ZeroExtend { result, value, to } => folder.fold_zero_extend(result, value, to),
+        Instruction::SignExtend { result, value, to } => folder.fold_sign_extend(result, value, to),
+        Instruction::Truncate { result, value, to } => folder.fold_truncate(result, value, to),
+        Instruction::Assert { condition, message } => folder.fold_assert(condition, message),
+        Instruction::Require { condition, message } => folder.fold_require(condition, message),
+        Instruction::Revert { message } => folder.fold_revert(message),
+        Instruction::Assign { result, value } => folder.fold_assign(result, value),
+        Instruction::Phi { result, values } => folder.fold_phi(result, values),
+        Instruction::Jump { target, args } => folder.fold_jump(target, args),
+        Instruction::Branch {
+            condition,
+            then_block,
+            else_block,
+            then_args,
+            else_args,
+        } => folder.fold_branch(condition, then_block, else_block, then_args, else_args),
+        Instruction::Return { value } => folder.fold_return(value),
+        Instruction::MemoryAlloc { result, size } => folder.fold_memory_alloc(result, size),
+        Instruction::MemoryCopy { dest, src, size } => folder.fold_memory_copy(dest, src, size),
+        Instruction::MemorySize { result } => folder.fold_memory_size(result),
+    }
+}
+
+pub fn rebuild_location<F: InstructionFolder + ?Sized>(
+    folder: &mut F,
+    location: Location,
+) -> Location {
+    match location {
+        Location::Stack { offset } => Location::Stack { offset },
+        Location::Memory { base, offset } => Location::Memory {
+            base: folder.fold_operand(base),
+            offset: folder.fold_operand(offset),
+        },
+        Location::Storage { slot } => Location::Storage {
+            slot: folder.fold_operand(slot),
+        },
+        Location::Calldata { offset } => Location::Calldata {
+            offset: folder.fold_operand(offset),
+        },
+        Location::ReturnData { offset } => Location::ReturnData {
+            offset: folder.fold_operand(offset),
+        },
+    }
+}
+
+pub fn rebuild_storage_key<F: InstructionFolder + ?Sized>(
+    folder: &mut F,
+    key: StorageKey,
+) -> StorageKey {
+    match key {
+        StorageKey::Slot(slot) => StorageKey::Slot(slot),
+        StorageKey::Dynamic(value) => StorageKey::Dynamic(folder.fold_operand(value)),
+        StorageKey::Computed(value) => StorageKey::Computed(folder.fold_operand(value)),
+        StorageKey::MappingKey { base, key } => StorageKey::MappingKey {
+            base,
+            key: folder.fold_operand(key),
+        },
+        StorageKey::ArrayElement { base, index } => StorageKey::ArrayElement {
+            base,
+            index: folder.fold_operand(index),
+        },
+    }
+}
+
+/* Calls `f` with every value `inst` reads, in field order: `Instruction::operands`. */
+pub fn visit_operands(inst: &Instruction, f: impl FnMut(&Value)) {
+    inst.operands().into_iter().for_each(f);
+}
+
+/* Rebuilds `inst` with every value it reads replaced by `f`, keeping its result. */
+pub fn map_operands(mut inst: Instruction, mut f: impl FnMut(Value) -> Value) -> Instruction {
+    for operand in inst.operands_mut() {
+        *operand = f(std::mem::replace(operand, Value::Undefined));
+    }
+    inst
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::values::{Constant, TempId};
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_visitor_and_folder_reach_nested_operands() {
+        struct StorageWrites(usize);
+
+        impl InstructionVisitor for StorageWrites {
+            fn visit_storage_store(&mut self, key: &StorageKey, value: &Value) {
+                self.0 += 1;
+                self.visit_storage_key(key);
+                self.visit_operand(value);
+            }
+        }
+
+        let temp = |id| Value::Temp(TempId(id));
+        let store = Instruction::StorageStore {
+            key: StorageKey::MappingKey {
+                base: BigUint::from(2u32),
+                key: temp(0),
+            },
+            value: temp(1),
+        };
+        let call = Instruction::Call {
+            result: temp(2),
+            target: CallTarget::External(temp(3)),
+            args: vec![temp(4)],
+            value: Some(temp(5)),
+        };
+
+        let mut writes = StorageWrites(0);
+        writes.visit_instruction(&store);
+        writes.visit_instruction(&call);
+        assert_eq!(writes.0, 1);
+
+        let mut seen = Vec::new();
+        visit_operands(&call, |value| seen.push(value.clone()));
+        assert_eq!(seen, vec![temp(3), temp(4), temp(5)]);
+
+        let zero = Value::Constant(Constant::Uint(BigUint::from(0u32), 256));
+        let renamed = map_operands(store, |_| zero.clone());
+        match renamed {
+            Instruction::StorageStore {
+                key: StorageKey::MappingKey { base, key },
+                value,
+            } => {
+                assert_eq!(base, BigUint::from(2u32));
+                assert_eq!(key, zero);
+                assert_eq!(value, zero);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let renamed = map_operands(call, |_| zero.clone());
+        assert_eq!(renamed.result(), Some(&temp(2)));
+    }
+}
@@ -5,6 +5,7 @@
 use num_bigint::BigUint;
 use thalir_core::{
     builder::{IRBuilder, InstBuilder, InstBuilderExt},
+    transform::GlobalValueNumbering,
     types::Type,
     values::Value,
 };
@@ -158,3 +159,29 @@ fn test_context_access() {
     let context_contract = registry.get_contract("ContextTest").unwrap();
     assert!(context_contract.functions.contains_key("getSender"));
 }
+
+#[test]
+fn test_ir_macro_feeds_a_pass() {
+    let mut function = thalir_core::ir! {
+        fn total(u256, u256) -> u256;
+        block0: {
+            v0 = add(p0, p1);
+            v1 = sload(0);
+            v2 = add(p0, p1);
+            v3 = sload(0);
+            v4 = mul(v0, v2);
+            v5 = add(v1, v3);
+            v6 = add(v4, v5);
+            ret v6
+        }
+    };
+
+    let removed = GlobalValueNumbering::new().run_function(&mut function);
+    assert_eq!(removed, 2);
+    assert_eq!(
+        function.body.blocks[&function.entry_block()]
+            .instructions
+            .len(),
+        5
+    );
+}
@@ -1,10 +1,20 @@
 #![allow(unused_imports)]
 #![allow(unused_variables)]
 
+use cranelift_codegen::ir::{types, Function as ClifFunction};
+use num_bigint::BigUint;
+use std::collections::HashMap;
 use thalir_core::{
+    block::{BlockId, Terminator},
     builder::{IRBuilder, InstBuilder},
-    codegen::module::ModuleBuilder,
+    codegen::{
+        compile_contract, lower_instruction, lowering::lower_terminator, module::ModuleBuilder,
+        CalleeNames, CodegenContext, WordAbi,
+    },
+    instructions::{CallTarget, Instruction},
     types::Type,
+    values::{TempId, Value},
+    IrError,
 };
 
 #[test]
@@ -34,3 +44,176 @@ fn test_simple_add_cranelift() {
     let res = module_builder.compile_contract(test_contract);
     assert!(res.is_ok());
 }
+
+#[test]
+fn test_block_arguments_reach_block_params() {
+    let mut builder = IRBuilder::new();
+    let mut contract = builder.contract("Test");
+
+    let mut func = contract.function("offset");
+    func.param("a", Type::Uint(64))
+        .param("b", Type::Uint(64))
+        .returns(Type::Uint(64));
+    let a = func.get_param(0);
+    let b = func.get_param(1);
+    let next = func.create_block_id();
+
+    let mut entry = func.entry_block();
+    let sum = entry.add(a.clone(), b, Type::Uint(64));
+    entry.jump_with_args(next, vec![sum]).unwrap();
+
+    let mut block = func.switch_to_block(next).unwrap();
+    let x = block.param("x", Type::Uint(64));
+    let result = block.sub(x, a, Type::Uint(64));
+    block.return_value(result).unwrap();
+
+    let function = func.build().unwrap();
+    assert_eq!(function.body.blocks[&next].params.len(), 1);
+    contract.build().unwrap();
+
+    let test_contract = builder.registry().get_contract("Test").unwrap();
+    let module_builder = ModuleBuilder::new().unwrap();
+    assert!(module_builder.compile_contract(test_contract).is_ok());
+}
+
+#[test]
+fn test_compile_contract_links_internal_calls_and_imports_the_runtime() {
+    let mut builder = IRBuilder::new();
+    let mut contract = builder.contract("Counter");
+
+    let mut func = contract.function("bump");
+    func.param("a", Type::Uint(256)).returns(Type::Uint(256));
+    let a = func.get_param(0);
+    let mut entry = func.entry_block();
+    let one = entry.constant_uint(1, 256);
+    let result = entry.add(a, one, Type::Uint(256));
+    entry.return_value(result).unwrap();
+    func.build().unwrap();
+
+    let mut func = contract.function("bumpStored");
+    func.returns(Type::Uint(256));
+    let mut entry = func.entry_block();
+    let stored = entry.storage_load(BigUint::from(0u32));
+    let bumped = entry.call_internal("bump", vec![stored]);
+    entry.return_value(bumped).unwrap();
+    func.build().unwrap();
+    contract.build().unwrap();
+
+    let counter = builder.registry().get_contract("Counter").unwrap();
+    let compiled = compile_contract(counter).unwrap();
+    assert!(!compiled.object.is_empty());
+    assert_eq!(compiled.functions, vec!["bump", "bumpStored"]);
+    assert_eq!(compiled.imports, vec!["thalir_rt_1_0"]);
+
+    /* The runtime import changes shape with the word ABI; the object still verifies and links. */
+    for abi in [WordAbi::I64Pair, WordAbi::Handle] {
+        let compiled = ModuleBuilder::new()
+            .unwrap()
+            .with_word_abi(abi)
+            .compile_contract(counter)
+            .unwrap();
+        assert_eq!(compiled.imports, vec!["thalir_rt_1_0"]);
+    }
+}
+
+#[test]
+fn test_undefined_values_are_errors() {
+    let mut builder = IRBuilder::new();
+    let mut contract = builder.contract("Broken");
+
+    let mut func = contract.function("add");
+    func.param("a", Type::Uint(64)).returns(Type::Uint(64));
+    let a = func.get_param(0);
+    let mut entry = func.entry_block();
+    let result = entry.add(a, Value::Temp(TempId(99)), Type::Uint(64));
+    entry.return_value(result).unwrap();
+    func.build().unwrap();
+    contract.build().unwrap();
+
+    let broken = builder.registry().get_contract("Broken").unwrap();
+    match ModuleBuilder::new().unwrap().compile_contract(broken) {
+        Err(IrError::UndefinedValue { value, instruction }) => {
+            assert_eq!(value, "Temp(TempId(99))");
+            assert!(instruction.starts_with("Add"), "{}", instruction);
+        }
+        other => panic!("expected an undefined value error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_lowering_reports_the_missing_value_and_block() {
+    let mut func = ClifFunction::new();
+    let mut context = CodegenContext::new(&mut func);
+    let mut builder = context.func_builder();
+    let block = builder.create_block();
+    builder.append_block_param(block, types::I64);
+    builder.switch_to_block(block);
+
+    let missing = Value::Temp(TempId(7));
+    let inst = Instruction::Keccak256 {
+        result: Value::Temp(TempId(8)),
+        data: missing.clone(),
+        len: missing.clone(),
+    };
+    let mut ssa_values = HashMap::new();
+    let mut callees = CalleeNames::new();
+    let err = lower_instruction(
+        &inst,
+        &HashMap::new(),
+        &mut ssa_values,
+        &mut builder,
+        &mut callees,
+        WordAbi::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, IrError::UndefinedValue { .. }), "{}", err);
+    assert!(err.to_string().contains("Temp(TempId(7))"), "{}", err);
+
+    let returned = Terminator::Return(Some(missing));
+    let err = lower_terminator(&returned, &ssa_values, &mut builder, &HashMap::new()).unwrap_err();
+    assert!(matches!(err, IrError::UndefinedValue { .. }), "{}", err);
+
+    let jump = Terminator::Jump(BlockId(3), Vec::new());
+    let err = lower_terminator(&jump, &ssa_values, &mut builder, &HashMap::new()).unwrap_err();
+    match err {
+        IrError::UndefinedBlock { block, .. } => assert_eq!(block, BlockId(3).to_string()),
+        other => panic!("expected an undefined block error, got {}", other),
+    }
+}
+
+#[test]
+fn test_callees_with_same_length_names_get_distinct_names() {
+    let mut func = ClifFunction::new();
+    let mut callees = CalleeNames::new();
+    {
+        let mut context = CodegenContext::new(&mut func);
+        let mut builder = context.func_builder();
+        let block = builder.create_block();
+        builder.switch_to_block(block);
+
+        let mut ssa_values = HashMap::new();
+        for (i, name) in ["foo", "bar", "foo"].into_iter().enumerate() {
+            let call = Instruction::Call {
+                result: Value::Temp(TempId(i as u32)),
+                target: CallTarget::Internal(name.to_string()),
+                args: Vec::new(),
+                value: None,
+            };
+            lower_instruction(
+                &call,
+                &HashMap::new(),
+                &mut ssa_values,
+                &mut builder,
+                &mut callees,
+                WordAbi::default(),
+            )
+            .unwrap();
+        }
+    }
+
+    let names: Vec<_> = func.params.user_named_funcs().values().cloned().collect();
+    assert_eq!(names.len(), 2);
+    assert_ne!(names[0], names[1]);
+    assert_eq!(callees.resolve(&names[0]), Some("foo"));
+    assert_eq!(callees.resolve(&names[1]), Some("bar"));
+}
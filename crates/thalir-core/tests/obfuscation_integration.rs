@@ -27,6 +27,8 @@ fn create_test_contract_with_identifiable_names() -> Contract {
                     var_type: Type::Uint(256),
                     name: "liquidityPoolReserves".to_string(),
                     packed_with: Vec::new(),
+                    location: None,
+                    is_private: false,
                 },
                 StorageSlot {
                     slot: BigUint::from(1u32),
@@ -34,11 +36,14 @@ fn create_test_contract_with_identifiable_names() -> Contract {
                     var_type: Type::Address,
                     name: "contractOwner".to_string(),
                     packed_with: Vec::new(),
+                    location: None,
+                    is_private: false,
                 },
             ],
             mappings: Vec::new(),
             arrays: Vec::new(),
             structs: Vec::new(),
+            inherited: Vec::new(),
         },
         events: Vec::new(),
         modifiers: Vec::new(),
@@ -128,7 +133,8 @@ fn test_end_to_end_obfuscation_workflow() {
     }
 
     let pass = manager.get_pass::<ObfuscationPass>().unwrap();
-    let mapping = pass.export_mapping();
+    let mut mapping = pass.export_mapping();
+    mapping.sign(b"audit-key");
 
     assert_eq!(
         mapping.deobfuscate("contract_0"),
@@ -143,7 +149,7 @@ fn test_end_to_end_obfuscation_workflow() {
         Access to storage var_0 after external call\n\
         Consider using checks-effects-interactions pattern";
 
-    let mapper = VulnerabilityMapper::from_mapping(mapping);
+    let mapper = VulnerabilityMapper::from_mapping_with_key(mapping, b"audit-key").unwrap();
     let original_report = mapper.deobfuscate_report(obfuscated_report);
 
     assert!(original_report.contains("NovelBondingCurveAMM"));
@@ -1,12 +1,17 @@
+use crate::config::EmitterConfig;
+use crate::intrinsics::{self, IntrinsicKind, IntrinsicMatch};
 use crate::ir_formatter_base::IRFormatterBase;
 use crate::thalir_emitter::{SSAContext, ThalIREmitter};
 use anyhow::Result;
+use num_bigint::BigUint;
 use thalir_core::{
+    analysis::DefUseChains,
     block::{BasicBlock, Terminator},
     contract::Contract,
     function::Function,
-    instructions::{CallTarget, Instruction},
-    ObfuscationConfig, ObfuscationMapping,
+    instructions::{CallTarget, Instruction, StorageKey},
+    review::ReviewFile,
+    stable_id, ObfuscationConfig, ObfuscationMapping,
 };
 
 #[derive(Debug, Clone)]
@@ -16,6 +21,7 @@ pub struct AnnotationConfig {
     pub use_ascii_cues: bool,
     pub emit_ordering_analysis: bool,
     pub emit_function_headers: bool,
+    pub condense_intrinsics: bool,
 }
 
 impl Default for AnnotationConfig {
@@ -26,6 +32,7 @@ impl Default for AnnotationConfig {
             use_ascii_cues: false,
             emit_ordering_analysis: true,
             emit_function_headers: true,
+            condense_intrinsics: false,
         }
     }
 }
@@ -100,6 +107,8 @@ struct SecurityAnalysis {
     unchecked_arith_positions: Vec<usize>,
     block_timestamp_positions: Vec<usize>,
     block_variable_positions: Vec<usize>,
+    transient_loads: Vec<(usize, BigUint)>,
+    transient_stores: Vec<(usize, BigUint)>,
 }
 
 impl SecurityAnalysis {
@@ -113,18 +122,51 @@ impl SecurityAnalysis {
             unchecked_arith_positions: Vec::new(),
             block_timestamp_positions: Vec::new(),
             block_variable_positions: Vec::new(),
+            transient_loads: Vec::new(),
+            transient_stores: Vec::new(),
         }
     }
 
-    fn has_reentrancy_risk(&self) -> bool {
+    /* An EIP-1153 mutex: a transient slot is read, set before the first external call, and
+     * written again after the last one. Calls inside that window cannot re-enter the function. */
+    fn transient_lock(&self) -> Option<(usize, usize)> {
+        let first_call = *self.external_call_positions.iter().min()?;
+        let last_call = *self.external_call_positions.iter().max()?;
+        self.transient_stores
+            .iter()
+            .filter(|(acquire, slot)| {
+                *acquire < first_call
+                    && self
+                        .transient_loads
+                        .iter()
+                        .any(|(read, read_slot)| read < acquire && read_slot == slot)
+            })
+            .find_map(|(acquire, slot)| {
+                self.transient_stores
+                    .iter()
+                    .find(|(release, release_slot)| *release > last_call && release_slot == slot)
+                    .map(|(release, _)| (*acquire, *release))
+            })
+    }
+
+    fn reentrant_pairs(&self) -> Vec<(usize, usize)> {
+        let lock = self.transient_lock();
+        let mut pairs = Vec::new();
         for &call_pos in &self.external_call_positions {
+            if lock.is_some_and(|(acquire, release)| acquire < call_pos && call_pos < release) {
+                continue;
+            }
             for &mod_pos in &self.state_modification_positions {
                 if call_pos < mod_pos {
-                    return true;
+                    pairs.push((call_pos, mod_pos));
                 }
             }
         }
-        false
+        pairs
+    }
+
+    fn has_reentrancy_risk(&self) -> bool {
+        !self.reentrant_pairs().is_empty()
     }
 
     fn has_security_issues(&self) -> bool {
@@ -141,7 +183,9 @@ impl SecurityAnalysis {
 pub struct AnnotatedIREmitter {
     base_emitter: ThalIREmitter,
     annotation_config: AnnotationConfig,
+    emitter_config: EmitterConfig,
     contracts: Vec<Contract>,
+    review: ReviewFile,
 }
 
 impl AnnotatedIREmitter {
@@ -149,7 +193,9 @@ impl AnnotatedIREmitter {
         Self {
             base_emitter: ThalIREmitter::new(contracts.clone()),
             annotation_config: AnnotationConfig::default(),
+            emitter_config: EmitterConfig::default(),
             contracts,
+            review: ReviewFile::default(),
         }
     }
 
@@ -158,6 +204,17 @@ impl AnnotatedIREmitter {
         self
     }
 
+    pub fn with_emitter_config(mut self, config: EmitterConfig) -> Self {
+        self.emitter_config = config;
+        self
+    }
+
+    /* Review notes to print under the function, block or instruction they target. */
+    pub fn with_review(mut self, review: ReviewFile) -> Self {
+        self.review = review;
+        self
+    }
+
     pub fn with_obfuscation(
         contracts: Vec<Contract>,
         obf_config: ObfuscationConfig,
@@ -171,16 +228,25 @@ impl AnnotatedIREmitter {
         let annotated = Self {
             base_emitter,
             annotation_config: ann_config,
+            emitter_config: EmitterConfig::default(),
             contracts: obfuscated_contracts,
+            review: ReviewFile::default(),
         };
 
         Ok((annotated, mapping))
     }
 
     pub fn emit_to_string(&self, with_types: bool) -> String {
+        let _span = tracing::info_span!(
+            "emit",
+            format = "annotated",
+            contracts = self.contracts.len()
+        )
+        .entered();
         let mut output = String::new();
 
         for contract in &self.contracts {
+            tracing::debug!(contract = %contract.name, "emitting contract");
             self.emit_contract(&mut output, contract, with_types);
         }
 
@@ -190,22 +256,43 @@ impl AnnotatedIREmitter {
     fn emit_contract(&self, output: &mut String, contract: &Contract, with_types: bool) {
         output.push_str(&format!("contract {} {{\n", contract.name));
 
+        if self.emitter_config.include_natspec {
+            if let Some(natspec) = &contract.metadata.natspec {
+                for line in natspec.lines() {
+                    output.push_str(&format!("  ; {}\n", line));
+                }
+            }
+        }
+
         if !contract.storage_layout.slots.is_empty() {
             output.push_str("\n  // Storage Layout\n");
             for var in &contract.storage_layout.slots {
-                output.push_str(&format!(
-                    "  slot {} = {}: {}\n",
-                    var.slot,
-                    var.name,
-                    IRFormatterBase::format_type(&var.var_type)
-                ));
+                if self.emitter_config.include_types {
+                    output.push_str(&format!(
+                        "  slot {} = {}: {}\n",
+                        var.slot,
+                        var.name,
+                        IRFormatterBase::format_type(&var.var_type)
+                    ));
+                } else {
+                    output.push_str(&format!("  slot {} = {}\n", var.slot, var.name));
+                }
             }
         }
 
         let mut ssa = SSAContext::new();
-        for (name, function) in &contract.functions {
+        let order = self.emitter_config.function_order;
+        let mut group = None;
+        for (name, function) in order.arrange(contract) {
+            if self.emitter_config.group_functions {
+                let current = order.group(function);
+                if let Some(header) = current.filter(|_| current != group) {
+                    output.push_str(&format!("\n  // {}\n", header));
+                }
+                group = current;
+            }
             output.push_str("\n");
-            self.emit_function(output, name, function, &mut ssa, with_types);
+            self.emit_function(output, contract, name, function, &mut ssa, with_types);
         }
 
         output.push_str("}\n");
@@ -214,6 +301,7 @@ impl AnnotatedIREmitter {
     fn emit_function(
         &self,
         output: &mut String,
+        contract: &Contract,
         name: &str,
         function: &Function,
         ssa: &mut SSAContext,
@@ -222,8 +310,17 @@ impl AnnotatedIREmitter {
         ssa.reset();
 
         let analysis = self.analyze_security(function);
+        let security_tags = self.emitter_config.include_security_tags;
 
-        if self.annotation_config.emit_function_headers {
+        if self.emitter_config.include_natspec {
+            if let Some(natspec) = &function.metadata.natspec {
+                for line in natspec.lines() {
+                    output.push_str(&format!("; {}\n", line));
+                }
+            }
+        }
+
+        if security_tags && self.annotation_config.emit_function_headers {
             output.push_str(&format!(
                 "; ### Function: {} ({})\n",
                 name,
@@ -262,7 +359,10 @@ impl AnnotatedIREmitter {
             }
         }
 
-        if self.annotation_config.emit_ordering_analysis && analysis.has_security_issues() {
+        if security_tags
+            && self.annotation_config.emit_ordering_analysis
+            && analysis.has_security_issues()
+        {
             self.emit_security_analysis_comment(output, &analysis);
         }
 
@@ -292,16 +392,17 @@ impl AnnotatedIREmitter {
             String::new()
         };
 
-        let visibility = IRFormatterBase::format_visibility(&function.visibility);
-        let mutability = IRFormatterBase::format_mutability(&function.mutability);
-
+        self.emit_review_notes(
+            output,
+            &stable_id::of_function(&contract.name, function),
+            "  ",
+        );
         output.push_str(&format!(
-            "  function %{}({}){} {} {} {{\n",
+            "  function %{}({}){} {} {{\n",
             name,
             param_types.join(", "),
             return_type,
-            visibility,
-            mutability
+            IRFormatterBase::format_function_attributes(function)
         ));
 
         if let Some(entry_block) = function.body.blocks.get(&function.body.entry_block) {
@@ -310,21 +411,49 @@ impl AnnotatedIREmitter {
                 if i > 0 {
                     output.push_str(", ");
                 }
-                output.push_str(&format!(
-                    "v{}: {}",
-                    param_vnums[i],
-                    IRFormatterBase::format_type(&param.param_type)
-                ));
+                if self.emitter_config.include_types {
+                    output.push_str(&format!(
+                        "v{}: {}",
+                        param_vnums[i],
+                        IRFormatterBase::format_type(&param.param_type)
+                    ));
+                } else {
+                    output.push_str(&format!("v{}", param_vnums[i]));
+                }
             }
             output.push_str("):\n");
 
+            let def_use = if self.emitter_config.include_def_use {
+                Some(DefUseChains::build(function))
+            } else {
+                None
+            };
+
             let mut position = 0;
-            self.emit_block_body(output, entry_block, ssa, &param_vnums, &mut position);
+            self.emit_block_body(
+                output,
+                contract,
+                function,
+                entry_block,
+                ssa,
+                &param_vnums,
+                &mut position,
+                def_use.as_ref(),
+            );
 
             for (block_id, block) in &function.body.blocks {
                 if block_id != &function.body.entry_block {
                     output.push_str(&format!("\n  block{}:\n", block.id.0));
-                    self.emit_block_body(output, block, ssa, &param_vnums, &mut position);
+                    self.emit_block_body(
+                        output,
+                        contract,
+                        function,
+                        block,
+                        ssa,
+                        &param_vnums,
+                        &mut position,
+                        def_use.as_ref(),
+                    );
                 }
             }
         }
@@ -332,15 +461,66 @@ impl AnnotatedIREmitter {
         output.push_str("  }\n");
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn emit_block_body(
         &self,
         output: &mut String,
+        contract: &Contract,
+        function: &Function,
         block: &BasicBlock,
         ssa: &mut SSAContext,
         param_vnums: &[u32],
         position: &mut usize,
+        def_use: Option<&DefUseChains>,
     ) {
-        for inst in &block.instructions {
+        let intrinsics = if self.annotation_config.condense_intrinsics {
+            intrinsics::recognize(function, block)
+        } else {
+            Vec::new()
+        };
+        let block_id = block.metadata.stable_id.as_deref();
+        if let Some(id) = block_id {
+            self.emit_review_notes(output, id, "    ");
+        }
+
+        for (index, inst) in block.instructions.iter().enumerate() {
+            if let Some(found) = intrinsics
+                .iter()
+                .find(|m| m.start <= index && index < m.end())
+            {
+                let _ = self.base_emitter.format_instruction(inst, ssa, param_vnums);
+                *position += 1;
+                if index + 1 < found.end() {
+                    continue;
+                }
+
+                output.push_str("    ");
+                if self.annotation_config.emit_position_markers {
+                    output.push_str(&format!("[{}-{}] ", *position - found.len, *position - 1));
+                }
+                if self.emitter_config.include_security_tags
+                    && self.annotation_config.emit_visual_cues
+                {
+                    let cue = match found.kind {
+                        IntrinsicKind::OnlyOwner => VisualCue::Checked,
+                        _ => VisualCue::ExternalCall,
+                    };
+                    output.push_str(&format!(
+                        "{} ",
+                        cue.format(self.annotation_config.use_ascii_cues)
+                    ));
+                }
+                output.push_str(&self.format_intrinsic(contract, found, ssa, param_vnums));
+                output.push('\n');
+                if let Some(id) = block_id {
+                    for covered in found.start..found.end() {
+                        let target = stable_id::instruction_id(id, covered);
+                        self.emit_review_notes(output, &target, "    ");
+                    }
+                }
+                continue;
+            }
+
             let visual_cue = self.get_visual_cue(inst);
 
             output.push_str("    ");
@@ -349,7 +529,8 @@ impl AnnotatedIREmitter {
                 output.push_str(&format!("[{}] ", position));
             }
 
-            if self.annotation_config.emit_visual_cues {
+            if self.emitter_config.include_security_tags && self.annotation_config.emit_visual_cues
+            {
                 if let Some(cue) = visual_cue {
                     output.push_str(&format!(
                         "{} ",
@@ -360,7 +541,28 @@ impl AnnotatedIREmitter {
 
             let inst_str = self.base_emitter.format_instruction(inst, ssa, param_vnums);
             output.push_str(&inst_str);
+
+            if self.emitter_config.include_source_mappings {
+                if let Some(location) = block.metadata.instruction_locations.get(&index) {
+                    output.push_str(&format!(
+                        "  ; @ {}:{}:{}",
+                        location.file, location.line, location.column
+                    ));
+                }
+            }
+
+            if let Some(chains) = def_use {
+                let defs = chains.get_inst_defs(block.id, index);
+                if !defs.is_empty() {
+                    let uses: usize = defs.iter().map(|d| chains.get_uses(*d).len()).sum();
+                    output.push_str(&format!("  ; uses: {}", uses));
+                }
+            }
             output.push('\n');
+            if let Some(id) = block_id {
+                let target = stable_id::instruction_id(id, index);
+                self.emit_review_notes(output, &target, "    ");
+            }
 
             *position += 1;
         }
@@ -370,6 +572,51 @@ impl AnnotatedIREmitter {
         output.push('\n');
     }
 
+    fn emit_review_notes(&self, output: &mut String, target: &str, indent: &str) {
+        for note in self.review.notes_for(target) {
+            output.push_str(&format!("{}; review {}\n", indent, note));
+        }
+    }
+
+    fn format_intrinsic(
+        &self,
+        contract: &Contract,
+        found: &IntrinsicMatch,
+        ssa: &mut SSAContext,
+        param_vnums: &[u32],
+    ) -> String {
+        let mut operands: Vec<String> = Vec::new();
+        if let Some(slot) = &found.slot {
+            operands.push(
+                match contract
+                    .storage_layout
+                    .slots
+                    .iter()
+                    .find(|var| &var.slot == slot)
+                {
+                    Some(var) => format!("@{}", var.name),
+                    None => format!("slot {}", slot),
+                },
+            );
+        }
+        operands.extend(
+            found
+                .operands
+                .iter()
+                .map(|v| self.base_emitter.format_value(v, ssa, param_vnums)),
+        );
+        if let Some(message) = &found.message {
+            operands.push(format!("\"{}\"", message));
+        }
+
+        format!(
+            "intrinsic.{} {}  ; condenses {} instructions",
+            found.kind.name(),
+            operands.join(", "),
+            found.len
+        )
+    }
+
     fn emit_terminator(
         &self,
         output: &mut String,
@@ -477,16 +724,19 @@ impl AnnotatedIREmitter {
             Instruction::Add { .. }
             | Instruction::Sub { .. }
             | Instruction::Mul { .. }
-            | Instruction::Div { .. } => Some(VisualCue::UncheckedArith),
+            | Instruction::Div { .. }
+            | Instruction::Sdiv { .. } => Some(VisualCue::UncheckedArith),
 
             Instruction::GetContext { var, .. } => match var {
                 ContextVariable::TxOrigin => Some(VisualCue::TxOrigin),
                 ContextVariable::BlockTimestamp => Some(VisualCue::BlockTimestamp),
                 ContextVariable::BlockNumber
                 | ContextVariable::BlockDifficulty
+                | ContextVariable::PrevRandao
                 | ContextVariable::BlockGasLimit
                 | ContextVariable::BlockCoinbase
-                | ContextVariable::BlockBaseFee => Some(VisualCue::BlockVariable),
+                | ContextVariable::BlockBaseFee
+                | ContextVariable::BlobBaseFee => Some(VisualCue::BlockVariable),
                 _ => None,
             },
             _ => None,
@@ -511,6 +761,18 @@ impl AnnotatedIREmitter {
                     Instruction::StorageStore { .. } | Instruction::MappingStore { .. } => {
                         analysis.state_modification_positions.push(position);
                     }
+                    Instruction::TransientLoad {
+                        key: StorageKey::Slot(slot),
+                        ..
+                    } => {
+                        analysis.transient_loads.push((position, slot.clone()));
+                    }
+                    Instruction::TransientStore {
+                        key: StorageKey::Slot(slot),
+                        ..
+                    } => {
+                        analysis.transient_stores.push((position, slot.clone()));
+                    }
 
                     Instruction::DelegateCall { .. } => {
                         analysis.delegatecall_positions.push(position);
@@ -521,7 +783,8 @@ impl AnnotatedIREmitter {
                     Instruction::Add { .. }
                     | Instruction::Sub { .. }
                     | Instruction::Mul { .. }
-                    | Instruction::Div { .. } => {
+                    | Instruction::Div { .. }
+                    | Instruction::Sdiv { .. } => {
                         analysis.unchecked_arith_positions.push(position);
                     }
                     Instruction::GetContext { var, .. } => match var {
@@ -533,9 +796,11 @@ impl AnnotatedIREmitter {
                         }
                         ContextVariable::BlockNumber
                         | ContextVariable::BlockDifficulty
+                        | ContextVariable::PrevRandao
                         | ContextVariable::BlockGasLimit
                         | ContextVariable::BlockCoinbase
-                        | ContextVariable::BlockBaseFee => {
+                        | ContextVariable::BlockBaseFee
+                        | ContextVariable::BlobBaseFee => {
                             analysis.block_variable_positions.push(position);
                         }
                         _ => {}
@@ -558,15 +823,17 @@ impl AnnotatedIREmitter {
         for &pos in &analysis.state_modification_positions {
             output.push_str(&format!("; - State modification at position [{}]\n", pos));
         }
-        for &call_pos in &analysis.external_call_positions {
-            for &mod_pos in &analysis.state_modification_positions {
-                if call_pos < mod_pos {
-                    output.push_str(&format!(
-                        "; - [{}] < [{}] → REENTRANCY RISK\n",
-                        call_pos, mod_pos
-                    ));
-                }
-            }
+        if let Some((acquire, release)) = analysis.transient_lock() {
+            output.push_str(&format!(
+                "; - Transient reentrancy lock held [{}]..[{}]\n",
+                acquire, release
+            ));
+        }
+        for (call_pos, mod_pos) in analysis.reentrant_pairs() {
+            output.push_str(&format!(
+                "; - [{}] < [{}] → REENTRANCY RISK\n",
+                call_pos, mod_pos
+            ));
         }
 
         if !analysis.tx_origin_positions.is_empty() {
@@ -682,6 +949,20 @@ mod tests {
         assert!(analysis.has_security_issues());
     }
 
+    #[test]
+    fn test_transient_lock_suppresses_reentrancy() {
+        let mut analysis = SecurityAnalysis::new();
+        analysis.transient_loads.push((0, BigUint::from(7u32)));
+        analysis.transient_stores.push((1, BigUint::from(7u32)));
+        analysis.external_call_positions.push(2);
+        analysis.state_modification_positions.push(3);
+        assert!(analysis.has_reentrancy_risk());
+
+        analysis.transient_stores.push((4, BigUint::from(7u32)));
+        assert_eq!(analysis.transient_lock(), Some((1, 4)));
+        assert!(!analysis.has_reentrancy_risk());
+    }
+
     #[test]
     fn test_security_analysis_safe() {
         let mut analysis = SecurityAnalysis::new();
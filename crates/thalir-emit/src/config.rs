@@ -1,4 +1,9 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thalir_core::{
+    contract::Contract,
+    function::{Function, Visibility},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmitterConfig {
@@ -7,9 +12,26 @@ pub struct EmitterConfig {
     pub max_line_width: Option<usize>,
     pub include_source_mappings: bool,
     pub include_types: bool,
+    #[serde(default = "default_true")]
+    pub include_security_tags: bool,
+    #[serde(default)]
+    pub include_def_use: bool,
+    #[serde(default)]
+    pub include_natspec: bool,
+    #[serde(default)]
+    pub include_summaries: bool,
+    #[serde(default)]
+    pub function_order: FunctionOrder,
+    /* A `// <group>` line before each run of functions the order puts together. */
+    #[serde(default)]
+    pub group_functions: bool,
     pub verbosity: VerbosityLevel,
 }
 
+fn default_true() -> bool {
+    true
+}
+
 impl Default for EmitterConfig {
     fn default() -> Self {
         Self {
@@ -18,11 +40,165 @@ impl Default for EmitterConfig {
             max_line_width: Some(120),
             include_source_mappings: false,
             include_types: true,
+            include_security_tags: true,
+            include_def_use: false,
+            include_natspec: false,
+            include_summaries: false,
+            function_order: FunctionOrder::Source,
+            group_functions: false,
             verbosity: VerbosityLevel::Normal,
         }
     }
 }
 
+impl EmitterConfig {
+    pub fn for_profile(profile: EmitterProfile) -> Self {
+        let verbosity = profile.verbosity();
+        Self {
+            include_source_mappings: verbosity.should_print_source_mappings(),
+            include_types: verbosity.should_print_types(),
+            include_security_tags: !matches!(profile, EmitterProfile::Minimal),
+            include_def_use: verbosity.should_print_ids(),
+            include_natspec: !matches!(profile, EmitterProfile::Minimal),
+            verbosity,
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmitterProfile {
+    Minimal,
+    Audit,
+    Debug,
+}
+
+impl EmitterProfile {
+    pub fn verbosity(&self) -> VerbosityLevel {
+        match self {
+            EmitterProfile::Minimal => VerbosityLevel::Quiet,
+            EmitterProfile::Audit => VerbosityLevel::Verbose,
+            EmitterProfile::Debug => VerbosityLevel::Debug,
+        }
+    }
+}
+
+impl std::str::FromStr for EmitterProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "minimal" => Ok(EmitterProfile::Minimal),
+            "audit" => Ok(EmitterProfile::Audit),
+            "debug" => Ok(EmitterProfile::Debug),
+            other => Err(format!("unknown emitter profile: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageNaming {
+    #[default]
+    Slots,
+    Comments,
+    Symbolic,
+}
+
+impl std::str::FromStr for StorageNaming {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "slots" => Ok(StorageNaming::Slots),
+            "comments" => Ok(StorageNaming::Comments),
+            "symbolic" => Ok(StorageNaming::Symbolic),
+            other => Err(format!("unknown storage naming: {}", other)),
+        }
+    }
+}
+
+/* The order functions are printed in. Declaration order rarely matches review order: an audit
+ * walks the external surface first, and comparing two builds is easiest by selector. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FunctionOrder {
+    #[default]
+    Source,
+    Alphabetical,
+    Visibility,
+    Selector,
+}
+
+impl FunctionOrder {
+    /* Sorting is stable, so functions that tie keep their declaration order. */
+    pub fn arrange<'c>(&self, contract: &'c Contract) -> Vec<(&'c String, &'c Function)> {
+        let mut functions: Vec<(&String, &Function)> = contract.functions.iter().collect();
+        match self {
+            FunctionOrder::Source => {}
+            FunctionOrder::Alphabetical => functions.sort_by_key(|&(name, _)| name),
+            FunctionOrder::Visibility => {
+                functions.sort_by_key(|(_, function)| visibility_rank(function))
+            }
+            FunctionOrder::Selector => {
+                let selectors: HashMap<String, u32> = contract
+                    .entry_points()
+                    .into_iter()
+                    .filter_map(|entry| Some((entry.name, entry.selector?)))
+                    .collect();
+                functions.sort_by_key(|&(name, _)| match selectors.get(name) {
+                    Some(selector) => (0, *selector, ""),
+                    None => (1, 0, name.as_str()),
+                });
+            }
+        }
+        functions
+    }
+
+    /* The header of the group `function` falls in, for orders that form groups. */
+    pub fn group(&self, function: &Function) -> Option<&'static str> {
+        match self {
+            FunctionOrder::Source | FunctionOrder::Alphabetical => None,
+            FunctionOrder::Visibility => Some(match visibility_rank(function) {
+                0 => "constructor",
+                1 => "external",
+                2 => "public",
+                3 => "internal",
+                _ => "private",
+            }),
+            FunctionOrder::Selector if function.is_entry_point() => Some("dispatched"),
+            FunctionOrder::Selector => Some("not dispatched"),
+        }
+    }
+}
+
+fn visibility_rank(function: &Function) -> u8 {
+    if function.metadata.is_constructor {
+        return 0;
+    }
+    match function.visibility {
+        Visibility::External => 1,
+        Visibility::Public => 2,
+        Visibility::Internal => 3,
+        Visibility::Private => 4,
+    }
+}
+
+impl std::str::FromStr for FunctionOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "source" => Ok(FunctionOrder::Source),
+            "alphabetical" => Ok(FunctionOrder::Alphabetical),
+            "visibility" => Ok(FunctionOrder::Visibility),
+            "selector" => Ok(FunctionOrder::Selector),
+            other => Err(format!("unknown function order: {}", other)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum IndentStyle {
     Spaces(usize),
@@ -0,0 +1,590 @@
+use crate::thalir_emitter::{SSAContext, ThalIREmitter};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use thalir_core::{
+    analysis::{AddressBook, ControlFlowGraph, Finding, Severity},
+    block::BlockId,
+    contract::Contract,
+    function::Function,
+};
+
+const NODE_WIDTH: usize = 120;
+const NODE_HEIGHT: usize = 36;
+const COLUMN_GAP: usize = 30;
+const ROW_GAP: usize = 40;
+const EXCERPT_CONTEXT: usize = 2;
+
+const STYLE: &str = "\
+body{font-family:sans-serif;margin:2em;color:#222}\
+pre{background:#f6f8fa;padding:0.5em;overflow-x:auto}\
+table{border-collapse:collapse}td,th{border:1px solid #ccc;padding:4px 8px;text-align:left}\
+.ir span{display:block}.ir .flagged{background:#ffe0e0}\
+.ir .block{font-weight:bold;color:#0550ae}\
+.sev-critical,.sev-high{color:#b00020;font-weight:bold}.sev-medium{color:#b35c00}\
+.sev-low{color:#665c00}.sev-info{color:#555}\
+.excerpt .hit{background:#fff3b0}\
+svg a rect{fill:#eef3ff;stroke:#0550ae}svg a.flagged rect{fill:#ffe0e0;stroke:#b00020}\
+svg line{stroke:#555}svg line.back{stroke-dasharray:4 3}";
+
+/* Bundles findings, the IR of every function they touch, a CFG drawing per function and source
+ * excerpts into one HTML file with no external assets. Findings link to the instruction they
+ * point at, CFG nodes link to their block in the listing, and each flagged instruction links
+ * back to its findings. */
+pub struct HtmlReportEmitter {
+    contracts: Vec<Contract>,
+    findings: Vec<Finding>,
+    formatter: ThalIREmitter,
+    title: String,
+    all_functions: bool,
+    address_book: AddressBook,
+}
+
+impl HtmlReportEmitter {
+    pub fn new(contracts: Vec<Contract>, findings: Vec<Finding>) -> Self {
+        Self {
+            contracts,
+            findings,
+            formatter: ThalIREmitter::new(Vec::new()),
+            title: "ThalIR report".to_string(),
+            all_functions: false,
+            address_book: AddressBook::default(),
+        }
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /* List every function, not only those with findings. */
+    pub fn with_all_functions(mut self, enabled: bool) -> Self {
+        self.all_functions = enabled;
+        self
+    }
+
+    /* List the hardcoded addresses after the findings. An empty book adds nothing. */
+    pub fn with_address_book(mut self, book: AddressBook) -> Self {
+        self.address_book = book;
+        self
+    }
+
+    pub fn emit_to_string(&self) -> String {
+        let _span = tracing::info_span!(
+            "emit",
+            format = "html",
+            contracts = self.contracts.len(),
+            findings = self.findings.len()
+        )
+        .entered();
+
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title>\
+             <style>{}</style></head><body>\n<h1>{}</h1>\n",
+            escape(&self.title),
+            STYLE,
+            escape(&self.title)
+        );
+        self.write_summary(&mut html);
+        self.write_address_book(&mut html);
+        for contract in &self.contracts {
+            self.write_contract(&mut html, contract);
+        }
+        html.push_str("</body></html>\n");
+        html
+    }
+
+    fn write_summary(&self, html: &mut String) {
+        let mut counts: BTreeMap<std::cmp::Reverse<Severity>, usize> = BTreeMap::new();
+        for finding in &self.findings {
+            *counts
+                .entry(std::cmp::Reverse(finding.severity))
+                .or_default() += 1;
+        }
+        let totals: Vec<String> = counts
+            .iter()
+            .map(|(severity, count)| format!("{} {}", count, severity.0))
+            .collect();
+        let _ = writeln!(
+            html,
+            "<p>{} finding(s) across {} contract(s){}</p>",
+            self.findings.len(),
+            self.contracts.len(),
+            if totals.is_empty() {
+                String::new()
+            } else {
+                format!(": {}", totals.join(", "))
+            }
+        );
+        if self.findings.is_empty() {
+            return;
+        }
+
+        html.push_str(
+            "<table><tr><th>#</th><th>Severity</th><th>Detector</th><th>Location</th>\
+             <th>Message</th></tr>\n",
+        );
+        for (number, finding) in self.findings.iter().enumerate() {
+            let target = match &finding.location {
+                Some(location) => instruction_anchor(
+                    &finding.contract,
+                    &finding.function,
+                    location.block,
+                    location.index,
+                ),
+                None => function_anchor(&finding.contract, &finding.function),
+            };
+            let place = match &finding.location {
+                Some(location) => format!(
+                    "{}::{} ({}:{})",
+                    finding.contract, finding.function, location.block, location.index
+                ),
+                None => format!("{}::{}", finding.contract, finding.function),
+            };
+            let _ = writeln!(
+                html,
+                "<tr id=\"{}\"><td>{}</td><td class=\"sev-{}\">{}</td><td>{}</td>\
+                 <td><a href=\"#{}\">{}</a></td><td>{}</td></tr>",
+                finding_anchor(number),
+                number + 1,
+                finding.severity,
+                finding.severity,
+                escape(&finding.detector),
+                target,
+                escape(&place),
+                escape(&finding.message)
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    fn write_address_book(&self, html: &mut String) {
+        if self.address_book.is_empty() {
+            return;
+        }
+        html.push_str(
+            "<h2 id=\"addresses\">Hardcoded addresses</h2>\n<table><tr><th>Address</th>\
+             <th>Label</th><th>Constants</th><th>Used in</th></tr>\n",
+        );
+        for entry in &self.address_book.entries {
+            let uses: Vec<String> = entry
+                .uses
+                .iter()
+                .map(|used| {
+                    let place = format!(
+                        "{}::{} ({}:{})",
+                        used.contract, used.function, used.location.block, used.location.index
+                    );
+                    match &used.constant {
+                        Some(constant) => format!("{} via {}", escape(&place), escape(constant)),
+                        None => escape(&place),
+                    }
+                })
+                .collect();
+            let _ = writeln!(
+                html,
+                "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                entry.address,
+                escape(entry.label.as_deref().unwrap_or("")),
+                escape(&entry.constants.join(", ")),
+                uses.join("<br>")
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    fn write_contract(&self, html: &mut String, contract: &Contract) {
+        let functions: Vec<(&String, &Function)> = contract
+            .functions
+            .iter()
+            .filter(|(_, function)| {
+                self.all_functions || !self.function_findings(contract, function).is_empty()
+            })
+            .collect();
+        if functions.is_empty() {
+            return;
+        }
+
+        let _ = writeln!(
+            html,
+            "<h2 id=\"{}\">contract {}</h2>",
+            anchor(&[contract.name.as_str()]),
+            escape(&contract.name)
+        );
+        let mut ssa = SSAContext::new();
+        for (name, function) in functions {
+            self.write_function(html, contract, name, function, &mut ssa);
+        }
+    }
+
+    fn function_findings(&self, contract: &Contract, function: &Function) -> Vec<usize> {
+        self.findings
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.contract == contract.name && f.function == function.name())
+            .map(|(number, _)| number)
+            .collect()
+    }
+
+    fn write_function(
+        &self,
+        html: &mut String,
+        contract: &Contract,
+        name: &str,
+        function: &Function,
+        ssa: &mut SSAContext,
+    ) {
+        let numbers = self.function_findings(contract, function);
+        let mut at: HashMap<(BlockId, usize), Vec<usize>> = HashMap::new();
+        for &number in &numbers {
+            if let Some(location) = &self.findings[number].location {
+                at.entry((location.block, location.index))
+                    .or_default()
+                    .push(number);
+            }
+        }
+
+        let _ = writeln!(
+            html,
+            "<h3 id=\"{}\">function {}</h3>",
+            function_anchor(&contract.name, function.name()),
+            escape(name)
+        );
+        html.push_str(&cfg_svg(contract, function, &at));
+
+        ssa.reset();
+        let param_vnums: Vec<u32> = (0..function.signature.params.len())
+            .map(|_| ssa.allocate_new())
+            .collect();
+
+        html.push_str("<pre class=\"ir\">");
+        for (&block_id, block) in &function.body.blocks {
+            let _ = write!(
+                html,
+                "<span id=\"{}\" class=\"block\">{}:</span>",
+                block_anchor(&contract.name, function.name(), block_id),
+                block_id
+            );
+            let mut body = String::new();
+            self.formatter
+                .print_block_body(&mut body, contract, block, ssa, &param_vnums);
+            for (index, line) in body.lines().enumerate() {
+                let is_instruction = index < block.instructions.len();
+                let hits = if is_instruction {
+                    at.get(&(block_id, index))
+                } else {
+                    None
+                };
+                let id = if is_instruction {
+                    format!(
+                        " id=\"{}\"",
+                        instruction_anchor(&contract.name, function.name(), block_id, index)
+                    )
+                } else {
+                    String::new()
+                };
+                let class = if hits.is_some() {
+                    " class=\"flagged\""
+                } else {
+                    ""
+                };
+                let links: String = hits
+                    .into_iter()
+                    .flatten()
+                    .map(|number| {
+                        format!(
+                            "  <a href=\"#{}\">[#{}]</a>",
+                            finding_anchor(*number),
+                            number + 1
+                        )
+                    })
+                    .collect();
+                let _ = write!(
+                    html,
+                    "<span{}{}>{}{}</span>",
+                    id,
+                    class,
+                    escape(line),
+                    links
+                );
+            }
+        }
+        html.push_str("</pre>\n");
+
+        for number in numbers {
+            let finding = &self.findings[number];
+            if let Some(excerpt) = source_excerpt(contract, function, finding) {
+                let _ = writeln!(
+                    html,
+                    "<p><a href=\"#{}\">[#{}]</a> {}</p>{}",
+                    finding_anchor(number),
+                    number + 1,
+                    escape(&finding.message),
+                    excerpt
+                );
+            }
+        }
+    }
+}
+
+/* Blocks are laid out in rows by their distance from the entry. Back edges are dashed. */
+fn cfg_svg(
+    contract: &Contract,
+    function: &Function,
+    flagged: &HashMap<(BlockId, usize), Vec<usize>>,
+) -> String {
+    let cfg = ControlFlowGraph::build(function);
+    let mut depth: HashMap<BlockId, usize> = HashMap::new();
+    let mut queue = std::collections::VecDeque::from([cfg.entry()]);
+    depth.insert(cfg.entry(), 0);
+    while let Some(block) = queue.pop_front() {
+        for &next in cfg.successors(block) {
+            if !depth.contains_key(&next) {
+                depth.insert(next, depth[&block] + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    let unreachable_row = depth.values().max().map_or(0, |max| max + 1);
+
+    let mut rows: BTreeMap<usize, Vec<BlockId>> = BTreeMap::new();
+    for &block in function.body.blocks.keys() {
+        let row = depth.get(&block).copied().unwrap_or(unreachable_row);
+        rows.entry(row).or_default().push(block);
+    }
+    let mut position: HashMap<BlockId, (usize, usize)> = HashMap::new();
+    for (row, blocks) in &rows {
+        for (column, &block) in blocks.iter().enumerate() {
+            position.insert(
+                block,
+                (
+                    COLUMN_GAP + column * (NODE_WIDTH + COLUMN_GAP),
+                    ROW_GAP / 2 + row * (NODE_HEIGHT + ROW_GAP),
+                ),
+            );
+        }
+    }
+    let columns = rows.values().map(Vec::len).max().unwrap_or(1);
+    let width = COLUMN_GAP + columns * (NODE_WIDTH + COLUMN_GAP);
+    let height = ROW_GAP + rows.len() * (NODE_HEIGHT + ROW_GAP);
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         font-family=\"monospace\" font-size=\"12\">\
+         <defs><marker id=\"arrow\" markerWidth=\"8\" markerHeight=\"8\" refX=\"8\" refY=\"4\" \
+         orient=\"auto\"><path d=\"M0,0 L8,4 L0,8 z\" fill=\"#555\"/></marker></defs>",
+        width, height
+    );
+    for (&block, &(x, y)) in &position {
+        for next in cfg.successors(block) {
+            let Some(&(nx, ny)) = position.get(next) else {
+                continue;
+            };
+            let class = if cfg.is_back_edge(block, *next) {
+                " class=\"back\""
+            } else {
+                ""
+            };
+            let _ = write!(
+                svg,
+                "<line{} x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" marker-end=\"url(#arrow)\"/>",
+                class,
+                x + NODE_WIDTH / 2,
+                y + NODE_HEIGHT,
+                nx + NODE_WIDTH / 2,
+                ny
+            );
+        }
+    }
+    for (&block, &(x, y)) in &position {
+        let hit = flagged.keys().any(|(b, _)| *b == block);
+        let instructions = function.body.blocks[&block].instructions.len();
+        let _ = write!(
+            svg,
+            "<a href=\"#{}\"{}><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\"/>\
+             <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{} ({})</text></a>",
+            block_anchor(&contract.name, function.name(), block),
+            if hit { " class=\"flagged\"" } else { "" },
+            x,
+            y,
+            NODE_WIDTH,
+            NODE_HEIGHT,
+            x + NODE_WIDTH / 2,
+            y + NODE_HEIGHT / 2 + 4,
+            block,
+            instructions
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/* Lines around the instruction's recorded source location, from the source kept on the
+ * contract. */
+fn source_excerpt(contract: &Contract, function: &Function, finding: &Finding) -> Option<String> {
+    let location = finding.location.as_ref()?;
+    let source = contract.metadata.source_code.as_ref()?;
+    let line = function
+        .body
+        .blocks
+        .get(&location.block)?
+        .metadata
+        .get_location(location.index)?
+        .line as usize;
+    let lines: Vec<&str> = source.lines().collect();
+    if line == 0 || line > lines.len() {
+        return None;
+    }
+
+    let first = line.saturating_sub(EXCERPT_CONTEXT).max(1);
+    let last = (line + EXCERPT_CONTEXT).min(lines.len());
+    let mut excerpt = String::from("<pre class=\"excerpt\">");
+    for number in first..=last {
+        let class = if number == line { " class=\"hit\"" } else { "" };
+        let _ = writeln!(
+            excerpt,
+            "<span{}>{:>5} | {}</span>",
+            class,
+            number,
+            escape(lines[number - 1])
+        );
+    }
+    excerpt.push_str("</pre>");
+    Some(excerpt)
+}
+
+fn anchor(parts: &[&str]) -> String {
+    parts
+        .iter()
+        .map(|part| {
+            part.chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn finding_anchor(number: usize) -> String {
+    format!("finding-{}", number + 1)
+}
+
+fn function_anchor(contract: &str, function: &str) -> String {
+    format!("fn-{}", anchor(&[contract, function]))
+}
+
+fn block_anchor(contract: &str, function: &str, block: BlockId) -> String {
+    format!("{}-{}", function_anchor(contract, function), block)
+}
+
+fn instruction_anchor(contract: &str, function: &str, block: BlockId, index: usize) -> String {
+    format!("{}-{}", block_anchor(contract, function, block), index)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use thalir_core::builder::IRBuilder;
+    use thalir_core::metadata::InstructionLocation;
+    use thalir_core::types::Type;
+
+    #[test]
+    fn test_report_links_findings_blocks_and_instructions() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Vault");
+        let mut func_builder = contract_builder.function("split");
+        func_builder.param("parts", Type::Uint(256));
+        let parts = func_builder.get_param(0);
+
+        let entry = func_builder.entry_block().block_id();
+        let ok = func_builder.create_block_id();
+        let bad = func_builder.create_block_id();
+
+        let mut entry_builder = func_builder.switch_to_block(entry).unwrap();
+        let zero = entry_builder.constant_uint(0, 256);
+        let positive = entry_builder.gt(parts.clone(), zero);
+        entry_builder.branch(positive, ok, bad).unwrap();
+
+        let mut ok_builder = func_builder.switch_to_block(ok).unwrap();
+        let total = ok_builder.constant_uint(100, 256);
+        let share = ok_builder.div(total, parts, Type::Uint(256));
+        ok_builder.return_value(share).unwrap();
+
+        let mut bad_builder = func_builder.switch_to_block(bad).unwrap();
+        bad_builder.revert("<zero>").unwrap();
+
+        func_builder.build().unwrap();
+        let contract = contract_builder.build().unwrap();
+
+        let index = contract.functions["split"].body.blocks[&ok]
+            .instructions
+            .len()
+            - 1;
+        let finding = Finding::new(
+            "division-guard",
+            Severity::Medium,
+            "Vault",
+            "split",
+            "Divides by <parts>".to_string(),
+        )
+        .at(InstructionLocation { block: ok, index });
+
+        let html = HtmlReportEmitter::new(vec![contract], vec![finding]).emit_to_string();
+
+        let target = format!("fn-Vault-split-{}-{}", ok, index);
+        assert!(html.contains(&format!("href=\"#{}\"", target)));
+        assert!(html.contains(&format!("id=\"{}\" class=\"flagged\"", target)));
+        assert!(html.contains(&format!("<a href=\"#fn-Vault-split-{}\">", bad)));
+        assert!(html.contains("<svg"));
+        assert!(html.contains("Divides by &lt;parts&gt;"));
+        assert!(html.contains("href=\"#finding-1\""));
+        assert!(!html.contains("<link") && !html.contains("<script"));
+    }
+
+    #[test]
+    fn test_report_lists_the_address_book() {
+        use thalir_core::analysis::{AddressEntry, AddressUse};
+
+        let empty = HtmlReportEmitter::new(Vec::new(), Vec::new()).emit_to_string();
+        assert!(!empty.contains("Hardcoded addresses"));
+
+        let book = AddressBook {
+            entries: vec![AddressEntry {
+                address: format!("0x{}", "7a".repeat(20)),
+                label: Some("Router <v2>".to_string()),
+                constants: vec!["Vault.ROUTER".to_string()],
+                uses: vec![AddressUse {
+                    contract: "Vault".to_string(),
+                    function: "swap".to_string(),
+                    location: InstructionLocation {
+                        block: BlockId(0),
+                        index: 2,
+                    },
+                    constant: Some("ROUTER".to_string()),
+                }],
+            }],
+        };
+        let html = HtmlReportEmitter::new(Vec::new(), Vec::new())
+            .with_address_book(book)
+            .emit_to_string();
+        assert!(html.contains(&format!("<code>0x{}</code>", "7a".repeat(20))));
+        assert!(html.contains("Router &lt;v2&gt;"));
+        assert!(html.contains("Vault::swap (block0:2) via ROUTER"));
+    }
+}
@@ -0,0 +1,261 @@
+/*! The external surface of a contract, as a Solidity `interface` and as ABI JSON.
+ *
+ * Only what a caller can reach is listed: public and external functions, fallback, receive and
+ * the declared events. The constructor goes in the ABI but not in the interface, which cannot
+ * declare one. Getters of public state variables are not functions in the IR, so they are missing
+ * from both. Structs are known to the IR only by id, so an entry taking or returning one is left
+ * out of the ABI and noted with a comment in the interface instead of being guessed at.
+ */
+
+use serde_json::{json, Map, Value as Json};
+use thalir_core::{
+    abi::{self, AbiType},
+    contract::{Contract, EventDefinition},
+    function::{Function, Mutability},
+    hash,
+    types::{Type, TypeRegistry},
+};
+
+pub struct InterfaceEmitter {
+    contracts: Vec<Contract>,
+}
+
+impl InterfaceEmitter {
+    pub fn new(contracts: Vec<Contract>) -> Self {
+        Self { contracts }
+    }
+
+    /* One `interface I<Name>` per contract. Each function carries its selector in a trailing
+     * comment, so diffing two versions shows a changed signature even where the text barely
+     * moves. */
+    pub fn emit_to_string(&self) -> String {
+        let mut out =
+            String::from("// SPDX-License-Identifier: UNLICENSED\npragma solidity ^0.8.0;\n");
+        for contract in &self.contracts {
+            out.push('\n');
+            out.push_str(&interface(contract));
+        }
+        out
+    }
+
+    /* ABI JSON in solc's format, one array per contract keyed by contract name. */
+    pub fn abi(&self) -> Json {
+        Json::Object(
+            self.contracts
+                .iter()
+                .map(|contract| (contract.name.clone(), Json::Array(abi_entries(contract))))
+                .collect::<Map<_, _>>(),
+        )
+    }
+}
+
+fn interface(contract: &Contract) -> String {
+    let mut out = format!("interface I{} {{\n", contract.name);
+    for event in &contract.events {
+        match event_params(event) {
+            Some(params) => out.push_str(&format!(
+                "    event {}({}){};\n",
+                event.name,
+                params
+                    .iter()
+                    .map(|(name, ty, indexed)| {
+                        declaration(
+                            &ty.to_string(),
+                            if *indexed { " indexed" } else { "" },
+                            name,
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                if event.anonymous { " anonymous" } else { "" }
+            )),
+            None => out.push_str(&format!(
+                "    // event {} has parameters with no ABI type\n",
+                event.name
+            )),
+        }
+    }
+    if !contract.events.is_empty() && contract.functions.values().any(Function::is_entry_point) {
+        out.push('\n');
+    }
+
+    for function in contract.functions.values().filter(|f| f.is_entry_point()) {
+        let mutability = match function.mutability {
+            Mutability::NonPayable if !function.signature.is_payable => "",
+            Mutability::Pure => " pure",
+            Mutability::View => " view",
+            _ => " payable",
+        };
+        if function.metadata.is_fallback {
+            out.push_str(&format!("    fallback() external{};\n", mutability));
+            continue;
+        }
+        if function.metadata.is_receive {
+            out.push_str("    receive() external payable;\n");
+            continue;
+        }
+        let (Some(inputs), Some(outputs)) = (params(function), returns(function)) else {
+            out.push_str(&format!(
+                "    // function {} has parameters or returns with no ABI type\n",
+                function.name()
+            ));
+            continue;
+        };
+
+        let types: Vec<AbiType> = inputs.iter().map(|(_, ty)| ty.clone()).collect();
+        let selector = hash::selector_u32(&abi::signature(function.name(), &types));
+        let returns = if outputs.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " returns ({})",
+                outputs
+                    .iter()
+                    .map(|ty| declaration(&ty.to_string(), location(ty, " memory"), ""))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        };
+        out.push_str(&format!(
+            "    function {}({}) external{}{}; // 0x{:08x}\n",
+            function.name(),
+            inputs
+                .iter()
+                .map(|(name, ty)| declaration(&ty.to_string(), location(ty, " calldata"), name))
+                .collect::<Vec<_>>()
+                .join(", "),
+            mutability,
+            returns,
+            selector
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn abi_entries(contract: &Contract) -> Vec<Json> {
+    let mut entries = Vec::new();
+    for function in contract.functions.values() {
+        if !(function.is_entry_point() || function.metadata.is_constructor) {
+            continue;
+        }
+        let mutability = match function.mutability {
+            _ if function.is_payable() => "payable",
+            Mutability::Pure => "pure",
+            Mutability::View => "view",
+            _ => "nonpayable",
+        };
+        if function.metadata.is_fallback || function.metadata.is_receive {
+            let kind = if function.metadata.is_receive {
+                "receive"
+            } else {
+                "fallback"
+            };
+            entries.push(json!({ "type": kind, "stateMutability": mutability }));
+            continue;
+        }
+        let (Some(inputs), Some(outputs)) = (params(function), returns(function)) else {
+            continue;
+        };
+
+        let inputs: Vec<Json> = inputs
+            .iter()
+            .map(|(name, ty)| json!({ "name": name, "type": ty.to_string() }))
+            .collect();
+        if function.metadata.is_constructor {
+            entries.push(json!({
+                "type": "constructor",
+                "inputs": inputs,
+                "stateMutability": mutability,
+            }));
+            continue;
+        }
+        let outputs: Vec<Json> = outputs
+            .iter()
+            .map(|ty| json!({ "name": "", "type": ty.to_string() }))
+            .collect();
+        entries.push(json!({
+            "type": "function",
+            "name": function.name(),
+            "inputs": inputs,
+            "outputs": outputs,
+            "stateMutability": mutability,
+        }));
+    }
+
+    for event in &contract.events {
+        let Some(params) = event_params(event) else {
+            continue;
+        };
+        entries.push(json!({
+            "type": "event",
+            "name": event.name,
+            "inputs": params
+                .iter()
+                .map(|(name, ty, indexed)| {
+                    json!({ "name": name, "type": ty.to_string(), "indexed": indexed })
+                })
+                .collect::<Vec<_>>(),
+            "anonymous": event.anonymous,
+        }));
+    }
+    entries
+}
+
+/* Without the contract's type registry structs have no fields, so `from_ir` gives up on them, as
+ * it does on mappings and function types. */
+fn abi_type(ty: &Type) -> Option<AbiType> {
+    AbiType::from_ir(ty, &TypeRegistry::new())
+}
+
+fn params(function: &Function) -> Option<Vec<(&str, AbiType)>> {
+    function
+        .signature
+        .params
+        .iter()
+        .map(|param| Some((param.name.as_str(), abi_type(&param.param_type)?)))
+        .collect()
+}
+
+fn returns(function: &Function) -> Option<Vec<AbiType>> {
+    function.signature.returns.iter().map(abi_type).collect()
+}
+
+fn event_params(event: &EventDefinition) -> Option<Vec<(&str, AbiType, bool)>> {
+    event
+        .parameters
+        .iter()
+        .map(|param| {
+            Some((
+                param.name.as_str(),
+                abi_type(&param.param_type)?,
+                param.indexed,
+            ))
+        })
+        .collect()
+}
+
+/* Dynamic types need a data location in a function signature; value types must not have one. */
+fn location(ty: &AbiType, location: &'static str) -> &'static str {
+    let by_reference = matches!(
+        ty,
+        AbiType::Bytes
+            | AbiType::String
+            | AbiType::Array(_)
+            | AbiType::FixedArray(_, _)
+            | AbiType::Tuple(_)
+    );
+    if by_reference {
+        location
+    } else {
+        ""
+    }
+}
+
+fn declaration(ty: &str, qualifier: &str, name: &str) -> String {
+    if name.is_empty() {
+        format!("{}{}", ty, qualifier)
+    } else {
+        format!("{}{} {}", ty, qualifier, name)
+    }
+}
@@ -0,0 +1,287 @@
+use num_bigint::BigUint;
+use std::collections::HashSet;
+use thalir_core::{
+    block::BasicBlock,
+    function::Function,
+    instructions::{CallTarget, ContextVariable, Instruction, StorageKey},
+    values::{Constant, Value},
+};
+
+const TRANSFER_SELECTOR: u32 = 0xa905_9cbb;
+const TRANSFER_FROM_SELECTOR: u32 = 0x23b8_72dd;
+const APPROVE_SELECTOR: u32 = 0x095e_a7b3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntrinsicKind {
+    OnlyOwner,
+    SafeTransfer,
+    SafeTransferFrom,
+    SafeApprove,
+    CheckedCall,
+}
+
+impl IntrinsicKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntrinsicKind::OnlyOwner => "only_owner",
+            IntrinsicKind::SafeTransfer => "safe_transfer",
+            IntrinsicKind::SafeTransferFrom => "safe_transfer_from",
+            IntrinsicKind::SafeApprove => "safe_approve",
+            IntrinsicKind::CheckedCall => "checked_call",
+        }
+    }
+
+    fn for_selector(selector: &Value) -> Self {
+        let selector = match selector {
+            Value::Constant(Constant::Uint(n, _)) => n,
+            _ => return IntrinsicKind::CheckedCall,
+        };
+        if *selector == BigUint::from(TRANSFER_SELECTOR) {
+            IntrinsicKind::SafeTransfer
+        } else if *selector == BigUint::from(TRANSFER_FROM_SELECTOR) {
+            IntrinsicKind::SafeTransferFrom
+        } else if *selector == BigUint::from(APPROVE_SELECTOR) {
+            IntrinsicKind::SafeApprove
+        } else {
+            IntrinsicKind::CheckedCall
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IntrinsicMatch {
+    pub kind: IntrinsicKind,
+    pub start: usize,
+    pub len: usize,
+    pub operands: Vec<Value>,
+    pub slot: Option<BigUint>,
+    pub message: Option<String>,
+}
+
+impl IntrinsicMatch {
+    pub fn end(&self) -> usize {
+        self.start + self.len
+    }
+}
+
+/* Regions of `block` that condense to one intrinsic line. A region is skipped when a value it
+ * defines is read outside it, since the condensed line would hide that value's definition. */
+pub fn recognize(function: &Function, block: &BasicBlock) -> Vec<IntrinsicMatch> {
+    recognize_block(block)
+        .into_iter()
+        .filter(|found| !hides_live_value(function, block, found))
+        .collect()
+}
+
+fn hides_live_value(function: &Function, block: &BasicBlock, found: &IntrinsicMatch) -> bool {
+    let hidden: HashSet<&Value> = block.instructions[found.start..found.end()]
+        .iter()
+        .filter_map(Instruction::result)
+        .collect();
+    function.body.blocks.values().any(|other| {
+        let inside =
+            |index: usize| other.id == block.id && found.start <= index && index < found.end();
+        other
+            .instructions
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !inside(*index))
+            .flat_map(|(_, inst)| inst.operands())
+            .chain(other.terminator.operands())
+            .any(|value| hidden.contains(value))
+    })
+}
+
+fn recognize_block(block: &BasicBlock) -> Vec<IntrinsicMatch> {
+    let instructions = &block.instructions;
+    let mut matches = Vec::new();
+    let mut index = 0;
+
+    while index < instructions.len() {
+        let window = &instructions[index..];
+        match only_owner(index, window).or_else(|| checked_call(index, window)) {
+            Some(found) => {
+                index = found.end();
+                matches.push(found);
+            }
+            None => index += 1,
+        }
+    }
+
+    matches
+}
+
+fn only_owner(start: usize, window: &[Instruction]) -> Option<IntrinsicMatch> {
+    let (sender, owner, slot) = match window {
+        [Instruction::GetContext {
+            result: sender,
+            var: ContextVariable::MsgSender,
+        }, Instruction::StorageLoad {
+            result: owner,
+            key: StorageKey::Slot(slot),
+        }, ..]
+        | [Instruction::StorageLoad {
+            result: owner,
+            key: StorageKey::Slot(slot),
+        }, Instruction::GetContext {
+            result: sender,
+            var: ContextVariable::MsgSender,
+        }, ..] => (sender, owner, slot),
+        _ => return None,
+    };
+
+    let message = match window.get(2..4)? {
+        [Instruction::Eq {
+            result,
+            left,
+            right,
+        }, Instruction::Require { condition, message }]
+            if condition == result
+                && ((left == sender && right == owner) || (left == owner && right == sender)) =>
+        {
+            message
+        }
+        _ => return None,
+    };
+
+    Some(IntrinsicMatch {
+        kind: IntrinsicKind::OnlyOwner,
+        start,
+        len: 4,
+        operands: Vec::new(),
+        slot: Some(slot.clone()),
+        message: Some(message.clone()).filter(|m| !m.is_empty()),
+    })
+}
+
+fn checked_call(start: usize, window: &[Instruction]) -> Option<IntrinsicMatch> {
+    let (result, target, args) = match window.first()? {
+        Instruction::Call {
+            result,
+            target: CallTarget::External(target),
+            args,
+            value: None,
+        } => (result, target, args),
+        _ => return None,
+    };
+
+    let (len, message) = match window.get(1..) {
+        Some([Instruction::Require { condition, message }, ..]) if condition == result => {
+            (2, message)
+        }
+        Some(
+            [Instruction::Eq {
+                result: check,
+                left,
+                right,
+            }, Instruction::Require { condition, message }, ..],
+        ) if condition == check && is_success_check(result, left, right) => (3, message),
+        _ => return None,
+    };
+
+    let kind = args
+        .first()
+        .map(IntrinsicKind::for_selector)
+        .unwrap_or(IntrinsicKind::CheckedCall);
+
+    let mut operands = vec![target.clone()];
+    operands.extend(args.iter().skip(1).cloned());
+
+    Some(IntrinsicMatch {
+        kind,
+        start,
+        len,
+        operands,
+        slot: None,
+        message: Some(message.clone()).filter(|m| !m.is_empty()),
+    })
+}
+
+fn is_success_check(result: &Value, left: &Value, right: &Value) -> bool {
+    let is_true = |value: &Value| match value {
+        Value::Constant(Constant::Bool(b)) => *b,
+        Value::Constant(Constant::Uint(n, _)) => *n == BigUint::from(1u32),
+        _ => false,
+    };
+    (left == result && is_true(right)) || (right == result && is_true(left))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use thalir_core::block::BlockId;
+    use thalir_core::values::{ParamId, TempId};
+
+    fn block_with(instructions: Vec<Instruction>) -> BasicBlock {
+        let mut block = BasicBlock::new(BlockId(0));
+        for inst in instructions {
+            block.add_instruction(inst);
+        }
+        block
+    }
+
+    #[test]
+    fn test_recognizes_owner_check_and_safe_transfer() {
+        let block = block_with(vec![
+            Instruction::GetContext {
+                result: Value::Temp(TempId(0)),
+                var: ContextVariable::MsgSender,
+            },
+            Instruction::StorageLoad {
+                result: Value::Temp(TempId(1)),
+                key: StorageKey::Slot(BigUint::from(0u32)),
+            },
+            Instruction::Eq {
+                result: Value::Temp(TempId(2)),
+                left: Value::Temp(TempId(0)),
+                right: Value::Temp(TempId(1)),
+            },
+            Instruction::Require {
+                condition: Value::Temp(TempId(2)),
+                message: "not owner".to_string(),
+            },
+            Instruction::Call {
+                result: Value::Temp(TempId(3)),
+                target: CallTarget::External(Value::Param(ParamId(0))),
+                args: vec![
+                    Value::Constant(Constant::Uint(BigUint::from(TRANSFER_SELECTOR), 32)),
+                    Value::Param(ParamId(1)),
+                    Value::Param(ParamId(2)),
+                ],
+                value: None,
+            },
+            Instruction::Require {
+                condition: Value::Temp(TempId(3)),
+                message: String::new(),
+            },
+        ]);
+
+        let matches = recognize_block(&block);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].kind, IntrinsicKind::OnlyOwner);
+        assert_eq!((matches[0].start, matches[0].len), (0, 4));
+        assert_eq!(matches[0].message.as_deref(), Some("not owner"));
+        assert_eq!(matches[1].kind, IntrinsicKind::SafeTransfer);
+        assert_eq!((matches[1].start, matches[1].len), (4, 2));
+        assert_eq!(matches[1].operands.len(), 3);
+        assert!(matches[1].message.is_none());
+    }
+
+    #[test]
+    fn test_unchecked_call_is_left_alone() {
+        let block = block_with(vec![
+            Instruction::Call {
+                result: Value::Temp(TempId(0)),
+                target: CallTarget::External(Value::Param(ParamId(0))),
+                args: vec![Value::Constant(Constant::Uint(BigUint::from(0u32), 32))],
+                value: None,
+            },
+            Instruction::Require {
+                condition: Value::Temp(TempId(7)),
+                message: String::new(),
+            },
+        ]);
+
+        assert!(recognize_block(&block).is_empty());
+    }
+}
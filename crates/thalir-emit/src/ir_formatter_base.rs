@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use thalir_core::{
     block::Terminator,
-    function::{Mutability, Visibility},
+    function::{Function, Mutability, Visibility},
     instructions::{CallTarget, StorageKey},
     types::Type,
     values::{Constant, Value, ValueId},
@@ -203,6 +203,40 @@ impl IRFormatterBase {
         }
     }
 
+    /* What a `function` header carries after its signature, so text reconstructs to the same
+     * metadata: visibility, mutability with `nonpayable` spelled out, the entry kind, the selector
+     * and the flags the body cannot show. Whether it calls out or writes storage is left to the
+     * body, which the parser re-derives those from. */
+    pub fn format_function_attributes(function: &Function) -> String {
+        let mutability = match function.mutability {
+            Mutability::NonPayable => "nonpayable",
+            ref mutability => Self::format_mutability(mutability),
+        };
+        let mut attributes = vec![
+            Self::format_visibility(&function.visibility).to_string(),
+            mutability.to_string(),
+        ];
+
+        let metadata = &function.metadata;
+        if metadata.is_constructor {
+            attributes.push("constructor".to_string());
+        } else if metadata.is_fallback {
+            attributes.push("fallback".to_string());
+        } else if metadata.is_receive {
+            attributes.push("receive".to_string());
+        }
+        if let Some(selector) = metadata.selector {
+            attributes.push(format!("selector 0x{:08x}", selector));
+        }
+        if metadata.has_assembly {
+            attributes.push("assembly".to_string());
+        }
+        if metadata.is_outlined {
+            attributes.push("outlined".to_string());
+        }
+        attributes.join(" ")
+    }
+
     pub fn format_call_target(
         target: &CallTarget,
         ssa: &mut SSAContext,
@@ -324,4 +358,26 @@ mod tests {
             "payable"
         );
     }
+
+    #[test]
+    fn test_format_function_attributes() {
+        let mut function = Function::new(thalir_core::function::FunctionSignature {
+            name: "transfer".to_string(),
+            params: vec![],
+            returns: vec![Type::Bool],
+            is_payable: false,
+        });
+        function.visibility = Visibility::Public;
+        assert_eq!(
+            IRFormatterBase::format_function_attributes(&function),
+            "public nonpayable"
+        );
+
+        function.metadata.selector = Some(0xa9059cbb);
+        function.metadata.has_assembly = true;
+        assert_eq!(
+            IRFormatterBase::format_function_attributes(&function),
+            "public nonpayable selector 0xa9059cbb assembly"
+        );
+    }
 }
@@ -0,0 +1,321 @@
+use crate::thalir_emitter::{SSAContext, ThalIREmitter};
+use anyhow::Result;
+use serde::Serialize;
+use std::io::Write;
+use thalir_core::{
+    analysis::PassManager,
+    block::{BasicBlock, BlockId, Terminator},
+    contract::Contract,
+    function::{Function, Mutability, Visibility},
+    stable_id, ObfuscationConfig, ObfuscationMapping, ObfuscationPass,
+};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JsonlRecord {
+    Contract {
+        id: String,
+        name: String,
+        storage_slots: usize,
+        functions: usize,
+    },
+    Function {
+        id: String,
+        parent: String,
+        name: String,
+        visibility: Visibility,
+        mutability: Mutability,
+        params: Vec<String>,
+        returns: Vec<String>,
+        entry_block: String,
+        blocks: usize,
+        stable_id: String,
+    },
+    Block {
+        id: String,
+        parent: String,
+        label: String,
+        instructions: usize,
+        terminator: String,
+        successors: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        stable_id: Option<String>,
+    },
+    Instruction {
+        id: String,
+        parent: String,
+        index: usize,
+        text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        stable_id: Option<String>,
+    },
+}
+
+impl JsonlRecord {
+    pub fn id(&self) -> &str {
+        match self {
+            JsonlRecord::Contract { id, .. }
+            | JsonlRecord::Function { id, .. }
+            | JsonlRecord::Block { id, .. }
+            | JsonlRecord::Instruction { id, .. } => id,
+        }
+    }
+
+    pub fn parent(&self) -> Option<&str> {
+        match self {
+            JsonlRecord::Contract { .. } => None,
+            JsonlRecord::Function { parent, .. }
+            | JsonlRecord::Block { parent, .. }
+            | JsonlRecord::Instruction { parent, .. } => Some(parent),
+        }
+    }
+}
+
+pub struct JsonlEmitter {
+    contracts: Vec<Contract>,
+    formatter: ThalIREmitter,
+}
+
+impl JsonlEmitter {
+    pub fn new(contracts: Vec<Contract>) -> Self {
+        Self {
+            contracts,
+            formatter: ThalIREmitter::new(Vec::new()),
+        }
+    }
+
+    pub fn with_obfuscation(
+        mut contracts: Vec<Contract>,
+        obf_config: ObfuscationConfig,
+    ) -> Result<(Self, Option<ObfuscationMapping>)> {
+        let mut manager = PassManager::new();
+        manager.register_pass(ObfuscationPass::new(obf_config.clone()));
+
+        for contract in &mut contracts {
+            manager.run_all(contract)?;
+        }
+
+        let mapping = if obf_config.retain_mapping {
+            manager
+                .get_pass::<ObfuscationPass>()
+                .map(|pass| pass.export_mapping())
+        } else {
+            None
+        };
+
+        Ok((Self::new(contracts), mapping))
+    }
+
+    pub fn records(&self) -> Vec<JsonlRecord> {
+        let mut records = Vec::new();
+        for contract in &self.contracts {
+            self.contract_records(contract, &mut records);
+        }
+        records
+    }
+
+    pub fn emit<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let _span = tracing::info_span!("emit", format = "jsonl", contracts = self.contracts.len())
+            .entered();
+        for contract in &self.contracts {
+            tracing::debug!(contract = %contract.name, "emitting contract");
+            let mut records = Vec::new();
+            self.contract_records(contract, &mut records);
+            for record in &records {
+                serde_json::to_writer(&mut *writer, record)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn emit_to_string(&self) -> Result<String> {
+        let mut buffer = Vec::new();
+        self.emit(&mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    fn contract_records(&self, contract: &Contract, records: &mut Vec<JsonlRecord>) {
+        let contract_id = contract.name.clone();
+        records.push(JsonlRecord::Contract {
+            id: contract_id.clone(),
+            name: contract.name.clone(),
+            storage_slots: contract.storage_layout.slots.len(),
+            functions: contract.functions.len(),
+        });
+
+        for (name, function) in &contract.functions {
+            self.function_records(&contract_id, name, function, records);
+        }
+    }
+
+    fn function_records(
+        &self,
+        contract_id: &str,
+        name: &str,
+        function: &Function,
+        records: &mut Vec<JsonlRecord>,
+    ) {
+        let function_id = format!("{}::{}", contract_id, name);
+        records.push(JsonlRecord::Function {
+            id: function_id.clone(),
+            parent: contract_id.to_string(),
+            name: name.to_string(),
+            visibility: function.visibility,
+            mutability: function.mutability,
+            params: function
+                .signature
+                .params
+                .iter()
+                .map(|p| self.formatter.format_type(&p.param_type))
+                .collect(),
+            returns: function
+                .signature
+                .returns
+                .iter()
+                .map(|t| self.formatter.format_type(t))
+                .collect(),
+            entry_block: block_id(&function_id, function.body.entry_block),
+            blocks: function.body.blocks.len(),
+            stable_id: stable_id::of_function(contract_id, function),
+        });
+
+        let mut ssa = SSAContext::new();
+        let param_vnums: Vec<u32> = (0..function.signature.params.len())
+            .map(|_| ssa.allocate_new())
+            .collect();
+
+        for block in function.body.blocks.values() {
+            let stable_id = stable_id::of_block(contract_id, function, block.id);
+            self.block_records(
+                &function_id,
+                block,
+                stable_id,
+                &mut ssa,
+                &param_vnums,
+                records,
+            );
+        }
+    }
+
+    fn block_records(
+        &self,
+        function_id: &str,
+        block: &BasicBlock,
+        stable_id: Option<String>,
+        ssa: &mut SSAContext,
+        param_vnums: &[u32],
+        records: &mut Vec<JsonlRecord>,
+    ) {
+        let id = block_id(function_id, block.id);
+        records.push(JsonlRecord::Block {
+            id: id.clone(),
+            parent: function_id.to_string(),
+            label: block.id.to_string(),
+            instructions: block.instructions.len(),
+            terminator: terminator_name(&block.terminator).to_string(),
+            successors: block
+                .terminator
+                .successors()
+                .into_iter()
+                .map(|succ| block_id(function_id, succ))
+                .collect(),
+            stable_id: stable_id.clone(),
+        });
+
+        for (index, inst) in block.instructions.iter().enumerate() {
+            records.push(JsonlRecord::Instruction {
+                id: format!("{}::{}", id, index),
+                parent: id.clone(),
+                index,
+                text: self.formatter.format_instruction(inst, ssa, param_vnums),
+                stable_id: stable_id
+                    .as_deref()
+                    .map(|block| stable_id::instruction_id(block, index)),
+            });
+        }
+    }
+}
+
+fn block_id(function_id: &str, block: BlockId) -> String {
+    format!("{}::{}", function_id, block)
+}
+
+fn terminator_name(terminator: &Terminator) -> &'static str {
+    match terminator {
+        Terminator::Jump(..) => "jump",
+        Terminator::Branch { .. } => "branch",
+        Terminator::Switch { .. } => "switch",
+        Terminator::Return(_) => "return",
+        Terminator::Revert(_) => "revert",
+        Terminator::Panic(_) => "panic",
+        Terminator::Invalid => "invalid",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use thalir_core::builder::IRBuilder;
+    use thalir_core::types::Type;
+
+    fn sample_contract() -> Contract {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Token");
+
+        let mut func_builder = contract_builder.function("bump");
+        let mut entry = func_builder.entry_block();
+        let one = entry.constant_uint(1, 256);
+        let two = entry.constant_uint(2, 256);
+        let sum = entry.add(one, two, Type::Uint(256));
+        entry.return_value(sum).unwrap();
+        func_builder.build().unwrap();
+
+        contract_builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_one_record_per_line_with_parent_links() {
+        let emitter = JsonlEmitter::new(vec![sample_contract()]);
+        let output = emitter.emit_to_string().unwrap();
+
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["kind"], "contract");
+        assert_eq!(lines[1]["kind"], "function");
+        assert_eq!(lines[1]["parent"], "Token");
+        assert_eq!(lines[2]["kind"], "block");
+        assert_eq!(lines[2]["parent"], "Token::bump");
+        assert_eq!(lines[3]["kind"], "instruction");
+        assert_eq!(lines[3]["parent"], lines[2]["id"]);
+    }
+
+    #[test]
+    fn test_ids_are_stable_across_runs() {
+        let first = JsonlEmitter::new(vec![sample_contract()]).records();
+        let second = JsonlEmitter::new(vec![sample_contract()]).records();
+
+        let first_ids: Vec<&str> = first.iter().map(JsonlRecord::id).collect();
+        let second_ids: Vec<&str> = second.iter().map(JsonlRecord::id).collect();
+        assert_eq!(first_ids, second_ids);
+        assert!(first_ids.iter().all(|id| id.starts_with("Token")));
+    }
+
+    #[test]
+    fn test_records_carry_stable_ids() {
+        let output = JsonlEmitter::new(vec![sample_contract()])
+            .emit_to_string()
+            .unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let function_id = lines[1]["stable_id"].as_str().unwrap();
+        assert_eq!(lines[2]["stable_id"], format!("{}#0", function_id));
+        assert_eq!(lines[3]["stable_id"], format!("{}#0:0", function_id));
+        assert!(lines[0].get("stable_id").is_none());
+    }
+}
@@ -3,18 +3,42 @@
  * IR is meant to be read by humans, not just machines. Whether you're debugging a transformation,
  * reviewing generated code, or sharing findings with other auditors, these emitters produce clean
  * text that preserves structure and makes patterns visible.
+ *
+ * How much gets printed is picked with an `EmitterProfile`: `minimal` is bare instructions for
+ * diffing and sharing, `audit` adds types, security tags, and NatSpec for review, and `debug` also
+ * prints source locations and def-use comments for chasing down lowering bugs.
+ *
+ * `TraceEmitter` prints a single path through a function instead, with values folded where they
+ * are known, for pasting into a finding writeup. `HtmlReportEmitter` bundles findings with the IR
+ * and CFG of the functions they touch into one file for readers without the CLI.
+ *
+ * `PseudoSolidityEmitter` rebuilds `if`/`else` and `while` from the CFG and prints Solidity-like
+ * pseudocode, for IR with no source to read alongside. `InterfaceEmitter` prints only what callers
+ * can reach, as a Solidity `interface` and as ABI JSON.
  */
 
 pub mod annotated_ir_emitter;
 pub mod config;
 pub mod emitter;
+pub mod html_report;
+pub mod interface_emitter;
+pub mod intrinsics;
 pub mod ir_formatter_base;
+pub mod jsonl_emitter;
 pub mod output;
+pub mod pseudo_solidity;
 pub mod thalir_emitter;
+pub mod trace_emitter;
 
 pub use annotated_ir_emitter::AnnotatedIREmitter;
-pub use config::{EmitterConfig, VerbosityLevel};
+pub use config::{EmitterConfig, EmitterProfile, FunctionOrder, StorageNaming, VerbosityLevel};
 pub use emitter::{EmitContext, EmitHelper, EmitResult, Emittable, Emitter};
+pub use html_report::HtmlReportEmitter;
+pub use interface_emitter::InterfaceEmitter;
+pub use intrinsics::{IntrinsicKind, IntrinsicMatch};
 pub use ir_formatter_base::{IRFormatterBase, SSAContext};
+pub use jsonl_emitter::{JsonlEmitter, JsonlRecord};
 pub use output::{OutputFormat, OutputStyle};
+pub use pseudo_solidity::PseudoSolidityEmitter;
 pub use thalir_emitter::ThalIREmitter;
+pub use trace_emitter::TraceEmitter;
@@ -0,0 +1,1028 @@
+use crate::ir_formatter_base::IRFormatterBase;
+use anyhow::Result;
+use num_bigint::BigUint;
+use std::collections::{HashMap, HashSet};
+use thalir_core::{
+    analysis::{ControlFlowGraph, PassManager},
+    block::{BlockId, Terminator},
+    contract::Contract,
+    function::Function,
+    instructions::{BuiltinFunction, CallTarget, ContextVariable, Instruction, StorageKey},
+    values::{BlockParamId, Constant, Location, ParamId, Value, ValueId},
+    ObfuscationConfig, ObfuscationMapping, ObfuscationPass,
+};
+
+const INDENT: &str = "    ";
+
+/* Prints contracts as Solidity-shaped pseudocode: branches become `if`/`else` again, loops
+ * become `while`, and storage is addressed by variable name. Meant for reading IR that never had
+ * source, lifted from bytecode or obfuscated, not for recompiling.
+ *
+ * Control flow is rebuilt from the CFG alone. A branch closes where its arms rejoin, at its
+ * immediate post-dominator; a loop is a `while` around its header, leaving at its first exit.
+ * Flow that does not fit, such as a jump into a block already printed elsewhere, is kept as a
+ * `goto` to a labelled block rather than guessed at. */
+pub struct PseudoSolidityEmitter {
+    contracts: Vec<Contract>,
+}
+
+impl PseudoSolidityEmitter {
+    pub fn new(contracts: Vec<Contract>) -> Self {
+        Self { contracts }
+    }
+
+    pub fn with_obfuscation(
+        mut contracts: Vec<Contract>,
+        obf_config: ObfuscationConfig,
+    ) -> Result<(Self, Option<ObfuscationMapping>)> {
+        let mut manager = PassManager::new();
+        manager.register_pass(ObfuscationPass::new(obf_config.clone()));
+
+        for contract in &mut contracts {
+            manager.run_all(contract)?;
+        }
+
+        let mapping = if obf_config.retain_mapping {
+            manager
+                .get_pass::<ObfuscationPass>()
+                .map(|pass| pass.export_mapping())
+        } else {
+            None
+        };
+        Ok((Self::new(contracts), mapping))
+    }
+
+    pub fn emit_to_string(&self) -> String {
+        let mut output = String::new();
+        for (index, contract) in self.contracts.iter().enumerate() {
+            if index > 0 {
+                output.push('\n');
+            }
+            emit_contract(contract, &mut output);
+        }
+        output
+    }
+}
+
+fn emit_contract(contract: &Contract, output: &mut String) {
+    if contract.metadata.bases.is_empty() {
+        output.push_str(&format!("contract {} {{\n", contract.name));
+    } else {
+        output.push_str(&format!(
+            "contract {} is {} {{\n",
+            contract.name,
+            contract.metadata.bases.join(", ")
+        ));
+    }
+
+    for var in &contract.storage_layout.slots {
+        let position = match var.offset {
+            0 => format!("slot {}", var.slot),
+            offset => format!("slot {}, offset {}", var.slot, offset),
+        };
+        output.push_str(&format!(
+            "{}{} {}; // {}\n",
+            INDENT, var.var_type, var.name, position
+        ));
+    }
+    for event in &contract.events {
+        let params: Vec<String> = event
+            .parameters
+            .iter()
+            .map(|p| {
+                let indexed = if p.indexed { " indexed" } else { "" };
+                format!("{}{} {}", p.param_type, indexed, p.name)
+            })
+            .collect();
+        output.push_str(&format!(
+            "{}event {}({});\n",
+            INDENT,
+            event.name,
+            params.join(", ")
+        ));
+    }
+
+    for function in contract.functions.values() {
+        output.push('\n');
+        emit_function(contract, function, output);
+    }
+    output.push_str("}\n");
+}
+
+fn emit_function(contract: &Contract, function: &Function, output: &mut String) {
+    let params: Vec<String> = function
+        .signature
+        .params
+        .iter()
+        .enumerate()
+        .map(|(i, p)| format!("{} {}", p.param_type, param_name(function, i)))
+        .collect();
+    let mut header = format!(
+        "function {}({}) {}",
+        function.name(),
+        params.join(", "),
+        IRFormatterBase::format_visibility(&function.visibility)
+    );
+    let mutability = IRFormatterBase::format_mutability(&function.mutability);
+    if !mutability.is_empty() {
+        header.push(' ');
+        header.push_str(mutability);
+    }
+    for modifier in contract.guards(function).modifiers {
+        header.push(' ');
+        header.push_str(&modifier);
+    }
+    if !function.signature.returns.is_empty() {
+        let returns: Vec<String> = function
+            .signature
+            .returns
+            .iter()
+            .map(|ty| ty.to_string())
+            .collect();
+        header.push_str(&format!(" returns ({})", returns.join(", ")));
+    }
+    output.push_str(&format!("{}{} {{\n", INDENT, header));
+
+    let mut structurer = Structurer::new(contract, function);
+    structurer.region(function.entry_block(), None, 2);
+    output.push_str(&structurer.out);
+    output.push_str(&format!("{}}}\n", INDENT));
+}
+
+fn param_name(function: &Function, index: usize) -> String {
+    match function.signature.params.get(index) {
+        Some(param) if !param.name.is_empty() => param.name.clone(),
+        _ => format!("arg{}", index),
+    }
+}
+
+struct Structurer<'a> {
+    contract: &'a Contract,
+    function: &'a Function,
+    cfg: ControlFlowGraph,
+    ipdom: HashMap<BlockId, BlockId>,
+    names: HashMap<ValueId, String>,
+    next_name: u32,
+    used: HashSet<ValueId>,
+    emitted: HashSet<BlockId>,
+    /* Innermost last: each loop's header, the block it leaves to, and its blocks. */
+    loops: Vec<(BlockId, Option<BlockId>, HashSet<BlockId>)>,
+    /* Blocks some `goto` jumps to, which get a label where they are printed. */
+    labels: HashSet<BlockId>,
+    out: String,
+}
+
+impl<'a> Structurer<'a> {
+    fn new(contract: &'a Contract, function: &'a Function) -> Self {
+        let mut used = HashSet::new();
+        for block in function.body.blocks.values() {
+            let operands = block
+                .instructions
+                .iter()
+                .flat_map(Instruction::operands)
+                .chain(block.terminator.operands());
+            used.extend(operands.filter_map(Value::as_register));
+        }
+
+        let mut structurer = Self {
+            contract,
+            function,
+            cfg: ControlFlowGraph::build(function),
+            ipdom: post_dominators(function),
+            names: HashMap::new(),
+            next_name: 0,
+            used,
+            emitted: HashSet::new(),
+            loops: Vec::new(),
+            labels: HashSet::new(),
+            out: String::new(),
+        };
+        structurer.labels = structurer.goto_targets();
+        structurer
+    }
+
+    /* A dry run of the structuring, so labels are known before the blocks they sit on print. */
+    fn goto_targets(&self) -> HashSet<BlockId> {
+        let mut dry = Self {
+            contract: self.contract,
+            function: self.function,
+            cfg: self.cfg.clone(),
+            ipdom: self.ipdom.clone(),
+            names: HashMap::new(),
+            next_name: 0,
+            used: self.used.clone(),
+            emitted: HashSet::new(),
+            loops: Vec::new(),
+            labels: HashSet::new(),
+            out: String::new(),
+        };
+        dry.region(self.function.entry_block(), None, 0);
+        dry.out
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("goto block"))
+            .filter_map(|rest| rest.trim_end_matches(';').parse().ok())
+            .map(BlockId)
+            .collect()
+    }
+
+    fn line(&mut self, depth: usize, text: &str) {
+        self.out.push_str(&INDENT.repeat(depth));
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    /* Prints from `block` on until control reaches `stop`, leaves the function, or continues or
+     * breaks out of the innermost loop. */
+    fn region(&mut self, mut block: BlockId, stop: Option<BlockId>, depth: usize) {
+        loop {
+            if Some(block) == stop {
+                return;
+            }
+            if let Some(jump) = self.loop_jump(block) {
+                self.line(depth, jump);
+                return;
+            }
+            if !self.emitted.insert(block) || !self.function.body.blocks.contains_key(&block) {
+                self.line(depth, &format!("goto {};", block));
+                return;
+            }
+            if self.labels.contains(&block) {
+                self.line(depth.saturating_sub(1), &format!("{}:", block));
+            }
+
+            let next = if self.cfg.is_loop_header(block) {
+                self.emit_loop(block, depth)
+            } else {
+                self.step(block, stop, depth)
+            };
+            match next {
+                Some(next) => block = next,
+                None => return,
+            }
+        }
+    }
+
+    /* Prints one block and any structure its terminator opens, returning where control goes
+     * once that structure closes. */
+    fn step(&mut self, block: BlockId, stop: Option<BlockId>, depth: usize) -> Option<BlockId> {
+        let function = self.function;
+        let data = &function.body.blocks[&block];
+        for inst in &data.instructions {
+            if let Some(statement) = self.statement(inst) {
+                self.line(depth, &statement);
+            }
+        }
+
+        match &data.terminator {
+            Terminator::Jump(target, args) => {
+                self.pass_args(*target, args, depth);
+                Some(*target)
+            }
+            Terminator::Branch {
+                condition,
+                then_block,
+                else_block,
+                then_args,
+                else_args,
+            } => {
+                let condition = self.name(condition);
+                let then_arm = (*then_block, then_args.as_slice());
+                let else_arm = (*else_block, else_args.as_slice());
+
+                /* An arm that leaves or restarts the loop, or that ends the function, reads best
+                 * as a guard, with the other arm carrying on at this level. */
+                let guard = if self.jumps_out(then_arm) || self.exits(else_arm.0) {
+                    Some((format!("!({})", condition), else_arm, then_arm))
+                } else if self.jumps_out(else_arm) || self.exits(then_arm.0) {
+                    Some((condition.clone(), then_arm, else_arm))
+                } else {
+                    None
+                };
+                let merge = self.merge_point(block);
+                match (guard, merge) {
+                    (Some((test, (target, args), rest)), _)
+                        if merge.is_none() || self.jumps_out((target, args)) =>
+                    {
+                        self.line(depth, &format!("if ({}) {{", test));
+                        match self.loop_jump(target) {
+                            Some(jump) => self.line(depth + 1, jump),
+                            None => {
+                                self.pass_args(target, args, depth + 1);
+                                self.region(target, stop, depth + 1);
+                            }
+                        }
+                        self.line(depth, "}");
+                        self.pass_args(rest.0, rest.1, depth);
+                        Some(rest.0)
+                    }
+                    (_, Some(merge)) => {
+                        self.emit_if(&condition, [then_arm, else_arm], merge, depth);
+                        Some(merge)
+                    }
+                    /* Both arms end the function. */
+                    (_, None) => {
+                        self.line(depth, &format!("if ({}) {{", condition));
+                        self.pass_args(*then_block, then_args, depth + 1);
+                        self.region(*then_block, stop, depth + 1);
+                        self.line(depth, "}");
+                        self.pass_args(*else_block, else_args, depth);
+                        Some(*else_block)
+                    }
+                }
+            }
+            Terminator::Switch {
+                value,
+                default,
+                cases,
+            } => {
+                let value = self.name(value);
+                let merge = self.merge_point(block).or(stop);
+                for (index, (case, target)) in cases.iter().enumerate() {
+                    let keyword = if index == 0 { "if" } else { "} else if" };
+                    let case = self.name(case);
+                    self.line(depth, &format!("{} ({} == {}) {{", keyword, value, case));
+                    self.region(*target, merge, depth + 1);
+                }
+                if cases.is_empty() {
+                    return Some(*default);
+                }
+                self.line(depth, "} else {");
+                self.region(*default, merge, depth + 1);
+                self.line(depth, "}");
+                merge
+            }
+            Terminator::Return(value) => {
+                match value {
+                    Some(value) => {
+                        let value = self.name(value);
+                        self.line(depth, &format!("return {};", value));
+                    }
+                    None => self.line(depth, "return;"),
+                }
+                None
+            }
+            Terminator::Revert(message) => {
+                self.line(depth, &format!("revert({:?});", message));
+                None
+            }
+            Terminator::Panic(message) => {
+                self.line(depth, &format!("panic({:?});", message));
+                None
+            }
+            Terminator::Invalid => {
+                self.line(depth, "invalid();");
+                None
+            }
+        }
+    }
+
+    fn emit_if(
+        &mut self,
+        condition: &str,
+        arms: [(BlockId, &[Value]); 2],
+        merge: BlockId,
+        depth: usize,
+    ) {
+        let [(then_block, then_args), (else_block, else_args)] = arms;
+        let then_empty = then_block == merge && then_args.is_empty();
+        let else_empty = else_block == merge && else_args.is_empty();
+
+        if then_empty && !else_empty {
+            self.line(depth, &format!("if (!({})) {{", condition));
+            self.pass_args(else_block, else_args, depth + 1);
+            self.region(else_block, Some(merge), depth + 1);
+            self.line(depth, "}");
+            return;
+        }
+
+        self.line(depth, &format!("if ({}) {{", condition));
+        self.pass_args(then_block, then_args, depth + 1);
+        self.region(then_block, Some(merge), depth + 1);
+        if !else_empty {
+            self.line(depth, "} else {");
+            self.pass_args(else_block, else_args, depth + 1);
+            self.region(else_block, Some(merge), depth + 1);
+        }
+        self.line(depth, "}");
+    }
+
+    /* The statement that reaches `target` from inside the innermost loop, if it is the loop's
+     * header or the block the loop leaves to. */
+    fn loop_jump(&self, target: BlockId) -> Option<&'static str> {
+        let (header, follow, _) = self.loops.last()?;
+        if target == *header {
+            Some("continue;")
+        } else if Some(target) == *follow {
+            Some("break;")
+        } else {
+            None
+        }
+    }
+
+    /* True when taking `arm` is a bare `break` or `continue`. */
+    fn jumps_out(&self, (target, args): (BlockId, &[Value])) -> bool {
+        args.is_empty() && self.loop_jump(target).is_some()
+    }
+
+    fn exits(&self, block: BlockId) -> bool {
+        self.function
+            .body
+            .blocks
+            .get(&block)
+            .is_some_and(|data| data.terminator.successors().is_empty())
+    }
+
+    /* A header with nothing but its exit test becomes `while (cond)`; anything else runs its
+     * instructions inside `while (true)` and leaves through a `break`. */
+    fn emit_loop(&mut self, header: BlockId, depth: usize) -> Option<BlockId> {
+        let mut blocks = HashSet::new();
+        let mut exits = Vec::new();
+        for found in self.cfg.loops().iter().filter(|l| l.header == header) {
+            blocks.extend(found.blocks.iter().copied());
+            exits.extend(found.exits.iter().copied());
+        }
+        let function = self.function;
+        let data = &function.body.blocks[&header];
+
+        if let Terminator::Branch {
+            condition,
+            then_block,
+            else_block,
+            then_args,
+            else_args,
+        } = &data.terminator
+        {
+            let then_inside = blocks.contains(then_block);
+            let else_inside = blocks.contains(else_block);
+            if data.instructions.is_empty() && then_inside != else_inside {
+                let condition = self.name(condition);
+                let (body, body_args, follow, follow_args, test) = if then_inside {
+                    (*then_block, then_args, *else_block, else_args, condition)
+                } else {
+                    (
+                        *else_block,
+                        else_args,
+                        *then_block,
+                        then_args,
+                        format!("!({})", condition),
+                    )
+                };
+
+                self.line(depth, &format!("while ({}) {{", test));
+                self.loops.push((header, Some(follow), blocks));
+                self.pass_args(body, body_args, depth + 1);
+                self.region(body, Some(header), depth + 1);
+                self.loops.pop();
+                self.line(depth, "}");
+                self.pass_args(follow, follow_args, depth);
+                return Some(follow);
+            }
+        }
+
+        let follow = exits
+            .iter()
+            .copied()
+            .find(|exit| self.ipdom.get(&header) == Some(exit))
+            .or_else(|| exits.first().copied());
+        self.line(depth, "while (true) {");
+        self.loops.push((header, follow, blocks));
+        if let Some(next) = self.step(header, Some(header), depth + 1) {
+            self.region(next, Some(header), depth + 1);
+        }
+        self.loops.pop();
+        self.line(depth, "}");
+        follow
+    }
+
+    /* Where the arms of a branch at `block` rejoin. Inside a loop, arms that only meet after
+     * leaving it meet at the next iteration instead. */
+    fn merge_point(&self, block: BlockId) -> Option<BlockId> {
+        let merge = self.ipdom.get(&block).copied()?;
+        match self.loops.last() {
+            Some((header, _, blocks)) if !blocks.contains(&merge) => Some(*header),
+            _ => Some(merge),
+        }
+    }
+
+    fn pass_args(&mut self, target: BlockId, args: &[Value], depth: usize) {
+        for (index, arg) in args.iter().enumerate() {
+            let param = self.name(&Value::BlockParam(BlockParamId {
+                block: target,
+                index: index as u32,
+            }));
+            let arg = self.name(arg);
+            self.line(depth, &format!("{} = {};", param, arg));
+        }
+    }
+
+    fn name(&mut self, value: &Value) -> String {
+        if let Value::Constant(constant) = value {
+            return render_constant(constant);
+        }
+        let Some(id) = value.as_register() else {
+            return "undefined".to_string();
+        };
+        if let ValueId::Param(ParamId(index)) = id {
+            return param_name(self.function, index as usize);
+        }
+        if let Some(name) = self.names.get(&id) {
+            return name.clone();
+        }
+        let name = format!("v{}", self.next_name);
+        self.next_name += 1;
+        self.names.insert(id, name.clone());
+        name
+    }
+
+    fn storage_name(&self, slot: &BigUint) -> String {
+        let vars = &self.contract.storage_layout.slots;
+        vars.iter()
+            .find(|var| &var.slot == slot && var.offset == 0)
+            .or_else(|| vars.iter().find(|var| &var.slot == slot))
+            .map(|var| var.name.clone())
+            .unwrap_or_else(|| format!("storage[{}]", slot))
+    }
+
+    fn storage_key(&mut self, key: &StorageKey) -> String {
+        match key {
+            StorageKey::Slot(slot) => self.storage_name(slot),
+            StorageKey::Dynamic(value) | StorageKey::Computed(value) => {
+                format!("storage[{}]", self.name(value))
+            }
+            StorageKey::MappingKey { base, key } => {
+                format!("{}[{}]", self.storage_name(base), self.name(key))
+            }
+            StorageKey::ArrayElement { base, index } => {
+                format!("{}[{}]", self.storage_name(base), self.name(index))
+            }
+        }
+    }
+
+    /* Mappings and arrays are addressed by their slot, which names the variable when constant. */
+    fn container(&mut self, value: &Value) -> String {
+        match value {
+            Value::Constant(Constant::Uint(slot, _)) => self.storage_name(slot),
+            other => self.name(other),
+        }
+    }
+
+    fn location(&mut self, location: &Location) -> String {
+        match location {
+            Location::Stack { offset } => format!("stack[{}]", offset),
+            Location::Memory { base, offset } => {
+                format!("mem[{} + {}]", self.name(base), self.name(offset))
+            }
+            Location::Storage { slot } => format!("storage[{}]", self.name(slot)),
+            Location::Calldata { offset } => format!("calldata[{}]", self.name(offset)),
+            Location::ReturnData { offset } => format!("returndata[{}]", self.name(offset)),
+        }
+    }
+
+    fn call(&mut self, callee: &str, args: &[Value]) -> String {
+        let args: Vec<String> = args.iter().map(|arg| self.name(arg)).collect();
+        format!("{}({})", callee, args.join(", "))
+    }
+
+    /* `result = expr;`, or just `expr;` for a call whose result nothing reads. */
+    fn assign(&mut self, result: &Value, expr: String, side_effects: bool) -> String {
+        let unused = result
+            .as_register()
+            .map_or(true, |id| !self.used.contains(&id));
+        if side_effects && unused {
+            return format!("{};", expr);
+        }
+        format!("{} = {};", self.name(result), expr)
+    }
+
+    fn statement(&mut self, inst: &Instruction) -> Option<String> {
+        let statement = match inst {
+            Instruction::Not { result, operand } => {
+                let expr = format!("~{}", self.name(operand));
+                self.assign(result, expr, false)
+            }
+            Instruction::Select {
+                result,
+                condition,
+                then_val,
+                else_val,
+            } => {
+                let expr = format!(
+                    "{} ? {} : {}",
+                    self.name(condition),
+                    self.name(then_val),
+                    self.name(else_val)
+                );
+                self.assign(result, expr, false)
+            }
+
+            Instruction::Load { result, location } => {
+                let expr = self.location(location);
+                self.assign(result, expr, false)
+            }
+            Instruction::Store { location, value } => {
+                format!("{} = {};", self.location(location), self.name(value))
+            }
+            Instruction::Allocate { result, ty, .. } => {
+                self.assign(result, format!("new {}", ty), false)
+            }
+            Instruction::Copy { dest, src, size } => {
+                let args = [self.location(dest), self.location(src), self.name(size)];
+                format!("copy({});", args.join(", "))
+            }
+            Instruction::MemoryAlloc { result, size } => {
+                let expr = format!("new bytes({})", self.name(size));
+                self.assign(result, expr, false)
+            }
+            Instruction::MemoryCopy { dest, src, size } => format!(
+                "{};",
+                self.call("mcopy", &[dest.clone(), src.clone(), size.clone()])
+            ),
+            Instruction::MemorySize { result } => self.assign(result, "msize()".to_string(), false),
+
+            Instruction::StorageLoad { result, key } => {
+                let expr = self.storage_key(key);
+                self.assign(result, expr, false)
+            }
+            Instruction::StorageStore { key, value } => {
+                format!("{} = {};", self.storage_key(key), self.name(value))
+            }
+            Instruction::StorageDelete { key } => format!("delete {};", self.storage_key(key)),
+            Instruction::TransientLoad { result, key } => {
+                let expr = format!("tload({})", self.storage_key(key));
+                self.assign(result, expr, false)
+            }
+            Instruction::TransientStore { key, value } => {
+                format!("tstore({}, {});", self.storage_key(key), self.name(value))
+            }
+            Instruction::MappingLoad {
+                result,
+                mapping,
+                key,
+            } => {
+                let expr = format!("{}[{}]", self.container(mapping), self.name(key));
+                self.assign(result, expr, false)
+            }
+            Instruction::MappingStore {
+                mapping,
+                key,
+                value,
+            } => format!(
+                "{}[{}] = {};",
+                self.container(mapping),
+                self.name(key),
+                self.name(value)
+            ),
+            Instruction::ArrayLoad {
+                result,
+                array,
+                index,
+            } => {
+                let expr = format!("{}[{}]", self.container(array), self.name(index));
+                self.assign(result, expr, false)
+            }
+            Instruction::ArrayStore {
+                array,
+                index,
+                value,
+            } => format!(
+                "{}[{}] = {};",
+                self.container(array),
+                self.name(index),
+                self.name(value)
+            ),
+            Instruction::ArrayLength { result, array } => {
+                let expr = format!("{}.length", self.container(array));
+                self.assign(result, expr, false)
+            }
+            Instruction::ArrayPush { array, value } => {
+                format!("{}.push({});", self.container(array), self.name(value))
+            }
+            Instruction::ArrayPop { result, array } => {
+                let expr = format!("{}.pop()", self.container(array));
+                self.assign(result, expr, true)
+            }
+
+            Instruction::Call {
+                result,
+                target,
+                args,
+                value,
+            } => {
+                let expr = match target {
+                    CallTarget::Internal(name) | CallTarget::Library(name) => self.call(name, args),
+                    CallTarget::Builtin(builtin) => self.call(builtin_name(builtin), args),
+                    CallTarget::External(address) => {
+                        let address = self.name(address);
+                        let method = match value {
+                            Some(value) => {
+                                format!("{}.call{{value: {}}}", address, self.name(value))
+                            }
+                            None => format!("{}.call", address),
+                        };
+                        self.call(&method, args)
+                    }
+                };
+                self.assign(result, expr, true)
+            }
+            Instruction::DelegateCall {
+                result,
+                target,
+                selector,
+                args,
+            }
+            | Instruction::StaticCall {
+                result,
+                target,
+                selector,
+                args,
+            } => {
+                let method = match inst {
+                    Instruction::DelegateCall { .. } => "delegatecall",
+                    _ => "staticcall",
+                };
+                let method = format!("{}.{}", self.name(target), method);
+                let mut call_args = vec![selector.clone()];
+                call_args.extend(args.iter().cloned());
+                let expr = self.call(&method, &call_args);
+                self.assign(result, expr, true)
+            }
+            Instruction::Create {
+                result,
+                code,
+                value,
+            } => {
+                let expr = self.call("create", &[code.clone(), value.clone()]);
+                self.assign(result, expr, true)
+            }
+            Instruction::Create2 {
+                result,
+                code,
+                salt,
+                value,
+            } => {
+                let expr = self.call("create2", &[code.clone(), salt.clone(), value.clone()]);
+                self.assign(result, expr, true)
+            }
+            Instruction::Selfdestruct { beneficiary } => {
+                format!("{};", self.call("selfdestruct", &[beneficiary.clone()]))
+            }
+
+            Instruction::GetContext { result, var } => match context_name(var) {
+                /* Fixed for the whole call, so the name stands in for the value. */
+                Some(name) => {
+                    if let Some(id) = result.as_register() {
+                        self.names.insert(id, name.to_string());
+                    }
+                    return None;
+                }
+                None => {
+                    let expr = match var {
+                        ContextVariable::GasLeft => "gasleft()",
+                        _ => "address(this).balance",
+                    };
+                    self.assign(result, expr.to_string(), false)
+                }
+            },
+            Instruction::GetBalance { result, address } => {
+                let expr = format!("{}.balance", self.name(address));
+                self.assign(result, expr, false)
+            }
+            Instruction::GetCode { result, address } => {
+                let expr = format!("{}.code", self.name(address));
+                self.assign(result, expr, false)
+            }
+            Instruction::GetCodeSize { result, address } => {
+                let expr = format!("{}.code.length", self.name(address));
+                self.assign(result, expr, false)
+            }
+            Instruction::GetCodeHash { result, address } => {
+                let expr = format!("{}.codehash", self.name(address));
+                self.assign(result, expr, false)
+            }
+            Instruction::Keccak256 { result, data, len }
+            | Instruction::Sha256 { result, data, len }
+            | Instruction::Ripemd160 { result, data, len } => {
+                let function = match inst {
+                    Instruction::Keccak256 { .. } => "keccak256",
+                    Instruction::Sha256 { .. } => "sha256",
+                    _ => "ripemd160",
+                };
+                let expr = self.call(function, &[data.clone(), len.clone()]);
+                self.assign(result, expr, false)
+            }
+            Instruction::EcRecover {
+                result,
+                hash,
+                v,
+                r,
+                s,
+            } => {
+                let expr = self.call(
+                    "ecrecover",
+                    &[hash.clone(), v.clone(), r.clone(), s.clone()],
+                );
+                self.assign(result, expr, false)
+            }
+            Instruction::EmitEvent {
+                event,
+                topics,
+                data,
+            } => {
+                let name = self
+                    .contract
+                    .events
+                    .iter()
+                    .find(|definition| definition.id == *event)
+                    .map(|definition| definition.name.clone())
+                    .unwrap_or_else(|| format!("event{}", event.0));
+                let args: Vec<Value> = topics.iter().chain(data).cloned().collect();
+                format!("emit {};", self.call(&name, &args))
+            }
+
+            Instruction::Cast { result, value, to }
+            | Instruction::ZeroExtend { result, value, to }
+            | Instruction::SignExtend { result, value, to }
+            | Instruction::Truncate { result, value, to } => {
+                let expr = format!("{}({})", to, self.name(value));
+                self.assign(result, expr, false)
+            }
+
+            Instruction::Assert { condition, .. } => format!("assert({});", self.name(condition)),
+            Instruction::Require { condition, message } => {
+                format!("require({}, {:?});", self.name(condition), message)
+            }
+            Instruction::Revert { message } => format!("revert({:?});", message),
+            Instruction::Assign { result, value } => {
+                let expr = self.name(value);
+                self.assign(result, expr, false)
+            }
+            Instruction::Phi { result, values } => {
+                let incoming: Vec<String> = values
+                    .iter()
+                    .map(|(block, value)| format!("{}: {}", block, self.name(value)))
+                    .collect();
+                self.assign(result, format!("phi({})", incoming.join(", ")), false)
+            }
+            Instruction::Jump { target, .. } => format!("goto {};", target),
+            Instruction::Branch {
+                condition,
+                then_block,
+                else_block,
+                ..
+            } => format!(
+                "if ({}) goto {}; else goto {};",
+                self.name(condition),
+                then_block,
+                else_block
+            ),
+            Instruction::Return { value } => match value {
+                Some(value) => format!("return {};", self.name(value)),
+                None => "return;".to_string(),
+            },
+            _ => {
+                let (Some(op), Some(result)) = (operator(inst), inst.result()) else {
+                    return None;
+                };
+                let operands = inst.operands();
+                let expr = format!(
+                    "{} {} {}",
+                    self.name(operands[0]),
+                    op,
+                    self.name(operands[1])
+                );
+                self.assign(result, expr, false)
+            }
+        };
+        Some(statement)
+    }
+}
+
+fn operator(inst: &Instruction) -> Option<&'static str> {
+    Some(match inst {
+        Instruction::Add { .. } | Instruction::CheckedAdd { .. } => "+",
+        Instruction::Sub { .. } | Instruction::CheckedSub { .. } => "-",
+        Instruction::Mul { .. } | Instruction::CheckedMul { .. } => "*",
+        Instruction::Div { .. } | Instruction::Sdiv { .. } | Instruction::CheckedDiv { .. } => "/",
+        Instruction::Mod { .. } | Instruction::Smod { .. } => "%",
+        Instruction::Pow { .. } => "**",
+        Instruction::And { .. } => "&",
+        Instruction::Or { .. } => "|",
+        Instruction::Xor { .. } => "^",
+        Instruction::Shl { .. } => "<<",
+        Instruction::Shr { .. } | Instruction::Sar { .. } => ">>",
+        Instruction::Eq { .. } => "==",
+        Instruction::Ne { .. } => "!=",
+        Instruction::Lt { .. } | Instruction::Slt { .. } => "<",
+        Instruction::Gt { .. } | Instruction::Sgt { .. } => ">",
+        Instruction::Le { .. } | Instruction::Sle { .. } => "<=",
+        Instruction::Ge { .. } | Instruction::Sge { .. } => ">=",
+        _ => return None,
+    })
+}
+
+/* Immediate post-dominators, by the same set intersection `DominatorTree` uses, over the reversed
+ * CFG. Blocks whose paths leave the function at different exits have none. */
+fn post_dominators(function: &Function) -> HashMap<BlockId, BlockId> {
+    let blocks: Vec<BlockId> = function.body.blocks.keys().copied().collect();
+    let all: HashSet<BlockId> = blocks.iter().copied().collect();
+    let successors: HashMap<BlockId, Vec<BlockId>> = function
+        .body
+        .blocks
+        .iter()
+        .map(|(&id, block)| {
+            let targets = block
+                .terminator
+                .successors()
+                .into_iter()
+                .filter(|target| all.contains(target))
+                .collect();
+            (id, targets)
+        })
+        .collect();
+
+    let mut pdom: HashMap<BlockId, HashSet<BlockId>> = blocks
+        .iter()
+        .map(|&id| match successors[&id].is_empty() {
+            true => (id, HashSet::from([id])),
+            false => (id, all.clone()),
+        })
+        .collect();
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &id in blocks.iter().rev() {
+            let mut succs = successors[&id].iter();
+            let Some(first) = succs.next() else {
+                continue;
+            };
+            let mut set = pdom[first].clone();
+            for succ in succs {
+                set.retain(|block| pdom[succ].contains(block));
+            }
+            set.insert(id);
+            if set != pdom[&id] {
+                pdom.insert(id, set);
+                changed = true;
+            }
+        }
+    }
+
+    let mut ipdom = HashMap::new();
+    for &id in &blocks {
+        let immediate = pdom[&id]
+            .iter()
+            .copied()
+            .filter(|&candidate| candidate != id)
+            .max_by_key(|candidate| (pdom[candidate].len(), candidate.0));
+        if let Some(candidate) = immediate {
+            ipdom.insert(id, candidate);
+        }
+    }
+    ipdom
+}
+
+fn render_constant(constant: &Constant) -> String {
+    match constant {
+        Constant::Bool(value) => value.to_string(),
+        Constant::Uint(value, _) => value.to_string(),
+        Constant::Int(value, _) => value.to_string(),
+        Constant::Address(bytes) => format!("address(0x{})", IRFormatterBase::format_bytes(bytes)),
+        Constant::Bytes(bytes) => format!("hex\"{}\"", IRFormatterBase::format_bytes(bytes)),
+        Constant::String(text) => format!("{:?}", text),
+        Constant::Null => "0".to_string(),
+    }
+}
+
+fn builtin_name(builtin: &BuiltinFunction) -> &'static str {
+    match builtin {
+        BuiltinFunction::AddMod => "addmod",
+        BuiltinFunction::MulMod => "mulmod",
+        BuiltinFunction::BlockHash => "blockhash",
+        BuiltinFunction::BlobHash => "blobhash",
+        BuiltinFunction::GasLeft => "gasleft",
+        BuiltinFunction::MappingSlot => "mappingSlot",
+    }
+}
+
+fn context_name(var: &ContextVariable) -> Option<&'static str> {
+    Some(match var {
+        ContextVariable::MsgSender => "msg.sender",
+        ContextVariable::MsgValue => "msg.value",
+        ContextVariable::MsgData => "msg.data",
+        ContextVariable::MsgSig => "msg.sig",
+        ContextVariable::BlockNumber => "block.number",
+        ContextVariable::BlockTimestamp => "block.timestamp",
+        ContextVariable::BlockDifficulty => "block.difficulty",
+        ContextVariable::PrevRandao => "block.prevrandao",
+        ContextVariable::BlockGasLimit => "block.gaslimit",
+        ContextVariable::BlockCoinbase => "block.coinbase",
+        ContextVariable::ChainId => "block.chainid",
+        ContextVariable::BlockBaseFee => "block.basefee",
+        ContextVariable::BlobBaseFee => "block.blobbasefee",
+        ContextVariable::TxOrigin => "tx.origin",
+        ContextVariable::TxGasPrice => "tx.gasprice",
+        ContextVariable::ThisAddress => "address(this)",
+        ContextVariable::GasLeft | ContextVariable::ThisBalance => return None,
+    })
+}
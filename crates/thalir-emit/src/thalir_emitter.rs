@@ -1,30 +1,80 @@
+use crate::config::{EmitterConfig, FunctionOrder, StorageNaming};
+use crate::ir_formatter_base::IRFormatterBase;
 use anyhow::Result;
+use num_bigint::BigUint;
 use std::collections::HashMap;
 use thalir_core::{
-    analysis::PassManager,
-    block::{BasicBlock, Terminator},
+    analysis::{FunctionSummary, PassManager},
+    block::{BasicBlock, BlockId, Terminator},
     contract::Contract,
-    function::{Function, Mutability, Visibility},
-    instructions::{Instruction, StorageKey},
+    function::Function,
+    instructions::{CallTarget, Instruction, Size, StorageKey},
     types::Type,
-    values::{Constant, Value},
+    values::{BlockParamId, Constant, Location, Value},
     ObfuscationConfig, ObfuscationMapping, ObfuscationPass,
 };
 
-fn format_bytes(bytes: &[u8]) -> String {
+fn accessed_slot(inst: &Instruction) -> Option<BigUint> {
+    match inst {
+        Instruction::StorageLoad { key, .. } | Instruction::StorageStore { key, .. } => match key {
+            StorageKey::Slot(slot)
+            | StorageKey::MappingKey { base: slot, .. }
+            | StorageKey::ArrayElement { base: slot, .. } => Some(slot.clone()),
+            StorageKey::Dynamic(_) | StorageKey::Computed(_) => None,
+        },
+        Instruction::MappingLoad { mapping, .. } | Instruction::MappingStore { mapping, .. } => {
+            match mapping {
+                Value::Constant(Constant::Uint(slot, _)) => Some(slot.clone()),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn storage_variable<'a>(contract: &'a Contract, slot: &BigUint) -> Option<&'a str> {
+    contract
+        .storage_layout
+        .slots
+        .iter()
+        .find(|var| &var.slot == slot)
+        .map(|var| var.name.as_str())
+}
+
+pub(crate) fn format_bytes(bytes: &[u8]) -> String {
     bytes
         .iter()
         .map(|b| format!("{:02x}", b))
         .collect::<String>()
 }
 
+/* Addresses, byte strings and integers too long to read inline are hoisted into the contract's
+ * `const cN` section and referenced by name. */
+fn is_named_constant(constant: &Constant) -> bool {
+    match constant {
+        Constant::Address(_) => true,
+        /* `bconst 0x` has no digits for a declaration to read; the empty string stays inline. */
+        Constant::Bytes(bytes) => !bytes.is_empty(),
+        Constant::Uint(val, _) => val.to_string().len() > 8,
+        Constant::Int(val, _) => val.magnitude().to_string().len() > 8,
+        _ => false,
+    }
+}
+
 pub struct ThalIREmitter {
     pub(crate) contracts: Vec<Contract>,
+    storage_naming: StorageNaming,
+    obfuscated: bool,
+    annotations: bool,
+    summaries: bool,
+    function_order: FunctionOrder,
+    group_functions: bool,
 }
 
 pub struct SSAContext {
     next_value: u32,
     value_map: HashMap<Value, u32>,
+    constant_names: HashMap<Constant, String>,
 }
 
 impl SSAContext {
@@ -32,9 +82,19 @@ impl SSAContext {
         Self {
             next_value: 0,
             value_map: HashMap::new(),
+            constant_names: HashMap::new(),
         }
     }
 
+    /* Contract constants outlive `reset`, which only restarts value numbering per function. */
+    pub fn name_constant(&mut self, constant: Constant, name: String) {
+        self.constant_names.insert(constant, name);
+    }
+
+    pub fn constant_name(&self, constant: &Constant) -> Option<&str> {
+        self.constant_names.get(constant).map(String::as_str)
+    }
+
     pub fn reset(&mut self) {
         self.next_value = 0;
         self.value_map.clear();
@@ -64,7 +124,39 @@ impl SSAContext {
 
 impl ThalIREmitter {
     pub fn new(contracts: Vec<Contract>) -> Self {
-        Self { contracts }
+        Self {
+            contracts,
+            storage_naming: StorageNaming::Slots,
+            obfuscated: false,
+            annotations: false,
+            summaries: false,
+            function_order: FunctionOrder::Source,
+            group_functions: false,
+        }
+    }
+
+    pub fn with_storage_naming(mut self, naming: StorageNaming) -> Self {
+        self.storage_naming = naming;
+        self
+    }
+
+    /* Print results passes attached to blocks and instructions as trailing `;` comments. */
+    pub fn with_annotations(mut self, enabled: bool) -> Self {
+        self.annotations = enabled;
+        self
+    }
+
+    /* Print a `;` header above each function listing the storage it reads and writes, what it
+     * calls and what guards it, so plain IR keeps that context without annotations. */
+    pub fn with_summaries(mut self, enabled: bool) -> Self {
+        self.summaries = enabled;
+        self
+    }
+
+    pub fn with_emitter_config(mut self, config: EmitterConfig) -> Self {
+        self.function_order = config.function_order;
+        self.group_functions = config.group_functions;
+        self.with_summaries(config.include_summaries)
     }
 
     pub fn with_obfuscation(
@@ -86,13 +178,19 @@ impl ThalIREmitter {
             None
         };
 
-        Ok((Self::new(contracts), mapping))
+        let mut emitter = Self::new(contracts);
+        emitter.obfuscated = true;
+        Ok((emitter, mapping))
     }
 
     pub fn emit_to_string(&self, with_types: bool) -> String {
+        let _span =
+            tracing::info_span!("emit", format = "thalir", contracts = self.contracts.len())
+                .entered();
         let mut output = String::new();
 
         for contract in &self.contracts {
+            tracing::debug!(contract = %contract.name, "emitting contract");
             self.print_contract(&mut output, contract, with_types);
         }
 
@@ -115,17 +213,67 @@ impl ThalIREmitter {
         }
 
         let mut ssa = SSAContext::new();
-        for (name, function) in &contract.functions {
+        let named: Vec<Constant> = contract
+            .constant_pool()
+            .iter()
+            .map(|(_, constant, _)| constant)
+            .filter(|constant| is_named_constant(constant))
+            .cloned()
+            .collect();
+        if !named.is_empty() {
+            output.push_str("\n");
+            for (i, constant) in named.into_iter().enumerate() {
+                let name = format!("c{}", i);
+                output.push_str(&format!(
+                    "  const {} = {}\n",
+                    name,
+                    self.format_constant(&constant)
+                ));
+                ssa.name_constant(constant, name);
+            }
+        }
+
+        let mut group = None;
+        for (name, function) in self.function_order.arrange(contract) {
+            if self.group_functions {
+                let current = self.function_order.group(function);
+                if let Some(header) = current.filter(|_| current != group) {
+                    output.push_str(&format!("\n  // {}\n", header));
+                }
+                group = current;
+            }
             output.push_str("\n");
-            self.print_function(output, name, function, &mut ssa, with_types);
+            self.print_function(output, contract, name, function, &mut ssa, with_types);
         }
 
         output.push_str("}\n");
     }
 
+    /* Value numbers as `print_function` assigns them, for listings that show only part of a
+     * function but should name values the way the full listing does. */
+    pub(crate) fn number_function(
+        &self,
+        contract: &Contract,
+        function: &Function,
+    ) -> (SSAContext, Vec<u32>) {
+        let mut ssa = SSAContext::new();
+        let name = &function.signature.name;
+        self.print_function(
+            &mut String::new(),
+            contract,
+            name,
+            function,
+            &mut ssa,
+            false,
+        );
+        let param_vnums = (0..function.signature.params.len() as u32).collect();
+        (ssa, param_vnums)
+    }
+
     fn print_function(
         &self,
         output: &mut String,
+        contract: &Contract,
         name: &str,
         function: &Function,
         ssa: &mut SSAContext,
@@ -159,27 +307,16 @@ impl ThalIREmitter {
             String::new()
         };
 
-        let visibility = match function.visibility {
-            Visibility::Public => "public",
-            Visibility::External => "external",
-            Visibility::Internal => "internal",
-            Visibility::Private => "private",
-        };
-
-        let mutability = match function.mutability {
-            Mutability::Pure => "pure",
-            Mutability::View => "view",
-            Mutability::Payable => "payable",
-            Mutability::NonPayable => "",
-        };
+        if self.summaries {
+            self.print_summary(output, contract, function);
+        }
 
         output.push_str(&format!(
-            "  function %{}({}){} {} {} {{\n",
+            "  function %{}({}){} {} {{\n",
             name,
             param_types.join(", "),
             return_type,
-            visibility,
-            mutability
+            IRFormatterBase::format_function_attributes(function)
         ));
 
         if let Some(entry_block) = function.body.blocks.get(&function.body.entry_block) {
@@ -196,12 +333,36 @@ impl ThalIREmitter {
             }
             output.push_str("):\n");
 
-            self.print_block_body(output, entry_block, ssa, &param_vnums);
+            self.print_block_body(output, contract, entry_block, ssa, &param_vnums);
 
             for (block_id, block) in &function.body.blocks {
                 if block_id != &function.body.entry_block {
-                    output.push_str(&format!("\n  block{}:\n", block.id.0));
-                    self.print_block_body(output, block, ssa, &param_vnums);
+                    if block.params.is_empty() {
+                        output.push_str(&format!("\n  block{}:\n", block.id.0));
+                    } else {
+                        let params: Vec<String> = block
+                            .params
+                            .iter()
+                            .enumerate()
+                            .map(|(index, param)| {
+                                let value = Value::BlockParam(BlockParamId {
+                                    block: *block_id,
+                                    index: index as u32,
+                                });
+                                format!(
+                                    "v{}: {}",
+                                    ssa.get_or_allocate(&value),
+                                    self.format_type(&param.param_type)
+                                )
+                            })
+                            .collect();
+                        output.push_str(&format!(
+                            "\n  block{}({}):\n",
+                            block.id.0,
+                            params.join(", ")
+                        ));
+                    }
+                    self.print_block_body(output, contract, block, ssa, &param_vnums);
                 }
             }
         }
@@ -209,62 +370,267 @@ impl ThalIREmitter {
         output.push_str("  }\n");
     }
 
-    fn print_block_body(
+    fn print_summary(&self, output: &mut String, contract: &Contract, function: &Function) {
+        let summary = FunctionSummary::of_function(contract, function);
+        let slots = |slots: &[BigUint], unresolved: bool| {
+            let mut parts: Vec<String> = slots
+                .iter()
+                .map(|slot| match storage_variable(contract, slot) {
+                    Some(name) => format!("slot {} ({})", slot, name),
+                    None => format!("slot {}", slot),
+                })
+                .collect();
+            if unresolved {
+                parts.push("unresolved".to_string());
+            }
+            parts
+        };
+        let mut guards = summary.guards.modifiers.clone();
+        match summary.guards.caller_checks {
+            0 => {}
+            1 => guards.push("1 caller check".to_string()),
+            n => guards.push(format!("{} caller checks", n)),
+        }
+
+        let lines = [
+            ("reads", slots(&summary.reads, summary.unresolved_reads)),
+            ("writes", slots(&summary.writes, summary.unresolved_writes)),
+            (
+                "calls",
+                summary.calls.iter().map(|call| call.to_string()).collect(),
+            ),
+            ("guards", guards),
+        ];
+        for (label, parts) in lines {
+            let parts = if parts.is_empty() {
+                "none".to_string()
+            } else {
+                parts.join(", ")
+            };
+            output.push_str(&format!("  ; {}: {}\n", label, parts));
+        }
+    }
+
+    pub(crate) fn print_block_body(
         &self,
         output: &mut String,
+        contract: &Contract,
         block: &BasicBlock,
         ssa: &mut SSAContext,
         param_vnums: &[u32],
     ) {
-        for inst in &block.instructions {
-            let inst_str = self.format_instruction(inst, ssa, param_vnums);
-            output.push_str(&format!("    {}\n", inst_str));
+        let annotations = &block.metadata.annotations;
+        let mut rendered = Vec::new();
+        if self.annotations {
+            for (pass, text) in annotations.render_block(&block.instructions) {
+                output.push_str(&format!("    ; {}: {}\n", pass, text));
+            }
+            rendered = annotations.render_instructions(&block.instructions);
         }
 
-        match &block.terminator {
-            Terminator::Return(None) => {
-                output.push_str("    return\n");
-            }
-            Terminator::Return(Some(val)) => {
-                let v = self.format_value(val, ssa, param_vnums);
-                output.push_str(&format!("    return {}\n", v));
-            }
-            Terminator::Jump(target, args) => {
-                if args.is_empty() {
-                    output.push_str(&format!("    jump block{}\n", target.0));
-                } else {
-                    let args_str: Vec<String> = args
-                        .iter()
-                        .map(|v| self.format_value(v, ssa, param_vnums))
-                        .collect();
-                    output.push_str(&format!(
-                        "    jump block{}({})\n",
-                        target.0,
-                        args_str.join(", ")
-                    ));
-                }
+        for (index, inst) in block.instructions.iter().enumerate() {
+            let inst_str = self.format_named_instruction(contract, inst, ssa, param_vnums);
+            let rendered = rendered.get(index).map(Vec::as_slice).unwrap_or_default();
+            if rendered.is_empty() {
+                output.push_str(&format!("    {}\n", inst_str));
+            } else {
+                let notes: Vec<String> = rendered
+                    .iter()
+                    .map(|(pass, text)| format!("{}: {}", pass, text))
+                    .collect();
+                output.push_str(&format!("    {}  ; {}\n", inst_str, notes.join(", ")));
             }
+        }
+
+        let terminator = match &block.terminator {
+            Terminator::Return(value) => self.format_return(value.as_ref(), ssa, param_vnums),
+            Terminator::Jump(target, args) => self.format_jump(*target, args, ssa, param_vnums),
             Terminator::Branch {
                 condition,
                 then_block,
-                else_block,
                 then_args,
+                else_block,
                 else_args,
-            } => {
-                let cond = self.format_value(condition, ssa, param_vnums);
-                if then_args.is_empty() && else_args.is_empty() {
-                    output.push_str(&format!(
-                        "    brz {}, block{}, block{}\n",
-                        cond, else_block.0, then_block.0
-                    ));
-                } else {
-                    output.push_str(&format!(
-                        "    brz {}, block{}, block{}\n",
-                        cond, else_block.0, then_block.0
-                    ));
+            } => self.format_branch(
+                condition,
+                (*then_block, then_args),
+                (*else_block, else_args),
+                ssa,
+                param_vnums,
+            ),
+            _ => return,
+        };
+        output.push_str(&format!("    {}\n", terminator));
+    }
+
+    fn format_return(
+        &self,
+        value: Option<&Value>,
+        ssa: &mut SSAContext,
+        param_vnums: &[u32],
+    ) -> String {
+        match value {
+            Some(value) => format!("return {}", self.format_value(value, ssa, param_vnums)),
+            None => "return".to_string(),
+        }
+    }
+
+    fn format_jump(
+        &self,
+        target: BlockId,
+        args: &[Value],
+        ssa: &mut SSAContext,
+        param_vnums: &[u32],
+    ) -> String {
+        format!(
+            "jump {}",
+            self.format_target(target, args, ssa, param_vnums)
+        )
+    }
+
+    fn format_branch(
+        &self,
+        condition: &Value,
+        (then_block, then_args): (BlockId, &[Value]),
+        (else_block, else_args): (BlockId, &[Value]),
+        ssa: &mut SSAContext,
+        param_vnums: &[u32],
+    ) -> String {
+        let cond = self.format_value(condition, ssa, param_vnums);
+        let else_target = self.format_target(else_block, else_args, ssa, param_vnums);
+        let then_target = self.format_target(then_block, then_args, ssa, param_vnums);
+        format!("brz {}, {}, {}", cond, else_target, then_target)
+    }
+
+    /* A jump target, with the arguments for the block's parameters when it takes any. */
+    fn format_target(
+        &self,
+        block: BlockId,
+        args: &[Value],
+        ssa: &mut SSAContext,
+        param_vnums: &[u32],
+    ) -> String {
+        if args.is_empty() {
+            return format!("block{}", block.0);
+        }
+        let args_str: Vec<String> = args
+            .iter()
+            .map(|v| self.format_value(v, ssa, param_vnums))
+            .collect();
+        format!("block{}({})", block.0, args_str.join(", "))
+    }
+
+    fn effective_storage_naming(&self) -> StorageNaming {
+        match self.storage_naming {
+            StorageNaming::Symbolic if self.obfuscated => StorageNaming::Comments,
+            naming => naming,
+        }
+    }
+
+    fn format_named_instruction(
+        &self,
+        contract: &Contract,
+        inst: &Instruction,
+        ssa: &mut SSAContext,
+        param_vnums: &[u32],
+    ) -> String {
+        match self.effective_storage_naming() {
+            StorageNaming::Slots => self.format_instruction(inst, ssa, param_vnums),
+            StorageNaming::Comments => {
+                let line = self.format_instruction(inst, ssa, param_vnums);
+                match accessed_slot(inst).and_then(|slot| storage_variable(contract, &slot)) {
+                    Some(name) => format!("{}  // {}", line, name),
+                    None => line,
                 }
             }
-            _ => {}
+            StorageNaming::Symbolic => self
+                .format_symbolic_storage(contract, inst, ssa, param_vnums)
+                .unwrap_or_else(|| self.format_instruction(inst, ssa, param_vnums)),
+        }
+    }
+
+    fn format_symbolic_storage(
+        &self,
+        contract: &Contract,
+        inst: &Instruction,
+        ssa: &mut SSAContext,
+        param_vnums: &[u32],
+    ) -> Option<String> {
+        accessed_slot(inst).and_then(|slot| storage_variable(contract, &slot))?;
+
+        let line = match inst {
+            Instruction::StorageStore { key, value } => {
+                let key_v = self.format_symbolic_key(contract, key, ssa);
+                let val_v = self.format_value(value, ssa, param_vnums);
+                format!("sstore {}, {}", key_v, val_v)
+            }
+            Instruction::StorageLoad { result, key } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let key_v = self.format_symbolic_key(contract, key, ssa);
+                format!("v{} = sload {}", result_v, key_v)
+            }
+            Instruction::MappingLoad {
+                result,
+                mapping,
+                key,
+            } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let mapping_v = self.format_symbolic_value(contract, mapping, ssa, param_vnums);
+                let key_v = self.format_value(key, ssa, param_vnums);
+                format!("v{} = mapping_load {}, {}", result_v, mapping_v, key_v)
+            }
+            Instruction::MappingStore {
+                mapping,
+                key,
+                value,
+            } => {
+                let mapping_v = self.format_symbolic_value(contract, mapping, ssa, param_vnums);
+                let key_v = self.format_value(key, ssa, param_vnums);
+                let value_v = self.format_value(value, ssa, param_vnums);
+                format!("mapping_store {}, {}, {}", mapping_v, key_v, value_v)
+            }
+            _ => return None,
+        };
+        Some(line)
+    }
+
+    fn format_symbolic_key(
+        &self,
+        contract: &Contract,
+        key: &StorageKey,
+        ssa: &mut SSAContext,
+    ) -> String {
+        let name = match key {
+            StorageKey::Slot(slot)
+            | StorageKey::MappingKey { base: slot, .. }
+            | StorageKey::ArrayElement { base: slot, .. } => storage_variable(contract, slot),
+            _ => None,
+        };
+        match (key, name) {
+            (StorageKey::Slot(_), Some(name)) => format!("@{}", name),
+            (StorageKey::MappingKey { key, .. }, Some(name)) => {
+                format!("mapping(@{}, {})", name, self.format_value(key, ssa, &[]))
+            }
+            (StorageKey::ArrayElement { index, .. }, Some(name)) => {
+                format!("array(@{}, {})", name, self.format_value(index, ssa, &[]))
+            }
+            _ => self.format_storage_key(key, ssa),
+        }
+    }
+
+    fn format_symbolic_value(
+        &self,
+        contract: &Contract,
+        value: &Value,
+        ssa: &mut SSAContext,
+        param_vnums: &[u32],
+    ) -> String {
+        match value {
+            Value::Constant(Constant::Uint(slot, _)) => match storage_variable(contract, slot) {
+                Some(name) => format!("@{}", name),
+                None => self.format_value(value, ssa, param_vnums),
+            },
+            _ => self.format_value(value, ssa, param_vnums),
         }
     }
 
@@ -326,6 +692,48 @@ impl ThalIREmitter {
                     right_v
                 )
             }
+            Instruction::CheckedAdd {
+                result,
+                left,
+                right,
+                ty,
+            }
+            | Instruction::CheckedSub {
+                result,
+                left,
+                right,
+                ty,
+            }
+            | Instruction::CheckedMul {
+                result,
+                left,
+                right,
+                ty,
+            }
+            | Instruction::CheckedDiv {
+                result,
+                left,
+                right,
+                ty,
+            } => {
+                let op = match inst {
+                    Instruction::CheckedAdd { .. } => "checked_add",
+                    Instruction::CheckedSub { .. } => "checked_sub",
+                    Instruction::CheckedMul { .. } => "checked_mul",
+                    _ => "checked_div",
+                };
+                let result_v = ssa.allocate_temp(result.clone());
+                let left_v = self.format_value(left, ssa, param_vnums);
+                let right_v = self.format_value(right, ssa, param_vnums);
+                format!(
+                    "v{} = {}.{} {}, {}",
+                    result_v,
+                    op,
+                    self.type_suffix(ty),
+                    left_v,
+                    right_v
+                )
+            }
             Instruction::Div {
                 result,
                 left,
@@ -360,16 +768,63 @@ impl ThalIREmitter {
                     right_v
                 )
             }
+            Instruction::Sdiv {
+                result,
+                left,
+                right,
+                ty,
+            } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let left_v = self.format_value(left, ssa, param_vnums);
+                let right_v = self.format_value(right, ssa, param_vnums);
+                format!(
+                    "v{} = sdiv.{} {}, {}",
+                    result_v,
+                    self.type_suffix(ty),
+                    left_v,
+                    right_v
+                )
+            }
+            Instruction::Smod {
+                result,
+                left,
+                right,
+                ty,
+            } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let left_v = self.format_value(left, ssa, param_vnums);
+                let right_v = self.format_value(right, ssa, param_vnums);
+                format!(
+                    "v{} = srem.{} {}, {}",
+                    result_v,
+                    self.type_suffix(ty),
+                    left_v,
+                    right_v
+                )
+            }
             Instruction::StorageStore { key, value } => {
                 let key_v = self.format_storage_key(key, ssa);
                 let val_v = self.format_value(value, ssa, param_vnums);
                 format!("sstore {}, {}", key_v, val_v)
             }
-            Instruction::StorageLoad { result: _, key } => {
-                let result_v = ssa.allocate_new();
+            Instruction::StorageDelete { key } => {
+                format!("sdelete {}", self.format_storage_key(key, ssa))
+            }
+            Instruction::StorageLoad { result, key } => {
+                let result_v = ssa.allocate_temp(result.clone());
                 let key_v = self.format_storage_key(key, ssa);
                 format!("v{} = sload {}", result_v, key_v)
             }
+            Instruction::TransientStore { key, value } => {
+                let key_v = self.format_storage_key(key, ssa);
+                let val_v = self.format_value(value, ssa, param_vnums);
+                format!("tstore {}, {}", key_v, val_v)
+            }
+            Instruction::TransientLoad { result, key } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let key_v = self.format_storage_key(key, ssa);
+                format!("v{} = tload {}", result_v, key_v)
+            }
             Instruction::Eq {
                 result,
                 left,
@@ -430,6 +885,46 @@ impl ThalIREmitter {
                 let right_v = self.format_value(right, ssa, param_vnums);
                 format!("v{} = icmp uge {}, {}", result_v, left_v, right_v)
             }
+            Instruction::Slt {
+                result,
+                left,
+                right,
+            } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let left_v = self.format_value(left, ssa, param_vnums);
+                let right_v = self.format_value(right, ssa, param_vnums);
+                format!("v{} = icmp slt {}, {}", result_v, left_v, right_v)
+            }
+            Instruction::Sle {
+                result,
+                left,
+                right,
+            } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let left_v = self.format_value(left, ssa, param_vnums);
+                let right_v = self.format_value(right, ssa, param_vnums);
+                format!("v{} = icmp sle {}, {}", result_v, left_v, right_v)
+            }
+            Instruction::Sgt {
+                result,
+                left,
+                right,
+            } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let left_v = self.format_value(left, ssa, param_vnums);
+                let right_v = self.format_value(right, ssa, param_vnums);
+                format!("v{} = icmp sgt {}, {}", result_v, left_v, right_v)
+            }
+            Instruction::Sge {
+                result,
+                left,
+                right,
+            } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let left_v = self.format_value(left, ssa, param_vnums);
+                let right_v = self.format_value(right, ssa, param_vnums);
+                format!("v{} = icmp sge {}, {}", result_v, left_v, right_v)
+            }
             Instruction::Select {
                 result,
                 condition,
@@ -446,18 +941,18 @@ impl ThalIREmitter {
                 result,
                 target,
                 args,
-                value: _,
+                value,
             } => {
                 let result_v = ssa.allocate_temp(result.clone());
                 let args_str: Vec<String> = args
                     .iter()
                     .map(|v| self.format_value(v, ssa, param_vnums))
                     .collect();
-                match target {
-                    thalir_core::instructions::CallTarget::Internal(name) => {
+                let call = match target {
+                    CallTarget::Internal(name) => {
                         format!("v{} = call %{}({})", result_v, name, args_str.join(", "))
                     }
-                    thalir_core::instructions::CallTarget::External(addr) => {
+                    CallTarget::External(addr) => {
                         let addr_str = self.format_value(addr, ssa, param_vnums);
                         format!(
                             "v{} = call_ext {}({})",
@@ -466,7 +961,7 @@ impl ThalIREmitter {
                             args_str.join(", ")
                         )
                     }
-                    thalir_core::instructions::CallTarget::Library(name) => {
+                    CallTarget::Library(name) => {
                         format!(
                             "v{} = call_lib %{}({})",
                             result_v,
@@ -474,7 +969,7 @@ impl ThalIREmitter {
                             args_str.join(", ")
                         )
                     }
-                    thalir_core::instructions::CallTarget::Builtin(builtin) => {
+                    CallTarget::Builtin(builtin) => {
                         format!(
                             "v{} = call_builtin {:?}({})",
                             result_v,
@@ -482,8 +977,71 @@ impl ThalIREmitter {
                             args_str.join(", ")
                         )
                     }
+                };
+                match value {
+                    Some(value) => {
+                        format!(
+                            "{} value {}",
+                            call,
+                            self.format_value(value, ssa, param_vnums)
+                        )
+                    }
+                    None => call,
                 }
             }
+            Instruction::DelegateCall {
+                result,
+                target,
+                selector,
+                args,
+            }
+            | Instruction::StaticCall {
+                result,
+                target,
+                selector,
+                args,
+            } => {
+                let op = match inst {
+                    Instruction::DelegateCall { .. } => "delegatecall",
+                    _ => "staticcall",
+                };
+                let result_v = ssa.allocate_temp(result.clone());
+                let mut operands = vec![
+                    self.format_value(target, ssa, param_vnums),
+                    self.format_value(selector, ssa, param_vnums),
+                ];
+                operands.extend(args.iter().map(|v| self.format_value(v, ssa, param_vnums)));
+                format!("v{} = {} {}", result_v, op, operands.join(", "))
+            }
+            Instruction::Create {
+                result,
+                code,
+                value,
+            } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let code_v = self.format_value(code, ssa, param_vnums);
+                let value_v = self.format_value(value, ssa, param_vnums);
+                format!("v{} = create {}, {}", result_v, code_v, value_v)
+            }
+            Instruction::Create2 {
+                result,
+                code,
+                salt,
+                value,
+            } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let code_v = self.format_value(code, ssa, param_vnums);
+                let salt_v = self.format_value(salt, ssa, param_vnums);
+                let value_v = self.format_value(value, ssa, param_vnums);
+                format!(
+                    "v{} = create2 {}, {}, {}",
+                    result_v, code_v, salt_v, value_v
+                )
+            }
+            Instruction::Selfdestruct { beneficiary } => {
+                let beneficiary_v = self.format_value(beneficiary, ssa, param_vnums);
+                format!("selfdestruct {}", beneficiary_v)
+            }
             Instruction::GetContext { result, var } => {
                 let result_v = ssa.allocate_temp(result.clone());
                 let var_name = match var {
@@ -496,13 +1054,105 @@ impl ThalIREmitter {
                     thalir_core::instructions::ContextVariable::BlockDifficulty => {
                         "block.difficulty"
                     }
+                    thalir_core::instructions::ContextVariable::PrevRandao => "block.prevrandao",
                     thalir_core::instructions::ContextVariable::BlockGasLimit => "block.gaslimit",
                     thalir_core::instructions::ContextVariable::BlockCoinbase => "block.coinbase",
                     thalir_core::instructions::ContextVariable::ChainId => "chain.id",
-                    _ => "unknown",
+                    thalir_core::instructions::ContextVariable::BlockBaseFee => "block.basefee",
+                    thalir_core::instructions::ContextVariable::BlobBaseFee => "block.blobbasefee",
+                    thalir_core::instructions::ContextVariable::TxOrigin => "tx.origin",
+                    thalir_core::instructions::ContextVariable::TxGasPrice => "tx.gasprice",
+                    thalir_core::instructions::ContextVariable::GasLeft => "gasleft",
+                    thalir_core::instructions::ContextVariable::ThisAddress => "address(this)",
+                    thalir_core::instructions::ContextVariable::ThisBalance => {
+                        "address(this).balance"
+                    }
                 };
                 format!("v{} = get_context {}", result_v, var_name)
             }
+            Instruction::GetBalance { result, address } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let address_v = self.format_value(address, ssa, param_vnums);
+                format!("v{} = balance {}", result_v, address_v)
+            }
+            Instruction::GetCode { result, address }
+            | Instruction::GetCodeSize { result, address }
+            | Instruction::GetCodeHash { result, address } => {
+                let op = match inst {
+                    Instruction::GetCode { .. } => "get_code",
+                    Instruction::GetCodeSize { .. } => "get_codesize",
+                    _ => "get_codehash",
+                };
+                let result_v = ssa.allocate_temp(result.clone());
+                let address_v = self.format_value(address, ssa, param_vnums);
+                format!("v{} = {} {}", result_v, op, address_v)
+            }
+            Instruction::Keccak256 { result, data, len }
+            | Instruction::Sha256 { result, data, len }
+            | Instruction::Ripemd160 { result, data, len } => {
+                let op = match inst {
+                    Instruction::Keccak256 { .. } => "keccak256",
+                    Instruction::Sha256 { .. } => "sha256",
+                    _ => "ripemd160",
+                };
+                let result_v = ssa.allocate_temp(result.clone());
+                let data_v = self.format_value(data, ssa, param_vnums);
+                let len_v = self.format_value(len, ssa, param_vnums);
+                format!("v{} = {} {}, {}", result_v, op, data_v, len_v)
+            }
+            Instruction::EcRecover {
+                result,
+                hash,
+                v,
+                r,
+                s,
+            } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let operands: Vec<String> = [hash, v, r, s]
+                    .into_iter()
+                    .map(|value| self.format_value(value, ssa, param_vnums))
+                    .collect();
+                format!("v{} = ecrecover {}", result_v, operands.join(", "))
+            }
+            Instruction::MemoryAlloc { result, size } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let size_v = self.format_value(size, ssa, param_vnums);
+                format!("v{} = malloc {}", result_v, size_v)
+            }
+            Instruction::MemorySize { result } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                format!("v{} = msize", result_v)
+            }
+            Instruction::MemoryCopy { dest, src, size } => {
+                let dest_v = self.format_value(dest, ssa, param_vnums);
+                let src_v = self.format_value(src, ssa, param_vnums);
+                let size_v = self.format_value(size, ssa, param_vnums);
+                format!("mcopy {}, {}, {}", dest_v, src_v, size_v)
+            }
+            Instruction::Load { result, location } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let location_s = self.format_location(location, ssa, param_vnums);
+                format!("v{} = load {}", result_v, location_s)
+            }
+            Instruction::Store { location, value } => {
+                let value_v = self.format_value(value, ssa, param_vnums);
+                let location_s = self.format_location(location, ssa, param_vnums);
+                format!("store {}, {}", value_v, location_s)
+            }
+            Instruction::Allocate { result, ty, size } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let size_s = match size {
+                    Size::Static(size) => size.to_string(),
+                    Size::Dynamic(value) => self.format_value(value, ssa, param_vnums),
+                };
+                format!("v{} = alloca.{} {}", result_v, self.format_type(ty), size_s)
+            }
+            Instruction::Copy { dest, src, size } => {
+                let dest_s = self.format_location(dest, ssa, param_vnums);
+                let src_s = self.format_location(src, ssa, param_vnums);
+                let size_v = self.format_value(size, ssa, param_vnums);
+                format!("copy {}, {}, {}", dest_s, src_s, size_v)
+            }
             Instruction::Assert { condition, message } => {
                 let cond = self.format_value(condition, ssa, param_vnums);
                 format!("assert {}, \"{}\"", cond, message)
@@ -645,7 +1295,109 @@ impl ThalIREmitter {
                 let shift_v = self.format_value(shift, ssa, param_vnums);
                 format!("v{} = ushr {}, {}", result_v, value_v, shift_v)
             }
-            _ => format!("{:?}", inst),
+            Instruction::Sar {
+                result,
+                value,
+                shift,
+            } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let value_v = self.format_value(value, ssa, param_vnums);
+                let shift_v = self.format_value(shift, ssa, param_vnums);
+                format!("v{} = sshr {}, {}", result_v, value_v, shift_v)
+            }
+            Instruction::Pow { result, base, exp } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let base_v = self.format_value(base, ssa, param_vnums);
+                let exp_v = self.format_value(exp, ssa, param_vnums);
+                format!("v{} = pow {}, {}", result_v, base_v, exp_v)
+            }
+            Instruction::Not { result, operand } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let operand_v = self.format_value(operand, ssa, param_vnums);
+                format!("v{} = bnot {}", result_v, operand_v)
+            }
+            Instruction::Cast { result, value, to }
+            | Instruction::ZeroExtend { result, value, to }
+            | Instruction::SignExtend { result, value, to }
+            | Instruction::Truncate { result, value, to } => {
+                let op = match inst {
+                    Instruction::Cast { .. } => "cast",
+                    Instruction::ZeroExtend { .. } => "uextend",
+                    Instruction::SignExtend { .. } => "sextend",
+                    _ => "ireduce",
+                };
+                let result_v = ssa.allocate_temp(result.clone());
+                let value_v = self.format_value(value, ssa, param_vnums);
+                format!(
+                    "v{} = {}.{} {}",
+                    result_v,
+                    op,
+                    self.format_type(to),
+                    value_v
+                )
+            }
+            Instruction::Assign { result, value } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let value_v = self.format_value(value, ssa, param_vnums);
+                format!("v{} = assign {}", result_v, value_v)
+            }
+            Instruction::Phi { result, values } => {
+                let result_v = ssa.allocate_temp(result.clone());
+                let incoming: Vec<String> = values
+                    .iter()
+                    .map(|(block, value)| {
+                        format!(
+                            "block{}({})",
+                            block.0,
+                            self.format_value(value, ssa, param_vnums)
+                        )
+                    })
+                    .collect();
+                format!("v{} = phi {}", result_v, incoming.join(", "))
+            }
+            Instruction::Jump { target, args } => self.format_jump(*target, args, ssa, param_vnums),
+            Instruction::Branch {
+                condition,
+                then_block,
+                then_args,
+                else_block,
+                else_args,
+            } => self.format_branch(
+                condition,
+                (*then_block, then_args),
+                (*else_block, else_args),
+                ssa,
+                param_vnums,
+            ),
+            Instruction::Return { value } => self.format_return(value.as_ref(), ssa, param_vnums),
+        }
+    }
+
+    fn format_location(
+        &self,
+        location: &Location,
+        ssa: &mut SSAContext,
+        param_vnums: &[u32],
+    ) -> String {
+        match location {
+            Location::Stack { offset } => format!("stack({})", offset),
+            Location::Memory { base, offset } => format!(
+                "memory({}, {})",
+                self.format_value(base, ssa, param_vnums),
+                self.format_value(offset, ssa, param_vnums)
+            ),
+            Location::Storage { slot } => {
+                format!("storage({})", self.format_value(slot, ssa, param_vnums))
+            }
+            Location::Calldata { offset } => {
+                format!("calldata({})", self.format_value(offset, ssa, param_vnums))
+            }
+            Location::ReturnData { offset } => {
+                format!(
+                    "returndata({})",
+                    self.format_value(offset, ssa, param_vnums)
+                )
+            }
         }
     }
 
@@ -680,7 +1432,10 @@ impl ThalIREmitter {
                 format!("reg_{}", ssa.get_or_allocate(value))
             }
             Value::Undefined => "undefined".to_string(),
-            Value::Constant(c) => self.format_constant(c),
+            Value::Constant(c) => match ssa.constant_name(c) {
+                Some(name) => name.to_string(),
+                None => self.format_constant(c),
+            },
         }
     }
 
@@ -0,0 +1,697 @@
+use crate::thalir_emitter::{format_bytes, SSAContext, ThalIREmitter};
+use anyhow::{anyhow, bail, Result};
+use num_bigint::BigUint;
+use std::collections::HashMap;
+use thalir_core::{
+    abi::{self, AbiType, AbiValue},
+    block::{BlockId, Terminator},
+    contract::Contract,
+    function::Function,
+    hash,
+    instructions::Instruction,
+    types::{Type, TypeRegistry},
+    values::{BlockParamId, Constant, ParamId, Value},
+};
+
+/* Prints one path through a function's CFG as a straight-line listing, the shape a symbolic
+ * executor or a reviewer walking a bug by hand would describe it in. Values that can be folded
+ * from constants and the supplied inputs are shown next to the instruction that defines them,
+ * and every branch along the way contributes a path condition. */
+pub struct TraceEmitter {
+    contracts: Vec<Contract>,
+    formatter: ThalIREmitter,
+    inputs: HashMap<ParamId, Constant>,
+    calldata: Option<Vec<u8>>,
+}
+
+impl TraceEmitter {
+    pub fn new(contracts: Vec<Contract>) -> Self {
+        Self {
+            contracts,
+            formatter: ThalIREmitter::new(Vec::new()),
+            inputs: HashMap::new(),
+            calldata: None,
+        }
+    }
+
+    /* Concrete value for the function's `index`th parameter, typically taken from a solver model. */
+    pub fn with_input(mut self, index: u32, value: Constant) -> Self {
+        self.inputs.insert(ParamId(index), value);
+        self
+    }
+
+    /* Raw calldata for the call being traced. The selector must name the traced function, and the
+     * decoded arguments take the place of any `with_input` values for the same parameters. */
+    pub fn with_calldata(mut self, data: Vec<u8>) -> Self {
+        self.calldata = Some(data);
+        self
+    }
+
+    /* Reads an input as written on the command line: `true`/`false`, a 0x-prefixed address or
+     * byte string, or a decimal uint256. */
+    pub fn parse_input(text: &str) -> Result<Constant> {
+        match text {
+            "true" => return Ok(Constant::Bool(true)),
+            "false" => return Ok(Constant::Bool(false)),
+            _ => {}
+        }
+        if text.starts_with("0x") {
+            let bytes = Self::parse_calldata(text)?;
+            return Ok(match <[u8; 20]>::try_from(bytes.as_slice()) {
+                Ok(addr) => Constant::Address(addr),
+                Err(_) => Constant::Bytes(bytes),
+            });
+        }
+        BigUint::parse_bytes(text.as_bytes(), 10)
+            .map(|val| Constant::Uint(val, 256))
+            .ok_or_else(|| anyhow!("Invalid input `{}`", text))
+    }
+
+    /* A 0x-prefixed hex string, as calldata is usually copied out of a transaction. */
+    pub fn parse_calldata(text: &str) -> Result<Vec<u8>> {
+        let hex = text
+            .strip_prefix("0x")
+            .filter(|hex| hex.len() % 2 == 0 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| anyhow!("Invalid hex input `{}`", text))?;
+        Ok((0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect())
+    }
+
+    pub fn trace(&self, contract: &str, function: &str, path: &[BlockId]) -> Result<String> {
+        let contract = self
+            .contracts
+            .iter()
+            .find(|c| c.name == contract)
+            .ok_or_else(|| anyhow!("Unknown contract `{}`", contract))?;
+        let func = contract
+            .functions
+            .get(function)
+            .ok_or_else(|| anyhow!("Unknown function `{}::{}`", contract.name, function))?;
+        validate_path(func, path)?;
+
+        let (mut ssa, param_vnums) = self.formatter.number_function(contract, func);
+
+        let mut bindings = Bindings::default();
+        for (param, value) in &self.inputs {
+            bindings.bind(Value::Param(*param), Some(value.clone()));
+        }
+        if let Some(data) = &self.calldata {
+            for (index, value) in decode_calldata(func, data)?.into_iter().enumerate() {
+                bindings.bind(Value::Param(ParamId(index as u32)), value);
+            }
+        }
+        let mut conditions = Vec::new();
+
+        let mut output = format!(
+            "trace {}::{} ({} steps)\n",
+            contract.name,
+            function,
+            path.len()
+        );
+        for (index, param) in func.signature.params.iter().enumerate() {
+            if let Some(value) = bindings.get(&Value::Param(ParamId(index as u32))) {
+                output.push_str(&format!(
+                    "  ; input v{} ({}) = {}\n",
+                    param_vnums[index],
+                    param.name,
+                    render(&value)
+                ));
+            }
+        }
+
+        'path: for (step, block_id) in path.iter().enumerate() {
+            let block = &func.body.blocks[block_id];
+            let previous = step.checked_sub(1).map(|i| path[i]);
+            let next = path.get(step + 1).copied();
+            output.push_str(&format!("\n  [{}] {}:\n", step, block_id));
+
+            for inst in &block.instructions {
+                let text = self
+                    .formatter
+                    .format_instruction(inst, &mut ssa, &param_vnums);
+                let note = match inst {
+                    Instruction::Require { condition, .. }
+                    | Instruction::Assert { condition, .. } => match bindings.truth(condition) {
+                        /* Nothing after a failing check runs, so the rest of the path is not
+                         * listed as if it did. */
+                        Some(false) => {
+                            output.push_str(&line(&text, Some("fails".to_string())));
+                            output.push_str(&format!(
+                                "    ; reverts here; {} of {} steps not reached\n",
+                                path.len() - step - 1,
+                                path.len()
+                            ));
+                            break 'path;
+                        }
+                        Some(true) => Some("holds".to_string()),
+                        None => None,
+                    },
+                    _ => inst.result().cloned().and_then(|result| {
+                        let value = bindings.evaluate(inst, previous);
+                        bindings.bind(result.clone(), value.clone());
+                        value.map(|value| {
+                            format!(
+                                "{} = {}",
+                                self.formatter.format_value(&result, &mut ssa, &param_vnums),
+                                render(&value)
+                            )
+                        })
+                    }),
+                };
+                output.push_str(&line(&text, note));
+            }
+
+            let (text, mut note) = self.terminator_step(
+                &block.terminator,
+                next,
+                &mut bindings,
+                &mut conditions,
+                &mut ssa,
+                &param_vnums,
+            );
+            if let (Terminator::Return(Some(value)), Some(note)) = (&block.terminator, &mut note) {
+                let encoded = bindings
+                    .get(value)
+                    .and_then(|constant| return_data(&func.signature.returns, &constant));
+                if let Some(data) = encoded {
+                    note.push_str(&format!(", returndata 0x{}", format_bytes(&data)));
+                }
+            }
+            output.push_str(&line(&text, note));
+        }
+
+        if !conditions.is_empty() {
+            output.push_str("\n  path conditions:\n");
+            for condition in &conditions {
+                output.push_str(&format!("    {}\n", condition));
+            }
+        }
+
+        Ok(output)
+    }
+
+    fn terminator_step(
+        &self,
+        terminator: &Terminator,
+        next: Option<BlockId>,
+        bindings: &mut Bindings,
+        conditions: &mut Vec<String>,
+        ssa: &mut SSAContext,
+        param_vnums: &[u32],
+    ) -> (String, Option<String>) {
+        let mut fmt = |value: &Value| self.formatter.format_value(value, ssa, param_vnums);
+
+        match terminator {
+            Terminator::Jump(target, args) => {
+                let text = if args.is_empty() {
+                    format!("jump {}", target)
+                } else {
+                    let args_str: Vec<String> = args.iter().map(&mut fmt).collect();
+                    format!("jump {}({})", target, args_str.join(", "))
+                };
+                bindings.pass_args(*target, args);
+                (text, None)
+            }
+            Terminator::Branch {
+                condition,
+                then_block,
+                then_args,
+                else_block,
+                else_args,
+            } => {
+                let cond = fmt(condition);
+                let text = format!("brz {}, {}, {}", cond, else_block, then_block);
+                let Some(taken) = next else {
+                    return (text, None);
+                };
+                let expected = taken == *then_block;
+                bindings.pass_args(taken, if expected { then_args } else { else_args });
+                conditions.push(format!("{} == {}", cond, expected));
+
+                let note = match bindings.truth(condition) {
+                    Some(actual) if actual != expected => {
+                        format!("taken -> {} (infeasible: {} = {})", taken, cond, actual)
+                    }
+                    _ => format!("taken -> {}", taken),
+                };
+                (text, Some(note))
+            }
+            Terminator::Switch {
+                value,
+                default,
+                cases,
+            } => {
+                let scrutinee = fmt(value);
+                let text = format!("switch {}, default {}", scrutinee, default);
+                let Some(taken) = next else {
+                    return (text, None);
+                };
+                match cases.iter().find(|(_, target)| *target == taken) {
+                    Some((case, _)) => {
+                        conditions.push(format!("{} == {}", scrutinee, fmt(case)));
+                    }
+                    None => {
+                        for (case, _) in cases {
+                            conditions.push(format!("{} != {}", scrutinee, fmt(case)));
+                        }
+                    }
+                }
+                (text, Some(format!("taken -> {}", taken)))
+            }
+            Terminator::Return(None) => ("return".to_string(), None),
+            Terminator::Return(Some(value)) => {
+                let text = format!("return {}", fmt(value));
+                let note = bindings
+                    .get(value)
+                    .map(|constant| format!("{} = {}", fmt(value), render(&constant)));
+                (text, note)
+            }
+            Terminator::Revert(message) => (format!("revert \"{}\"", message), None),
+            Terminator::Panic(message) => (format!("panic \"{}\"", message), None),
+            Terminator::Invalid => ("invalid".to_string(), None),
+        }
+    }
+}
+
+fn validate_path(function: &Function, path: &[BlockId]) -> Result<()> {
+    if path.is_empty() {
+        bail!("Trace path for `{}` is empty", function.signature.name);
+    }
+    for block in path {
+        if !function.body.blocks.contains_key(block) {
+            bail!("{} does not exist in `{}`", block, function.signature.name);
+        }
+    }
+    for pair in path.windows(2) {
+        let successors = function.body.blocks[&pair[0]].terminator.successors();
+        if !successors.contains(&pair[1]) {
+            bail!("{} is not a successor of {}", pair[1], pair[0]);
+        }
+    }
+    Ok(())
+}
+
+/* One entry per parameter; `None` where the IR value has no constant form (arrays, tuples). */
+fn decode_calldata(function: &Function, data: &[u8]) -> Result<Vec<Option<Constant>>> {
+    let name = &function.signature.name;
+    let types = function
+        .signature
+        .params
+        .iter()
+        .map(|param| {
+            AbiType::from_ir(&param.param_type, &TypeRegistry::default()).ok_or_else(|| {
+                anyhow!(
+                    "Parameter `{}` of `{}` has no ABI encoding",
+                    param.name,
+                    name
+                )
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let signature = abi::signature(name, &types);
+    if data.len() < 4 || data[..4] != hash::selector(&signature) {
+        bail!(
+            "Calldata does not start with the selector of `{}`",
+            signature
+        );
+    }
+    let values = abi::decode(&types, &data[4..])
+        .map_err(|error| anyhow!("Calldata for `{}`: {}", signature, error))?;
+    Ok(types
+        .iter()
+        .zip(values)
+        .map(|(ty, value)| constant_from_abi(ty, value))
+        .collect())
+}
+
+fn constant_from_abi(ty: &AbiType, value: AbiValue) -> Option<Constant> {
+    Some(match (ty, value) {
+        (AbiType::Uint(bits), AbiValue::Uint(n)) => Constant::Uint(n, *bits),
+        (AbiType::Int(bits), AbiValue::Int(n)) => Constant::Int(n, *bits),
+        (_, AbiValue::Address(addr)) => Constant::Address(addr),
+        (_, AbiValue::Bool(b)) => Constant::Bool(b),
+        (_, AbiValue::FixedBytes(bytes) | AbiValue::Bytes(bytes)) => Constant::Bytes(bytes),
+        (_, AbiValue::String(s)) => Constant::String(s),
+        _ => return None,
+    })
+}
+
+/* Return data for a single known return value, encoded as the function's declared return type. */
+fn return_data(returns: &[Type], constant: &Constant) -> Option<Vec<u8>> {
+    let [ty] = returns else {
+        return None;
+    };
+    let ty = AbiType::from_ir(ty, &TypeRegistry::default())?;
+    let value = match (&ty, constant) {
+        (AbiType::Uint(_), Constant::Uint(n, _)) => AbiValue::Uint(n.clone()),
+        (AbiType::Int(_), Constant::Int(n, _)) => AbiValue::Int(n.clone()),
+        (AbiType::Address, Constant::Address(addr)) => AbiValue::Address(*addr),
+        (AbiType::Bool, Constant::Bool(b)) => AbiValue::Bool(*b),
+        (AbiType::FixedBytes(_), Constant::Bytes(bytes)) => AbiValue::FixedBytes(bytes.clone()),
+        (AbiType::Bytes, Constant::Bytes(bytes)) => AbiValue::Bytes(bytes.clone()),
+        (AbiType::String, Constant::String(s)) => AbiValue::String(s.clone()),
+        _ => return None,
+    };
+    abi::encode(&[ty], &[value]).ok()
+}
+
+fn line(text: &str, note: Option<String>) -> String {
+    match note {
+        Some(note) => format!("    {:<40}  ; {}\n", text, note),
+        None => format!("    {}\n", text),
+    }
+}
+
+fn render(constant: &Constant) -> String {
+    match constant {
+        Constant::Uint(val, _) => val.to_string(),
+        Constant::Int(val, _) => val.to_string(),
+        Constant::Bool(b) => b.to_string(),
+        Constant::Address(addr) => format!("0x{}", format_bytes(addr)),
+        Constant::Bytes(bytes) => format!("0x{}", format_bytes(bytes)),
+        Constant::String(s) => format!("\"{}\"", s),
+        Constant::Null => "null".to_string(),
+    }
+}
+
+/* Values known at the current point of the trace. Only unsigned and boolean arithmetic is folded;
+ * anything else, and anything a checked operation would revert on, stays unknown. */
+#[derive(Default)]
+struct Bindings {
+    known: HashMap<Value, Constant>,
+}
+
+impl Bindings {
+    fn get(&self, value: &Value) -> Option<Constant> {
+        match value {
+            Value::Constant(constant) => Some(constant.clone()),
+            other => self.known.get(other).cloned(),
+        }
+    }
+
+    /* Loops revisit blocks, so an unknown result must clear what an earlier visit bound. */
+    fn bind(&mut self, value: Value, constant: Option<Constant>) {
+        match constant {
+            Some(constant) => {
+                self.known.insert(value, constant);
+            }
+            None => {
+                self.known.remove(&value);
+            }
+        }
+    }
+
+    fn pass_args(&mut self, target: BlockId, args: &[Value]) {
+        let values: Vec<Option<Constant>> = args.iter().map(|arg| self.get(arg)).collect();
+        for (index, value) in values.into_iter().enumerate() {
+            let param = Value::BlockParam(BlockParamId {
+                block: target,
+                index: index as u32,
+            });
+            self.bind(param, value);
+        }
+    }
+
+    fn uint(&self, value: &Value) -> Option<BigUint> {
+        match self.get(value)? {
+            Constant::Uint(val, _) => Some(val),
+            Constant::Bool(b) => Some(BigUint::from(b as u8)),
+            Constant::Address(addr) => Some(BigUint::from_bytes_be(&addr)),
+            _ => None,
+        }
+    }
+
+    fn truth(&self, value: &Value) -> Option<bool> {
+        match self.get(value)? {
+            Constant::Bool(b) => Some(b),
+            Constant::Uint(val, _) => Some(val.bits() > 0),
+            _ => None,
+        }
+    }
+
+    fn evaluate(&self, inst: &Instruction, previous: Option<BlockId>) -> Option<Constant> {
+        match inst {
+            Instruction::Add {
+                left, right, ty, ..
+            } => self.arith(left, right, ty, false, |a, b, _| Some(a + b)),
+            Instruction::Sub {
+                left, right, ty, ..
+            } => self.arith(left, right, ty, false, |a, b, m| Some(a + m - b)),
+            Instruction::Mul {
+                left, right, ty, ..
+            } => self.arith(left, right, ty, false, |a, b, _| Some(a * b)),
+            Instruction::Div {
+                left, right, ty, ..
+            }
+            | Instruction::CheckedDiv {
+                left, right, ty, ..
+            } => self.arith(left, right, ty, false, |a, b, _| {
+                (b.bits() > 0).then(|| a / b)
+            }),
+            Instruction::Mod {
+                left, right, ty, ..
+            } => self.arith(left, right, ty, false, |a, b, _| {
+                (b.bits() > 0).then(|| a % b)
+            }),
+            Instruction::CheckedAdd {
+                left, right, ty, ..
+            } => self.arith(left, right, ty, true, |a, b, _| Some(a + b)),
+            Instruction::CheckedSub {
+                left, right, ty, ..
+            } => self.arith(left, right, ty, true, |a, b, _| (a >= b).then(|| a - b)),
+            Instruction::CheckedMul {
+                left, right, ty, ..
+            } => self.arith(left, right, ty, true, |a, b, _| Some(a * b)),
+
+            Instruction::Eq { left, right, .. } => self.equal(left, right).map(Constant::Bool),
+            Instruction::Ne { left, right, .. } => {
+                self.equal(left, right).map(|eq| Constant::Bool(!eq))
+            }
+            Instruction::Lt { left, right, .. } => self.compare(left, right, |a, b| a < b),
+            Instruction::Gt { left, right, .. } => self.compare(left, right, |a, b| a > b),
+            Instruction::Le { left, right, .. } => self.compare(left, right, |a, b| a <= b),
+            Instruction::Ge { left, right, .. } => self.compare(left, right, |a, b| a >= b),
+
+            Instruction::And { left, right, .. } => {
+                self.bitwise(left, right, |a, b| a && b, |a, b| a & b)
+            }
+            Instruction::Or { left, right, .. } => {
+                self.bitwise(left, right, |a, b| a || b, |a, b| a | b)
+            }
+            Instruction::Xor { left, right, .. } => {
+                self.bitwise(left, right, |a, b| a != b, |a, b| a ^ b)
+            }
+            Instruction::Not { operand, .. } => match self.get(operand)? {
+                Constant::Bool(b) => Some(Constant::Bool(!b)),
+                _ => None,
+            },
+
+            Instruction::Select {
+                condition,
+                then_val,
+                else_val,
+                ..
+            } => {
+                if self.truth(condition)? {
+                    self.get(then_val)
+                } else {
+                    self.get(else_val)
+                }
+            }
+            Instruction::Assign { value, .. } => self.get(value),
+            Instruction::Phi { values, .. } => values
+                .iter()
+                .find(|(block, _)| Some(*block) == previous)
+                .and_then(|(_, value)| self.get(value)),
+            _ => None,
+        }
+    }
+
+    fn arith(
+        &self,
+        left: &Value,
+        right: &Value,
+        ty: &Type,
+        checked: bool,
+        op: impl Fn(&BigUint, &BigUint, &BigUint) -> Option<BigUint>,
+    ) -> Option<Constant> {
+        let Type::Uint(bits) = *ty else {
+            return None;
+        };
+        let modulus = BigUint::from(1u32) << bits as usize;
+        let a = self.uint(left)? % &modulus;
+        let b = self.uint(right)? % &modulus;
+        let raw = op(&a, &b, &modulus)?;
+        if checked && raw >= modulus {
+            return None;
+        }
+        Some(Constant::Uint(raw % modulus, bits))
+    }
+
+    fn equal(&self, left: &Value, right: &Value) -> Option<bool> {
+        match (self.uint(left), self.uint(right)) {
+            (Some(a), Some(b)) => Some(a == b),
+            _ => Some(self.get(left)? == self.get(right)?),
+        }
+    }
+
+    fn compare(
+        &self,
+        left: &Value,
+        right: &Value,
+        op: impl Fn(&BigUint, &BigUint) -> bool,
+    ) -> Option<Constant> {
+        Some(Constant::Bool(op(&self.uint(left)?, &self.uint(right)?)))
+    }
+
+    fn bitwise(
+        &self,
+        left: &Value,
+        right: &Value,
+        logical: impl Fn(bool, bool) -> bool,
+        bits: impl Fn(&BigUint, &BigUint) -> BigUint,
+    ) -> Option<Constant> {
+        match (self.get(left)?, self.get(right)?) {
+            (Constant::Bool(a), Constant::Bool(b)) => Some(Constant::Bool(logical(a, b))),
+            (Constant::Uint(a, width), Constant::Uint(b, _)) => {
+                Some(Constant::Uint(bits(&a, &b), width))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use thalir_core::builder::IRBuilder;
+
+    fn guarded_increment() -> Contract {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Counter");
+        let mut func_builder = contract_builder.function("bump");
+        func_builder.param("x", Type::Uint(256));
+        func_builder.returns(Type::Uint(256));
+        let x = func_builder.get_param(0);
+
+        let entry = func_builder.entry_block().block_id();
+        let small = func_builder.create_block_id();
+        let big = func_builder.create_block_id();
+
+        let mut entry_builder = func_builder.switch_to_block(entry).unwrap();
+        let limit = entry_builder.constant_uint(10, 256);
+        let is_small = entry_builder.lt(x.clone(), limit);
+        entry_builder.branch(is_small, small, big).unwrap();
+
+        let mut small_builder = func_builder.switch_to_block(small).unwrap();
+        let one = small_builder.constant_uint(1, 256);
+        let next = small_builder.add(x, one, Type::Uint(256));
+        small_builder.return_value(next).unwrap();
+
+        let mut big_builder = func_builder.switch_to_block(big).unwrap();
+        big_builder.revert("too big").unwrap();
+
+        func_builder.build().unwrap();
+        contract_builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_trace_resolves_values_and_records_branch() {
+        let emitter = TraceEmitter::new(vec![guarded_increment()])
+            .with_input(0, Constant::Uint(BigUint::from(3u32), 256));
+        let trace = emitter
+            .trace("Counter", "bump", &[BlockId(0), BlockId(1)])
+            .unwrap();
+
+        assert!(trace.contains("[0] block0:"));
+        assert!(trace.contains("[1] block1:"));
+        assert!(trace.contains("= true"));
+        assert!(trace.contains("taken -> block1"));
+        assert!(trace.contains("= 4"));
+        assert!(trace.contains("path conditions:"));
+        assert!(!trace.contains("infeasible"));
+
+        let infeasible = emitter
+            .trace("Counter", "bump", &[BlockId(0), BlockId(2)])
+            .unwrap();
+        assert!(infeasible.contains("infeasible"));
+        assert!(emitter
+            .trace("Counter", "bump", &[BlockId(1), BlockId(2)])
+            .is_err());
+    }
+
+    #[test]
+    fn test_trace_numbers_values_like_the_full_listing() {
+        let contract = guarded_increment();
+        let full = ThalIREmitter::new(vec![contract.clone()]).emit_to_string(false);
+        let add = full
+            .lines()
+            .map(str::trim)
+            .find(|line| line.contains("iadd"))
+            .unwrap()
+            .to_string();
+
+        let trace = TraceEmitter::new(vec![contract])
+            .trace("Counter", "bump", &[BlockId(0), BlockId(1)])
+            .unwrap();
+        assert!(trace.contains(&add), "{} not in\n{}", add, trace);
+    }
+
+    #[test]
+    fn test_trace_stops_at_failing_require() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Counter");
+        let mut func_builder = contract_builder.function("bump");
+        func_builder.param("x", Type::Uint(256));
+        let x = func_builder.get_param(0);
+
+        let entry = func_builder.entry_block().block_id();
+        let done = func_builder.create_block_id();
+
+        let mut entry_builder = func_builder.switch_to_block(entry).unwrap();
+        let limit = entry_builder.constant_uint(10, 256);
+        let is_small = entry_builder.lt(x, limit);
+        entry_builder.require(is_small, "too big");
+        entry_builder.jump(done).unwrap();
+
+        let mut done_builder = func_builder.switch_to_block(done).unwrap();
+        done_builder.return_void().unwrap();
+
+        func_builder.build().unwrap();
+        let contract = contract_builder.build().unwrap();
+
+        let trace = TraceEmitter::new(vec![contract])
+            .with_input(0, Constant::Uint(BigUint::from(20u32), 256))
+            .trace("Counter", "bump", &[BlockId(0), BlockId(1)])
+            .unwrap();
+        assert!(trace.contains("fails"));
+        assert!(trace.contains("reverts here; 1 of 2 steps not reached"));
+        assert!(!trace.contains("[1] block1:"));
+    }
+
+    #[test]
+    fn test_trace_decodes_calldata_and_encodes_return_data() {
+        let mut calldata = hash::selector("bump(uint256)").to_vec();
+        calldata
+            .extend(abi::encode(&[AbiType::Uint(256)], &[AbiValue::Uint(3u32.into())]).unwrap());
+        let emitter = TraceEmitter::new(vec![guarded_increment()]).with_calldata(calldata.clone());
+        let trace = emitter
+            .trace("Counter", "bump", &[BlockId(0), BlockId(1)])
+            .unwrap();
+
+        assert!(trace.contains("(x) = 3"), "{}", trace);
+        assert!(
+            trace.contains(&format!("returndata 0x{:064x}", 4)),
+            "{}",
+            trace
+        );
+
+        calldata[0] ^= 0xff;
+        let wrong_selector = TraceEmitter::new(vec![guarded_increment()]).with_calldata(calldata);
+        assert!(wrong_selector
+            .trace("Counter", "bump", &[BlockId(0), BlockId(1)])
+            .is_err());
+    }
+}
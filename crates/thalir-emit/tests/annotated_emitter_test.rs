@@ -3,13 +3,19 @@ use thalir_core::{
     block::Terminator,
     contract::Contract,
     function::{
-        Function, FunctionBody, FunctionMetadata, FunctionSignature, Mutability, Visibility,
+        Function, FunctionBody, FunctionMetadata, FunctionSignature, Mutability, Parameter,
+        Visibility,
     },
-    instructions::{Instruction, StorageKey},
+    instructions::{CallTarget, ContextVariable, Instruction, StorageKey},
+    metadata::NatSpec,
+    review::{ReviewFile, ReviewNote, ReviewStatus},
+    stable_id,
     types::Type,
-    values::{Constant, TempId, Value},
+    values::{Constant, ParamId, SourceLocation, TempId, Value},
+};
+use thalir_emit::{
+    annotated_ir_emitter::AnnotationConfig, AnnotatedIREmitter, EmitterConfig, EmitterProfile,
 };
-use thalir_emit::{annotated_ir_emitter::AnnotationConfig, AnnotatedIREmitter};
 
 #[test]
 fn test_annotated_emitter_basic() {
@@ -103,6 +109,7 @@ fn test_annotation_config_ascii_mode() {
         use_ascii_cues: true,
         emit_ordering_analysis: false,
         emit_function_headers: false,
+        condense_intrinsics: false,
     };
 
     let emitter = AnnotatedIREmitter::new(vec![contract]).with_annotation_config(config);
@@ -154,6 +161,7 @@ fn test_annotation_disabled() {
         use_ascii_cues: false,
         emit_ordering_analysis: false,
         emit_function_headers: false,
+        condense_intrinsics: false,
     };
 
     let emitter = AnnotatedIREmitter::new(vec![contract]).with_annotation_config(config);
@@ -171,3 +179,214 @@ fn test_annotation_disabled() {
         "Should not contain ASCII markers"
     );
 }
+
+fn profile_contract() -> Contract {
+    let mut function_body = FunctionBody::new();
+
+    let entry_block = function_body
+        .get_block_mut(function_body.entry_block())
+        .unwrap();
+    entry_block.add_instruction(Instruction::Add {
+        result: Value::Temp(TempId(1)),
+        left: Value::Param(ParamId(0)),
+        right: Value::Constant(Constant::Uint(BigUint::from(1u32), 256)),
+        ty: Type::Uint(256),
+    });
+    entry_block.add_instruction(Instruction::StorageStore {
+        key: StorageKey::Slot(BigUint::from(0u32)),
+        value: Value::Temp(TempId(1)),
+    });
+    entry_block.metadata.instruction_locations.insert(
+        0,
+        SourceLocation::new("Counter.sol".to_string(), 7, 9, 120, 131),
+    );
+    entry_block.set_terminator(Terminator::Return(None));
+
+    let signature = FunctionSignature {
+        name: "bump".to_string(),
+        params: vec![Parameter::new("amount", Type::Uint(256))],
+        returns: vec![],
+        is_payable: false,
+    };
+
+    let function = Function {
+        signature,
+        visibility: Visibility::Public,
+        mutability: Mutability::NonPayable,
+        modifiers: vec![],
+        body: function_body,
+        metadata: FunctionMetadata::default(),
+    };
+
+    let mut contract = Contract::new("Counter".to_string());
+    contract.add_function(function);
+    contract.metadata.natspec = NatSpec::parse(&["/// @notice Counts bumps"]);
+    contract
+}
+
+fn emit_with_profile(profile: EmitterProfile) -> String {
+    let config = AnnotationConfig {
+        emit_position_markers: false,
+        emit_visual_cues: true,
+        use_ascii_cues: true,
+        emit_ordering_analysis: true,
+        emit_function_headers: true,
+        condense_intrinsics: false,
+    };
+    AnnotatedIREmitter::new(vec![profile_contract()])
+        .with_annotation_config(config)
+        .with_emitter_config(EmitterConfig::for_profile(profile))
+        .emit_to_string(false)
+}
+
+#[test]
+fn test_minimal_profile_output() {
+    let output = emit_with_profile(EmitterProfile::Minimal);
+
+    assert!(output.contains("block0(v0):"), "Params should be untyped");
+    assert!(!output.contains("[STATE_WRITE]"));
+    assert!(!output.contains("; ### Function"));
+    assert!(!output.contains("@notice"));
+    assert!(!output.contains("; @ Counter.sol"));
+    assert!(!output.contains("; uses:"));
+}
+
+#[test]
+fn test_audit_profile_output() {
+    let output = emit_with_profile(EmitterProfile::Audit);
+
+    assert!(
+        output.contains("block0(v0: i256):"),
+        "Params should be typed"
+    );
+    assert!(output.contains("[STATE_WRITE]"));
+    assert!(output.contains("; ### Function: bump"));
+    assert!(output.contains("; @notice Counts bumps"));
+    assert!(!output.contains("; @ Counter.sol"));
+    assert!(!output.contains("; uses:"));
+}
+
+#[test]
+fn test_debug_profile_output() {
+    let output = emit_with_profile(EmitterProfile::Debug);
+
+    assert!(output.contains("block0(v0: i256):"));
+    assert!(output.contains("[STATE_WRITE]"));
+    assert!(output.contains("; @ Counter.sol:7:9"));
+    assert!(output.contains("; uses: 1"));
+}
+
+#[test]
+fn test_review_notes_render_under_their_targets() {
+    let mut contract = profile_contract();
+    stable_id::assign_contract(&mut contract);
+    let function = &contract.functions["bump"];
+    let function_id = stable_id::of_function("Counter", function);
+    let block_id = stable_id::block_id(&function_id, 0);
+
+    let note = |target: String, status, note: &str| ReviewNote {
+        target,
+        status,
+        author: Some("ana".to_string()),
+        note: note.to_string(),
+        label: None,
+    };
+    let review = ReviewFile {
+        notes: vec![
+            note(function_id, Some(ReviewStatus::Reviewed), ""),
+            note(
+                stable_id::instruction_id(&block_id, 1),
+                Some(ReviewStatus::Suspicious),
+                "unbounded counter",
+            ),
+            note(block_id, None, "single block"),
+        ],
+        ..ReviewFile::default()
+    };
+
+    let output = AnnotatedIREmitter::new(vec![contract])
+        .with_review(review)
+        .emit_to_string(false);
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+    let at = |text: &str| lines.iter().position(|line| line.contains(text)).unwrap();
+
+    assert_eq!(lines[at("function %bump") - 1], "; review [reviewed] (ana)");
+    assert_eq!(lines[at("block0(") + 1], "; review single block (ana)");
+    assert_eq!(
+        lines[at("sstore") + 1],
+        "; review [suspicious] unbounded counter (ana)"
+    );
+}
+
+#[test]
+fn test_condensing_keeps_results_used_later_visible() {
+    let mut function_body = FunctionBody::new();
+    let entry_block = function_body
+        .get_block_mut(function_body.entry_block())
+        .unwrap();
+    for inst in [
+        Instruction::GetContext {
+            result: Value::Temp(TempId(0)),
+            var: ContextVariable::MsgSender,
+        },
+        Instruction::StorageLoad {
+            result: Value::Temp(TempId(1)),
+            key: StorageKey::Slot(BigUint::from(0u32)),
+        },
+        Instruction::Eq {
+            result: Value::Temp(TempId(2)),
+            left: Value::Temp(TempId(0)),
+            right: Value::Temp(TempId(1)),
+        },
+        Instruction::Require {
+            condition: Value::Temp(TempId(2)),
+            message: "not owner".to_string(),
+        },
+        Instruction::Call {
+            result: Value::Temp(TempId(3)),
+            target: CallTarget::External(Value::Param(ParamId(0))),
+            args: vec![],
+            value: None,
+        },
+        Instruction::Require {
+            condition: Value::Temp(TempId(3)),
+            message: String::new(),
+        },
+        Instruction::StorageStore {
+            key: StorageKey::Slot(BigUint::from(1u32)),
+            value: Value::Temp(TempId(3)),
+        },
+    ] {
+        entry_block.add_instruction(inst);
+    }
+    entry_block.set_terminator(Terminator::Return(None));
+
+    let function = Function {
+        signature: FunctionSignature {
+            name: "poke".to_string(),
+            params: vec![Parameter::new("target", Type::Address)],
+            returns: vec![],
+            is_payable: false,
+        },
+        visibility: Visibility::External,
+        mutability: Mutability::NonPayable,
+        modifiers: vec![],
+        body: function_body,
+        metadata: FunctionMetadata::default(),
+    };
+    let mut contract = Contract::new("Owned".to_string());
+    contract.add_function(function);
+
+    let config = AnnotationConfig {
+        condense_intrinsics: true,
+        ..AnnotationConfig::default()
+    };
+    let output = AnnotatedIREmitter::new(vec![contract])
+        .with_annotation_config(config)
+        .emit_to_string(false);
+
+    assert!(output.contains("only_owner"), "{}", output);
+    assert!(!output.contains("checked_call"), "{}", output);
+    assert!(output.contains("require"), "{}", output);
+    assert!(output.contains("sstore"), "{}", output);
+}
@@ -0,0 +1,79 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use thalir_core::{builder::IRBuilder, contract::Contract, types::Type};
+use thalir_emit::ThalIREmitter;
+
+const FUNCTIONS: [&str; 6] = ["deposit", "withdraw", "transfer", "approve", "mint", "burn"];
+
+fn build_contract() -> Contract {
+    let mut builder = IRBuilder::new();
+    let mut contract = builder.contract("Ledger");
+    contract.state_variable("total", Type::Uint(256), 0);
+    contract.state_variable("owner", Type::Address, 1);
+
+    for name in FUNCTIONS {
+        let mut func = contract.function(name);
+        func.param("amount", Type::Uint(256));
+        let amount = func.get_param(0);
+
+        if name == "withdraw" {
+            let ok_id = func.create_block_id();
+            let fail_id = func.create_block_id();
+            {
+                let mut entry = func.entry_block();
+                let limit = entry.constant_uint(1000, 256);
+                let condition = entry.le(amount.clone(), limit);
+                entry.branch(condition, ok_id, fail_id).unwrap();
+            }
+            {
+                let mut ok = func.switch_to_block(ok_id).unwrap();
+                ok.storage_store(0u32.into(), amount);
+                ok.return_void().unwrap();
+            }
+            {
+                let mut fail = func.switch_to_block(fail_id).unwrap();
+                fail.revert("limit exceeded").unwrap();
+            }
+        } else {
+            let mut entry = func.entry_block();
+            let one = entry.constant_uint(1, 256);
+            let sum = entry.add(amount, one, Type::Uint(256));
+            entry.storage_store(0u32.into(), sum);
+            entry.return_void().unwrap();
+        }
+
+        func.build().unwrap();
+    }
+
+    contract.build().unwrap()
+}
+
+fn emitted_hash() -> (u64, String) {
+    let output = ThalIREmitter::new(vec![build_contract()]).emit_to_string(false);
+    let mut hasher = DefaultHasher::new();
+    output.hash(&mut hasher);
+    (hasher.finish(), output)
+}
+
+#[test]
+fn test_emission_is_identical_across_runs() {
+    let (expected, first_output) = emitted_hash();
+
+    for _ in 0..20 {
+        let (hash, output) = emitted_hash();
+        assert_eq!(
+            hash, expected,
+            "Emitted IR changed between runs:\n{}",
+            output
+        );
+    }
+
+    let positions: Vec<usize> = FUNCTIONS
+        .iter()
+        .map(|name| first_output.find(&format!("function %{}(", name)).unwrap())
+        .collect();
+    assert!(
+        positions.windows(2).all(|w| w[0] < w[1]),
+        "Functions should be emitted in declaration order"
+    );
+}
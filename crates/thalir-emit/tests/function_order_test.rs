@@ -0,0 +1,92 @@
+use thalir_core::{builder::IRBuilder, contract::Contract, function::Visibility, types::Type};
+use thalir_emit::{EmitterConfig, FunctionOrder, ThalIREmitter};
+
+fn build_contract() -> Contract {
+    let mut builder = IRBuilder::new();
+    let mut contract = builder.contract("Vault");
+
+    let mut settle = contract.function("_settle");
+    settle.visibility(Visibility::Internal);
+    settle.entry_block().return_void().unwrap();
+    settle.build().unwrap();
+
+    let mut withdraw = contract.function("withdraw");
+    withdraw
+        .param("amount", Type::Uint(256))
+        .visibility(Visibility::External);
+    withdraw.entry_block().return_void().unwrap();
+    withdraw.build().unwrap();
+
+    let mut deposit = contract.function("deposit");
+    deposit.visibility(Visibility::Public);
+    deposit.entry_block().return_void().unwrap();
+    deposit.build().unwrap();
+
+    let mut constructor = contract.function("constructor");
+    constructor.visibility(Visibility::Public).constructor();
+    constructor.entry_block().return_void().unwrap();
+    constructor.build().unwrap();
+
+    contract.build().unwrap()
+}
+
+/* Function names and group headers, in the order they were printed. */
+fn outline(order: FunctionOrder, group_functions: bool) -> Vec<String> {
+    let config = EmitterConfig {
+        function_order: order,
+        group_functions,
+        ..EmitterConfig::default()
+    };
+    ThalIREmitter::new(vec![build_contract()])
+        .with_emitter_config(config)
+        .emit_to_string(false)
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if let Some(rest) = line.strip_prefix("function %") {
+                return rest.split('(').next().map(str::to_string);
+            }
+            line.strip_prefix("// ")
+                .filter(|header| *header != "Storage Layout")
+                .map(|header| format!("[{}]", header))
+        })
+        .collect()
+}
+
+#[test]
+fn test_functions_print_in_the_configured_order() {
+    assert_eq!(
+        outline(FunctionOrder::Source, false),
+        ["_settle", "withdraw", "deposit", "constructor"]
+    );
+    assert_eq!(
+        outline(FunctionOrder::Alphabetical, false),
+        ["_settle", "constructor", "deposit", "withdraw"]
+    );
+    /* withdraw(uint256) is 0x2e1a7d4d and deposit() 0xd0e30db0. */
+    assert_eq!(
+        outline(FunctionOrder::Selector, false),
+        ["withdraw", "deposit", "_settle", "constructor"]
+    );
+}
+
+#[test]
+fn test_group_headers_mark_each_run() {
+    assert_eq!(
+        outline(FunctionOrder::Visibility, true),
+        [
+            "[constructor]",
+            "constructor",
+            "[external]",
+            "withdraw",
+            "[public]",
+            "deposit",
+            "[internal]",
+            "_settle"
+        ]
+    );
+    assert_eq!(
+        outline(FunctionOrder::Source, true),
+        ["_settle", "withdraw", "deposit", "constructor"]
+    );
+}
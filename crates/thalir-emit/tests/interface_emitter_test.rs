@@ -0,0 +1,104 @@
+use pretty_assertions::assert_eq;
+use thalir_core::{
+    builder::IRBuilder,
+    contract::Contract,
+    function::{Mutability, Visibility},
+    types::Type,
+};
+use thalir_emit::InterfaceEmitter;
+
+fn build_token() -> Contract {
+    let mut builder = IRBuilder::new();
+    let mut contract = builder.contract("Token");
+    let transfer_event = contract
+        .event("Transfer")
+        .indexed("from", Type::Address)
+        .indexed("to", Type::Address)
+        .data("value", Type::Uint(256))
+        .build();
+    contract.add_event(transfer_event);
+
+    let mut constructor = contract.function("constructor");
+    constructor
+        .param("supply", Type::Uint(256))
+        .visibility(Visibility::Public)
+        .constructor();
+    constructor.entry_block().return_void().unwrap();
+    constructor.build().unwrap();
+
+    let mut transfer = contract.function("transfer");
+    transfer
+        .param("to", Type::Address)
+        .param("amount", Type::Uint(256))
+        .returns(Type::Bool)
+        .visibility(Visibility::External);
+    let mut entry = transfer.entry_block();
+    let ok = entry.constant_bool(true);
+    entry.return_value(ok).unwrap();
+    transfer.build().unwrap();
+
+    let mut name = contract.function("name");
+    name.returns(Type::String)
+        .visibility(Visibility::Public)
+        .mutability(Mutability::View);
+    let mut entry = name.entry_block();
+    let text = entry.storage_load(0u32.into());
+    entry.return_value(text).unwrap();
+    name.build().unwrap();
+
+    let mut helper = contract.function("_move");
+    helper
+        .param("amount", Type::Uint(256))
+        .visibility(Visibility::Internal);
+    helper.entry_block().return_void().unwrap();
+    helper.build().unwrap();
+
+    let mut receive = contract.function("receive");
+    receive.visibility(Visibility::External).receive();
+    receive.entry_block().return_void().unwrap();
+    receive.build().unwrap();
+
+    contract.build().unwrap()
+}
+
+#[test]
+fn test_interface_lists_only_the_external_surface() {
+    let text = InterfaceEmitter::new(vec![build_token()]).emit_to_string();
+    assert_eq!(
+        text,
+        "// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.0;
+
+interface IToken {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+
+    function transfer(address to, uint256 amount) external returns (bool); // 0xa9059cbb
+    function name() external view returns (string memory); // 0x06fdde03
+    receive() external payable;
+}
+"
+    );
+}
+
+#[test]
+fn test_abi_matches_solc_layout() {
+    let abi = InterfaceEmitter::new(vec![build_token()]).abi();
+    let entries = abi["Token"].as_array().unwrap();
+    let kinds: Vec<&str> = entries
+        .iter()
+        .map(|entry| entry["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        kinds,
+        ["constructor", "function", "function", "receive", "event"]
+    );
+
+    assert_eq!(entries[0]["inputs"][0]["type"], "uint256");
+    assert_eq!(entries[0]["stateMutability"], "nonpayable");
+    assert_eq!(entries[1]["name"], "transfer");
+    assert_eq!(entries[1]["outputs"][0]["type"], "bool");
+    assert_eq!(entries[2]["stateMutability"], "view");
+    assert_eq!(entries[3]["stateMutability"], "payable");
+    assert_eq!(entries[4]["inputs"][1]["indexed"], true);
+    assert_eq!(entries[4]["inputs"][2]["name"], "value");
+}
@@ -0,0 +1,101 @@
+use pretty_assertions::assert_eq;
+use thalir_core::{builder::IRBuilder, contract::Contract, function::Visibility, types::Type};
+use thalir_emit::PseudoSolidityEmitter;
+
+fn build_contract() -> Contract {
+    let mut builder = IRBuilder::new();
+    let mut contract = builder.contract("Counter");
+    contract.state_variable("count", Type::Uint(256), 0);
+
+    let mut bump = contract.function("bump");
+    bump.param("limit", Type::Uint(256))
+        .visibility(Visibility::External);
+    let limit = bump.get_param(0);
+    let then_id = bump.create_block_id();
+    let else_id = bump.create_block_id();
+    let merge_id = bump.create_block_id();
+
+    let mut entry = bump.entry_block();
+    let count = entry.storage_load(0u32.into());
+    let below = entry.lt(count.clone(), limit);
+    entry.branch(below, then_id, else_id).unwrap();
+
+    let mut then_block = bump.block_with_id(then_id);
+    let one = then_block.constant_uint(1, 256);
+    let next = then_block.add(count, one, Type::Uint(256));
+    then_block.storage_store(0u32.into(), next);
+    then_block.jump(merge_id).unwrap();
+
+    let mut else_block = bump.block_with_id(else_id);
+    let zero = else_block.constant_uint(0, 256);
+    else_block.storage_store(0u32.into(), zero);
+    else_block.jump(merge_id).unwrap();
+
+    bump.block_with_id(merge_id).return_void().unwrap();
+    bump.build().unwrap();
+
+    let mut drain = contract.function("drain");
+    drain
+        .param("floor", Type::Uint(256))
+        .visibility(Visibility::External);
+    let floor = drain.get_param(0);
+    let header_id = drain.create_block_id();
+    let body_id = drain.create_block_id();
+    let exit_id = drain.create_block_id();
+
+    drain.entry_block().jump(header_id).unwrap();
+
+    let mut header = drain.block_with_id(header_id);
+    let count = header.storage_load(0u32.into());
+    let above = header.gt(count.clone(), floor);
+    header.branch(above, body_id, exit_id).unwrap();
+
+    let mut body = drain.block_with_id(body_id);
+    let one = body.constant_uint(1, 256);
+    let next = body.sub(count, one, Type::Uint(256));
+    body.storage_store(0u32.into(), next);
+    body.jump(header_id).unwrap();
+
+    drain.block_with_id(exit_id).return_void().unwrap();
+    drain.build().unwrap();
+
+    contract.build().unwrap()
+}
+
+#[test]
+fn test_branches_and_loops_are_restructured() {
+    let output = PseudoSolidityEmitter::new(vec![build_contract()]).emit_to_string();
+
+    let expected = "\
+contract Counter {
+    uint256 count; // slot 0
+
+    function bump(uint256 limit) external {
+        v0 = count;
+        v1 = v0 < limit;
+        if (v1) {
+            v2 = v0 + 1;
+            count = v2;
+        } else {
+            count = 0;
+        }
+        return;
+    }
+
+    function drain(uint256 floor) external {
+        while (true) {
+            v0 = count;
+            v1 = v0 > floor;
+            if (!(v1)) {
+                break;
+            }
+            v2 = v0 - 1;
+            count = v2;
+        }
+        return;
+    }
+}
+";
+    assert_eq!(output, expected);
+    assert!(!output.contains("goto"));
+}
@@ -0,0 +1,59 @@
+use thalir_core::{
+    builder::IRBuilder,
+    contract::Contract,
+    types::Type,
+    values::{Constant, Value},
+};
+use thalir_emit::{StorageNaming, ThalIREmitter};
+
+fn build_contract() -> Contract {
+    let mut builder = IRBuilder::new();
+    let mut contract = builder.contract("Vault");
+    contract.state_variable("owner", Type::Address, 0);
+    contract.state_variable(
+        "balances",
+        Type::Mapping(Box::new(Type::Address), Box::new(Type::Uint(256))),
+        1,
+    );
+
+    let mut func = contract.function("credit");
+    func.param("amount", Type::Uint(256));
+    let amount = func.get_param(0);
+    let mut entry = func.entry_block();
+    entry.storage_load(0u32.into());
+    let balances = Value::Constant(Constant::Uint(1u32.into(), 256));
+    let credit = entry.constant_uint(5, 256);
+    entry.mapping_store(balances, amount, credit);
+    entry.return_void().unwrap();
+    func.build().unwrap();
+
+    contract.build().unwrap()
+}
+
+fn emit(naming: StorageNaming) -> String {
+    ThalIREmitter::new(vec![build_contract()])
+        .with_storage_naming(naming)
+        .emit_to_string(false)
+}
+
+#[test]
+fn test_slots_are_emitted_by_default() {
+    let output = emit(StorageNaming::Slots);
+    assert!(output.contains("sload iconst.i256 0\n"));
+    assert!(!output.contains("@owner"));
+    assert_eq!(
+        output,
+        ThalIREmitter::new(vec![build_contract()]).emit_to_string(false)
+    );
+}
+
+#[test]
+fn test_storage_accesses_are_annotated_with_variable_names() {
+    let comments = emit(StorageNaming::Comments);
+    assert!(comments.contains("sload iconst.i256 0  // owner"));
+    assert!(comments.contains("mapping_store iconst.i256 1, v0, iconst.i256 5  // balances"));
+
+    let symbolic = emit(StorageNaming::Symbolic);
+    assert!(symbolic.contains("v1 = sload @owner"));
+    assert!(symbolic.contains("mapping_store @balances, v0, iconst.i256 5"));
+}
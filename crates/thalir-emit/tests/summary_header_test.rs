@@ -0,0 +1,51 @@
+use thalir_core::{builder::IRBuilder, contract::Contract, types::Type};
+use thalir_emit::{EmitterConfig, ThalIREmitter};
+
+fn build_contract() -> Contract {
+    let mut builder = IRBuilder::new();
+    let mut contract = builder.contract("Vault");
+    contract.state_variable("owner", Type::Address, 0);
+    contract.state_variable("total", Type::Uint(256), 1);
+
+    let mut func = contract.function("sweep");
+    func.param("amount", Type::Uint(256));
+    let amount = func.get_param(0);
+    let mut entry = func.entry_block();
+    let owner = entry.storage_load(0u32.into());
+    let sender = entry.msg_sender();
+    let is_owner = entry.eq(sender, owner);
+    entry.require(is_owner, "not owner");
+    entry.storage_store(1u32.into(), amount);
+    entry.call_internal("_settle", Vec::new());
+    entry.return_void().unwrap();
+    func.build().unwrap();
+
+    contract.build().unwrap()
+}
+
+#[test]
+fn test_summary_header_precedes_each_function() {
+    let config = EmitterConfig {
+        include_summaries: true,
+        ..EmitterConfig::default()
+    };
+    let output = ThalIREmitter::new(vec![build_contract()])
+        .with_emitter_config(config)
+        .emit_to_string(false);
+
+    let header = [
+        "  ; reads: slot 0 (owner)",
+        "  ; writes: slot 1 (total)",
+        "  ; calls: internal _settle",
+        "  ; guards: 1 caller check",
+        "  function %sweep(",
+    ]
+    .join("\n");
+    assert!(output.contains(&header), "{}", output);
+}
+
+#[test]
+fn test_summaries_are_off_by_default() {
+    let output = ThalIREmitter::new(vec![build_contract()]).emit_to_string(false);
+    assert!(!output.contains("; reads:"));
+}
@@ -50,6 +50,7 @@ impl VisualCue {
 #[derive(Debug, Clone, Default)]
 pub struct InstructionAnnotations {
     pub position: Option<usize>,
+    pub position_end: Option<usize>,
     pub visual_cue: Option<VisualCue>,
 }
 
@@ -77,16 +78,21 @@ pub enum AnalysisComment {
 }
 
 pub fn extract_position(pair: &Pair<Rule>) -> Option<usize> {
-    pair.clone()
+    extract_position_range(pair).map(|(start, _)| start)
+}
+
+/* Condensed intrinsics carry a `[start-end]` marker; a plain `[n]` is the range `(n, n)`. */
+pub fn extract_position_range(pair: &Pair<Rule>) -> Option<(usize, usize)> {
+    let marker = pair
+        .clone()
         .into_inner()
-        .find(|p| p.as_rule() == Rule::position_marker)
-        .and_then(|p| {
-            let text = p.as_str();
+        .find(|p| p.as_rule() == Rule::position_marker)?;
+    let text = marker.as_str().trim_matches(|c| c == '[' || c == ']');
 
-            text.trim_matches(|c| c == '[' || c == ']')
-                .parse::<usize>()
-                .ok()
-        })
+    match text.split_once('-') {
+        Some((start, end)) => Some((start.parse().ok()?, end.parse().ok()?)),
+        None => text.parse().ok().map(|n| (n, n)),
+    }
 }
 
 pub fn extract_visual_cue(pair: &Pair<Rule>) -> Option<VisualCue> {
@@ -100,8 +106,10 @@ pub fn extract_visual_cue(pair: &Pair<Rule>) -> Option<VisualCue> {
 }
 
 pub fn extract_instruction_annotations(pair: &Pair<Rule>) -> InstructionAnnotations {
+    let range = extract_position_range(pair);
     InstructionAnnotations {
-        position: extract_position(pair),
+        position: range.map(|(start, _)| start),
+        position_end: range.and_then(|(start, end)| (end != start).then_some(end)),
         visual_cue: extract_visual_cue(pair),
     }
 }
@@ -111,8 +119,10 @@ pub fn extract_analysis_comment(pair: &Pair<Rule>) -> Option<AnalysisComment> {
         return None;
     }
 
-    let text = pair.as_str();
+    Some(classify_comment(pair.as_str()))
+}
 
+pub fn classify_comment(text: &str) -> AnalysisComment {
     if text.contains("### Function:") {
         let parts: Vec<&str> = text.split("Function:").collect();
         if parts.len() > 1 {
@@ -128,33 +138,80 @@ pub fn extract_analysis_comment(pair: &Pair<Rule>) -> Option<AnalysisComment> {
             } else {
                 (rest.to_string(), None)
             };
-            return Some(AnalysisComment::FunctionHeader { name, visibility });
+            return AnalysisComment::FunctionHeader { name, visibility };
         }
     }
 
     if text.contains("ORDERING ANALYSIS") {
-        return Some(AnalysisComment::OrderingHeader);
+        return AnalysisComment::OrderingHeader;
     }
 
     if text.contains("External call at position") {
         if let Some(pos) = extract_position_from_text(text) {
-            return Some(AnalysisComment::ExternalCallPosition(pos));
+            return AnalysisComment::ExternalCallPosition(pos);
         }
     }
 
     if text.contains("State modification at position") {
         if let Some(pos) = extract_position_from_text(text) {
-            return Some(AnalysisComment::StateModificationPosition(pos));
+            return AnalysisComment::StateModificationPosition(pos);
         }
     }
 
     if text.contains("→") {
         if let Some(comparison) = parse_ordering_comparison(text) {
-            return Some(comparison);
+            return comparison;
         }
     }
 
-    Some(AnalysisComment::Other(text.to_string()))
+    AnalysisComment::Other(text.to_string())
+}
+
+#[derive(Debug, Clone)]
+pub struct AnnotationLine {
+    pub line: usize,
+    pub trailing: bool,
+    pub comment: AnalysisComment,
+}
+
+/* The grammar skips comments, so annotations are recovered from the source text alongside the
+ * parse. Both `;` analysis lines and `//` storage notes are kept, whether they stand alone or
+ * trail an instruction. */
+pub fn collect_annotations(input: &str) -> Vec<AnnotationLine> {
+    input
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let start = comment_start(line)?;
+            let text = line[start..].trim_end();
+            let comment = if text.starts_with(';') {
+                classify_comment(text)
+            } else if text.trim_start_matches('/').trim() == "Storage Layout" {
+                return None;
+            } else {
+                AnalysisComment::Other(text.to_string())
+            };
+            Some(AnnotationLine {
+                line: index + 1,
+                trailing: !line[..start].trim().is_empty(),
+                comment,
+            })
+        })
+        .collect()
+}
+
+fn comment_start(line: &str) -> Option<usize> {
+    let bytes = line.as_bytes();
+    let mut in_string = false;
+    for (index, byte) in bytes.iter().enumerate() {
+        match byte {
+            b'"' => in_string = !in_string,
+            b';' if !in_string => return Some(index),
+            b'/' if !in_string && bytes.get(index + 1) == Some(&b'/') => return Some(index),
+            _ => {}
+        }
+    }
+    None
 }
 
 fn extract_position_from_text(text: &str) -> Option<usize> {
@@ -237,6 +294,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_collect_annotations_keeps_line_and_trailing_comments() {
+        let input = "; ### Function: withdraw (PUBLIC)\n\
+                     block0(v0: i256):\n    \
+                     [0] v1 = sload iconst.i256 0  // owner\n    \
+                     [1-4] intrinsic.only_owner @owner, \"a;b\"  ; condenses 4 instructions\n";
+        let annotations = collect_annotations(input);
+
+        assert_eq!(annotations.len(), 3);
+        assert!(matches!(
+            &annotations[0].comment,
+            AnalysisComment::FunctionHeader { name, .. } if name == "withdraw"
+        ));
+        assert!(!annotations[0].trailing);
+        assert_eq!((annotations[1].line, annotations[1].trailing), (3, true));
+        assert!(
+            matches!(&annotations[2].comment, AnalysisComment::Other(text) if text.starts_with("; condenses"))
+        );
+    }
+
     #[test]
     fn test_parse_ordering_comparison() {
         let text = "; - [4] < [8] → REENTRANCY RISK";
@@ -0,0 +1,163 @@
+/* A header line of a filetest, in the order it appears. `test` names what to run, `set` passes
+ * an option to every test in the file, and `target` picks the chain the file is checked for. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Directive {
+    Test { command: String, args: Vec<String> },
+    Set { key: String, value: String },
+    Target { name: String, args: Vec<String> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectiveLine {
+    pub line: usize,
+    pub directive: Directive,
+}
+
+/* What the output of a test must contain, from `; check:` and `; not:` comments. Checks match
+ * output lines as substrings, in order; a `not` must match no line at all. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expectation {
+    Check(String),
+    Not(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectationLine {
+    pub line: usize,
+    pub expectation: Expectation,
+}
+
+/* Directives are read line by line from the top of the file, up to the first line that is
+ * neither a directive, a comment nor blank. The grammar only skips them, and a directive there
+ * runs on to the end of the input. */
+pub fn directives(input: &str) -> Vec<DirectiveLine> {
+    let mut found = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let text = line.split(';').next().unwrap_or(line);
+        let mut words = text.split_whitespace().map(str::to_string);
+        let directive = match words.next().as_deref() {
+            None => continue,
+            Some("test") => Directive::Test {
+                command: words.next().unwrap_or_default(),
+                args: words.collect(),
+            },
+            Some("set") => {
+                let setting = words.collect::<Vec<_>>().join(" ");
+                let (key, value) = setting
+                    .split_once('=')
+                    .unwrap_or((setting.as_str(), "true"));
+                Directive::Set {
+                    key: key.trim().to_string(),
+                    value: value.trim().to_string(),
+                }
+            }
+            Some("target") => Directive::Target {
+                name: words.next().unwrap_or_default(),
+                args: words.collect(),
+            },
+            Some(_) => break,
+        };
+        found.push(DirectiveLine {
+            line: index + 1,
+            directive,
+        });
+    }
+    found
+}
+
+/* The input with its directive lines blanked, so the rest reconstructs and keeps its line
+ * numbers. */
+pub fn strip_directives(input: &str) -> String {
+    let header: Vec<usize> = directives(input).iter().map(|d| d.line).collect();
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            if header.contains(&(index + 1)) {
+                ""
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn expectations(input: &str) -> Vec<ExpectationLine> {
+    input
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let comment = line.trim_start().strip_prefix(';')?.trim();
+            let expectation = if let Some(text) = comment.strip_prefix("check:") {
+                Expectation::Check(text.trim().to_string())
+            } else if let Some(text) = comment.strip_prefix("not:") {
+                Expectation::Not(text.trim().to_string())
+            } else {
+                return None;
+            };
+            Some(ExpectationLine {
+                line: index + 1,
+                expectation,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directives_are_read_from_the_header() {
+        let input = r#"test analyze unguarded-division
+set callbacks=erc721,aave
+target arbitrum cancun ; chain the file is checked for
+
+; check: unguarded-division: Vault::share
+; not: Vault::owner
+contract Vault {
+
+  function %share(i256) -> i256 public  {
+  block0(v0: i256):
+    v1 = sload iconst.i256 0
+    v2 = udiv.i256 v1, v0
+    return v2
+  }
+}
+"#;
+        let found = directives(input);
+        assert_eq!(
+            found
+                .iter()
+                .map(|d| d.directive.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                Directive::Test {
+                    command: "analyze".to_string(),
+                    args: vec!["unguarded-division".to_string()],
+                },
+                Directive::Set {
+                    key: "callbacks".to_string(),
+                    value: "erc721,aave".to_string(),
+                },
+                Directive::Target {
+                    name: "arbitrum".to_string(),
+                    args: vec!["cancun".to_string()],
+                },
+            ]
+        );
+        assert_eq!(found[2].line, 3);
+
+        let reconstruction = crate::reconstruct(&strip_directives(input)).unwrap();
+        assert_eq!(reconstruction.contracts.len(), 1);
+        assert!(reconstruction.unrecognized.is_empty());
+
+        let expected = expectations(input);
+        assert_eq!(expected.len(), 2);
+        assert_eq!(
+            expected[1].expectation,
+            Expectation::Not("Vault::owner".to_string())
+        );
+    }
+}
@@ -2,7 +2,9 @@
  *
  * Round-tripping IR through text files enables version control, tool interop, and transformation
  * validation. This parser reads IR back into memory so you can analyze it, transform it, or verify
- * it matches expectations.
+ * it matches expectations. `reconstruct` goes one step further and rebuilds `Contract`s from emitted
+ * text, so analyses can run on an IR file without the Solidity it came from. `directives` reads
+ * the `test`, `set` and `target` header of a filetest and its `; check:` expectations.
  */
 
 #![allow(unreachable_patterns)]
@@ -12,6 +14,14 @@ use pest_derive::Parser;
 use std::path::Path;
 
 pub mod annotations;
+pub mod directives;
+pub mod reconstruct;
+
+pub use directives::{
+    directives, expectations, strip_directives, Directive, DirectiveLine, Expectation,
+    ExpectationLine,
+};
+pub use reconstruct::{reconstruct, ReconstructError, Reconstruction};
 
 #[derive(Parser)]
 #[grammar = "grammar.pest"]
@@ -23,6 +33,18 @@ pub fn parse(input: &str) -> ParseResult<pest::iterators::Pairs<'_, Rule>> {
     ThalirParser::parse(Rule::module, input).map_err(|e| Box::new(e))
 }
 
+pub struct AnnotatedModule<'i> {
+    pub pairs: pest::iterators::Pairs<'i, Rule>,
+    pub annotations: Vec<annotations::AnnotationLine>,
+}
+
+pub fn parse_annotated(input: &str) -> ParseResult<AnnotatedModule<'_>> {
+    Ok(AnnotatedModule {
+        pairs: parse(input)?,
+        annotations: annotations::collect_annotations(input),
+    })
+}
+
 pub fn parse_file<P: AsRef<Path>>(path: P) -> ParseResult<String> {
     std::fs::read_to_string(path).map_err(|e| {
         Box::new(pest::error::Error::new_from_pos(
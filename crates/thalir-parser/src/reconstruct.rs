@@ -0,0 +1,1148 @@
+use crate::{parse, Rule};
+use num_bigint::{BigInt, BigUint};
+use pest::iterators::Pair;
+use std::collections::HashMap;
+use thalir_core::{
+    block::{BasicBlock, BlockId, BlockParam, Terminator},
+    contract::{Contract, EventId, StorageSlot},
+    function::{Function, FunctionMetadata, FunctionSignature, Mutability, Parameter, Visibility},
+    instructions::{BuiltinFunction, CallTarget, ContextVariable, Instruction, Size, StorageKey},
+    stable_id,
+    types::Type,
+    values::{BlockParamId, Constant, Location, ParamId, TempId, Value},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ReconstructError {
+    #[error("{0}")]
+    Parse(#[from] Box<pest::error::Error<Rule>>),
+}
+
+/* Contracts rebuilt from emitted text, plus every instruction line that had no IR counterpart.
+ * Those lines are dropped from the rebuilt blocks, so a non-empty list means the contracts
+ * describe less than the text did. */
+#[derive(Debug, Clone)]
+pub struct Reconstruction {
+    pub contracts: Vec<Contract>,
+    pub unrecognized: Vec<String>,
+}
+
+/* Rebuilds `Contract`s from the text `ThalIREmitter` produces. Value numbers become temps and
+ * parameter names, which the text does not carry, become `arg0`, `arg1`, ... Functions outside a
+ * `contract` block are not reconstructed. */
+pub fn reconstruct(input: &str) -> Result<Reconstruction, ReconstructError> {
+    let mut reconstruction = Reconstruction {
+        contracts: Vec::new(),
+        unrecognized: Vec::new(),
+    };
+
+    for pair in parse(input)?.flat_map(|module| module.into_inner()) {
+        if pair.as_rule() == Rule::contract_def {
+            let contract = contract_from(pair, &mut reconstruction.unrecognized);
+            reconstruction.contracts.push(contract);
+        }
+    }
+
+    Ok(reconstruction)
+}
+
+fn contract_from(pair: Pair<Rule>, unrecognized: &mut Vec<String>) -> Contract {
+    let mut inner = pair.into_inner();
+    let name = inner
+        .next()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_default();
+    let mut contract = Contract::new(name);
+    let mut consts = HashMap::new();
+
+    for item in inner.flat_map(|body| body.into_inner()) {
+        match item.as_rule() {
+            Rule::storage_layout => {
+                for decl in item.into_inner() {
+                    contract.storage_layout.slots.push(storage_slot(decl));
+                }
+            }
+            Rule::contract_const_decl => {
+                let Some(name) = item.clone().into_inner().next() else {
+                    continue;
+                };
+                let rhs = item.as_str().split_once('=').map(|(_, rhs)| item_text(rhs));
+                if let Some(constant) = rhs.as_deref().and_then(constant) {
+                    consts.insert(name.as_str().to_string(), constant);
+                }
+            }
+            Rule::function => {
+                let mut decoder = Decoder {
+                    consts: &consts,
+                    slots: contract
+                        .storage_layout
+                        .slots
+                        .iter()
+                        .map(|slot| (slot.name.clone(), slot.slot.clone()))
+                        .collect(),
+                    params: HashMap::new(),
+                };
+                let function = decoder.function(item, unrecognized);
+                contract.add_function(function);
+            }
+            _ => {}
+        }
+    }
+
+    /* Text does not carry the stable IDs; they are recomputed the way the builder stamps them. */
+    stable_id::assign_contract(&mut contract);
+    contract
+}
+
+fn storage_slot(decl: Pair<Rule>) -> StorageSlot {
+    let mut slot = BigUint::default();
+    let mut name = String::new();
+    let mut var_type = Type::Uint(256);
+    for part in decl.into_inner() {
+        match part.as_rule() {
+            Rule::integer => slot = number(part.as_str()).unwrap_or_default(),
+            Rule::ident => name = part.as_str().to_string(),
+            Rule::ty => var_type = parse_type(part.as_str()),
+            _ => {}
+        }
+    }
+    StorageSlot {
+        slot,
+        offset: 0,
+        var_type,
+        name,
+        packed_with: Vec::new(),
+        location: None,
+        is_private: false,
+    }
+}
+
+struct Decoder<'a> {
+    consts: &'a HashMap<String, Constant>,
+    slots: HashMap<String, BigUint>,
+    params: HashMap<String, Value>,
+}
+
+impl Decoder<'_> {
+    fn function(&mut self, pair: Pair<Rule>, unrecognized: &mut Vec<String>) -> Function {
+        let mut name = String::new();
+        let mut params = Vec::new();
+        let mut returns = Vec::new();
+        let mut visibility = Visibility::Private;
+        let mut mutability = Mutability::NonPayable;
+        let mut metadata = FunctionMetadata::default();
+        let mut blocks = Vec::new();
+
+        for part in pair.into_inner() {
+            match part.as_rule() {
+                Rule::name => name = part.as_str().trim_start_matches('%').to_string(),
+                Rule::signature => {
+                    for sig in part.into_inner() {
+                        match sig.as_rule() {
+                            Rule::param_list => {
+                                params = sig
+                                    .into_inner()
+                                    .filter(|p| p.as_rule() == Rule::param)
+                                    .enumerate()
+                                    .map(|(i, p)| {
+                                        Parameter::new(format!("arg{}", i), parse_type(p.as_str()))
+                                    })
+                                    .collect()
+                            }
+                            Rule::return_type => returns.push(parse_type(sig.as_str())),
+                            _ => {}
+                        }
+                    }
+                }
+                Rule::visibility_modifier => {
+                    visibility = match part.as_str() {
+                        "public" => Visibility::Public,
+                        "external" => Visibility::External,
+                        "internal" => Visibility::Internal,
+                        _ => Visibility::Private,
+                    }
+                }
+                Rule::mutability_modifier => {
+                    mutability = match part.as_str() {
+                        "pure" => Mutability::Pure,
+                        "view" => Mutability::View,
+                        "payable" => Mutability::Payable,
+                        _ => Mutability::NonPayable,
+                    }
+                }
+                Rule::function_kind => match part.as_str() {
+                    "constructor" => metadata.is_constructor = true,
+                    "fallback" => metadata.is_fallback = true,
+                    _ => metadata.is_receive = true,
+                },
+                Rule::selector_attr => {
+                    metadata.selector = part
+                        .into_inner()
+                        .next()
+                        .and_then(|selector| number(selector.as_str()))
+                        .and_then(|selector| u32::try_from(selector).ok())
+                }
+                Rule::function_tag => match part.as_str() {
+                    "assembly" => metadata.has_assembly = true,
+                    _ => metadata.is_outlined = true,
+                },
+                Rule::block => blocks.push(part),
+                _ => {}
+            }
+        }
+
+        let mut function = Function::new(FunctionSignature {
+            name,
+            params,
+            returns,
+            is_payable: mutability == Mutability::Payable,
+        });
+        function.visibility = visibility;
+        function.mutability = mutability;
+        function.metadata = metadata;
+        function.body.blocks.clear();
+
+        for (index, pair) in blocks.into_iter().enumerate() {
+            let block = self.block(pair, index == 0, unrecognized);
+            if index == 0 {
+                function.body.entry_block = block.id;
+            }
+            function.body.insert_block(block);
+        }
+        function
+    }
+
+    fn block(
+        &mut self,
+        pair: Pair<Rule>,
+        entry: bool,
+        unrecognized: &mut Vec<String>,
+    ) -> BasicBlock {
+        let mut block = BasicBlock::new(BlockId(0));
+        let mut lines = Vec::new();
+
+        for part in pair.into_inner() {
+            match part.as_rule() {
+                Rule::block_label => {
+                    for label in part.into_inner() {
+                        match label.as_rule() {
+                            Rule::block_ref => {
+                                block.id = block_id(label.as_str()).unwrap_or(BlockId(0))
+                            }
+                            Rule::block_params => {
+                                let params = label
+                                    .into_inner()
+                                    .flat_map(|list| list.into_inner())
+                                    .filter(|p| p.as_rule() == Rule::block_param);
+                                for (index, param) in params.enumerate() {
+                                    let fields: Vec<_> = param.into_inner().collect();
+                                    let field = |rule| fields.iter().find(|f| f.as_rule() == rule);
+                                    let value =
+                                        field(Rule::value).map(|v| v.as_str()).unwrap_or("");
+                                    let ty = field(Rule::ty).map(|t| parse_type(t.as_str()));
+                                    let bound = if entry {
+                                        Value::Param(ParamId(index as u32))
+                                    } else {
+                                        Value::BlockParam(BlockParamId {
+                                            block: block.id,
+                                            index: index as u32,
+                                        })
+                                    };
+                                    self.params.insert(value.to_string(), bound);
+                                    if !entry {
+                                        block.add_param(BlockParam::new(
+                                            value,
+                                            ty.unwrap_or(Type::Uint(256)),
+                                        ));
+                                    }
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Rule::instruction => lines.push(part),
+                _ => {}
+            }
+        }
+
+        for line in lines {
+            let mut results = Vec::new();
+            let mut body = None;
+            for part in line.clone().into_inner() {
+                match part.as_rule() {
+                    Rule::result_list => {
+                        results = part
+                            .into_inner()
+                            .filter(|r| r.as_rule() == Rule::result)
+                            .map(|r| self.value(r.as_str()))
+                            .collect()
+                    }
+                    Rule::position_marker | Rule::visual_marker => {}
+                    _ if body.is_none() => {
+                        let offset = part.as_span().start() - line.as_span().start();
+                        body = Some(item_text(&line.as_str()[offset..]));
+                    }
+                    _ => {}
+                }
+            }
+            let Some(body) = body else {
+                continue;
+            };
+            let result = results.into_iter().next().unwrap_or(Value::Undefined);
+
+            if let Some(terminator) = self.terminator(&body) {
+                block.terminator = terminator;
+            } else if let Some(inst) = self.instruction(&body, result) {
+                block.instructions.push(inst);
+            } else {
+                unrecognized.push(item_text(line.as_str()));
+            }
+        }
+
+        /* Revert terminators are not printed; a block that ends in a revert instruction was one. */
+        if matches!(block.terminator, Terminator::Invalid) {
+            if let Some(Instruction::Revert { message }) = block.instructions.last() {
+                block.terminator = Terminator::Revert(message.clone());
+                block.instructions.pop();
+            }
+        }
+
+        block
+    }
+
+    fn terminator(&mut self, body: &str) -> Option<Terminator> {
+        let (op, rest) = split_opcode(body);
+        match op {
+            "return" if rest.is_empty() => Some(Terminator::Return(None)),
+            "return" => Some(Terminator::Return(Some(self.value(rest)))),
+            "jump" => {
+                let (target, args) = call_parts(rest);
+                let args = args.iter().map(|a| self.value(a)).collect();
+                Some(Terminator::Jump(block_id(target)?, args))
+            }
+            "brz" => match operands(rest).as_slice() {
+                [condition, else_target, then_target] => {
+                    let (else_block, else_args) = call_parts(else_target);
+                    let (then_block, then_args) = call_parts(then_target);
+                    Some(Terminator::Branch {
+                        condition: self.value(condition),
+                        then_block: block_id(then_block)?,
+                        then_args: then_args.iter().map(|a| self.value(a)).collect(),
+                        else_block: block_id(else_block)?,
+                        else_args: else_args.iter().map(|a| self.value(a)).collect(),
+                    })
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn instruction(&mut self, body: &str, result: Value) -> Option<Instruction> {
+        let (opcode, rest) = split_opcode(body);
+        let (op, suffix) = opcode.split_once('.').unwrap_or((opcode, ""));
+        let ty = if suffix.is_empty() {
+            Type::Uint(256)
+        } else {
+            parse_type(suffix)
+        };
+        let args: Vec<String> = operands(rest);
+        let arg = |i: usize| args.get(i).map(String::as_str).unwrap_or("");
+
+        let inst = match op {
+            "iadd" | "isub" | "imul" | "udiv" | "urem" | "sdiv" | "srem" | "checked_add"
+            | "checked_sub" | "checked_mul" | "checked_div" => {
+                let (left, right) = (self.value(arg(0)), self.value(arg(1)));
+                match op {
+                    "iadd" => Instruction::Add {
+                        result,
+                        left,
+                        right,
+                        ty,
+                    },
+                    "isub" => Instruction::Sub {
+                        result,
+                        left,
+                        right,
+                        ty,
+                    },
+                    "imul" => Instruction::Mul {
+                        result,
+                        left,
+                        right,
+                        ty,
+                    },
+                    "udiv" => Instruction::Div {
+                        result,
+                        left,
+                        right,
+                        ty,
+                    },
+                    "urem" => Instruction::Mod {
+                        result,
+                        left,
+                        right,
+                        ty,
+                    },
+                    "sdiv" => Instruction::Sdiv {
+                        result,
+                        left,
+                        right,
+                        ty,
+                    },
+                    "srem" => Instruction::Smod {
+                        result,
+                        left,
+                        right,
+                        ty,
+                    },
+                    "checked_add" => Instruction::CheckedAdd {
+                        result,
+                        left,
+                        right,
+                        ty,
+                    },
+                    "checked_sub" => Instruction::CheckedSub {
+                        result,
+                        left,
+                        right,
+                        ty,
+                    },
+                    "checked_mul" => Instruction::CheckedMul {
+                        result,
+                        left,
+                        right,
+                        ty,
+                    },
+                    _ => Instruction::CheckedDiv {
+                        result,
+                        left,
+                        right,
+                        ty,
+                    },
+                }
+            }
+            "pow" => Instruction::Pow {
+                result,
+                base: self.value(arg(0)),
+                exp: self.value(arg(1)),
+            },
+            "band" | "bor" | "bxor" => {
+                let (left, right) = (self.value(arg(0)), self.value(arg(1)));
+                match op {
+                    "band" => Instruction::And {
+                        result,
+                        left,
+                        right,
+                    },
+                    "bor" => Instruction::Or {
+                        result,
+                        left,
+                        right,
+                    },
+                    _ => Instruction::Xor {
+                        result,
+                        left,
+                        right,
+                    },
+                }
+            }
+            "bnot" => Instruction::Not {
+                result,
+                operand: self.value(rest),
+            },
+            "ishl" | "ushr" | "sshr" => {
+                let (value, shift) = (self.value(arg(0)), self.value(arg(1)));
+                match op {
+                    "ishl" => Instruction::Shl {
+                        result,
+                        value,
+                        shift,
+                    },
+                    "ushr" => Instruction::Shr {
+                        result,
+                        value,
+                        shift,
+                    },
+                    _ => Instruction::Sar {
+                        result,
+                        value,
+                        shift,
+                    },
+                }
+            }
+            "icmp" => {
+                let (cond, first) = rest.split_once(' ')?;
+                let args = operands(first);
+                let left = self.value(args.first()?);
+                let right = self.value(args.get(1)?);
+                match cond {
+                    "eq" => Instruction::Eq {
+                        result,
+                        left,
+                        right,
+                    },
+                    "ne" => Instruction::Ne {
+                        result,
+                        left,
+                        right,
+                    },
+                    "ult" => Instruction::Lt {
+                        result,
+                        left,
+                        right,
+                    },
+                    "ule" => Instruction::Le {
+                        result,
+                        left,
+                        right,
+                    },
+                    "ugt" => Instruction::Gt {
+                        result,
+                        left,
+                        right,
+                    },
+                    "uge" => Instruction::Ge {
+                        result,
+                        left,
+                        right,
+                    },
+                    "slt" => Instruction::Slt {
+                        result,
+                        left,
+                        right,
+                    },
+                    "sle" => Instruction::Sle {
+                        result,
+                        left,
+                        right,
+                    },
+                    "sgt" => Instruction::Sgt {
+                        result,
+                        left,
+                        right,
+                    },
+                    "sge" => Instruction::Sge {
+                        result,
+                        left,
+                        right,
+                    },
+                    _ => return None,
+                }
+            }
+            "select" => Instruction::Select {
+                result,
+                condition: self.value(arg(0)),
+                then_val: self.value(arg(1)),
+                else_val: self.value(arg(2)),
+            },
+            "load" => Instruction::Load {
+                result,
+                location: self.location(rest)?,
+            },
+            "store" => Instruction::Store {
+                value: self.value(arg(0)),
+                location: self.location(arg(1))?,
+            },
+            "alloca" => Instruction::Allocate {
+                result,
+                ty,
+                size: match rest.parse() {
+                    Ok(size) => Size::Static(size),
+                    Err(_) => Size::Dynamic(self.value(rest)),
+                },
+            },
+            "copy" => Instruction::Copy {
+                dest: self.location(arg(0))?,
+                src: self.location(arg(1))?,
+                size: self.value(arg(2)),
+            },
+            "sload" => Instruction::StorageLoad {
+                result,
+                key: self.storage_key(rest)?,
+            },
+            "sstore" => Instruction::StorageStore {
+                key: self.storage_key(arg(0))?,
+                value: self.value(arg(1)),
+            },
+            "sdelete" => Instruction::StorageDelete {
+                key: self.storage_key(rest)?,
+            },
+            "tload" => Instruction::TransientLoad {
+                result,
+                key: self.storage_key(rest)?,
+            },
+            "tstore" => Instruction::TransientStore {
+                key: self.storage_key(arg(0))?,
+                value: self.value(arg(1)),
+            },
+            "mapping_load" => Instruction::MappingLoad {
+                result,
+                mapping: self.value(arg(0)),
+                key: self.value(arg(1)),
+            },
+            "mapping_store" => Instruction::MappingStore {
+                mapping: self.value(arg(0)),
+                key: self.value(arg(1)),
+                value: self.value(arg(2)),
+            },
+            "array_load" => Instruction::ArrayLoad {
+                result,
+                array: self.value(arg(0)),
+                index: self.value(arg(1)),
+            },
+            "array_store" => Instruction::ArrayStore {
+                array: self.value(arg(0)),
+                index: self.value(arg(1)),
+                value: self.value(arg(2)),
+            },
+            "array_length" => Instruction::ArrayLength {
+                result,
+                array: self.value(arg(0)),
+            },
+            "array_push" => Instruction::ArrayPush {
+                array: self.value(arg(0)),
+                value: self.value(arg(1)),
+            },
+            "array_pop" => Instruction::ArrayPop {
+                result,
+                array: self.value(arg(0)),
+            },
+            "get_context" => Instruction::GetContext {
+                result,
+                var: context_variable(rest)?,
+            },
+            "balance" => Instruction::GetBalance {
+                result,
+                address: self.value(rest),
+            },
+            "get_code" => Instruction::GetCode {
+                result,
+                address: self.value(rest),
+            },
+            "get_codesize" => Instruction::GetCodeSize {
+                result,
+                address: self.value(rest),
+            },
+            "get_codehash" => Instruction::GetCodeHash {
+                result,
+                address: self.value(rest),
+            },
+            "keccak256" => Instruction::Keccak256 {
+                result,
+                data: self.value(arg(0)),
+                len: self.value(arg(1)),
+            },
+            "sha256" => Instruction::Sha256 {
+                result,
+                data: self.value(arg(0)),
+                len: self.value(arg(1)),
+            },
+            "ripemd160" => Instruction::Ripemd160 {
+                result,
+                data: self.value(arg(0)),
+                len: self.value(arg(1)),
+            },
+            "ecrecover" => Instruction::EcRecover {
+                result,
+                hash: self.value(arg(0)),
+                v: self.value(arg(1)),
+                r: self.value(arg(2)),
+                s: self.value(arg(3)),
+            },
+            "cast" | "uextend" | "sextend" | "ireduce" => {
+                let (value, to) = (self.value(rest), ty);
+                match op {
+                    "cast" => Instruction::Cast { result, value, to },
+                    "uextend" => Instruction::ZeroExtend { result, value, to },
+                    "sextend" => Instruction::SignExtend { result, value, to },
+                    _ => Instruction::Truncate { result, value, to },
+                }
+            }
+            "assign" => Instruction::Assign {
+                result,
+                value: self.value(rest),
+            },
+            "phi" => {
+                let mut values = Vec::new();
+                for incoming in &args {
+                    let (block, value) = call_parts(incoming);
+                    values.push((block_id(block)?, self.value(value.first()?)));
+                }
+                Instruction::Phi { result, values }
+            }
+            "malloc" => Instruction::MemoryAlloc {
+                result,
+                size: self.value(rest),
+            },
+            "msize" => Instruction::MemorySize { result },
+            "mcopy" => Instruction::MemoryCopy {
+                dest: self.value(arg(0)),
+                src: self.value(arg(1)),
+                size: self.value(arg(2)),
+            },
+            "require" => Instruction::Require {
+                condition: self.value(arg(0)),
+                message: unquote(arg(1)),
+            },
+            "assert" => Instruction::Assert {
+                condition: self.value(arg(0)),
+                message: unquote(arg(1)),
+            },
+            "revert" => Instruction::Revert {
+                message: unquote(rest),
+            },
+            "call" | "call_ext" | "call_lib" | "call_builtin" => {
+                /* A call that sends ether ends in `value v3` after its arguments. */
+                let (call, value) = match rest.rsplit_once(") value ") {
+                    Some((call, value)) => (&rest[..call.len() + 1], Some(self.value(value))),
+                    None => (rest, None),
+                };
+                let (target, args) = call_parts(call);
+                let args = args.iter().map(|a| self.value(a)).collect();
+                let name = target.trim_start_matches('%').to_string();
+                let target = match op {
+                    "call" => CallTarget::Internal(name),
+                    "call_lib" => CallTarget::Library(name),
+                    "call_builtin" => CallTarget::Builtin(builtin(target)?),
+                    _ => CallTarget::External(self.value(target)),
+                };
+                Instruction::Call {
+                    result,
+                    target,
+                    args,
+                    value,
+                }
+            }
+            "delegatecall" | "staticcall" => {
+                let target = self.value(arg(0));
+                let selector = self.value(arg(1));
+                let args = args.iter().skip(2).map(|a| self.value(a)).collect();
+                match op {
+                    "delegatecall" => Instruction::DelegateCall {
+                        result,
+                        target,
+                        selector,
+                        args,
+                    },
+                    _ => Instruction::StaticCall {
+                        result,
+                        target,
+                        selector,
+                        args,
+                    },
+                }
+            }
+            "create" => Instruction::Create {
+                result,
+                code: self.value(arg(0)),
+                value: self.value(arg(1)),
+            },
+            "create2" => Instruction::Create2 {
+                result,
+                code: self.value(arg(0)),
+                salt: self.value(arg(1)),
+                value: self.value(arg(2)),
+            },
+            "selfdestruct" => Instruction::Selfdestruct {
+                beneficiary: self.value(rest),
+            },
+            "emit" => {
+                let (head, data) = call_parts(rest);
+                let (event, topics) = match head.split_once('[') {
+                    Some((event, topics)) => (event, operands(topics.trim_end_matches(']'))),
+                    None => (head, Vec::new()),
+                };
+                let id = event.strip_prefix("event_")?.parse().ok()?;
+                Instruction::EmitEvent {
+                    event: EventId(id),
+                    topics: topics.iter().map(|t| self.value(t)).collect(),
+                    data: data.iter().map(|d| self.value(d)).collect(),
+                }
+            }
+            _ => return None,
+        };
+        Some(inst)
+    }
+
+    fn storage_key(&mut self, text: &str) -> Option<StorageKey> {
+        let text = text.trim();
+        if let Some(inner) = text.strip_prefix("mapping(") {
+            let args = operands(inner.strip_suffix(')')?);
+            return Some(StorageKey::MappingKey {
+                base: self.slot(args.first()?)?,
+                key: self.value(args.get(1)?),
+            });
+        }
+        if let Some(inner) = text.strip_prefix("array(") {
+            let args = operands(inner.strip_suffix(')')?);
+            return Some(StorageKey::ArrayElement {
+                base: self.slot(args.first()?)?,
+                index: self.value(args.get(1)?),
+            });
+        }
+        if let Some(inner) = text.strip_prefix("dynamic(") {
+            return Some(StorageKey::Dynamic(self.value(inner.strip_suffix(')')?)));
+        }
+        if let Some(inner) = text.strip_prefix("computed(") {
+            return Some(StorageKey::Computed(self.value(inner.strip_suffix(')')?)));
+        }
+        Some(StorageKey::Slot(self.slot(text)?))
+    }
+
+    fn location(&mut self, text: &str) -> Option<Location> {
+        let (kind, args) = call_parts(text);
+        let arg = |i: usize| args.get(i).map(String::as_str);
+        Some(match kind {
+            "stack" => Location::Stack {
+                offset: arg(0)?.parse().ok()?,
+            },
+            "memory" => Location::Memory {
+                base: self.value(arg(0)?),
+                offset: self.value(arg(1)?),
+            },
+            "storage" => Location::Storage {
+                slot: self.value(arg(0)?),
+            },
+            "calldata" => Location::Calldata {
+                offset: self.value(arg(0)?),
+            },
+            "returndata" => Location::ReturnData {
+                offset: self.value(arg(0)?),
+            },
+            _ => return None,
+        })
+    }
+
+    fn slot(&self, text: &str) -> Option<BigUint> {
+        let text = text.trim();
+        match text.strip_prefix('@') {
+            Some(name) => self.slots.get(name).cloned(),
+            None => number(text.rsplit(' ').next()?),
+        }
+    }
+
+    fn value(&mut self, text: &str) -> Value {
+        let text = text.trim();
+        if let Some(param) = self.params.get(text) {
+            return param.clone();
+        }
+        if let Some(constant) = self.consts.get(text) {
+            return Value::Constant(constant.clone());
+        }
+        if let Some(n) = text.strip_prefix('v').and_then(|n| n.parse().ok()) {
+            return Value::Temp(TempId(n));
+        }
+        if text.starts_with('@') {
+            if let Some(slot) = self.slot(text) {
+                return Value::Constant(Constant::Uint(slot, 256));
+            }
+        }
+        match constant(text) {
+            Some(constant) => Value::Constant(constant),
+            None => Value::Undefined,
+        }
+    }
+}
+
+fn constant(text: &str) -> Option<Constant> {
+    let (op, literal) = split_opcode(text);
+    let (kind, suffix) = op.split_once('.').unwrap_or((op, ""));
+    match kind {
+        "iconst" => {
+            let bits: u16 = suffix.strip_prefix('i')?.parse().ok()?;
+            if let Some(hex) = literal.strip_prefix("0x") {
+                let bytes = hex_bytes(hex)?;
+                return Some(match <[u8; 20]>::try_from(bytes.as_slice()) {
+                    Ok(addr) if bits == 160 => Constant::Address(addr),
+                    _ => Constant::Uint(BigUint::from_bytes_be(&bytes), bits),
+                });
+            }
+            if literal.starts_with('-') {
+                let val = BigInt::parse_bytes(literal.replace('_', "").as_bytes(), 10)?;
+                return Some(Constant::Int(val, bits));
+            }
+            let val = number(literal)?;
+            Some(if bits == 1 {
+                Constant::Bool(val.bits() > 0)
+            } else {
+                Constant::Uint(val, bits)
+            })
+        }
+        "bconst" => Some(Constant::Bytes(hex_bytes(literal.strip_prefix("0x")?)?)),
+        "sconst" => Some(Constant::String(unquote(literal))),
+        "null" => Some(Constant::Null),
+        _ => None,
+    }
+}
+
+fn context_variable(text: &str) -> Option<ContextVariable> {
+    Some(match text.trim() {
+        "msg.sender" => ContextVariable::MsgSender,
+        "msg.value" => ContextVariable::MsgValue,
+        "msg.data" => ContextVariable::MsgData,
+        "msg.sig" => ContextVariable::MsgSig,
+        "block.number" => ContextVariable::BlockNumber,
+        "block.timestamp" => ContextVariable::BlockTimestamp,
+        "block.difficulty" => ContextVariable::BlockDifficulty,
+        "block.prevrandao" => ContextVariable::PrevRandao,
+        "block.gaslimit" => ContextVariable::BlockGasLimit,
+        "block.coinbase" => ContextVariable::BlockCoinbase,
+        "block.basefee" => ContextVariable::BlockBaseFee,
+        "block.blobbasefee" => ContextVariable::BlobBaseFee,
+        "chain.id" | "block.chainid" => ContextVariable::ChainId,
+        "tx.origin" => ContextVariable::TxOrigin,
+        "tx.gasprice" => ContextVariable::TxGasPrice,
+        "gasleft" => ContextVariable::GasLeft,
+        "address(this)" => ContextVariable::ThisAddress,
+        "address(this).balance" => ContextVariable::ThisBalance,
+        _ => return None,
+    })
+}
+
+fn builtin(text: &str) -> Option<BuiltinFunction> {
+    Some(match text.trim() {
+        "AddMod" => BuiltinFunction::AddMod,
+        "MulMod" => BuiltinFunction::MulMod,
+        "BlockHash" => BuiltinFunction::BlockHash,
+        "BlobHash" => BuiltinFunction::BlobHash,
+        "GasLeft" => BuiltinFunction::GasLeft,
+        "MappingSlot" => BuiltinFunction::MappingSlot,
+        _ => return None,
+    })
+}
+
+/* Emitted type names back to IR types. `i1` and `i160` are how bools and addresses print. */
+pub fn parse_type(text: &str) -> Type {
+    let text = text.trim();
+    if let Some(inner) = text
+        .strip_prefix("mapping(")
+        .and_then(|t| t.strip_suffix(')'))
+    {
+        if let Some((key, value)) = inner.split_once("=>") {
+            return Type::Mapping(Box::new(parse_type(key)), Box::new(parse_type(value)));
+        }
+    }
+    if let Some(inner) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        return match inner.rsplit_once(';') {
+            Some((element, size)) => {
+                Type::Array(Box::new(parse_type(element)), size.trim().parse().ok())
+            }
+            None => Type::Array(Box::new(parse_type(inner)), None),
+        };
+    }
+    match text {
+        "i1" | "b1" => Type::Bool,
+        "i160" | "address" => Type::Address,
+        "string" => Type::String,
+        "bytes4" => Type::Bytes4,
+        "bytes20" => Type::Bytes20,
+        "bytes32" => Type::Bytes32,
+        _ => {
+            if let Some(bits) = text.strip_prefix('i').and_then(|b| b.parse().ok()) {
+                Type::Uint(bits)
+            } else if let Some(size) = text.strip_prefix("bytes").and_then(|b| b.parse().ok()) {
+                Type::Bytes(size)
+            } else {
+                Type::Uint(256)
+            }
+        }
+    }
+}
+
+/* The instruction text without trailing `;` or `//` comments. */
+fn item_text(text: &str) -> String {
+    let mut quoted = false;
+    let bytes = text.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'"' => quoted = !quoted,
+            b';' if !quoted => return text[..i].trim().to_string(),
+            b'/' if !quoted && bytes.get(i + 1) == Some(&b'/') => {
+                return text[..i].trim().to_string()
+            }
+            _ => {}
+        }
+    }
+    text.trim().to_string()
+}
+
+fn split_opcode(text: &str) -> (&str, &str) {
+    let text = text.trim();
+    match text.split_once(char::is_whitespace) {
+        Some((op, rest)) => (op, rest.trim()),
+        None => (text, ""),
+    }
+}
+
+/* Splits on commas that are not nested in brackets or quotes. */
+fn operands(text: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut quoted = false;
+    let mut current = String::new();
+    for c in text.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '(' | '[' if !quoted => depth += 1,
+            ')' | ']' if !quoted => depth -= 1,
+            ',' if !quoted && depth == 0 => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+    parts
+}
+
+/* `target(a, b)` into the target and its arguments; a bare target has none. */
+fn call_parts(text: &str) -> (&str, Vec<String>) {
+    let text = text.trim();
+    match text.split_once('(') {
+        Some((target, args)) if text.ends_with(')') => {
+            (target.trim(), operands(&args[..args.len() - 1]))
+        }
+        _ => (text, Vec::new()),
+    }
+}
+
+fn block_id(text: &str) -> Option<BlockId> {
+    text.trim().strip_prefix("block")?.parse().ok().map(BlockId)
+}
+
+fn number(text: &str) -> Option<BigUint> {
+    let text = text.trim().replace('_', "");
+    match text.strip_prefix("0x") {
+        Some(hex) => BigUint::parse_bytes(hex.as_bytes(), 16),
+        None => BigUint::parse_bytes(text.as_bytes(), 10),
+    }
+}
+
+fn hex_bytes(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn unquote(text: &str) -> String {
+    text.trim().trim_matches('"').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VAULT: &str = r#"contract Vault {
+
+  // Storage Layout
+  slot 0 = owner: i160
+
+  function %withdraw(i256) -> i256 public  {
+  block0(v0: i256):
+    v1 = sload iconst.i256 0
+    v2 = get_context msg.sender
+    v3 = icmp eq v2, v1
+    brz v3, block2, block1
+
+  block1:
+    v4 = iadd.i256 v0, iconst.i256 1
+    return v4
+
+  block2:
+    revert "not owner"
+  }
+}
+"#;
+
+    #[test]
+    fn test_reconstructs_blocks_terminators_and_params() {
+        let reconstruction = reconstruct(VAULT).unwrap();
+        assert!(reconstruction.unrecognized.is_empty());
+        assert_eq!(reconstruction.contracts.len(), 1);
+
+        let contract = &reconstruction.contracts[0];
+        assert_eq!(contract.name, "Vault");
+        assert_eq!(contract.storage_layout.slots[0].name, "owner");
+        assert_eq!(contract.storage_layout.slots[0].var_type, Type::Address);
+
+        let function = &contract.functions["withdraw"];
+        assert_eq!(function.visibility, Visibility::Public);
+        assert_eq!(function.signature.params.len(), 1);
+        assert_eq!(function.body.blocks.len(), 3);
+
+        let entry = &function.body.blocks[&BlockId(0)];
+        assert_eq!(entry.instructions.len(), 3);
+        assert!(matches!(
+            entry.instructions[0],
+            Instruction::StorageLoad {
+                key: StorageKey::Slot(_),
+                ..
+            }
+        ));
+        assert!(matches!(
+            entry.terminator,
+            Terminator::Branch {
+                then_block: BlockId(1),
+                else_block: BlockId(2),
+                ..
+            }
+        ));
+
+        let add = &function.body.blocks[&BlockId(1)].instructions[0];
+        assert!(matches!(
+            add,
+            Instruction::Add {
+                left: Value::Param(ParamId(0)),
+                ..
+            }
+        ));
+        assert!(matches!(
+            function.body.blocks[&BlockId(2)].terminator,
+            Terminator::Revert(ref message) if message == "not owner"
+        ));
+    }
+
+    #[test]
+    fn test_function_headers_keep_metadata() {
+        let reconstruction = reconstruct(
+            r#"contract Token {
+
+  function %transfer(i160, i256) -> i1 external nonpayable selector 0xa9059cbb {
+  block0(v0: i160, v1: i256):
+    return iconst.i1 1
+  }
+
+  function %fallback() external payable fallback assembly {
+  block0:
+    return
+  }
+}
+"#,
+        )
+        .unwrap();
+        let contract = &reconstruction.contracts[0];
+
+        let transfer = &contract.functions["transfer"];
+        assert_eq!(transfer.mutability, Mutability::NonPayable);
+        assert_eq!(transfer.metadata.selector, Some(0xa9059cbb));
+        assert!(!transfer.metadata.is_fallback);
+
+        let fallback = &contract.functions["fallback"];
+        assert!(fallback.is_payable());
+        assert!(fallback.metadata.is_fallback);
+        assert!(fallback.metadata.has_assembly);
+        assert_eq!(fallback.metadata.selector, None);
+    }
+}
@@ -367,3 +367,31 @@ block0(v0: i32):
         "Non-sequential position markers should parse (sparse numbering)"
     );
 }
+
+#[test]
+fn test_annotated_obfuscated_output_round_trips() {
+    let input = r#"contract contract_0 {
+
+  // Storage Layout
+  slot 0 = var_0
+  slot 1 = var_1
+
+; ### Function: fn_0 (PUBLIC)
+  function %fn_0(i256) public  {
+  block0(v0: i256):
+    [0-3] [CHECKED] intrinsic.only_owner @var_0, "error_0"  ; condenses 4 instructions
+    [4] v5 = sload iconst.i256 1  // var_1
+    [5] [STATE_WRITE] mapping_store @var_1, v0, v5
+    return
+  }
+}
+"#;
+
+    let module = thalir_parser::parse_annotated(input).expect("annotated output should parse");
+    assert_eq!(module.pairs.count(), 1);
+    assert_eq!(module.annotations.len(), 3);
+
+    let annot = parse_instruction("[0-3] [CHECKED] intrinsic.only_owner @var_0").unwrap();
+    assert_eq!((annot.position, annot.position_end), (Some(0), Some(3)));
+    assert_eq!(annot.visual_cue, Some(VisualCue::Checked));
+}
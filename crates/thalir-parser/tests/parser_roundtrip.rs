@@ -1,4 +1,11 @@
-use thalir_parser::{annotations::*, parse, Rule, ThalirParser};
+use thalir_core::{
+    builder::{IRBuilder, InstBuilderExt},
+    contract::Contract,
+    types::Type,
+    values::{Constant, Value},
+};
+use thalir_emit::ThalIREmitter;
+use thalir_parser::{annotations::*, parse, reconstruct, Rule, ThalirParser};
 
 #[test]
 fn test_parser_roundtrip_simple() {
@@ -169,6 +176,49 @@ contract MyContract {
     );
 }
 
+#[test]
+fn test_parser_roundtrip_contract_constants() {
+    let mut builder = IRBuilder::new();
+    let mut contract = builder.contract("Router");
+    let mut function = contract.function("approve");
+    function.param("spender", Type::Address).returns(Type::Bool);
+    let spender = function.get_param(0);
+
+    let mut entry = function.entry_block();
+    let router = entry.constant_address([0x7a; 20]);
+    let is_router = entry.eq(spender, router);
+    let selector = Value::Constant(Constant::Bytes(vec![0x09, 0x5e, 0xa7, 0xb3]));
+    let empty = Value::Constant(Constant::Bytes(Vec::new()));
+    let no_data = entry.eq(selector, empty);
+    let approved = entry.and(is_router, no_data);
+    entry.return_value(approved).unwrap();
+    function.build().unwrap();
+    let contract = contract.build().unwrap();
+
+    let text = ThalIREmitter::new(vec![contract.clone()]).emit_to_string(false);
+    assert!(text.contains("const c0 = iconst.i160 0x7a7a"), "{}", text);
+    assert!(text.contains("const c1 = bconst 0x095ea7b3"), "{}", text);
+    assert!(!text.contains("const c2"), "{}", text);
+
+    let reconstruction = reconstruct(&text).expect("emitted constants should parse");
+    assert!(
+        reconstruction.unrecognized.is_empty(),
+        "unrecognized: {:?}",
+        reconstruction.unrecognized
+    );
+    let constants = |contract: &Contract| -> Vec<Constant> {
+        contract
+            .constant_pool()
+            .iter()
+            .map(|(_, constant, _)| constant.clone())
+            .collect()
+    };
+    assert_eq!(
+        constants(&reconstruction.contracts[0]),
+        constants(&contract)
+    );
+}
+
 #[test]
 fn test_parser_roundtrip_complex_function() {
     let input = r#"
@@ -13,7 +13,12 @@
 
 pub mod solidity_to_ir;
 
-pub use solidity_to_ir::{transform_solidity_to_ir, transform_solidity_to_ir_with_filename};
+pub use solidity_to_ir::{
+    transform_solidity_to_ir, transform_solidity_to_ir_lenient,
+    transform_solidity_to_ir_with_diagnostics, transform_solidity_to_ir_with_filename,
+    CoverageReport, Diagnostic, DiagnosticKind, DiagnosticSeverity, Diagnostics, InputLimits,
+    KindCoverage, LoweringStatus, TransformError, TransformOutput, TransformationPipeline,
+};
 
 #[cfg(test)]
 mod tests {
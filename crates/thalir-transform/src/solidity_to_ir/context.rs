@@ -1,10 +1,19 @@
 use std::collections::HashMap;
-use thalir_core::{block::BlockId, builder::IRBuilder, types::Type, values::Value};
+use thalir_core::{
+    block::BlockId,
+    builder::IRBuilder,
+    types::{StructId, Type},
+    values::Value,
+};
 use tree_sitter::Node;
 
 pub trait TypeContext {
     fn get_node_text(&self, node: Node) -> &str;
     fn lookup_symbol(&self, name: &str) -> Option<&Symbol>;
+
+    fn lookup_struct(&self, _name: &str) -> Option<StructId> {
+        None
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -56,6 +65,55 @@ impl Scope {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct LocalScopes {
+    scopes: Vec<HashMap<String, Value>>,
+}
+
+impl LocalScopes {
+    pub fn new() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    pub fn pop_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
+        }
+    }
+
+    pub fn declare(&mut self, name: String, value: Value) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, value);
+        }
+    }
+
+    pub fn assign(&mut self, name: &str, value: Value) -> bool {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(slot) = scope.get_mut(name) {
+                *slot = value;
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+}
+
+impl Default for LocalScopes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ControlFlowContext {
     pub current_block: Option<BlockId>,
@@ -77,11 +135,22 @@ pub struct TransformationContext<'a> {
 
 pub struct SimpleContext<'a> {
     pub source: &'a str,
+    pub structs: Option<&'a HashMap<String, StructId>>,
 }
 
 impl<'a> SimpleContext<'a> {
     pub fn new(source: &'a str) -> Self {
-        Self { source }
+        Self {
+            source,
+            structs: None,
+        }
+    }
+
+    pub fn with_structs(source: &'a str, structs: &'a HashMap<String, StructId>) -> Self {
+        Self {
+            source,
+            structs: Some(structs),
+        }
     }
 }
 
@@ -93,6 +162,10 @@ impl<'a> TypeContext for SimpleContext<'a> {
     fn lookup_symbol(&self, _name: &str) -> Option<&Symbol> {
         None
     }
+
+    fn lookup_struct(&self, name: &str) -> Option<StructId> {
+        self.structs?.get(name).copied()
+    }
 }
 
 impl<'a> TransformationContext<'a> {
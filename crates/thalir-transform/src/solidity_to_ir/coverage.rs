@@ -0,0 +1,162 @@
+use super::{transform_solidity_to_ir_lenient, DiagnosticKind, Diagnostics};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tree_sitter::Node;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoweringStatus {
+    Full,
+    Partial,
+    Ignored,
+}
+
+impl LoweringStatus {
+    fn from_kind(kind: DiagnosticKind) -> Self {
+        match kind {
+            DiagnosticKind::SkippedSyntaxError
+            | DiagnosticKind::DroppedStatement
+            | DiagnosticKind::UnsupportedNode => LoweringStatus::Ignored,
+            DiagnosticKind::UnresolvedIdentifier | DiagnosticKind::HardcodedValue => {
+                LoweringStatus::Partial
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KindCoverage {
+    pub kind: String,
+    pub full: usize,
+    pub partial: usize,
+    pub ignored: usize,
+}
+
+impl KindCoverage {
+    pub fn total(&self) -> usize {
+        self.full + self.partial + self.ignored
+    }
+
+    fn count(&self, status: LoweringStatus) -> usize {
+        match status {
+            LoweringStatus::Full => self.full,
+            LoweringStatus::Partial => self.partial,
+            LoweringStatus::Ignored => self.ignored,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoverageReport {
+    pub file: String,
+    pub kinds: Vec<KindCoverage>,
+    pub diagnostics: Diagnostics,
+}
+
+impl CoverageReport {
+    pub fn analyze(source: &str, filename: &str) -> Result<Self> {
+        let mut parser = tree_sitter::Parser::new();
+        let language = tree_sitter_solidity::LANGUAGE.into();
+        parser
+            .set_language(&language)
+            .map_err(|e| anyhow!("Failed to set language: {}", e))?;
+        let tree = parser
+            .parse(source, None)
+            .ok_or_else(|| anyhow!("Failed to parse source"))?;
+
+        let diagnostics = transform_solidity_to_ir_lenient(source, Some(filename))?.diagnostics;
+
+        let mut features = Vec::new();
+        collect_features(tree.root_node(), &mut features);
+        let mut statuses = vec![LoweringStatus::Full; features.len()];
+
+        for diagnostic in diagnostics.iter() {
+            let start = diagnostic.location.start_byte;
+            let end = diagnostic.location.end_byte;
+            let status = LoweringStatus::from_kind(diagnostic.kind);
+
+            let target = features
+                .iter()
+                .enumerate()
+                .filter(|(_, node)| node.start_byte() <= start && end <= node.end_byte())
+                .min_by_key(|(_, node)| {
+                    (
+                        node.end_byte() - node.start_byte(),
+                        node.kind() != diagnostic.node_kind,
+                    )
+                })
+                .map(|(index, _)| index);
+            if let Some(index) = target {
+                statuses[index] = statuses[index].max(status);
+            }
+
+            if matches!(
+                diagnostic.kind,
+                DiagnosticKind::SkippedSyntaxError | DiagnosticKind::DroppedStatement
+            ) {
+                for (index, node) in features.iter().enumerate() {
+                    if start <= node.start_byte() && node.end_byte() <= end {
+                        statuses[index] = LoweringStatus::Ignored;
+                    }
+                }
+            }
+        }
+
+        let mut by_kind: BTreeMap<&str, KindCoverage> = BTreeMap::new();
+        for (node, status) in features.iter().zip(&statuses) {
+            let entry = by_kind.entry(node.kind()).or_insert_with(|| KindCoverage {
+                kind: node.kind().to_string(),
+                ..KindCoverage::default()
+            });
+            match status {
+                LoweringStatus::Full => entry.full += 1,
+                LoweringStatus::Partial => entry.partial += 1,
+                LoweringStatus::Ignored => entry.ignored += 1,
+            }
+        }
+
+        Ok(Self {
+            file: filename.to_string(),
+            kinds: by_kind.into_values().collect(),
+            diagnostics,
+        })
+    }
+
+    pub fn total(&self) -> usize {
+        self.kinds.iter().map(KindCoverage::total).sum()
+    }
+
+    pub fn count(&self, status: LoweringStatus) -> usize {
+        self.kinds.iter().map(|k| k.count(status)).sum()
+    }
+
+    pub fn percent(&self, status: LoweringStatus) -> f64 {
+        let total = self.total();
+        if total == 0 {
+            return 0.0;
+        }
+        self.count(status) as f64 * 100.0 / total as f64
+    }
+
+    pub fn kind(&self, kind: &str) -> Option<&KindCoverage> {
+        self.kinds.iter().find(|k| k.kind == kind)
+    }
+}
+
+fn is_feature(kind: &str) -> bool {
+    kind.ends_with("_statement")
+        || kind.ends_with("_expression")
+        || kind.ends_with("_definition")
+        || kind.ends_with("_declaration")
+}
+
+fn collect_features<'a>(node: Node<'a>, features: &mut Vec<Node<'a>>) {
+    if node.is_named() && is_feature(node.kind()) {
+        features.push(node);
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_features(child, features);
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thalir_core::values::SourceLocation;
+use tree_sitter::Node;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum DiagnosticSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for DiagnosticSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagnosticSeverity::Info => write!(f, "info"),
+            DiagnosticSeverity::Warning => write!(f, "warning"),
+            DiagnosticSeverity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DiagnosticKind {
+    SkippedSyntaxError,
+    UnsupportedNode,
+    UnresolvedIdentifier,
+    HardcodedValue,
+    DroppedStatement,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: DiagnosticSeverity,
+    pub kind: DiagnosticKind,
+    pub node_kind: String,
+    pub message: String,
+    pub location: SourceLocation,
+}
+
+impl Diagnostic {
+    pub fn new(
+        severity: DiagnosticSeverity,
+        kind: DiagnosticKind,
+        node: Node,
+        filename: &str,
+        message: String,
+    ) -> Self {
+        Self {
+            severity,
+            kind,
+            node_kind: node.kind().to_string(),
+            message,
+            location: SourceLocation::from_node(filename.to_string(), &node),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}:{}:{}: {}",
+            self.severity,
+            self.location.file,
+            self.location.line,
+            self.location.column,
+            self.message
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Diagnostics {
+    entries: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.entries.push(diagnostic);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn extend(&mut self, other: Diagnostics) {
+        self.entries.extend(other.entries);
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|d| d.severity == DiagnosticSeverity::Error)
+    }
+
+    pub fn max_severity(&self) -> Option<DiagnosticSeverity> {
+        self.entries.iter().map(|d| d.severity).max()
+    }
+
+    pub fn at_least(&self, severity: DiagnosticSeverity) -> impl Iterator<Item = &Diagnostic> {
+        self.entries.iter().filter(move |d| d.severity >= severity)
+    }
+
+    pub fn skipped_syntax_errors(source_file: Node, source: &str, filename: &str) -> Self {
+        let mut diagnostics = Self::new();
+        let mut cursor = source_file.walk();
+        for child in source_file.children(&mut cursor) {
+            if !child.has_error() {
+                continue;
+            }
+            match child.kind() {
+                "contract_declaration" | "interface_declaration" | "library_declaration" => {
+                    let mut inner = child.walk();
+                    for part in child.children(&mut inner) {
+                        if !part.has_error() {
+                            continue;
+                        }
+                        if child.child_by_field_name("body") == Some(part) {
+                            let mut body_cursor = part.walk();
+                            for member in part.children(&mut body_cursor) {
+                                if member.has_error() {
+                                    diagnostics.push_skipped(member, source, filename);
+                                }
+                            }
+                        } else {
+                            diagnostics.push_skipped(part, source, filename);
+                        }
+                    }
+                }
+                _ => diagnostics.push_skipped(child, source, filename),
+            }
+        }
+        diagnostics
+    }
+
+    fn push_skipped(&mut self, node: Node, source: &str, filename: &str) {
+        let text = &source[node.byte_range()];
+        let first_line = text.lines().next().unwrap_or("").trim();
+        let line_count = text.lines().count().max(1);
+        self.push(Diagnostic::new(
+            DiagnosticSeverity::Error,
+            DiagnosticKind::SkippedSyntaxError,
+            node,
+            filename,
+            format!(
+                "skipped {} ({} line{}) containing a syntax error: `{}`",
+                node.kind(),
+                line_count,
+                if line_count == 1 { "" } else { "s" },
+                first_line
+            ),
+        ));
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
@@ -1,3 +1,4 @@
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -30,6 +31,18 @@ pub enum TransformError {
     #[error("Symbol not found: {0}")]
     SymbolNotFound(String),
 
+    #[error("Source is {size} bytes, over the limit of {limit} bytes")]
+    SourceTooLarge { size: usize, limit: usize },
+
+    #[error("Syntax tree nests deeper than {limit} levels at line {line}")]
+    TooDeep { limit: usize, line: usize },
+
+    #[error("{stage} did not finish within {}s", .limit.as_secs_f64())]
+    Timeout {
+        stage: &'static str,
+        limit: Duration,
+    },
+
     #[error("Multiple errors occurred: {0:?}")]
     Multiple(Vec<TransformError>),
 }
@@ -5,7 +5,7 @@ use num_bigint::BigUint;
 use thalir_core::{
     builder::{BlockBuilder, InstBuilderExt},
     types::Type,
-    values::Value,
+    values::{Constant, Value},
 };
 use tree_sitter::Node;
 
@@ -86,17 +86,9 @@ impl ExpressionTransformer {
         block: &mut BlockBuilder,
     ) -> Result<Value> {
         let text = ctx.get_node_text(node);
-
-        let clean_text = text.replace('_', "");
-
-        if clean_text.starts_with("0x") || clean_text.starts_with("0X") {
-            let hex_str = &clean_text[2..];
-            let value = u64::from_str_radix(hex_str, 16).unwrap_or(0);
-            Ok(block.constant_uint(value, 256))
-        } else {
-            let value = clean_text.parse::<u64>().unwrap_or(0);
-            Ok(block.constant_uint(value, 256))
-        }
+        Ok(literal_constant(text)
+            .map(Value::Constant)
+            .unwrap_or_else(|| block.constant_uint(0, 256)))
     }
 
     fn transform_string_literal(
@@ -532,3 +524,23 @@ impl ExpressionTransformer {
         }
     }
 }
+
+/* The value of a number literal. Forty hex digits are an address literal, which is the only way
+ * Solidity writes one; anything with a unit or an exponent is not folded. */
+pub(super) fn literal_constant(text: &str) -> Option<Constant> {
+    let text = text.trim().replace('_', "");
+    let Some(digits) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) else {
+        return text
+            .parse::<BigUint>()
+            .ok()
+            .map(|value| Constant::Uint(value, 256));
+    };
+    if digits.len() == 40 {
+        let mut address = [0u8; 20];
+        for (i, byte) in address.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(digits.get(2 * i..2 * i + 2)?, 16).ok()?;
+        }
+        return Some(Constant::Address(address));
+    }
+    BigUint::parse_bytes(digits.as_bytes(), 16).map(|value| Constant::Uint(value, 256))
+}
@@ -0,0 +1,112 @@
+use super::errors::TransformError;
+use std::time::{Duration, Instant};
+use tree_sitter::Node;
+
+/* Sources fetched from explorers or picked up from a shared checkout are not trusted, and a
+ * pathological one should fail with an error naming the limit rather than exhaust memory, overflow
+ * the transformer's recursion or hang. The defaults sit well above any real contract: the largest
+ * verified flattened sources are a few megabytes and rarely nest past a hundred levels. The depth
+ * is also bounded by the transformer's recursion, which has to fit in a main-thread-sized stack
+ * in unoptimised builds too. There is no default timeout, since how long is too long depends on
+ * the machine. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputLimits {
+    pub max_source_bytes: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub timeout: Option<Duration>,
+}
+
+impl InputLimits {
+    pub const DEFAULT_MAX_SOURCE_BYTES: usize = 16 * 1024 * 1024;
+    pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+    pub fn unlimited() -> Self {
+        Self {
+            max_source_bytes: None,
+            max_depth: None,
+            timeout: None,
+        }
+    }
+
+    pub fn check_size(&self, size: usize) -> Result<(), TransformError> {
+        match self.max_source_bytes {
+            Some(limit) if size > limit => Err(TransformError::SourceTooLarge { size, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /* Walks the tree with a cursor rather than recursively, so the check itself cannot overflow on
+     * the input it is meant to reject. */
+    pub fn check_depth(&self, root: Node) -> Result<(), TransformError> {
+        let Some(limit) = self.max_depth else {
+            return Ok(());
+        };
+        let mut cursor = root.walk();
+        let mut depth = 0;
+        loop {
+            if depth > limit {
+                return Err(TransformError::TooDeep {
+                    limit,
+                    line: cursor.node().start_position().row + 1,
+                });
+            }
+            if cursor.goto_first_child() {
+                depth += 1;
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    return Ok(());
+                }
+                depth -= 1;
+            }
+        }
+    }
+
+    pub fn deadline(&self) -> Option<Deadline> {
+        self.timeout.map(Deadline::after)
+    }
+}
+
+impl Default for InputLimits {
+    fn default() -> Self {
+        Self {
+            max_source_bytes: Some(Self::DEFAULT_MAX_SOURCE_BYTES),
+            max_depth: Some(Self::DEFAULT_MAX_DEPTH),
+            timeout: None,
+        }
+    }
+}
+
+/* The point by which a parse and transform must finish, shared by the pipeline and its
+ * transformers. Transformers check it between functions, so one huge function can overrun it by
+ * the time that function takes. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline {
+    at: Instant,
+    limit: Duration,
+}
+
+impl Deadline {
+    pub fn after(limit: Duration) -> Self {
+        Self {
+            at: Instant::now() + limit,
+            limit,
+        }
+    }
+
+    pub fn expired(&self) -> bool {
+        Instant::now() >= self.at
+    }
+
+    pub fn check(&self, stage: &'static str) -> Result<(), TransformError> {
+        if self.expired() {
+            Err(TransformError::Timeout {
+                stage,
+                limit: self.limit,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
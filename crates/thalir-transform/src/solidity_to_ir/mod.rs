@@ -8,17 +8,23 @@
 mod context;
 mod control_flow_builder;
 mod control_flow_cursor;
+mod coverage;
+mod diagnostics;
 mod errors;
 mod expression_transformer;
+mod limits;
 mod structural_transformer;
 mod structural_transformer_cursor;
 mod type_resolver;
 
 use anyhow::{anyhow, Result};
-use thalir_core::{builder::IRBuilder, Contract};
+use thalir_core::{builder::IRBuilder, Contract, EvmVersion};
 use tree_sitter::{Node, Tree};
 
+pub use coverage::{CoverageReport, KindCoverage, LoweringStatus};
+pub use diagnostics::{Diagnostic, DiagnosticKind, DiagnosticSeverity, Diagnostics};
 pub use errors::TransformError;
+pub use limits::{Deadline, InputLimits};
 
 pub trait IRTransformer {
     fn name(&self) -> &str;
@@ -28,61 +34,135 @@ pub trait IRTransformer {
     fn check_prerequisites(&self, _builder: &IRBuilder) -> Result<()> {
         Ok(())
     }
+
+    fn take_diagnostics(&mut self) -> Diagnostics {
+        Diagnostics::new()
+    }
+
+    fn set_evm_version(&mut self, _evm_version: EvmVersion) {}
+
+    fn set_deadline(&mut self, _deadline: Deadline) {}
+
+    /* A fresh transformer for a single contract declaration of the file rooted at `root`, primed
+     * with whatever file-level state it needs. Transformers that can only work on the whole file
+     * return `None`, which keeps the pipeline sequential. */
+    fn split(&self, _root: &Node, _source: &str) -> Option<Box<dyn IRTransformer + Send>> {
+        None
+    }
 }
 
 pub struct TransformationPipeline {
     source: String,
+    /* Set by `with_filename`; the contracts then record the file and its source. */
+    filename: Option<String>,
     ast: Option<Tree>,
     transformers: Vec<Box<dyn IRTransformer>>,
+    lenient: bool,
+    evm_version: Option<EvmVersion>,
+    parallel: bool,
+    limits: InputLimits,
+}
+
+pub struct TransformOutput {
+    pub contracts: Vec<Contract>,
+    pub diagnostics: Diagnostics,
 }
 
 impl TransformationPipeline {
     pub fn default(source: &str) -> Self {
         Self {
             source: source.to_string(),
+            filename: None,
             ast: None,
             transformers: vec![Box::new(
                 structural_transformer::StructuralTransformer::new(),
             )],
+            lenient: false,
+            evm_version: None,
+            parallel: true,
+            limits: InputLimits::default(),
         }
     }
 
     pub fn with_filename(source: &str, filename: String) -> Self {
         Self {
             source: source.to_string(),
+            filename: Some(filename.clone()),
             ast: None,
             transformers: vec![Box::new(
                 structural_transformer::StructuralTransformer::with_filename(filename),
             )],
+            lenient: false,
+            evm_version: None,
+            parallel: true,
+            limits: InputLimits::default(),
         }
     }
 
     pub fn new(source: &str) -> Self {
         Self {
             source: source.to_string(),
+            filename: None,
             ast: None,
             transformers: vec![],
+            lenient: false,
+            evm_version: None,
+            parallel: true,
+            limits: InputLimits::default(),
         }
     }
 
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    pub fn evm_version(mut self, evm_version: Option<EvmVersion>) -> Self {
+        self.evm_version = evm_version;
+        self
+    }
+
+    /* Transforms the contracts of a multi-contract file concurrently, each on its own builder.
+     * On by default. A file where any contract inherits is still transformed sequentially, since
+     * a derived contract's storage is laid out after its bases' and needs them in its builder. */
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    pub fn limits(mut self, limits: InputLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
     pub fn with_transformer(mut self, transformer: Box<dyn IRTransformer>) -> Self {
         self.transformers.push(transformer);
         self
     }
 
-    pub fn transform(mut self) -> Result<Vec<Contract>> {
+    pub fn transform(self) -> Result<Vec<Contract>> {
+        self.transform_with_diagnostics()
+            .map(|output| output.contracts)
+    }
+
+    pub fn transform_with_diagnostics(mut self) -> Result<TransformOutput> {
+        let file = self.filename.as_deref().unwrap_or("<unknown>");
+        let _span = tracing::info_span!("transform", file).entered();
+
+        self.limits.check_size(self.source.len())?;
+        let deadline = self.limits.deadline();
+
         if self.ast.is_none() {
+            let _parse = tracing::info_span!("parse", bytes = self.source.len()).entered();
             let mut parser = tree_sitter::Parser::new();
             let language = tree_sitter_solidity::LANGUAGE.into();
             parser
                 .set_language(&language)
                 .map_err(|e| anyhow!("Failed to set language: {}", e))?;
 
-            let tree = parser
-                .parse(&self.source, None)
-                .ok_or_else(|| anyhow!("Failed to parse source"))?;
+            let tree = parse_streaming(&mut parser, &self.source, deadline)?;
 
-            if tree.root_node().has_error() {
+            if tree.root_node().has_error() && !self.lenient {
                 return Err(anyhow!("Failed to parse source: syntax errors detected"));
             }
 
@@ -94,12 +174,64 @@ impl TransformationPipeline {
             .as_ref()
             .ok_or_else(|| anyhow!("AST not initialized - call parse() first"))?;
         let root_node = ast.root_node();
+        self.limits.check_depth(root_node)?;
 
-        let mut builder = IRBuilder::new();
+        let mut diagnostics = if root_node.has_error() {
+            Diagnostics::skipped_syntax_errors(root_node, &self.source, file)
+        } else {
+            Diagnostics::new()
+        };
 
         for transformer in &mut self.transformers {
-            transformer.check_prerequisites(&builder)?;
-            transformer.transform(&mut builder, &root_node, &self.source)?;
+            if let Some(evm_version) = self.evm_version {
+                transformer.set_evm_version(evm_version);
+            }
+            if let Some(deadline) = deadline {
+                transformer.set_deadline(deadline);
+            }
+        }
+
+        let _build = tracing::info_span!("build").entered();
+        let mut builder = IRBuilder::new();
+
+        let declarations = contract_declarations(root_node);
+        let jobs = if self.parallel
+            && declarations.len() > 1
+            && !declarations.iter().copied().any(inherits)
+        {
+            declarations
+                .into_iter()
+                .map(|declaration| {
+                    let transformers = self
+                        .transformers
+                        .iter()
+                        .map(|transformer| transformer.split(&root_node, &self.source))
+                        .collect::<Option<Vec<_>>>()?;
+                    Some((declaration, transformers))
+                })
+                .collect::<Option<Vec<_>>>()
+        } else {
+            None
+        };
+
+        match jobs {
+            Some(mut jobs) => {
+                tracing::debug!(contracts = jobs.len(), "transforming contracts in parallel");
+                let results = transform_contracts(&mut jobs, &self.source)?;
+                for (contracts, contract_diagnostics) in results {
+                    for contract in contracts {
+                        builder.registry_mut().add_contract(contract)?;
+                    }
+                    diagnostics.extend(contract_diagnostics);
+                }
+            }
+            None => {
+                for transformer in &mut self.transformers {
+                    transformer.check_prerequisites(&builder)?;
+                    transformer.transform(&mut builder, &root_node, &self.source)?;
+                    diagnostics.extend(transformer.take_diagnostics());
+                }
+            }
         }
 
         builder.validate()?;
@@ -108,11 +240,140 @@ impl TransformationPipeline {
         let mut contracts = Vec::new();
 
         for (_name, contract) in registry.contracts() {
-            contracts.push(contract.clone());
+            let mut contract = contract.clone();
+            if let Some(file) = &self.filename {
+                contract.metadata.source_file = Some(file.clone());
+                contract.metadata.source_code = Some(self.source.clone());
+            }
+            contracts.push(contract);
         }
+        tracing::debug!(
+            contracts = contracts.len(),
+            diagnostics = diagnostics.len(),
+            "transform finished"
+        );
+
+        Ok(TransformOutput {
+            contracts,
+            diagnostics,
+        })
+    }
+}
 
-        Ok(contracts)
+const PARSE_CHUNK: usize = 64 * 1024;
+
+/* Hands the source to tree-sitter a chunk at a time. The progress callback runs as the parser
+ * advances, so an expired deadline cancels a parse stuck in error recovery instead of letting it
+ * run to completion. */
+fn parse_streaming(
+    parser: &mut tree_sitter::Parser,
+    source: &str,
+    deadline: Option<Deadline>,
+) -> Result<Tree> {
+    let bytes = source.as_bytes();
+    let mut read = |offset: usize, _: tree_sitter::Point| {
+        let end = bytes.len().min(offset.saturating_add(PARSE_CHUNK));
+        bytes.get(offset..end).unwrap_or_default()
+    };
+    let mut progress = |_: &tree_sitter::ParseState| deadline.is_some_and(|d| d.expired());
+    let options = tree_sitter::ParseOptions::new().progress_callback(&mut progress);
+
+    if let Some(tree) = parser.parse_with_options(&mut read, None, Some(options)) {
+        return Ok(tree);
     }
+    if let Some(deadline) = deadline {
+        deadline.check("parsing")?;
+    }
+    Err(anyhow!("Failed to parse source"))
+}
+
+/* The transformer recurses along the syntax tree, so workers get the main thread's stack rather
+ * than the smaller default for spawned threads, and accept the same nesting depth. */
+const WORKER_STACK_BYTES: usize = 8 * 1024 * 1024;
+
+type ContractJob<'tree> = (Node<'tree>, Vec<Box<dyn IRTransformer + Send>>);
+
+fn contract_declarations(root: Node) -> Vec<Node> {
+    let mut cursor = root.walk();
+    root.children(&mut cursor)
+        .filter(|child| {
+            matches!(
+                child.kind(),
+                "contract_declaration" | "interface_declaration" | "library_declaration"
+            )
+        })
+        .collect()
+}
+
+fn inherits(declaration: Node) -> bool {
+    let mut cursor = declaration.walk();
+    declaration
+        .children(&mut cursor)
+        .any(|child| child.kind() == "inheritance_specifier")
+}
+
+/* Contracts share nothing but file-level state, which each split transformer already holds, so
+ * they are spread over the available cores in contiguous runs. Results come back in declaration
+ * order, so contracts reach the registry in the same order as in a sequential run. Temporaries are
+ * numbered per contract rather than per file, which the emitters renumber anyway. */
+fn transform_contracts(
+    jobs: &mut [ContractJob<'_>],
+    source: &str,
+) -> Result<Vec<(Vec<Contract>, Diagnostics)>> {
+    let workers = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(jobs.len())
+        .max(1);
+    let run = jobs.len().div_ceil(workers).max(1);
+
+    std::thread::scope(|scope| {
+        let handles = jobs
+            .chunks_mut(run)
+            .map(|chunk| {
+                std::thread::Builder::new()
+                    .stack_size(WORKER_STACK_BYTES)
+                    .spawn_scoped(scope, move || {
+                        chunk
+                            .iter_mut()
+                            .map(|(declaration, transformers)| {
+                                transform_contract(*declaration, transformers, source)
+                            })
+                            .collect::<Result<Vec<_>>>()
+                    })
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        let mut results = Vec::new();
+        for handle in handles {
+            let chunk = handle
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))?;
+            results.extend(chunk);
+        }
+        Ok(results)
+    })
+}
+
+fn transform_contract(
+    declaration: Node,
+    transformers: &mut [Box<dyn IRTransformer + Send>],
+    source: &str,
+) -> Result<(Vec<Contract>, Diagnostics)> {
+    let mut builder = IRBuilder::new();
+    let mut diagnostics = Diagnostics::new();
+    for transformer in transformers.iter_mut() {
+        transformer.check_prerequisites(&builder)?;
+        transformer.transform(&mut builder, &declaration, source)?;
+        diagnostics.extend(transformer.take_diagnostics());
+    }
+    builder.validate()?;
+
+    let contracts = builder
+        .registry()
+        .contracts()
+        .map(|(_name, contract)| contract.clone())
+        .collect();
+    Ok((contracts, diagnostics))
 }
 
 pub fn transform_solidity_to_ir(source: &str) -> Result<Vec<Contract>> {
@@ -123,20 +384,30 @@ pub fn transform_solidity_to_ir_with_filename(
     source: &str,
     filename: Option<&str>,
 ) -> Result<Vec<Contract>> {
-    let mut contracts = if let Some(file) = filename {
-        TransformationPipeline::with_filename(source, file.to_string()).transform()?
-    } else {
-        TransformationPipeline::default(source).transform()?
-    };
+    pipeline(source, filename).transform()
+}
 
-    if let Some(file) = filename {
-        for contract in &mut contracts {
-            contract.metadata.source_file = Some(file.to_string());
-            contract.metadata.source_code = Some(source.to_string());
-        }
-    }
+pub fn transform_solidity_to_ir_with_diagnostics(
+    source: &str,
+    filename: Option<&str>,
+) -> Result<TransformOutput> {
+    pipeline(source, filename).transform_with_diagnostics()
+}
 
-    Ok(contracts)
+pub fn transform_solidity_to_ir_lenient(
+    source: &str,
+    filename: Option<&str>,
+) -> Result<TransformOutput> {
+    pipeline(source, filename)
+        .lenient(true)
+        .transform_with_diagnostics()
+}
+
+fn pipeline(source: &str, filename: Option<&str>) -> TransformationPipeline {
+    match filename {
+        Some(file) => TransformationPipeline::with_filename(source, file.to_string()),
+        None => TransformationPipeline::default(source),
+    }
 }
 
 pub fn transform_solidity_to_ir_with_cfg(source: &str) -> Result<Vec<Contract>> {
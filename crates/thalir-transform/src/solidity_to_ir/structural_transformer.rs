@@ -1,13 +1,26 @@
 use super::control_flow_builder::ControlFlowBuilder;
-use super::expression_transformer::ExpressionTransformer;
-use super::{context::SimpleContext, type_resolver::TypeResolver, IRTransformer};
+use super::expression_transformer::{literal_constant, ExpressionTransformer};
+use super::{
+    context::{LocalScopes, SimpleContext},
+    type_resolver::TypeResolver,
+    Deadline, IRTransformer,
+};
+use super::{Diagnostic, DiagnosticKind, DiagnosticSeverity, Diagnostics};
 use anyhow::Result;
-use std::collections::HashMap;
+use num_bigint::BigUint;
+use num_traits::{ToPrimitive, Zero};
+use std::collections::{HashMap, HashSet};
 use thalir_core::{
+    analysis::CallbackModel,
     builder::{BlockBuilder, ContractBuilder, IRBuilder, InstBuilderExt},
+    contract::{packed_size, StorageLayout},
     function::{Mutability, Visibility},
-    types::Type,
-    values::{SourceLocation, Value},
+    hash::selector_u32,
+    metadata::NatSpec,
+    obfuscation::policy::abi_signature,
+    types::{StructDefinition, StructFieldDef, StructId, Type, TypeRegistry},
+    values::{Constant, SourceLocation, Value},
+    version::{EvmVersion, VersionPragma},
 };
 use tree_sitter::Node;
 
@@ -15,6 +28,42 @@ pub struct StructuralTransformer {
     expression_transformer: ExpressionTransformer,
     control_flow_builder: ControlFlowBuilder,
     filename: String,
+    diagnostics: Diagnostics,
+    value_types: HashMap<String, Type>,
+    /* Functions of the contract being transformed, by source name: IR name and parameter types. */
+    overloads: HashMap<String, Vec<(String, Vec<Type>)>>,
+    /* Contracts, interfaces and libraries declared in the file, for `IERC20(token)` conversions. */
+    contract_names: HashSet<String>,
+    pragma: Option<VersionPragma>,
+    evm_version: Option<EvmVersion>,
+    deadline: Option<Deadline>,
+    unchecked_depth: usize,
+    /* Types a `using SafeMath for ..` directive of the current contract covers; `None` is `*`.
+     * Below 0.7 this includes the directives of its bases. */
+    safe_math: Vec<Option<Type>>,
+    /* Each contract's own `using SafeMath` directives, for the contracts deriving from it. */
+    safe_math_by_contract: HashMap<String, Vec<Option<Type>>>,
+    /* Structs visible to the contract being transformed, by name, and their definitions. */
+    struct_ids: HashMap<String, StructId>,
+    types: TypeRegistry,
+    /* State variables of the current contract that share their slot, by name: byte offset. */
+    packed_state: HashMap<String, u8>,
+    /* `constant` state variables of the current contract with a literal value. They have no slot,
+     * so reads use the value directly. */
+    code_constants: HashMap<String, Constant>,
+}
+
+/* Where a storage expression such as `config.fees.treasury` or `positions[id].owner` lives. A
+ * mapping or array element keeps its base and key, so `balances[a]` still lowers to a mapping
+ * load; anything addressed inside one goes through the slot derived for it. A value type that
+ * shares its word is `Packed`: the word's place, the byte offset from its low end, and the type. */
+#[derive(Clone)]
+enum StoragePlace {
+    Slot(BigUint),
+    Dynamic(Value),
+    MappingEntry { mapping: Value, key: Value },
+    ArrayElement { array: Value, index: Value },
+    Packed(Box<StoragePlace>, u8, Type),
 }
 
 impl StructuralTransformer {
@@ -23,6 +72,20 @@ impl StructuralTransformer {
             expression_transformer: ExpressionTransformer::new(),
             control_flow_builder: ControlFlowBuilder::new(),
             filename: "<unknown>".to_string(),
+            diagnostics: Diagnostics::new(),
+            value_types: HashMap::new(),
+            overloads: HashMap::new(),
+            contract_names: HashSet::new(),
+            pragma: None,
+            evm_version: None,
+            deadline: None,
+            unchecked_depth: 0,
+            safe_math: Vec::new(),
+            safe_math_by_contract: HashMap::new(),
+            struct_ids: HashMap::new(),
+            types: TypeRegistry::new(),
+            packed_state: HashMap::new(),
+            code_constants: HashMap::new(),
         }
     }
 
@@ -31,19 +94,249 @@ impl StructuralTransformer {
             expression_transformer: ExpressionTransformer::new(),
             control_flow_builder: ControlFlowBuilder::new(),
             filename,
+            diagnostics: Diagnostics::new(),
+            value_types: HashMap::new(),
+            overloads: HashMap::new(),
+            contract_names: HashSet::new(),
+            pragma: None,
+            evm_version: None,
+            deadline: None,
+            unchecked_depth: 0,
+            safe_math: Vec::new(),
+            safe_math_by_contract: HashMap::new(),
+            struct_ids: HashMap::new(),
+            types: TypeRegistry::new(),
+            packed_state: HashMap::new(),
+            code_constants: HashMap::new(),
         }
     }
 
+    /* Explicit `--evm-version` wins; otherwise assume the default target of the oldest compiler
+     * the pragma admits. */
+    fn target_evm(&self) -> Option<EvmVersion> {
+        self.evm_version
+            .or_else(|| self.pragma.as_ref()?.minimum.map(EvmVersion::default_for))
+    }
+
+    fn checked_arithmetic(&self) -> bool {
+        self.unchecked_depth == 0
+            && self
+                .pragma
+                .as_ref()
+                .map(VersionPragma::checked_arithmetic)
+                .unwrap_or(true)
+    }
+
+    fn is_safe_math(library: &str) -> bool {
+        matches!(library, "SafeMath" | "SafeMathUpgradeable")
+    }
+
+    /* The type `using SafeMath for uint256;` attaches the library to, `None` for `for *`. Other
+     * libraries and non-integer targets give nothing. */
+    fn safe_math_directive(text: &str) -> Option<Option<Type>> {
+        let rest = text
+            .trim()
+            .strip_prefix("using")?
+            .trim_end()
+            .trim_end_matches(';');
+        let (library, target) = rest.split_once(" for ")?;
+        if !Self::is_safe_math(library.trim()) {
+            return None;
+        }
+        match target.trim().trim_end_matches("global").trim() {
+            "*" => Some(None),
+            target => Some(Some(Self::integer_type_name(target)?)),
+        }
+    }
+
+    fn safe_math_covers(&self, ty: &Type) -> bool {
+        self.safe_math
+            .iter()
+            .any(|covered| covered.is_none() || covered.as_ref() == Some(ty))
+    }
+
+    /* The type of a `SafeMath.mul(a, b)` or covered `a.mul(b)` call, so a chained `.div(c)` still
+     * sees an integer receiver. */
+    fn safe_math_result(
+        &self,
+        callee: Node,
+        source: &str,
+        state_vars: &HashMap<String, (u32, Type)>,
+    ) -> Option<Type> {
+        if !matches!(
+            callee.kind(),
+            "member_expression" | "member_access_expression"
+        ) {
+            return None;
+        }
+        let object = callee
+            .child_by_field_name("object")
+            .or_else(|| callee.child(0))?;
+        let member = callee
+            .child_by_field_name("property")
+            .or_else(|| callee.child_by_field_name("member"))
+            .or_else(|| callee.child(2))?;
+        if !matches!(&source[member.byte_range()], "add" | "sub" | "mul" | "div") {
+            return None;
+        }
+        if Self::is_safe_math(source[object.byte_range()].trim()) {
+            return Some(Type::Uint(256));
+        }
+        self.expression_type(object, source, state_vars)
+            .filter(|ty| self.safe_math_covers(ty))
+    }
+
+    /* SafeMath's add/sub/mul/div revert exactly where 0.8 checked arithmetic does, so folding the
+     * library calls into checked instructions lets overflow analyses read legacy and modern code
+     * the same way. Handles both `SafeMath.add(a, b)` and `a.add(b)`, the latter only when `a` has
+     * a known type that a `using SafeMath` directive covers. */
+    #[allow(clippy::too_many_arguments)]
+    fn fold_safe_math(
+        &mut self,
+        call: Node,
+        receiver: Node,
+        member: &str,
+        source: &str,
+        block: &mut BlockBuilder,
+        param_map: &HashMap<String, u32>,
+        state_vars: &HashMap<String, (u32, Type)>,
+        local_vars: &mut LocalScopes,
+    ) -> Result<Option<Value>> {
+        let library_call = Self::is_safe_math(source[receiver.byte_range()].trim());
+        if !matches!(member, "add" | "sub" | "mul" | "div") {
+            return Ok(None);
+        }
+        if !library_call
+            && !self
+                .expression_type(receiver, source, state_vars)
+                .is_some_and(|ty| self.safe_math_covers(&ty))
+        {
+            return Ok(None);
+        }
+
+        let mut operands = Vec::new();
+        if !library_call {
+            operands.push(receiver);
+        }
+        let mut cursor = call.walk();
+        for child in call.children(&mut cursor) {
+            if child.kind() == "call_argument" {
+                operands.push(child.child(0).unwrap_or(child));
+            }
+        }
+        /* A third operand is the revert reason of the `sub(a, b, "message")` overloads. */
+        let (left_node, right_node) = match operands.as_slice() {
+            [left, right] | [left, right, _] => (*left, *right),
+            _ => return Ok(None),
+        };
+
+        let ty = self
+            .expression_type(left_node, source, state_vars)
+            .or_else(|| self.expression_type(right_node, source, state_vars));
+        if matches!(&ty, Some(ty) if !matches!(ty, Type::Uint(_) | Type::Int(_))) {
+            return Ok(None);
+        }
+        let ty = Self::numeric_type(ty);
+        let left =
+            self.process_expression(left_node, source, block, param_map, state_vars, local_vars)?;
+        let right =
+            self.process_expression(right_node, source, block, param_map, state_vars, local_vars)?;
+        Ok(Some(match member {
+            "add" => block.checked_add(left, right, ty),
+            "sub" => block.checked_sub(left, right, ty),
+            "mul" => block.checked_mul(left, right, ty),
+            _ => block.checked_div(left, right, ty),
+        }))
+    }
+
+    fn version_pragma(node: Node, source: &str) -> Option<VersionPragma> {
+        let text = source[node.byte_range()]
+            .trim()
+            .strip_prefix("pragma")?
+            .trim_start()
+            .strip_prefix("solidity")?;
+        Some(VersionPragma::parse(text.trim_end().trim_end_matches(';')))
+    }
+
     fn source_location_from_node(&self, node: Node) -> SourceLocation {
         SourceLocation::from_node(self.filename.clone(), &node)
     }
 
+    fn record(
+        &mut self,
+        node: Node,
+        severity: DiagnosticSeverity,
+        kind: DiagnosticKind,
+        message: String,
+    ) {
+        self.diagnostics.push(Diagnostic::new(
+            severity,
+            kind,
+            node,
+            &self.filename,
+            message,
+        ));
+    }
+
+    fn unsupported(&mut self, node: Node, source: &str, block: &mut BlockBuilder) -> Value {
+        self.record(
+            node,
+            DiagnosticSeverity::Error,
+            DiagnosticKind::UnsupportedNode,
+            format!(
+                "unsupported {} `{}` lowered to constant 0",
+                node.kind(),
+                first_line(&source[node.byte_range()])
+            ),
+        );
+        block.constant_uint(0, 256)
+    }
+
+    fn unresolved(&mut self, node: Node, source: &str, block: &mut BlockBuilder) -> Value {
+        self.record(
+            node,
+            DiagnosticSeverity::Warning,
+            DiagnosticKind::UnresolvedIdentifier,
+            format!(
+                "could not resolve `{}`, using constant 0",
+                first_line(&source[node.byte_range()])
+            ),
+        );
+        block.constant_uint(0, 256)
+    }
+
+    fn placeholder_amount(&mut self, node: Node, block: &mut BlockBuilder) -> Value {
+        self.record(
+            node,
+            DiagnosticSeverity::Error,
+            DiagnosticKind::HardcodedValue,
+            "ether amount not extracted, using placeholder constant 100".to_string(),
+        );
+        block.constant_uint(100, 256)
+    }
+
+    fn leading_natspec(node: Node, source: &str) -> Option<NatSpec> {
+        let mut comments = Vec::new();
+        let mut prev = node.prev_sibling();
+        while let Some(sibling) = prev {
+            if sibling.kind() != "comment" {
+                break;
+            }
+            comments.push(&source[sibling.byte_range()]);
+            prev = sibling.prev_sibling();
+        }
+        comments.reverse();
+        NatSpec::parse(&comments)
+    }
+
     fn process_source_file(
         &mut self,
         node: Node,
         source: &str,
         builder: &mut IRBuilder,
     ) -> Result<()> {
+        self.read_source_file(node, source);
+
         let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
             match child.kind() {
@@ -57,6 +350,114 @@ impl StructuralTransformer {
         Ok(())
     }
 
+    /* The file-level state every contract in the file sees: the version pragma and the names of
+     * the contracts declared alongside it. */
+    fn read_source_file(&mut self, node: Node, source: &str) {
+        self.struct_ids.clear();
+        let mut cursor = node.walk();
+        self.pragma = node
+            .children(&mut cursor)
+            .filter(|child| child.kind() == "pragma_directive")
+            .find_map(|child| Self::version_pragma(child, source));
+
+        cursor = node.walk();
+        self.contract_names = node
+            .children(&mut cursor)
+            .filter(|child| {
+                matches!(
+                    child.kind(),
+                    "contract_declaration" | "interface_declaration" | "library_declaration"
+                )
+            })
+            .filter_map(|child| child.child_by_field_name("name"))
+            .map(|name| source[name.byte_range()].to_string())
+            .collect();
+    }
+
+    /* Registers the structs a contract can see, those declared at file level and its own, and keeps
+     * their definitions for resolving member chains. Every name is registered before any field is
+     * resolved, so a field may use a struct declared further down. */
+    fn register_structs(
+        &mut self,
+        node: Node,
+        source: &str,
+        contract_builder: &mut ContractBuilder,
+    ) -> Result<()> {
+        let mut declarations = Vec::new();
+        if let Some(file) = node.parent() {
+            let mut cursor = file.walk();
+            declarations.extend(file.children(&mut cursor).filter(|child| {
+                child.kind() == "struct_declaration"
+                    && child.child_by_field_name("name").is_some_and(|name| {
+                        !self.struct_ids.contains_key(&source[name.byte_range()])
+                    })
+            }));
+        }
+        if let Some(body) = node.child_by_field_name("body") {
+            let mut cursor = body.walk();
+            declarations.extend(
+                body.children(&mut cursor)
+                    .filter(|child| child.kind() == "struct_declaration"),
+            );
+        }
+
+        let mut registered = Vec::new();
+        for declaration in declarations {
+            let Some(name) = declaration.child_by_field_name("name") else {
+                continue;
+            };
+            let name = source[name.byte_range()].to_string();
+            let id = contract_builder.types().add_struct(StructDefinition {
+                name: name.clone(),
+                fields: Vec::new(),
+            });
+            self.struct_ids.insert(name, id);
+            registered.push((declaration, id));
+        }
+
+        for (declaration, id) in registered {
+            let ctx = SimpleContext::with_structs(source, &self.struct_ids);
+            let mut fields = Vec::new();
+            for member in Self::struct_members(declaration) {
+                let (Some(name), Some(type_node)) = (
+                    member.child_by_field_name("name"),
+                    member.child_by_field_name("type"),
+                ) else {
+                    continue;
+                };
+                fields.push(StructFieldDef {
+                    name: source[name.byte_range()].to_string(),
+                    field_type: TypeResolver::resolve_type(type_node, &ctx)?,
+                });
+            }
+            if let Some(def) = contract_builder.types().structs.get_mut(&id) {
+                def.fields = fields;
+            }
+        }
+        self.types = contract_builder.types().clone();
+        Ok(())
+    }
+
+    fn struct_members(declaration: Node) -> Vec<Node> {
+        let mut members = Vec::new();
+        let mut cursor = declaration.walk();
+        for child in declaration.children(&mut cursor) {
+            match child.kind() {
+                "struct_member" => members.push(child),
+                "struct_body" => {
+                    let mut body_cursor = child.walk();
+                    members.extend(
+                        child
+                            .children(&mut body_cursor)
+                            .filter(|member| member.kind() == "struct_member"),
+                    );
+                }
+                _ => {}
+            }
+        }
+        members
+    }
+
     fn process_contract(
         &mut self,
         node: Node,
@@ -68,14 +469,69 @@ impl StructuralTransformer {
             .map(|n| &source[n.byte_range()])
             .unwrap_or("UnnamedContract");
 
+        if let Some(deadline) = &self.deadline {
+            deadline.check("transformation")?;
+        }
+
         let mut contract_builder = builder.contract(name);
+        contract_builder.location(self.source_location_from_node(node));
+
+        if let Some(natspec) = Self::leading_natspec(node, source) {
+            contract_builder.natspec(natspec);
+        }
+        let mut cursor = node.walk();
+        for specifier in node
+            .children(&mut cursor)
+            .filter(|child| child.kind() == "inheritance_specifier")
+        {
+            let ancestor = specifier
+                .child_by_field_name("ancestor")
+                .unwrap_or(specifier);
+            let base = source[ancestor.byte_range()]
+                .split('(')
+                .next()
+                .unwrap_or_default()
+                .trim();
+            contract_builder.inherits(base);
+        }
+        if let Some(pragma) = &self.pragma {
+            contract_builder.pragma(pragma.clone());
+        }
+        if let Some(evm_version) = self.target_evm() {
+            contract_builder.evm_version(evm_version);
+        }
+
+        self.register_structs(node, source, &mut contract_builder)?;
 
         if let Some(body_node) = node.child_by_field_name("body") {
-            let mut slot = 0u32;
             let mut cursor = body_node.walk();
             let mut state_vars = std::collections::HashMap::new();
+            let mut offsets = HashMap::new();
+            self.safe_math.clear();
+            self.overloads.clear();
+            self.packed_state.clear();
+            self.code_constants.clear();
 
             for child in body_node.children(&mut cursor) {
+                if child.has_error() {
+                    continue;
+                }
+                if child.kind() == "using_directive" {
+                    if let Some(covered) = Self::safe_math_directive(&source[child.byte_range()]) {
+                        self.safe_math.push(covered);
+                    }
+                }
+                if child.kind() == "function_definition"
+                    && Self::special_function_kind(child, source).is_none()
+                {
+                    let name = &source[child.child_by_field_name("name").unwrap().byte_range()];
+                    let ir_name = self.function_ir_name(name, child, source);
+                    let params = self.extract_parameter_types(child, source)?;
+                    self.overloads
+                        .entry(name.to_string())
+                        .or_default()
+                        .push((ir_name, params));
+                }
                 if child.kind() == "state_variable_declaration" {
                     let var_name = child
                         .child_by_field_name("name")
@@ -83,20 +539,72 @@ impl StructuralTransformer {
                         .unwrap_or("unnamed");
 
                     let ty = if let Some(type_node) = child.child_by_field_name("type") {
-                        let ctx = SimpleContext::new(source);
+                        let ctx = SimpleContext::with_structs(source, &self.struct_ids);
                         TypeResolver::resolve_type(type_node, &ctx)?
                     } else {
                         Type::Uint(256)
                     };
 
-                    contract_builder.state_variable(var_name, ty.clone(), slot);
+                    let mut words = child.walk();
+                    let keywords: Vec<&str> = child
+                        .children(&mut words)
+                        .map(|word| &source[word.byte_range()])
+                        .collect();
+
+                    /* Constants and immutables live in the code, not in storage. */
+                    if keywords
+                        .iter()
+                        .any(|word| matches!(*word, "constant" | "immutable"))
+                    {
+                        if let Some(value) = Self::constant_value(child, source) {
+                            contract_builder.constant(var_name, ty.clone(), value.clone());
+                            self.code_constants.insert(var_name.to_string(), value);
+                        }
+                        continue;
+                    }
+
+                    let (slot, offset) = contract_builder.allocate_state_variable(
+                        var_name,
+                        ty.clone(),
+                        Some(self.source_location_from_node(child)),
+                    );
+                    if keywords.contains(&"private") {
+                        contract_builder.private_state_variable(var_name);
+                    }
+                    let slot = slot.to_u32().unwrap_or(u32::MAX);
                     state_vars.insert(var_name.to_string(), (slot, ty));
-                    slot += 1;
+                    offsets.insert(var_name.to_string(), (slot, offset));
+                }
+            }
+
+            self.safe_math_by_contract
+                .insert(name.to_string(), self.safe_math.clone());
+            if self
+                .pragma
+                .as_ref()
+                .is_some_and(VersionPragma::inherits_using_for)
+            {
+                for base in contract_builder.linearized().iter().skip(1) {
+                    if let Some(directives) = self.safe_math_by_contract.get(base) {
+                        self.safe_math.extend(directives.iter().cloned());
+                    }
+                }
+            }
+
+            /* Only variables that share a word, with each other or with a base contract's last
+             * variable, need their bytes masked out of it. One alone in its slot is used whole. */
+            for (name, &(slot, offset)) in &offsets {
+                let shared = offsets.values().filter(|(other, _)| *other == slot).count() > 1;
+                if offset > 0 || shared {
+                    self.packed_state.insert(name.clone(), offset);
                 }
             }
 
             cursor = body_node.walk();
             for child in body_node.children(&mut cursor) {
+                if child.has_error() {
+                    continue;
+                }
                 match child.kind() {
                     "function_definition" => {
                         self.process_function_in_contract(
@@ -106,7 +614,7 @@ impl StructuralTransformer {
                             &state_vars,
                         )?;
                     }
-                    "constructor_definition" => {
+                    "constructor_definition" | "fallback_receive_definition" => {
                         self.process_function_in_contract(
                             child,
                             source,
@@ -123,6 +631,37 @@ impl StructuralTransformer {
         Ok(())
     }
 
+    /* The value of a `constant` state variable initialised with a literal, looking through casts
+     * such as `address(...)` or `IERC20(...)`. */
+    fn constant_value(declaration: Node, source: &str) -> Option<Constant> {
+        let declared = &source[declaration.byte_range()];
+        let (head, _) = declared.split_once('=')?;
+        if !head.split_whitespace().any(|word| word == "constant") {
+            return None;
+        }
+        let value = declaration.child_by_field_name("value")?;
+        let mut text = source[value.byte_range()].trim();
+        while let Some((callee, rest)) = text.split_once('(') {
+            if !callee
+                .trim()
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_')
+            {
+                return None;
+            }
+            text = rest.strip_suffix(')')?.trim();
+        }
+        literal_constant(text)
+    }
+
+    /* A constructor's base arguments parse as modifier invocations. A base declared in another
+     * file is not among `contract_names`, so a capitalised name there is taken for one as well. */
+    fn is_base_constructor_call(&self, name: &str, special: Option<&str>) -> bool {
+        self.contract_names.contains(name)
+            || (special == Some("constructor")
+                && name.starts_with(|c: char| c.is_ascii_uppercase()))
+    }
+
     fn process_function_in_contract(
         &mut self,
         node: Node,
@@ -130,27 +669,42 @@ impl StructuralTransformer {
         contract_builder: &mut ContractBuilder,
         state_vars: &std::collections::HashMap<String, (u32, Type)>,
     ) -> Result<()> {
-        let base_func_name = if node.kind() == "constructor_definition" {
-            "constructor"
+        if let Some(deadline) = &self.deadline {
+            deadline.check("transformation")?;
+        }
+        let special = Self::special_function_kind(node, source);
+        let base_func_name = if let Some(kind) = special {
+            kind
         } else {
             node.child_by_field_name("name")
                 .map(|n| &source[n.byte_range()])
                 .unwrap_or("unnamed")
         };
 
-        let param_type_names = self.extract_parameter_type_names(node, source);
-
-        let func_name = if param_type_names.is_empty() {
-            base_func_name.to_string()
-        } else {
-            Self::mangle_function_name_from_strings(base_func_name, &param_type_names)
-        };
+        let func_name = self.function_ir_name(base_func_name, node, source);
 
         let mut func_builder = contract_builder.function(&func_name);
 
+        if let Some(natspec) = Self::leading_natspec(node, source) {
+            func_builder.natspec(natspec);
+        }
+        match special {
+            Some("constructor") => func_builder.constructor(),
+            Some("receive") => func_builder.receive(),
+            Some("fallback") => func_builder.fallback(),
+            _ => &mut func_builder,
+        };
+
         let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
             let text = &source[child.byte_range()];
+            if child.kind() == "modifier_invocation" {
+                let modifier = text.split('(').next().unwrap_or_default().trim();
+                if !self.is_base_constructor_call(modifier, special) {
+                    func_builder.modifier(modifier);
+                }
+                continue;
+            }
             match text {
                 "public" => func_builder.visibility(Visibility::Public),
                 "external" => func_builder.visibility(Visibility::External),
@@ -183,7 +737,7 @@ impl StructuralTransformer {
                         .unwrap_or("unnamed");
 
                     let ty = if let Some(type_node) = child.child_by_field_name("type") {
-                        let ctx = SimpleContext::new(source);
+                        let ctx = SimpleContext::with_structs(source, &self.struct_ids);
                         TypeResolver::resolve_type(type_node, &ctx)?
                     } else {
                         Type::Uint(256)
@@ -202,7 +756,7 @@ impl StructuralTransformer {
                         .unwrap_or("unnamed");
 
                     let ty = if let Some(type_node) = child.child_by_field_name("type") {
-                        let ctx = SimpleContext::new(source);
+                        let ctx = SimpleContext::with_structs(source, &self.struct_ids);
                         TypeResolver::resolve_type(type_node, &ctx)?
                     } else {
                         Type::Uint(256)
@@ -213,6 +767,18 @@ impl StructuralTransformer {
             }
         }
 
+        if special.is_none()
+            && matches!(
+                func_builder.current_function().visibility,
+                Visibility::Public | Visibility::External
+            )
+        {
+            let params = func_builder.get_params().iter().map(|p| &p.param_type);
+            if let Some(signature) = abi_signature(base_func_name, params) {
+                func_builder.selector(selector_u32(&signature));
+            }
+        }
+
         if let Some(returns_node) = node.child_by_field_name("return_type") {
             if let Some(type_node) = returns_node.child_by_field_name("type") {
                 let ctx = SimpleContext::new(source);
@@ -223,8 +789,11 @@ impl StructuralTransformer {
 
         if let Some(body_node) = node.child_by_field_name("body") {
             let mut param_map = std::collections::HashMap::new();
+            self.value_types.clear();
             for (idx, param) in func_builder.get_params().iter().enumerate() {
                 param_map.insert(param.name.clone(), idx as u32);
+                self.value_types
+                    .insert(param.name.clone(), param.param_type.clone());
             }
 
             let has_control_flow = self.has_control_flow_statements(body_node);
@@ -246,6 +815,23 @@ impl StructuralTransformer {
         Ok(())
     }
 
+    /* Constructors, `receive` and `fallback` (including the pre-0.6 unnamed `function()`) have no
+     * name field; they are registered under their keyword. */
+    fn special_function_kind(node: Node, source: &str) -> Option<&'static str> {
+        match node.kind() {
+            "constructor_definition" => Some("constructor"),
+            "fallback_receive_definition" => {
+                let mut cursor = node.walk();
+                let receive = node
+                    .children(&mut cursor)
+                    .any(|child| &source[child.byte_range()] == "receive");
+                Some(if receive { "receive" } else { "fallback" })
+            }
+            "function_definition" if node.child_by_field_name("name").is_none() => Some("fallback"),
+            _ => None,
+        }
+    }
+
     fn has_control_flow_statements(&self, node: Node) -> bool {
         let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
@@ -293,12 +879,10 @@ impl StructuralTransformer {
         func_builder: &mut thalir_core::builder::FunctionBuilder,
         block_id_iter: &mut std::vec::IntoIter<thalir_core::block::BlockId>,
     ) -> Result<()> {
-        use thalir_core::values::Value;
         let mut has_return = false;
         let mut return_value = None;
 
-        let mut local_vars: std::collections::HashMap<String, Value> =
-            std::collections::HashMap::new();
+        let mut local_vars = LocalScopes::new();
 
         let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
@@ -382,7 +966,7 @@ impl StructuralTransformer {
 
                             if let Some(name_node) = name_node {
                                 let name = &source[name_node.byte_range()];
-                                local_vars.insert(name.to_string(), value);
+                                local_vars.declare(name.to_string(), value);
                             }
                         }
                     }
@@ -400,7 +984,10 @@ impl StructuralTransformer {
         Ok(())
     }
 
+    /* Both branches see the locals declared before the `if`; each gets its own child scope from
+     * `process_block_with_jump`, so its declarations end with it. */
     #[allow(dead_code)]
+    #[allow(clippy::too_many_arguments)]
     fn process_if_with_blocks(
         &mut self,
         node: Node,
@@ -408,6 +995,7 @@ impl StructuralTransformer {
         mut current_block: thalir_core::builder::BlockBuilder,
         param_map: &std::collections::HashMap<String, u32>,
         state_vars: &std::collections::HashMap<String, (u32, Type)>,
+        local_vars: &mut LocalScopes,
         func_builder: &mut thalir_core::builder::FunctionBuilder,
         block_id_iter: &mut std::vec::IntoIter<thalir_core::block::BlockId>,
     ) -> Result<()> {
@@ -424,12 +1012,13 @@ impl StructuralTransformer {
         let condition_node = node
             .child_by_field_name("condition")
             .ok_or_else(|| anyhow::anyhow!("If statement missing condition"))?;
-        let condition = self.process_expression_simple(
+        let condition = self.process_expression(
             condition_node,
             source,
             &mut current_block,
             param_map,
             state_vars,
+            local_vars,
         )?;
 
         current_block.branch(condition, then_block_id, else_block_id)?;
@@ -438,14 +1027,13 @@ impl StructuralTransformer {
             .child_by_field_name("body")
             .ok_or_else(|| anyhow::anyhow!("If statement missing then body"))?;
         let mut then_block = func_builder.block_with_id(then_block_id);
-        let mut local_vars = std::collections::HashMap::new();
         self.process_block_with_jump(
             then_body,
             source,
             &mut then_block,
             param_map,
             state_vars,
-            &mut local_vars,
+            local_vars,
             merge_block_id,
         )?;
 
@@ -463,7 +1051,7 @@ impl StructuralTransformer {
                 &mut else_block,
                 param_map,
                 state_vars,
-                &mut local_vars,
+                local_vars,
                 merge_block_id,
             )?;
         } else {
@@ -484,11 +1072,12 @@ impl StructuralTransformer {
         block: &mut thalir_core::builder::BlockBuilder,
         param_map: &std::collections::HashMap<String, u32>,
         state_vars: &std::collections::HashMap<String, (u32, Type)>,
-        local_vars: &mut std::collections::HashMap<String, thalir_core::values::Value>,
+        local_vars: &mut LocalScopes,
         jump_target: thalir_core::block::BlockId,
     ) -> Result<()> {
         let mut has_return = false;
         let mut return_value = None;
+        local_vars.push_scope();
 
         let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
@@ -537,6 +1126,8 @@ impl StructuralTransformer {
             }
         }
 
+        local_vars.pop_scope();
+
         if let Some(val) = return_value {
             block.return_value(val)?;
         } else {
@@ -554,8 +1145,7 @@ impl StructuralTransformer {
         param_map: &std::collections::HashMap<String, u32>,
         state_vars: &std::collections::HashMap<String, (u32, Type)>,
     ) -> Result<()> {
-        let mut local_vars: std::collections::HashMap<String, thalir_core::values::Value> =
-            std::collections::HashMap::new();
+        let mut local_vars = LocalScopes::new();
         self.process_function_body_impl(
             node,
             source,
@@ -574,11 +1164,12 @@ impl StructuralTransformer {
         block: &mut thalir_core::builder::BlockBuilder,
         param_map: &std::collections::HashMap<String, u32>,
         state_vars: &std::collections::HashMap<String, (u32, Type)>,
-        mut local_vars: &mut std::collections::HashMap<String, thalir_core::values::Value>,
+        mut local_vars: &mut LocalScopes,
         add_terminator: bool,
     ) -> Result<()> {
         let mut has_return = false;
         let mut return_value = None;
+        local_vars.push_scope();
 
         let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
@@ -777,6 +1368,16 @@ impl StructuralTransformer {
                             None
                         });
 
+                        if let (Some(name_node), Some(type_node)) =
+                            (name_node, decl.child_by_field_name("type"))
+                        {
+                            let ctx = SimpleContext::new(source);
+                            if let Ok(ty) = TypeResolver::resolve_type(type_node, &ctx) {
+                                self.value_types
+                                    .insert(source[name_node.byte_range()].to_string(), ty);
+                            }
+                        }
+
                         let init_expr = actual_statement.child(2);
 
                         if let Some(init_expr) = init_expr {
@@ -791,7 +1392,7 @@ impl StructuralTransformer {
 
                             if let Some(name_node) = name_node {
                                 let name = &source[name_node.byte_range()];
-                                local_vars.insert(name.to_string(), value);
+                                local_vars.declare(name.to_string(), value);
                             }
                         }
                     }
@@ -808,10 +1409,45 @@ impl StructuralTransformer {
                         )?;
                     }
                 }
+                "block_statement" => {
+                    let unchecked = actual_statement
+                        .child(0)
+                        .is_some_and(|child| child.kind() == "unchecked");
+                    if unchecked {
+                        self.unchecked_depth += 1;
+                    }
+                    let lowered = self.process_function_body_impl(
+                        actual_statement,
+                        source,
+                        block,
+                        param_map,
+                        state_vars,
+                        local_vars,
+                        false,
+                    );
+                    if unchecked {
+                        self.unchecked_depth -= 1;
+                    }
+                    lowered?;
+                }
+                kind if actual_statement.is_named() && kind != "comment" => {
+                    self.record(
+                        actual_statement,
+                        DiagnosticSeverity::Warning,
+                        DiagnosticKind::DroppedStatement,
+                        format!(
+                            "{} `{}` is not lowered",
+                            kind,
+                            first_line(&source[actual_statement.byte_range()])
+                        ),
+                    );
+                }
                 _ => {}
             }
         }
 
+        local_vars.pop_scope();
+
         if add_terminator {
             if let Some(val) = return_value {
                 block.return_value(val)?;
@@ -823,25 +1459,6 @@ impl StructuralTransformer {
         Ok(())
     }
 
-    fn process_expression_simple(
-        &mut self,
-        node: Node,
-        source: &str,
-        block: &mut thalir_core::builder::BlockBuilder,
-        param_map: &std::collections::HashMap<String, u32>,
-        state_vars: &std::collections::HashMap<String, (u32, Type)>,
-    ) -> Result<thalir_core::values::Value> {
-        let mut empty_locals = std::collections::HashMap::new();
-        self.process_expression(
-            node,
-            source,
-            block,
-            param_map,
-            state_vars,
-            &mut empty_locals,
-        )
-    }
-
     fn process_expression(
         &mut self,
         node: Node,
@@ -849,7 +1466,7 @@ impl StructuralTransformer {
         block: &mut thalir_core::builder::BlockBuilder,
         param_map: &std::collections::HashMap<String, u32>,
         state_vars: &std::collections::HashMap<String, (u32, Type)>,
-        local_vars: &mut std::collections::HashMap<String, thalir_core::values::Value>,
+        local_vars: &mut LocalScopes,
     ) -> Result<thalir_core::values::Value> {
         use thalir_core::types::Type;
         use thalir_core::values::Value;
@@ -865,8 +1482,9 @@ impl StructuralTransformer {
         match actual_node.kind() {
             "number_literal" => {
                 let text = &source[actual_node.byte_range()];
-                let value = text.parse::<u64>().unwrap_or(0);
-                Ok(block.constant_uint(value, 256))
+                Ok(literal_constant(text)
+                    .map(Value::Constant)
+                    .unwrap_or_else(|| block.constant_uint(0, 256)))
             }
             "identifier" => {
                 let name = &source[actual_node.byte_range()];
@@ -876,10 +1494,14 @@ impl StructuralTransformer {
                 } else if let Some(&param_idx) = param_map.get(name) {
                     Ok(Value::Param(thalir_core::values::ParamId(param_idx)))
                 } else if let Some(&(slot, ref ty)) = state_vars.get(name) {
-                    let slot_bigint = num_bigint::BigUint::from(slot);
-                    Ok(block.storage_load(slot_bigint))
+                    let place = self.state_place(name, slot, ty);
+                    Ok(Self::load_place(block, place))
+                } else if let Some(value) = self.code_constants.get(name) {
+                    Ok(Value::Constant(value.clone()))
+                } else if name == "this" {
+                    Ok(block.this_address())
                 } else {
-                    Ok(block.constant_uint(0, 256))
+                    Ok(self.unresolved(actual_node, source, block))
                 }
             }
             "binary_expression" => {
@@ -887,6 +1509,14 @@ impl StructuralTransformer {
                 let right_node = actual_node.child_by_field_name("right").unwrap();
                 let op_node = actual_node.child_by_field_name("operator").unwrap();
 
+                let left_type = self.expression_type(left_node, source, state_vars);
+                let operand_type = Self::numeric_type(
+                    left_type
+                        .clone()
+                        .or_else(|| self.expression_type(right_node, source, state_vars)),
+                );
+                let signed = operand_type.is_signed();
+
                 let left = self.process_expression(
                     left_node, source, block, param_map, state_vars, local_vars,
                 )?;
@@ -895,17 +1525,22 @@ impl StructuralTransformer {
                 )?;
                 let op = &source[op_node.byte_range()];
 
+                if let Some(value) =
+                    self.arithmetic(block, op, left.clone(), right.clone(), operand_type)
+                {
+                    return Ok(value);
+                }
+
                 match op {
-                    "+" => Ok(block.add(left, right, Type::Uint(256))),
-                    "-" => Ok(block.sub(left, right, Type::Uint(256))),
-                    "*" => Ok(block.mul(left, right, Type::Uint(256))),
-                    "/" => Ok(block.div(left, right, Type::Uint(256))),
-                    "%" => Ok(block.mod_(left, right, Type::Uint(256))),
                     "==" => Ok(block.eq(left, right)),
                     "!=" => Ok(block.ne(left, right)),
+                    "<" if signed => Ok(block.slt(left, right)),
                     "<" => Ok(block.lt(left, right)),
+                    "<=" if signed => Ok(block.sle(left, right)),
                     "<=" => Ok(block.le(left, right)),
+                    ">" if signed => Ok(block.sgt(left, right)),
                     ">" => Ok(block.gt(left, right)),
+                    ">=" if signed => Ok(block.sge(left, right)),
                     ">=" => Ok(block.ge(left, right)),
 
                     "||" => {
@@ -921,8 +1556,19 @@ impl StructuralTransformer {
                     "|" => Ok(block.or(left, right)),
                     "^" => Ok(block.xor(left, right)),
                     "<<" => Ok(block.shl(left, right)),
+                    ">>" if left_type.as_ref().is_some_and(Type::is_signed) => {
+                        Ok(block.sar(left, right))
+                    }
                     ">>" => Ok(block.shr(left, right)),
-                    _ => Ok(left),
+                    _ => {
+                        self.record(
+                            actual_node,
+                            DiagnosticSeverity::Error,
+                            DiagnosticKind::UnsupportedNode,
+                            format!("unsupported operator `{}`, kept left operand", op),
+                        );
+                        Ok(left)
+                    }
                 }
             }
             "assignment_expression" => {
@@ -943,9 +1589,11 @@ impl StructuralTransformer {
                     "identifier" => {
                         let name = &source[actual_left.byte_range()];
 
-                        if let Some(&(slot, ref ty)) = state_vars.get(name) {
-                            let slot_bigint = num_bigint::BigUint::from(slot);
-                            block.storage_store(slot_bigint, value.clone());
+                        if !local_vars.assign(name, value.clone()) {
+                            if let Some(&(slot, ref ty)) = state_vars.get(name) {
+                                let place = self.state_place(name, slot, ty);
+                                Self::store_place(block, place, value.clone());
+                            }
                         }
                     }
                     "index_access_expression" | "subscript_expression" | "array_access" => {
@@ -997,9 +1645,30 @@ impl StructuralTransformer {
                                     }
                                     _ => {}
                                 }
+                            } else if let Some((place, _)) = self.storage_place(
+                                actual_left,
+                                source,
+                                block,
+                                param_map,
+                                state_vars,
+                                local_vars,
+                            )? {
+                                Self::store_place(block, place, value.clone());
                             }
                         }
                     }
+                    "member_access_expression" | "member_expression" => {
+                        if let Some((place, _)) = self.storage_place(
+                            actual_left,
+                            source,
+                            block,
+                            param_map,
+                            state_vars,
+                            local_vars,
+                        )? {
+                            Self::store_place(block, place, value.clone());
+                        }
+                    }
                     _ => {}
                 }
 
@@ -1027,22 +1696,22 @@ impl StructuralTransformer {
                     "identifier" => {
                         let name = &source[actual_left.byte_range()];
 
-                        if let Some(&(slot, ref _ty)) = state_vars.get(name) {
-                            let slot_bigint = num_bigint::BigUint::from(slot);
-
-                            let current = block.storage_load(slot_bigint.clone());
-
-                            let new_value = match operator {
-                                "+=" => block.add(current, right_value.clone(), Type::Uint(256)),
-                                "-=" => block.sub(current, right_value.clone(), Type::Uint(256)),
-                                "*=" => block.mul(current, right_value.clone(), Type::Uint(256)),
-                                "/=" => block.div(current, right_value.clone(), Type::Uint(256)),
-                                "%=" => block.mod_(current, right_value.clone(), Type::Uint(256)),
-                                _ => right_value.clone(),
-                            };
-
-                            block.storage_store(slot_bigint, new_value.clone());
+                        if let Some(current) = local_vars.get(name).cloned() {
+                            let new_value = self
+                                .arithmetic(
+                                    block,
+                                    operator.trim_end_matches('='),
+                                    current,
+                                    right_value.clone(),
+                                    Self::numeric_type(self.value_types.get(name).cloned()),
+                                )
+                                .unwrap_or_else(|| right_value.clone());
+
+                            local_vars.assign(name, new_value.clone());
                             Ok(new_value)
+                        } else if let Some(&(slot, ref ty)) = state_vars.get(name) {
+                            let place = self.state_place(name, slot, ty);
+                            Ok(self.update_place(block, place, ty.clone(), operator, right_value))
                         } else {
                             Ok(right_value)
                         }
@@ -1071,7 +1740,7 @@ impl StructuralTransformer {
 
                             if let Some(&(slot, ref ty)) = state_vars.get(base_name) {
                                 match ty {
-                                    Type::Mapping(_, _) => {
+                                    Type::Mapping(_, value_ty) => {
                                         let key = self.process_expression(
                                             index, source, block, param_map, state_vars, local_vars,
                                         )?;
@@ -1084,39 +1753,20 @@ impl StructuralTransformer {
                                         let current =
                                             block.mapping_load(mapping.clone(), key.clone());
 
-                                        let new_value = match operator {
-                                            "+=" => block.add(
-                                                current,
-                                                right_value.clone(),
-                                                Type::Uint(256),
-                                            ),
-                                            "-=" => block.sub(
-                                                current,
-                                                right_value.clone(),
-                                                Type::Uint(256),
-                                            ),
-                                            "*=" => block.mul(
-                                                current,
-                                                right_value.clone(),
-                                                Type::Uint(256),
-                                            ),
-                                            "/=" => block.div(
-                                                current,
-                                                right_value.clone(),
-                                                Type::Uint(256),
-                                            ),
-                                            "%=" => block.mod_(
+                                        let new_value = self
+                                            .arithmetic(
+                                                block,
+                                                operator.trim_end_matches('='),
                                                 current,
                                                 right_value.clone(),
-                                                Type::Uint(256),
-                                            ),
-                                            _ => right_value.clone(),
-                                        };
+                                                Self::numeric_type(Some(value_ty.as_ref().clone())),
+                                            )
+                                            .unwrap_or_else(|| right_value.clone());
 
                                         block.mapping_store(mapping, key, new_value.clone());
                                         Ok(new_value)
                                     }
-                                    Type::Array(_, _) => {
+                                    Type::Array(value_ty, _) => {
                                         let index_val = self.process_expression(
                                             index, source, block, param_map, state_vars, local_vars,
                                         )?;
@@ -1129,34 +1779,15 @@ impl StructuralTransformer {
                                         let current =
                                             block.array_load(array.clone(), index_val.clone());
 
-                                        let new_value = match operator {
-                                            "+=" => block.add(
-                                                current,
-                                                right_value.clone(),
-                                                Type::Uint(256),
-                                            ),
-                                            "-=" => block.sub(
-                                                current,
-                                                right_value.clone(),
-                                                Type::Uint(256),
-                                            ),
-                                            "*=" => block.mul(
+                                        let new_value = self
+                                            .arithmetic(
+                                                block,
+                                                operator.trim_end_matches('='),
                                                 current,
                                                 right_value.clone(),
-                                                Type::Uint(256),
-                                            ),
-                                            "/=" => block.div(
-                                                current,
-                                                right_value.clone(),
-                                                Type::Uint(256),
-                                            ),
-                                            "%=" => block.mod_(
-                                                current,
-                                                right_value.clone(),
-                                                Type::Uint(256),
-                                            ),
-                                            _ => right_value.clone(),
-                                        };
+                                                Self::numeric_type(Some(value_ty.as_ref().clone())),
+                                            )
+                                            .unwrap_or_else(|| right_value.clone());
 
                                         block.array_store(array, index_val, new_value.clone());
                                         Ok(new_value)
@@ -1164,16 +1795,47 @@ impl StructuralTransformer {
                                     _ => Ok(right_value),
                                 }
                             } else {
-                                Ok(right_value)
+                                match self.storage_place(
+                                    actual_left,
+                                    source,
+                                    block,
+                                    param_map,
+                                    state_vars,
+                                    local_vars,
+                                )? {
+                                    Some((place, ty)) => Ok(self.update_place(
+                                        block,
+                                        place,
+                                        ty,
+                                        operator,
+                                        right_value,
+                                    )),
+                                    None => Ok(right_value),
+                                }
                             }
                         } else {
                             Ok(right_value)
                         }
                     }
+                    "member_access_expression" | "member_expression" => {
+                        match self.storage_place(
+                            actual_left,
+                            source,
+                            block,
+                            param_map,
+                            state_vars,
+                            local_vars,
+                        )? {
+                            Some((place, ty)) => {
+                                Ok(self.update_place(block, place, ty, operator, right_value))
+                            }
+                            None => Ok(right_value),
+                        }
+                    }
                     _ => Ok(right_value),
                 }
             }
-            "call_expression" | "function_call_expression" => {
+            "call_expression" | "function_call_expression" | "type_cast_expression" => {
                 let function_node = actual_node
                     .child_by_field_name("function")
                     .or_else(|| actual_node.child(0));
@@ -1239,7 +1901,7 @@ impl StructuralTransformer {
 
                                 match member_name {
                                     "transfer" => {
-                                        let amount = block.constant_uint(100, 256);
+                                        let amount = self.placeholder_amount(actual_node, block);
                                         let selector = block.constant_uint(0, 32);
                                         let result = block.call_external(
                                             target,
@@ -1251,7 +1913,7 @@ impl StructuralTransformer {
                                         return Ok(block.constant_uint(0, 256));
                                     }
                                     "send" => {
-                                        let amount = block.constant_uint(100, 256);
+                                        let amount = self.placeholder_amount(actual_node, block);
                                         let selector = block.constant_uint(0, 32);
                                         return Ok(block.call_external(
                                             target,
@@ -1261,7 +1923,7 @@ impl StructuralTransformer {
                                         ));
                                     }
                                     "call" => {
-                                        let amount = block.constant_uint(100, 256);
+                                        let amount = self.placeholder_amount(actual_node, block);
                                         let selector = block.constant_uint(0, 32);
                                         let result = block.call_external(
                                             target,
@@ -1278,20 +1940,17 @@ impl StructuralTransformer {
                         }
                     }
 
-                    if func_text == "payable" {
-                        if let Some(args_node) = actual_node.child_by_field_name("arguments") {
-                            let mut cursor = args_node.walk();
-                            for child in args_node.children(&mut cursor) {
-                                if child.kind() != "," && child.kind() != "(" && child.kind() != ")"
-                                {
-                                    return self.process_expression(
-                                        child, source, block, param_map, state_vars, local_vars,
-                                    );
-                                }
-                            }
-                        }
-
-                        return Ok(block.constant_uint(0, 160));
+                    if let Some(to) = self.conversion_target(func_text.trim()) {
+                        return self.process_conversion(
+                            actual_node,
+                            func_node,
+                            to,
+                            source,
+                            block,
+                            param_map,
+                            state_vars,
+                            local_vars,
+                        );
                     }
 
                     let actual_func =
@@ -1371,6 +2030,19 @@ impl StructuralTransformer {
                                 return Ok(block.call_internal(&parent_func_name, args));
                             }
 
+                            if let Some(value) = self.fold_safe_math(
+                                actual_node,
+                                obj,
+                                member_name,
+                                source,
+                                block,
+                                param_map,
+                                state_vars,
+                                local_vars,
+                            )? {
+                                return Ok(value);
+                            }
+
                             if member_name == "transfer"
                                 || member_name == "send"
                                 || member_name == "call"
@@ -1423,7 +2095,7 @@ impl StructuralTransformer {
 
                                 match member_name {
                                     "transfer" => {
-                                        let amount = block.constant_uint(100, 256);
+                                        let amount = self.placeholder_amount(actual_node, block);
                                         let selector = block.constant_uint(0, 32);
                                         let result = block.call_external(
                                             target.clone(),
@@ -1435,7 +2107,7 @@ impl StructuralTransformer {
                                         return Ok(block.constant_uint(0, 256));
                                     }
                                     "send" => {
-                                        let amount = block.constant_uint(100, 256);
+                                        let amount = self.placeholder_amount(actual_node, block);
                                         let selector = block.constant_uint(0, 32);
                                         let result = block.call_external(
                                             target.clone(),
@@ -1446,7 +2118,7 @@ impl StructuralTransformer {
                                         return Ok(result);
                                     }
                                     "call" => {
-                                        let amount = block.constant_uint(100, 256);
+                                        let amount = self.placeholder_amount(actual_node, block);
                                         let selector = block.constant_uint(0, 32);
                                         let result = block.call_external(
                                             target.clone(),
@@ -1510,6 +2182,7 @@ impl StructuralTransformer {
                                     Type::Address | Type::Contract(_) | Type::String
                                 ) {
                                     let mut args = Vec::new();
+                                    let mut arg_nodes = Vec::new();
                                     let mut cursor = actual_node.walk();
                                     for child in actual_node.children(&mut cursor) {
                                         if child.kind() == "call_argument" {
@@ -1523,13 +2196,20 @@ impl StructuralTransformer {
                                                 local_vars,
                                             )?;
                                             args.push(arg_value);
+                                            arg_nodes.push(arg_expr);
                                         }
                                     }
 
                                     let target =
                                         block.storage_load(num_bigint::BigUint::from(slot));
 
-                                    let selector = block.constant_uint(0, 32);
+                                    let selector = self.external_selector(
+                                        member_name,
+                                        &arg_nodes,
+                                        source,
+                                        state_vars,
+                                    );
+                                    let selector = block.constant_uint(selector as u64, 32);
                                     return Ok(block.call_external(target, selector, args, None));
                                 }
                             }
@@ -1601,6 +2281,23 @@ impl StructuralTransformer {
                             }
                             Ok(block.constant_uint(0, 256))
                         }
+                        "blockhash" | "blobhash" => {
+                            let mut cursor = actual_node.walk();
+                            let index = actual_node
+                                .children(&mut cursor)
+                                .find(|child| child.kind() == "call_argument");
+                            let index = match index {
+                                Some(arg) => self.process_expression(
+                                    arg, source, block, param_map, state_vars, local_vars,
+                                )?,
+                                None => block.constant_uint(0, 256),
+                            };
+                            Ok(match func_name {
+                                "blockhash" => block.block_hash(index),
+                                _ => block.blob_hash(index),
+                            })
+                        }
+                        "gasleft" => Ok(block.gas_left()),
                         "revert" => {
                             let mut message = "Transaction reverted";
                             if let Some(args_node) = actual_node.child_by_field_name("arguments") {
@@ -1789,6 +2486,7 @@ impl StructuralTransformer {
                             }
 
                             let mut args = Vec::new();
+                            let mut arg_types = Vec::new();
                             if let Some(args_node) = actual_node.child_by_field_name("arguments") {
                                 let mut cursor = args_node.walk();
                                 for child in args_node.children(&mut cursor) {
@@ -1800,11 +2498,19 @@ impl StructuralTransformer {
                                             child, source, block, param_map, state_vars, local_vars,
                                         )?;
                                         args.push(arg_value);
+                                        let expr = if child.kind() == "call_argument" {
+                                            child.named_child(0).unwrap_or(child)
+                                        } else {
+                                            child
+                                        };
+                                        arg_types
+                                            .push(self.expression_type(expr, source, state_vars));
                                     }
                                 }
                             }
 
-                            Ok(block.call_internal(func_name, args))
+                            let target = self.resolve_overload(func_name, &arg_types);
+                            Ok(block.call_internal(&target, args))
                         }
                     }
                 } else {
@@ -1839,11 +2545,35 @@ impl StructuralTransformer {
                         ("msg", "sig") => Ok(block.msg_sig()),
                         ("block", "number") => Ok(block.block_number()),
                         ("block", "timestamp") => Ok(block.block_timestamp()),
+                        ("block", "difficulty")
+                            if self.target_evm().is_some_and(|evm| evm.has_prevrandao()) =>
+                        {
+                            Ok(block.block_prevrandao())
+                        }
                         ("block", "difficulty") => Ok(block.block_difficulty()),
+                        ("block", "prevrandao") => Ok(block.block_prevrandao()),
                         ("block", "gaslimit") => Ok(block.block_gaslimit()),
                         ("block", "coinbase") => Ok(block.block_coinbase()),
                         ("block", "chainid") => Ok(block.block_chainid()),
                         ("block", "basefee") => Ok(block.block_basefee()),
+                        ("block", "blobbasefee") => Ok(block.block_blobbasefee()),
+                        ("address(this)", "balance") => Ok(block.self_balance()),
+                        (_, "balance")
+                            if obj.kind() == "call_expression"
+                                && obj_name.starts_with("address(") =>
+                        {
+                            let mut cursor = obj.walk();
+                            let inner = obj
+                                .children(&mut cursor)
+                                .find(|child| child.kind() == "call_argument");
+                            let address = match inner {
+                                Some(arg) => self.process_expression(
+                                    arg, source, block, param_map, state_vars, local_vars,
+                                )?,
+                                None => self.unresolved(obj, source, block),
+                            };
+                            Ok(block.balance(address))
+                        }
                         ("tx", "origin") => Ok(block.tx_origin()),
                         ("tx", "gasprice") => Ok(block.tx_gasprice()),
                         _ => {
@@ -1862,13 +2592,32 @@ impl StructuralTransformer {
                                 if let Some(array_val) = local_vars.get(obj_name) {
                                     return Ok(block.array_length(array_val.clone()));
                                 }
+
+                                if let Some((place, Type::Array(_, _))) = self.storage_place(
+                                    obj, source, block, param_map, state_vars, local_vars,
+                                )? {
+                                    if let Some(array) = Self::place_slot(block, place) {
+                                        return Ok(block.array_length(array));
+                                    }
+                                }
+                            }
+
+                            if let Some((place, _)) = self.storage_place(
+                                actual_node,
+                                source,
+                                block,
+                                param_map,
+                                state_vars,
+                                local_vars,
+                            )? {
+                                return Ok(Self::load_place(block, place));
                             }
 
                             if let Some(&(slot, ref ty)) = state_vars.get(obj_name) {
                                 let slot_bigint = num_bigint::BigUint::from(slot);
                                 Ok(block.storage_load(slot_bigint))
                             } else {
-                                Ok(block.constant_uint(0, 256))
+                                Ok(self.unresolved(actual_node, source, block))
                             }
                         }
                     }
@@ -1926,13 +2675,22 @@ impl StructuralTransformer {
                                     index_val,
                                 ))
                             }
-                            _ => Ok(block.constant_uint(0, 256)),
+                            _ => Ok(self.unsupported(actual_node, source, block)),
                         }
+                    } else if let Some((place, _)) = self.storage_place(
+                        actual_node,
+                        source,
+                        block,
+                        param_map,
+                        state_vars,
+                        local_vars,
+                    )? {
+                        Ok(Self::load_place(block, place))
                     } else {
-                        Ok(block.constant_uint(0, 256))
+                        Ok(self.unresolved(actual_node, source, block))
                     }
                 } else {
-                    Ok(block.constant_uint(0, 256))
+                    Ok(self.unsupported(actual_node, source, block))
                 }
             }
             "boolean_literal" => {
@@ -1940,7 +2698,507 @@ impl StructuralTransformer {
                 let value = text == "true";
                 Ok(block.constant_bool(value))
             }
-            _ => Ok(block.constant_uint(0, 256)),
+            _ => Ok(self.unsupported(actual_node, source, block)),
+        }
+    }
+
+    fn expression_type(
+        &self,
+        node: Node,
+        source: &str,
+        state_vars: &std::collections::HashMap<String, (u32, Type)>,
+    ) -> Option<Type> {
+        let node = if node.kind() == "expression" && node.child_count() > 0 {
+            node.child(0).unwrap()
+        } else {
+            node
+        };
+
+        match node.kind() {
+            "identifier" => {
+                let name = &source[node.byte_range()];
+                self.value_types
+                    .get(name)
+                    .cloned()
+                    .or_else(|| state_vars.get(name).map(|(_, ty)| ty.clone()))
+                    .or_else(|| (name == "this").then_some(Type::Address))
+            }
+            "parenthesized_expression" | "tuple_expression" => node
+                .named_child(0)
+                .and_then(|inner| self.expression_type(inner, source, state_vars)),
+            "unary_expression" => {
+                let operand = node
+                    .child_by_field_name("argument")
+                    .or_else(|| node.named_child(0))?;
+                let ty = self.expression_type(operand, source, state_vars);
+                if source[node.byte_range()].starts_with('-') {
+                    ty.or(Some(Type::Int(256)))
+                } else {
+                    ty
+                }
+            }
+            "binary_expression" => {
+                let op = node
+                    .child_by_field_name("operator")
+                    .map(|op| &source[op.byte_range()])
+                    .unwrap_or("");
+                let left = node.child_by_field_name("left")?;
+                match op {
+                    "==" | "!=" | "<" | ">" | "<=" | ">=" | "&&" | "||" => Some(Type::Bool),
+                    "<<" | ">>" | "**" => self.expression_type(left, source, state_vars),
+                    _ => self.expression_type(left, source, state_vars).or_else(|| {
+                        node.child_by_field_name("right")
+                            .and_then(|right| self.expression_type(right, source, state_vars))
+                    }),
+                }
+            }
+            "index_access_expression" | "subscript_expression" | "array_access" => {
+                let base = node.child_by_field_name("base").or_else(|| node.child(0))?;
+                match self.expression_type(base, source, state_vars)? {
+                    Type::Mapping(_, value_ty) => Some(*value_ty),
+                    Type::Array(element_ty, _) => Some(*element_ty),
+                    _ => None,
+                }
+            }
+            "call_expression" | "function_call_expression" | "type_cast_expression" => {
+                let callee = node
+                    .child_by_field_name("function")
+                    .or_else(|| node.child(0))?;
+                let callee = match callee.kind() {
+                    "expression" => callee.child(0).unwrap_or(callee),
+                    _ => callee,
+                };
+                self.conversion_target(source[callee.byte_range()].trim())
+                    .or_else(|| self.safe_math_result(callee, source, state_vars))
+            }
+            _ => None,
+        }
+    }
+
+    /* The type a call-shaped conversion produces. Contract and interface conversions yield the
+     * address they wrap. */
+    fn conversion_target(&self, callee: &str) -> Option<Type> {
+        match callee {
+            "address" | "payable" | "address payable" => Some(Type::Address),
+            _ if self.contract_names.contains(callee) => Some(Type::Address),
+            _ => Self::integer_type_name(callee),
+        }
+    }
+
+    /* Lowers `address(x)`, `payable(x)`, `uint160(x)` and `IERC20(x)` to explicit width changes.
+     * When the operand's type is known, narrowing truncates and widening zero- or sign-extends
+     * by the operand's signedness; whatever is left (sign or address reinterpretation, unknown
+     * operands) is a `Cast`. */
+    #[allow(clippy::too_many_arguments)]
+    fn process_conversion(
+        &mut self,
+        node: Node,
+        callee: Node,
+        to: Type,
+        source: &str,
+        block: &mut BlockBuilder,
+        param_map: &HashMap<String, u32>,
+        state_vars: &HashMap<String, (u32, Type)>,
+        local_vars: &LocalScopes,
+    ) -> Result<Value> {
+        let mut cursor = node.walk();
+        let operand = node
+            .named_children(&mut cursor)
+            .find(|child| child.id() != callee.id());
+        let Some(operand) = operand else {
+            return Ok(self.unresolved(node, source, block));
+        };
+        let expr = if operand.kind() == "call_argument" {
+            operand.named_child(0).unwrap_or(operand)
+        } else {
+            operand
+        };
+        let from = self.expression_type(expr, source, state_vars);
+        let value =
+            self.process_expression(operand, source, block, param_map, state_vars, local_vars)?;
+
+        let width = |ty: &Type| match ty {
+            Type::Uint(bits) | Type::Int(bits) => Some(*bits),
+            Type::Address | Type::Contract(_) => Some(160),
+            _ => None,
+        };
+        let Some(from) = from else {
+            return Ok(block.cast(value, to));
+        };
+        if from == to {
+            return Ok(value);
+        }
+        let resized = match (width(&from), width(&to)) {
+            (Some(from_bits), Some(to_bits)) if from_bits != to_bits => {
+                let (ty, signed) = if from.is_signed() {
+                    (Type::Int(to_bits), true)
+                } else {
+                    (Type::Uint(to_bits), false)
+                };
+                let value = if from_bits > to_bits {
+                    block.trunc(value, ty.clone())
+                } else if signed {
+                    block.sext(value, ty.clone())
+                } else {
+                    block.zext(value, ty.clone())
+                };
+                (value, ty)
+            }
+            _ => (value, from),
+        };
+        Ok(match resized {
+            (value, ty) if ty == to => value,
+            (value, _) => block.cast(value, to),
+        })
+    }
+
+    fn integer_type_name(name: &str) -> Option<Type> {
+        let (bits, signed) = if let Some(bits) = name.strip_prefix("uint") {
+            (bits, false)
+        } else if let Some(bits) = name.strip_prefix("int") {
+            (bits, true)
+        } else {
+            return None;
+        };
+        let bits = if bits.is_empty() {
+            256
+        } else {
+            bits.parse::<u16>().ok()?
+        };
+        Some(if signed {
+            Type::Int(bits)
+        } else {
+            Type::Uint(bits)
+        })
+    }
+
+    fn numeric_type(ty: Option<Type>) -> Type {
+        match ty {
+            Some(ty @ (Type::Uint(_) | Type::Int(_))) => ty,
+            _ => Type::Uint(256),
+        }
+    }
+
+    fn arithmetic(
+        &self,
+        block: &mut BlockBuilder,
+        op: &str,
+        left: Value,
+        right: Value,
+        ty: Type,
+    ) -> Option<Value> {
+        let signed = ty.is_signed();
+        let checked = self.checked_arithmetic();
+        Some(match op {
+            "+" if checked => block.checked_add(left, right, ty),
+            "-" if checked => block.checked_sub(left, right, ty),
+            "*" if checked => block.checked_mul(left, right, ty),
+            "+" => block.add(left, right, ty),
+            "-" => block.sub(left, right, ty),
+            "*" => block.mul(left, right, ty),
+            "/" if signed => block.sdiv(left, right, ty),
+            "/" => block.div(left, right, ty),
+            "%" if signed => block.smod(left, right, ty),
+            "%" => block.mod_(left, right, ty),
+            _ => return None,
+        })
+    }
+
+    /* Resolves a chain of state variable, struct member and index accesses to the storage it
+     * names, evaluating keys and indices along the way. Struct fields are offsets from the slot
+     * the struct starts at, and a mapping value addressed inside goes through its derived slot,
+     * so `config.fees.treasury` and `allowance[a][b]` each reach their own word. */
+    fn storage_place(
+        &mut self,
+        node: Node,
+        source: &str,
+        block: &mut BlockBuilder,
+        param_map: &HashMap<String, u32>,
+        state_vars: &HashMap<String, (u32, Type)>,
+        local_vars: &mut LocalScopes,
+    ) -> Result<Option<(StoragePlace, Type)>> {
+        let node = if node.kind() == "expression" && node.child_count() > 0 {
+            node.child(0).unwrap()
+        } else {
+            node
+        };
+
+        match node.kind() {
+            "identifier" => {
+                let name = &source[node.byte_range()];
+                if local_vars.get(name).is_some() || param_map.contains_key(name) {
+                    return Ok(None);
+                }
+                Ok(state_vars
+                    .get(name)
+                    .map(|(slot, ty)| (self.state_place(name, *slot, ty), ty.clone())))
+            }
+            "member_access_expression" | "member_expression" => {
+                let object = node.child_by_field_name("object").or_else(|| node.child(0));
+                let member = node
+                    .child_by_field_name("property")
+                    .or_else(|| node.child_by_field_name("member"))
+                    .or_else(|| node.child(2));
+                let (Some(object), Some(member)) = (object, member) else {
+                    return Ok(None);
+                };
+                let Some((base, Type::Struct(id))) =
+                    self.storage_place(object, source, block, param_map, state_vars, local_vars)?
+                else {
+                    return Ok(None);
+                };
+                let Some(def) = self.types.structs.get(&id) else {
+                    return Ok(None);
+                };
+                let name = &source[member.byte_range()];
+                let Some(field) = StorageLayout::of_struct(def, &self.types)
+                    .slots
+                    .into_iter()
+                    .find(|field| field.name == name)
+                else {
+                    return Ok(None);
+                };
+
+                let shared = !field.packed_with.is_empty();
+                let place = match base {
+                    StoragePlace::Slot(start) => StoragePlace::Slot(start + field.slot),
+                    base => {
+                        let Some(start) = Self::place_slot(block, base) else {
+                            return Ok(None);
+                        };
+                        if field.slot.is_zero() {
+                            StoragePlace::Dynamic(start)
+                        } else {
+                            let offset = Value::Constant(Constant::Uint(field.slot, 256));
+                            StoragePlace::Dynamic(block.add(start, offset, Type::Uint(256)))
+                        }
+                    }
+                };
+                let place = if shared {
+                    Self::packed_place(place, field.offset, field.var_type.clone())
+                } else {
+                    place
+                };
+                Ok(Some((place, field.var_type)))
+            }
+            "index_access_expression" | "subscript_expression" | "array_access" => {
+                let base = node
+                    .child_by_field_name("base")
+                    .or_else(|| node.child_by_field_name("object"))
+                    .or_else(|| node.child(0));
+                let index = node.child_by_field_name("index").or_else(|| {
+                    let mut cursor = node.walk();
+                    for child in node.children(&mut cursor) {
+                        if !matches!(child.kind(), "[" | "]") && Some(child) != base {
+                            return Some(child);
+                        }
+                    }
+                    None
+                });
+                let (Some(base), Some(index)) = (base, index) else {
+                    return Ok(None);
+                };
+                let Some((place, ty)) =
+                    self.storage_place(base, source, block, param_map, state_vars, local_vars)?
+                else {
+                    return Ok(None);
+                };
+
+                match ty {
+                    Type::Mapping(_, value_ty) => {
+                        let Some(mapping) = Self::place_slot(block, place) else {
+                            return Ok(None);
+                        };
+                        let key = self.process_expression(
+                            index, source, block, param_map, state_vars, local_vars,
+                        )?;
+                        Ok(Some((
+                            StoragePlace::MappingEntry { mapping, key },
+                            *value_ty,
+                        )))
+                    }
+                    Type::Array(element_ty, _) => {
+                        let Some(array) = Self::place_slot(block, place) else {
+                            return Ok(None);
+                        };
+                        let index = self.process_expression(
+                            index, source, block, param_map, state_vars, local_vars,
+                        )?;
+                        Ok(Some((
+                            StoragePlace::ArrayElement { array, index },
+                            *element_ty,
+                        )))
+                    }
+                    _ => Ok(None),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /* The slot a place starts at, as a value later accesses can build on. Array elements are not
+     * modelled as slots, so nothing is addressed inside them. */
+    fn place_slot(block: &mut BlockBuilder, place: StoragePlace) -> Option<Value> {
+        match place {
+            StoragePlace::Slot(slot) => Some(Value::Constant(Constant::Uint(slot, 256))),
+            StoragePlace::Dynamic(slot) => Some(slot),
+            StoragePlace::MappingEntry { mapping, key } => Some(block.mapping_slot(mapping, key)),
+            StoragePlace::ArrayElement { .. } => None,
+            StoragePlace::Packed(word, ..) => Self::place_slot(block, *word),
+        }
+    }
+
+    /* Where state variable `name` lives, narrowed to its bytes when it shares the slot. */
+    fn state_place(&self, name: &str, slot: u32, ty: &Type) -> StoragePlace {
+        let word = StoragePlace::Slot(BigUint::from(slot));
+        match self.packed_state.get(name) {
+            Some(&offset) => Self::packed_place(word, offset, ty.clone()),
+            None => word,
+        }
+    }
+
+    fn packed_place(word: StoragePlace, offset: u8, ty: Type) -> StoragePlace {
+        match packed_size(&ty) {
+            Some(size) if size < 32 => StoragePlace::Packed(Box::new(word), offset, ty),
+            _ => word,
+        }
+    }
+
+    fn load_place(block: &mut BlockBuilder, place: StoragePlace) -> Value {
+        match place {
+            StoragePlace::Slot(slot) => block.storage_load(slot),
+            StoragePlace::Dynamic(slot) => block.storage_load_dynamic(slot),
+            StoragePlace::MappingEntry { mapping, key } => block.mapping_load(mapping, key),
+            StoragePlace::ArrayElement { array, index } => block.array_load(array, index),
+            StoragePlace::Packed(word, offset, ty) => {
+                let word = Self::load_place(block, *word);
+                Self::extract_packed(block, word, offset, &ty)
+            }
+        }
+    }
+
+    fn store_place(block: &mut BlockBuilder, place: StoragePlace, value: Value) {
+        match place {
+            StoragePlace::Slot(slot) => block.storage_store(slot, value),
+            StoragePlace::Dynamic(slot) => block.storage_store_dynamic(slot, value),
+            StoragePlace::MappingEntry { mapping, key } => block.mapping_store(mapping, key, value),
+            StoragePlace::ArrayElement { array, index } => block.array_store(array, index, value),
+            StoragePlace::Packed(word, offset, ty) => {
+                let current = Self::load_place(block, (*word).clone());
+                let updated = Self::insert_packed(block, current, value, offset, &ty);
+                Self::store_place(block, *word, updated);
+            }
+        }
+    }
+
+    /* The bytes of a packed value, moved down to the low end of the word. Signed values are
+     * shifted up to the top first so the arithmetic shift back extends their sign. */
+    fn extract_packed(block: &mut BlockBuilder, word: Value, offset: u8, ty: &Type) -> Value {
+        let size = packed_size(ty).unwrap_or(32);
+        let bits = |bytes: usize| Value::Constant(Constant::Uint(BigUint::from(bytes * 8), 256));
+        if let Type::Int(_) = ty {
+            let high = block.shl(word, bits(32 - size - offset as usize));
+            return block.sar(high, bits(32 - size));
+        }
+        let shifted = if offset == 0 {
+            word
+        } else {
+            block.shr(word, bits(offset as usize))
+        };
+        let mask = Value::Constant(Constant::Uint(low_mask(size), 256));
+        block.and(shifted, mask)
+    }
+
+    /* `word` with the bytes at `offset` replaced by `value` and everything else left as is. */
+    fn insert_packed(
+        block: &mut BlockBuilder,
+        word: Value,
+        value: Value,
+        offset: u8,
+        ty: &Type,
+    ) -> Value {
+        let size = packed_size(ty).unwrap_or(32);
+        let shift = offset as usize * 8;
+        let field = low_mask(size) << shift;
+        let keep = low_mask(32) ^ &field;
+        let cleared = block.and(word, Value::Constant(Constant::Uint(keep, 256)));
+        let value = block.and(value, Value::Constant(Constant::Uint(low_mask(size), 256)));
+        let value = if shift == 0 {
+            value
+        } else {
+            let shift = Value::Constant(Constant::Uint(BigUint::from(shift), 256));
+            block.shl(value, shift)
+        };
+        block.or(cleared, value)
+    }
+
+    /* `place op= value`: reads the place, applies the operator and writes the result back. */
+    fn update_place(
+        &self,
+        block: &mut BlockBuilder,
+        place: StoragePlace,
+        ty: Type,
+        operator: &str,
+        right_value: Value,
+    ) -> Value {
+        let current = Self::load_place(block, place.clone());
+        let new_value = self
+            .arithmetic(
+                block,
+                operator.trim_end_matches('='),
+                current,
+                right_value.clone(),
+                Self::numeric_type(Some(ty)),
+            )
+            .unwrap_or(right_value);
+        Self::store_place(block, place, new_value.clone());
+        new_value
+    }
+
+    /* Functions with parameters are named after their parameter types so overloads stay apart. */
+    fn function_ir_name(&self, base_name: &str, node: Node, source: &str) -> String {
+        let param_type_names = self.extract_parameter_type_names(node, source);
+        if param_type_names.is_empty() {
+            base_name.to_string()
+        } else {
+            Self::mangle_function_name_from_strings(base_name, &param_type_names)
+        }
+    }
+
+    /* Picks the overload solc would: matching arity, every argument implicitly convertible to its
+     * parameter, and an exact match preferred when several remain. Arguments whose type is not
+     * known match anything, and a call that stays ambiguous takes the first declaration. */
+    fn resolve_overload(&self, name: &str, arg_types: &[Option<Type>]) -> String {
+        let Some(candidates) = self.overloads.get(name) else {
+            return name.to_string();
+        };
+        let accepts = |params: &[Type], exact: bool| {
+            params.len() == arg_types.len()
+                && params.iter().zip(arg_types).all(|(param, arg)| match arg {
+                    Some(arg) if exact => arg == param,
+                    Some(arg) => Self::implicitly_converts(arg, param),
+                    None => true,
+                })
+        };
+        candidates
+            .iter()
+            .find(|(_, params)| accepts(params, true))
+            .or_else(|| candidates.iter().find(|(_, params)| accepts(params, false)))
+            .or_else(|| {
+                candidates
+                    .iter()
+                    .find(|(_, params)| params.len() == arg_types.len())
+            })
+            .map(|(ir_name, _)| ir_name.clone())
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    fn implicitly_converts(from: &Type, to: &Type) -> bool {
+        match (from, to) {
+            (Type::Uint(a), Type::Uint(b)) | (Type::Int(a), Type::Int(b)) => a <= b,
+            (Type::Uint(a), Type::Int(b)) => a < b,
+            (Type::Bytes(a), Type::Bytes(b)) => a <= b,
+            _ => from == to,
         }
     }
 
@@ -2016,7 +3274,7 @@ impl StructuralTransformer {
             for child in params_node.children(&mut cursor) {
                 if child.kind() == "parameter" {
                     let ty = if let Some(type_node) = child.child_by_field_name("type") {
-                        let ctx = SimpleContext::new(source);
+                        let ctx = SimpleContext::with_structs(source, &self.struct_ids);
                         TypeResolver::resolve_type(type_node, &ctx)?
                     } else {
                         Type::Uint(256)
@@ -2029,7 +3287,7 @@ impl StructuralTransformer {
             for child in node.children(&mut cursor) {
                 if child.kind() == "parameter" {
                     let ty = if let Some(type_node) = child.child_by_field_name("type") {
-                        let ctx = SimpleContext::new(source);
+                        let ctx = SimpleContext::with_structs(source, &self.struct_ids);
                         TypeResolver::resolve_type(type_node, &ctx)?
                     } else {
                         Type::Uint(256)
@@ -2116,7 +3374,7 @@ impl StructuralTransformer {
         block: &mut BlockBuilder,
         param_map: &HashMap<String, u32>,
         state_vars: &HashMap<String, (u32, Type)>,
-        local_vars: &HashMap<String, Value>,
+        local_vars: &LocalScopes,
     ) -> Result<Value> {
         let parts: Vec<&str> = expr_text.split("||").collect();
 
@@ -2180,7 +3438,7 @@ impl StructuralTransformer {
         block: &mut BlockBuilder,
         param_map: &HashMap<String, u32>,
         _state_vars: &HashMap<String, (u32, Type)>,
-        local_vars: &HashMap<String, Value>,
+        local_vars: &LocalScopes,
     ) -> Result<Value> {
         if let Some(eq_pos) = expr.find("==") {
             let left = expr[..eq_pos].trim();
@@ -2226,7 +3484,7 @@ impl StructuralTransformer {
         block: &mut BlockBuilder,
         param_map: &HashMap<String, u32>,
         _state_vars: &HashMap<String, (u32, Type)>,
-        local_vars: &HashMap<String, Value>,
+        local_vars: &LocalScopes,
     ) -> Result<Value> {
         if let Some(super_pos) = expr.find("super.") {
             let after_super = &expr[super_pos + 6..];
@@ -2260,21 +3518,32 @@ impl StructuralTransformer {
         Ok(block.constant_bool(false))
     }
 
-    fn compute_function_selector(signature: &str) -> u32 {
-        use tiny_keccak::{Hasher, Keccak};
-
-        let mut keccak = Keccak::v256();
-        let mut output = [0u8; 32];
-        keccak.update(signature.as_bytes());
-        keccak.finalize(&mut output);
-
-        u32::from_be_bytes([output[0], output[1], output[2], output[3]])
+    /* The selector an external call to `member` with `args` dispatches on. Callback triggers are
+     * looked up by name and arity, so safe transfers and flash loans are recognised even with
+     * `bytes` or literal arguments; otherwise the signature comes from the argument types, and a
+     * call with an argument of unknown type keeps selector 0. */
+    fn external_selector(
+        &self,
+        member: &str,
+        args: &[Node],
+        source: &str,
+        state_vars: &std::collections::HashMap<String, (u32, Type)>,
+    ) -> u32 {
+        if let Some(trigger) = CallbackModel::standard().trigger_named(member, args.len()) {
+            return selector_u32(trigger);
+        }
+        args.iter()
+            .map(|arg| self.expression_type(*arg, source, state_vars))
+            .collect::<Option<Vec<Type>>>()
+            .and_then(|types| abi_signature(member, &types))
+            .map(|signature| selector_u32(&signature))
+            .unwrap_or(0)
     }
 
     fn compute_interface_id(function_signatures: &[&str]) -> u32 {
         function_signatures
             .iter()
-            .map(|sig| Self::compute_function_selector(sig))
+            .map(|sig| selector_u32(sig))
             .fold(0u32, |acc, selector| acc ^ selector)
     }
 }
@@ -2285,9 +3554,42 @@ impl IRTransformer for StructuralTransformer {
     }
 
     fn transform(&mut self, builder: &mut IRBuilder, ast: &Node, source: &str) -> Result<()> {
-        if ast.kind() == "source_file" {
-            self.process_source_file(*ast, source, builder)?;
+        match ast.kind() {
+            "source_file" => self.process_source_file(*ast, source, builder)?,
+            "contract_declaration" | "interface_declaration" | "library_declaration" => {
+                self.process_contract(*ast, source, builder)?
+            }
+            _ => {}
         }
         Ok(())
     }
+
+    fn split(&self, root: &Node, source: &str) -> Option<Box<dyn IRTransformer + Send>> {
+        let mut transformer = Self::with_filename(self.filename.clone());
+        transformer.evm_version = self.evm_version;
+        transformer.deadline = self.deadline;
+        transformer.read_source_file(*root, source);
+        Some(Box::new(transformer))
+    }
+
+    fn take_diagnostics(&mut self) -> Diagnostics {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    fn set_evm_version(&mut self, evm_version: EvmVersion) {
+        self.evm_version = Some(evm_version);
+    }
+
+    fn set_deadline(&mut self, deadline: Deadline) {
+        self.deadline = Some(deadline);
+    }
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or("").trim()
+}
+
+/* The low `bytes` bytes of a word set. */
+fn low_mask(bytes: usize) -> BigUint {
+    (BigUint::from(1u32) << (bytes * 8)) - 1u32
 }
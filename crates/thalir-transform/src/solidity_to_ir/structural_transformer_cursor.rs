@@ -1,11 +1,13 @@
+use super::context::LocalScopes;
 use anyhow::{anyhow, Result};
+use num_traits::ToPrimitive;
 use std::collections::HashMap;
 use thalir_core::{
     block::BlockId,
     builder::{FunctionBuilderCursor, IRContext, IRRegistry},
     contract::Contract,
     function::{Function, Mutability, Visibility},
-    types::Type,
+    types::{Type, TypeRegistry},
     values::{SourceLocation, Value},
 };
 use tree_sitter::Node;
@@ -115,10 +117,21 @@ impl StructuralTransformerCursor {
             .ok_or_else(|| anyhow!("State variable missing type"))?;
         let var_type = self.resolve_type(type_node, source)?;
 
-        let slot = self.state_vars.len() as u32;
-        contract
-            .storage_layout
-            .add_variable(var_name.to_string(), var_type.clone(), slot);
+        /* Constants and immutables live in the code and take no slot. */
+        let mut cursor = node.walk();
+        if node
+            .children(&mut cursor)
+            .any(|child| matches!(&source[child.byte_range()], "constant" | "immutable"))
+        {
+            return Ok(());
+        }
+
+        let (slot, _offset) = contract.storage_layout.allocate(
+            var_name.to_string(),
+            var_type.clone(),
+            &TypeRegistry::new(),
+        );
+        let slot = slot.to_u32().unwrap_or(u32::MAX);
         self.state_vars
             .insert(var_name.to_string(), (slot, var_type));
 
@@ -217,7 +230,7 @@ impl StructuralTransformerCursor {
                 body_node,
                 source,
                 &param_map,
-                &mut HashMap::new(),
+                &mut LocalScopes::new(),
             )?;
 
             if let Some(final_block) = exit_block {
@@ -239,7 +252,22 @@ impl StructuralTransformerCursor {
         node: Node,
         source: &str,
         param_map: &HashMap<String, u32>,
-        local_vars: &mut HashMap<String, Value>,
+        local_vars: &mut LocalScopes,
+    ) -> Result<Option<BlockId>> {
+        local_vars.push_scope();
+        let result =
+            self.process_scoped_statements(func_builder, node, source, param_map, local_vars);
+        local_vars.pop_scope();
+        result
+    }
+
+    fn process_scoped_statements(
+        &mut self,
+        func_builder: &mut FunctionBuilderCursor,
+        node: Node,
+        source: &str,
+        param_map: &HashMap<String, u32>,
+        local_vars: &mut LocalScopes,
     ) -> Result<Option<BlockId>> {
         let mut current_block = func_builder.current_block();
 
@@ -459,7 +487,7 @@ impl StructuralTransformerCursor {
         node: Node,
         source: &str,
         param_map: &HashMap<String, u32>,
-        local_vars: &mut HashMap<String, Value>,
+        local_vars: &mut LocalScopes,
     ) -> Result<BlockId> {
         let then_block = func_builder.create_block();
         let merge_block = func_builder.create_block();
@@ -563,7 +591,7 @@ impl StructuralTransformerCursor {
         node: Node,
         source: &str,
         param_map: &HashMap<String, u32>,
-        local_vars: &mut HashMap<String, Value>,
+        local_vars: &mut LocalScopes,
     ) -> Result<BlockId> {
         let header_block = func_builder.create_block();
         let body_block = func_builder.create_block();
@@ -618,7 +646,22 @@ impl StructuralTransformerCursor {
         node: Node,
         source: &str,
         param_map: &HashMap<String, u32>,
-        local_vars: &mut HashMap<String, Value>,
+        local_vars: &mut LocalScopes,
+    ) -> Result<BlockId> {
+        local_vars.push_scope();
+        let result =
+            self.process_for_loop_in_scope(func_builder, node, source, param_map, local_vars);
+        local_vars.pop_scope();
+        result
+    }
+
+    fn process_for_loop_in_scope(
+        &mut self,
+        func_builder: &mut FunctionBuilderCursor,
+        node: Node,
+        source: &str,
+        param_map: &HashMap<String, u32>,
+        local_vars: &mut LocalScopes,
     ) -> Result<BlockId> {
         if let Some(init_node) = node.child_by_field_name("initializer") {
             if init_node.kind() == "variable_declaration_statement" {
@@ -698,7 +741,7 @@ impl StructuralTransformerCursor {
         node: Node,
         source: &str,
         param_map: &HashMap<String, u32>,
-        local_vars: &mut HashMap<String, Value>,
+        local_vars: &mut LocalScopes,
     ) -> Result<()> {
         if let Some(decl_node) = node
             .child_by_field_name("declaration")
@@ -732,7 +775,7 @@ impl StructuralTransformerCursor {
 
             if let (Some(name_node), Some(value)) = (name_node, init_value) {
                 let name = &source[name_node.byte_range()];
-                local_vars.insert(name.to_string(), value);
+                local_vars.declare(name.to_string(), value);
             }
         }
 
@@ -745,7 +788,7 @@ impl StructuralTransformerCursor {
         node: Node,
         source: &str,
         param_map: &HashMap<String, u32>,
-        local_vars: &mut HashMap<String, Value>,
+        local_vars: &mut LocalScopes,
     ) -> Result<Value> {
         func_builder.set_source_location(self.source_location_from_node(node));
 
@@ -860,7 +903,9 @@ impl StructuralTransformerCursor {
                 if left_node.kind() == "identifier" {
                     let name = &source[left_node.byte_range()];
 
-                    local_vars.insert(name.to_string(), value.clone());
+                    if !local_vars.assign(name, value.clone()) {
+                        local_vars.declare(name.to_string(), value.clone());
+                    }
 
                     if let Some(&(slot, _)) = self.state_vars.get(name) {
                         let mut inst = func_builder.ins()?;
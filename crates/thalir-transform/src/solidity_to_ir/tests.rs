@@ -1,5 +1,6 @@
 use super::transform_solidity_to_ir;
 use super::*;
+use thalir_core::EntryKind;
 
 #[test]
 fn test_empty_contract_transformation() {
@@ -251,6 +252,117 @@ fn test_visibility_modifiers() {
         && f.visibility == thalir_core::function::Visibility::Private));
 }
 
+#[test]
+fn test_entry_points_include_fallback_and_receive() {
+    let source = r#"
+        contract Wallet {
+            address owner;
+
+            constructor() {
+                owner = msg.sender;
+            }
+
+            function transfer(address to, uint256 amount) external {
+                require(msg.sender == owner, "not owner");
+            }
+
+            function audit() internal {}
+
+            receive() external payable {}
+
+            fallback() external {}
+        }
+    "#;
+    let contracts = transform_solidity_to_ir(source).unwrap();
+    let entries = contracts[0].entry_points();
+
+    let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names.len(), 3);
+    assert!(!names.contains(&"constructor") && !names.contains(&"audit"));
+
+    let transfer = entries
+        .iter()
+        .find(|e| e.kind == EntryKind::Function)
+        .unwrap();
+    assert_eq!(transfer.selector, Some(0xa905_9cbb));
+    assert!(transfer.guards.is_guarded());
+
+    let receive = entries
+        .iter()
+        .find(|e| e.kind == EntryKind::Receive)
+        .unwrap();
+    assert!(receive.payable);
+    assert!(entries
+        .iter()
+        .any(|e| e.kind == EntryKind::Fallback && !e.payable));
+}
+
+#[test]
+fn test_modifier_invocations_are_recorded() {
+    let source = r#"
+        contract Vault is Ownable {
+            modifier nonReentrant() { _; }
+
+            constructor(address admin) Ownable(admin) {}
+
+            function withdraw(uint256 amount) external onlyOwner nonReentrant {}
+        }
+    "#;
+    let contracts = transform_solidity_to_ir(source).unwrap();
+    let vault = &contracts[0];
+    let guards = |name: &str| {
+        let function = vault
+            .functions
+            .values()
+            .find(|f| f.signature.name == name)
+            .unwrap();
+        vault.guards(function).modifiers
+    };
+    assert_eq!(guards("withdraw"), vec!["onlyOwner", "nonReentrant"]);
+    assert!(guards("constructor").is_empty());
+}
+
+#[test]
+fn test_address_literals_and_constants_are_kept() {
+    use thalir_core::analysis::AddressBook;
+    use thalir_core::values::Constant;
+
+    let source = r#"
+        contract Swapper {
+            address constant ROUTER = address(0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D);
+            uint256 constant FEE = 0x1f4;
+
+            function router() external pure returns (address) {
+                return ROUTER;
+            }
+
+            function feed() external pure returns (address) {
+                return 0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419;
+            }
+        }
+    "#;
+    let contracts = transform_solidity_to_ir(source).unwrap();
+    let constants: Vec<(&str, &Constant)> = contracts[0]
+        .constants
+        .iter()
+        .map(|c| (c.name.as_str(), &c.value))
+        .collect();
+    assert_eq!(constants.len(), 2);
+    assert!(matches!(constants[0], ("ROUTER", Constant::Address(_))));
+    assert_eq!(constants[1], ("FEE", &Constant::Uint(500u32.into(), 256)));
+
+    let book = AddressBook::collect(&contracts);
+    let router = book
+        .get("0x7a250d5630b4cf539739df2c5dacb4c659f2488d")
+        .unwrap();
+    assert_eq!(router.constants, ["Swapper.ROUTER"]);
+    assert_eq!(router.uses[0].function, "router");
+    let feed = book
+        .get("0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419")
+        .unwrap();
+    assert_eq!(feed.uses[0].function, "feed");
+}
+
 #[test]
 fn test_mutability_modifiers() {
     let source = r#"
@@ -277,3 +389,1024 @@ fn test_mutability_modifiers() {
     assert!(funcs.values().any(|f| f.signature.name == "normalFunc"
         && f.mutability == thalir_core::function::Mutability::NonPayable));
 }
+
+#[test]
+fn test_natspec_is_captured() {
+    let source = r#"
+        // SPDX-License-Identifier: MIT
+        /// @title Vault für Einlagen
+        /// @notice Holds deposits
+        contract Vault {
+            uint256 total;
+
+            /**
+             * @notice Deposit funds into the vault
+             * @dev Updates the running total
+             * @param amount The amount to add
+             */
+            function deposit(uint256 amount) public {
+                total = total + amount;
+            }
+
+            function plain() public {}
+        }
+    "#;
+    let contracts = transform_solidity_to_ir(source).unwrap();
+    let contract = &contracts[0];
+
+    let contract_doc = contract.metadata.natspec.as_ref().unwrap();
+    assert_eq!(contract_doc.title.as_deref(), Some("Vault für Einlagen"));
+    assert_eq!(contract_doc.notice.as_deref(), Some("Holds deposits"));
+
+    let deposit = contract
+        .functions
+        .values()
+        .find(|f| f.signature.name.starts_with("deposit"))
+        .unwrap();
+    let doc = deposit.metadata.natspec.as_ref().unwrap();
+    assert_eq!(doc.notice.as_deref(), Some("Deposit funds into the vault"));
+    assert_eq!(doc.dev.as_deref(), Some("Updates the running total"));
+    assert_eq!(
+        doc.params,
+        vec![("amount".to_string(), "The amount to add".to_string())]
+    );
+
+    let plain = contract.functions.get("plain").unwrap();
+    assert!(plain.metadata.natspec.is_none());
+}
+
+#[test]
+fn test_lenient_mode_skips_broken_function() {
+    let source = r#"
+        contract Partial {
+            uint256 value;
+
+            function broken() public {
+                value = (1 + ;
+            }
+
+            function fine() public {
+                value = 2;
+            }
+        }
+    "#;
+
+    assert!(transform_solidity_to_ir(source).is_err());
+
+    let output = transform_solidity_to_ir_lenient(source, Some("Partial.sol")).unwrap();
+    assert_eq!(output.contracts.len(), 1);
+    let contract = &output.contracts[0];
+    assert!(contract.functions.contains_key("fine"));
+    assert!(!contract.functions.contains_key("broken"));
+
+    assert!(output.diagnostics.has_errors());
+    let skipped: Vec<&Diagnostic> = output.diagnostics.iter().collect();
+    assert_eq!(skipped[0].kind, DiagnosticKind::SkippedSyntaxError);
+    assert_eq!(skipped[0].location.file, "Partial.sol");
+    assert_eq!(skipped[0].location.line, 5);
+}
+
+#[test]
+fn test_fallbacks_are_reported_as_diagnostics() {
+    let clean = r#"
+        contract Clean {
+            uint256 value;
+
+            function set() public {
+                value = 2;
+            }
+        }
+    "#;
+    let output = transform_solidity_to_ir_with_diagnostics(clean, Some("Clean.sol")).unwrap();
+    assert!(output.diagnostics.is_empty());
+
+    let lossy = r#"
+        contract Lossy {
+            uint256 value;
+
+            function square(uint256 x) public {
+                value = x ** 2;
+            }
+
+            function lookup() public {
+                value = missing;
+            }
+        }
+    "#;
+    let output = transform_solidity_to_ir_with_diagnostics(lossy, Some("Lossy.sol")).unwrap();
+    assert_eq!(output.contracts.len(), 1);
+    assert!(output.diagnostics.has_errors());
+    assert_eq!(
+        output.diagnostics.max_severity(),
+        Some(DiagnosticSeverity::Error)
+    );
+
+    let operator = output
+        .diagnostics
+        .iter()
+        .find(|d| d.kind == DiagnosticKind::UnsupportedNode)
+        .unwrap();
+    assert!(operator.message.contains("**"));
+    assert_eq!(operator.location.file, "Lossy.sol");
+    assert_eq!(operator.location.line, 6);
+
+    let unresolved = output
+        .diagnostics
+        .iter()
+        .find(|d| d.kind == DiagnosticKind::UnresolvedIdentifier)
+        .unwrap();
+    assert_eq!(unresolved.severity, DiagnosticSeverity::Warning);
+    assert!(unresolved.message.contains("missing"));
+}
+
+#[test]
+fn test_coverage_report_classifies_nodes() {
+    let source = r#"
+        contract Lossy {
+            uint256 value;
+
+            function square(uint256 x) public {
+                value = x ** 2;
+            }
+
+            function lookup() public {
+                value = missing;
+            }
+
+            function set() public {
+                value = 3;
+            }
+        }
+    "#;
+    let report = CoverageReport::analyze(source, "Lossy.sol").unwrap();
+
+    assert_eq!(report.kind("binary_expression").unwrap().ignored, 1);
+    let assignments = report.kind("assignment_expression").unwrap();
+    assert_eq!(assignments.total(), 3);
+    assert_eq!(assignments.partial, 1);
+    assert_eq!(assignments.full, 2);
+
+    let total = report.count(LoweringStatus::Full)
+        + report.count(LoweringStatus::Partial)
+        + report.count(LoweringStatus::Ignored);
+    assert_eq!(total, report.total());
+    assert!(report.percent(LoweringStatus::Full) > 50.0);
+    assert!(!report.diagnostics.is_empty());
+}
+
+#[test]
+fn test_signed_operands_select_signed_instructions() {
+    use thalir_core::instructions::Instruction;
+
+    let source = r#"
+        contract Signed {
+            int256 balance;
+            uint256 supply;
+
+            function scale(int256 factor, int256 divisor) public {
+                balance = factor / divisor;
+                require(factor < divisor);
+            }
+
+            function split(uint256 amount, uint256 parts) public {
+                supply = amount / parts;
+                require(amount < parts);
+            }
+
+            function shrink(int256 delta) public {
+                balance %= delta;
+            }
+        }
+    "#;
+    let contracts = transform_solidity_to_ir(source).unwrap();
+    let contract = &contracts[0];
+
+    let instructions = |prefix: &str| -> Vec<Instruction> {
+        contract
+            .functions
+            .values()
+            .find(|f| f.signature.name.starts_with(prefix))
+            .unwrap()
+            .body
+            .blocks
+            .values()
+            .flat_map(|b| b.instructions.clone())
+            .collect()
+    };
+
+    let scale = instructions("scale");
+    assert!(scale
+        .iter()
+        .any(|i| matches!(i, Instruction::Sdiv { ty, .. } if ty.is_signed())));
+    assert!(scale.iter().any(|i| matches!(i, Instruction::Slt { .. })));
+    assert!(!scale.iter().any(|i| matches!(i, Instruction::Div { .. })));
+
+    let split = instructions("split");
+    assert!(split.iter().any(|i| matches!(i, Instruction::Div { .. })));
+    assert!(split.iter().any(|i| matches!(i, Instruction::Lt { .. })));
+    assert!(!split.iter().any(|i| matches!(i, Instruction::Slt { .. })));
+
+    let shrink = instructions("shrink");
+    assert!(shrink.iter().any(|i| matches!(i, Instruction::Smod { .. })));
+}
+
+#[test]
+fn test_block_scoped_locals_shadow_outer_bindings() {
+    use thalir_core::instructions::Instruction;
+    use thalir_core::values::{ParamId, Value};
+
+    let source = r#"
+        contract Scoped {
+            uint256 total;
+
+            function run(uint256 a, uint256 b) public {
+                uint256 x = a;
+                if (a > b) {
+                    uint256 x = b;
+                    total = x;
+                }
+                total = x;
+            }
+        }
+    "#;
+    let contracts = transform_solidity_to_ir(source).unwrap();
+    let function = contracts[0].functions.values().next().unwrap();
+
+    let stored: Vec<Value> = function
+        .body
+        .blocks
+        .values()
+        .flat_map(|b| b.instructions.iter())
+        .filter_map(|i| match i {
+            Instruction::StorageStore { value, .. } => Some(value.clone()),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(
+        stored,
+        vec![Value::Param(ParamId(1)), Value::Param(ParamId(0))]
+    );
+}
+
+#[test]
+fn test_pragma_selects_arithmetic_and_randomness_semantics() {
+    use thalir_core::instructions::{ContextVariable, Instruction};
+    use thalir_core::{EvmVersion, SolcVersion};
+
+    let body = r#"
+        contract Versioned {
+            uint256 total;
+
+            function bump(uint256 amount) public {
+                total = total + amount;
+                unchecked {
+                    total = total * amount;
+                }
+                total = block.difficulty;
+            }
+        }
+    "#;
+    let instructions = |source: &str, evm_version: Option<EvmVersion>| {
+        let output = TransformationPipeline::default(source)
+            .evm_version(evm_version)
+            .transform_with_diagnostics()
+            .unwrap();
+        let contract = output.contracts[0].clone();
+        let instructions: Vec<Instruction> = contract
+            .functions
+            .values()
+            .find(|f| f.signature.name.starts_with("bump"))
+            .unwrap()
+            .body
+            .blocks
+            .values()
+            .flat_map(|b| b.instructions.clone())
+            .collect();
+        (contract.metadata, instructions)
+    };
+    let randomness = |instructions: &[Instruction]| {
+        instructions.iter().find_map(|i| match i {
+            Instruction::GetContext { var, .. } => Some(*var),
+            _ => None,
+        })
+    };
+
+    let (metadata, modern) = instructions(&format!("pragma solidity ^0.8.19;\n{}", body), None);
+    assert_eq!(
+        metadata.pragma.as_ref().and_then(|p| p.minimum),
+        Some(SolcVersion::new(0, 8, 19))
+    );
+    assert_eq!(metadata.evm_version, Some(EvmVersion::Paris));
+    assert!(modern
+        .iter()
+        .any(|i| matches!(i, Instruction::CheckedAdd { .. })));
+    assert!(modern.iter().any(|i| matches!(i, Instruction::Mul { .. })));
+    assert!(!modern.iter().any(|i| matches!(i, Instruction::Add { .. })));
+    assert_eq!(randomness(&modern), Some(ContextVariable::PrevRandao));
+
+    let (metadata, legacy) = instructions(
+        &format!("pragma solidity >=0.6.0 <0.9.0;\n{}", body),
+        Some(EvmVersion::London),
+    );
+    assert!(!metadata.checked_arithmetic());
+    assert!(legacy.iter().any(|i| matches!(i, Instruction::Add { .. })));
+    assert!(!legacy
+        .iter()
+        .any(|i| matches!(i, Instruction::CheckedAdd { .. })));
+    assert_eq!(randomness(&legacy), Some(ContextVariable::BlockDifficulty));
+}
+
+#[test]
+fn test_safe_math_calls_fold_to_checked_arithmetic() {
+    use thalir_core::instructions::Instruction;
+
+    let source = r#"
+        pragma solidity ^0.6.12;
+
+        contract Legacy {
+            using SafeMath for uint256;
+
+            uint256 total;
+
+            function deposit(uint256 amount, uint256 fee) public {
+                total = total.add(amount).sub(fee, "fee too high");
+                total = SafeMath.mul(total, 2).div(fee);
+            }
+        }
+    "#;
+    let contracts = transform_solidity_to_ir(source).unwrap();
+    let instructions: Vec<Instruction> = contracts[0]
+        .functions
+        .values()
+        .find(|f| f.signature.name.starts_with("deposit"))
+        .unwrap()
+        .body
+        .blocks
+        .values()
+        .flat_map(|b| b.instructions.clone())
+        .collect();
+
+    let count = |pred: fn(&Instruction) -> bool| instructions.iter().filter(|i| pred(i)).count();
+    assert_eq!(count(|i| matches!(i, Instruction::CheckedAdd { .. })), 1);
+    assert_eq!(count(|i| matches!(i, Instruction::CheckedSub { .. })), 1);
+    assert_eq!(count(|i| matches!(i, Instruction::CheckedMul { .. })), 1);
+    assert_eq!(count(|i| matches!(i, Instruction::CheckedDiv { .. })), 1);
+    assert_eq!(count(|i| matches!(i, Instruction::Call { .. })), 0);
+}
+
+#[test]
+fn test_safe_math_directive_on_a_base_covers_derived_contracts_before_0_7() {
+    use thalir_core::instructions::Instruction;
+
+    let checked_adds = |pragma: &str| {
+        let source = format!(
+            r#"
+            pragma solidity {};
+
+            contract Base {{
+                using SafeMath for uint256;
+            }}
+
+            contract Token is Base {{
+                uint256 cap;
+
+                function raise(uint256 amount) public {{
+                    cap = cap.add(amount);
+                }}
+            }}
+        "#,
+            pragma
+        );
+        let contracts = transform_solidity_to_ir(&source).unwrap();
+        contracts
+            .iter()
+            .find(|c| c.name == "Token")
+            .unwrap()
+            .functions
+            .values()
+            .find(|f| f.signature.name.starts_with("raise"))
+            .unwrap()
+            .body
+            .blocks
+            .values()
+            .flat_map(|b| b.instructions.iter())
+            .filter(|i| matches!(i, Instruction::CheckedAdd { .. }))
+            .count()
+    };
+
+    assert_eq!(checked_adds("^0.6.12"), 1);
+    assert_eq!(checked_adds("^0.7.6"), 0);
+}
+
+#[test]
+fn test_safe_math_folding_needs_the_library_and_a_covered_receiver() {
+    use thalir_core::instructions::Instruction;
+
+    let source = r#"
+        pragma solidity ^0.6.12;
+
+        contract Legacy {
+            using SafeMath for uint256;
+            using MySafeMathLib for uint128;
+
+            uint256 total;
+            uint128 small;
+
+            function chained(uint256 a, uint256 b, uint256 c) public {
+                total = SafeMath.mul(a, b).div(c);
+            }
+
+            function lookalike(uint128 x) public {
+                small = small.add(x);
+                total = MySafeMathLib.add(total, 1);
+            }
+        }
+    "#;
+    let contracts = transform_solidity_to_ir(source).unwrap();
+    let instructions = |name: &str| -> Vec<Instruction> {
+        contracts[0]
+            .functions
+            .values()
+            .find(|f| f.signature.name.starts_with(name))
+            .unwrap()
+            .body
+            .blocks
+            .values()
+            .flat_map(|b| b.instructions.clone())
+            .collect()
+    };
+
+    let chained = instructions("chained");
+    assert!(chained
+        .iter()
+        .any(|i| matches!(i, Instruction::CheckedMul { .. })));
+    assert!(chained
+        .iter()
+        .any(|i| matches!(i, Instruction::CheckedDiv { .. })));
+    assert!(!chained
+        .iter()
+        .any(|i| matches!(i, Instruction::Call { .. })));
+
+    let lookalike = instructions("lookalike");
+    assert!(!lookalike.iter().any(|i| matches!(
+        i,
+        Instruction::CheckedAdd { .. } | Instruction::CheckedMul { .. }
+    )));
+}
+
+#[test]
+fn test_recent_builtins_lower_to_context_reads() {
+    use thalir_core::instructions::{BuiltinFunction, CallTarget, ContextVariable, Instruction};
+
+    let source = r#"
+        pragma solidity ^0.8.24;
+
+        contract Modern {
+            uint256 seen;
+
+            function observe(address who) public {
+                seen = address(this).balance + address(who).balance + gasleft();
+                bytes32 versioned = blobhash(0);
+                seen = block.blobbasefee;
+            }
+        }
+    "#;
+    let contracts = transform_solidity_to_ir(source).unwrap();
+    let instructions: Vec<Instruction> = contracts[0]
+        .functions
+        .values()
+        .find(|f| f.signature.name.starts_with("observe"))
+        .unwrap()
+        .body
+        .blocks
+        .values()
+        .flat_map(|b| b.instructions.clone())
+        .collect();
+
+    let reads = |wanted: ContextVariable| {
+        instructions
+            .iter()
+            .any(|i| matches!(i, Instruction::GetContext { var, .. } if *var == wanted))
+    };
+    assert!(reads(ContextVariable::ThisBalance));
+    assert!(reads(ContextVariable::GasLeft));
+    assert!(reads(ContextVariable::BlobBaseFee));
+    assert!(instructions
+        .iter()
+        .any(|i| matches!(i, Instruction::GetBalance { .. })));
+    assert!(instructions.iter().any(|i| matches!(
+        i,
+        Instruction::Call {
+            target: CallTarget::Builtin(BuiltinFunction::BlobHash),
+            ..
+        }
+    )));
+    assert!(!instructions.iter().any(|i| matches!(
+        i,
+        Instruction::Call {
+            target: CallTarget::Internal(_),
+            ..
+        }
+    )));
+}
+
+#[test]
+fn test_overloaded_calls_resolve_by_argument_type() {
+    use thalir_core::instructions::{CallTarget, Instruction};
+
+    let source = r#"
+        contract Ledger {
+            uint256 total;
+
+            function record(uint256 amount) internal { total += amount; }
+            function record(address who) internal { total += 1; }
+            function record(uint8 small, bool flag) internal { total += small; }
+
+            function run(address who, uint8 tiny, uint256 amount) public {
+                record(who);
+                record(tiny);
+                record(amount);
+                record(tiny, true);
+            }
+        }
+    "#;
+    let contracts = transform_solidity_to_ir(source).unwrap();
+    let run = contracts[0]
+        .functions
+        .values()
+        .find(|f| f.signature.name.starts_with("run"))
+        .unwrap();
+    let targets: Vec<&str> = run
+        .body
+        .blocks
+        .values()
+        .flat_map(|b| b.instructions.iter())
+        .filter_map(|i| match i {
+            Instruction::Call {
+                target: CallTarget::Internal(name),
+                ..
+            } => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(
+        targets,
+        vec![
+            "record_address",
+            "record_uint256",
+            "record_uint256",
+            "record_uint8_bool"
+        ]
+    );
+    assert!(targets
+        .iter()
+        .all(|name| contracts[0].functions.contains_key(*name)));
+}
+
+#[test]
+fn test_address_conversions_lower_to_width_changes() {
+    use thalir_core::instructions::{ContextVariable, Instruction};
+    use thalir_core::types::Type;
+
+    let source = r#"
+        interface IVault {
+            function deposit() external;
+        }
+
+        contract Router {
+            address last;
+
+            function route(uint256 raw, int8 delta, address who) public {
+                last = address(uint160(raw));
+                last = address(this);
+                last = address(IVault(who));
+                last = payable(who);
+                int256 wide = int256(delta);
+            }
+        }
+    "#;
+    let contracts = transform_solidity_to_ir(source).unwrap();
+    let router = contracts.iter().find(|c| c.name == "Router").unwrap();
+    let instructions: Vec<Instruction> = router
+        .functions
+        .values()
+        .find(|f| f.signature.name.starts_with("route"))
+        .unwrap()
+        .body
+        .blocks
+        .values()
+        .flat_map(|b| b.instructions.clone())
+        .collect();
+
+    assert!(instructions.iter().any(|i| matches!(
+        i,
+        Instruction::Truncate {
+            to: Type::Uint(160),
+            ..
+        }
+    )));
+    assert!(instructions.iter().any(|i| matches!(
+        i,
+        Instruction::Cast {
+            to: Type::Address,
+            ..
+        }
+    )));
+    assert!(instructions.iter().any(|i| matches!(
+        i,
+        Instruction::SignExtend {
+            to: Type::Int(256),
+            ..
+        }
+    )));
+    assert!(instructions.iter().any(|i| matches!(
+        i,
+        Instruction::GetContext {
+            var: ContextVariable::ThisAddress,
+            ..
+        }
+    )));
+    assert!(!instructions
+        .iter()
+        .any(|i| matches!(i, Instruction::Call { .. })));
+}
+
+#[test]
+fn test_member_chains_derive_nested_storage_slots() {
+    use num_bigint::BigUint;
+    use thalir_core::instructions::{BuiltinFunction, CallTarget, Instruction, StorageKey};
+
+    let source = r#"
+        struct Fees {
+            uint256 rate;
+            address treasury;
+        }
+
+        contract Vault {
+            struct Config {
+                address owner;
+                Fees fees;
+            }
+
+            uint256 total;
+            Config config;
+            mapping(address => mapping(address => uint256)) allowance;
+            mapping(uint256 => Config) configs;
+
+            function run(address a, address b, uint256 id) public returns (address) {
+                allowance[a][b] += 1;
+                configs[id].fees.rate = 7;
+                return config.fees.treasury;
+            }
+        }
+    "#;
+    let contracts = transform_solidity_to_ir(source).unwrap();
+    let instructions: Vec<Instruction> = contracts[0]
+        .functions
+        .values()
+        .find(|f| f.signature.name.starts_with("run"))
+        .unwrap()
+        .body
+        .blocks
+        .values()
+        .flat_map(|b| b.instructions.clone())
+        .collect();
+
+    /* `config` starts at slot 1 and `treasury` is the second word of its second field. */
+    assert!(instructions.iter().any(|i| matches!(
+        i,
+        Instruction::StorageLoad {
+            key: StorageKey::Slot(slot),
+            ..
+        } if *slot == BigUint::from(3u32)
+    )));
+    let mapping_slots = instructions
+        .iter()
+        .filter(|i| {
+            matches!(
+                i,
+                Instruction::Call {
+                    target: CallTarget::Builtin(BuiltinFunction::MappingSlot),
+                    ..
+                }
+            )
+        })
+        .count();
+    assert_eq!(mapping_slots, 2);
+    assert!(instructions
+        .iter()
+        .any(|i| matches!(i, Instruction::MappingStore { .. })));
+    assert!(instructions.iter().any(|i| matches!(
+        i,
+        Instruction::StorageStore {
+            key: StorageKey::Dynamic(_),
+            ..
+        }
+    )));
+}
+
+#[test]
+fn test_parallel_transform_matches_sequential() {
+    use num_bigint::BigUint;
+
+    let source = r#"
+        pragma solidity ^0.7.6;
+
+        struct Position {
+            address owner;
+            uint256 size;
+        }
+
+        interface IOracle {
+            function price() external view returns (uint256);
+        }
+
+        library Math {
+            function min(uint256 a, uint256 b) internal pure returns (uint256) {
+                if (a < b) {
+                    return a;
+                }
+                return b;
+            }
+        }
+
+        contract Book {
+            Position head;
+            uint256 total;
+
+            function open(uint256 size) public {
+                head.size = size;
+                total = total + size;
+            }
+
+            function lookup() public {
+                total = missing;
+            }
+        }
+    "#;
+    let run = |source: &str, parallel: bool| {
+        TransformationPipeline::with_filename(source, "Book.sol".to_string())
+            .parallel(parallel)
+            .transform_with_diagnostics()
+            .unwrap()
+    };
+    let sequential = run(source, false);
+    let parallel = run(source, true);
+
+    let shape = |output: &TransformOutput| {
+        output
+            .contracts
+            .iter()
+            .map(|contract| {
+                let functions: Vec<(String, usize)> = contract
+                    .functions
+                    .values()
+                    .map(|f| {
+                        let instructions = f.body.blocks.values().map(|b| b.instructions.len());
+                        (f.signature.name.clone(), instructions.sum())
+                    })
+                    .collect();
+                let slots: Vec<String> = contract
+                    .storage_layout
+                    .slots
+                    .iter()
+                    .map(|v| format!("{}@{}", v.name, v.slot))
+                    .collect();
+                (contract.name.clone(), functions, slots)
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(shape(&parallel), shape(&sequential));
+    assert_eq!(
+        parallel
+            .contracts
+            .iter()
+            .map(|c| &c.name)
+            .collect::<Vec<_>>(),
+        vec!["IOracle", "Math", "Book"]
+    );
+    assert!(parallel
+        .contracts
+        .iter()
+        .all(|c| c.metadata.pragma.is_some()
+            && c.metadata.pragma == sequential.contracts[0].metadata.pragma));
+
+    let messages = |output: &TransformOutput| {
+        output
+            .diagnostics
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(messages(&parallel), messages(&sequential));
+    assert!(parallel
+        .diagnostics
+        .iter()
+        .any(|d| d.kind == DiagnosticKind::UnresolvedIdentifier));
+
+    let inherited = r#"
+        contract Base {
+            uint256 supply;
+            address owner;
+        }
+
+        contract Token is Base {
+            uint256 cap;
+
+            function raise(uint256 amount) public {
+                cap = cap + amount;
+            }
+        }
+    "#;
+    let sequential = run(inherited, false);
+    let parallel = run(inherited, true);
+    assert_eq!(shape(&parallel), shape(&sequential));
+    let token = parallel
+        .contracts
+        .iter()
+        .find(|c| c.name == "Token")
+        .unwrap();
+    assert_eq!(token.storage_layout.inherited.len(), 2);
+    assert_eq!(token.storage_layout.slots[0].slot, BigUint::from(2u32));
+}
+
+#[test]
+fn test_input_limits_fail_with_structured_errors() {
+    let nested = format!(
+        "contract Deep {{ function f() public pure returns (uint256) {{ return {}1{}; }} }}",
+        "(".repeat(40),
+        ")".repeat(40)
+    );
+    let transform = |source: &str, limits: InputLimits| {
+        TransformationPipeline::default(source)
+            .limits(limits)
+            .transform()
+            .unwrap_err()
+            .downcast::<TransformError>()
+            .unwrap()
+    };
+
+    let small = InputLimits {
+        max_source_bytes: Some(64),
+        ..InputLimits::unlimited()
+    };
+    assert!(matches!(
+        transform(&nested, small),
+        TransformError::SourceTooLarge { limit: 64, .. }
+    ));
+
+    let shallow = InputLimits {
+        max_depth: Some(30),
+        ..InputLimits::unlimited()
+    };
+    assert!(matches!(
+        transform(&nested, shallow),
+        TransformError::TooDeep { limit: 30, line: 1 }
+    ));
+
+    let expired = InputLimits {
+        timeout: Some(std::time::Duration::ZERO),
+        ..InputLimits::unlimited()
+    };
+    assert!(matches!(
+        transform(&nested, expired),
+        TransformError::Timeout { .. }
+    ));
+}
+
+/* The depth check walks the tree iteratively, but the transformer after it recurses. Input just
+ * under the default limit has to transform on a stack the size of the main thread's, the one the
+ * sequential path runs on and workers are given, without overflowing it. */
+#[test]
+fn test_input_just_under_the_default_depth_limit_transforms() {
+    let expression = |terms: usize| {
+        format!(
+            "contract Deep {{ function f(uint256 x) public pure returns (uint256) {{ return x{}; }} }}",
+            " + x".repeat(terms)
+        )
+    };
+    let blocks = |levels: usize| {
+        format!(
+            "contract Deep {{ uint256 total; function f() public {{ {}total = 1;{} }} }}",
+            "{ ".repeat(levels),
+            " }".repeat(levels)
+        )
+    };
+    let within_default = |source: &str| {
+        let mut parser = tree_sitter::Parser::new();
+        parser
+            .set_language(&tree_sitter_solidity::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        InputLimits::default().check_depth(tree.root_node()).is_ok()
+    };
+
+    for shape in [&expression as &dyn Fn(usize) -> String, &blocks] {
+        let deepest = (1..)
+            .take_while(|&n| within_default(&shape(n)))
+            .last()
+            .unwrap();
+        assert!(matches!(
+            TransformationPipeline::default(&shape(deepest + 1))
+                .transform()
+                .unwrap_err()
+                .downcast::<TransformError>()
+                .unwrap(),
+            TransformError::TooDeep { .. }
+        ));
+
+        let source = shape(deepest);
+        let contracts = std::thread::Builder::new()
+            .stack_size(WORKER_STACK_BYTES)
+            .spawn(move || TransformationPipeline::default(&source).transform())
+            .unwrap()
+            .join()
+            .unwrap()
+            .unwrap();
+        assert_eq!(contracts[0].name, "Deep");
+    }
+}
+
+#[test]
+fn test_packed_state_variables_follow_bases_and_skip_constants() {
+    use num_bigint::BigUint;
+    use num_traits::Zero;
+    use thalir_core::instructions::{Instruction, StorageKey};
+    use thalir_core::values::{Constant, Value};
+
+    let source = r#"
+        contract Base {
+            uint128 supply;
+        }
+
+        contract Vault is Base {
+            uint256 constant FEE = 30;
+            address immutable admin;
+            uint64 cap;
+            bool paused;
+
+            constructor() {
+                admin = msg.sender;
+            }
+
+            function run() public returns (uint256) {
+                paused = true;
+                return cap + FEE;
+            }
+        }
+    "#;
+    let contracts = TransformationPipeline::default(source).transform().unwrap();
+    let vault = contracts.iter().find(|c| c.name == "Vault").unwrap();
+
+    let layout = &vault.storage_layout;
+    let names: Vec<&str> = layout.slots.iter().map(|var| var.name.as_str()).collect();
+    assert_eq!(names, ["cap", "paused"]);
+    assert_eq!(layout.inherited[0].name, "supply");
+    assert_eq!(
+        (layout.slots[0].slot.clone(), layout.slots[0].offset),
+        (BigUint::from(0u32), 16)
+    );
+    assert_eq!(
+        (layout.slots[1].slot.clone(), layout.slots[1].offset),
+        (BigUint::from(0u32), 24)
+    );
+    assert!(vault.constants.iter().any(|c| c.name == "FEE"));
+
+    let instructions: Vec<Instruction> = vault
+        .functions
+        .values()
+        .find(|f| f.signature.name == "run")
+        .unwrap()
+        .body
+        .blocks
+        .values()
+        .flat_map(|b| b.instructions.clone())
+        .collect();
+    let shift = |amount: u32| {
+        instructions.iter().any(|i| {
+            matches!(
+                i,
+                Instruction::Shr { shift: Value::Constant(Constant::Uint(n, _)), .. }
+                    if *n == BigUint::from(amount)
+            )
+        })
+    };
+    /* `cap` sits above `supply`, and `paused = true` keeps the bytes around it. */
+    assert!(shift(128));
+    assert!(instructions
+        .iter()
+        .any(|i| matches!(i, Instruction::Or { .. })));
+    assert!(instructions.iter().all(|i| match i {
+        Instruction::StorageLoad {
+            key: StorageKey::Slot(slot),
+            ..
+        }
+        | Instruction::StorageStore {
+            key: StorageKey::Slot(slot),
+            ..
+        } => slot.is_zero(),
+        _ => true,
+    }));
+}
@@ -216,8 +216,11 @@ impl TypeResolver {
         ctx: &dyn TypeContext,
     ) -> Result<Type, TransformError> {
         let type_name = ctx.get_node_text(node);
+        let name = type_name.rsplit('.').next().unwrap_or(type_name).trim();
 
-        Ok(Type::String)
+        /* Structs the transformer has registered get their layout; other user types such as
+         * enums and contracts keep the old placeholder. */
+        Ok(ctx.lookup_struct(name).map_or(Type::String, Type::Struct))
     }
 
     pub fn infer_expression_type(
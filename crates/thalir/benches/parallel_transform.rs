@@ -0,0 +1,77 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use thalir::bench::fixture;
+use thalir_transform::solidity_to_ir::TransformationPipeline;
+
+const VAULTS: usize = 48;
+
+/* A flattened file of independent vaults, the shape that gains the most from transforming each
+ * contract on its own core. */
+fn vaults(count: usize) -> String {
+    let mut source = String::from("// SPDX-License-Identifier: MIT\npragma solidity ^0.8.20;\n");
+    for i in 0..count {
+        source.push_str(&format!(
+            r#"
+contract Vault{i} {{
+    mapping(address => uint256) balances;
+    uint256 totalDeposits;
+    uint256 fee;
+
+    function deposit(uint256 amount) public {{
+        uint256 charged = amount * fee / 10000;
+        balances[msg.sender] += amount - charged;
+        totalDeposits += amount - charged;
+    }}
+
+    function withdraw(uint256 amount) public {{
+        require(balances[msg.sender] >= amount, "insufficient");
+        balances[msg.sender] -= amount;
+        totalDeposits -= amount;
+        (bool ok, ) = msg.sender.call{{value: amount}}("");
+        require(ok, "transfer failed");
+    }}
+
+    function sweep(address[] memory accounts) public {{
+        for (uint256 i = 0; i < accounts.length; i++) {{
+            if (balances[accounts[i]] == 0) {{
+                continue;
+            }}
+            totalDeposits -= balances[accounts[i]];
+            balances[accounts[i]] = 0;
+        }}
+    }}
+}}
+"#
+        ));
+    }
+    source
+}
+
+fn parallel_transform(c: &mut Criterion) {
+    let protocol = fixture("flattened_protocol").expect("bundled fixture");
+    let sources = [
+        (protocol.name.to_string(), protocol.source.to_string()),
+        (format!("vaults_{}", VAULTS), vaults(VAULTS)),
+    ];
+
+    let mut group = c.benchmark_group("transform_contracts");
+    for (name, source) in &sources {
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        for parallel in [false, true] {
+            let mode = if parallel { "parallel" } else { "sequential" };
+            group.bench_with_input(BenchmarkId::new(mode, name), source, |b, source| {
+                b.iter(|| {
+                    black_box(
+                        TransformationPipeline::default(source)
+                            .parallel(parallel)
+                            .transform()
+                            .unwrap(),
+                    )
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parallel_transform);
+criterion_main!(benches);
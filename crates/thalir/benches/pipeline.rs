@@ -0,0 +1,25 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use thalir::bench::{Stage, Workload, FIXTURES};
+
+fn pipeline(c: &mut Criterion) {
+    let workloads: Vec<Workload> = FIXTURES
+        .iter()
+        .map(|fixture| Workload::prepare(fixture).expect("bundled fixture should transform"))
+        .collect();
+
+    for stage in Stage::ALL {
+        let mut group = c.benchmark_group(stage.name());
+        for workload in &workloads {
+            group.throughput(Throughput::Bytes(workload.fixture.source.len() as u64));
+            group.bench_with_input(
+                BenchmarkId::from_parameter(workload.fixture.name),
+                workload,
+                |b, workload| b.iter(|| black_box(workload.run(stage).unwrap())),
+            );
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, pipeline);
+criterion_main!(benches);
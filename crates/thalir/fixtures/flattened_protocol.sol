@@ -0,0 +1,549 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+// Flattened lending protocol: token, oracle, interest model, vault, staking and governance.
+
+interface IERC20 {
+    function totalSupply() external view returns (uint256);
+    function balanceOf(address account) external view returns (uint256);
+    function transfer(address to, uint256 amount) external returns (bool);
+    function transferFrom(address from, address to, uint256 amount) external returns (bool);
+    function approve(address spender, uint256 amount) external returns (bool);
+}
+
+interface IPriceOracle {
+    function getPrice(address asset) external view returns (uint256);
+}
+
+library MathLib {
+    uint256 internal constant WAD = 1e18;
+
+    function mulWad(uint256 a, uint256 b) internal pure returns (uint256) {
+        return a * b / WAD;
+    }
+
+    function divWad(uint256 a, uint256 b) internal pure returns (uint256) {
+        require(b != 0, "division by zero");
+        return a * WAD / b;
+    }
+
+    function min(uint256 a, uint256 b) internal pure returns (uint256) {
+        return a < b ? a : b;
+    }
+
+    function max(uint256 a, uint256 b) internal pure returns (uint256) {
+        return a > b ? a : b;
+    }
+}
+
+abstract contract Ownable {
+    address public owner;
+    address public pendingOwner;
+
+    event OwnershipTransferred(address indexed previousOwner, address indexed newOwner);
+
+    constructor() {
+        owner = msg.sender;
+    }
+
+    modifier onlyOwner() {
+        require(msg.sender == owner, "not owner");
+        _;
+    }
+
+    function transferOwnership(address newOwner) external onlyOwner {
+        pendingOwner = newOwner;
+    }
+
+    function acceptOwnership() external {
+        require(msg.sender == pendingOwner, "not pending owner");
+        emit OwnershipTransferred(owner, pendingOwner);
+        owner = pendingOwner;
+        pendingOwner = address(0);
+    }
+}
+
+abstract contract Pausable is Ownable {
+    bool public paused;
+
+    modifier whenNotPaused() {
+        require(!paused, "paused");
+        _;
+    }
+
+    function pause() external onlyOwner {
+        paused = true;
+    }
+
+    function unpause() external onlyOwner {
+        paused = false;
+    }
+}
+
+abstract contract ReentrancyGuard {
+    uint256 private status = 1;
+
+    modifier nonReentrant() {
+        require(status == 1, "reentrant call");
+        status = 2;
+        _;
+        status = 1;
+    }
+}
+
+contract ProtocolToken is IERC20, Ownable {
+    string public name = "Protocol Token";
+    string public symbol = "PROT";
+    uint8 public decimals = 18;
+    uint256 public override totalSupply;
+    uint256 public immutable cap;
+
+    mapping(address => uint256) public override balanceOf;
+    mapping(address => mapping(address => uint256)) public allowance;
+    mapping(address => bool) public minters;
+
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    event Approval(address indexed owner, address indexed spender, uint256 value);
+
+    constructor(uint256 _cap) {
+        cap = _cap;
+    }
+
+    function setMinter(address minter, bool allowed) external onlyOwner {
+        minters[minter] = allowed;
+    }
+
+    function mint(address to, uint256 amount) external {
+        require(minters[msg.sender], "not minter");
+        require(totalSupply + amount <= cap, "cap exceeded");
+        totalSupply += amount;
+        balanceOf[to] += amount;
+        emit Transfer(address(0), to, amount);
+    }
+
+    function transfer(address to, uint256 amount) external override returns (bool) {
+        _transfer(msg.sender, to, amount);
+        return true;
+    }
+
+    function transferFrom(address from, address to, uint256 amount) external override returns (bool) {
+        uint256 allowed = allowance[from][msg.sender];
+        require(allowed >= amount, "insufficient allowance");
+        allowance[from][msg.sender] = allowed - amount;
+        _transfer(from, to, amount);
+        return true;
+    }
+
+    function approve(address spender, uint256 amount) external override returns (bool) {
+        allowance[msg.sender][spender] = amount;
+        emit Approval(msg.sender, spender, amount);
+        return true;
+    }
+
+    function _transfer(address from, address to, uint256 amount) internal {
+        require(to != address(0), "zero address");
+        require(balanceOf[from] >= amount, "insufficient balance");
+        balanceOf[from] -= amount;
+        balanceOf[to] += amount;
+        emit Transfer(from, to, amount);
+    }
+}
+
+contract SimpleOracle is IPriceOracle, Ownable {
+    struct Observation {
+        uint256 price;
+        uint256 timestamp;
+    }
+
+    mapping(address => Observation) public observations;
+    uint256 public maxAge = 3600;
+
+    function setPrice(address asset, uint256 price) external onlyOwner {
+        require(price > 0, "invalid price");
+        observations[asset] = Observation(price, block.timestamp);
+    }
+
+    function setMaxAge(uint256 age) external onlyOwner {
+        maxAge = age;
+    }
+
+    function getPrice(address asset) external view override returns (uint256) {
+        Observation memory obs = observations[asset];
+        require(obs.price > 0, "no price");
+        require(block.timestamp - obs.timestamp <= maxAge, "stale price");
+        return obs.price;
+    }
+}
+
+contract InterestRateModel {
+    uint256 public baseRate;
+    uint256 public slope1;
+    uint256 public slope2;
+    uint256 public kink;
+
+    constructor(uint256 _baseRate, uint256 _slope1, uint256 _slope2, uint256 _kink) {
+        baseRate = _baseRate;
+        slope1 = _slope1;
+        slope2 = _slope2;
+        kink = _kink;
+    }
+
+    function utilization(uint256 cash, uint256 borrows) public pure returns (uint256) {
+        if (borrows == 0) {
+            return 0;
+        }
+        return borrows * 1e18 / (cash + borrows);
+    }
+
+    function borrowRate(uint256 cash, uint256 borrows) public view returns (uint256) {
+        uint256 util = utilization(cash, borrows);
+        if (util <= kink) {
+            return baseRate + util * slope1 / 1e18;
+        }
+        uint256 normalRate = baseRate + kink * slope1 / 1e18;
+        uint256 excess = util - kink;
+        return normalRate + excess * slope2 / 1e18;
+    }
+
+    function supplyRate(uint256 cash, uint256 borrows, uint256 reserveFactor) public view returns (uint256) {
+        uint256 rate = borrowRate(cash, borrows);
+        uint256 util = utilization(cash, borrows);
+        uint256 rateToPool = rate * (1e18 - reserveFactor) / 1e18;
+        return util * rateToPool / 1e18;
+    }
+}
+
+contract LendingVault is Pausable, ReentrancyGuard {
+    using MathLib for uint256;
+
+    struct Account {
+        uint256 collateral;
+        uint256 borrowShares;
+        uint256 supplyShares;
+        uint256 lastUpdate;
+    }
+
+    IERC20 public asset;
+    IERC20 public collateralToken;
+    IPriceOracle public oracle;
+    InterestRateModel public rateModel;
+
+    uint256 public totalSupplyShares;
+    uint256 public totalBorrowShares;
+    uint256 public totalBorrows;
+    uint256 public totalReserves;
+    uint256 public reserveFactor = 1e17;
+    uint256 public collateralFactor = 75e16;
+    uint256 public liquidationBonus = 105e16;
+    uint256 public lastAccrual;
+
+    mapping(address => Account) public accounts;
+
+    event Deposit(address indexed user, uint256 amount, uint256 shares);
+    event Withdraw(address indexed user, uint256 amount, uint256 shares);
+    event Borrow(address indexed user, uint256 amount);
+    event Repay(address indexed user, uint256 amount);
+    event Liquidate(address indexed liquidator, address indexed borrower, uint256 repaid, uint256 seized);
+
+    constructor(address _asset, address _collateral, address _oracle, address _rateModel) {
+        asset = IERC20(_asset);
+        collateralToken = IERC20(_collateral);
+        oracle = IPriceOracle(_oracle);
+        rateModel = InterestRateModel(_rateModel);
+        lastAccrual = block.timestamp;
+    }
+
+    function cash() public view returns (uint256) {
+        return asset.balanceOf(address(this)) - totalReserves;
+    }
+
+    function accrueInterest() public {
+        uint256 elapsed = block.timestamp - lastAccrual;
+        if (elapsed == 0) {
+            return;
+        }
+        uint256 rate = rateModel.borrowRate(cash(), totalBorrows);
+        uint256 interest = totalBorrows * rate / 1e18 * elapsed / 365 days;
+        totalBorrows += interest;
+        totalReserves += interest * reserveFactor / 1e18;
+        lastAccrual = block.timestamp;
+    }
+
+    function totalAssets() public view returns (uint256) {
+        return cash() + totalBorrows;
+    }
+
+    function deposit(uint256 amount) external nonReentrant whenNotPaused returns (uint256 shares) {
+        accrueInterest();
+        if (totalSupplyShares == 0) {
+            shares = amount;
+        } else {
+            shares = amount * totalSupplyShares / totalAssets();
+        }
+        require(shares > 0, "zero shares");
+        require(asset.transferFrom(msg.sender, address(this), amount), "transfer failed");
+        accounts[msg.sender].supplyShares += shares;
+        totalSupplyShares += shares;
+        emit Deposit(msg.sender, amount, shares);
+    }
+
+    function withdraw(uint256 shares) external nonReentrant returns (uint256 amount) {
+        accrueInterest();
+        Account storage account = accounts[msg.sender];
+        require(account.supplyShares >= shares, "insufficient shares");
+        amount = shares * totalAssets() / totalSupplyShares;
+        require(amount <= cash(), "insufficient cash");
+        account.supplyShares -= shares;
+        totalSupplyShares -= shares;
+        require(asset.transfer(msg.sender, amount), "transfer failed");
+        emit Withdraw(msg.sender, amount, shares);
+    }
+
+    function depositCollateral(uint256 amount) external nonReentrant whenNotPaused {
+        require(collateralToken.transferFrom(msg.sender, address(this), amount), "transfer failed");
+        accounts[msg.sender].collateral += amount;
+    }
+
+    function withdrawCollateral(uint256 amount) external nonReentrant {
+        accrueInterest();
+        Account storage account = accounts[msg.sender];
+        require(account.collateral >= amount, "insufficient collateral");
+        account.collateral -= amount;
+        require(isHealthy(msg.sender), "undercollateralized");
+        require(collateralToken.transfer(msg.sender, amount), "transfer failed");
+    }
+
+    function borrowBalance(address user) public view returns (uint256) {
+        if (totalBorrowShares == 0) {
+            return 0;
+        }
+        return accounts[user].borrowShares * totalBorrows / totalBorrowShares;
+    }
+
+    function collateralValue(address user) public view returns (uint256) {
+        uint256 price = oracle.getPrice(address(collateralToken));
+        return accounts[user].collateral * price / 1e18 * collateralFactor / 1e18;
+    }
+
+    function isHealthy(address user) public view returns (bool) {
+        uint256 debtPrice = oracle.getPrice(address(asset));
+        uint256 debtValue = borrowBalance(user) * debtPrice / 1e18;
+        return collateralValue(user) >= debtValue;
+    }
+
+    function borrow(uint256 amount) external nonReentrant whenNotPaused {
+        accrueInterest();
+        require(amount <= cash(), "insufficient cash");
+        uint256 shares;
+        if (totalBorrowShares == 0) {
+            shares = amount;
+        } else {
+            shares = amount * totalBorrowShares / totalBorrows;
+        }
+        accounts[msg.sender].borrowShares += shares;
+        totalBorrowShares += shares;
+        totalBorrows += amount;
+        require(isHealthy(msg.sender), "undercollateralized");
+        require(asset.transfer(msg.sender, amount), "transfer failed");
+        emit Borrow(msg.sender, amount);
+    }
+
+    function repay(uint256 amount) external nonReentrant {
+        accrueInterest();
+        uint256 owed = borrowBalance(msg.sender);
+        uint256 payment = MathLib.min(amount, owed);
+        uint256 shares = payment * totalBorrowShares / totalBorrows;
+        require(asset.transferFrom(msg.sender, address(this), payment), "transfer failed");
+        accounts[msg.sender].borrowShares -= shares;
+        totalBorrowShares -= shares;
+        totalBorrows -= payment;
+        emit Repay(msg.sender, payment);
+    }
+
+    function liquidate(address borrower, uint256 repayAmount) external nonReentrant {
+        accrueInterest();
+        require(!isHealthy(borrower), "borrower healthy");
+        uint256 owed = borrowBalance(borrower);
+        require(repayAmount <= owed / 2, "repay too large");
+
+        uint256 debtPrice = oracle.getPrice(address(asset));
+        uint256 collateralPrice = oracle.getPrice(address(collateralToken));
+        uint256 seize = repayAmount * debtPrice / collateralPrice * liquidationBonus / 1e18;
+        Account storage account = accounts[borrower];
+        require(seize <= account.collateral, "insufficient collateral");
+
+        uint256 shares = repayAmount * totalBorrowShares / totalBorrows;
+        require(asset.transferFrom(msg.sender, address(this), repayAmount), "transfer failed");
+        account.borrowShares -= shares;
+        totalBorrowShares -= shares;
+        totalBorrows -= repayAmount;
+        account.collateral -= seize;
+        require(collateralToken.transfer(msg.sender, seize), "transfer failed");
+        emit Liquidate(msg.sender, borrower, repayAmount, seize);
+    }
+
+    function setRiskParameters(uint256 _collateralFactor, uint256 _liquidationBonus, uint256 _reserveFactor) external onlyOwner {
+        require(_collateralFactor <= 9e17, "factor too high");
+        require(_liquidationBonus >= 1e18, "bonus too low");
+        require(_reserveFactor <= 5e17, "reserve too high");
+        collateralFactor = _collateralFactor;
+        liquidationBonus = _liquidationBonus;
+        reserveFactor = _reserveFactor;
+    }
+
+    function withdrawReserves(address to, uint256 amount) external onlyOwner {
+        require(amount <= totalReserves, "insufficient reserves");
+        totalReserves -= amount;
+        require(asset.transfer(to, amount), "transfer failed");
+    }
+}
+
+contract StakingRewards is Ownable, ReentrancyGuard {
+    IERC20 public stakingToken;
+    IERC20 public rewardToken;
+
+    uint256 public rewardRate;
+    uint256 public periodFinish;
+    uint256 public lastUpdateTime;
+    uint256 public rewardPerTokenStored;
+    uint256 public totalStaked;
+
+    mapping(address => uint256) public staked;
+    mapping(address => uint256) public userRewardPerTokenPaid;
+    mapping(address => uint256) public rewards;
+
+    constructor(address _stakingToken, address _rewardToken) {
+        stakingToken = IERC20(_stakingToken);
+        rewardToken = IERC20(_rewardToken);
+    }
+
+    function lastTimeRewardApplicable() public view returns (uint256) {
+        return block.timestamp < periodFinish ? block.timestamp : periodFinish;
+    }
+
+    function rewardPerToken() public view returns (uint256) {
+        if (totalStaked == 0) {
+            return rewardPerTokenStored;
+        }
+        return rewardPerTokenStored + (lastTimeRewardApplicable() - lastUpdateTime) * rewardRate * 1e18 / totalStaked;
+    }
+
+    function earned(address account) public view returns (uint256) {
+        return staked[account] * (rewardPerToken() - userRewardPerTokenPaid[account]) / 1e18 + rewards[account];
+    }
+
+    function updateReward(address account) internal {
+        rewardPerTokenStored = rewardPerToken();
+        lastUpdateTime = lastTimeRewardApplicable();
+        if (account != address(0)) {
+            rewards[account] = earned(account);
+            userRewardPerTokenPaid[account] = rewardPerTokenStored;
+        }
+    }
+
+    function stake(uint256 amount) external nonReentrant {
+        require(amount > 0, "zero amount");
+        updateReward(msg.sender);
+        totalStaked += amount;
+        staked[msg.sender] += amount;
+        require(stakingToken.transferFrom(msg.sender, address(this), amount), "transfer failed");
+    }
+
+    function unstake(uint256 amount) public nonReentrant {
+        require(amount > 0, "zero amount");
+        updateReward(msg.sender);
+        totalStaked -= amount;
+        staked[msg.sender] -= amount;
+        require(stakingToken.transfer(msg.sender, amount), "transfer failed");
+    }
+
+    function claim() public nonReentrant {
+        updateReward(msg.sender);
+        uint256 reward = rewards[msg.sender];
+        if (reward > 0) {
+            rewards[msg.sender] = 0;
+            require(rewardToken.transfer(msg.sender, reward), "transfer failed");
+        }
+    }
+
+    function notifyRewardAmount(uint256 reward, uint256 duration) external onlyOwner {
+        updateReward(address(0));
+        if (block.timestamp >= periodFinish) {
+            rewardRate = reward / duration;
+        } else {
+            uint256 remaining = periodFinish - block.timestamp;
+            uint256 leftover = remaining * rewardRate;
+            rewardRate = (reward + leftover) / duration;
+        }
+        lastUpdateTime = block.timestamp;
+        periodFinish = block.timestamp + duration;
+    }
+}
+
+contract Governor {
+    struct Proposal {
+        address proposer;
+        address target;
+        bytes data;
+        uint256 forVotes;
+        uint256 againstVotes;
+        uint256 startBlock;
+        uint256 endBlock;
+        bool executed;
+    }
+
+    IERC20 public votingToken;
+    uint256 public proposalCount;
+    uint256 public votingPeriod = 17280;
+    uint256 public quorum;
+
+    mapping(uint256 => Proposal) public proposals;
+    mapping(uint256 => mapping(address => bool)) public hasVoted;
+
+    event ProposalCreated(uint256 indexed id, address proposer, address target);
+    event VoteCast(uint256 indexed id, address voter, bool support, uint256 weight);
+    event ProposalExecuted(uint256 indexed id);
+
+    constructor(address _votingToken, uint256 _quorum) {
+        votingToken = IERC20(_votingToken);
+        quorum = _quorum;
+    }
+
+    function propose(address target, bytes calldata data) external returns (uint256 id) {
+        require(votingToken.balanceOf(msg.sender) * 100 >= votingToken.totalSupply(), "below threshold");
+        id = ++proposalCount;
+        Proposal storage proposal = proposals[id];
+        proposal.proposer = msg.sender;
+        proposal.target = target;
+        proposal.data = data;
+        proposal.startBlock = block.number;
+        proposal.endBlock = block.number + votingPeriod;
+        emit ProposalCreated(id, msg.sender, target);
+    }
+
+    function castVote(uint256 id, bool support) external {
+        Proposal storage proposal = proposals[id];
+        require(block.number <= proposal.endBlock, "voting closed");
+        require(!hasVoted[id][msg.sender], "already voted");
+        uint256 weight = votingToken.balanceOf(msg.sender);
+        hasVoted[id][msg.sender] = true;
+        if (support) {
+            proposal.forVotes += weight;
+        } else {
+            proposal.againstVotes += weight;
+        }
+        emit VoteCast(id, msg.sender, support, weight);
+    }
+
+    function execute(uint256 id) external {
+        Proposal storage proposal = proposals[id];
+        require(block.number > proposal.endBlock, "voting open");
+        require(!proposal.executed, "already executed");
+        require(proposal.forVotes > proposal.againstVotes, "defeated");
+        require(proposal.forVotes + proposal.againstVotes >= quorum, "quorum not met");
+        proposal.executed = true;
+        (bool ok, ) = proposal.target.call(proposal.data);
+        require(ok, "execution failed");
+        emit ProposalExecuted(id);
+    }
+}
@@ -0,0 +1,220 @@
+/*! Reproducible pipeline workloads for benchmarking.
+ *
+ * Representation changes such as arenas or interned values are only worth their churn if they
+ * measurably speed up real contracts. This module bundles a fixed corpus and the stages worth
+ * timing so the criterion suite and `thalir bench` measure exactly the same work, and compares
+ * runs against a saved baseline to catch regressions.
+ */
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use thalir_core::{
+    analysis::{
+        ControlFlowGraph, DefUseChains, DivisionGuardAnalysis, DominatorTree, PrecisionLossAnalysis,
+    },
+    contract::Contract,
+};
+use thalir_emit::ThalIREmitter;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Fixture {
+    pub name: &'static str,
+    pub source: &'static str,
+}
+
+pub const FIXTURES: &[Fixture] = &[
+    Fixture {
+        name: "erc20",
+        source: include_str!("../fixtures/erc20.sol"),
+    },
+    Fixture {
+        name: "uniswap_pair",
+        source: include_str!("../fixtures/uniswap_pair.sol"),
+    },
+    Fixture {
+        name: "flattened_protocol",
+        source: include_str!("../fixtures/flattened_protocol.sol"),
+    },
+];
+
+pub fn fixture(name: &str) -> Option<&'static Fixture> {
+    FIXTURES.iter().find(|fixture| fixture.name == name)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    Transform,
+    Emit,
+    Parse,
+    Analyze,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [Stage::Transform, Stage::Emit, Stage::Parse, Stage::Analyze];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Transform => "transform",
+            Stage::Emit => "emit",
+            Stage::Parse => "parse",
+            Stage::Analyze => "analyze",
+        }
+    }
+}
+
+pub struct Workload {
+    pub fixture: &'static Fixture,
+    contracts: Vec<Contract>,
+    text: String,
+}
+
+impl Workload {
+    pub fn prepare(fixture: &'static Fixture) -> anyhow::Result<Self> {
+        let contracts = thalir_transform::transform_solidity_to_ir(fixture.source)?;
+        let text = ThalIREmitter::new(contracts.clone()).emit_to_string(false);
+        Ok(Self {
+            fixture,
+            contracts,
+            text,
+        })
+    }
+
+    pub fn run(&self, stage: Stage) -> anyhow::Result<usize> {
+        match stage {
+            Stage::Transform => {
+                Ok(thalir_transform::transform_solidity_to_ir(self.fixture.source)?.len())
+            }
+            Stage::Emit => Ok(ThalIREmitter::new(self.contracts.clone())
+                .emit_to_string(false)
+                .len()),
+            Stage::Parse => thalir_parser::parse(&self.text)
+                .map(|pairs| pairs.count())
+                .map_err(|e| anyhow::anyhow!("{}", e)),
+            Stage::Analyze => Ok(self.contracts.iter().map(analyze).sum()),
+        }
+    }
+}
+
+fn analyze(contract: &Contract) -> usize {
+    let mut work = 0;
+    for function in contract.functions.values() {
+        let cfg = ControlFlowGraph::build(function);
+        let dominators = DominatorTree::build(function);
+        let chains = DefUseChains::build(function);
+        std::hint::black_box((&cfg, &dominators, &chains));
+        work += function.body.blocks.len();
+    }
+    work += DivisionGuardAnalysis::analyze_contract(contract).len();
+    work += PrecisionLossAnalysis::analyze_contract(contract).len();
+    work
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Measurement {
+    pub fixture: String,
+    pub stage: Stage,
+    pub iterations: u32,
+    pub mean_ns: u128,
+    pub min_ns: u128,
+}
+
+impl Measurement {
+    pub fn mean(&self) -> Duration {
+        Duration::from_nanos(self.mean_ns as u64)
+    }
+}
+
+pub fn measure(workload: &Workload, stage: Stage, iterations: u32) -> anyhow::Result<Measurement> {
+    let iterations = iterations.max(1);
+    std::hint::black_box(workload.run(stage)?);
+
+    let mut total = 0u128;
+    let mut min = u128::MAX;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        std::hint::black_box(workload.run(stage)?);
+        let elapsed = start.elapsed().as_nanos();
+        total += elapsed;
+        min = min.min(elapsed);
+    }
+
+    Ok(Measurement {
+        fixture: workload.fixture.name.to_string(),
+        stage,
+        iterations,
+        mean_ns: total / iterations as u128,
+        min_ns: min,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct Regression {
+    pub fixture: String,
+    pub stage: Stage,
+    pub baseline_ns: u128,
+    pub current_ns: u128,
+}
+
+impl Regression {
+    pub fn slowdown_percent(&self) -> f64 {
+        (self.current_ns as f64 / self.baseline_ns.max(1) as f64 - 1.0) * 100.0
+    }
+}
+
+/* Minimum times are compared rather than means: they are far less sensitive to a noisy machine,
+ * which keeps the gate usable in shared CI runners. */
+pub fn regressions(
+    baseline: &[Measurement],
+    current: &[Measurement],
+    max_regression_percent: f64,
+) -> Vec<Regression> {
+    current
+        .iter()
+        .filter_map(|now| {
+            let before = baseline
+                .iter()
+                .find(|m| m.fixture == now.fixture && m.stage == now.stage)?;
+            let regression = Regression {
+                fixture: now.fixture.clone(),
+                stage: now.stage,
+                baseline_ns: before.min_ns,
+                current_ns: now.min_ns,
+            };
+            (regression.slowdown_percent() > max_regression_percent).then_some(regression)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(fixture: &str, stage: Stage, min_ns: u128) -> Measurement {
+        Measurement {
+            fixture: fixture.to_string(),
+            stage,
+            iterations: 1,
+            mean_ns: min_ns,
+            min_ns,
+        }
+    }
+
+    #[test]
+    fn test_regressions_beyond_threshold_are_reported() {
+        let baseline = vec![
+            sample("erc20", Stage::Transform, 1_000),
+            sample("erc20", Stage::Emit, 1_000),
+        ];
+        let current = vec![
+            sample("erc20", Stage::Transform, 1_050),
+            sample("erc20", Stage::Emit, 1_200),
+            sample("uniswap_pair", Stage::Emit, 9_000),
+        ];
+
+        let found = regressions(&baseline, &current, 10.0);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].stage, Stage::Emit);
+        assert!((found[0].slowdown_percent() - 20.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,339 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thalir_core::analysis::{AddressLabels, CallbackConfig, LintConfig, TrustZones};
+use thalir_core::codegen::WordAbi;
+use thalir_core::contract::Contract;
+use thalir_core::{EvmVersion, ObfuscationLevel};
+use thalir_emit::{EmitterProfile, FunctionOrder, StorageNaming};
+use thalir_transform::InputLimits;
+
+pub const CONFIG_FILE: &str = "thalir.toml";
+
+/* Project defaults read from `thalir.toml`. Every section is optional; command-line flags win over
+ * anything set here, and anything unset falls back to the built-in defaults. */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    pub evm_version: Option<EvmVersion>,
+    pub remappings: Vec<String>,
+    pub cache_dir: Option<PathBuf>,
+    pub output: OutputSettings,
+    pub obfuscation: ObfuscationSettings,
+    pub passes: PassSettings,
+    pub lint: LintConfig,
+    pub codegen: CodegenSettings,
+    pub limits: LimitSettings,
+    pub callbacks: CallbackConfig,
+    pub project: ProjectSettings,
+    pub addresses: AddressSettings,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Annotated,
+    Jsonl,
+    Pseudo,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct OutputSettings {
+    pub format: OutputFormat,
+    /* Only the annotated format has levels of detail; the others ignore it. */
+    pub verbosity: Option<EmitterProfile>,
+    pub ascii: bool,
+    pub storage_names: Option<StorageNaming>,
+    pub summaries: bool,
+    pub function_order: Option<FunctionOrder>,
+    pub group_functions: bool,
+}
+
+/* `retain` and `aliases` take the same names as the `--retain` and `--alias` flags. */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ObfuscationSettings {
+    pub level: ObfuscationLevel,
+    pub retain: Vec<String>,
+    pub aliases: Vec<String>,
+}
+
+/* Analysis passes by name. Leaving `enabled` unset runs everything not listed in `disabled`. */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PassSettings {
+    pub enabled: Option<Vec<String>>,
+    pub disabled: Vec<String>,
+}
+
+/* `word-abi` is how words are passed to the embedder's runtime helpers: "i128", "i64-pair" or
+ * "handle". */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct CodegenSettings {
+    pub word_abi: WordAbi,
+}
+
+/* Guards for untrusted sources. Unset keys keep the built-in limits; `max-file-size` is in bytes
+ * and `timeout`, which has no default, in seconds. */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LimitSettings {
+    pub max_file_size: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub timeout: Option<u64>,
+}
+
+/* What `analyze` treats as the protocol. `targets` names the contracts under review; left empty,
+ * every contract outside the `vendored` directories is one. Contracts the targets never reach are
+ * left out of the analysis. The vendored directories hold trusted dependencies: findings are
+ * attributed to them, and with `suppress-dependency-findings` dropped. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ProjectSettings {
+    pub targets: Vec<String>,
+    pub vendored: Vec<String>,
+    pub suppress_dependency_findings: bool,
+}
+
+impl Default for ProjectSettings {
+    fn default() -> Self {
+        Self {
+            targets: Vec::new(),
+            vendored: vec!["lib/".to_string(), "node_modules/".to_string()],
+            suppress_dependency_findings: false,
+        }
+    }
+}
+
+impl ProjectSettings {
+    /* A directory matches wherever it appears in the path, so `lib/` covers `./lib/...` and
+     * `/home/me/protocol/lib/...` alike. */
+    pub fn is_vendored(&self, file: &str) -> bool {
+        let components: Vec<&str> = file.split(['/', '\\']).filter(|c| !c.is_empty()).collect();
+        self.vendored.iter().any(|dir| {
+            let dir: Vec<&str> = dir.split('/').filter(|c| !c.is_empty()).collect();
+            !dir.is_empty() && components.windows(dir.len()).any(|window| window == dir)
+        })
+    }
+
+    pub fn trust_zones(&self, contracts: &[Contract]) -> TrustZones {
+        TrustZones::classify(contracts, |file| self.is_vendored(file))
+    }
+
+    pub fn roots<'a>(&'a self, contracts: &'a [Contract]) -> Vec<&'a str> {
+        if !self.targets.is_empty() {
+            return self.targets.iter().map(String::as_str).collect();
+        }
+        contracts
+            .iter()
+            .filter(|contract| {
+                !contract
+                    .metadata
+                    .source_file
+                    .as_deref()
+                    .is_some_and(|file| self.is_vendored(file))
+            })
+            .map(|contract| contract.name.as_str())
+            .collect()
+    }
+}
+
+/* Labels for hardcoded addresses: a JSON `registry` mapping addresses to labels, and `labels`
+ * written inline, which win over the registry's. */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AddressSettings {
+    pub registry: Option<PathBuf>,
+    pub labels: BTreeMap<String, String>,
+}
+
+impl AddressSettings {
+    pub fn address_labels(&self) -> Result<AddressLabels> {
+        let mut labels = match &self.registry {
+            Some(path) => AddressLabels::load(path)?,
+            None => AddressLabels::default(),
+        };
+        labels.extend(self.labels.clone());
+        Ok(labels)
+    }
+}
+
+impl LimitSettings {
+    pub fn input_limits(&self) -> InputLimits {
+        let defaults = InputLimits::default();
+        InputLimits {
+            max_source_bytes: self.max_file_size.or(defaults.max_source_bytes),
+            max_depth: self.max_depth.or(defaults.max_depth),
+            timeout: self.timeout.map(Duration::from_secs),
+        }
+    }
+}
+
+impl PassSettings {
+    pub fn is_enabled(&self, pass: &str) -> bool {
+        self.enabled
+            .iter()
+            .all(|enabled| enabled.iter().any(|name| name == pass))
+            && !self.disabled.iter().any(|name| name == pass)
+    }
+}
+
+impl Config {
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let config: Self = toml::from_str(text)?;
+        let unknown = config.lint.unknown_rules();
+        if !unknown.is_empty() {
+            return Err(anyhow!("Unknown lint rule(s): {}", unknown.join(", ")));
+        }
+        let unknown = config.callbacks.unknown_interfaces();
+        if !unknown.is_empty() {
+            return Err(anyhow!(
+                "Unknown callback interface(s): {}",
+                unknown.join(", ")
+            ));
+        }
+        Ok(config)
+    }
+
+    /* A relative `cache-dir` or address registry is taken relative to the file, not the working
+     * directory. */
+    pub fn load(path: &Path) -> Result<Self> {
+        let text =
+            fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+        let mut config =
+            Self::from_toml(&text).with_context(|| format!("Invalid {}", path.display()))?;
+        if let Some(base) = path.parent() {
+            for dir in [
+                config.cache_dir.as_mut(),
+                config.addresses.registry.as_mut(),
+            ]
+            .into_iter()
+            .flatten()
+            {
+                if dir.is_relative() {
+                    *dir = base.join(&*dir);
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /* Looks for `thalir.toml` in `start` and each of its ancestors, like cargo does for manifests. */
+    pub fn discover(start: &Path) -> Result<Option<(PathBuf, Self)>> {
+        for dir in start.ancestors() {
+            let candidate = dir.join(CONFIG_FILE);
+            if candidate.is_file() {
+                let config = Self::load(&candidate)?;
+                return Ok(Some((candidate, config)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use thalir_core::analysis::{LintLevel, LintRule};
+
+    #[test]
+    fn test_sections_parse_and_unknown_keys_are_rejected() {
+        let config = Config::from_toml(
+            r#"
+            evm-version = "shanghai"
+            remappings = ["@oz/=lib/openzeppelin-contracts/"]
+            cache-dir = ".thalir-cache"
+
+            [output]
+            format = "annotated"
+            verbosity = "debug"
+            summaries = true
+            function-order = "visibility"
+
+            [obfuscation]
+            level = "standard"
+            retain = ["lines", "selectors"]
+
+            [passes]
+            disabled = ["precision-loss"]
+
+            [lint]
+            dead-store = "deny"
+
+            [codegen]
+            word-abi = "i64-pair"
+
+            [limits]
+            max-file-size = 1048576
+            timeout = 30
+
+            [callbacks]
+            interfaces = ["erc721", "aave"]
+
+            [[callbacks.custom]]
+            name = "vault-hooks"
+            kind = "recipient"
+            triggers = ["depositFor(address,uint256)"]
+            hooks = ["onDeposit"]
+
+            [project]
+            vendored = ["lib/", "packages/deps/"]
+            suppress-dependency-findings = true
+
+            [addresses.labels]
+            "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D" = "Uniswap V2 Router"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.evm_version, Some(EvmVersion::Shanghai));
+        assert_eq!(config.output.format, OutputFormat::Annotated);
+        assert_eq!(config.output.verbosity, Some(EmitterProfile::Debug));
+        assert!(config.output.summaries);
+        assert_eq!(
+            config.output.function_order,
+            Some(FunctionOrder::Visibility)
+        );
+        assert_eq!(config.obfuscation.level, ObfuscationLevel::Standard);
+        assert!(config.passes.is_enabled("unguarded-division"));
+        assert!(!config.passes.is_enabled("precision-loss"));
+        assert_eq!(config.lint.level(LintRule::DeadStore), LintLevel::Deny);
+        assert_eq!(config.codegen.word_abi, WordAbi::I64Pair);
+        let limits = config.limits.input_limits();
+        assert_eq!(limits.max_source_bytes, Some(1048576));
+        assert_eq!(limits.max_depth, Some(InputLimits::DEFAULT_MAX_DEPTH));
+        assert_eq!(limits.timeout, Some(Duration::from_secs(30)));
+        let names: Vec<String> = config
+            .callbacks
+            .model()
+            .interfaces
+            .into_iter()
+            .map(|interface| interface.name)
+            .collect();
+        assert_eq!(names, vec!["erc721", "aave", "vault-hooks"]);
+        assert!(config.project.is_vendored("./lib/forge-std/src/Test.sol"));
+        assert!(config.project.is_vendored("/work/packages/deps/Math.sol"));
+        assert!(!config.project.is_vendored("src/library/Math.sol"));
+        assert!(config.project.suppress_dependency_findings);
+        assert!(!config.project.is_vendored("node_modules/x/X.sol"));
+        assert!(Config::default()
+            .project
+            .is_vendored("node_modules/x/X.sol"));
+        let labels = config.addresses.address_labels().unwrap();
+        assert_eq!(
+            labels.get("0x7a250d5630b4cf539739df2c5dacb4c659f2488d"),
+            Some("Uniswap V2 Router")
+        );
+
+        assert!(Config::from_toml("[output]\nformatt = \"text\"").is_err());
+        assert!(Config::from_toml("[lint]\nno-such-rule = \"warn\"").is_err());
+        assert!(Config::from_toml("[callbacks]\ninterfaces = [\"erc20\"]").is_err());
+    }
+}
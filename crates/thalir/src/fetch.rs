@@ -0,0 +1,308 @@
+use anyhow::{anyhow, bail, Context, Result};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use thalir_core::EvmVersion;
+use thalir_transform::{InputLimits, TransformOutput};
+
+use crate::standard_json::{transform_sources, SourceFile, SourceUnit, StandardJsonInput};
+
+/* Etherscan's multichain endpoint; the chain is picked with `chainid`. Other explorers that speak
+ * the same `module=contract&action=getsourcecode` API can be swapped in with `with_api_url`. */
+pub const ETHERSCAN_API: &str = "https://api.etherscan.io/v2/api";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifiedSource {
+    pub address: String,
+    pub chain_id: u64,
+    pub contract_name: String,
+    pub compiler_version: String,
+    pub evm_version: Option<String>,
+    /* Set when the explorer has matched the address as a proxy; the logic lives at this address. */
+    pub implementation: Option<String>,
+    pub files: Vec<SourceFile>,
+}
+
+impl VerifiedSource {
+    pub fn transform(
+        &self,
+        lenient: bool,
+        evm_version: Option<EvmVersion>,
+        limits: InputLimits,
+    ) -> Result<TransformOutput> {
+        let evm_version = evm_version.or_else(|| self.evm_version.as_deref()?.parse().ok());
+        transform_sources(&self.files, lenient, evm_version, limits)
+    }
+}
+
+pub struct SourceFetcher {
+    api_url: String,
+    api_key: Option<String>,
+    cache_dir: Option<PathBuf>,
+}
+
+impl Default for SourceFetcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SourceFetcher {
+    /* Picks up `ETHERSCAN_API_KEY` and caches under `THALIR_CACHE_DIR`, falling back to the
+     * user's cache directory. */
+    pub fn new() -> Self {
+        Self {
+            api_url: ETHERSCAN_API.to_string(),
+            api_key: std::env::var("ETHERSCAN_API_KEY").ok(),
+            cache_dir: default_cache_dir(),
+        }
+    }
+
+    pub fn with_api_url(mut self, url: impl Into<String>) -> Self {
+        self.api_url = url.into();
+        self
+    }
+
+    pub fn with_api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    /* `None` turns caching off. */
+    pub fn with_cache_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.cache_dir = dir;
+        self
+    }
+
+    /* Verified source is immutable once published, so a cached copy is used without revalidation.
+     * Entries are kept per explorer, since two explorers can disagree about the same address. */
+    pub fn fetch(&self, address: &str, chain_id: u64) -> Result<VerifiedSource> {
+        let address = normalize_address(address)?;
+        let cache_path = self.cache_dir.as_ref().map(|dir| {
+            dir.join(api_host(&self.api_url))
+                .join(chain_id.to_string())
+                .join(format!("{}.json", address))
+        });
+
+        if let Some(path) = cache_path.as_ref().filter(|path| path.exists()) {
+            let cached = fs::read_to_string(path)?;
+            return serde_json::from_str(&cached)
+                .with_context(|| format!("Corrupt source cache entry {}", path.display()));
+        }
+
+        let mut request = ureq::get(&self.api_url)
+            .query("chainid", &chain_id.to_string())
+            .query("module", "contract")
+            .query("action", "getsourcecode")
+            .query("address", &address);
+        if let Some(key) = &self.api_key {
+            request = request.query("apikey", key);
+        }
+        /* The transport error quotes the request URL, key included. */
+        let response: serde_json::Value = request
+            .call()
+            .map_err(|error| {
+                anyhow!(
+                    "Source request for {} on chain {} failed: {}",
+                    address,
+                    chain_id,
+                    redact(&error.to_string(), self.api_key.as_deref())
+                )
+            })?
+            .into_json()?;
+        let source = parse_response(&response, &address, chain_id)?;
+
+        if let Some(path) = cache_path {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, serde_json::to_string_pretty(&source)?)?;
+        }
+
+        Ok(source)
+    }
+}
+
+fn default_cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("THALIR_CACHE_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("thalir").join("sources"))
+}
+
+/* The explorer's host and port as a directory name. */
+fn api_host(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    host.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn redact(message: &str, key: Option<&str>) -> String {
+    match key.filter(|key| !key.is_empty()) {
+        Some(key) => message.replace(key, "<redacted>"),
+        None => message.to_string(),
+    }
+}
+
+fn normalize_address(address: &str) -> Result<String> {
+    let hex = address.strip_prefix("0x").unwrap_or(address);
+    if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("`{}` is not a 20-byte hex address", address);
+    }
+    Ok(format!("0x{}", hex.to_ascii_lowercase()))
+}
+
+fn parse_response(
+    response: &serde_json::Value,
+    address: &str,
+    chain_id: u64,
+) -> Result<VerifiedSource> {
+    let field = |entry: &serde_json::Value, name: &str| {
+        entry
+            .get(name)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let entry = match response.get("result") {
+        Some(serde_json::Value::Array(entries)) if response["status"] == "1" => entries
+            .first()
+            .ok_or_else(|| anyhow!("Explorer returned no entry for {}", address))?,
+        Some(result) => bail!(
+            "Explorer rejected the request: {} ({})",
+            field(response, "message"),
+            result.as_str().unwrap_or_default()
+        ),
+        None => bail!("Explorer response has no `result` field"),
+    };
+
+    let contract_name = field(entry, "ContractName");
+    let raw = field(entry, "SourceCode");
+    if raw.trim().is_empty() {
+        bail!("{} on chain {} has no verified source", address, chain_id);
+    }
+
+    let evm_version = Some(field(entry, "EVMVersion"))
+        .filter(|v| !v.is_empty() && !v.eq_ignore_ascii_case("default"));
+    let implementation = Some(field(entry, "Implementation"))
+        .filter(|imp| field(entry, "Proxy") == "1" && !imp.is_empty());
+
+    Ok(VerifiedSource {
+        address: address.to_string(),
+        chain_id,
+        files: source_files(&raw, &contract_name)?,
+        contract_name,
+        compiler_version: field(entry, "CompilerVersion"),
+        evm_version,
+        implementation,
+    })
+}
+
+/* `SourceCode` comes in three shapes: a single flattened file, a `{path: {content}}` map, or
+ * standard-json input wrapped in an extra pair of braces. */
+fn source_files(raw: &str, contract_name: &str) -> Result<Vec<SourceFile>> {
+    let trimmed = raw.trim();
+    let json = if trimmed.starts_with("{{") && trimmed.ends_with("}}") {
+        Some(&trimmed[1..trimmed.len() - 1])
+    } else if trimmed.starts_with('{') {
+        Some(trimmed)
+    } else {
+        None
+    };
+
+    let units = json.and_then(|text| match StandardJsonInput::parse(text) {
+        Ok(input) => Some(input.sources),
+        Err(_) => serde_json::from_str::<IndexMap<String, SourceUnit>>(text).ok(),
+    });
+    let Some(units) = units else {
+        return Ok(vec![SourceFile {
+            path: format!("{}.sol", contract_name),
+            content: raw.to_string(),
+        }]);
+    };
+
+    let files: Vec<SourceFile> = units
+        .into_iter()
+        .filter_map(|(path, unit)| {
+            Some(SourceFile {
+                path,
+                content: unit.content?,
+            })
+        })
+        .collect();
+    if files.is_empty() {
+        bail!("Verified source for {} lists no files", contract_name);
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_standard_json_and_flat_sources_are_unpacked() {
+        let standard = json!({
+            "language": "Solidity",
+            "sources": {
+                "src/Vault.sol": { "content": "contract Vault {}" },
+                "src/Base.sol": { "content": "contract Base {}" }
+            }
+        });
+        let response = json!({
+            "status": "1",
+            "message": "OK",
+            "result": [{
+                "SourceCode": format!("{{{}}}", standard),
+                "ContractName": "Vault",
+                "CompilerVersion": "v0.8.24+commit.e11b9ed9",
+                "EVMVersion": "Default",
+                "Proxy": "0",
+                "Implementation": ""
+            }]
+        });
+        let address = "0x00000000000000000000000000000000000000aa";
+        let source = parse_response(&response, address, 1).unwrap();
+        assert_eq!(source.contract_name, "Vault");
+        assert_eq!(source.files.len(), 2);
+        assert_eq!(source.evm_version, None);
+        assert_eq!(source.implementation, None);
+
+        let flat = source_files("pragma solidity ^0.8.0;\ncontract Vault {}", "Vault").unwrap();
+        assert_eq!(flat[0].path, "Vault.sol");
+
+        let unverified = json!({
+            "status": "1",
+            "message": "OK",
+            "result": [{ "SourceCode": "", "ContractName": "" }]
+        });
+        assert!(parse_response(&unverified, address, 1).is_err());
+        assert!(normalize_address("0x1234").is_err());
+    }
+
+    #[test]
+    fn test_cache_is_keyed_by_host_and_errors_hide_the_key() {
+        assert_eq!(api_host(ETHERSCAN_API), "api.etherscan.io");
+        assert_eq!(api_host("http://localhost:8545/api?x=1"), "localhost_8545");
+
+        let message = "https://api.etherscan.io/v2/api?chainid=1&apikey=SECRET123: status code 403";
+        let redacted = redact(message, Some("SECRET123"));
+        assert!(!redacted.contains("SECRET123"));
+        assert!(redacted.contains("apikey=<redacted>"));
+        assert_eq!(redact(message, None), message);
+    }
+}
@@ -0,0 +1,300 @@
+/*! Run `.thalir` files that state their own expectations, in the style of Cranelift's filetests.
+ *
+ * A filetest starts with directives naming what to run and how:
+ *
+ * ```text
+ * test analyze unguarded-division
+ * set callbacks=erc721
+ * target arbitrum cancun
+ * ; check: unguarded-division: Vault::share
+ * ; not: Vault::owner
+ * ```
+ *
+ * `test parse` passes when the IR reconstructs with nothing left out. `test emit` re-emits it and
+ * `test analyze` runs the named detectors, or all of them, and both check their output against
+ * the file's `; check:` and `; not:` lines, the same lines for every test in the file. With a
+ * `target`, `test analyze` also reports context reads the chain does not answer like mainnet, at
+ * the given EVM version or the newest.
+ */
+
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+use thalir_core::{
+    analysis::{
+        run_detectors, CallbackAnalysis, CallbackConfig, ContextUsageAnalysis, CONTRACT_DETECTORS,
+        WORKSPACE_DETECTORS,
+    },
+    chain::ChainCapabilities,
+    contract::Contract,
+    version::EvmVersion,
+};
+use thalir_emit::ThalIREmitter;
+use thalir_parser::{Directive, Expectation, ExpectationLine};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestOutcome {
+    /* The `test` line as written, e.g. `analyze unguarded-division`. */
+    pub command: String,
+    pub failures: Vec<String>,
+}
+
+impl TestOutcome {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileTestReport {
+    pub path: PathBuf,
+    pub outcomes: Vec<TestOutcome>,
+}
+
+impl FileTestReport {
+    pub fn passed(&self) -> bool {
+        self.outcomes.iter().all(TestOutcome::passed)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Settings {
+    callbacks: CallbackConfig,
+    annotations: bool,
+    target: Option<(ChainCapabilities, EvmVersion)>,
+}
+
+/* `None` for a file with no `test` line, which is IR rather than a test. */
+pub fn run_file(path: &Path) -> Result<Option<FileTestReport>> {
+    let source = std::fs::read_to_string(path)?;
+    let outcomes = run_source(&source).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+    Ok((!outcomes.is_empty()).then(|| FileTestReport {
+        path: path.to_path_buf(),
+        outcomes,
+    }))
+}
+
+/* Errors are for files whose directives are wrong; a test that runs and does not meet its
+ * expectations is a failed `TestOutcome`. */
+pub fn run_source(source: &str) -> Result<Vec<TestOutcome>> {
+    let directives = thalir_parser::directives(source);
+    let expectations = thalir_parser::expectations(source);
+    let source = &thalir_parser::strip_directives(source);
+
+    let mut settings = Settings::default();
+    let mut tests = Vec::new();
+    for line in directives {
+        match line.directive {
+            Directive::Test { command, args } => tests.push((line.line, command, args)),
+            Directive::Set { key, value } => match key.as_str() {
+                "callbacks" => {
+                    settings.callbacks.interfaces =
+                        Some(value.split(',').map(|s| s.trim().to_string()).collect());
+                    let unknown = settings.callbacks.unknown_interfaces();
+                    if !unknown.is_empty() {
+                        return Err(anyhow!(
+                            "line {}: unknown callback interface(s): {}",
+                            line.line,
+                            unknown.join(", ")
+                        ));
+                    }
+                }
+                "annotations" => {
+                    settings.annotations = value
+                        .parse()
+                        .map_err(|_| anyhow!("line {}: `{}` is not a bool", line.line, value))?
+                }
+                _ => return Err(anyhow!("line {}: unknown setting `{}`", line.line, key)),
+            },
+            Directive::Target { name, args } => {
+                let chain = ChainCapabilities::from_name(&name)
+                    .ok_or_else(|| anyhow!("line {}: unknown target `{}`", line.line, name))?;
+                let evm = match args.first() {
+                    Some(version) => version.parse::<EvmVersion>().map_err(|_| {
+                        anyhow!("line {}: unknown EVM version `{}`", line.line, version)
+                    })?,
+                    None => EvmVersion::Prague,
+                };
+                settings.target = Some((chain, evm));
+            }
+        }
+    }
+
+    let mut outcomes = Vec::new();
+    for (line, command, args) in tests {
+        let output = match command.as_str() {
+            "parse" => parse(source).map(|()| None),
+            "emit" => reconstruct(source).map(|contracts| {
+                Some(
+                    ThalIREmitter::new(contracts)
+                        .with_annotations(settings.annotations)
+                        .emit_to_string(false),
+                )
+            }),
+            "analyze" => {
+                let detectors = detectors(&args).map_err(|e| anyhow!("line {}: {}", line, e))?;
+                reconstruct(source)
+                    .map(|contracts| Some(analyze(&contracts, &detectors, &settings)))
+            }
+            _ => return Err(anyhow!("line {}: unknown test `{}`", line, command)),
+        };
+        let failures = match output {
+            Ok(Some(output)) => check(&output, &expectations),
+            Ok(None) => Vec::new(),
+            Err(error) => vec![error],
+        };
+        outcomes.push(TestOutcome {
+            command: std::iter::once(command)
+                .chain(args)
+                .collect::<Vec<_>>()
+                .join(" "),
+            failures,
+        });
+    }
+    Ok(outcomes)
+}
+
+fn reconstruct(source: &str) -> Result<Vec<Contract>, String> {
+    thalir_parser::reconstruct(source)
+        .map(|reconstruction| reconstruction.contracts)
+        .map_err(|e| e.to_string())
+}
+
+fn parse(source: &str) -> Result<(), String> {
+    let reconstruction = thalir_parser::reconstruct(source).map_err(|e| e.to_string())?;
+    match reconstruction.unrecognized.first() {
+        Some(first) => Err(format!(
+            "{} instruction(s) not reconstructed, the first `{}`",
+            reconstruction.unrecognized.len(),
+            first
+        )),
+        None => Ok(()),
+    }
+}
+
+/* Detectors named in full or by their last words, so `reentrancy` selects
+ * `callback-reentrancy`. No names selects every detector. */
+fn detectors(names: &[String]) -> Result<Vec<&'static str>> {
+    let all = CONTRACT_DETECTORS
+        .iter()
+        .map(|(name, _)| *name)
+        .chain(WORKSPACE_DETECTORS.iter().copied());
+    if names.is_empty() {
+        return Ok(all.collect());
+    }
+    let mut selected = Vec::new();
+    for name in names {
+        let before = selected.len();
+        let suffix = format!("-{}", name);
+        selected.extend(
+            all.clone()
+                .filter(|detector| *detector == name.as_str() || detector.ends_with(&suffix)),
+        );
+        if selected.len() == before {
+            return Err(anyhow!("unknown detector `{}`", name));
+        }
+    }
+    Ok(selected)
+}
+
+fn analyze(contracts: &[Contract], detectors: &[&str], settings: &Settings) -> String {
+    let mut findings = run_detectors(contracts, |name| {
+        name != CallbackAnalysis::DETECTOR && detectors.iter().any(|d| *d == name)
+    });
+    if detectors.contains(&CallbackAnalysis::DETECTOR) {
+        let model = settings.callbacks.model();
+        for contract in contracts {
+            findings.extend(CallbackAnalysis::findings_with(contract, &model));
+        }
+    }
+
+    let mut lines: Vec<String> = findings.iter().map(ToString::to_string).collect();
+    if let Some((chain, evm)) = &settings.target {
+        for contract in contracts {
+            let usage = ContextUsageAnalysis::analyze_contract(contract);
+            for issue in usage.capability_issues(chain, *evm) {
+                lines.push(format!("[target] {}::{}", contract.name, issue));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+fn check(output: &str, expectations: &[ExpectationLine]) -> Vec<String> {
+    let lines: Vec<&str> = output.lines().collect();
+    let mut next = 0;
+    let mut failures = Vec::new();
+    for expected in expectations {
+        match &expected.expectation {
+            Expectation::Check(text) => {
+                match lines[next..]
+                    .iter()
+                    .position(|line| line.contains(text.as_str()))
+                {
+                    Some(offset) => next += offset + 1,
+                    None => failures.push(format!(
+                        "line {}: `check: {}` not found{}",
+                        expected.line,
+                        text,
+                        if next > 0 {
+                            " after the previous check"
+                        } else {
+                            ""
+                        }
+                    )),
+                }
+            }
+            Expectation::Not(text) => {
+                if let Some(line) = lines.iter().find(|line| line.contains(text.as_str())) {
+                    failures.push(format!(
+                        "line {}: `not: {}` matched `{}`",
+                        expected.line, text, line
+                    ));
+                }
+            }
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VAULT: &str = r#"contract Vault {
+
+  // Storage Layout
+  slot 0 = owner: i160
+
+  function %share(i256) -> i256 public  {
+  block0(v0: i256):
+    v1 = sload iconst.i256 0
+    v2 = udiv.i256 v1, v0
+    return v2
+  }
+}
+"#;
+
+    #[test]
+    fn test_directives_select_tests_and_expectations() {
+        let source = format!(
+            "test parse\ntest analyze division\n; check: unguarded-division: Vault::share\n; not: Vault::owner\n{}",
+            VAULT
+        );
+        let outcomes = run_source(&source).unwrap();
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(outcomes[1].command, "analyze division");
+        assert!(outcomes.iter().all(TestOutcome::passed), "{:?}", outcomes);
+
+        let failing = format!(
+            "test analyze unguarded-division\n; check: Vault::withdraw\n{}",
+            VAULT
+        );
+        let outcomes = run_source(&failing).unwrap();
+        assert_eq!(outcomes[0].failures.len(), 1);
+        assert!(outcomes[0].failures[0].starts_with("line 2:"));
+
+        assert!(run_source(&format!("test analyze no-such-pass\n{}", VAULT)).is_err());
+        assert!(run_source(&format!("set colour=true\ntest parse\n{}", VAULT)).is_err());
+        assert!(run_source(VAULT).unwrap().is_empty());
+    }
+}
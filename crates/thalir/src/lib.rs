@@ -2,11 +2,47 @@
  *
  * Single import for everything you need: transforming Solidity to IR, parsing/emitting text format,
  * and accessing analysis tools. Batteries-included entry point for auditing workflows.
+ *
+ * `config` is the typed form of a project's `thalir.toml`: output and obfuscation defaults, enabled
+ * passes, lint levels, remappings and the source cache location.
+ *
+ * `filetest` runs `.thalir` files that carry `test` directives and `; check:` expectations.
+ *
+ * `standard_json` reads solc `--standard-json` input documents, keeping their source order and
+ * resolving `urls` through the listed remappings.
+ *
+ * With the `fetch` feature, `fetch::SourceFetcher` pulls verified source for a deployed address from
+ * an Etherscan-compatible explorer and caches it on disk, so audits can start from a chain address.
+ *
+ * With the `query` feature, `query::run_query` runs a Rhai script over the IR, for one-off audit
+ * questions that do not deserve a detector.
+ *
+ * `prelude` is the stable subset, versioned by semver. `filetest` and the component crates
+ * re-exported as `core`, `emit`, `parser` and `transform` are experimental and need the
+ * `unstable` feature. `bench` is not part of the facade: it backs the criterion suite and
+ * `thalir bench`, and only exists with the dev-only `bench` feature.
  */
 
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+pub mod config;
+#[cfg(feature = "fetch")]
+pub mod fetch;
+#[cfg(feature = "unstable")]
+pub mod filetest;
+pub mod prelude;
+#[cfg(feature = "query")]
+pub mod query;
+pub mod standard_json;
+
+#[cfg(feature = "unstable")]
 pub use thalir_core as core;
+#[cfg(feature = "unstable")]
 pub use thalir_emit as emit;
+#[cfg(feature = "unstable")]
 pub use thalir_parser as parser;
+#[cfg(feature = "unstable")]
 pub use thalir_transform as transform;
 
 pub use thalir_core::{
@@ -23,3 +59,5 @@ pub use thalir_emit::{AnnotatedIREmitter, ThalIREmitter};
 pub use thalir_parser::parse;
 
 pub use thalir_transform::transform_solidity_to_ir;
+
+pub use config::Config;
@@ -0,0 +1,78 @@
+/*! The stable API, for tools built on ThalIR: `use thalir::prelude::*;`.
+ *
+ * Everything here follows semver. Until 1.0 a breaking change to any of it comes with a minor
+ * version bump (0.1 to 0.2), never a patch release, so a `thalir = "0.1"` requirement keeps
+ * building. Modules outside the prelude and the crate root, and the component crates themselves,
+ * are only reachable with the `unstable` feature and may change in any release.
+ *
+ * ```
+ * use thalir::prelude::*;
+ *
+ * let contracts = transform_solidity_to_ir(
+ *     "contract Vault { uint256 total; function add(uint256 x) public { total += x; } }",
+ * )
+ * .unwrap();
+ * let findings: Vec<Finding> = run_detectors(&contracts, |_| true);
+ * assert!(findings.iter().all(|finding| finding.contract == "Vault"));
+ *
+ * let text = ThalIREmitter::new(contracts).emit_to_string(false);
+ * let reconstruction: Reconstruction = reconstruct(&text).unwrap();
+ * assert_eq!(reconstruction.contracts[0].name, "Vault");
+ * ```
+ */
+
+pub use thalir_core::{
+    analysis::{
+        run_detectors, AnalysisID, Finding, Pass, PassManager, Severity, CONTRACT_DETECTORS,
+        WORKSPACE_DETECTORS,
+    },
+    block::{BasicBlock, BlockId, BlockParam, Terminator},
+    builder::{IRBuilder, InstBuilder, InstBuilderExt},
+    contract::{Contract, StorageLayout},
+    function::{Function, FunctionSignature, Mutability, Parameter, Visibility},
+    instructions::{CallTarget, ContextVariable, Instruction, StorageKey},
+    transform::{CanonicalizationPass, Canonicalizer, Inliner, InliningPass},
+    types::Type,
+    values::{Constant, Location, Value},
+    version::EvmVersion,
+};
+
+pub use thalir_emit::{
+    AnnotatedIREmitter, EmitterConfig, EmitterProfile, PseudoSolidityEmitter, ThalIREmitter,
+};
+
+pub use thalir_parser::{parse, reconstruct, ReconstructError, Reconstruction};
+
+pub use thalir_transform::{
+    transform_solidity_to_ir, InputLimits, TransformError, TransformOutput, TransformationPipeline,
+};
+
+pub use crate::config::Config;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* Builds, prints and reads back a contract with nothing but the prelude in scope. */
+    #[test]
+    fn test_prelude_round_trips_a_contract() {
+        let mut builder = IRBuilder::new();
+        let mut contract_builder = builder.contract("Vault");
+        let mut function = contract_builder.function("owner");
+        function.visibility(Visibility::External);
+        let mut entry = function.entry_block();
+        let owner = entry.storage_load(0u32.into());
+        entry.return_value(owner).unwrap();
+        function.build().unwrap();
+        let contract = contract_builder.build().unwrap();
+
+        let text = ThalIREmitter::new(vec![contract]).emit_to_string(false);
+        let reconstruction: Reconstruction = reconstruct(&text).unwrap();
+        let function: &Function = &reconstruction.contracts[0].functions["owner"];
+        assert_eq!(function.visibility, Visibility::External);
+        assert!(matches!(
+            function.body.blocks[&function.entry_block()].instructions[0],
+            Instruction::StorageLoad { .. }
+        ));
+    }
+}
@@ -0,0 +1,307 @@
+use anyhow::{anyhow, Result};
+use num_bigint::BigUint;
+use rhai::{Array, Dynamic, Engine, Scope};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use thalir_core::{
+    analysis::StorageAccess,
+    block::BlockId,
+    contract::Contract,
+    function::Function,
+    instructions::{CallTarget, Instruction},
+};
+
+/* Caps a runaway script; a query over a large protocol stays well below this. */
+const MAX_OPERATIONS: u64 = 50_000_000;
+
+/* A contract as a script sees it. Scripts get the `contracts` array and read everything through
+ * properties, so the IR types stay out of the scripting surface. */
+#[derive(Clone)]
+pub struct ScriptContract {
+    contract: Arc<Contract>,
+}
+
+impl ScriptContract {
+    fn name(&mut self) -> String {
+        self.contract.name.clone()
+    }
+
+    fn functions(&mut self) -> Array {
+        self.contract
+            .functions
+            .keys()
+            .map(|key| {
+                Dynamic::from(ScriptFunction {
+                    contract: self.contract.clone(),
+                    key: key.clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn state_variables(&mut self) -> Array {
+        strings(self.contract.storage_layout.slots.iter().map(|s| &s.name))
+    }
+
+    fn events(&mut self) -> Array {
+        strings(self.contract.events.iter().map(|e| &e.name))
+    }
+
+    fn bases(&mut self) -> Array {
+        strings(&self.contract.metadata.bases)
+    }
+}
+
+#[derive(Clone)]
+pub struct ScriptFunction {
+    contract: Arc<Contract>,
+    key: String,
+}
+
+impl ScriptFunction {
+    fn function(&self) -> &Function {
+        &self.contract.functions[&self.key]
+    }
+
+    fn name(&mut self) -> String {
+        self.function().name().to_string()
+    }
+
+    fn contract_name(&mut self) -> String {
+        self.contract.name.clone()
+    }
+
+    fn visibility(&mut self) -> String {
+        format!("{:?}", self.function().visibility).to_lowercase()
+    }
+
+    fn mutability(&mut self) -> String {
+        format!("{:?}", self.function().mutability).to_lowercase()
+    }
+
+    fn is_entry_point(&mut self) -> bool {
+        self.function().is_entry_point()
+    }
+
+    fn modifiers(&mut self) -> Array {
+        strings(&self.contract.guards(self.function()).modifiers)
+    }
+
+    fn reads(&mut self) -> Array {
+        let access = StorageAccess::of_function(self.function());
+        self.variables(access.reads.iter())
+    }
+
+    fn writes(&mut self) -> Array {
+        let access = StorageAccess::of_function(self.function());
+        self.variables(access.writes.iter())
+    }
+
+    fn callees(&mut self) -> Array {
+        let mut callees: Vec<&String> = Vec::new();
+        for inst in self.instructions() {
+            if let Instruction::Call {
+                target: CallTarget::Internal(name),
+                ..
+            } = inst
+            {
+                if !callees.contains(&name) {
+                    callees.push(name);
+                }
+            }
+        }
+        strings(callees)
+    }
+
+    fn calls_external(&mut self) -> bool {
+        self.instructions().any(Instruction::is_external_call)
+    }
+
+    /* True when some storage write can run after an external call: later in the call's own block,
+     * or anywhere in a block reachable from it. */
+    fn writes_storage_after_call(&mut self) -> bool {
+        let body = &self.function().body;
+        let mut pending: Vec<BlockId> = Vec::new();
+        for block in body.blocks.values() {
+            if let Some(call) = block.instructions.iter().position(|i| i.is_external_call()) {
+                if block.instructions[call + 1..].iter().any(writes_storage) {
+                    return true;
+                }
+                pending.extend(block.terminator.successors());
+            }
+        }
+
+        let mut visited = HashSet::new();
+        while let Some(id) = pending.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            let Some(block) = body.blocks.get(&id) else {
+                continue;
+            };
+            if block.instructions.iter().any(writes_storage) {
+                return true;
+            }
+            pending.extend(block.terminator.successors());
+        }
+        false
+    }
+
+    fn instruction_count(&mut self) -> i64 {
+        self.instructions().count() as i64
+    }
+
+    fn instructions(&self) -> impl Iterator<Item = &Instruction> {
+        self.function()
+            .body
+            .blocks
+            .values()
+            .flat_map(|b| &b.instructions)
+    }
+
+    /* Slots by variable name, or as `slot N` when nothing is declared there. */
+    fn variables<'a>(&self, slots: impl Iterator<Item = &'a BigUint>) -> Array {
+        let mut slots: Vec<_> = slots.collect();
+        slots.sort();
+        let mut names = Vec::new();
+        for slot in slots {
+            let declared: Vec<&String> = self
+                .contract
+                .storage_layout
+                .slots
+                .iter()
+                .filter(|var| &var.slot == slot)
+                .map(|var| &var.name)
+                .collect();
+            if declared.is_empty() {
+                names.push(format!("slot {}", slot));
+            } else {
+                names.extend(declared.into_iter().cloned());
+            }
+        }
+        strings(&names)
+    }
+}
+
+fn writes_storage(inst: &Instruction) -> bool {
+    matches!(
+        inst,
+        Instruction::StorageStore { .. }
+            | Instruction::StorageDelete { .. }
+            | Instruction::MappingStore { .. }
+            | Instruction::ArrayStore { .. }
+            | Instruction::ArrayPush { .. }
+            | Instruction::ArrayPop { .. }
+    )
+}
+
+fn strings<'a>(values: impl IntoIterator<Item = &'a String>) -> Array {
+    values
+        .into_iter()
+        .map(|value| Dynamic::from(value.clone()))
+        .collect()
+}
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    engine
+        .register_type_with_name::<ScriptContract>("Contract")
+        .register_get("name", ScriptContract::name)
+        .register_get("functions", ScriptContract::functions)
+        .register_get("state_variables", ScriptContract::state_variables)
+        .register_get("events", ScriptContract::events)
+        .register_get("bases", ScriptContract::bases);
+
+    engine
+        .register_type_with_name::<ScriptFunction>("Function")
+        .register_get("name", ScriptFunction::name)
+        .register_get("contract", ScriptFunction::contract_name)
+        .register_get("visibility", ScriptFunction::visibility)
+        .register_get("mutability", ScriptFunction::mutability)
+        .register_get("is_entry_point", ScriptFunction::is_entry_point)
+        .register_get("modifiers", ScriptFunction::modifiers)
+        .register_get("reads", ScriptFunction::reads)
+        .register_get("writes", ScriptFunction::writes)
+        .register_get("callees", ScriptFunction::callees)
+        .register_get("instruction_count", ScriptFunction::instruction_count)
+        .register_fn("calls_external", ScriptFunction::calls_external)
+        .register_fn(
+            "writes_storage_after_call",
+            ScriptFunction::writes_storage_after_call,
+        );
+
+    engine
+}
+
+/* Runs a Rhai script against `contracts` and returns what it printed, line by line. */
+pub fn run_query(script: &str, contracts: Vec<Contract>) -> Result<Vec<String>> {
+    let mut engine = engine();
+    let printed = Arc::new(Mutex::new(Vec::new()));
+    let sink = printed.clone();
+    engine.on_print(move |line| sink.lock().unwrap().push(line.to_string()));
+
+    let contracts: Array = contracts
+        .into_iter()
+        .map(|contract| {
+            Dynamic::from(ScriptContract {
+                contract: Arc::new(contract),
+            })
+        })
+        .collect();
+    let mut scope = Scope::new();
+    scope.push("contracts", contracts);
+
+    engine
+        .run_with_scope(&mut scope, script)
+        .map_err(|e| anyhow!("Query failed: {}", e))?;
+
+    let lines = std::mem::take(&mut *printed.lock().unwrap());
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use thalir_core::builder::IRBuilder;
+    use thalir_core::types::Type;
+
+    #[test]
+    fn test_script_finds_writes_after_external_calls() {
+        let mut builder = IRBuilder::new();
+        let mut contract = builder.contract("Bank");
+        contract.state_variable("balance", Type::Uint(256), 0);
+
+        for (name, write_first) in [("withdraw", false), ("safeWithdraw", true)] {
+            let mut function = contract.function(name);
+            let mut entry = function.entry_block();
+            let zero = entry.constant_uint(0, 256);
+            let recipient = entry.msg_sender();
+            if write_first {
+                entry.storage_store(0u32.into(), zero.clone());
+            }
+            let selector = entry.constant_uint(0, 32);
+            entry.call_external(recipient, selector, Vec::new(), Some(zero.clone()));
+            if !write_first {
+                entry.storage_store(0u32.into(), zero);
+            }
+            entry.return_void().unwrap();
+            function.build().unwrap();
+        }
+
+        let script = r#"
+            for c in contracts {
+                for f in c.functions {
+                    if f.calls_external() && f.writes_storage_after_call() {
+                        print(`${c.name}.${f.name} writes ${f.writes[0]} after a call`);
+                    }
+                }
+            }
+        "#;
+        let printed = run_query(script, vec![contract.build().unwrap()]).unwrap();
+        assert_eq!(printed, vec!["Bank.withdraw writes balance after a call"]);
+
+        assert!(run_query("contracts[0].nope", Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use anyhow::{anyhow, Context, Result};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use thalir_core::EvmVersion;
+use thalir_transform::{Diagnostics, InputLimits, TransformOutput, TransformationPipeline};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceFile {
+    pub path: String,
+    pub content: String,
+}
+
+/* The solc `--standard-json` input document. Only the parts that affect which sources get lowered
+ * are read; optimizer and output selection settings are ignored. Sources keep document order. */
+#[derive(Debug, Clone, Deserialize)]
+pub struct StandardJsonInput {
+    #[serde(default)]
+    pub language: Option<String>,
+    pub sources: IndexMap<String, SourceUnit>,
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SourceUnit {
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub urls: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+    #[serde(default)]
+    pub remappings: Vec<String>,
+    #[serde(default)]
+    pub evm_version: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remapping {
+    pub context: String,
+    pub prefix: String,
+    pub target: String,
+}
+
+impl Remapping {
+    /* `[context:]prefix=target`, as accepted by solc. */
+    pub fn parse(spec: &str) -> Option<Self> {
+        let (lhs, target) = spec.split_once('=')?;
+        let (context, prefix) = lhs.split_once(':').unwrap_or(("", lhs));
+        if prefix.is_empty() {
+            return None;
+        }
+        Some(Self {
+            context: context.to_string(),
+            prefix: prefix.to_string(),
+            target: target.to_string(),
+        })
+    }
+}
+
+impl StandardJsonInput {
+    pub fn parse(text: &str) -> Result<Self> {
+        let input: Self =
+            serde_json::from_str(text).context("Not a solc standard-json input document")?;
+        if let Some(language) = input.language.as_deref() {
+            if language != "Solidity" {
+                return Err(anyhow!(
+                    "Standard-json input is for {}, only Solidity is supported",
+                    language
+                ));
+            }
+        }
+        Ok(input)
+    }
+
+    pub fn remappings(&self) -> Vec<Remapping> {
+        self.settings
+            .remappings
+            .iter()
+            .filter_map(|spec| Remapping::parse(spec))
+            .collect()
+    }
+
+    pub fn evm_version(&self) -> Option<EvmVersion> {
+        self.settings.evm_version.as_deref()?.parse().ok()
+    }
+
+    /* Solc's own resolution: the longest matching prefix wins, and a remapping with a context only
+     * applies to files under that context. */
+    pub fn remap(&self, importer: &str, path: &str) -> String {
+        let best = self
+            .remappings()
+            .into_iter()
+            .filter(|r| importer.starts_with(&r.context) && path.starts_with(&r.prefix))
+            .max_by_key(|r| (r.context.len(), r.prefix.len()));
+        match best {
+            Some(r) => format!("{}{}", r.target, &path[r.prefix.len()..]),
+            None => path.to_string(),
+        }
+    }
+
+    /* Inline `content` is used as-is. Sources given only by `urls` are read from disk, relative to
+     * `base_dir`, after remapping; the first URL that resolves to a file wins. */
+    pub fn load_sources(&self, base_dir: &Path) -> Result<Vec<SourceFile>> {
+        self.sources
+            .iter()
+            .map(|(path, unit)| {
+                let content = match &unit.content {
+                    Some(content) => content.clone(),
+                    None => unit
+                        .urls
+                        .iter()
+                        .map(|url| base_dir.join(self.remap(path, url)))
+                        .find(|candidate| candidate.is_file())
+                        .map(fs::read_to_string)
+                        .transpose()?
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "Source {} has no content and none of its urls resolve",
+                                path
+                            )
+                        })?,
+                };
+                Ok(SourceFile {
+                    path: path.clone(),
+                    content,
+                })
+            })
+            .collect()
+    }
+}
+
+/* Lowers each file on its own and pools the contracts, so diagnostics keep the path the file was
+ * listed under rather than pointing into a flattened blob. Every file is held to the size limit
+ * before any is lowered, so an oversized one late in the list fails the run up front. */
+pub fn transform_sources(
+    files: &[SourceFile],
+    lenient: bool,
+    evm_version: Option<EvmVersion>,
+    limits: InputLimits,
+) -> Result<TransformOutput> {
+    for file in files {
+        limits
+            .check_size(file.content.len())
+            .with_context(|| format!("Failed to transform {}", file.path))?;
+    }
+    let mut output = TransformOutput {
+        contracts: Vec::new(),
+        diagnostics: Diagnostics::new(),
+    };
+    for file in files {
+        let part = TransformationPipeline::with_filename(&file.content, file.path.clone())
+            .lenient(lenient)
+            .evm_version(evm_version)
+            .limits(limits)
+            .transform_with_diagnostics()
+            .with_context(|| format!("Failed to transform {}", file.path))?;
+        output.contracts.extend(part.contracts);
+        output.diagnostics.extend(part.diagnostics);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sources_keep_order_and_remap_urls() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("lib/oz")).unwrap();
+        fs::write(dir.join("lib/oz/Ownable.sol"), "contract Ownable {}").unwrap();
+
+        let input = StandardJsonInput::parse(
+            r#"{
+                "language": "Solidity",
+                "sources": {
+                    "src/Vault.sol": { "content": "contract Vault {}" },
+                    "@oz/Ownable.sol": { "urls": ["@oz/Ownable.sol"] },
+                    "src/Alpha.sol": { "content": "contract Alpha {}" }
+                },
+                "settings": {
+                    "remappings": ["@oz/=lib/oz/"],
+                    "evmVersion": "paris",
+                    "optimizer": { "enabled": true }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            input.remap("src/Vault.sol", "@oz/Ownable.sol"),
+            "lib/oz/Ownable.sol"
+        );
+        assert!(input.evm_version().is_some());
+
+        let files = input.load_sources(dir).unwrap();
+        let paths: Vec<_> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["src/Vault.sol", "@oz/Ownable.sol", "src/Alpha.sol"]);
+        assert_eq!(files[1].content, "contract Ownable {}");
+    }
+
+    #[test]
+    fn test_oversized_source_is_rejected_before_lowering() {
+        let files = [
+            SourceFile {
+                path: "src/Small.sol".to_string(),
+                content: "contract Small {}".to_string(),
+            },
+            SourceFile {
+                path: "src/Large.sol".to_string(),
+                content: format!("contract Large {{}}{}", " ".repeat(64)),
+            },
+        ];
+        let limits = InputLimits {
+            max_source_bytes: Some(32),
+            ..InputLimits::unlimited()
+        };
+
+        let err = transform_sources(&files, false, None, limits).unwrap_err();
+        assert!(format!("{:#}", err).contains("src/Large.sol"));
+        assert!(transform_sources(&files[..1], false, None, limits).is_ok());
+    }
+}
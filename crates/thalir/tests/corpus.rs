@@ -0,0 +1,112 @@
+/* Transforms each vulnerable sample in `corpus/`, runs every detector on it and checks the result
+ * against `corpus/expected.toml`. A detector change that loses a listed finding, or starts
+ * reporting one listed as absent, fails here. */
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use thalir::prelude::*;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Corpus {
+    sample: Vec<Sample>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Sample {
+    file: String,
+    vulnerability: String,
+    #[serde(default)]
+    expect: Vec<Expected>,
+    #[serde(default)]
+    absent: Vec<Expected>,
+    #[serde(default)]
+    known_gap: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Expected {
+    detector: String,
+    contract: String,
+    #[serde(default)]
+    function: String,
+}
+
+impl Expected {
+    fn matches(&self, finding: &Finding) -> bool {
+        finding.detector == self.detector
+            && finding.contract == self.contract
+            && finding.function == self.function
+    }
+}
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../corpus")
+}
+
+#[test]
+fn test_corpus_findings_match_expectations() {
+    let dir = corpus_dir();
+    let manifest = std::fs::read_to_string(dir.join("expected.toml")).unwrap();
+    let corpus: Corpus = toml::from_str(&manifest).unwrap();
+
+    let mut failures = Vec::new();
+    for sample in &corpus.sample {
+        assert_eq!(
+            sample.known_gap,
+            sample.expect.is_empty(),
+            "{}: a sample lists expected findings or is a known gap, not both or neither",
+            sample.file
+        );
+
+        let source = std::fs::read_to_string(dir.join(&sample.file)).unwrap();
+        let contracts = match transform_solidity_to_ir(&source) {
+            Ok(contracts) => contracts,
+            Err(e) => {
+                failures.push(format!("{}: does not transform: {}", sample.file, e));
+                continue;
+            }
+        };
+        let findings = run_detectors(&contracts, |_| true);
+
+        for expected in &sample.expect {
+            if !findings.iter().any(|finding| expected.matches(finding)) {
+                failures.push(format!(
+                    "{} ({}): missing {}: {}::{}",
+                    sample.file,
+                    sample.vulnerability,
+                    expected.detector,
+                    expected.contract,
+                    expected.function
+                ));
+            }
+        }
+        for absent in &sample.absent {
+            if let Some(finding) = findings.iter().find(|finding| absent.matches(finding)) {
+                failures.push(format!("{}: unexpected {}", sample.file, finding));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+/* A sample on disk that the manifest does not mention is never checked. */
+#[test]
+fn test_corpus_lists_every_sample() {
+    let dir = corpus_dir();
+    let corpus: Corpus =
+        toml::from_str(&std::fs::read_to_string(dir.join("expected.toml")).unwrap()).unwrap();
+    for entry in std::fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|ext| ext == "sol") {
+            let name = path.file_name().unwrap().to_string_lossy();
+            assert!(
+                corpus.sample.iter().any(|sample| sample.file == name),
+                "{} is not listed in expected.toml",
+                name
+            );
+        }
+    }
+}
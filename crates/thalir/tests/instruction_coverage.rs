@@ -0,0 +1,471 @@
+/* Every `Instruction` variant goes through the builder, the text emitter, the text reconstructor
+ * and Cranelift lowering. Adding a variant without a sample here, or without support in one of
+ * those stages, fails this test instead of falling through to a catch-all arm. */
+
+use cranelift_codegen::ir::{types, Function as ClifFunction};
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use std::collections::{HashMap, HashSet};
+use thalir_core::{
+    block::{BlockId, Terminator},
+    builder::{BlockBuilder, IRBuilder, InstBuilder, InstBuilderExt},
+    codegen::{lower_instruction, CalleeNames, CodegenContext, WordAbi},
+    contract::{Contract, EventId},
+    instructions::{Instruction, Size},
+    types::Type,
+    values::{Location, Value},
+    InstructionVisitor,
+};
+use thalir_emit::ThalIREmitter;
+use thalir_parser::reconstruct;
+
+/* Builds one instruction from the sample function's parameters: a uint256, a bool, an address and
+ * a uint64, in that order. */
+type Sample = fn(&mut BlockBuilder<'_>, &[Value]);
+
+fn samples() -> Vec<Sample> {
+    vec![
+        |b, p| {
+            b.add(p[0].clone(), p[3].clone(), Type::Uint(256));
+        },
+        |b, p| {
+            b.sub(p[0].clone(), p[3].clone(), Type::Uint(256));
+        },
+        |b, p| {
+            b.mul(p[0].clone(), p[3].clone(), Type::Uint(256));
+        },
+        |b, p| {
+            b.div(p[0].clone(), p[3].clone(), Type::Uint(256));
+        },
+        |b, p| {
+            b.mod_(p[0].clone(), p[3].clone(), Type::Uint(256));
+        },
+        |b, p| {
+            b.sdiv(p[0].clone(), p[3].clone(), Type::Int(256));
+        },
+        |b, p| {
+            b.smod(p[0].clone(), p[3].clone(), Type::Int(256));
+        },
+        |b, p| {
+            b.pow(p[0].clone(), p[3].clone());
+        },
+        |b, p| {
+            b.checked_add(p[0].clone(), p[3].clone(), Type::Uint(256));
+        },
+        |b, p| {
+            b.checked_sub(p[0].clone(), p[3].clone(), Type::Uint(256));
+        },
+        |b, p| {
+            b.checked_mul(p[0].clone(), p[3].clone(), Type::Uint(256));
+        },
+        |b, p| {
+            b.checked_div(p[0].clone(), p[3].clone(), Type::Uint(256));
+        },
+        |b, p| {
+            b.and(p[0].clone(), p[0].clone());
+        },
+        |b, p| {
+            b.or(p[0].clone(), p[0].clone());
+        },
+        |b, p| {
+            b.xor(p[0].clone(), p[0].clone());
+        },
+        |b, p| {
+            b.not(p[0].clone());
+        },
+        |b, p| {
+            b.shl(p[0].clone(), p[3].clone());
+        },
+        |b, p| {
+            b.shr(p[0].clone(), p[3].clone());
+        },
+        |b, p| {
+            b.sar(p[0].clone(), p[3].clone());
+        },
+        |b, p| {
+            b.eq(p[0].clone(), p[0].clone());
+        },
+        |b, p| {
+            b.ne(p[0].clone(), p[0].clone());
+        },
+        |b, p| {
+            b.lt(p[0].clone(), p[0].clone());
+        },
+        |b, p| {
+            b.gt(p[0].clone(), p[0].clone());
+        },
+        |b, p| {
+            b.le(p[0].clone(), p[0].clone());
+        },
+        |b, p| {
+            b.ge(p[0].clone(), p[0].clone());
+        },
+        |b, p| {
+            b.slt(p[0].clone(), p[0].clone());
+        },
+        |b, p| {
+            b.sgt(p[0].clone(), p[0].clone());
+        },
+        |b, p| {
+            b.sle(p[0].clone(), p[0].clone());
+        },
+        |b, p| {
+            b.sge(p[0].clone(), p[0].clone());
+        },
+        |b, p| {
+            b.select(p[1].clone(), p[0].clone(), p[0].clone());
+        },
+        |b, p| {
+            b.load(Location::Memory {
+                base: p[0].clone(),
+                offset: p[3].clone(),
+            });
+        },
+        |b, p| {
+            let location = Location::Memory {
+                base: p[0].clone(),
+                offset: p[3].clone(),
+            };
+            b.store(location, p[0].clone());
+        },
+        |b, _| {
+            b.allocate(Type::Uint(256), Size::Static(32));
+        },
+        |b, p| {
+            let dest = Location::Memory {
+                base: p[0].clone(),
+                offset: p[3].clone(),
+            };
+            b.copy(dest.clone(), dest, p[3].clone());
+        },
+        |b, _| {
+            b.storage_load(1u32.into());
+        },
+        |b, p| b.storage_store(1u32.into(), p[0].clone()),
+        |b, _| b.storage_delete(1u32.into()),
+        |b, _| {
+            b.transient_load(2u32.into());
+        },
+        |b, p| b.transient_store(2u32.into(), p[0].clone()),
+        |b, p| {
+            let mapping = b.constant_uint(3, 256);
+            b.mapping_load(mapping, p[2].clone());
+        },
+        |b, p| {
+            let mapping = b.constant_uint(3, 256);
+            b.mapping_store(mapping, p[2].clone(), p[0].clone());
+        },
+        |b, p| {
+            b.array_load(p[0].clone(), p[3].clone());
+        },
+        |b, p| b.array_store(p[0].clone(), p[3].clone(), p[0].clone()),
+        |b, p| {
+            b.array_length(p[0].clone());
+        },
+        |b, p| b.array_push(p[0].clone(), p[3].clone()),
+        |b, p| {
+            b.array_pop(p[0].clone());
+        },
+        |b, p| {
+            b.call_internal("helper", vec![p[0].clone()]);
+        },
+        |b, p| {
+            let selector = b.constant_uint(0xa9059cbb, 32);
+            b.call_external(
+                p[2].clone(),
+                selector,
+                vec![p[0].clone()],
+                Some(p[3].clone()),
+            );
+        },
+        |b, p| {
+            b.block_hash(p[0].clone());
+        },
+        |b, p| {
+            let selector = b.constant_uint(0xa9059cbb, 32);
+            b.delegate_call(p[2].clone(), selector, vec![p[0].clone()]);
+        },
+        |b, p| {
+            let selector = b.constant_uint(0x70a08231, 32);
+            b.static_call(p[2].clone(), selector, vec![p[0].clone()]);
+        },
+        |b, p| {
+            b.create(p[0].clone(), p[3].clone());
+        },
+        |b, p| {
+            b.create2(p[0].clone(), p[0].clone(), p[3].clone());
+        },
+        |b, p| b.selfdestruct(p[2].clone()),
+        |b, _| {
+            b.msg_sender();
+        },
+        |b, p| {
+            b.balance(p[2].clone());
+        },
+        |b, p| {
+            b.code(p[2].clone());
+        },
+        |b, p| {
+            b.code_size(p[2].clone());
+        },
+        |b, p| {
+            b.code_hash(p[2].clone());
+        },
+        |b, p| {
+            b.keccak256(p[0].clone(), p[3].clone());
+        },
+        |b, p| {
+            b.sha256(p[0].clone(), p[3].clone());
+        },
+        |b, p| {
+            b.ripemd160(p[0].clone(), p[3].clone());
+        },
+        |b, p| {
+            b.ecrecover(p[0].clone(), p[3].clone(), p[0].clone(), p[0].clone());
+        },
+        |b, p| b.emit_event(EventId(0), vec![p[2].clone()], vec![p[0].clone()]),
+        |b, p| {
+            b.cast(p[0].clone(), Type::Address);
+        },
+        |b, p| {
+            b.zext(p[3].clone(), Type::Uint(256));
+        },
+        |b, p| {
+            b.sext(p[3].clone(), Type::Int(256));
+        },
+        |b, p| {
+            b.trunc(p[0].clone(), Type::Uint(64));
+        },
+        |b, p| b.assert(p[1].clone(), "invariant"),
+        |b, p| b.require(p[1].clone(), "not allowed"),
+        |b, _| InstBuilderExt::revert(b, "failed"),
+        |b, p| {
+            let dest = b.new_temp();
+            b.assign(dest, p[0].clone());
+        },
+        |b, p| {
+            let entry = b.block_id();
+            b.phi(vec![(entry, p[0].clone()), (BlockId(1), p[3].clone())]);
+        },
+        |b, p| InstBuilder::jump(b, BlockId(1), vec![p[0].clone()]),
+        |b, p| InstBuilder::branch(b, p[1].clone(), BlockId(1), BlockId(2), vec![], vec![]),
+        |b, p| InstBuilder::return_value(b, Some(p[0].clone())),
+        |b, p| {
+            b.memory_alloc(p[3].clone());
+        },
+        |b, p| b.memory_copy(p[0].clone(), p[0].clone(), p[3].clone()),
+        |b, _| {
+            b.memory_size();
+        },
+    ]
+}
+
+/* Records the variant names the derived `Deserialize` impl hands to `deserialize_enum`, which
+ * serde generates from the enum itself, so the list cannot fall behind it. */
+struct VariantNames<'a>(&'a mut &'static [&'static str]);
+
+impl<'de> Deserializer<'de> for VariantNames<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("only enums are supported"))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = variants;
+        Err(de::Error::custom("variants recorded"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+fn all_variants() -> &'static [&'static str] {
+    let mut variants: &'static [&'static str] = &[];
+    let _ = Instruction::deserialize(VariantNames(&mut variants));
+    variants
+}
+
+/* Control flow instructions read back as the terminator of the same name. */
+fn terminator_variant(terminator: &Terminator) -> Option<&'static str> {
+    match terminator {
+        Terminator::Jump(..) => Some("Jump"),
+        Terminator::Branch { .. } => Some("Branch"),
+        Terminator::Return(_) => Some("Return"),
+        Terminator::Revert(_) => Some("Revert"),
+        _ => None,
+    }
+}
+
+/* One function per sample, each holding just that instruction, so control flow instructions
+ * cannot stand in for one another once they are read back as terminators. */
+fn build_contract() -> (Contract, Vec<Instruction>) {
+    let mut builder = IRBuilder::new();
+    let mut contract = builder.contract("Coverage");
+    let mut built = Vec::new();
+
+    for (index, sample) in samples().into_iter().enumerate() {
+        let mut function = contract.function(&format!("sample{}", index));
+        function
+            .param("amount", Type::Uint(256))
+            .param("flag", Type::Bool)
+            .param("account", Type::Address)
+            .param("small", Type::Uint(64));
+        let params: Vec<Value> = (0..4).map(|i| function.get_param(i)).collect();
+        let mut entry = function.entry_block();
+        sample(&mut entry, &params);
+        entry.seal_with_terminator(Terminator::Invalid).unwrap();
+        let function = function.build().unwrap();
+
+        let block = &function.body.blocks[&function.entry_block()];
+        assert_eq!(block.instructions.len(), 1, "sample{} built", index);
+        built.push(block.instructions[0].clone());
+    }
+
+    (contract.build().unwrap(), built)
+}
+
+#[test]
+fn test_samples_cover_every_instruction_variant() {
+    let (_, built) = build_contract();
+    let sampled: HashSet<&str> = built.iter().map(Instruction::opcode_name).collect();
+    let missing: Vec<&str> = all_variants()
+        .iter()
+        .copied()
+        .filter(|name| !sampled.contains(name))
+        .collect();
+
+    assert!(!all_variants().is_empty());
+    assert!(missing.is_empty(), "no sample for {:?}", missing);
+}
+
+/* Passes read values through `Instruction::result` and `Instruction::operands`; a visitor's
+ * default walk has to reach exactly those, or overriding one method would change what it sees. */
+#[test]
+fn test_visitor_walk_matches_result_and_operands() {
+    #[derive(Default)]
+    struct Values {
+        results: Vec<Value>,
+        operands: Vec<Value>,
+    }
+
+    impl InstructionVisitor for Values {
+        fn visit_result(&mut self, value: &Value) {
+            self.results.push(value.clone());
+        }
+
+        fn visit_operand(&mut self, value: &Value) {
+            self.operands.push(value.clone());
+        }
+    }
+
+    let (_, built) = build_contract();
+    for inst in &built {
+        let mut values = Values::default();
+        values.visit_instruction(inst);
+        assert_eq!(
+            values.results.iter().collect::<Vec<_>>(),
+            inst.result().into_iter().collect::<Vec<_>>(),
+            "{} result",
+            inst.opcode_name()
+        );
+        assert_eq!(
+            values.operands.iter().collect::<Vec<_>>(),
+            inst.operands(),
+            "{} operands",
+            inst.opcode_name()
+        );
+    }
+}
+
+#[test]
+fn test_every_instruction_survives_emit_and_reconstruct() {
+    let (contract, built) = build_contract();
+    let text = ThalIREmitter::new(vec![contract]).emit_to_string(false);
+    let reconstruction = reconstruct(&text).unwrap();
+    assert!(
+        reconstruction.unrecognized.is_empty(),
+        "unrecognized lines: {:?}",
+        reconstruction.unrecognized
+    );
+
+    let rebuilt = &reconstruction.contracts[0];
+    for (index, original) in built.iter().enumerate() {
+        let name = format!("sample{}", index);
+        let function = rebuilt
+            .functions
+            .values()
+            .find(|f| f.name() == name)
+            .unwrap_or_else(|| panic!("{} missing after reconstruction", name));
+        let expected = original.opcode_name();
+        let operands = original.operands().len();
+
+        let as_instruction = function
+            .body
+            .blocks
+            .values()
+            .flat_map(|block| &block.instructions)
+            .any(|inst| inst.opcode_name() == expected && inst.operands().len() == operands);
+        let as_terminator = function.body.blocks.values().any(|block| {
+            terminator_variant(&block.terminator) == Some(expected)
+                && block.terminator.operands().len() == operands
+        });
+        assert!(
+            as_instruction || as_terminator,
+            "{} ({}) did not round-trip:\n{}",
+            expected,
+            name,
+            text
+        );
+    }
+}
+
+#[test]
+fn test_every_instruction_lowers_to_cranelift() {
+    let (_, built) = build_contract();
+    let abis = [WordAbi::I128, WordAbi::I64Pair, WordAbi::Handle];
+    for (inst, abi) in built.iter().flat_map(|inst| abis.map(|abi| (inst, abi))) {
+        let mut func = ClifFunction::new();
+        let mut context = CodegenContext::new(&mut func).with_word_abi(abi);
+        let mut builder = context.func_builder();
+        let block = builder.create_block();
+        /* The runtime context pointer comes first; context reads load through it. */
+        builder.append_block_param(block, types::I64);
+        let mut ssa_values = HashMap::new();
+        for operand in inst.operands() {
+            let param = builder.append_block_param(block, types::I128);
+            ssa_values.insert(operand.clone(), param);
+        }
+        builder.switch_to_block(block);
+
+        let lowered = lower_instruction(
+            inst,
+            &HashMap::new(),
+            &mut ssa_values,
+            &mut builder,
+            &mut CalleeNames::new(),
+            abi,
+        );
+        assert!(
+            lowered.is_ok(),
+            "{} failed to lower with {:?} words: {:?}",
+            inst.opcode_name(),
+            abi,
+            lowered
+        );
+        if let Some(result) = inst.result() {
+            assert!(
+                ssa_values.contains_key(result),
+                "{} lowered without a result",
+                inst.opcode_name()
+            );
+        }
+    }
+}